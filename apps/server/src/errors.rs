@@ -212,6 +212,7 @@ impl From<TryFromIntError> for APIError {
 impl From<CoreError> for APIError {
 	fn from(err: CoreError) -> Self {
 		match err {
+			CoreError::BadRequest(err) => APIError::BadRequest(err),
 			CoreError::InternalError(err) => APIError::InternalServerError(err),
 			CoreError::IoError(err) => APIError::InternalServerError(err.to_string()),
 			CoreError::MigrationError(err) => APIError::InternalServerError(err),
@@ -45,9 +45,11 @@ pub async fn run_http_server(config: StumpConfig) -> ServerResult<()> {
 		.map_err(|e| ServerError::ServerStartError(e.to_string()))?;
 
 	// Initialize the scheduler
-	core.init_scheduler()
-		.await
-		.map_err(|e| ServerError::ServerStartError(e.to_string()))?;
+	core.init_scheduler().await;
+
+	if let Err(error) = core.init_library_watcher().await {
+		tracing::error!(?error, "Failed to initialize library watcher");
+	}
 
 	let server_ctx = core.get_context();
 	let app_state = server_ctx.arced();
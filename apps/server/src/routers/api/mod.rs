@@ -34,8 +34,9 @@ mod tests {
 			epub::*,
 			job::*,
 			library::*,
-			media::{individual::*, thumbnails::*},
+			media::{bulk::*, individual::*, thumbnails::*},
 			metadata::*,
+			person::*,
 			series::*,
 			smart_list::*,
 			user::*,
@@ -158,22 +159,71 @@ mod tests {
 		file.write_all(
 			format!("{}\n\n", ts_export::<GenerateLibraryThumbnails>()?).as_bytes(),
 		)?;
+		file.write_all(
+			format!("{}\n\n", ts_export::<PreviewFilenamePatterns>()?).as_bytes(),
+		)?;
+		file.write_all(
+			format!("{}\n\n", ts_export::<FilenamePatternResult>()?).as_bytes(),
+		)?;
+		file.write_all(
+			format!("{}\n\n", ts_export::<FilenamePatternPreview>()?).as_bytes(),
+		)?;
+		file.write_all(format!("{}\n\n", ts_export::<ScanDryRunResponse>()?).as_bytes())?;
+		file.write_all(
+			format!("{}\n\n", ts_export::<IsbnEnrichmentRequest>()?).as_bytes(),
+		)?;
+		file.write_all(
+			format!("{}\n\n", ts_export::<IsbnEnrichmentResponse>()?).as_bytes(),
+		)?;
+		file.write_all(
+			format!("{}\n\n", ts_export::<MetadataImportResponse>()?).as_bytes(),
+		)?;
 		file.write_all(format!("{}\n\n", ts_export::<LibraryStatsParams>()?).as_bytes())?;
 
 		file.write_all(
 			format!("{}\n\n", ts_export::<PutMediaCompletionStatus>()?).as_bytes(),
 		)?;
 		file.write_all(format!("{}\n\n", ts_export::<MediaIsComplete>()?).as_bytes())?;
+		file.write_all(
+			format!("{}\n\n", ts_export::<MetadataConflictResolution>()?).as_bytes(),
+		)?;
+		file.write_all(
+			format!("{}\n\n", ts_export::<ResolveMetadataConflict>()?).as_bytes(),
+		)?;
+		file.write_all(
+			format!("{}\n\n", ts_export::<ApplyMetadataCandidate>()?).as_bytes(),
+		)?;
+		file.write_all(format!("{}\n\n", ts_export::<MetadataLocks>()?).as_bytes())?;
+		file.write_all(format!("{}\n\n", ts_export::<SimilarMediaQuery>()?).as_bytes())?;
+		file.write_all(format!("{}\n\n", ts_export::<SimilarMedia>()?).as_bytes())?;
+		file.write_all(
+			format!("{}\n\n", ts_export::<SimilarMediaCluster>()?).as_bytes(),
+		)?;
 		file.write_all(
 			format!("{}\n\n", ts_export::<MediaMetadataOverview>()?).as_bytes(),
 		)?;
+		file.write_all(
+			format!("{}\n\n", ts_export::<ComicVineMatchRequest>()?).as_bytes(),
+		)?;
+		file.write_all(
+			format!("{}\n\n", ts_export::<ConfirmComicVineMatch>()?).as_bytes(),
+		)?;
+		file.write_all(
+			format!("{}\n\n", ts_export::<ComicVineMatchResponse>()?).as_bytes(),
+		)?;
 		file.write_all(
 			format!("{}\n\n", ts_export::<CreateOrUpdateBookmark>()?).as_bytes(),
 		)?;
 		file.write_all(format!("{}\n\n", ts_export::<DeleteBookmark>()?).as_bytes())?;
 		file.write_all(format!("{}\n\n", ts_export::<SeriesIsComplete>()?).as_bytes())?;
 		file.write_all(
-			format!("{}\n\n", ts_export::<UpdateSchedulerConfig>()?).as_bytes(),
+			format!("{}\n\n", ts_export::<CreateOrUpdateJobSchedule>()?).as_bytes(),
+		)?;
+		file.write_all(format!("{}\n\n", ts_export::<PeopleQuery>()?).as_bytes())?;
+		file.write_all(format!("{}\n\n", ts_export::<PersonMediaQuery>()?).as_bytes())?;
+		file.write_all(format!("{}\n\n", ts_export::<MergePeople>()?).as_bytes())?;
+		file.write_all(
+			format!("{}\n\n", ts_export::<SyncPeopleCreditsResponse>()?).as_bytes(),
 		)?;
 
 		file.write_all(format!("{}\n\n", ts_export::<GetBookClubsParams>()?).as_bytes())?;
@@ -0,0 +1,590 @@
+use axum::{
+	extract::{Path, State},
+	middleware,
+	routing::get,
+	Extension, Json, Router,
+};
+use axum_extra::extract::Query;
+use prisma_client_rust::{or, Direction, QueryError};
+use stump_core::{
+	db::{
+		entity::{
+			Collection, CollectionItem, CollectionItemInput, CreateOrUpdateCollection,
+			User, UserPermission,
+		},
+		query::pagination::{Pageable, Pagination, PaginationQuery},
+	},
+	prisma::{
+		active_reading_session, collection, collection_item, finished_reading_session,
+		media, series, PrismaClient,
+	},
+};
+use tracing::trace;
+
+use crate::{
+	config::state::AppState,
+	errors::{APIError, APIResult},
+	middleware::auth::{auth_middleware, RequestContext},
+	routers::api::{
+		filters::{
+			apply_media_restrictions_for_user, apply_series_restrictions_for_user,
+		},
+		v1::{
+			media::thumbnails::get_media_thumbnail_by_id,
+			series::get_series_thumbnail_by_id,
+		},
+	},
+	utils::http::ImageResponse,
+};
+
+pub(crate) fn mount(app_state: AppState) -> Router<AppState> {
+	Router::new()
+		.route("/collections", get(get_collections).post(create_collection))
+		.nest(
+			"/collections/:id",
+			Router::new()
+				.route(
+					"/",
+					get(get_collection_by_id)
+						.put(update_collection)
+						.delete(delete_collection),
+				)
+				.route("/items", get(get_collection_items))
+				.route("/thumbnail", get(get_collection_thumbnail)),
+		)
+		.layer(middleware::from_fn_with_state(app_state, auth_middleware))
+}
+
+/// A filter for the items of a collection which the user can access, i.e. series or books
+/// which are not in a hidden library or restricted by age
+pub(crate) fn collection_item_visible_to_user_filter(
+	user: &User,
+) -> collection_item::WhereParam {
+	or![
+		collection_item::series::is(apply_series_restrictions_for_user(user)),
+		collection_item::media::is(apply_media_restrictions_for_user(user)),
+	]
+}
+
+/// A filter for the collections visible to the user. Users who can manage libraries see
+/// every collection, including empty ones, while everyone else only sees the collections
+/// with at least one item they can access.
+pub(crate) fn collection_visible_to_user_filter(
+	user: &User,
+) -> Vec<collection::WhereParam> {
+	if user.has_permission(UserPermission::ManageLibrary) {
+		vec![]
+	} else {
+		vec![collection::items::some(vec![
+			collection_item_visible_to_user_filter(user),
+		])]
+	}
+}
+
+/// Fetch the items of a collection which the user can access, in the order of the
+/// collection. Series are loaded with their metadata, and books with their metadata and
+/// the reading sessions of the user.
+pub(crate) async fn fetch_collection_items(
+	ctx: &AppState,
+	collection_id: String,
+	user: &User,
+) -> APIResult<Vec<collection_item::Data>> {
+	let items = ctx
+		.db
+		.collection_item()
+		.find_many(vec![
+			collection_item::collection_id::equals(collection_id),
+			collection_item_visible_to_user_filter(user),
+		])
+		.with(collection_item::series::fetch().with(series::metadata::fetch()))
+		.with(
+			collection_item::media::fetch()
+				.with(media::metadata::fetch())
+				.with(media::active_user_reading_sessions::fetch(vec![
+					active_reading_session::user_id::equals(user.id.clone()),
+				]))
+				.with(media::finished_user_reading_sessions::fetch(vec![
+					finished_reading_session::user_id::equals(user.id.clone()),
+				])),
+		)
+		.order_by(collection_item::position::order(Direction::Asc))
+		.exec()
+		.await?;
+
+	Ok(items)
+}
+
+/// Assert that every series and book to be added to a collection exists and is accessible
+/// by the user
+async fn validate_collection_items(
+	db: &PrismaClient,
+	user: &User,
+	items: &[CollectionItemInput],
+) -> APIResult<()> {
+	let (series_ids, media_ids) = items.iter().fold(
+		(Vec::new(), Vec::new()),
+		|(mut series_ids, mut media_ids), item| {
+			match item {
+				CollectionItemInput::Series { series_id } => {
+					series_ids.push(series_id.clone())
+				},
+				CollectionItemInput::Media { media_id } => {
+					media_ids.push(media_id.clone())
+				},
+			}
+			(series_ids, media_ids)
+		},
+	);
+
+	let visible_series = db
+		.series()
+		.count(
+			[series::id::in_vec(series_ids.clone())]
+				.into_iter()
+				.chain(apply_series_restrictions_for_user(user))
+				.collect(),
+		)
+		.exec()
+		.await?;
+	let visible_media = db
+		.media()
+		.count(
+			[media::id::in_vec(media_ids.clone())]
+				.into_iter()
+				.chain(apply_media_restrictions_for_user(user))
+				.collect(),
+		)
+		.exec()
+		.await?;
+
+	if visible_series != series_ids.len() as i64
+		|| visible_media != media_ids.len() as i64
+	{
+		return Err(APIError::NotFound(String::from(
+			"One or more collection items were not found",
+		)));
+	}
+
+	Ok(())
+}
+
+/// Replace the items of a collection with the given items, in order
+async fn set_collection_items(
+	client: &PrismaClient,
+	collection_id: &str,
+	items: Vec<CollectionItemInput>,
+) -> Result<Vec<collection_item::Data>, QueryError> {
+	client
+		.collection_item()
+		.delete_many(vec![collection_item::collection_id::equals(
+			collection_id.to_string(),
+		)])
+		.exec()
+		.await?;
+
+	let item_creates = items
+		.into_iter()
+		.enumerate()
+		.map(|(idx, item)| {
+			let params = match item {
+				CollectionItemInput::Series { series_id } => {
+					vec![collection_item::series::connect(series::id::equals(
+						series_id,
+					))]
+				},
+				CollectionItemInput::Media { media_id } => {
+					vec![collection_item::media::connect(media::id::equals(media_id))]
+				},
+			};
+
+			client.collection_item().create(
+				idx as i32,
+				collection::id::equals(collection_id.to_string()),
+				params,
+			)
+		})
+		.collect::<Vec<_>>();
+
+	client._batch(item_creates).await
+}
+
+#[utoipa::path(
+	get,
+	path = "/api/v1/collections",
+	tag = "collection",
+	params(
+		("pagination_query" = Option<PaginationQuery>, Query, description = "The pagination options"),
+	),
+	responses(
+		(status = 200, description = "Successfully fetched collections.", body = [Collection]),
+		(status = 401, description = "Unauthorized."),
+		(status = 500, description = "Internal server error."),
+	)
+)]
+/// Get all collections visible to the user, ordered by name
+async fn get_collections(
+	pagination_query: Query<PaginationQuery>,
+	State(ctx): State<AppState>,
+	Extension(req): Extension<RequestContext>,
+) -> APIResult<Json<Pageable<Vec<Collection>>>> {
+	let pagination = pagination_query.0.get();
+	let pagination_cloned = pagination.clone();
+
+	trace!(?pagination, "get_collections");
+
+	let is_unpaged = pagination.is_unpaged();
+	let where_params = collection_visible_to_user_filter(req.user());
+
+	let (collections, count) = ctx
+		.db
+		._transaction()
+		.run(|client| async move {
+			let mut query = client
+				.collection()
+				.find_many(where_params.clone())
+				.order_by(collection::name::order(Direction::Asc));
+
+			if !is_unpaged {
+				match pagination_cloned {
+					Pagination::Page(page_query) => {
+						let (skip, take) = page_query.get_skip_take();
+						query = query.skip(skip).take(take);
+					},
+					Pagination::Cursor(cursor_query) => {
+						if let Some(cursor) = cursor_query.cursor {
+							query = query.cursor(collection::id::equals(cursor)).skip(1);
+						}
+						if let Some(limit) = cursor_query.limit {
+							query = query.take(limit);
+						}
+					},
+					_ => unreachable!(),
+				}
+			}
+
+			let collections = query
+				.exec()
+				.await?
+				.into_iter()
+				.map(Collection::from)
+				.collect::<Vec<_>>();
+
+			if is_unpaged {
+				return Ok((collections, None));
+			}
+
+			client
+				.collection()
+				.count(where_params)
+				.exec()
+				.await
+				.map(|count| (collections, Some(count)))
+		})
+		.await?;
+
+	if let Some(count) = count {
+		return Ok(Json(Pageable::from((collections, count, pagination))));
+	}
+
+	Ok(Json(Pageable::from(collections)))
+}
+
+#[utoipa::path(
+	post,
+	path = "/api/v1/collections",
+	tag = "collection",
+	request_body = CreateOrUpdateCollection,
+	responses(
+		(status = 200, description = "Successfully created collection.", body = Collection),
+		(status = 401, description = "Unauthorized."),
+		(status = 403, description = "Forbidden."),
+		(status = 404, description = "One or more items not found."),
+		(status = 500, description = "Internal server error."),
+	)
+)]
+/// Create a collection, optionally with its items in order
+async fn create_collection(
+	State(ctx): State<AppState>,
+	Extension(req): Extension<RequestContext>,
+	Json(input): Json<CreateOrUpdateCollection>,
+) -> APIResult<Json<Collection>> {
+	let user = req.user_and_enforce_permissions(&[UserPermission::ManageLibrary])?;
+
+	let items = CollectionItemInput::dedupe(input.items.unwrap_or_default());
+	validate_collection_items(&ctx.db, &user, &items).await?;
+
+	let (created_collection, created_items) = ctx
+		.db
+		._transaction()
+		.run(|client| async move {
+			let created_collection = client
+				.collection()
+				.create(
+					input.name,
+					vec![collection::description::set(input.description)],
+				)
+				.exec()
+				.await?;
+
+			set_collection_items(&client, &created_collection.id, items)
+				.await
+				.map(|created_items| (created_collection, created_items))
+		})
+		.await?;
+
+	Ok(Json(Collection {
+		items: Some(
+			created_items
+				.into_iter()
+				.map(CollectionItem::from)
+				.collect(),
+		),
+		..Collection::from(created_collection)
+	}))
+}
+
+#[utoipa::path(
+	get,
+	path = "/api/v1/collections/:id",
+	tag = "collection",
+	params(
+		("id" = String, Path, description = "The ID of the collection"),
+	),
+	responses(
+		(status = 200, description = "Successfully fetched collection.", body = Collection),
+		(status = 401, description = "Unauthorized."),
+		(status = 404, description = "Collection not found."),
+		(status = 500, description = "Internal server error."),
+	)
+)]
+/// Get a collection by its ID
+async fn get_collection_by_id(
+	Path(id): Path<String>,
+	State(ctx): State<AppState>,
+	Extension(req): Extension<RequestContext>,
+) -> APIResult<Json<Collection>> {
+	let collection = ctx
+		.db
+		.collection()
+		.find_first(
+			[collection::id::equals(id.clone())]
+				.into_iter()
+				.chain(collection_visible_to_user_filter(req.user()))
+				.collect(),
+		)
+		.exec()
+		.await?
+		.ok_or(APIError::NotFound(format!(
+			"Collection with id {id} not found"
+		)))?;
+
+	Ok(Json(Collection::from(collection)))
+}
+
+#[utoipa::path(
+	put,
+	path = "/api/v1/collections/:id",
+	tag = "collection",
+	params(
+		("id" = String, Path, description = "The ID of the collection"),
+	),
+	request_body = CreateOrUpdateCollection,
+	responses(
+		(status = 200, description = "Successfully updated collection.", body = Collection),
+		(status = 401, description = "Unauthorized."),
+		(status = 403, description = "Forbidden."),
+		(status = 404, description = "Collection or items not found."),
+		(status = 500, description = "Internal server error."),
+	)
+)]
+/// Update the name and description of a collection. If items are provided, they replace
+/// the items of the collection in the given order.
+async fn update_collection(
+	Path(id): Path<String>,
+	State(ctx): State<AppState>,
+	Extension(req): Extension<RequestContext>,
+	Json(input): Json<CreateOrUpdateCollection>,
+) -> APIResult<Json<Collection>> {
+	let user = req.user_and_enforce_permissions(&[UserPermission::ManageLibrary])?;
+
+	ctx.db
+		.collection()
+		.find_unique(collection::id::equals(id.clone()))
+		.exec()
+		.await?
+		.ok_or(APIError::NotFound(format!(
+			"Collection with id {id} not found"
+		)))?;
+
+	let items = input.items.map(CollectionItemInput::dedupe);
+	if let Some(ref items) = items {
+		validate_collection_items(&ctx.db, &user, items).await?;
+	}
+
+	let (updated_collection, updated_items) = ctx
+		.db
+		._transaction()
+		.run(|client| async move {
+			let updated_collection = client
+				.collection()
+				.update(
+					collection::id::equals(id),
+					vec![
+						collection::name::set(input.name),
+						collection::description::set(input.description),
+					],
+				)
+				.exec()
+				.await?;
+
+			match items {
+				Some(items) => {
+					set_collection_items(&client, &updated_collection.id, items)
+						.await
+						.map(|updated_items| (updated_collection, Some(updated_items)))
+				},
+				None => Ok((updated_collection, None)),
+			}
+		})
+		.await?;
+
+	Ok(Json(Collection {
+		items: updated_items
+			.map(|items| items.into_iter().map(CollectionItem::from).collect()),
+		..Collection::from(updated_collection)
+	}))
+}
+
+#[utoipa::path(
+	delete,
+	path = "/api/v1/collections/:id",
+	tag = "collection",
+	params(
+		("id" = String, Path, description = "The ID of the collection"),
+	),
+	responses(
+		(status = 200, description = "Successfully deleted collection.", body = Collection),
+		(status = 401, description = "Unauthorized."),
+		(status = 403, description = "Forbidden."),
+		(status = 404, description = "Collection not found."),
+		(status = 500, description = "Internal server error."),
+	)
+)]
+/// Delete a collection. The series and books in it are not affected
+async fn delete_collection(
+	Path(id): Path<String>,
+	State(ctx): State<AppState>,
+	Extension(req): Extension<RequestContext>,
+) -> APIResult<Json<Collection>> {
+	req.enforce_permissions(&[UserPermission::ManageLibrary])?;
+
+	ctx.db
+		.collection()
+		.find_unique(collection::id::equals(id.clone()))
+		.exec()
+		.await?
+		.ok_or(APIError::NotFound(format!(
+			"Collection with id {id} not found"
+		)))?;
+
+	let deleted_collection = ctx
+		.db
+		.collection()
+		.delete(collection::id::equals(id))
+		.exec()
+		.await?;
+
+	Ok(Json(Collection::from(deleted_collection)))
+}
+
+#[utoipa::path(
+	get,
+	path = "/api/v1/collections/:id/items",
+	tag = "collection",
+	params(
+		("id" = String, Path, description = "The ID of the collection"),
+	),
+	responses(
+		(status = 200, description = "Successfully fetched collection items.", body = [CollectionItem]),
+		(status = 401, description = "Unauthorized."),
+		(status = 404, description = "Collection not found."),
+		(status = 500, description = "Internal server error."),
+	)
+)]
+/// Get the items of a collection which are accessible by the user, in order
+async fn get_collection_items(
+	Path(id): Path<String>,
+	State(ctx): State<AppState>,
+	Extension(req): Extension<RequestContext>,
+) -> APIResult<Json<Vec<CollectionItem>>> {
+	let user = req.user();
+
+	ctx.db
+		.collection()
+		.find_first(
+			[collection::id::equals(id.clone())]
+				.into_iter()
+				.chain(collection_visible_to_user_filter(user))
+				.collect(),
+		)
+		.exec()
+		.await?
+		.ok_or(APIError::NotFound(format!(
+			"Collection with id {id} not found"
+		)))?;
+
+	let items = fetch_collection_items(&ctx, id, user).await?;
+
+	Ok(Json(items.into_iter().map(CollectionItem::from).collect()))
+}
+
+#[utoipa::path(
+	get,
+	path = "/api/v1/collections/:id/thumbnail",
+	tag = "collection",
+	params(
+		("id" = String, Path, description = "The ID of the collection"),
+	),
+	responses(
+		(status = 200, description = "Successfully fetched collection thumbnail."),
+		(status = 401, description = "Unauthorized."),
+		(status = 404, description = "Collection not found."),
+		(status = 500, description = "Internal server error."),
+	)
+)]
+/// Get the thumbnail of a collection, which is the thumbnail of its first item accessible
+/// by the user
+async fn get_collection_thumbnail(
+	Path(id): Path<String>,
+	State(ctx): State<AppState>,
+	Extension(req): Extension<RequestContext>,
+) -> APIResult<ImageResponse> {
+	let user = req.user();
+
+	let first_item = ctx
+		.db
+		.collection_item()
+		.find_first(vec![
+			collection_item::collection_id::equals(id),
+			collection_item_visible_to_user_filter(user),
+		])
+		.order_by(collection_item::position::order(Direction::Asc))
+		.exec()
+		.await?
+		.ok_or(APIError::NotFound(String::from(
+			"Collection does not have a thumbnail",
+		)))?;
+
+	match (first_item.series_id, first_item.media_id) {
+		(Some(series_id), _) => {
+			get_series_thumbnail_by_id(series_id, &ctx.db, user, &ctx.config).await
+		},
+		(None, Some(media_id)) => {
+			get_media_thumbnail_by_id(media_id, &ctx.db, user, &ctx.config).await
+		},
+		(None, None) => Err(APIError::NotFound(String::from(
+			"Collection does not have a thumbnail",
+		))),
+	}
+	.map(ImageResponse::from)
+}
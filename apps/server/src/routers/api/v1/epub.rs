@@ -13,12 +13,13 @@ use stump_core::{
 		ActiveReadingSession, Bookmark, Epub, FinishedReadingSession,
 		ProgressUpdateReturn, UpdateEpubProgress,
 	},
-	filesystem::media::EpubProcessor,
+	filesystem::{media::EpubProcessor, storage::resolve_local_path},
 	prisma::{
 		active_reading_session, bookmark, finished_reading_session, media,
 		media_annotation, user,
 	},
 };
+use tokio::task::spawn_blocking;
 use utoipa::ToSchema;
 
 use crate::{
@@ -72,7 +73,7 @@ async fn get_epub_by_id(
 		.await?;
 
 	if let Some(book) = result {
-		Ok(Json(Epub::try_from(book)?))
+		Ok(Json(Epub::try_from(book, &ctx.config)?))
 	} else {
 		Err(APIError::NotFound(format!("Media with id {id} not found")))
 	}
@@ -276,7 +277,17 @@ async fn get_epub_chapter(
 		.await?;
 
 	if let Some(book) = result {
-		Ok(EpubProcessor::get_chapter(book.path.as_str(), chapter)?.into())
+		let config = ctx.config.clone();
+		// Resolving the path may download the book from object storage, so it is done
+		// alongside the (blocking) read of the chapter
+		let chapter = spawn_blocking(move || {
+			let local_path =
+				resolve_local_path(std::path::Path::new(&book.path), &config)?;
+			EpubProcessor::get_chapter(local_path.to_str().unwrap_or_default(), chapter)
+		})
+		.await
+		.map_err(|e| APIError::InternalServerError(e.to_string()))??;
+		Ok(chapter.into())
 	} else {
 		Err(APIError::NotFound(format!("Media with id {id} not found")))
 	}
@@ -299,21 +310,27 @@ async fn get_epub_meta(
 		.await?;
 
 	if let Some(book) = result {
-		let (content_type, buffer) = if root == "META-INF" {
-			// reserved for accessing resources via resource id
-			EpubProcessor::get_resource_by_id(
-				book.path.as_str(),
-				resource.to_str().unwrap_or_default(),
-			)?
-		} else {
-			// NOTE: when a resource is loaded from a path, it is likely something inside the contents of an epub page,
-			// such as a css file or an image file.
-			EpubProcessor::get_resource_by_path(
-				book.path.as_str(),
-				root.as_str(),
-				resource,
-			)?
-		};
+		let config = ctx.config.clone();
+		// Resolving the path may download the book from object storage, so it is done
+		// alongside the (blocking) read of the resource
+		let (content_type, buffer) = spawn_blocking(move || {
+			let local_path =
+				resolve_local_path(std::path::Path::new(&book.path), &config)?;
+			let book_path = local_path.to_str().unwrap_or_default();
+			if root == "META-INF" {
+				// reserved for accessing resources via resource id
+				EpubProcessor::get_resource_by_id(
+					book_path,
+					resource.to_str().unwrap_or_default(),
+				)
+			} else {
+				// NOTE: when a resource is loaded from a path, it is likely something inside the contents of an epub page,
+				// such as a css file or an image file.
+				EpubProcessor::get_resource_by_path(book_path, root.as_str(), resource)
+			}
+		})
+		.await
+		.map_err(|e| APIError::InternalServerError(e.to_string()))??;
 
 		Ok(BufferResponse::new(content_type, buffer))
 	} else {
@@ -1,24 +1,33 @@
+use std::collections::HashMap;
+
 use axum::{
 	extract::{Path, Query, State},
 	middleware,
 	routing::{delete, get},
 	Json, Router,
 };
+use prisma_client_rust::{
+	chrono::{DateTime, FixedOffset, Utc},
+	Direction,
+};
 use serde::{Deserialize, Serialize};
 use serde_qs::axum::QsQuery;
 use specta::Type;
 use stump_core::{
 	db::{
-		entity::{JobSchedulerConfig, PersistedJob},
+		entity::{JobSchedule, PersistedJob, ScheduledJobKind},
 		query::{
 			ordering::QueryOrder,
 			pagination::{Pageable, Pagination, PaginationQuery},
 		},
 	},
-	job::{AcknowledgeableCommand, JobControllerCommand},
+	job::{
+		next_scheduled_run, parse_cron_expression, AcknowledgeableCommand,
+		JobControllerCommand, JobStatus, QueuedJob,
+	},
 	prisma::{
 		job::{self, OrderByParam as JobOrderByParam},
-		job_schedule_config, library, server_config,
+		job_schedule, library, PrismaClient,
 	},
 };
 use tokio::sync::oneshot;
@@ -27,8 +36,8 @@ use utoipa::ToSchema;
 use crate::{
 	config::state::AppState,
 	errors::{APIError, APIResult},
-	filter::chain_optional_iter,
 	middleware::auth::{auth_middleware, server_owner_middleware},
+	utils::default_true,
 };
 
 pub(crate) fn mount(app_state: AppState) -> Router<AppState> {
@@ -37,15 +46,23 @@ pub(crate) fn mount(app_state: AppState) -> Router<AppState> {
 			"/jobs",
 			Router::new()
 				.route("/", get(get_jobs).delete(delete_jobs))
+				.route("/queue", get(get_job_queue))
 				.nest(
 					"/:id",
 					Router::new()
-						.route("/", delete(delete_job_by_id))
+						.route("/", get(get_job_by_id).delete(delete_job_by_id))
 						.route("/cancel", delete(cancel_job_by_id)),
 				)
-				.route(
-					"/scheduler-config",
-					get(get_scheduler_config).post(update_scheduler_config),
+				.nest(
+					"/schedules",
+					Router::new()
+						.route("/", get(get_job_schedules).post(create_job_schedule))
+						.route(
+							"/:id",
+							get(get_job_schedule_by_id)
+								.put(update_job_schedule)
+								.delete(delete_job_schedule),
+						),
 				),
 		)
 		// TODO: consider permissions around job management
@@ -57,9 +74,13 @@ pub(crate) fn mount(app_state: AppState) -> Router<AppState> {
 pub struct GetJobsParams {
 	#[serde(default)]
 	pub load_logs: bool,
+	/// Only return jobs with the given status, e.g. `DEAD_LETTER` for jobs which have
+	/// exhausted their retries
+	#[serde(default)]
+	pub status: Option<JobStatus>,
 }
 
-// TODO: support filtering
+// TODO: support more filtering
 #[utoipa::path(
 	get,
 	path = "/api/v1/jobs",
@@ -81,6 +102,11 @@ async fn get_jobs(
 	let pagination = pagination_query.0.get();
 	let order = order.0;
 	let load_logs = relation_query.load_logs;
+	let where_params = relation_query
+		.status
+		.as_ref()
+		.map(|status| vec![job::status::equals(status.to_string())])
+		.unwrap_or_default();
 
 	tracing::trace!(load_logs, ?pagination, ?order, "get_jobs");
 
@@ -93,7 +119,10 @@ async fn get_jobs(
 	let (jobs, count) = db
 		._transaction()
 		.run(|client| async move {
-			let mut query = client.job().find_many(vec![]).order_by(order_by_param);
+			let mut query = client
+				.job()
+				.find_many(where_params.clone())
+				.order_by(order_by_param);
 
 			if load_logs {
 				query = query.with(job::logs::fetch(vec![]));
@@ -130,7 +159,7 @@ async fn get_jobs(
 
 			client
 				.job()
-				.count(vec![])
+				.count(where_params)
 				.exec()
 				.await
 				.map(|count| (jobs, Some(count)))
@@ -144,6 +173,23 @@ async fn get_jobs(
 	Ok(Json(Pageable::from(jobs)))
 }
 
+#[utoipa::path(
+	get,
+	path = "/api/v1/jobs/queue",
+	tag = "job",
+	responses(
+		(status = 200, description = "Successfully retrieved the job queue", body = [QueuedJob]),
+		(status = 401, description = "No user is logged in (unauthorized)."),
+		(status = 403, description = "User does not have permission to access this resource."),
+		(status = 500, description = "Internal server error."),
+	)
+)]
+/// Get the jobs which are waiting for a worker, in the order they will be started. Running
+/// jobs are not included.
+async fn get_job_queue(State(ctx): State<AppState>) -> APIResult<Json<Vec<QueuedJob>>> {
+	Ok(Json(ctx.job_controller.get_queued_jobs().await))
+}
+
 #[utoipa::path(
 	delete,
 	path = "/api/v1/jobs",
@@ -162,6 +208,83 @@ async fn delete_jobs(State(ctx): State<AppState>) -> APIResult<()> {
 	Ok(())
 }
 
+#[utoipa::path(
+	get,
+	path = "/api/v1/jobs/:id",
+	tag = "job",
+	params(
+		("id" = String, Path, description = "The ID of the job to fetch."),
+		("load_logs" = Option<bool>, Query, description = "Whether to include the job's logs."),
+	),
+	responses(
+		(status = 200, description = "Successfully retrieved job report", body = PersistedJob),
+		(status = 401, description = "No user is logged in (unauthorized)."),
+		(status = 403, description = "User does not have permission to access this resource."),
+		(status = 404, description = "Job not found."),
+		(status = 500, description = "Internal server error."),
+	)
+)]
+/// Get a job by its ID, including its output once it has completed and the tree of
+/// follow-up jobs which descend from it
+async fn get_job_by_id(
+	State(ctx): State<AppState>,
+	Path(job_id): Path<String>,
+	relation_query: Query<GetJobsParams>,
+) -> APIResult<Json<PersistedJob>> {
+	let mut query = ctx.db.job().find_unique(job::id::equals(job_id.clone()));
+	if relation_query.load_logs {
+		query = query.with(job::logs::fetch(vec![]));
+	}
+
+	let job = query.exec().await?.ok_or(APIError::NotFound(format!(
+		"Job with id {job_id} not found"
+	)))?;
+
+	let mut job = PersistedJob::from(job);
+	load_job_children(&ctx.db, &mut job).await?;
+
+	Ok(Json(job))
+}
+
+/// Load every job which descends from the given job, and attach them as its children
+async fn load_job_children(db: &PrismaClient, job: &mut PersistedJob) -> APIResult<()> {
+	let mut children_by_parent = HashMap::<String, Vec<PersistedJob>>::new();
+	let mut parent_ids = vec![job.id.clone()];
+
+	while !parent_ids.is_empty() {
+		let children = db
+			.job()
+			.find_many(vec![job::parent_id::in_vec(parent_ids)])
+			.order_by(job::created_at::order(Direction::Asc))
+			.exec()
+			.await?;
+		parent_ids = children.iter().map(|child| child.id.clone()).collect();
+
+		for child in children {
+			let parent_id = child.parent_id.clone().unwrap_or_default();
+			children_by_parent
+				.entry(parent_id)
+				.or_default()
+				.push(PersistedJob::from(child));
+		}
+	}
+
+	attach_job_children(job, &mut children_by_parent);
+
+	Ok(())
+}
+
+fn attach_job_children(
+	job: &mut PersistedJob,
+	children_by_parent: &mut HashMap<String, Vec<PersistedJob>>,
+) {
+	let mut children = children_by_parent.remove(&job.id).unwrap_or_default();
+	for child in children.iter_mut() {
+		attach_job_children(child, children_by_parent);
+	}
+	job.children = Some(children);
+}
+
 #[utoipa::path(
 	delete,
 	path = "/api/v1/jobs/:id",
@@ -224,144 +347,260 @@ async fn cancel_job_by_id(
 
 #[utoipa::path(
 	get,
-	path = "/api/v1/jobs/scheduler-config",
+	path = "/api/v1/jobs/schedules",
 	tag = "job",
 	responses(
-		(status = 200, description = "Successfully fetched JobSchedulerConfig", body = JobSchedulerConfig),
-		(status = 401, description = "No user is not logged in (unauthorized)."),
+		(status = 200, description = "Successfully fetched job schedules", body = [JobSchedule]),
+		(status = 401, description = "No user is logged in (unauthorized)."),
 		(status = 403, description = "User does not have permission to access this resource."),
 		(status = 500, description = "Internal server error."),
 	)
 )]
-async fn get_scheduler_config(
+/// Get all of the job schedules
+async fn get_job_schedules(
 	State(ctx): State<AppState>,
-) -> APIResult<Json<JobSchedulerConfig>> {
-	let client = &ctx.db;
-
-	let server_config = client
-		.server_config()
-		.find_first(vec![])
-		.with(
-			server_config::job_schedule_config::fetch()
-				.with(job_schedule_config::excluded_libraries::fetch(vec![])),
-		)
+) -> APIResult<Json<Vec<JobSchedule>>> {
+	let schedules = ctx
+		.db
+		.job_schedule()
+		.find_many(vec![])
+		.with(job_schedule::target_libraries::fetch(vec![]))
+		.order_by(job_schedule::created_at::order(Direction::Asc))
 		.exec()
 		.await?
-		.ok_or(APIError::InternalServerError(
-			"Server preferences have not been initialized".to_string(),
-		))?;
-
-	let config = server_config
-		.job_schedule_config()?
-		.map(|c| c.to_owned())
-		.ok_or(APIError::NotFound(
-			"Job scheduler config has not been initialized".to_string(),
-		))?;
-
-	Ok(Json(JobSchedulerConfig::from(config)))
+		.into_iter()
+		.map(JobSchedule::try_from)
+		.collect::<Result<Vec<_>, _>>()?;
+
+	Ok(Json(schedules))
+}
+
+#[derive(Debug, Deserialize, Serialize, ToSchema, Type)]
+pub struct CreateOrUpdateJobSchedule {
+	pub name: String,
+	/// A standard 5-field cron expression, e.g. `0 3 * * *` for every day at 3am
+	pub cron_expression: String,
+	pub job_kind: ScheduledJobKind,
+	#[serde(default = "default_true")]
+	pub enabled: bool,
+	/// The libraries to target, if the job kind operates on libraries. An empty list targets
+	/// every library
+	#[serde(default)]
+	pub target_library_ids: Vec<String>,
 }
 
-#[derive(Debug, Deserialize, Serialize, ToSchema, specta::Type)]
-pub struct UpdateSchedulerConfig {
-	pub interval_secs: Option<i32>,
-	pub excluded_library_ids: Option<Vec<String>>,
+impl CreateOrUpdateJobSchedule {
+	fn validate(&self) -> APIResult<()> {
+		if self.name.trim().is_empty() {
+			return Err(APIError::BadRequest(
+				"The name of a schedule cannot be empty".to_string(),
+			));
+		}
+
+		parse_cron_expression(&self.cron_expression)?;
+
+		if !self.job_kind.targets_libraries() && !self.target_library_ids.is_empty() {
+			return Err(APIError::BadRequest(format!(
+				"{} jobs cannot target specific libraries",
+				self.job_kind
+			)));
+		}
+
+		Ok(())
+	}
+
+	/// The next time the schedule should run, which is reset whenever the schedule changes
+	fn next_run_at(&self) -> APIResult<Option<DateTime<FixedOffset>>> {
+		if !self.enabled {
+			return Ok(None);
+		}
+		let next_run_at = next_scheduled_run(&self.cron_expression, Utc::now())?;
+		Ok(Some(next_run_at.into()))
+	}
 }
 
 #[utoipa::path(
 	post,
-	path = "/api/v1/jobs/scheduler-config",
+	path = "/api/v1/jobs/schedules",
 	tag = "job",
+	request_body = CreateOrUpdateJobSchedule,
 	responses(
-		(status = 200, description = "Successfully updated JobSchedulerConfig", body = Option<JobSchedulerConfig>),
-		(status = 401, description = "No user is not logged in (unauthorized)."),
+		(status = 200, description = "Successfully created job schedule", body = JobSchedule),
+		(status = 400, description = "Invalid cron expression or targets."),
+		(status = 401, description = "No user is logged in (unauthorized)."),
 		(status = 403, description = "User does not have permission to access this resource."),
 		(status = 500, description = "Internal server error."),
 	)
 )]
-async fn update_scheduler_config(
+/// Create a new job schedule. The scheduler picks up the schedule immediately.
+async fn create_job_schedule(
 	State(ctx): State<AppState>,
-	Json(input): Json<UpdateSchedulerConfig>,
-) -> APIResult<Json<Option<JobSchedulerConfig>>> {
-	let db = &ctx.db;
+	Json(input): Json<CreateOrUpdateJobSchedule>,
+) -> APIResult<Json<JobSchedule>> {
+	input.validate()?;
+	let next_run_at = input.next_run_at()?;
+
+	let schedule = ctx
+		.db
+		.job_schedule()
+		.create(
+			input.name.trim().to_string(),
+			input.cron_expression,
+			input.job_kind.to_string(),
+			vec![
+				job_schedule::enabled::set(input.enabled),
+				job_schedule::next_run_at::set(next_run_at),
+				job_schedule::target_libraries::connect(
+					input
+						.target_library_ids
+						.into_iter()
+						.map(library::id::equals)
+						.collect(),
+				),
+			],
+		)
+		.with(job_schedule::target_libraries::fetch(vec![]))
+		.exec()
+		.await?;
+	ctx.job_scheduler.reload();
 
-	let should_remove_config =
-		input.excluded_library_ids.is_none() && input.interval_secs.is_none();
+	Ok(Json(JobSchedule::try_from(schedule)?))
+}
 
-	tracing::trace!(should_remove_config, ?input, "update_scheduler_config");
+#[utoipa::path(
+	get,
+	path = "/api/v1/jobs/schedules/:id",
+	tag = "job",
+	params(
+		("id" = String, Path, description = "The ID of the job schedule to fetch."),
+	),
+	responses(
+		(status = 200, description = "Successfully fetched job schedule", body = JobSchedule),
+		(status = 401, description = "No user is logged in (unauthorized)."),
+		(status = 403, description = "User does not have permission to access this resource."),
+		(status = 404, description = "Job schedule not found."),
+		(status = 500, description = "Internal server error."),
+	)
+)]
+/// Get a job schedule by its ID
+async fn get_job_schedule_by_id(
+	State(ctx): State<AppState>,
+	Path(id): Path<String>,
+) -> APIResult<Json<JobSchedule>> {
+	let schedule = ctx
+		.db
+		.job_schedule()
+		.find_unique(job_schedule::id::equals(id.clone()))
+		.with(job_schedule::target_libraries::fetch(vec![]))
+		.exec()
+		.await?
+		.ok_or(APIError::NotFound(format!(
+			"Job schedule with id {id} not found"
+		)))?;
 
-	let result: Result<Option<JobSchedulerConfig>, APIError> = db
-		._transaction()
-		.run(|client| async move {
-			let server_config = client
-				.server_config()
-				.find_first(vec![])
-				.with(server_config::job_schedule_config::fetch())
-				.exec()
-				.await?
-				.ok_or(APIError::InternalServerError(String::from(
-					"Server preferences are missing!",
-				)))?;
-
-			let existing_config = server_config.job_schedule_config()?.cloned();
-
-			if should_remove_config {
-				client
-					.job_schedule_config()
-					.delete_many(vec![])
-					.exec()
-					.await?;
-				Ok(None)
-			} else if let Some(config) = existing_config {
-				let updated_config = client
-					.job_schedule_config()
-					.update(
-						job_schedule_config::id::equals(config.id),
-						chain_optional_iter(
-							[],
-							[
-								input
-									.interval_secs
-									.map(job_schedule_config::interval_secs::set),
-								input.excluded_library_ids.map(|list| {
-									job_schedule_config::excluded_libraries::set(
-										list.into_iter()
-											.map(library::id::equals)
-											.collect(),
-									)
-								}),
-							],
-						),
-					)
-					.with(job_schedule_config::excluded_libraries::fetch(vec![]))
-					.exec()
-					.await?;
-				Ok(Some(JobSchedulerConfig::from(updated_config)))
-			} else {
-				let created_config = client
-					.job_schedule_config()
-					.create(chain_optional_iter(
-						[job_schedule_config::server_config::connect(
-							server_config::id::equals(server_config.id),
-						)],
-						[
-							input
-								.interval_secs
-								.map(job_schedule_config::interval_secs::set),
-							input.excluded_library_ids.map(|list| {
-								job_schedule_config::excluded_libraries::connect(
-									list.into_iter().map(library::id::equals).collect(),
-								)
-							}),
-						],
-					))
-					.with(job_schedule_config::excluded_libraries::fetch(vec![]))
-					.exec()
-					.await?;
-				Ok(Some(JobSchedulerConfig::from(created_config)))
-			}
-		})
-		.await;
-	let updated_or_deleted_config = result?;
+	Ok(Json(JobSchedule::try_from(schedule)?))
+}
 
-	Ok(Json(updated_or_deleted_config))
+#[utoipa::path(
+	put,
+	path = "/api/v1/jobs/schedules/:id",
+	tag = "job",
+	request_body = CreateOrUpdateJobSchedule,
+	params(
+		("id" = String, Path, description = "The ID of the job schedule to update."),
+	),
+	responses(
+		(status = 200, description = "Successfully updated job schedule", body = JobSchedule),
+		(status = 400, description = "Invalid cron expression or targets."),
+		(status = 401, description = "No user is logged in (unauthorized)."),
+		(status = 403, description = "User does not have permission to access this resource."),
+		(status = 404, description = "Job schedule not found."),
+		(status = 500, description = "Internal server error."),
+	)
+)]
+/// Update a job schedule. The next run is recalculated from the current time, and the scheduler
+/// picks up the change immediately.
+async fn update_job_schedule(
+	State(ctx): State<AppState>,
+	Path(id): Path<String>,
+	Json(input): Json<CreateOrUpdateJobSchedule>,
+) -> APIResult<Json<JobSchedule>> {
+	input.validate()?;
+	let next_run_at = input.next_run_at()?;
+
+	let exists = ctx
+		.db
+		.job_schedule()
+		.find_unique(job_schedule::id::equals(id.clone()))
+		.exec()
+		.await?
+		.is_some();
+	if !exists {
+		return Err(APIError::NotFound(format!(
+			"Job schedule with id {id} not found"
+		)));
+	}
+
+	let schedule = ctx
+		.db
+		.job_schedule()
+		.update(
+			job_schedule::id::equals(id),
+			vec![
+				job_schedule::name::set(input.name.trim().to_string()),
+				job_schedule::cron_expression::set(input.cron_expression),
+				job_schedule::job_kind::set(input.job_kind.to_string()),
+				job_schedule::enabled::set(input.enabled),
+				job_schedule::next_run_at::set(next_run_at),
+				job_schedule::target_libraries::set(
+					input
+						.target_library_ids
+						.into_iter()
+						.map(library::id::equals)
+						.collect(),
+				),
+			],
+		)
+		.with(job_schedule::target_libraries::fetch(vec![]))
+		.exec()
+		.await?;
+	ctx.job_scheduler.reload();
+
+	Ok(Json(JobSchedule::try_from(schedule)?))
+}
+
+#[utoipa::path(
+	delete,
+	path = "/api/v1/jobs/schedules/:id",
+	tag = "job",
+	params(
+		("id" = String, Path, description = "The ID of the job schedule to delete."),
+	),
+	responses(
+		(status = 200, description = "Successfully deleted job schedule"),
+		(status = 401, description = "No user is logged in (unauthorized)."),
+		(status = 403, description = "User does not have permission to access this resource."),
+		(status = 404, description = "Job schedule not found."),
+		(status = 500, description = "Internal server error."),
+	)
+)]
+/// Delete a job schedule. Jobs which the schedule already enqueued are not affected.
+async fn delete_job_schedule(
+	State(ctx): State<AppState>,
+	Path(id): Path<String>,
+) -> APIResult<()> {
+	let deleted_count = ctx
+		.db
+		.job_schedule()
+		.delete_many(vec![job_schedule::id::equals(id.clone())])
+		.exec()
+		.await?;
+	if deleted_count == 0 {
+		return Err(APIError::NotFound(format!(
+			"Job schedule with id {id} not found"
+		)));
+	}
+	ctx.job_scheduler.reload();
+
+	Ok(())
 }
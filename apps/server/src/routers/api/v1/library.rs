@@ -1,9 +1,11 @@
 use axum::{
+	body::Bytes,
 	extract::{DefaultBodyLimit, Multipart, Path, Query, State},
 	middleware,
 	routing::{get, post, put},
 	Extension, Json, Router,
 };
+use axum_typed_multipart::{FieldData, TryFromMultipart, TypedMultipart};
 use chrono::Duration;
 use prisma_client_rust::{chrono::Utc, not, or, raw, Direction, PrismaValue};
 use serde::{Deserialize, Serialize};
@@ -11,7 +13,7 @@ use serde_qs::axum::QsQuery;
 use serde_with::skip_serializing_none;
 use specta::Type;
 use std::path;
-use tokio::fs;
+use tokio::{fs, task::spawn_blocking};
 use tracing::{debug, error, trace};
 use utoipa::ToSchema;
 
@@ -23,8 +25,9 @@ use stump_core::{
 				library_series_ids_media_ids_include, library_tags_select,
 				library_thumbnails_deletion_include, series_or_library_thumbnail,
 			},
-			FileStatus, Library, LibraryConfig, LibraryScanMode, LibraryStats, Media,
-			Series, TagName, User, UserPermission,
+			FileStatus, FilenameMetadata, FilenamePattern, Library, LibraryConfig,
+			LibraryScanMode, LibraryStats, Media, MergeStrategy, Series, TagName, User,
+			UserPermission,
 		},
 		query::pagination::{Pageable, Pagination, PaginationQuery},
 		PrismaCountTrait,
@@ -37,14 +40,20 @@ use stump_core::{
 			GenerateThumbnailOptions, ImageFormat, ImageProcessorOptions,
 			ThumbnailGenerationJob, ThumbnailGenerationJobParams,
 		},
-		scanner::{LibraryScanJob, ScanOptions},
+		scanner::{LibraryScanJob, ScanDryRunJob, ScanOptions},
+		storage::storage_for_path,
 		ContentType,
 	},
+	job::{Executor, JobPriority},
+	metadata::{
+		export_library_metadata, IsbnEnrichmentJob, MetadataImportJob,
+		MetadataTransferFormat,
+	},
 	prisma::{
-		last_library_visit, library, library_config,
+		last_library_visit, library, library_config, library_root,
 		media::{self, OrderByParam as MediaOrderByParam},
 		series::{self, OrderByParam as SeriesOrderByParam},
-		tag, user,
+		tag, user, PrismaClient,
 	},
 };
 
@@ -60,7 +69,10 @@ use crate::{
 		apply_media_pagination, apply_series_age_restriction, apply_series_filters,
 		library_not_hidden_from_user_filter,
 	},
-	utils::{http::ImageResponse, validate_and_load_image},
+	utils::{
+		http::{AttachmentResponse, ImageResponse},
+		validate_and_load_image,
+	},
 };
 
 use super::series::get_series_thumbnail;
@@ -70,6 +82,10 @@ pub(crate) fn mount(app_state: AppState) -> Router<AppState> {
 	Router::new()
 		.route("/libraries", get(get_libraries).post(create_library))
 		.route("/libraries/stats", get(get_libraries_stats))
+		.route(
+			"/libraries/filename-patterns/preview",
+			post(preview_filename_patterns),
+		)
 		.nest(
 			"/libraries/last-visited",
 			Router::new()
@@ -91,10 +107,19 @@ pub(crate) fn mount(app_state: AppState) -> Router<AppState> {
 					get(get_library_excluded_users).post(update_library_excluded_users),
 				)
 				.route("/scan", post(scan_library))
+				.route("/scan/dry-run", post(scan_library_dry_run))
 				.route("/clean", put(clean_library))
 				.route("/series", get(get_library_series))
 				.route("/media", get(get_library_media))
 				.route("/analyze", post(start_media_analysis))
+				.route("/metadata/isbn-enrichment", post(enrich_library_isbn))
+				.route("/metadata/export", get(export_library_metadata_handler))
+				.route(
+					"/metadata/import",
+					post(import_library_metadata).layer(DefaultBodyLimit::max(
+						app_state.config.max_file_upload_size,
+					)),
+				)
 				.nest(
 					"/thumbnail",
 					Router::new()
@@ -153,6 +178,7 @@ async fn get_libraries(
 		.find_many(where_conditions.clone())
 		.with(library::tags::fetch(vec![]))
 		.with(library::config::fetch())
+		.with(library::additional_roots::fetch(vec![]))
 		.order_by(order_by);
 
 	if !is_unpaged {
@@ -314,6 +340,91 @@ async fn get_libraries_stats(
 	Ok(Json(stats))
 }
 
+#[derive(Deserialize, Debug, Type, ToSchema)]
+pub struct PreviewFilenamePatterns {
+	/// The patterns to preview, in the order they would be applied
+	pub patterns: Vec<FilenamePattern>,
+	/// The sample paths to apply the patterns to. These are not required to exist
+	pub paths: Vec<String>,
+}
+
+#[skip_serializing_none]
+#[derive(Serialize, Debug, Type, ToSchema)]
+pub struct FilenamePatternResult {
+	pub pattern: FilenamePattern,
+	/// The metadata which was extracted, if the pattern matched the name
+	pub metadata: Option<FilenameMetadata>,
+	/// The reason the pattern could not be applied, e.g. it is invalid
+	pub error: Option<String>,
+}
+
+#[derive(Serialize, Debug, Type, ToSchema)]
+pub struct FilenamePatternPreview {
+	pub path: String,
+	/// The name the patterns were matched against, i.e. the file name without its extension
+	pub name: String,
+	pub results: Vec<FilenamePatternResult>,
+	/// The metadata which would be used during a scan, i.e. from the first matching pattern
+	pub matched: Option<FilenameMetadata>,
+}
+
+#[utoipa::path(
+	post,
+	path = "/api/v1/libraries/filename-patterns/preview",
+	tag = "library",
+	request_body = PreviewFilenamePatterns,
+	responses(
+		(status = 200, description = "Successfully previewed patterns", body = [FilenamePatternPreview]),
+		(status = 401, description = "Unauthorized"),
+		(status = 403, description = "Forbidden"),
+	)
+)]
+/// Preview what each of the given filename patterns would extract from a set of sample
+/// paths, without persisting anything
+async fn preview_filename_patterns(
+	Extension(req): Extension<RequestContext>,
+	Json(input): Json<PreviewFilenamePatterns>,
+) -> APIResult<Json<Vec<FilenamePatternPreview>>> {
+	req.enforce_permissions(&[UserPermission::EditLibrary])?;
+
+	let previews = input
+		.paths
+		.into_iter()
+		.map(|path| {
+			let name = path::Path::new(&path)
+				.file_stem()
+				.map(|stem| stem.to_string_lossy().to_string())
+				.unwrap_or_else(|| path.clone());
+			let results = input
+				.patterns
+				.iter()
+				.map(|pattern| match pattern.extract(&name) {
+					Ok(metadata) => FilenamePatternResult {
+						pattern: pattern.clone(),
+						metadata,
+						error: None,
+					},
+					Err(e) => FilenamePatternResult {
+						pattern: pattern.clone(),
+						metadata: None,
+						error: Some(e.to_string()),
+					},
+				})
+				.collect::<Vec<_>>();
+			let matched = results.iter().find_map(|result| result.metadata.clone());
+
+			FilenamePatternPreview {
+				path,
+				name,
+				results,
+				matched,
+			}
+		})
+		.collect();
+
+	Ok(Json(previews))
+}
+
 #[utoipa::path(
 	get,
 	path = "/api/v1/libraries/:id",
@@ -347,6 +458,7 @@ async fn get_library_by_id(
 		)
 		.with(library::config::fetch())
 		.with(library::tags::fetch(vec![]))
+		.with(library::additional_roots::fetch(vec![]))
 		.exec()
 		.await?
 		.ok_or(APIError::NotFound("Library not found".to_string()))?;
@@ -854,13 +966,16 @@ async fn generate_library_thumbnails(
 	let options = input.image_options.or(existing_options).unwrap_or_default();
 	let config =
 		ThumbnailGenerationJobParams::single_library(library.id, input.force_regenerate);
-	ctx.enqueue_job(ThumbnailGenerationJob::new(options, config))
-		.map_err(|e| {
-			error!(?e, "Failed to enqueue thumbnail generation job");
-			APIError::InternalServerError(
-				"Failed to enqueue thumbnail generation job".to_string(),
-			)
-		})?;
+	ctx.enqueue_job(
+		ThumbnailGenerationJob::new(options, config)
+			.with_priority(JobPriority::Interactive),
+	)
+	.map_err(|e| {
+		error!(?e, "Failed to enqueue thumbnail generation job");
+		APIError::InternalServerError(
+			"Failed to enqueue thumbnail generation job".to_string(),
+		)
+	})?;
 
 	Ok(Json(()))
 }
@@ -1025,17 +1140,289 @@ async fn scan_library(
 			"Library with id {id} not found"
 		)))?;
 
-	ctx.enqueue_job(LibraryScanJob::new(library.id, library.path, options))
-		.map_err(|e| {
-			error!(?e, "Failed to enqueue library scan job");
-			APIError::InternalServerError(
-				"Failed to enqueue library scan job".to_string(),
-			)
-		})?;
+	ctx.enqueue_job(
+		LibraryScanJob::new(library.id, library.path, options)
+			.with_priority(JobPriority::Interactive),
+	)
+	.map_err(|e| {
+		error!(?e, "Failed to enqueue library scan job");
+		APIError::InternalServerError("Failed to enqueue library scan job".to_string())
+	})?;
 
 	Ok(())
 }
 
+#[derive(Serialize, Type, ToSchema)]
+pub struct ScanDryRunResponse {
+	/// The ID of the queued job. The report is available in the job's output once it completes
+	job_id: String,
+}
+
+#[utoipa::path(
+	post,
+	path = "/api/v1/libraries/:id/scan/dry-run",
+	tag = "library",
+	responses(
+		(status = 200, description = "Successfully queued library scan dry-run", body = ScanDryRunResponse),
+		(status = 401, description = "Unauthorized"),
+		(status = 404, description = "Library not found"),
+		(status = 500, description = "Internal server error")
+	)
+)]
+/// Queue a job which reports what a scan of the library would change, without writing
+/// anything to the database
+async fn scan_library_dry_run(
+	Path(id): Path<String>,
+	State(ctx): State<AppState>,
+	Extension(req): Extension<RequestContext>,
+	Json(options): Json<Option<ScanOptions>>,
+) -> APIResult<Json<ScanDryRunResponse>> {
+	let user = req.user_and_enforce_permissions(&[UserPermission::ScanLibrary])?;
+
+	let library = ctx
+		.db
+		.library()
+		.find_first(vec![
+			library::id::equals(id.clone()),
+			library_not_hidden_from_user_filter(&user),
+		])
+		.exec()
+		.await?
+		.ok_or(APIError::NotFound(format!(
+			"Library with id {id} not found"
+		)))?;
+
+	let job = ScanDryRunJob::library(library.id, library.path, options)
+		.with_priority(JobPriority::Interactive);
+	let job_id = job.id().to_string();
+	ctx.enqueue_job(job).map_err(|e| {
+		error!(?e, "Failed to enqueue library scan dry-run job");
+		APIError::InternalServerError(
+			"Failed to enqueue library scan dry-run job".to_string(),
+		)
+	})?;
+
+	Ok(Json(ScanDryRunResponse { job_id }))
+}
+
+#[derive(Default, Deserialize, ToSchema, Type)]
+pub struct IsbnEnrichmentRequest {
+	/// The strategy used to reconcile the fetched metadata with the existing metadata.
+	/// Defaults to the merge strategy of the library
+	merge_strategy: Option<MergeStrategy>,
+}
+
+#[derive(Serialize, ToSchema, Type)]
+pub struct IsbnEnrichmentResponse {
+	/// The ID of the queued job. A summary is available in the job's output once it completes
+	job_id: String,
+}
+
+#[utoipa::path(
+	post,
+	path = "/api/v1/libraries/:id/metadata/isbn-enrichment",
+	tag = "library",
+	request_body = IsbnEnrichmentRequest,
+	responses(
+		(status = 200, description = "Successfully queued ISBN enrichment", body = IsbnEnrichmentResponse),
+		(status = 401, description = "Unauthorized"),
+		(status = 404, description = "Library not found"),
+		(status = 500, description = "Internal server error")
+	)
+)]
+/// Queue a job which fills the metadata of the EPUB and PDF books in the library from Google
+/// Books, using the ISBN of each book
+async fn enrich_library_isbn(
+	Path(id): Path<String>,
+	State(ctx): State<AppState>,
+	Extension(req): Extension<RequestContext>,
+	Json(payload): Json<Option<IsbnEnrichmentRequest>>,
+) -> APIResult<Json<IsbnEnrichmentResponse>> {
+	let user = req.user_and_enforce_permissions(&[UserPermission::ManageLibrary])?;
+
+	let library = ctx
+		.db
+		.library()
+		.find_first(vec![
+			library::id::equals(id.clone()),
+			library_not_hidden_from_user_filter(&user),
+		])
+		.exec()
+		.await?
+		.ok_or(APIError::NotFound(format!(
+			"Library with id {id} not found"
+		)))?;
+
+	let payload = payload.unwrap_or_default();
+	let job = IsbnEnrichmentJob::new(library.id, payload.merge_strategy)
+		.with_priority(JobPriority::Interactive);
+	let job_id = job.id().to_string();
+	ctx.enqueue_job(job).map_err(|e| {
+		error!(?e, "Failed to enqueue ISBN enrichment job");
+		APIError::InternalServerError("Failed to enqueue ISBN enrichment job".to_string())
+	})?;
+
+	Ok(Json(IsbnEnrichmentResponse { job_id }))
+}
+
+#[derive(Debug, Default, Deserialize, ToSchema, Type)]
+pub struct MetadataExportQuery {
+	/// The format of the exported file. Defaults to CSV
+	#[serde(default)]
+	format: MetadataTransferFormat,
+}
+
+#[utoipa::path(
+	get,
+	path = "/api/v1/libraries/:id/metadata/export",
+	tag = "library",
+	params(
+		("id" = String, Path, description = "The library ID"),
+		("format" = Option<MetadataTransferFormat>, Query, description = "The format of the exported file"),
+	),
+	responses(
+		(status = 200, description = "Successfully exported library metadata"),
+		(status = 401, description = "Unauthorized"),
+		(status = 404, description = "Library not found"),
+		(status = 500, description = "Internal server error")
+	)
+)]
+/// Export the metadata of every series and book in the library as a CSV or JSON file, which
+/// can be edited and imported back
+async fn export_library_metadata_handler(
+	Path(id): Path<String>,
+	Query(query): Query<MetadataExportQuery>,
+	State(ctx): State<AppState>,
+	Extension(req): Extension<RequestContext>,
+) -> APIResult<AttachmentResponse> {
+	let user = req.user_and_enforce_permissions(&[UserPermission::ManageLibrary])?;
+
+	let library = ctx
+		.db
+		.library()
+		.find_first(vec![
+			library::id::equals(id.clone()),
+			library_not_hidden_from_user_filter(&user),
+		])
+		.exec()
+		.await?
+		.ok_or(APIError::NotFound(format!(
+			"Library with id {id} not found"
+		)))?;
+
+	let records = export_library_metadata(&ctx.db, &library.id).await?;
+	let format = query.format;
+	let data = format.write(&records)?;
+
+	// The name ends up in a header, so anything which isn't safe there is replaced
+	let library_name = library
+		.name
+		.chars()
+		.map(|c| {
+			if c.is_ascii_alphanumeric() || c == '-' || c == '_' {
+				c
+			} else {
+				'_'
+			}
+		})
+		.collect::<String>();
+
+	Ok(AttachmentResponse {
+		file_name: format!("{library_name}-metadata.{}", format.extension()),
+		content_type: format.mime_type().to_string(),
+		data,
+	})
+}
+
+#[derive(TryFromMultipart)]
+struct MetadataImportRequest {
+	/// The exported file, edited or not. The format is determined by its extension
+	#[form_data(limit = "unlimited")]
+	file: FieldData<Bytes>,
+	/// Whether to only report the changes the import would make, without applying them
+	dry_run: Option<bool>,
+}
+
+#[derive(Serialize, ToSchema, Type)]
+pub struct MetadataImportResponse {
+	/// The ID of the queued job. The changes, or the would-be changes of a dry run, are
+	/// available in the job's output once it completes
+	job_id: String,
+}
+
+#[utoipa::path(
+	post,
+	path = "/api/v1/libraries/:id/metadata/import",
+	tag = "library",
+	request_body(content_type = "multipart/form-data", content = MetadataImportRequest),
+	params(
+		("id" = String, Path, description = "The library ID"),
+	),
+	responses(
+		(status = 200, description = "Successfully queued metadata import", body = MetadataImportResponse),
+		(status = 400, description = "Bad request"),
+		(status = 401, description = "Unauthorized"),
+		(status = 404, description = "Library not found"),
+		(status = 500, description = "Internal server error")
+	)
+)]
+/// Queue a job which applies a metadata file, in the format of the export, to the series and
+/// books of the library. The file is parsed up front, so malformed files are rejected before
+/// anything is queued
+async fn import_library_metadata(
+	Path(id): Path<String>,
+	State(ctx): State<AppState>,
+	Extension(req): Extension<RequestContext>,
+	TypedMultipart(import_request): TypedMultipart<MetadataImportRequest>,
+) -> APIResult<Json<MetadataImportResponse>> {
+	let user = req.user_and_enforce_permissions(&[UserPermission::ManageLibrary])?;
+
+	let library = ctx
+		.db
+		.library()
+		.find_first(vec![
+			library::id::equals(id.clone()),
+			library_not_hidden_from_user_filter(&user),
+		])
+		.exec()
+		.await?
+		.ok_or(APIError::NotFound(format!(
+			"Library with id {id} not found"
+		)))?;
+
+	let format = import_request
+		.file
+		.metadata
+		.file_name
+		.as_deref()
+		.and_then(|name| path::Path::new(name).extension())
+		.and_then(|extension| extension.to_str())
+		.and_then(MetadataTransferFormat::from_extension)
+		.ok_or(APIError::BadRequest(
+			"Metadata imports must be a .csv or .json file".to_string(),
+		))?;
+	let records = format.read(&import_request.file.contents)?;
+	if records.is_empty() {
+		return Err(APIError::BadRequest(
+			"The metadata file does not contain any records".to_string(),
+		));
+	}
+
+	let job = MetadataImportJob::new(
+		library.id,
+		records,
+		import_request.dry_run.unwrap_or(false),
+	)
+	.with_priority(JobPriority::Interactive);
+	let job_id = job.id().to_string();
+	ctx.enqueue_job(job).map_err(|e| {
+		error!(?e, "Failed to enqueue metadata import job");
+		APIError::InternalServerError("Failed to enqueue metadata import job".to_string())
+	})?;
+
+	Ok(Json(MetadataImportResponse { job_id }))
+}
+
 #[derive(Debug, Deserialize, Serialize, ToSchema, Type)]
 pub struct CleanLibraryResponse {
 	deleted_media_count: i32,
@@ -1198,6 +1585,87 @@ pub struct CreateLibrary {
 	/// Optional options to apply to the library. When not provided, the default options will be used.
 	#[specta(optional)]
 	pub config: Option<LibraryConfig>,
+	/// Optional root paths for the library in addition to `path`, e.g. for a collection which is
+	/// split across multiple disks.
+	#[serde(default)]
+	pub additional_roots: Vec<String>,
+}
+
+/// Whether the path is an existing directory in whichever storage backend holds it. Checking
+/// object storage is blocking, so it is done on a blocking thread
+async fn is_existing_directory(path: &str, config: &StumpConfig) -> bool {
+	let path = path::PathBuf::from(path);
+	let config = config.clone();
+	spawn_blocking(move || {
+		storage_for_path(&path, &config)
+			.and_then(|storage| storage.metadata(&path))
+			.is_ok_and(|entry| entry.is_dir)
+	})
+	.await
+	.unwrap_or(false)
+}
+
+/// Validates the additional roots of a library. Each root must be an existing directory which
+/// does not overlap with the primary path, the other roots, or the roots of another library.
+async fn validate_additional_roots(
+	db: &PrismaClient,
+	config: &StumpConfig,
+	library_id: Option<&str>,
+	path: &str,
+	additional_roots: &[String],
+) -> APIResult<()> {
+	let overlaps = |a: &str, b: &str| {
+		path::Path::new(a).starts_with(b) || path::Path::new(b).starts_with(a)
+	};
+
+	for (idx, root) in additional_roots.iter().enumerate() {
+		if !is_existing_directory(root, config).await {
+			return Err(APIError::BadRequest(format!(
+				"The library root directory does not exist: {root}"
+			)));
+		}
+
+		let overlaps_other_root = std::iter::once(path)
+			.chain(additional_roots.iter().map(String::as_str))
+			.enumerate()
+			.any(|(other_idx, other)| other_idx != idx + 1 && overlaps(root, other));
+		if overlaps_other_root {
+			return Err(APIError::BadRequest(format!(
+				"The library root {root} overlaps with another root of the library"
+			)));
+		}
+	}
+
+	if additional_roots.is_empty() {
+		return Ok(());
+	}
+
+	let other_library_filter =
+		chain_optional_iter([], [library_id.map(|id| library::id::not(id.to_string()))]);
+	let other_libraries = db
+		.library()
+		.find_many(other_library_filter)
+		.with(library::additional_roots::fetch(vec![]))
+		.exec()
+		.await?
+		.into_iter()
+		.map(Library::from)
+		.collect::<Vec<_>>();
+
+	let conflict = other_libraries.iter().find(|library| {
+		library
+			.root_paths()
+			.iter()
+			.any(|other| additional_roots.iter().any(|root| overlaps(root, other)))
+	});
+	if let Some(library) = conflict {
+		return Err(APIError::BadRequest(format!(
+			"A library root overlaps with the library {}",
+			library.name
+		)));
+	}
+
+	Ok(())
 }
 
 #[utoipa::path(
@@ -1223,7 +1691,7 @@ async fn create_library(
 	req.enforce_permissions(&[UserPermission::CreateLibrary])?;
 	let db = &ctx.db;
 
-	if !path::Path::new(&input.path).exists() {
+	if !is_existing_directory(&input.path, &ctx.config).await {
 		return Err(APIError::BadRequest(format!(
 			"The library directory does not exist: {}",
 			input.path
@@ -1242,9 +1710,27 @@ async fn create_library(
 		)));
 	}
 
+	validate_additional_roots(
+		db,
+		&ctx.config,
+		None,
+		&input.path,
+		&input.additional_roots,
+	)
+	.await?;
+
 	// TODO(prisma-nested-create): Refactor once nested create is supported
 	// https://github.com/Brendonovich/prisma-client-rust/issues/44
 	let library_config = input.config.unwrap_or_default();
+	library_config
+		.filename_patterns
+		.build()
+		.map_err(|e| APIError::BadRequest(e.to_string()))?;
+	library_config
+		.metadata_providers
+		.validate()
+		.map_err(|e| APIError::BadRequest(e.to_string()))?;
+
 	let transaction_result: Result<Library, APIError> = db
 		._transaction()
 		.with_timeout(Duration::seconds(30).num_milliseconds() as u64)
@@ -1252,6 +1738,10 @@ async fn create_library(
 			let ignore_rules = (!library_config.ignore_rules.is_empty())
 				.then(|| library_config.ignore_rules.as_bytes())
 				.transpose()?;
+			let filename_patterns = (!library_config.filename_patterns.is_empty())
+				.then(|| library_config.filename_patterns.as_bytes())
+				.transpose()?;
+			let metadata_providers = library_config.metadata_providers.as_bytes()?;
 			let thumbnail_config = library_config
 				.thumbnail_config
 				.map(|options| options.as_bytes())
@@ -1287,8 +1777,16 @@ async fn create_library(
 					library_config::library_pattern::set(
 						library_config.library_pattern.to_string(),
 					),
+					library_config::watch_mode::set(
+						library_config.watch_mode.to_string(),
+					),
+					library_config::merge_strategy::set(
+						library_config.merge_strategy.to_string(),
+					),
 					library_config::thumbnail_config::set(thumbnail_config),
 					library_config::ignore_rules::set(ignore_rules),
+					library_config::filename_patterns::set(filename_patterns),
+					library_config::metadata_providers::set(Some(metadata_providers)),
 				])
 				.exec()
 				.await?;
@@ -1357,6 +1855,20 @@ async fn create_library(
 				.exec()
 				.await?;
 
+			if !input.additional_roots.is_empty() {
+				client
+					.library_root()
+					.create_many(
+						input
+							.additional_roots
+							.iter()
+							.map(|root| (root.clone(), library.id.clone(), vec![]))
+							.collect(),
+					)
+					.exec()
+					.await?;
+			}
+
 			let library_config = client
 				.library_config()
 				.update(
@@ -1371,11 +1883,19 @@ async fn create_library(
 				.exec()
 				.await?;
 
-			Ok(Library::from((library, library_config)))
+			Ok(Library {
+				additional_roots: Some(input.additional_roots.clone()),
+				..Library::from((library, library_config))
+			})
 		})
 		.await;
 
 	let library = transaction_result?;
+
+	if let Err(error) = ctx.library_watcher.sync_library(&library.id).await {
+		error!(?error, "Failed to watch library");
+	}
+
 	let scan_mode = input.scan_mode.unwrap_or_default();
 	if scan_mode != LibraryScanMode::None {
 		ctx.enqueue_job(LibraryScanJob::new(
@@ -1414,6 +1934,10 @@ pub struct UpdateLibrary {
 	/// Optional flag to indicate how the library should be automatically scanned after update. Default is `BATCHED`.
 	#[serde(default)]
 	pub scan_mode: Option<LibraryScanMode>,
+	/// The updated additional root paths of the library. When not provided, the existing roots
+	/// are left as-is.
+	#[specta(optional)]
+	pub additional_roots: Option<Vec<String>>,
 }
 
 // TODO(prisma-nested-create): Refactor once nested create is supported
@@ -1444,7 +1968,7 @@ async fn update_library(
 	let user = req.user_and_enforce_permissions(&[UserPermission::EditLibrary])?;
 	let db = &ctx.db;
 
-	if !path::Path::new(&input.path).exists() {
+	if !is_existing_directory(&input.path, &ctx.config).await {
 		return Err(APIError::BadRequest(format!(
 			"Updated path does not exist: {}",
 			input.path
@@ -1463,6 +1987,27 @@ async fn update_library(
 		.ok_or(APIError::NotFound("Library not found".to_string()))?;
 	let existing_tags = existing_library.tags;
 
+	input
+		.config
+		.filename_patterns
+		.build()
+		.map_err(|e| APIError::BadRequest(e.to_string()))?;
+	input
+		.config
+		.metadata_providers
+		.validate()
+		.map_err(|e| APIError::BadRequest(e.to_string()))?;
+	if let Some(additional_roots) = &input.additional_roots {
+		validate_additional_roots(
+			db,
+			&ctx.config,
+			Some(&id),
+			&input.path,
+			additional_roots,
+		)
+		.await?;
+	}
+
 	let update_result: Result<Library, APIError> = db
 		._transaction()
 		.with_timeout(Duration::seconds(30).num_milliseconds() as u64)
@@ -1471,6 +2016,10 @@ async fn update_library(
 			let ignore_rules = (!library_config.ignore_rules.is_empty())
 				.then(|| library_config.ignore_rules.as_bytes())
 				.transpose()?;
+			let filename_patterns = (!library_config.filename_patterns.is_empty())
+				.then(|| library_config.filename_patterns.as_bytes())
+				.transpose()?;
+			let metadata_providers = library_config.metadata_providers.as_bytes()?;
 			let thumbnail_config = library_config
 				.thumbnail_config
 				.map(|options| options.as_bytes())
@@ -1506,7 +2055,15 @@ async fn update_library(
 							library_config.generate_koreader_hashes,
 						),
 						library_config::ignore_rules::set(ignore_rules),
+						library_config::filename_patterns::set(filename_patterns),
+						library_config::metadata_providers::set(Some(metadata_providers)),
 						library_config::thumbnail_config::set(thumbnail_config),
+						library_config::watch_mode::set(
+							library_config.watch_mode.to_string(),
+						),
+						library_config::merge_strategy::set(
+							library_config.merge_strategy.to_string(),
+						),
 					],
 				)
 				.exec()
@@ -1571,6 +2128,24 @@ async fn update_library(
 				_ => (vec![], vec![]),
 			};
 
+			if let Some(additional_roots) = input.additional_roots {
+				client
+					.library_root()
+					.delete_many(vec![library_root::library_id::equals(id.clone())])
+					.exec()
+					.await?;
+				client
+					.library_root()
+					.create_many(
+						additional_roots
+							.into_iter()
+							.map(|root| (root, id.clone(), vec![]))
+							.collect(),
+					)
+					.exec()
+					.await?;
+			}
+
 			let set_params = chain_optional_iter(
 				[
 					library::name::set(input.name),
@@ -1602,6 +2177,7 @@ async fn update_library(
 				.library()
 				.update(library::id::equals(id), set_params)
 				.with(library::tags::fetch(vec![]))
+				.with(library::additional_roots::fetch(vec![]))
 				.exec()
 				.await
 				.map(Library::from)?)
@@ -1609,6 +2185,10 @@ async fn update_library(
 		.await;
 	let updated_library = update_result?;
 
+	if let Err(error) = ctx.library_watcher.sync_library(&updated_library.id).await {
+		error!(?error, "Failed to update library watcher");
+	}
+
 	let scan_mode = input.scan_mode.unwrap_or_default();
 
 	if scan_mode != LibraryScanMode::None {
@@ -1675,6 +2255,7 @@ async fn delete_library(
 		.include(library_series_ids_media_ids_include::include())
 		.exec()
 		.await?;
+	ctx.library_watcher.unwatch(&deleted_library.id).await;
 
 	let media_ids = deleted_library
 		.series
@@ -1788,12 +2369,14 @@ async fn start_media_analysis(
 	req.enforce_permissions(&[UserPermission::ManageLibrary])?;
 
 	// Start analysis job
-	ctx.enqueue_job(AnalyzeMediaJob::analyze_library(id))
-		.map_err(|e| {
-			let err = "Failed to enqueue analyze library media job";
-			error!(?e, err);
-			APIError::InternalServerError(err.to_string())
-		})?;
+	ctx.enqueue_job(
+		AnalyzeMediaJob::analyze_library(id).with_priority(JobPriority::Interactive),
+	)
+	.map_err(|e| {
+		let err = "Failed to enqueue analyze library media job";
+		error!(?e, err);
+		APIError::InternalServerError(err.to_string())
+	})?;
 
 	APIResult::Ok(())
 }
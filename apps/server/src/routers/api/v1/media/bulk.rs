@@ -1,23 +1,31 @@
+use std::collections::HashMap;
+
 use axum::{extract::State, Extension, Json};
 use axum_extra::extract::Query;
 use prisma_client_rust::{raw, Direction, PrismaValue};
+use serde::{Deserialize, Serialize};
 use serde_qs::axum::QsQuery;
+use specta::Type;
 use stump_core::{
 	db::{
-		entity::Media,
+		entity::{Media, UserPermission},
 		query::pagination::{PageQuery, Pageable, Pagination, PaginationQuery},
 		CountQueryReturn,
 	},
+	filesystem::image::{cluster_by_similarity, PerceptualHash},
 	prisma::{
 		active_reading_session, finished_reading_session,
 		media::{self, OrderByParam as MediaOrderByParam, WhereParam},
+		series,
 	},
 };
+use tokio::task::spawn_blocking;
+use utoipa::ToSchema;
 
 use crate::{
 	config::state::AppState,
 	errors::{APIError, APIResult},
-	filter::{FilterableQuery, MediaFilter},
+	filter::{chain_optional_iter, FilterableQuery, MediaFilter},
 	middleware::auth::RequestContext,
 	routers::api::filters::{
 		apply_media_age_restriction, apply_media_filters_for_user,
@@ -197,6 +205,171 @@ pub(crate) async fn get_duplicate_media(
 	Ok(Json(result?))
 }
 
+/// The default minimum similarity for two media to be considered likely duplicates
+const DEFAULT_SIMILARITY_THRESHOLD: f64 = 0.9;
+
+#[derive(Debug, Deserialize, Type, ToSchema)]
+pub struct SimilarMediaQuery {
+	/// The minimum similarity, between 0 and 1, for two media to be grouped together.
+	/// Defaults to 0.9
+	#[specta(optional)]
+	pub threshold: Option<f64>,
+	/// Restrict the search to a single library
+	#[specta(optional)]
+	pub library_id: Option<String>,
+}
+
+#[derive(Debug, Serialize, Type, ToSchema)]
+pub struct SimilarMedia {
+	pub media: Media,
+	/// The similarity, between 0 and 1, of the media to the first media in its cluster
+	pub similarity: f64,
+}
+
+#[derive(Debug, Serialize, Type, ToSchema)]
+pub struct SimilarMediaCluster {
+	/// The media in the cluster, ordered by size descending. The largest file is typically
+	/// the highest quality, so it is used as the point of comparison for the others.
+	pub media: Vec<SimilarMedia>,
+}
+
+#[utoipa::path(
+	get,
+	path = "/api/v1/media/duplicates/similar",
+	tag = "media",
+	params(
+		("params" = Option<SimilarMediaQuery>, Query, description = "The similarity options"),
+		("pagination" = Option<PageQuery>, Query, description = "The pagination options"),
+	),
+	responses(
+		(status = 200, description = "Successfully fetched similar media", body = [SimilarMediaCluster]),
+		(status = 400, description = "Bad request"),
+		(status = 401, description = "Unauthorized"),
+		(status = 403, description = "Forbidden"),
+		(status = 500, description = "Internal server error"),
+	)
+)]
+/// Get clusters of media which are likely duplicates, based on the perceptual hashes computed
+/// during media analysis. Unlike [get_duplicate_media], this finds the same book in different
+/// qualities or formats. Media which have not been analyzed are not included. The clusters
+/// are paginated, largest first.
+pub(crate) async fn get_similar_media(
+	Query(params): Query<SimilarMediaQuery>,
+	pagination: Query<PageQuery>,
+	State(ctx): State<AppState>,
+	Extension(req): Extension<RequestContext>,
+) -> APIResult<Json<Pageable<Vec<SimilarMediaCluster>>>> {
+	let user = req.user_and_enforce_permissions(&[UserPermission::ManageLibrary])?;
+
+	let threshold = params.threshold.unwrap_or(DEFAULT_SIMILARITY_THRESHOLD);
+	if !(0.0..=1.0).contains(&threshold) {
+		return Err(APIError::BadRequest(
+			"The threshold must be between 0 and 1".to_string(),
+		));
+	}
+
+	let where_conditions = chain_optional_iter(
+		[media::perceptual_hash::not(None)],
+		[params
+			.library_id
+			.map(|id| media::series::is(vec![series::library_id::equals(Some(id))]))],
+	)
+	.into_iter()
+	.chain(apply_media_library_not_hidden_for_user_filter(&user))
+	.collect::<Vec<WhereParam>>();
+
+	// Only what is needed to cluster the media is loaded here. The full media are only
+	// loaded for the clusters on the requested page
+	let (candidates, hashes): (Vec<(String, i64)>, Vec<PerceptualHash>) = ctx
+		.db
+		.media()
+		.find_many(where_conditions)
+		.select(media::select!({ id size perceptual_hash }))
+		.exec()
+		.await?
+		.into_iter()
+		.filter_map(|data| {
+			let hash = data.perceptual_hash.as_deref()?.parse().map_or_else(
+				|error| {
+					tracing::error!(
+						?error,
+						id = data.id,
+						"Failed to parse perceptual hash"
+					);
+					None
+				},
+				Some,
+			)?;
+			Some(((data.id, data.size), hash))
+		})
+		.unzip();
+
+	let clusters = spawn_blocking(move || {
+		let mut clusters = cluster_by_similarity(&hashes, threshold)
+			.into_iter()
+			.map(|mut members| {
+				members.sort_by_key(|index| std::cmp::Reverse(candidates[*index].1));
+				let reference = &hashes[members[0]];
+				members
+					.into_iter()
+					.map(|index| {
+						(
+							candidates[index].0.clone(),
+							reference.similarity(&hashes[index]),
+						)
+					})
+					.collect::<Vec<_>>()
+			})
+			.collect::<Vec<_>>();
+		clusters.sort_by_key(|cluster| std::cmp::Reverse(cluster.len()));
+		clusters
+	})
+	.await
+	.map_err(|e| APIError::InternalServerError(e.to_string()))?;
+
+	let page_params = pagination.0.page_params();
+	let page_bounds = page_params.get_page_bounds();
+	let total = clusters.len() as i64;
+	let clusters = clusters
+		.into_iter()
+		.skip(page_bounds.skip as usize)
+		.take(page_bounds.take as usize)
+		.collect::<Vec<_>>();
+
+	let media_ids = clusters
+		.iter()
+		.flatten()
+		.map(|(id, _)| id.clone())
+		.collect();
+	let mut media = ctx
+		.db
+		.media()
+		.find_many(vec![media::id::in_vec(media_ids)])
+		.with(media::metadata::fetch())
+		.exec()
+		.await?
+		.into_iter()
+		.map(|data| (data.id.clone(), Media::from(data)))
+		.collect::<HashMap<_, _>>();
+
+	// A media which was deleted since it was clustered is left out of its cluster
+	let clusters = clusters
+		.into_iter()
+		.map(|members| SimilarMediaCluster {
+			media: members
+				.into_iter()
+				.filter_map(|(id, similarity)| {
+					media
+						.remove(&id)
+						.map(|media| SimilarMedia { media, similarity })
+				})
+				.collect(),
+		})
+		.collect();
+
+	Ok(Json(Pageable::with_count(clusters, total, &page_params)))
+}
+
 #[utoipa::path(
 	get,
 	path = "/api/v1/media/in-progress",
@@ -15,13 +15,24 @@ use stump_core::{
 			reading_session_with_book_pages,
 		},
 		ActiveReadingSession, FinishedReadingSession, Media, MediaMetadata,
-		PageDimension, PageDimensionsEntity, ProgressUpdateReturn, User, UserPermission,
+		MediaMetadataConflict, MergeStrategy, MetadataProviderKind, PageDimension,
+		PageDimensionsEntity, ProgressUpdateReturn, StoryArcMember, User, UserPermission,
+	},
+	filesystem::{
+		analyze_media_job::AnalyzeMediaJob,
+		get_page_async,
+		image::{place_thumbnail, remove_thumbnails},
+		ContentType,
+	},
+	job::JobPriority,
+	metadata::{
+		apply_identified_metadata, fetch_identified_cover, identify_media,
+		sync_media_credits, MetadataCandidate,
 	},
-	filesystem::{analyze_media_job::AnalyzeMediaJob, get_page_async},
 	prisma::{
 		active_reading_session, finished_reading_session, library,
 		media::{self, WhereParam},
-		media_metadata, series, user,
+		media_metadata, media_metadata_conflict, series, story_arc_member, user,
 	},
 	Ctx,
 };
@@ -223,7 +234,7 @@ pub(crate) async fn get_media_file(
 
 	tracing::trace!(?media, "Downloading media file");
 
-	Ok(NamedFile::open(media.path.clone()).await?)
+	Ok(NamedFile::open_from_storage(media.path.clone(), &ctx.config).await?)
 }
 
 #[utoipa::path(
@@ -686,12 +697,14 @@ pub(crate) async fn start_media_analysis(
 	req.enforce_permissions(&[UserPermission::ManageLibrary])?;
 
 	// Start analysis job
-	ctx.enqueue_job(AnalyzeMediaJob::analyze_media_item(id))
-		.map_err(|e| {
-			let err = "Failed to enqueue analyze media job";
-			error!(?e, err);
-			APIError::InternalServerError(err.to_string())
-		})?;
+	ctx.enqueue_job(
+		AnalyzeMediaJob::analyze_media_item(id).with_priority(JobPriority::Interactive),
+	)
+	.map_err(|e| {
+		let err = "Failed to enqueue analyze media job";
+		error!(?e, err);
+		APIError::InternalServerError(err.to_string())
+	})?;
 
 	APIResult::Ok(())
 }
@@ -869,54 +882,472 @@ pub(crate) async fn get_media_metadata(
 	)
 )]
 /// Update the metadata for a media record. This is a full update, so any existing metadata
-/// will be replaced with the new metadata.
+/// will be replaced with the new metadata. Every field which is changed by the update is
+/// locked, so that it isn't overwritten when the media is rescanned or identified.
 pub(crate) async fn put_media_metadata(
 	Path(id): Path<String>,
 	State(ctx): State<AppState>,
 	Extension(req): Extension<RequestContext>,
 	Json(metadata): Json<MediaMetadata>,
 ) -> APIResult<Json<MediaMetadata>> {
-	req.enforce_permissions(&[UserPermission::ManageLibrary])?;
+	let user = req.user_and_enforce_permissions(&[UserPermission::ManageLibrary])?;
+	let media_id = find_accessible_media_id(&ctx, &user, &id).await?;
 
 	let db = &ctx.db;
-	let user = req.user();
+	let existing_metadata = db
+		.media_metadata()
+		.find_unique(media_metadata::media_id::equals(media_id.clone()))
+		.exec()
+		.await?
+		.map(MediaMetadata::from);
+
+	let metadata = MediaMetadata::apply_manual_edit(existing_metadata, metadata);
+	let locked_fields_param = metadata.locked_fields_param();
+	let set_params = metadata
+		.into_prisma()
+		.into_iter()
+		.chain(std::iter::once(locked_fields_param))
+		.collect::<Vec<_>>();
+
+	let meta = db
+		.media_metadata()
+		.upsert(
+			media_metadata::media_id::equals(media_id.clone()),
+			set_params
+				.clone()
+				.into_iter()
+				.chain(vec![media_metadata::media::connect(media::id::equals(
+					media_id.clone(),
+				))])
+				.collect::<Vec<_>>(),
+			set_params,
+		)
+		.exec()
+		.await?;
+	let metadata = MediaMetadata::from(meta);
+	sync_media_credits(db, &media_id, &metadata).await?;
+
+	Ok(Json(metadata))
+}
+
+/// The fields of a metadata record which are locked, i.e. which are kept as is when the
+/// metadata is refreshed from a file or fetched from a metadata provider
+#[derive(Debug, Deserialize, ToSchema, Type)]
+pub(crate) struct MetadataLocks {
+	pub locked_fields: Vec<String>,
+}
+
+#[utoipa::path(
+	put,
+	path = "/api/v1/media/:id/metadata/locks",
+	tag = "media",
+	params(
+		("id" = String, Path, description = "The ID of the media to lock metadata fields for")
+	),
+	request_body = MetadataLocks,
+	responses(
+		(status = 200, description = "Successfully updated locked fields", body = MediaMetadata),
+		(status = 400, description = "A field cannot be locked"),
+		(status = 401, description = "Unauthorized"),
+		(status = 403, description = "Forbidden"),
+		(status = 404, description = "Media metadata not available"),
+		(status = 500, description = "Internal server error"),
+	)
+)]
+/// Replace the locked fields of the metadata for a media record. An empty list unlocks every
+/// field, so that they are updated by the next scan
+pub(crate) async fn put_media_metadata_locks(
+	Path(id): Path<String>,
+	State(ctx): State<AppState>,
+	Extension(req): Extension<RequestContext>,
+	Json(input): Json<MetadataLocks>,
+) -> APIResult<Json<MediaMetadata>> {
+	let user = req.user_and_enforce_permissions(&[UserPermission::ManageLibrary])?;
+	let media_id = find_accessible_media_id(&ctx, &user, &id).await?;
+
+	let db = &ctx.db;
+	let mut metadata = db
+		.media_metadata()
+		.find_unique(media_metadata::media_id::equals(media_id.clone()))
+		.exec()
+		.await?
+		.map(MediaMetadata::from)
+		.ok_or(APIError::NotFound(String::from(
+			"Media metadata not available",
+		)))?;
+	metadata.set_locked_fields(input.locked_fields)?;
+
+	let updated = db
+		.media_metadata()
+		.update(
+			media_metadata::media_id::equals(media_id),
+			vec![metadata.locked_fields_param()],
+		)
+		.exec()
+		.await?;
+
+	Ok(Json(MediaMetadata::from(updated)))
+}
+
+#[utoipa::path(
+	get,
+	path = "/api/v1/media/:id/metadata/conflicts",
+	tag = "media",
+	params(
+		("id" = String, Path, description = "The ID of the media to get metadata conflicts for")
+	),
+	responses(
+		(status = 200, description = "Successfully fetched metadata conflicts", body = [MediaMetadataConflict]),
+		(status = 401, description = "Unauthorized"),
+		(status = 403, description = "Forbidden"),
+		(status = 404, description = "Media not found"),
+		(status = 500, description = "Internal server error"),
+	)
+)]
+/// Get the metadata conflicts which are pending review for a media record
+pub(crate) async fn get_media_metadata_conflicts(
+	Path(id): Path<String>,
+	State(ctx): State<AppState>,
+	Extension(req): Extension<RequestContext>,
+) -> APIResult<Json<Vec<MediaMetadataConflict>>> {
+	let user = req.user_and_enforce_permissions(&[UserPermission::ManageLibrary])?;
+
+	let book = ctx
+		.db
+		.media()
+		.find_first(
+			[media::id::equals(id.clone())]
+				.into_iter()
+				.chain(apply_media_library_not_hidden_for_user_filter(&user))
+				.collect(),
+		)
+		.select(media_id_select::select())
+		.exec()
+		.await?
+		.ok_or(APIError::NotFound(String::from("Media not found")))?;
+
+	let conflicts = ctx
+		.db
+		.media_metadata_conflict()
+		.find_many(vec![media_metadata_conflict::media_id::equals(book.id)])
+		.order_by(media_metadata_conflict::field::order(Direction::Asc))
+		.exec()
+		.await?;
+
+	Ok(Json(
+		conflicts
+			.into_iter()
+			.map(MediaMetadataConflict::from)
+			.collect(),
+	))
+}
+
+/// The possible resolutions for a metadata conflict
+#[derive(Debug, Deserialize, ToSchema, Type)]
+pub(crate) enum MetadataConflictResolution {
+	/// Apply the value which was read from the file
+	#[serde(rename = "ACCEPT")]
+	Accept,
+	/// Keep the currently stored value
+	#[serde(rename = "REJECT")]
+	Reject,
+}
+
+#[derive(Debug, Deserialize, ToSchema, Type)]
+pub(crate) struct ResolveMetadataConflict {
+	resolution: MetadataConflictResolution,
+}
+
+#[utoipa::path(
+	put,
+	path = "/api/v1/media/:id/metadata/conflicts/:conflict_id",
+	tag = "media",
+	params(
+		("id" = String, Path, description = "The ID of the media the conflict belongs to"),
+		("conflict_id" = String, Path, description = "The ID of the conflict to resolve")
+	),
+	request_body = ResolveMetadataConflict,
+	responses(
+		(status = 200, description = "Successfully resolved metadata conflict", body = MediaMetadata),
+		(status = 400, description = "Bad request"),
+		(status = 401, description = "Unauthorized"),
+		(status = 403, description = "Forbidden"),
+		(status = 404, description = "Conflict not found"),
+		(status = 500, description = "Internal server error"),
+	)
+)]
+/// Resolve a metadata conflict for a media record, either applying the value read from the
+/// file or keeping the stored value. The conflict is removed in either case.
+pub(crate) async fn resolve_media_metadata_conflict(
+	Path((id, conflict_id)): Path<(String, String)>,
+	State(ctx): State<AppState>,
+	Extension(req): Extension<RequestContext>,
+	Json(input): Json<ResolveMetadataConflict>,
+) -> APIResult<Json<Option<MediaMetadata>>> {
+	let user = req.user_and_enforce_permissions(&[UserPermission::ManageLibrary])?;
+	let db = &ctx.db;
+
+	let conflict = db
+		.media_metadata_conflict()
+		.find_first(vec![
+			media_metadata_conflict::id::equals(conflict_id.clone()),
+			media_metadata_conflict::media::is(
+				[media::id::equals(id.clone())]
+					.into_iter()
+					.chain(apply_media_library_not_hidden_for_user_filter(&user))
+					.collect(),
+			),
+		])
+		.exec()
+		.await?
+		.map(MediaMetadataConflict::from)
+		.ok_or(APIError::NotFound(String::from("Conflict not found")))?;
+
+	let existing_metadata = db
+		.media_metadata()
+		.find_unique(media_metadata::media_id::equals(id.clone()))
+		.exec()
+		.await?
+		.map(MediaMetadata::from);
+
+	let metadata = match input.resolution {
+		MetadataConflictResolution::Accept => {
+			let mut metadata = existing_metadata.unwrap_or_default();
+			metadata
+				.set_field_value(
+					&conflict.field,
+					conflict.incoming_value.unwrap_or_default(),
+				)
+				.map_err(|e| APIError::BadRequest(e.to_string()))?;
+
+			let set_params = metadata.into_prisma();
+			let (updated_metadata, _) = db
+				._batch((
+					db.media_metadata().upsert(
+						media_metadata::media_id::equals(id.clone()),
+						set_params
+							.clone()
+							.into_iter()
+							.chain(vec![media_metadata::media::connect(
+								media::id::equals(id.clone()),
+							)])
+							.collect::<Vec<_>>(),
+						set_params,
+					),
+					db.media_metadata_conflict()
+						.delete(media_metadata_conflict::id::equals(conflict.id)),
+				))
+				.await?;
+			let updated_metadata = MediaMetadata::from(updated_metadata);
+			sync_media_credits(db, &id, &updated_metadata).await?;
+
+			Some(updated_metadata)
+		},
+		MetadataConflictResolution::Reject => {
+			db.media_metadata_conflict()
+				.delete(media_metadata_conflict::id::equals(conflict.id))
+				.exec()
+				.await?;
+			existing_metadata
+		},
+	};
+
+	Ok(Json(metadata))
+}
+
+/// Find a media which the user may access, erroring if it does not exist or is hidden from
+/// the user
+async fn find_accessible_media_id(
+	ctx: &AppState,
+	user: &User,
+	id: &str,
+) -> APIResult<String> {
 	let age_restrictions = user
 		.age_restriction
 		.as_ref()
 		.map(|ar| apply_media_age_restriction(ar.age, ar.restrict_on_unset));
 	let where_params = chain_optional_iter(
-		[media::id::equals(id.clone())]
+		[media::id::equals(id.to_string())]
 			.into_iter()
 			.chain(apply_media_library_not_hidden_for_user_filter(user))
 			.collect::<Vec<WhereParam>>(),
 		[age_restrictions],
 	);
 
-	let book = db
+	let book = ctx
+		.db
 		.media()
-		.find_first(where_params.clone())
+		.find_first(where_params)
 		.select(media_id_select::select())
 		.exec()
 		.await?
 		.ok_or(APIError::NotFound(String::from("Media not found")))?;
 
-	let set_params = metadata.into_prisma();
+	Ok(book.id)
+}
 
-	let meta = db
-		.media_metadata()
-		.upsert(
-			media_metadata::media_id::equals(id.clone()),
-			set_params
-				.clone()
-				.into_iter()
-				.chain(vec![media_metadata::media::connect(media::id::equals(
-					book.id.clone(),
-				))])
-				.collect::<Vec<_>>(),
-			set_params.clone(),
-		)
-		.exec()
-		.await?;
+#[utoipa::path(
+	post,
+	path = "/api/v1/media/:id/metadata/identify",
+	tag = "media",
+	params(
+		("id" = String, Path, description = "The ID of the media to identify")
+	),
+	responses(
+		(status = 200, description = "Successfully identified media", body = [MetadataCandidate]),
+		(status = 401, description = "Unauthorized"),
+		(status = 403, description = "Forbidden"),
+		(status = 404, description = "Media not found"),
+		(status = 500, description = "Internal server error"),
+	)
+)]
+/// Search the metadata providers of the media's library for books which could be the media,
+/// ranked by how closely they match. Nothing is changed until a candidate is applied
+pub(crate) async fn identify_media_metadata(
+	Path(id): Path<String>,
+	State(ctx): State<AppState>,
+	Extension(req): Extension<RequestContext>,
+) -> APIResult<Json<Vec<MetadataCandidate>>> {
+	let user = req.user_and_enforce_permissions(&[UserPermission::ManageLibrary])?;
+	let media_id = find_accessible_media_id(&ctx, &user, &id).await?;
+
+	Ok(Json(identify_media(&ctx.db, &media_id).await?))
+}
+
+#[derive(Debug, Deserialize, ToSchema, Type)]
+pub(crate) struct ApplyMetadataCandidate {
+	/// The provider which suggested the candidate
+	provider: MetadataProviderKind,
+	/// The ID of the candidate with the provider
+	id: String,
+	/// The strategy used to reconcile the fetched metadata with the existing metadata.
+	/// Defaults to the merge strategy of the library
+	#[serde(default)]
+	#[specta(optional)]
+	merge_strategy: Option<MergeStrategy>,
+	/// Whether to replace the thumbnail of the media with the cover of the candidate
+	#[serde(default)]
+	#[specta(optional)]
+	use_cover: Option<bool>,
+}
+
+#[utoipa::path(
+	post,
+	path = "/api/v1/media/:id/metadata/identify/apply",
+	tag = "media",
+	params(
+		("id" = String, Path, description = "The ID of the media to apply the candidate to")
+	),
+	request_body = ApplyMetadataCandidate,
+	responses(
+		(status = 200, description = "Successfully applied metadata candidate", body = MediaMetadata),
+		(status = 400, description = "Bad request"),
+		(status = 401, description = "Unauthorized"),
+		(status = 403, description = "Forbidden"),
+		(status = 404, description = "Media or candidate not found"),
+		(status = 500, description = "Internal server error"),
+	)
+)]
+/// Apply a candidate returned by the identify endpoint to a media, optionally using its
+/// cover as the thumbnail of the media
+pub(crate) async fn apply_media_metadata_candidate(
+	Path(id): Path<String>,
+	State(ctx): State<AppState>,
+	Extension(req): Extension<RequestContext>,
+	Json(input): Json<ApplyMetadataCandidate>,
+) -> APIResult<Json<MediaMetadata>> {
+	let user = req.user_and_enforce_permissions(&[UserPermission::ManageLibrary])?;
+	let media_id = find_accessible_media_id(&ctx, &user, &id).await?;
+
+	let metadata = apply_identified_metadata(
+		&ctx.db,
+		&media_id,
+		input.provider,
+		&input.id,
+		input.merge_strategy,
+	)
+	.await?;
+
+	if input.use_cover.unwrap_or(false) {
+		let cover =
+			fetch_identified_cover(&ctx.db, &media_id, input.provider, &input.id).await?;
+		match cover {
+			Some(bytes) => {
+				let content_type = ContentType::from_bytes(&bytes);
+				if !content_type.is_image() {
+					return Err(APIError::InternalServerError(
+						"The provider returned a cover which is not an image".to_string(),
+					));
+				}
+
+				if let Err(e) = remove_thumbnails(
+					&[media_id.clone()],
+					&ctx.config.get_thumbnails_dir(),
+				) {
+					error!(
+						?e,
+						"Failed to remove existing media thumbnail before replacing!"
+					);
+				}
+				place_thumbnail(&media_id, content_type.extension(), &bytes, &ctx.config)
+					.await?;
+			},
+			None => tracing::debug!(%media_id, "The candidate has no cover to apply"),
+		}
+	}
+
+	Ok(Json(metadata))
+}
+
+#[utoipa::path(
+	get,
+	path = "/api/v1/media/:id/story-arcs",
+	tag = "media",
+	params(
+		("id" = String, Path, description = "The ID of the media to get story arcs for")
+	),
+	responses(
+		(status = 200, description = "Successfully fetched media story arcs", body = [StoryArcMember]),
+		(status = 401, description = "Unauthorized"),
+		(status = 403, description = "Forbidden"),
+		(status = 500, description = "Internal server error"),
+	)
+)]
+/// Get the story arcs a media is part of, with its position in each, ordered by the name
+/// of the story arc
+pub(crate) async fn get_media_story_arcs(
+	Path(id): Path<String>,
+	State(ctx): State<AppState>,
+	Extension(req): Extension<RequestContext>,
+) -> APIResult<Json<Vec<StoryArcMember>>> {
+	let db = &ctx.db;
+	let user = req.user();
+	let age_restrictions = user
+		.age_restriction
+		.as_ref()
+		.map(|ar| apply_media_age_restriction(ar.age, ar.restrict_on_unset));
+	let where_params = chain_optional_iter(
+		[media::id::equals(id.clone())]
+			.into_iter()
+			.chain(apply_media_library_not_hidden_for_user_filter(user))
+			.collect::<Vec<WhereParam>>(),
+		[age_restrictions],
+	);
 
-	Ok(Json(MediaMetadata::from(meta)))
+	let mut members = db
+		.story_arc_member()
+		.find_many(vec![story_arc_member::media::is(where_params)])
+		.with(story_arc_member::story_arc::fetch())
+		.exec()
+		.await?
+		.into_iter()
+		.map(StoryArcMember::from)
+		.collect::<Vec<_>>();
+	members.sort_by(|a, b| {
+		let a_name = a.story_arc.as_ref().map(|arc| arc.name.as_str());
+		let b_name = b.story_arc.as_ref().map(|arc| arc.name.as_str());
+		a_name.cmp(&b_name)
+	});
+
+	Ok(Json(members))
 }
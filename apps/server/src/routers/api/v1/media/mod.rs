@@ -15,6 +15,7 @@ pub(crate) fn mount(app_state: AppState) -> Router<AppState> {
 	Router::new()
 		.route("/media", get(bulk::get_media))
 		.route("/media/duplicates", get(bulk::get_duplicate_media))
+		.route("/media/duplicates/similar", get(bulk::get_similar_media))
 		.route("/media/keep-reading", get(bulk::get_in_progress_media))
 		.route("/media/recently-added", get(bulk::get_recently_added_media))
 		.route("/media/path/:path", get(individual::get_media_by_path))
@@ -55,7 +56,25 @@ pub(crate) fn mount(app_state: AppState) -> Router<AppState> {
 					"/metadata",
 					get(individual::get_media_metadata)
 						.put(individual::put_media_metadata),
-				),
+				)
+				.route("/metadata/locks", put(individual::put_media_metadata_locks))
+				.route(
+					"/metadata/identify",
+					post(individual::identify_media_metadata),
+				)
+				.route(
+					"/metadata/identify/apply",
+					post(individual::apply_media_metadata_candidate),
+				)
+				.route(
+					"/metadata/conflicts",
+					get(individual::get_media_metadata_conflicts),
+				)
+				.route(
+					"/metadata/conflicts/:conflict_id",
+					put(individual::resolve_media_metadata_conflict),
+				)
+				.route("/story-arcs", get(individual::get_media_story_arcs)),
 		)
 		.layer(middleware::from_fn_with_state(app_state, auth_middleware))
 }
@@ -1,28 +1,37 @@
-use std::collections::BTreeSet;
+use std::{collections::BTreeSet, str::FromStr};
 
-use axum::{extract::State, middleware, routing::get, Json, Router};
+use axum::{
+	extract::{Path, State},
+	middleware,
+	routing::get,
+	Extension, Json, Router,
+};
 use prisma_client_rust::Direction;
 use serde::{Deserialize, Serialize};
 use serde_qs::axum::QsQuery;
 use specta::Type;
 use stump_core::{
-	db::entity::macros::{
-		metadata_available_characters_select, metadata_available_colorists_select,
-		metadata_available_editors_select, metadata_available_genre_select,
-		metadata_available_inkers_select, metadata_available_letterers_select,
-		metadata_available_pencillers_select, metadata_available_publisher_select,
-		metadata_available_teams_select, metadata_available_writers_select,
+	db::entity::{
+		macros::{
+			metadata_available_characters_select, metadata_available_colorists_select,
+			metadata_available_editors_select, metadata_available_genre_select,
+			metadata_available_inkers_select, metadata_available_letterers_select,
+			metadata_available_pencillers_select, metadata_available_publisher_select,
+			metadata_available_teams_select, metadata_available_writers_select,
+		},
+		MetadataProviderConfig, MetadataProviderConfigInput, MetadataProviderKind,
+		UserPermission,
 	},
-	prisma::{media_metadata, PrismaClient},
+	prisma::{media_metadata, metadata_provider_config, PrismaClient},
 };
 use tracing::trace;
 use utoipa::ToSchema;
 
 use crate::{
 	config::state::AppState,
-	errors::APIResult,
+	errors::{APIError, APIResult},
 	filter::{FilterableQuery, MediaMetadataFilter},
-	middleware::auth::auth_middleware,
+	middleware::auth::{auth_middleware, RequestContext},
 	routers::api::filters::apply_media_metadata_filters,
 };
 
@@ -43,6 +52,13 @@ pub(crate) fn mount(app_state: AppState) -> Router<AppState> {
 				.route("/characters", get(get_characters_handler))
 				.route("/teams", get(get_teams_handler)),
 		)
+		.nest(
+			"/metadata/providers",
+			Router::new().route("/", get(get_metadata_providers)).route(
+				"/:provider",
+				get(get_metadata_provider).put(update_metadata_provider),
+			),
+		)
 		.layer(middleware::from_fn_with_state(app_state, auth_middleware))
 }
 
@@ -534,3 +550,120 @@ async fn get_teams_handler(
 		get_teams(&ctx.db, &apply_media_metadata_filters(filters)).await?,
 	))
 }
+
+fn parse_provider(provider: &str) -> APIResult<MetadataProviderKind> {
+	MetadataProviderKind::from_str(provider).map_err(APIError::BadRequest)
+}
+
+async fn find_provider_config(
+	client: &PrismaClient,
+	provider: MetadataProviderKind,
+) -> APIResult<MetadataProviderConfig> {
+	let config = client
+		.metadata_provider_config()
+		.find_unique(metadata_provider_config::provider::equals(
+			provider.to_string(),
+		))
+		.exec()
+		.await?;
+
+	match config {
+		Some(config) => Ok(MetadataProviderConfig::try_from(config)?),
+		None => Ok(MetadataProviderConfig::unconfigured(provider)),
+	}
+}
+
+#[utoipa::path(
+	get,
+	path = "/api/v1/metadata/providers",
+	tag = "metadata",
+	responses(
+		(status = 200, description = "Successfully fetched metadata providers", body = Vec<MetadataProviderConfig>),
+		(status = 401, description = "Unauthorized."),
+		(status = 403, description = "Forbidden."),
+		(status = 500, description = "Internal server error."),
+	)
+)]
+/// Get the configuration of every metadata provider, including those which have not been
+/// configured yet
+async fn get_metadata_providers(
+	State(ctx): State<AppState>,
+	Extension(req): Extension<RequestContext>,
+) -> APIResult<Json<Vec<MetadataProviderConfig>>> {
+	req.enforce_permissions(&[UserPermission::ManageServer])?;
+
+	let mut configs = vec![];
+	for provider in MetadataProviderKind::ALL {
+		configs.push(find_provider_config(&ctx.db, provider).await?);
+	}
+
+	Ok(Json(configs))
+}
+
+#[utoipa::path(
+	get,
+	path = "/api/v1/metadata/providers/:provider",
+	tag = "metadata",
+	params(
+		("provider" = String, Path, description = "The provider, e.g. COMICVINE")
+	),
+	responses(
+		(status = 200, description = "Successfully fetched metadata provider", body = MetadataProviderConfig),
+		(status = 400, description = "Unknown provider."),
+		(status = 401, description = "Unauthorized."),
+		(status = 403, description = "Forbidden."),
+		(status = 500, description = "Internal server error."),
+	)
+)]
+async fn get_metadata_provider(
+	Path(provider): Path<String>,
+	State(ctx): State<AppState>,
+	Extension(req): Extension<RequestContext>,
+) -> APIResult<Json<MetadataProviderConfig>> {
+	req.enforce_permissions(&[UserPermission::ManageServer])?;
+
+	let provider = parse_provider(&provider)?;
+	Ok(Json(find_provider_config(&ctx.db, provider).await?))
+}
+
+#[utoipa::path(
+	put,
+	path = "/api/v1/metadata/providers/:provider",
+	tag = "metadata",
+	params(
+		("provider" = String, Path, description = "The provider, e.g. COMICVINE")
+	),
+	request_body = MetadataProviderConfigInput,
+	responses(
+		(status = 200, description = "Successfully updated metadata provider", body = MetadataProviderConfig),
+		(status = 400, description = "Unknown provider."),
+		(status = 401, description = "Unauthorized."),
+		(status = 403, description = "Forbidden."),
+		(status = 500, description = "Internal server error."),
+	)
+)]
+/// Update the configuration of a metadata provider. The API key is encrypted before it is
+/// stored, and is never returned
+async fn update_metadata_provider(
+	Path(provider): Path<String>,
+	State(ctx): State<AppState>,
+	Extension(req): Extension<RequestContext>,
+	Json(input): Json<MetadataProviderConfigInput>,
+) -> APIResult<Json<MetadataProviderConfig>> {
+	req.enforce_permissions(&[UserPermission::ManageServer])?;
+
+	let provider = parse_provider(&provider)?;
+	let params = input.into_params(&ctx).await?;
+	let config = ctx
+		.db
+		.metadata_provider_config()
+		.upsert(
+			metadata_provider_config::provider::equals(provider.to_string()),
+			(provider.to_string(), params.clone()),
+			params,
+		)
+		.exec()
+		.await?;
+
+	Ok(Json(MetadataProviderConfig::try_from(config)?))
+}
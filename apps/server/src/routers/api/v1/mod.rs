@@ -21,6 +21,7 @@ use crate::{
 pub(crate) mod api_key;
 pub(crate) mod auth;
 pub(crate) mod book_club;
+pub(crate) mod collection;
 pub(crate) mod config;
 pub(crate) mod emailer;
 pub(crate) mod epub;
@@ -31,9 +32,11 @@ pub(crate) mod log;
 pub(crate) mod media;
 pub(crate) mod metadata;
 pub(crate) mod notifier;
+pub(crate) mod person;
 pub(crate) mod reading_list;
 pub(crate) mod series;
 pub(crate) mod smart_list;
+pub(crate) mod story_arc;
 pub(crate) mod tag;
 pub(crate) mod upload;
 pub(crate) mod user;
@@ -56,6 +59,9 @@ pub(crate) fn mount(app_state: AppState) -> Router<AppState> {
 		.merge(user::mount(app_state.clone()))
 		.merge(reading_list::mount(app_state.clone()))
 		.merge(smart_list::mount(app_state.clone()))
+		.merge(story_arc::mount(app_state.clone()))
+		.merge(person::mount(app_state.clone()))
+		.merge(collection::mount(app_state.clone()))
 		.merge(book_club::mount(app_state.clone()))
 		.merge(config::mount(app_state.clone()))
 		.route("/claim", get(claim))
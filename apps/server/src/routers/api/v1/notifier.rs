@@ -7,7 +7,10 @@ use axum::{
 use serde::Deserialize;
 use specta::Type;
 use stump_core::{
-	db::entity::{Notifier, NotifierConfigInput, NotifierType, UserPermission},
+	db::entity::{
+		Notifier, NotifierConfigInput, NotifierJobSubscription, NotifierType,
+		UserPermission,
+	},
 	prisma::notifier,
 };
 use utoipa::ToSchema;
@@ -107,6 +110,9 @@ pub struct CreateOrUpdateNotifier {
 	#[serde(rename = "type")]
 	_type: NotifierType,
 	config: NotifierConfigInput,
+	/// The jobs which the notifier should be sent events for. If omitted, the notifier is
+	/// subscribed to the completion of scans
+	subscriptions: Option<Vec<NotifierJobSubscription>>,
 }
 
 #[utoipa::path(
@@ -131,9 +137,17 @@ async fn create_notifier(
 
 	let client = &ctx.db;
 	let config = payload.config.into_config(&ctx).await?.into_bytes()?;
+	let subscriptions = payload
+		.subscriptions
+		.map(NotifierJobSubscription::into_bytes)
+		.transpose()?;
 	let notifier = client
 		.notifier()
-		.create(payload._type.to_string(), config, vec![])
+		.create(
+			payload._type.to_string(),
+			config,
+			vec![notifier::subscriptions::set(subscriptions)],
+		)
 		.exec()
 		.await?;
 
@@ -167,6 +181,10 @@ async fn update_notifier(
 
 	let client = &ctx.db;
 	let config = payload.config.into_config(&ctx).await?.into_bytes()?;
+	let subscriptions = payload
+		.subscriptions
+		.map(NotifierJobSubscription::into_bytes)
+		.transpose()?;
 	let notifier = client
 		.notifier()
 		.update(
@@ -174,6 +192,7 @@ async fn update_notifier(
 			vec![
 				notifier::r#type::set(payload._type.to_string()),
 				notifier::config::set(config),
+				notifier::subscriptions::set(subscriptions),
 			],
 		)
 		.exec()
@@ -187,6 +206,7 @@ pub struct PatchNotifier {
 	#[serde(rename = "type")]
 	_type: Option<NotifierType>,
 	config: Option<NotifierConfigInput>,
+	subscriptions: Option<Vec<NotifierJobSubscription>>,
 }
 
 #[utoipa::path(
@@ -219,6 +239,10 @@ async fn patch_notifier(
 	} else {
 		None
 	};
+	let subscriptions = payload
+		.subscriptions
+		.map(NotifierJobSubscription::into_bytes)
+		.transpose()?;
 
 	let patched_notifier = client
 		.notifier()
@@ -231,6 +255,7 @@ async fn patch_notifier(
 						._type
 						.map(|_type| notifier::r#type::set(_type.to_string())),
 					config.map(notifier::config::set),
+					subscriptions.map(|bytes| notifier::subscriptions::set(Some(bytes))),
 				],
 			),
 		)
@@ -0,0 +1,464 @@
+use axum::{
+	extract::{Path, State},
+	middleware,
+	routing::{get, post},
+	Extension, Json, Router,
+};
+use axum_extra::extract::Query;
+use prisma_client_rust::{or, Direction};
+use serde::{Deserialize, Serialize};
+use specta::Type;
+use stump_core::{
+	db::{
+		entity::{CreatorRole, Media, Person, PersonRoleCount, User, UserPermission},
+		query::pagination::{Pageable, Pagination, PaginationQuery},
+	},
+	job::{Executor, JobPriority},
+	metadata::{merge_people, MediaCreditsSyncJob},
+	prisma::{
+		active_reading_session, finished_reading_session, media, media_credit, person,
+		person_alias,
+	},
+};
+use tracing::{error, trace};
+use utoipa::ToSchema;
+
+use crate::{
+	config::state::AppState,
+	errors::{APIError, APIResult},
+	filter::chain_optional_iter,
+	middleware::auth::{auth_middleware, RequestContext},
+	routers::api::filters::apply_media_restrictions_for_user,
+};
+
+pub(crate) fn mount(app_state: AppState) -> Router<AppState> {
+	Router::new()
+		.route("/people", get(get_people))
+		.route("/people/credits/sync", post(sync_people_credits))
+		.nest(
+			"/people/:id",
+			Router::new()
+				.route("/", get(get_person_by_id))
+				.route("/media", get(get_person_media))
+				.route("/merge", post(merge_into_person)),
+		)
+		.layer(middleware::from_fn_with_state(app_state, auth_middleware))
+}
+
+/// A filter for the credits on books the user can access, optionally limited to a role
+fn visible_credit_filter(
+	user: &User,
+	role: Option<CreatorRole>,
+) -> Vec<media_credit::WhereParam> {
+	chain_optional_iter(
+		[media_credit::media::is(apply_media_restrictions_for_user(
+			user,
+		))],
+		[role.map(|role| media_credit::role::equals(role.to_string()))],
+	)
+}
+
+/// A filter for the people credited on at least one book the user can access, optionally
+/// in a specific role. People only credited on books in hidden libraries, or restricted by
+/// age, are not visible.
+pub(crate) fn person_visible_to_user_filter(
+	user: &User,
+	role: Option<CreatorRole>,
+) -> person::WhereParam {
+	person::credits::some(visible_credit_filter(user, role))
+}
+
+/// A filter for the books a person is credited on which the user can access, optionally in a
+/// specific role
+pub(crate) fn person_media_filter(
+	user: &User,
+	person_id: String,
+	role: Option<CreatorRole>,
+) -> Vec<media::WhereParam> {
+	apply_media_restrictions_for_user(user)
+		.into_iter()
+		.chain([media::credits::some(chain_optional_iter(
+			[media_credit::person_id::equals(person_id)],
+			[role.map(|role| media_credit::role::equals(role.to_string()))],
+		))])
+		.collect()
+}
+
+/// Count the books a person is credited on which the user can access, per role. Roles without
+/// any books are omitted
+pub(crate) async fn get_person_role_counts(
+	ctx: &AppState,
+	person_id: String,
+	user: &User,
+) -> APIResult<Vec<PersonRoleCount>> {
+	let credits = ctx
+		.db
+		.media_credit()
+		.find_many(
+			[media_credit::person_id::equals(person_id)]
+				.into_iter()
+				.chain(visible_credit_filter(user, None))
+				.collect(),
+		)
+		.select(media_credit::select!({ role }))
+		.exec()
+		.await?;
+
+	let counts = CreatorRole::ALL
+		.into_iter()
+		.map(|role| PersonRoleCount {
+			role,
+			count: credits
+				.iter()
+				.filter(|credit| credit.role == role.to_string())
+				.count() as i64,
+		})
+		.filter(|role_count| role_count.count > 0)
+		.collect();
+
+	Ok(counts)
+}
+
+#[derive(Debug, Default, Deserialize, ToSchema, Type)]
+pub struct PeopleQuery {
+	/// Only include people whose name, or one of whose aliases, contains the search
+	#[serde(default)]
+	search: Option<String>,
+	/// Only include people credited in this role
+	#[serde(default)]
+	role: Option<CreatorRole>,
+}
+
+#[utoipa::path(
+	get,
+	path = "/api/v1/people",
+	tag = "person",
+	params(
+		("pagination_query" = Option<PaginationQuery>, Query, description = "The pagination options"),
+		("search" = Option<String>, Query, description = "Search by name or alias"),
+		("role" = Option<CreatorRole>, Query, description = "Only include people credited in this role"),
+	),
+	responses(
+		(status = 200, description = "Successfully fetched people.", body = [Person]),
+		(status = 401, description = "Unauthorized."),
+		(status = 500, description = "Internal server error."),
+	)
+)]
+/// Get all people credited on at least one book accessible by the user, ordered by name
+async fn get_people(
+	pagination_query: Query<PaginationQuery>,
+	Query(query): Query<PeopleQuery>,
+	State(ctx): State<AppState>,
+	Extension(req): Extension<RequestContext>,
+) -> APIResult<Json<Pageable<Vec<Person>>>> {
+	let pagination = pagination_query.0.get();
+	let pagination_cloned = pagination.clone();
+
+	trace!(?pagination, ?query, "get_people");
+
+	let is_unpaged = pagination.is_unpaged();
+	let where_params = chain_optional_iter(
+		[person_visible_to_user_filter(req.user(), query.role)],
+		[query.search.map(|search| {
+			or![
+				person::name::contains(search.clone()),
+				person::aliases::some(vec![person_alias::name::contains(search)]),
+			]
+		})],
+	);
+
+	let (people, count) = ctx
+		.db
+		._transaction()
+		.run(|client| async move {
+			let mut query = client
+				.person()
+				.find_many(where_params.clone())
+				.order_by(person::name::order(Direction::Asc));
+
+			if !is_unpaged {
+				match pagination_cloned {
+					Pagination::Page(page_query) => {
+						let (skip, take) = page_query.get_skip_take();
+						query = query.skip(skip).take(take);
+					},
+					Pagination::Cursor(cursor_query) => {
+						if let Some(cursor) = cursor_query.cursor {
+							query = query.cursor(person::id::equals(cursor)).skip(1);
+						}
+						if let Some(limit) = cursor_query.limit {
+							query = query.take(limit);
+						}
+					},
+					_ => unreachable!(),
+				}
+			}
+
+			let people = query
+				.exec()
+				.await?
+				.into_iter()
+				.map(Person::from)
+				.collect::<Vec<_>>();
+
+			if is_unpaged {
+				return Ok((people, None));
+			}
+
+			client
+				.person()
+				.count(where_params)
+				.exec()
+				.await
+				.map(|count| (people, Some(count)))
+		})
+		.await?;
+
+	if let Some(count) = count {
+		return Ok(Json(Pageable::from((people, count, pagination))));
+	}
+
+	Ok(Json(Pageable::from(people)))
+}
+
+#[utoipa::path(
+	get,
+	path = "/api/v1/people/:id",
+	tag = "person",
+	params(
+		("id" = String, Path, description = "The ID of the person"),
+	),
+	responses(
+		(status = 200, description = "Successfully fetched person.", body = Person),
+		(status = 401, description = "Unauthorized."),
+		(status = 404, description = "Person not found."),
+		(status = 500, description = "Internal server error."),
+	)
+)]
+/// Get a person by their ID, with their aliases and the number of accessible books they are
+/// credited on in each role
+async fn get_person_by_id(
+	Path(id): Path<String>,
+	State(ctx): State<AppState>,
+	Extension(req): Extension<RequestContext>,
+) -> APIResult<Json<Person>> {
+	let user = req.user();
+	let person = ctx
+		.db
+		.person()
+		.find_first(vec![
+			person::id::equals(id.clone()),
+			person_visible_to_user_filter(user, None),
+		])
+		.with(person::aliases::fetch(vec![]))
+		.exec()
+		.await?
+		.ok_or(APIError::NotFound(format!("Person with id {id} not found")))?;
+
+	let credit_counts = get_person_role_counts(&ctx, id, user).await?;
+
+	Ok(Json(Person {
+		credit_counts: Some(credit_counts),
+		..Person::from(person)
+	}))
+}
+
+#[derive(Debug, Default, Deserialize, ToSchema, Type)]
+pub struct PersonMediaQuery {
+	/// Only include books the person is credited on in this role
+	#[serde(default)]
+	role: Option<CreatorRole>,
+}
+
+#[utoipa::path(
+	get,
+	path = "/api/v1/people/:id/media",
+	tag = "person",
+	params(
+		("id" = String, Path, description = "The ID of the person"),
+		("pagination_query" = Option<PaginationQuery>, Query, description = "The pagination options"),
+		("role" = Option<CreatorRole>, Query, description = "Only include books credited in this role"),
+	),
+	responses(
+		(status = 200, description = "Successfully fetched person media.", body = PageableMedia),
+		(status = 401, description = "Unauthorized."),
+		(status = 500, description = "Internal server error."),
+	)
+)]
+/// Get the books a person is credited on which are accessible by the user, ordered by name
+async fn get_person_media(
+	Path(id): Path<String>,
+	pagination_query: Query<PaginationQuery>,
+	Query(query): Query<PersonMediaQuery>,
+	State(ctx): State<AppState>,
+	Extension(req): Extension<RequestContext>,
+) -> APIResult<Json<Pageable<Vec<Media>>>> {
+	let user = req.user();
+	let user_id = user.id.clone();
+	let pagination = pagination_query.0.get();
+	let pagination_cloned = pagination.clone();
+
+	trace!(?pagination, ?query, "get_person_media");
+
+	let is_unpaged = pagination.is_unpaged();
+	let where_params = person_media_filter(user, id, query.role);
+
+	let (media, count) = ctx
+		.db
+		._transaction()
+		.run(|client| async move {
+			let mut query = client
+				.media()
+				.find_many(where_params.clone())
+				.with(media::metadata::fetch())
+				.with(media::active_user_reading_sessions::fetch(vec![
+					active_reading_session::user_id::equals(user_id.clone()),
+				]))
+				.with(media::finished_user_reading_sessions::fetch(vec![
+					finished_reading_session::user_id::equals(user_id),
+				]))
+				.order_by(media::name::order(Direction::Asc));
+
+			if !is_unpaged {
+				match pagination_cloned {
+					Pagination::Page(page_query) => {
+						let (skip, take) = page_query.get_skip_take();
+						query = query.skip(skip).take(take);
+					},
+					Pagination::Cursor(cursor_query) => {
+						if let Some(cursor) = cursor_query.cursor {
+							query = query.cursor(media::id::equals(cursor)).skip(1);
+						}
+						if let Some(limit) = cursor_query.limit {
+							query = query.take(limit);
+						}
+					},
+					_ => unreachable!(),
+				}
+			}
+
+			let media = query
+				.exec()
+				.await?
+				.into_iter()
+				.map(Media::from)
+				.collect::<Vec<_>>();
+
+			if is_unpaged {
+				return Ok((media, None));
+			}
+
+			client
+				.media()
+				.count(where_params)
+				.exec()
+				.await
+				.map(|count| (media, Some(count)))
+		})
+		.await?;
+
+	if let Some(count) = count {
+		return Ok(Json(Pageable::from((media, count, pagination))));
+	}
+
+	Ok(Json(Pageable::from(media)))
+}
+
+#[derive(Debug, Deserialize, ToSchema, Type)]
+pub struct MergePeople {
+	/// The IDs of the people to merge into the target person
+	person_ids: Vec<String>,
+}
+
+#[utoipa::path(
+	post,
+	path = "/api/v1/people/:id/merge",
+	tag = "person",
+	params(
+		("id" = String, Path, description = "The ID of the person to merge into"),
+	),
+	request_body = MergePeople,
+	responses(
+		(status = 200, description = "Successfully merged people.", body = Person),
+		(status = 400, description = "No people to merge."),
+		(status = 401, description = "Unauthorized."),
+		(status = 403, description = "Forbidden."),
+		(status = 404, description = "Person not found."),
+		(status = 500, description = "Internal server error."),
+	)
+)]
+/// Merge duplicate people into the person with the given ID. Their credits are moved to the
+/// person, and their names are kept as aliases so that books crediting them are matched to
+/// the person by future scans.
+async fn merge_into_person(
+	Path(id): Path<String>,
+	State(ctx): State<AppState>,
+	Extension(req): Extension<RequestContext>,
+	Json(input): Json<MergePeople>,
+) -> APIResult<Json<Person>> {
+	let user = req.user_and_enforce_permissions(&[UserPermission::ManageLibrary])?;
+
+	let mut person_ids = input.person_ids;
+	person_ids.push(id.clone());
+	person_ids.sort();
+	person_ids.dedup();
+
+	let visible_people = ctx
+		.db
+		.person()
+		.count(vec![
+			person::id::in_vec(person_ids.clone()),
+			person_visible_to_user_filter(&user, None),
+		])
+		.exec()
+		.await?;
+	if visible_people != person_ids.len() as i64 {
+		return Err(APIError::NotFound(String::from(
+			"One or more people were not found",
+		)));
+	}
+
+	let person = merge_people(&ctx.db, &id, person_ids).await?;
+	let credit_counts = get_person_role_counts(&ctx, id, &user).await?;
+
+	Ok(Json(Person {
+		credit_counts: Some(credit_counts),
+		..Person::from(person)
+	}))
+}
+
+#[derive(Serialize, ToSchema, Type)]
+pub struct SyncPeopleCreditsResponse {
+	/// The ID of the queued job
+	job_id: String,
+}
+
+#[utoipa::path(
+	post,
+	path = "/api/v1/people/credits/sync",
+	tag = "person",
+	responses(
+		(status = 200, description = "Successfully queued the credits sync.", body = SyncPeopleCreditsResponse),
+		(status = 401, description = "Unauthorized."),
+		(status = 403, description = "Forbidden."),
+		(status = 500, description = "Internal server error."),
+	)
+)]
+/// Queue a job which rebuilds the credits of every book from its stored metadata. This is
+/// only needed for books which were added before people were tracked, since credits are
+/// otherwise kept in sync whenever metadata changes.
+async fn sync_people_credits(
+	State(ctx): State<AppState>,
+	Extension(req): Extension<RequestContext>,
+) -> APIResult<Json<SyncPeopleCreditsResponse>> {
+	req.user_and_enforce_permissions(&[UserPermission::ManageLibrary])?;
+
+	let job = MediaCreditsSyncJob::new().with_priority(JobPriority::Interactive);
+	let job_id = job.id().to_string();
+	ctx.enqueue_job(job).map_err(|e| {
+		error!(?e, "Failed to enqueue credits sync job");
+		APIError::InternalServerError("Failed to enqueue credits sync job".to_string())
+	})?;
+
+	Ok(Json(SyncPeopleCreditsResponse { job_id }))
+}
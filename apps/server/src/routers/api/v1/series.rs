@@ -3,7 +3,7 @@ use std::collections::HashSet;
 use axum::{
 	extract::{DefaultBodyLimit, Multipart, Path, State},
 	middleware,
-	routing::{get, post},
+	routing::{get, post, put},
 	Extension, Json, Router,
 };
 use axum_extra::extract::Query;
@@ -17,7 +17,8 @@ use stump_core::{
 			macros::{
 				finished_reading_session_series_complete, series_or_library_thumbnail,
 			},
-			LibraryConfig, Media, Series, UserPermission,
+			LibraryConfig, Media, MergeStrategy, Series, SeriesMetadata, User,
+			UserPermission,
 		},
 		query::{
 			ordering::QueryOrder,
@@ -32,13 +33,16 @@ use stump_core::{
 			generate_book_thumbnail, place_thumbnail, remove_thumbnails,
 			GenerateThumbnailOptions, ImageFormat, ImageProcessorOptions,
 		},
-		scanner::SeriesScanJob,
+		scanner::{ScanDryRunJob, ScanOptions, SeriesScanJob},
 		ContentType,
 	},
+	job::{Executor, JobPriority},
+	metadata::ComicVineMatchJob,
 	prisma::{
 		active_reading_session, finished_reading_session, library,
 		media::{self, OrderByParam as MediaOrderByParam},
 		series::{self, OrderByParam, WhereParam},
+		series_metadata, PrismaClient,
 	},
 };
 use tokio::fs;
@@ -56,7 +60,10 @@ use crate::{
 			apply_series_filters_for_user,
 			apply_series_library_not_hidden_for_user_filter,
 		},
-		v1::media::thumbnails::get_media_thumbnail,
+		v1::{
+			library::ScanDryRunResponse,
+			media::{individual::MetadataLocks, thumbnails::get_media_thumbnail},
+		},
 	},
 	utils::{http::ImageResponse, validate_and_load_image},
 };
@@ -75,6 +82,14 @@ pub(crate) fn mount(app_state: AppState) -> Router<AppState> {
 			Router::new()
 				.route("/", get(get_series_by_id))
 				.route("/scan", post(scan_series))
+				.route("/scan/dry-run", post(scan_series_dry_run))
+				.route("/metadata", put(put_series_metadata))
+				.route("/metadata/locks", put(put_series_metadata_locks))
+				.route("/metadata/comicvine/match", post(match_series_comicvine))
+				.route(
+					"/metadata/comicvine/confirm",
+					post(confirm_series_comicvine),
+				)
 				.route("/media", get(get_series_media))
 				.route("/analyze", post(start_media_analysis))
 				.route("/media/next", get(get_next_in_series))
@@ -298,6 +313,7 @@ async fn get_series_by_id(
 	tag = "series",
 	responses(
 		(status = 200, description = "Successfully queued series scan"),
+		(status = 400, description = "Series is not part of a library"),
 		(status = 401, description = "Unauthorized"),
 		(status = 404, description = "Series not found"),
 		(status = 500, description = "Internal server error")
@@ -318,16 +334,334 @@ async fn scan_series(
 		.exec()
 		.await?
 		.ok_or(APIError::NotFound("Series not found".to_string()))?;
+	let library_id = series.library_id.ok_or(APIError::BadRequest(
+		"Series is not part of a library".to_string(),
+	))?;
 
-	ctx.enqueue_job(SeriesScanJob::new(series.id, series.path, None))
-		.map_err(|e| {
-			error!(?e, "Failed to enqueue series scan job");
-			APIError::InternalServerError("Failed to enqueue series scan job".to_string())
-		})?;
+	ctx.enqueue_job(
+		SeriesScanJob::new(series.id, library_id, series.path, None)
+			.with_priority(JobPriority::Interactive),
+	)
+	.map_err(|e| {
+		error!(?e, "Failed to enqueue series scan job");
+		APIError::InternalServerError("Failed to enqueue series scan job".to_string())
+	})?;
 
 	Ok(())
 }
 
+#[utoipa::path(
+	post,
+	path = "/api/v1/series/:id/scan/dry-run",
+	tag = "series",
+	responses(
+		(status = 200, description = "Successfully queued series scan dry-run", body = ScanDryRunResponse),
+		(status = 401, description = "Unauthorized"),
+		(status = 404, description = "Series not found"),
+		(status = 500, description = "Internal server error")
+	)
+)]
+/// Queue a job which reports what a scan of the series would change, without writing
+/// anything to the database
+async fn scan_series_dry_run(
+	Path(id): Path<String>,
+	State(ctx): State<AppState>,
+	Extension(req): Extension<RequestContext>,
+	Json(options): Json<Option<ScanOptions>>,
+) -> APIResult<Json<ScanDryRunResponse>> {
+	req.enforce_permissions(&[UserPermission::ScanLibrary])?;
+
+	let series = ctx
+		.db
+		.series()
+		.find_unique(series::id::equals(id.clone()))
+		.exec()
+		.await?
+		.ok_or(APIError::NotFound("Series not found".to_string()))?;
+
+	let job = ScanDryRunJob::series(series.id, series.path, options)
+		.with_priority(JobPriority::Interactive);
+	let job_id = job.id().to_string();
+	ctx.enqueue_job(job).map_err(|e| {
+		error!(?e, "Failed to enqueue series scan dry-run job");
+		APIError::InternalServerError(
+			"Failed to enqueue series scan dry-run job".to_string(),
+		)
+	})?;
+
+	Ok(Json(ScanDryRunResponse { job_id }))
+}
+
+#[derive(Default, Deserialize, ToSchema, specta::Type)]
+pub struct ComicVineMatchRequest {
+	/// The strategy used to reconcile the fetched metadata with the existing metadata.
+	/// Defaults to the merge strategy of the library
+	merge_strategy: Option<MergeStrategy>,
+}
+
+#[derive(Deserialize, ToSchema, specta::Type)]
+pub struct ConfirmComicVineMatch {
+	/// The ID of the ComicVine volume which the series should be matched to
+	volume_id: i32,
+	/// The strategy used to reconcile the fetched metadata with the existing metadata.
+	/// Defaults to the merge strategy of the library
+	merge_strategy: Option<MergeStrategy>,
+}
+
+#[derive(Serialize, ToSchema, specta::Type)]
+pub struct ComicVineMatchResponse {
+	/// The ID of the queued job. Any candidate volumes are available in the job's output once
+	/// it completes
+	job_id: String,
+}
+
+fn enqueue_comicvine_match(
+	ctx: &AppState,
+	series_id: String,
+	merge_strategy: Option<MergeStrategy>,
+) -> APIResult<ComicVineMatchResponse> {
+	let job = ComicVineMatchJob::new(series_id, merge_strategy)
+		.with_priority(JobPriority::Interactive);
+	let job_id = job.id().to_string();
+	ctx.enqueue_job(job).map_err(|e| {
+		error!(?e, "Failed to enqueue ComicVine match job");
+		APIError::InternalServerError("Failed to enqueue ComicVine match job".to_string())
+	})?;
+
+	Ok(ComicVineMatchResponse { job_id })
+}
+
+#[utoipa::path(
+	post,
+	path = "/api/v1/series/:id/metadata/comicvine/match",
+	tag = "series",
+	request_body = ComicVineMatchRequest,
+	responses(
+		(status = 200, description = "Successfully queued ComicVine match", body = ComicVineMatchResponse),
+		(status = 401, description = "Unauthorized"),
+		(status = 404, description = "Series not found"),
+		(status = 500, description = "Internal server error")
+	)
+)]
+/// Queue a job which matches the series to a ComicVine volume. If the series already knows its
+/// ComicVine ID, the metadata of the series and its books is filled from it. Otherwise, the
+/// candidate volumes are reported in the job's output so that one can be confirmed
+async fn match_series_comicvine(
+	Path(id): Path<String>,
+	State(ctx): State<AppState>,
+	Extension(req): Extension<RequestContext>,
+	Json(payload): Json<Option<ComicVineMatchRequest>>,
+) -> APIResult<Json<ComicVineMatchResponse>> {
+	req.enforce_permissions(&[UserPermission::ManageLibrary])?;
+
+	let series = ctx
+		.db
+		.series()
+		.find_unique(series::id::equals(id.clone()))
+		.exec()
+		.await?
+		.ok_or(APIError::NotFound("Series not found".to_string()))?;
+
+	let payload = payload.unwrap_or_default();
+	Ok(Json(enqueue_comicvine_match(
+		&ctx,
+		series.id,
+		payload.merge_strategy,
+	)?))
+}
+
+#[utoipa::path(
+	post,
+	path = "/api/v1/series/:id/metadata/comicvine/confirm",
+	tag = "series",
+	request_body = ConfirmComicVineMatch,
+	responses(
+		(status = 200, description = "Successfully queued ComicVine match", body = ComicVineMatchResponse),
+		(status = 401, description = "Unauthorized"),
+		(status = 404, description = "Series not found"),
+		(status = 500, description = "Internal server error")
+	)
+)]
+/// Confirm the ComicVine volume of the series, e.g. one of the candidates of a previous match,
+/// and queue a job to fill the metadata of the series and its books from it
+async fn confirm_series_comicvine(
+	Path(id): Path<String>,
+	State(ctx): State<AppState>,
+	Extension(req): Extension<RequestContext>,
+	Json(payload): Json<ConfirmComicVineMatch>,
+) -> APIResult<Json<ComicVineMatchResponse>> {
+	req.enforce_permissions(&[UserPermission::ManageLibrary])?;
+
+	let series = ctx
+		.db
+		.series()
+		.find_unique(series::id::equals(id.clone()))
+		.exec()
+		.await?
+		.ok_or(APIError::NotFound("Series not found".to_string()))?;
+
+	let params = vec![series_metadata::comicid::set(Some(payload.volume_id))];
+	ctx.db
+		.series_metadata()
+		.upsert(
+			series_metadata::series_id::equals(series.id.clone()),
+			(
+				"comicSeries".to_string(),
+				series::id::equals(series.id.clone()),
+				params.clone(),
+			),
+			params,
+		)
+		.exec()
+		.await?;
+
+	Ok(Json(enqueue_comicvine_match(
+		&ctx,
+		series.id,
+		payload.merge_strategy,
+	)?))
+}
+
+/// Find a series which the user may access, erroring if it does not exist or is hidden from
+/// the user
+async fn find_accessible_series_id(
+	ctx: &AppState,
+	user: &User,
+	id: &str,
+) -> APIResult<String> {
+	let age_restrictions = user
+		.age_restriction
+		.as_ref()
+		.map(|ar| apply_series_age_restriction(ar.age, ar.restrict_on_unset));
+	let where_params = [series::id::equals(id.to_string())]
+		.into_iter()
+		.chain(apply_series_library_not_hidden_for_user_filter(user))
+		.chain(age_restrictions.map(|ar| vec![ar]).unwrap_or_default())
+		.collect::<Vec<WhereParam>>();
+
+	let series = ctx
+		.db
+		.series()
+		.find_first(where_params)
+		.exec()
+		.await?
+		.ok_or(APIError::NotFound(String::from("Series not found")))?;
+
+	Ok(series.id)
+}
+
+#[utoipa::path(
+	put,
+	path = "/api/v1/series/:id/metadata",
+	tag = "series",
+	params(
+		("id" = String, Path, description = "The ID of the series to update metadata for")
+	),
+	request_body = SeriesMetadata,
+	responses(
+		(status = 200, description = "Successfully updated series metadata", body = SeriesMetadata),
+		(status = 401, description = "Unauthorized"),
+		(status = 403, description = "Forbidden"),
+		(status = 404, description = "Series not found"),
+		(status = 500, description = "Internal server error"),
+	)
+)]
+/// Update the metadata for a series. This is a full update, so any existing metadata will be
+/// replaced with the new metadata. Every field which is changed by the update is locked, so
+/// that it isn't overwritten by a ComicVine match.
+async fn put_series_metadata(
+	Path(id): Path<String>,
+	State(ctx): State<AppState>,
+	Extension(req): Extension<RequestContext>,
+	Json(metadata): Json<SeriesMetadata>,
+) -> APIResult<Json<SeriesMetadata>> {
+	let user = req.user_and_enforce_permissions(&[UserPermission::ManageLibrary])?;
+	let series_id = find_accessible_series_id(&ctx, &user, &id).await?;
+
+	let db = &ctx.db;
+	let existing_metadata = db
+		.series_metadata()
+		.find_unique(series_metadata::series_id::equals(series_id.clone()))
+		.exec()
+		.await?
+		.map(SeriesMetadata::from);
+
+	let metadata = SeriesMetadata::apply_manual_edit(existing_metadata, metadata);
+	let locked_fields_param = metadata.locked_fields_param();
+	let (meta_type, mut params) = metadata.create_action();
+	params.push(locked_fields_param);
+
+	let updated = db
+		.series_metadata()
+		.upsert(
+			series_metadata::series_id::equals(series_id.clone()),
+			(
+				meta_type.clone(),
+				series::id::equals(series_id),
+				params.clone(),
+			),
+			params
+				.into_iter()
+				.chain(std::iter::once(series_metadata::meta_type::set(meta_type)))
+				.collect(),
+		)
+		.exec()
+		.await?;
+
+	Ok(Json(SeriesMetadata::from(updated)))
+}
+
+#[utoipa::path(
+	put,
+	path = "/api/v1/series/:id/metadata/locks",
+	tag = "series",
+	params(
+		("id" = String, Path, description = "The ID of the series to lock metadata fields for")
+	),
+	request_body = MetadataLocks,
+	responses(
+		(status = 200, description = "Successfully updated locked fields", body = SeriesMetadata),
+		(status = 400, description = "A field cannot be locked"),
+		(status = 401, description = "Unauthorized"),
+		(status = 403, description = "Forbidden"),
+		(status = 404, description = "Series metadata not available"),
+		(status = 500, description = "Internal server error"),
+	)
+)]
+/// Replace the locked fields of the metadata for a series. An empty list unlocks every field
+async fn put_series_metadata_locks(
+	Path(id): Path<String>,
+	State(ctx): State<AppState>,
+	Extension(req): Extension<RequestContext>,
+	Json(input): Json<MetadataLocks>,
+) -> APIResult<Json<SeriesMetadata>> {
+	let user = req.user_and_enforce_permissions(&[UserPermission::ManageLibrary])?;
+	let series_id = find_accessible_series_id(&ctx, &user, &id).await?;
+
+	let db = &ctx.db;
+	let mut metadata = db
+		.series_metadata()
+		.find_unique(series_metadata::series_id::equals(series_id.clone()))
+		.exec()
+		.await?
+		.map(SeriesMetadata::from)
+		.ok_or(APIError::NotFound(String::from(
+			"Series metadata not available",
+		)))?;
+	metadata.set_locked_fields(input.locked_fields)?;
+
+	let updated = db
+		.series_metadata()
+		.update(
+			series_metadata::series_id::equals(series_id),
+			vec![metadata.locked_fields_param()],
+		)
+		.exec()
+		.await?;
+
+	Ok(Json(SeriesMetadata::from(updated)))
+}
+
 // FIXME: This hand written SQL needs to factor in age restrictions!
 #[utoipa::path(
 	get,
@@ -412,9 +746,17 @@ async fn get_series_thumbnail_handler(
 	State(ctx): State<AppState>,
 	Extension(req): Extension<RequestContext>,
 ) -> APIResult<ImageResponse> {
-	let db = &ctx.db;
+	get_series_thumbnail_by_id(id, &ctx.db, req.user(), &ctx.config)
+		.await
+		.map(ImageResponse::from)
+}
 
-	let user = req.user();
+pub(crate) async fn get_series_thumbnail_by_id(
+	id: String,
+	db: &PrismaClient,
+	user: &User,
+	config: &StumpConfig,
+) -> APIResult<(ContentType, Vec<u8>)> {
 	let age_restriction = user.age_restriction.as_ref();
 	let series_age_restriction = age_restriction
 		.map(|ar| apply_series_age_restriction(ar.age, ar.restrict_on_unset));
@@ -444,9 +786,7 @@ async fn get_series_thumbnail_handler(
 	let library_config = series.library.map(|l| l.config).map(LibraryConfig::from);
 	let image_format = library_config.and_then(|o| o.thumbnail_config.map(|c| c.format));
 
-	get_series_thumbnail(&id, first_book, image_format, &ctx.config)
-		.await
-		.map(ImageResponse::from)
+	get_series_thumbnail(&id, first_book, image_format, config).await
 }
 
 #[derive(Deserialize, ToSchema, specta::Type)]
@@ -828,27 +1168,7 @@ async fn get_next_in_series(
 			e
 		})?;
 
-		let next_book = media
-			.iter()
-			.find(|m| {
-				match m
-					.active_user_reading_sessions()
-					.ok()
-					.and_then(|sessions| sessions.first())
-				{
-					// If there is a percentage, and it is less than 1.0, then it is next!
-					Some(session) if session.epubcfi.is_some() => session
-						.percentage_completed
-						.is_some_and(|value| value < 1.0),
-					// If there is a page, and it is less than the total pages, then it is next!
-					Some(session) if session.page.is_some() => {
-						session.page.unwrap_or(1) < m.pages
-					},
-					// No session means it is up next!
-					_ => true,
-				}
-			})
-			.or_else(|| media.first());
+		let next_book = find_next_book(media);
 
 		Ok(Json(next_book.map(|data| Media::from(data.to_owned()))))
 	} else {
@@ -856,6 +1176,33 @@ async fn get_next_in_series(
 	}
 }
 
+/// Find the next book to read in an ordered list of books, which must have the active
+/// reading sessions of the user loaded. The first book which is not finished is next,
+/// falling back to the first book.
+pub(crate) fn find_next_book(books: &[media::Data]) -> Option<&media::Data> {
+	books
+		.iter()
+		.find(|m| {
+			match m
+				.active_user_reading_sessions()
+				.ok()
+				.and_then(|sessions| sessions.first())
+			{
+				// If there is a percentage, and it is less than 1.0, then it is next!
+				Some(session) if session.epubcfi.is_some() => session
+					.percentage_completed
+					.is_some_and(|value| value < 1.0),
+				// If there is a page, and it is less than the total pages, then it is next!
+				Some(session) if session.page.is_some() => {
+					session.page.unwrap_or(1) < m.pages
+				},
+				// No session means it is up next!
+				_ => true,
+			}
+		})
+		.or_else(|| books.first())
+}
+
 #[derive(Deserialize, Serialize, ToSchema, specta::Type)]
 pub struct SeriesIsComplete {
 	is_complete: bool,
@@ -964,12 +1311,14 @@ async fn start_media_analysis(
 	req.enforce_permissions(&[UserPermission::ManageLibrary])?;
 
 	// Start analysis job
-	ctx.enqueue_job(AnalyzeMediaJob::analyze_series(id))
-		.map_err(|e| {
-			let err = "Failed to enqueue analyze series media job";
-			error!(?e, err);
-			APIError::InternalServerError(err.to_string())
-		})?;
+	ctx.enqueue_job(
+		AnalyzeMediaJob::analyze_series(id).with_priority(JobPriority::Interactive),
+	)
+	.map_err(|e| {
+		let err = "Failed to enqueue analyze series media job";
+		error!(?e, err);
+		APIError::InternalServerError(err.to_string())
+	})?;
 
 	APIResult::Ok(())
 }
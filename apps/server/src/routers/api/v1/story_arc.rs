@@ -0,0 +1,266 @@
+use axum::{
+	extract::{Path, State},
+	middleware,
+	routing::get,
+	Extension, Json, Router,
+};
+use axum_extra::extract::Query;
+use prisma_client_rust::Direction;
+use stump_core::{
+	db::{
+		entity::{cmp_story_arc_positions, Media, StoryArc, StoryArcMember, User},
+		query::pagination::{PageQuery, Pageable, Pagination, PaginationQuery},
+	},
+	prisma::{
+		active_reading_session, finished_reading_session, media, story_arc,
+		story_arc_member,
+	},
+};
+use tracing::trace;
+
+use crate::{
+	config::state::AppState,
+	errors::{APIError, APIResult},
+	middleware::auth::{auth_middleware, RequestContext},
+	routers::api::{
+		filters::apply_media_restrictions_for_user, v1::series::find_next_book,
+	},
+};
+
+pub(crate) fn mount(app_state: AppState) -> Router<AppState> {
+	Router::new()
+		.route("/story-arcs", get(get_story_arcs))
+		.nest(
+			"/story-arcs/:id",
+			Router::new()
+				.route("/", get(get_story_arc_by_id))
+				.route("/media", get(get_story_arc_media))
+				.route("/media/next", get(get_next_in_story_arc)),
+		)
+		.layer(middleware::from_fn_with_state(app_state, auth_middleware))
+}
+
+/// A filter for the story arcs which have at least one book the user can access. Arcs
+/// made up only of books in hidden libraries, or restricted by age, are not visible.
+pub(crate) fn story_arc_visible_to_user_filter(user: &User) -> story_arc::WhereParam {
+	story_arc::members::some(vec![story_arc_member::media::is(
+		apply_media_restrictions_for_user(user),
+	)])
+}
+
+/// Fetch the members of a story arc whose books the user can access, with the books and
+/// their reading sessions loaded, in the order of the arc. Books without a position are
+/// ordered last, by name.
+pub(crate) async fn fetch_story_arc_members(
+	ctx: &AppState,
+	story_arc_id: String,
+	user: &User,
+) -> APIResult<Vec<story_arc_member::Data>> {
+	let mut members = ctx
+		.db
+		.story_arc_member()
+		.find_many(vec![
+			story_arc_member::story_arc_id::equals(story_arc_id),
+			story_arc_member::media::is(apply_media_restrictions_for_user(user)),
+		])
+		.with(
+			story_arc_member::media::fetch()
+				.with(media::metadata::fetch())
+				.with(media::active_user_reading_sessions::fetch(vec![
+					active_reading_session::user_id::equals(user.id.clone()),
+				]))
+				.with(media::finished_user_reading_sessions::fetch(vec![
+					finished_reading_session::user_id::equals(user.id.clone()),
+				])),
+		)
+		.exec()
+		.await?;
+
+	members.sort_by(|a, b| {
+		cmp_story_arc_positions(a.position, b.position).then_with(|| {
+			let a_name = a.media().map(|m| m.name.as_str()).unwrap_or_default();
+			let b_name = b.media().map(|m| m.name.as_str()).unwrap_or_default();
+			a_name.cmp(b_name)
+		})
+	});
+
+	Ok(members)
+}
+
+#[utoipa::path(
+	get,
+	path = "/api/v1/story-arcs",
+	tag = "story-arc",
+	params(
+		("pagination_query" = Option<PaginationQuery>, Query, description = "The pagination options"),
+	),
+	responses(
+		(status = 200, description = "Successfully fetched story arcs.", body = [StoryArc]),
+		(status = 401, description = "Unauthorized."),
+		(status = 500, description = "Internal server error."),
+	)
+)]
+/// Get all story arcs which have at least one book accessible by the user, ordered by name
+async fn get_story_arcs(
+	pagination_query: Query<PaginationQuery>,
+	State(ctx): State<AppState>,
+	Extension(req): Extension<RequestContext>,
+) -> APIResult<Json<Pageable<Vec<StoryArc>>>> {
+	let pagination = pagination_query.0.get();
+	let pagination_cloned = pagination.clone();
+
+	trace!(?pagination, "get_story_arcs");
+
+	let is_unpaged = pagination.is_unpaged();
+	let where_params = vec![story_arc_visible_to_user_filter(req.user())];
+
+	let (story_arcs, count) = ctx
+		.db
+		._transaction()
+		.run(|client| async move {
+			let mut query = client
+				.story_arc()
+				.find_many(where_params.clone())
+				.order_by(story_arc::name::order(Direction::Asc));
+
+			if !is_unpaged {
+				match pagination_cloned {
+					Pagination::Page(page_query) => {
+						let (skip, take) = page_query.get_skip_take();
+						query = query.skip(skip).take(take);
+					},
+					Pagination::Cursor(cursor_query) => {
+						if let Some(cursor) = cursor_query.cursor {
+							query = query.cursor(story_arc::id::equals(cursor)).skip(1);
+						}
+						if let Some(limit) = cursor_query.limit {
+							query = query.take(limit);
+						}
+					},
+					_ => unreachable!(),
+				}
+			}
+
+			let story_arcs = query
+				.exec()
+				.await?
+				.into_iter()
+				.map(StoryArc::from)
+				.collect::<Vec<_>>();
+
+			if is_unpaged {
+				return Ok((story_arcs, None));
+			}
+
+			client
+				.story_arc()
+				.count(where_params)
+				.exec()
+				.await
+				.map(|count| (story_arcs, Some(count)))
+		})
+		.await?;
+
+	if let Some(count) = count {
+		return Ok(Json(Pageable::from((story_arcs, count, pagination))));
+	}
+
+	Ok(Json(Pageable::from(story_arcs)))
+}
+
+#[utoipa::path(
+	get,
+	path = "/api/v1/story-arcs/:id",
+	tag = "story-arc",
+	params(
+		("id" = String, Path, description = "The ID of the story arc"),
+	),
+	responses(
+		(status = 200, description = "Successfully fetched story arc.", body = StoryArc),
+		(status = 401, description = "Unauthorized."),
+		(status = 404, description = "Story arc not found."),
+		(status = 500, description = "Internal server error."),
+	)
+)]
+/// Get a story arc by its ID
+async fn get_story_arc_by_id(
+	Path(id): Path<String>,
+	State(ctx): State<AppState>,
+	Extension(req): Extension<RequestContext>,
+) -> APIResult<Json<StoryArc>> {
+	let story_arc = ctx
+		.db
+		.story_arc()
+		.find_first(vec![
+			story_arc::id::equals(id.clone()),
+			story_arc_visible_to_user_filter(req.user()),
+		])
+		.exec()
+		.await?
+		.ok_or(APIError::NotFound(format!(
+			"Story arc with id {id} not found"
+		)))?;
+
+	Ok(Json(StoryArc::from(story_arc)))
+}
+
+#[utoipa::path(
+	get,
+	path = "/api/v1/story-arcs/:id/media",
+	tag = "story-arc",
+	params(
+		("id" = String, Path, description = "The ID of the story arc"),
+		("pagination" = Option<PageQuery>, Query, description = "The pagination options"),
+	),
+	responses(
+		(status = 200, description = "Successfully fetched story arc media.", body = [StoryArcMember]),
+		(status = 401, description = "Unauthorized."),
+		(status = 500, description = "Internal server error."),
+	)
+)]
+/// Get the books of a story arc in reading order, each with its position in the arc.
+/// Books without a position are ordered after those with one.
+async fn get_story_arc_media(
+	Path(id): Path<String>,
+	pagination: Query<PageQuery>,
+	State(ctx): State<AppState>,
+	Extension(req): Extension<RequestContext>,
+) -> APIResult<Json<Pageable<Vec<StoryArcMember>>>> {
+	let members = fetch_story_arc_members(&ctx, id, req.user())
+		.await?
+		.into_iter()
+		.map(StoryArcMember::from)
+		.collect::<Vec<_>>();
+
+	Ok(Json(Pageable::from((members, Some(pagination.0)))))
+}
+
+#[utoipa::path(
+	get,
+	path = "/api/v1/story-arcs/:id/media/next",
+	tag = "story-arc",
+	params(
+		("id" = String, Path, description = "The ID of the story arc"),
+	),
+	responses(
+		(status = 200, description = "Successfully fetched media up-next in story arc", body = Option<Media>),
+		(status = 401, description = "Unauthorized."),
+		(status = 500, description = "Internal server error."),
+	)
+)]
+/// Get the next book to read in a story arc, following the same rules as the next book
+/// in a series
+async fn get_next_in_story_arc(
+	Path(id): Path<String>,
+	State(ctx): State<AppState>,
+	Extension(req): Extension<RequestContext>,
+) -> APIResult<Json<Option<Media>>> {
+	let books = fetch_story_arc_members(&ctx, id, req.user())
+		.await?
+		.into_iter()
+		.filter_map(|member| member.media().ok().cloned())
+		.collect::<Vec<_>>();
+	let next_book = find_next_book(&books);
+
+	Ok(Json(next_book.map(|data| Media::from(data.to_owned()))))
+}
@@ -12,7 +12,7 @@ use axum::{
 };
 use axum_typed_multipart::{FieldData, TryFromMultipart, TypedMultipart};
 use tempfile::NamedTempFile;
-use tokio::fs;
+use tokio::task::spawn_blocking;
 use zip::read::ZipFile;
 
 use crate::{
@@ -23,7 +23,10 @@ use crate::{
 };
 use stump_core::{
 	db::entity::{macros::library_path_with_options_select, User, UserPermission},
-	filesystem::scanner::LibraryScanJob,
+	filesystem::{
+		scanner::LibraryScanJob,
+		storage::{storage_for_path, StorageBackend},
+	},
 	prisma::{library, PrismaClient},
 };
 
@@ -80,8 +83,16 @@ async fn upload_books(
 	// Validate and path that uploads will be placed at, account for possible full path
 	let placement_path = get_books_path(&books_request, &library)?;
 
+	let storage = storage_for_path(&placement_path, &ctx.config)?;
 	// Check that it is a directory and already exists
-	if !fs::metadata(&placement_path).await?.is_dir() {
+	let placement = spawn_blocking({
+		let storage = storage.clone();
+		let placement_path = placement_path.clone();
+		move || storage.metadata(&placement_path)
+	})
+	.await
+	.map_err(|e| APIError::InternalServerError(e.to_string()))??;
+	if !placement.is_dir {
 		return Err(APIError::BadRequest(
 			"Book uploads must be placed at an existing directory.".to_string(),
 		));
@@ -95,7 +106,7 @@ async fn upload_books(
 		))?;
 		let target_path = placement_path.join(file_name);
 
-		copy_tempfile_to_location(f, &target_path).await?;
+		copy_tempfile_to_location(f, &target_path, storage.clone()).await?;
 	}
 
 	// Start a scan of the library
@@ -153,29 +164,30 @@ async fn upload_series(
 	// Validate the placement path parameters and create the full path, error otherwise
 	let placement_path = get_series_path(&series_request, &library)?;
 
-	// Validate the contents of the zip file
-	validate_series_upload_contents(&series_request, &placement_path, false)?;
-
-	// Create directory if necessary
-	if let Err(e) = fs::metadata(&placement_path).await {
-		if e.kind() == tokio::io::ErrorKind::NotFound {
-			fs::create_dir_all(&placement_path).await?;
-		} else {
-			return Err(APIError::InternalServerError(format!(
-				"Error accessing directory {placement_path:?}: {e}"
-			)));
+	let storage = storage_for_path(&placement_path, &ctx.config)?;
+	// Validate the contents of the zip file. This reads the archive and checks the storage
+	// for each file, so it runs on a blocking thread
+	let (series_request, placement_path) = spawn_blocking({
+		let storage = storage.clone();
+		move || {
+			validate_series_upload_contents(
+				&series_request,
+				&placement_path,
+				storage.as_ref(),
+				false,
+			)
+			.map(|_| (series_request, placement_path))
 		}
-	}
+	})
+	.await
+	.map_err(|e| APIError::InternalServerError(e.to_string()))??;
 
 	// Get a zip crate file handle to the temporary file
 	let temp_file = series_request.file.contents.as_file().try_clone()?;
-	let mut zip_archive = zip::ZipArchive::new(temp_file).map_err(|e| {
-		APIError::InternalServerError(format!("Error opening zip archive: {e}"))
-	})?;
-	// Extract the contents
-	zip_archive.extract(placement_path).map_err(|e| {
-		APIError::InternalServerError(format!("Error unpacking zip archive: {e}"))
-	})?;
+	// Extract the contents. Missing directories are created by the storage as files are written
+	spawn_blocking(move || extract_series_archive(temp_file, &placement_path, storage))
+		.await
+		.map_err(|e| APIError::InternalServerError(e.to_string()))??;
 
 	// Start a scan of the library
 	ctx.enqueue_job(LibraryScanJob::new(id, library.path, None))
@@ -193,20 +205,63 @@ async fn upload_series(
 async fn copy_tempfile_to_location(
 	field_data: FieldData<NamedTempFile>,
 	target_path: &path::Path,
+	storage: Arc<dyn StorageBackend>,
 ) -> APIResult<()> {
 	// We want to prevent overwriting something that already exists
-	if fs::metadata(target_path).await.is_ok() {
+	let exists = spawn_blocking({
+		let storage = storage.clone();
+		let target_path = target_path.to_path_buf();
+		move || storage.exists(&target_path)
+	})
+	.await
+	.map_err(|e| APIError::InternalServerError(e.to_string()))??;
+	if exists {
 		return Err(APIError::BadRequest(format!(
 			"File already exists at {target_path:?}",
 		)));
 	}
 
-	// Get a tokio::fs::File for the temporary file
-	let mut temp_file = fs::File::from_std(field_data.contents.into_file());
+	// Copy the bytes to the target location. The tempfile is moved into the task so that it
+	// isn't deleted until the copy is complete
+	let temp_file = field_data.contents;
+	let target_path = target_path.to_path_buf();
+	spawn_blocking(move || storage.upload(temp_file.path(), &target_path))
+		.await
+		.map_err(|e| APIError::InternalServerError(e.to_string()))??;
+
+	Ok(())
+}
+
+/// Extracts each file of a (validated) series archive into the series directory, via the
+/// storage backend of the library
+fn extract_series_archive(
+	archive_file: std::fs::File,
+	series_path: &path::Path,
+	storage: Arc<dyn StorageBackend>,
+) -> APIResult<()> {
+	let mut zip_archive = zip::ZipArchive::new(archive_file).map_err(|e| {
+		APIError::InternalServerError(format!("Error opening zip archive: {e}"))
+	})?;
+
+	for i in 0..zip_archive.len() {
+		let mut zip_file = zip_archive.by_index(i).map_err(|e| {
+			APIError::InternalServerError(format!("Error unpacking zip archive: {e}"))
+		})?;
+		if zip_file.is_dir() {
+			continue;
+		}
+		let Some(enclosed_path) = zip_file.enclosed_name() else {
+			return Err(APIError::InternalServerError(
+				"Series zip contained a malformed path".to_string(),
+			));
+		};
 
-	// Copy the bytes to the target location
-	let mut target_file = fs::File::create(target_path).await?;
-	tokio::io::copy(&mut temp_file, &mut target_file).await?;
+		// Each file is staged on disk rather than in memory, since series archives are
+		// not subject to the upload size limit
+		let mut staged = NamedTempFile::new()?;
+		std::io::copy(&mut zip_file, &mut staged)?;
+		storage.upload(staged.path(), &series_path.join(enclosed_path))?;
+	}
 
 	Ok(())
 }
@@ -313,6 +368,7 @@ fn validate_series_upload(series_request: &UploadSeriesRequest) -> APIResult<()>
 fn validate_series_upload_contents(
 	series_request: &UploadSeriesRequest,
 	series_path: &path::Path,
+	storage: &dyn StorageBackend,
 	allow_overwrite: bool,
 ) -> APIResult<()> {
 	let temp_file = series_request.file.contents.as_file().try_clone()?;
@@ -344,7 +400,7 @@ fn validate_series_upload_contents(
 		let extraction_path = series_path.join(enclosed_path);
 
 		// Error if the file already exists and we aren't allowing overwrites
-		if extraction_path.exists() && !allow_overwrite {
+		if !allow_overwrite && storage.exists(&extraction_path)? {
 			return Err(APIError::InternalServerError(format!(
 				"Unable to extract zip contents to {extraction_path:?}, overwrites are disabled"
 			)));
@@ -470,6 +526,17 @@ async fn get_library(
 		.ok_or(APIError::NotFound(String::from("Library not found")))
 }
 
+/// Resolves the directory an upload should be placed in. The placement may be a full path
+/// within any of the library's roots, otherwise it is relative to the library's primary root.
+fn resolve_placement_path(place_at: &str, library: &LibraryData) -> PathBuf {
+	let roots = library.roots();
+	if roots.split(path::Path::new(place_at)).is_some() {
+		path::PathBuf::from(place_at)
+	} else {
+		roots.primary().join(place_at)
+	}
+}
+
 /// A helper function to generate the path at which books should be placed
 /// given an input [`UploadBooksRequest`] and library.
 fn get_books_path(
@@ -483,14 +550,7 @@ fn get_books_path(
 			"Invalid upload path placement parameters".to_string(),
 		));
 	}
-	// Get path that uploads will be placed at, account for possible full path
-	let placement_path = if books_request.place_at.starts_with(&library.path) {
-		path::PathBuf::from(&books_request.place_at)
-	} else {
-		path::Path::new(&library.path).join(&books_request.place_at)
-	};
-
-	Ok(placement_path)
+	Ok(resolve_placement_path(&books_request.place_at, library))
 }
 
 /// A helper function to generate the path at which a series zip should be placed
@@ -511,17 +571,8 @@ fn get_series_path(
 		));
 	}
 
-	// Get path that the series upload will be placed at, accounting for possible full path
-	let placement_path = if series_request.place_at.starts_with(&library.path) {
-		path::PathBuf::from(&series_request.place_at)
-			.join(&series_request.series_dir_name)
-	} else {
-		path::Path::new(&library.path)
-			.join(&series_request.place_at)
-			.join(&series_request.series_dir_name)
-	};
-
-	Ok(placement_path)
+	Ok(resolve_placement_path(&series_request.place_at, library)
+		.join(&series_request.series_dir_name))
 }
 
 #[cfg(test)]
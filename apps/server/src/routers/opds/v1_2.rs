@@ -8,7 +8,10 @@ use prisma_client_rust::or;
 use prisma_client_rust::{chrono, Direction};
 use serde::{Deserialize, Serialize};
 use stump_core::{
-	db::{entity::UserPermission, query::pagination::PageQuery},
+	db::{
+		entity::{CreatorRole, UserPermission},
+		query::pagination::PageQuery,
+	},
 	filesystem::{
 		get_page_async,
 		image::{GenericImageProcessor, ImageProcessor, ImageProcessorOptions},
@@ -22,7 +25,10 @@ use stump_core::{
 		link::{OpdsLink, OpdsLinkRel, OpdsLinkType},
 		opensearch::OpdsOpenSearch,
 	},
-	prisma::{active_reading_session, library, media, series, series_metadata, user},
+	prisma::{
+		active_reading_session, collection, library, media, person, person_alias, series,
+		series_metadata, story_arc, user,
+	},
 };
 use tracing::{debug, trace};
 
@@ -37,7 +43,12 @@ use crate::{
 			apply_media_library_not_hidden_for_user_filter, apply_series_age_restriction,
 			library_not_hidden_from_user_filter,
 		},
-		v1::media::thumbnails::get_media_thumbnail_by_id,
+		v1::{
+			collection::{collection_visible_to_user_filter, fetch_collection_items},
+			media::thumbnails::get_media_thumbnail_by_id,
+			person::{person_media_filter, person_visible_to_user_filter},
+			story_arc::{fetch_story_arc_members, story_arc_visible_to_user_filter},
+		},
 	},
 	utils::http::{ImageResponse, NamedFile, Xml},
 };
@@ -60,6 +71,24 @@ pub(crate) fn mount(app_state: AppState) -> Router<AppState> {
 				.route("/latest", get(get_latest_series))
 				.route("/:id", get(get_series_by_id)),
 		)
+		.nest(
+			"/story-arcs",
+			Router::new()
+				.route("/", get(get_story_arcs))
+				.route("/:id", get(get_story_arc_by_id)),
+		)
+		.nest(
+			"/authors",
+			Router::new()
+				.route("/", get(get_authors))
+				.route("/:id", get(get_author_by_id)),
+		)
+		.nest(
+			"/collections",
+			Router::new()
+				.route("/", get(get_collections))
+				.route("/:id", get(get_collection_by_id)),
+		)
 		.nest(
 			"/books/:id",
 			Router::new()
@@ -200,6 +229,45 @@ async fn catalog(Extension(req): Extension<RequestContext>) -> APIResult<Xml> {
 			}]),
 			None,
 		),
+		OpdsEntry::new(
+			"storyArcs".to_string(),
+			chrono::Utc::now().into(),
+			"Story arcs".to_string(),
+			Some(String::from("Browse by story arc")),
+			None,
+			Some(vec![OpdsLink {
+				link_type: OpdsLinkType::Navigation,
+				rel: OpdsLinkRel::Subsection,
+				href: catalog_url(&req, "story-arcs"),
+			}]),
+			None,
+		),
+		OpdsEntry::new(
+			"authors".to_string(),
+			chrono::Utc::now().into(),
+			"Authors".to_string(),
+			Some(String::from("Browse by author")),
+			None,
+			Some(vec![OpdsLink {
+				link_type: OpdsLinkType::Navigation,
+				rel: OpdsLinkRel::Subsection,
+				href: catalog_url(&req, "authors"),
+			}]),
+			None,
+		),
+		OpdsEntry::new(
+			"collections".to_string(),
+			chrono::Utc::now().into(),
+			"Collections".to_string(),
+			Some(String::from("Browse by collection")),
+			None,
+			Some(vec![OpdsLink {
+				link_type: OpdsLinkType::Navigation,
+				rel: OpdsLinkRel::Subsection,
+				href: catalog_url(&req, "collections"),
+			}]),
+			None,
+		),
 		// TODO: more?
 		// TODO: get user stored searches, so they don't have to redo them over and over?
 		// e.g. /opds/v1.2/series?search={searchTerms}, /opds/v1.2/libraries?search={searchTerms}, etc.
@@ -656,6 +724,392 @@ async fn get_series_by_id(
 	}
 }
 
+/// A handler for GET /opds/v1.2/story-arcs, accepts `page` and `search` URL params
+async fn get_story_arcs(
+	State(ctx): State<AppState>,
+	Query(pagination): Query<PageQuery>,
+	Query(OPDSSearchQuery { search }): Query<OPDSSearchQuery>,
+	Extension(req): Extension<RequestContext>,
+) -> APIResult<Xml> {
+	let db = &ctx.db;
+
+	let page = pagination.page.unwrap_or(0);
+	let (skip, take) = pagination_bounds(page.into(), 20);
+
+	let where_params = chain_optional_iter(
+		[story_arc_visible_to_user_filter(req.user())],
+		[search
+			.as_ref()
+			.map(|q| story_arc::name::contains(q.clone()))],
+	);
+
+	let (story_arcs, count) = db
+		._transaction()
+		.run(|client| async move {
+			let story_arcs = client
+				.story_arc()
+				.find_many(where_params.clone())
+				.skip(skip)
+				.take(take)
+				.order_by(story_arc::name::order(Direction::Asc))
+				.exec()
+				.await?;
+
+			client
+				.story_arc()
+				.count(where_params)
+				.exec()
+				.await
+				.map(|count| (story_arcs, count))
+		})
+		.await?;
+
+	let entries = story_arcs
+		.into_iter()
+		.map(|arc| {
+			OPDSEntryBuilder::<story_arc::Data>::new(arc, req.api_key()).into_opds_entry()
+		})
+		.collect::<Vec<OpdsEntry>>();
+
+	let feed = OPDSFeedBuilder::new(req.api_key()).paginated(OPDSFeedBuilderParams {
+		id: "storyArcs".to_string(),
+		title: "Story Arcs".to_string(),
+		entries,
+		href_postfix: "story-arcs".to_string(),
+		page_params: Some(OPDSFeedBuilderPageParams {
+			page: page.into(),
+			count,
+		}),
+		search,
+	})?;
+
+	Ok(Xml(feed.build()?))
+}
+
+/// A handler for GET /opds/v1.2/story-arcs/:id, which lists the books of the story arc
+/// in reading order
+async fn get_story_arc_by_id(
+	Path(OPDSURLParams {
+		params: OPDSIDURLParams { id },
+		..
+	}): Path<OPDSURLParams<OPDSIDURLParams>>,
+	State(ctx): State<AppState>,
+	pagination: Query<PageQuery>,
+	Extension(req): Extension<RequestContext>,
+) -> APIResult<Xml> {
+	let page = pagination.page.unwrap_or(0);
+	let (skip, take) = pagination_bounds(page.into(), 20);
+	let user = req.user();
+
+	let story_arc = ctx
+		.db
+		.story_arc()
+		.find_first(vec![
+			story_arc::id::equals(id.clone()),
+			story_arc_visible_to_user_filter(user),
+		])
+		.exec()
+		.await?
+		.ok_or(APIError::NotFound(format!("Story arc {id} not found")))?;
+
+	let members = fetch_story_arc_members(&ctx, id, user).await?;
+	let count = members.len() as i64;
+	let entries = members
+		.into_iter()
+		.skip(skip as usize)
+		.take(take as usize)
+		.filter_map(|member| member.media().ok().cloned())
+		.map(|m| OPDSEntryBuilder::<media::Data>::new(m, req.api_key()).into_opds_entry())
+		.collect();
+
+	let feed = OPDSFeedBuilder::new(req.api_key()).paginated(OPDSFeedBuilderParams {
+		id: story_arc.id.clone(),
+		title: story_arc.name.clone(),
+		entries,
+		href_postfix: format!("story-arcs/{}", &story_arc.id),
+		page_params: Some(OPDSFeedBuilderPageParams {
+			page: page.into(),
+			count,
+		}),
+		search: None,
+	})?;
+
+	Ok(Xml(feed.build()?))
+}
+
+/// A handler for GET /opds/v1.2/collections, accepts `page` and `search` URL params
+async fn get_collections(
+	State(ctx): State<AppState>,
+	Query(pagination): Query<PageQuery>,
+	Query(OPDSSearchQuery { search }): Query<OPDSSearchQuery>,
+	Extension(req): Extension<RequestContext>,
+) -> APIResult<Xml> {
+	let db = &ctx.db;
+
+	let page = pagination.page.unwrap_or(0);
+	let (skip, take) = pagination_bounds(page.into(), 20);
+
+	let where_params = chain_optional_iter(
+		collection_visible_to_user_filter(req.user()),
+		[search
+			.as_ref()
+			.map(|q| collection::name::contains(q.clone()))],
+	);
+
+	let (collections, count) = db
+		._transaction()
+		.run(|client| async move {
+			let collections = client
+				.collection()
+				.find_many(where_params.clone())
+				.skip(skip)
+				.take(take)
+				.order_by(collection::name::order(Direction::Asc))
+				.exec()
+				.await?;
+
+			client
+				.collection()
+				.count(where_params)
+				.exec()
+				.await
+				.map(|count| (collections, count))
+		})
+		.await?;
+
+	let entries = collections
+		.into_iter()
+		.map(|collection| {
+			OPDSEntryBuilder::<collection::Data>::new(collection, req.api_key())
+				.into_opds_entry()
+		})
+		.collect::<Vec<OpdsEntry>>();
+
+	let feed = OPDSFeedBuilder::new(req.api_key()).paginated(OPDSFeedBuilderParams {
+		id: "collections".to_string(),
+		title: "Collections".to_string(),
+		entries,
+		href_postfix: "collections".to_string(),
+		page_params: Some(OPDSFeedBuilderPageParams {
+			page: page.into(),
+			count,
+		}),
+		search,
+	})?;
+
+	Ok(Xml(feed.build()?))
+}
+
+/// A handler for GET /opds/v1.2/collections/:id, which lists the items of the collection in
+/// order. Series are navigation entries and books are acquisition entries
+async fn get_collection_by_id(
+	Path(OPDSURLParams {
+		params: OPDSIDURLParams { id },
+		..
+	}): Path<OPDSURLParams<OPDSIDURLParams>>,
+	State(ctx): State<AppState>,
+	pagination: Query<PageQuery>,
+	Extension(req): Extension<RequestContext>,
+) -> APIResult<Xml> {
+	let page = pagination.page.unwrap_or(0);
+	let (skip, take) = pagination_bounds(page.into(), 20);
+	let user = req.user();
+
+	let collection = ctx
+		.db
+		.collection()
+		.find_first(
+			[collection::id::equals(id.clone())]
+				.into_iter()
+				.chain(collection_visible_to_user_filter(user))
+				.collect(),
+		)
+		.exec()
+		.await?
+		.ok_or(APIError::NotFound(format!("Collection {id} not found")))?;
+
+	let items = fetch_collection_items(&ctx, id, user).await?;
+	let count = items.len() as i64;
+	let entries = items
+		.into_iter()
+		.skip(skip as usize)
+		.take(take as usize)
+		.filter_map(|item| {
+			if let Ok(Some(series)) = item.series() {
+				Some(
+					OPDSEntryBuilder::<series::Data>::new(
+						series.to_owned(),
+						req.api_key(),
+					)
+					.into_opds_entry(),
+				)
+			} else if let Ok(Some(book)) = item.media() {
+				Some(
+					OPDSEntryBuilder::<media::Data>::new(book.to_owned(), req.api_key())
+						.into_opds_entry(),
+				)
+			} else {
+				None
+			}
+		})
+		.collect();
+
+	let feed = OPDSFeedBuilder::new(req.api_key()).paginated(OPDSFeedBuilderParams {
+		id: collection.id.clone(),
+		title: collection.name.clone(),
+		entries,
+		href_postfix: format!("collections/{}", &collection.id),
+		page_params: Some(OPDSFeedBuilderPageParams {
+			page: page.into(),
+			count,
+		}),
+		search: None,
+	})?;
+
+	Ok(Xml(feed.build()?))
+}
+
+/// A handler for GET /opds/v1.2/authors, accepts `page` and `search` URL params. Authors are
+/// the people credited as the writer of at least one book
+async fn get_authors(
+	State(ctx): State<AppState>,
+	Query(pagination): Query<PageQuery>,
+	Query(OPDSSearchQuery { search }): Query<OPDSSearchQuery>,
+	Extension(req): Extension<RequestContext>,
+) -> APIResult<Xml> {
+	let db = &ctx.db;
+
+	let page = pagination.page.unwrap_or(0);
+	let (skip, take) = pagination_bounds(page.into(), 20);
+
+	let where_params = chain_optional_iter(
+		[person_visible_to_user_filter(
+			req.user(),
+			Some(CreatorRole::Writer),
+		)],
+		[search.as_ref().map(|q| {
+			or![
+				person::name::contains(q.clone()),
+				person::aliases::some(vec![person_alias::name::contains(q.clone())]),
+			]
+		})],
+	);
+
+	let (people, count) = db
+		._transaction()
+		.run(|client| async move {
+			let people = client
+				.person()
+				.find_many(where_params.clone())
+				.skip(skip)
+				.take(take)
+				.order_by(person::name::order(Direction::Asc))
+				.exec()
+				.await?;
+
+			client
+				.person()
+				.count(where_params)
+				.exec()
+				.await
+				.map(|count| (people, count))
+		})
+		.await?;
+
+	let entries = people
+		.into_iter()
+		.map(|person| {
+			OPDSEntryBuilder::<person::Data>::new(person, req.api_key()).into_opds_entry()
+		})
+		.collect::<Vec<OpdsEntry>>();
+
+	let feed = OPDSFeedBuilder::new(req.api_key()).paginated(OPDSFeedBuilderParams {
+		id: "authors".to_string(),
+		title: "Authors".to_string(),
+		entries,
+		href_postfix: "authors".to_string(),
+		page_params: Some(OPDSFeedBuilderPageParams {
+			page: page.into(),
+			count,
+		}),
+		search,
+	})?;
+
+	Ok(Xml(feed.build()?))
+}
+
+/// A handler for GET /opds/v1.2/authors/:id, which lists the books written by the author
+async fn get_author_by_id(
+	Path(OPDSURLParams {
+		params: OPDSIDURLParams { id },
+		..
+	}): Path<OPDSURLParams<OPDSIDURLParams>>,
+	State(ctx): State<AppState>,
+	pagination: Query<PageQuery>,
+	Extension(req): Extension<RequestContext>,
+) -> APIResult<Xml> {
+	let page = pagination.page.unwrap_or(0);
+	let (skip, take) = pagination_bounds(page.into(), 20);
+	let user = req.user();
+
+	let author = ctx
+		.db
+		.person()
+		.find_first(vec![
+			person::id::equals(id.clone()),
+			person_visible_to_user_filter(user, Some(CreatorRole::Writer)),
+		])
+		.exec()
+		.await?
+		.ok_or(APIError::NotFound(format!("Author {id} not found")))?;
+
+	let where_params = person_media_filter(user, id, Some(CreatorRole::Writer));
+	let user_id = user.id.clone();
+	let (books, count) = ctx
+		.db
+		._transaction()
+		.run(|client| async move {
+			let books = client
+				.media()
+				.find_many(where_params.clone())
+				.with(media::active_user_reading_sessions::fetch(vec![
+					active_reading_session::user_id::equals(user_id),
+				]))
+				.skip(skip)
+				.take(take)
+				.order_by(media::name::order(Direction::Asc))
+				.exec()
+				.await?;
+
+			client
+				.media()
+				.count(where_params)
+				.exec()
+				.await
+				.map(|count| (books, count))
+		})
+		.await?;
+
+	let entries = books
+		.into_iter()
+		.map(|m| OPDSEntryBuilder::<media::Data>::new(m, req.api_key()).into_opds_entry())
+		.collect();
+
+	let feed = OPDSFeedBuilder::new(req.api_key()).paginated(OPDSFeedBuilderParams {
+		id: author.id.clone(),
+		title: author.name.clone(),
+		entries,
+		href_postfix: format!("authors/{}", &author.id),
+		page_params: Some(OPDSFeedBuilderPageParams {
+			page: page.into(),
+			count,
+		}),
+		search: None,
+	})?;
+
+	Ok(Xml(feed.build()?))
+}
+
 // TODO: support something like `STRICT_OPDS` to enforce OPDS compliance conditionally
 fn handle_opds_image_response(
 	content_type: ContentType,
@@ -807,5 +1261,5 @@ async fn download_book(
 		.await?
 		.ok_or(APIError::NotFound(String::from("Book not found")))?;
 
-	Ok(NamedFile::open(book.path.clone()).await?)
+	Ok(NamedFile::open_from_storage(book.path.clone(), &ctx.config).await?)
 }
@@ -17,7 +17,7 @@ use stump_core::{
 				apply_media_age_restriction,
 				apply_media_library_not_hidden_for_user_filter,
 			},
-			User, UserPermission,
+			CreatorRole, User, UserPermission,
 		},
 		query::pagination::PageQuery,
 	},
@@ -37,7 +37,7 @@ use stump_core::{
 		metadata::{OPDSMetadata, OPDSMetadataBuilder, OPDSPaginationMetadataBuilder},
 		publication::OPDSPublication,
 	},
-	prisma::{library, media, series},
+	prisma::{collection, library, media, media_credit, person, series, story_arc},
 	Ctx,
 };
 
@@ -54,6 +54,11 @@ use crate::{
 			apply_in_progress_filter_for_user, apply_media_restrictions_for_user,
 			apply_series_restrictions_for_user, library_not_hidden_from_user_filter,
 		},
+		api::v1::collection::{
+			collection_visible_to_user_filter, fetch_collection_items,
+		},
+		api::v1::person::person_visible_to_user_filter,
+		api::v1::story_arc::{fetch_story_arc_members, story_arc_visible_to_user_filter},
 		relative_favicon_path,
 	},
 	utils::http::{ImageResponse, NamedFile},
@@ -86,6 +91,24 @@ pub(crate) fn mount(app_state: AppState) -> Router<AppState> {
 						.route("/", get(browse_series))
 						.nest("/:id", Router::new().route("/", get(browse_series_by_id))),
 				)
+				.nest(
+					"/story-arcs",
+					Router::new()
+						.route("/", get(browse_story_arcs))
+						.route("/:id", get(browse_story_arc_by_id)),
+				)
+				.nest(
+					"/authors",
+					Router::new()
+						.route("/", get(browse_authors))
+						.route("/:id", get(browse_author_by_id)),
+				)
+				.nest(
+					"/collections",
+					Router::new()
+						.route("/", get(browse_collections))
+						.route("/:id", get(browse_collection_by_id)),
+				)
 				// TODO(OPDS-V2): Support smart list feeds
 				// .nest("/smart-lists", Router::new())
 				.nest(
@@ -243,15 +266,44 @@ async fn catalog(
 					.rel(OPDSLinkRel::Start.item())
 					.build()?.as_link(),
 			]))
-			.navigation(vec![OPDSNavigationLinkBuilder::default()
-				.title("Libraries".to_string())
-				.base_link(
-					OPDSBaseLinkBuilder::default()
-						.href(link_finalizer.format_link("/opds/v2.0/libraries"))
-						.rel(OPDSLinkRel::Subsection.item())
-						.build()?,
-				)
-				.build()?])
+			.navigation(vec![
+				OPDSNavigationLinkBuilder::default()
+					.title("Libraries".to_string())
+					.base_link(
+						OPDSBaseLinkBuilder::default()
+							.href(link_finalizer.format_link("/opds/v2.0/libraries"))
+							.rel(OPDSLinkRel::Subsection.item())
+							.build()?,
+					)
+					.build()?,
+				OPDSNavigationLinkBuilder::default()
+					.title("Story Arcs".to_string())
+					.base_link(
+						OPDSBaseLinkBuilder::default()
+							.href(link_finalizer.format_link("/opds/v2.0/story-arcs"))
+							.rel(OPDSLinkRel::Subsection.item())
+							.build()?,
+					)
+					.build()?,
+				OPDSNavigationLinkBuilder::default()
+					.title("Authors".to_string())
+					.base_link(
+						OPDSBaseLinkBuilder::default()
+							.href(link_finalizer.format_link("/opds/v2.0/authors"))
+							.rel(OPDSLinkRel::Subsection.item())
+							.build()?,
+					)
+					.build()?,
+				OPDSNavigationLinkBuilder::default()
+					.title("Collections".to_string())
+					.base_link(
+						OPDSBaseLinkBuilder::default()
+							.href(link_finalizer.format_link("/opds/v2.0/collections"))
+							.rel(OPDSLinkRel::Subsection.item())
+							.build()?,
+					)
+					.build()?,
+			])
 			.groups(vec![library_group, latest_books_group])
 			.build()?,
 	))
@@ -558,6 +610,29 @@ async fn fetch_books_and_generate_feed(
 	let publications =
 		OPDSPublication::vec_from_books(client, link_finalizer.clone(), books).await?;
 
+	generate_books_feed(
+		link_finalizer,
+		None,
+		publications,
+		books_count,
+		pagination,
+		title,
+		base_url,
+	)
+}
+
+/// A helper function to generate a paginated OPDS feed for a page of publications, with
+/// optional navigation links alongside them. This is not a route
+fn generate_books_feed(
+	link_finalizer: OPDSLinkFinalizer,
+	navigation: Option<Vec<OPDSNavigationLink>>,
+	publications: Vec<OPDSPublication>,
+	books_count: i64,
+	pagination: PageQuery,
+	title: &str,
+	base_url: &str,
+) -> APIResult<Json<OPDSFeed>> {
+	let (_, take) = pagination.get_skip_take();
 	let next_page = pagination.get_next_page();
 	let previous_link = if let Some(page) = pagination.page {
 		Some(
@@ -611,6 +686,7 @@ async fn fetch_books_and_generate_feed(
 					.build()?,
 			)
 			.links(links)
+			.navigation(navigation)
 			.publications(publications)
 			.build()?,
 	))
@@ -754,6 +830,377 @@ async fn browse_series_by_id(
 	.await
 }
 
+#[tracing::instrument(skip(ctx))]
+async fn browse_story_arcs(
+	State(ctx): State<AppState>,
+	HostExtractor(host): HostExtractor,
+	pagination: Query<PageQuery>,
+	Extension(req): Extension<RequestContext>,
+) -> APIResult<Json<OPDSFeed>> {
+	let client = &ctx.db;
+	let user = req.user();
+
+	let (skip, take) = pagination.get_skip_take();
+	let story_arc_conditions = vec![story_arc_visible_to_user_filter(user)];
+	let story_arcs = client
+		.story_arc()
+		.find_many(story_arc_conditions.clone())
+		.take(take)
+		.skip(skip)
+		.order_by(story_arc::name::order(Direction::Asc))
+		.exec()
+		.await?;
+	let story_arc_count = client
+		.story_arc()
+		.count(story_arc_conditions)
+		.exec()
+		.await?;
+
+	let current_page = i64::from(pagination.zero_indexed_page() + 1);
+	let link_finalizer = OPDSLinkFinalizer::from(host);
+
+	Ok(Json(
+		OPDSFeedBuilder::default()
+			.metadata(
+				OPDSMetadataBuilder::default()
+					.title("Browse Story Arcs".to_string())
+					.pagination(Some(
+						OPDSPaginationMetadataBuilder::default()
+							.number_of_items(story_arc_count)
+							.items_per_page(take)
+							.current_page(current_page)
+							.build()?,
+					))
+					.build()?,
+			)
+			.links(link_finalizer.finalize_all(vec![
+				OPDSLink::Link(
+					OPDSBaseLinkBuilder::default()
+						.href("/opds/v2.0/story-arcs".to_string())
+						.rel(OPDSLinkRel::SelfLink.item())
+						.build()?,
+				),
+				OPDSLink::Link(
+					OPDSBaseLinkBuilder::default()
+						.href("/opds/v2.0/catalog".to_string())
+						.rel(OPDSLinkRel::Start.item())
+						.build()?,
+				),
+			]))
+			.navigation(
+				story_arcs
+					.into_iter()
+					.map(OPDSNavigationLink::from)
+					.map(|link| link.finalize(&link_finalizer))
+					.collect::<Vec<OPDSNavigationLink>>(),
+			)
+			.build()?,
+	))
+}
+
+/// A route handler which returns a feed of the books in a story arc, in reading order
+#[tracing::instrument(skip(ctx))]
+async fn browse_story_arc_by_id(
+	State(ctx): State<AppState>,
+	HostExtractor(host): HostExtractor,
+	pagination: Query<PageQuery>,
+	Path(id): Path<String>,
+	Extension(req): Extension<RequestContext>,
+) -> APIResult<Json<OPDSFeed>> {
+	let client = &ctx.db;
+	let user = req.user();
+	let link_finalizer = OPDSLinkFinalizer::from(host);
+
+	let story_arc = client
+		.story_arc()
+		.find_first(vec![
+			story_arc::id::equals(id.clone()),
+			story_arc_visible_to_user_filter(user),
+		])
+		.exec()
+		.await?
+		.ok_or(APIError::NotFound(String::from("Story arc not found")))?;
+
+	let (skip, take) = pagination.get_skip_take();
+	let members = fetch_story_arc_members(&ctx, id.clone(), user).await?;
+	let books_count = members.len() as i64;
+	let book_ids = members
+		.into_iter()
+		.skip(skip as usize)
+		.take(take as usize)
+		.map(|member| member.media_id)
+		.collect::<Vec<_>>();
+
+	// The books are fetched again to include what publications need, and then put back in
+	// the order of the arc
+	let mut books = client
+		.media()
+		.find_many(vec![media::id::in_vec(book_ids.clone())])
+		.include(books_as_publications::include())
+		.exec()
+		.await?;
+	books.sort_by_key(|book| book_ids.iter().position(|book_id| book_id == &book.id));
+	let publications =
+		OPDSPublication::vec_from_books(client, link_finalizer.clone(), books).await?;
+
+	generate_books_feed(
+		link_finalizer,
+		None,
+		publications,
+		books_count,
+		pagination.0,
+		&story_arc.name,
+		&format!("/opds/v2.0/story-arcs/{id}"),
+	)
+}
+
+/// A route handler which returns a feed of the collections visible to the user
+#[tracing::instrument(skip(ctx))]
+async fn browse_collections(
+	State(ctx): State<AppState>,
+	HostExtractor(host): HostExtractor,
+	pagination: Query<PageQuery>,
+	Extension(req): Extension<RequestContext>,
+) -> APIResult<Json<OPDSFeed>> {
+	let client = &ctx.db;
+	let user = req.user();
+
+	let (skip, take) = pagination.get_skip_take();
+	let collection_conditions = collection_visible_to_user_filter(user);
+	let collections = client
+		.collection()
+		.find_many(collection_conditions.clone())
+		.take(take)
+		.skip(skip)
+		.order_by(collection::name::order(Direction::Asc))
+		.exec()
+		.await?;
+	let collection_count = client
+		.collection()
+		.count(collection_conditions)
+		.exec()
+		.await?;
+
+	let current_page = i64::from(pagination.zero_indexed_page() + 1);
+	let link_finalizer = OPDSLinkFinalizer::from(host);
+
+	Ok(Json(
+		OPDSFeedBuilder::default()
+			.metadata(
+				OPDSMetadataBuilder::default()
+					.title("Browse Collections".to_string())
+					.pagination(Some(
+						OPDSPaginationMetadataBuilder::default()
+							.number_of_items(collection_count)
+							.items_per_page(take)
+							.current_page(current_page)
+							.build()?,
+					))
+					.build()?,
+			)
+			.links(link_finalizer.finalize_all(vec![
+				OPDSLink::Link(
+					OPDSBaseLinkBuilder::default()
+						.href("/opds/v2.0/collections".to_string())
+						.rel(OPDSLinkRel::SelfLink.item())
+						.build()?,
+				),
+				OPDSLink::Link(
+					OPDSBaseLinkBuilder::default()
+						.href("/opds/v2.0/catalog".to_string())
+						.rel(OPDSLinkRel::Start.item())
+						.build()?,
+				),
+			]))
+			.navigation(
+				collections
+					.into_iter()
+					.map(OPDSNavigationLink::from)
+					.map(|link| link.finalize(&link_finalizer))
+					.collect::<Vec<OPDSNavigationLink>>(),
+			)
+			.build()?,
+	))
+}
+
+/// A route handler which returns a feed of the items in a collection, in order. Series are
+/// navigation links and books are publications
+#[tracing::instrument(skip(ctx))]
+async fn browse_collection_by_id(
+	State(ctx): State<AppState>,
+	HostExtractor(host): HostExtractor,
+	pagination: Query<PageQuery>,
+	Path(id): Path<String>,
+	Extension(req): Extension<RequestContext>,
+) -> APIResult<Json<OPDSFeed>> {
+	let client = &ctx.db;
+	let user = req.user();
+	let link_finalizer = OPDSLinkFinalizer::from(host);
+
+	let collection = client
+		.collection()
+		.find_first(
+			[collection::id::equals(id.clone())]
+				.into_iter()
+				.chain(collection_visible_to_user_filter(user))
+				.collect(),
+		)
+		.exec()
+		.await?
+		.ok_or(APIError::NotFound(String::from("Collection not found")))?;
+
+	let (skip, take) = pagination.get_skip_take();
+	let items = fetch_collection_items(&ctx, id.clone(), user).await?;
+	let items_count = items.len() as i64;
+	let (series_links, book_ids) = items
+		.into_iter()
+		.skip(skip as usize)
+		.take(take as usize)
+		.fold(
+			(Vec::new(), Vec::new()),
+			|(mut links, mut book_ids), item| {
+				if let Ok(Some(series)) = item.series() {
+					links.push(
+						OPDSNavigationLink::from(series.to_owned())
+							.finalize(&link_finalizer),
+					);
+				} else if let Some(media_id) = item.media_id.clone() {
+					book_ids.push(media_id);
+				}
+				(links, book_ids)
+			},
+		);
+
+	// The books are fetched again to include what publications need, and then put back in
+	// the order of the collection
+	let mut books = client
+		.media()
+		.find_many(vec![media::id::in_vec(book_ids.clone())])
+		.include(books_as_publications::include())
+		.exec()
+		.await?;
+	books.sort_by_key(|book| book_ids.iter().position(|book_id| book_id == &book.id));
+	let publications =
+		OPDSPublication::vec_from_books(client, link_finalizer.clone(), books).await?;
+
+	generate_books_feed(
+		link_finalizer,
+		(!series_links.is_empty()).then_some(series_links),
+		publications,
+		items_count,
+		pagination.0,
+		&collection.name,
+		&format!("/opds/v2.0/collections/{id}"),
+	)
+}
+
+/// A route handler which returns a feed of authors, i.e. the people credited as the writer of
+/// at least one book
+#[tracing::instrument(skip(ctx))]
+async fn browse_authors(
+	State(ctx): State<AppState>,
+	HostExtractor(host): HostExtractor,
+	pagination: Query<PageQuery>,
+	Extension(req): Extension<RequestContext>,
+) -> APIResult<Json<OPDSFeed>> {
+	let client = &ctx.db;
+	let user = req.user();
+
+	let (skip, take) = pagination.get_skip_take();
+	let author_conditions = vec![person_visible_to_user_filter(
+		user,
+		Some(CreatorRole::Writer),
+	)];
+	let authors = client
+		.person()
+		.find_many(author_conditions.clone())
+		.take(take)
+		.skip(skip)
+		.order_by(person::name::order(Direction::Asc))
+		.exec()
+		.await?;
+	let author_count = client.person().count(author_conditions).exec().await?;
+
+	let current_page = i64::from(pagination.zero_indexed_page() + 1);
+	let link_finalizer = OPDSLinkFinalizer::from(host);
+
+	Ok(Json(
+		OPDSFeedBuilder::default()
+			.metadata(
+				OPDSMetadataBuilder::default()
+					.title("Browse Authors".to_string())
+					.pagination(Some(
+						OPDSPaginationMetadataBuilder::default()
+							.number_of_items(author_count)
+							.items_per_page(take)
+							.current_page(current_page)
+							.build()?,
+					))
+					.build()?,
+			)
+			.links(link_finalizer.finalize_all(vec![
+				OPDSLink::Link(
+					OPDSBaseLinkBuilder::default()
+						.href("/opds/v2.0/authors".to_string())
+						.rel(OPDSLinkRel::SelfLink.item())
+						.build()?,
+				),
+				OPDSLink::Link(
+					OPDSBaseLinkBuilder::default()
+						.href("/opds/v2.0/catalog".to_string())
+						.rel(OPDSLinkRel::Start.item())
+						.build()?,
+				),
+			]))
+			.navigation(
+				authors
+					.into_iter()
+					.map(OPDSNavigationLink::from)
+					.map(|link| link.finalize(&link_finalizer))
+					.collect::<Vec<OPDSNavigationLink>>(),
+			)
+			.build()?,
+	))
+}
+
+/// A route handler which returns a feed of the books written by an author
+#[tracing::instrument(skip(ctx))]
+async fn browse_author_by_id(
+	State(ctx): State<AppState>,
+	HostExtractor(host): HostExtractor,
+	pagination: Query<PageQuery>,
+	Path(id): Path<String>,
+	Extension(req): Extension<RequestContext>,
+) -> APIResult<Json<OPDSFeed>> {
+	let user = req.user();
+
+	let author = ctx
+		.db
+		.person()
+		.find_first(vec![
+			person::id::equals(id.clone()),
+			person_visible_to_user_filter(user, Some(CreatorRole::Writer)),
+		])
+		.exec()
+		.await?
+		.ok_or(APIError::NotFound(String::from("Author not found")))?;
+
+	fetch_books_and_generate_feed(
+		&ctx,
+		OPDSLinkFinalizer::from(host),
+		user,
+		vec![media::credits::some(vec![
+			media_credit::person_id::equals(id.clone()),
+			media_credit::role::equals(CreatorRole::Writer.to_string()),
+		])],
+		media::name::order(Direction::Asc),
+		pagination.0,
+		&author.name,
+		&format!("/opds/v2.0/authors/{id}"),
+	)
+	.await
+}
+
 /// A route handler which returns a feed of books for a user.
 #[tracing::instrument(skip(ctx))]
 async fn browse_books(
@@ -955,5 +1402,5 @@ async fn download_book(
 		.await?
 		.ok_or(APIError::NotFound(String::from("Book not found")))?;
 
-	Ok(NamedFile::open(book.path.clone()).await?)
+	Ok(NamedFile::open_from_storage(book.path.clone(), &ctx.config).await?)
 }
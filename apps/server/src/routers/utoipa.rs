@@ -7,7 +7,8 @@ use stump_core::db::query::{ordering::*, pagination::*};
 use stump_core::filesystem::{
 	DirectoryListing, DirectoryListingFile, DirectoryListingInput,
 };
-use stump_core::job::JobStatus;
+use stump_core::job::{JobPriority, JobStatus, QueuedJob};
+use stump_core::metadata::{MetadataCandidate, MetadataTransferFormat};
 
 use utoipa::OpenApi;
 use utoipa_swagger_ui::SwaggerUi;
@@ -20,8 +21,9 @@ use crate::middleware::auth::auth_middleware;
 use super::api::{
 	self,
 	v1::{
-		auth::LoginOrRegisterArgs, library::*, media::individual::*, notifier::*,
-		series::*, smart_list::*, user::*, ClaimResponse, StumpVersion,
+		auth::LoginOrRegisterArgs, job::CreateOrUpdateJobSchedule, library::*,
+		media::bulk::*, media::individual::*, notifier::*, person::*, series::*,
+		smart_list::*, user::*, ClaimResponse, StumpVersion,
 	},
 };
 
@@ -42,19 +44,28 @@ use super::api::{
         // TODO: epub here
         api::v1::filesystem::list_directory,
         api::v1::job::get_jobs,
+        api::v1::job::get_job_queue,
+        api::v1::job::get_job_by_id,
         api::v1::job::delete_jobs,
         api::v1::job::delete_job_by_id,
         api::v1::job::cancel_job_by_id,
-        api::v1::job::get_scheduler_config,
-        api::v1::job::update_scheduler_config,
+        api::v1::job::get_job_schedules,
+        api::v1::job::create_job_schedule,
+        api::v1::job::get_job_schedule_by_id,
+        api::v1::job::update_job_schedule,
+        api::v1::job::delete_job_schedule,
         api::v1::library::get_libraries,
         api::v1::library::get_libraries_stats,
+        api::v1::library::preview_filename_patterns,
         api::v1::library::get_library_by_id,
         api::v1::library::get_library_series,
         api::v1::library::get_library_thumbnail_handler,
         api::v1::library::delete_library_thumbnails,
         api::v1::library::generate_library_thumbnails,
         api::v1::library::scan_library,
+        api::v1::library::scan_library_dry_run,
+        api::v1::library::enrich_library_isbn,
+        api::v1::library::export_library_metadata_handler,
         api::v1::library::clean_library,
         api::v1::library::create_library,
         api::v1::library::update_library,
@@ -63,6 +74,7 @@ use super::api::{
         api::v1::log::delete_logs,
         api::v1::media::bulk::get_media,
         api::v1::media::bulk::get_duplicate_media,
+        api::v1::media::bulk::get_similar_media,
         api::v1::media::bulk::get_in_progress_media,
         api::v1::media::bulk::get_recently_added_media,
         api::v1::media::individual::get_media_by_id,
@@ -75,6 +87,12 @@ use super::api::{
         api::v1::media::individual::delete_media_progress,
         api::v1::media::individual::get_is_media_completed,
         api::v1::media::individual::put_media_complete_status,
+        api::v1::media::individual::get_media_metadata_conflicts,
+        api::v1::media::individual::resolve_media_metadata_conflict,
+        api::v1::media::individual::identify_media_metadata,
+        api::v1::media::individual::apply_media_metadata_candidate,
+        api::v1::media::individual::put_media_metadata_locks,
+        api::v1::media::individual::get_media_story_arcs,
         api::v1::metadata::get_metadata_overview,
         api::v1::metadata::get_genres_handler,
         api::v1::metadata::get_writers_handler,
@@ -86,6 +104,9 @@ use super::api::{
         api::v1::metadata::get_publishers_handler,
         api::v1::metadata::get_characters_handler,
         api::v1::metadata::get_teams_handler,
+        api::v1::metadata::get_metadata_providers,
+        api::v1::metadata::get_metadata_provider,
+        api::v1::metadata::update_metadata_provider,
         api::v1::notifier::get_notifiers,
         api::v1::notifier::get_notifier_by_id,
         api::v1::notifier::create_notifier,
@@ -117,7 +138,28 @@ use super::api::{
         api::v1::smart_list::delete_smart_list_view,
         api::v1::tag::get_tags,
         api::v1::tag::create_tags,
+        api::v1::story_arc::get_story_arcs,
+        api::v1::story_arc::get_story_arc_by_id,
+        api::v1::story_arc::get_story_arc_media,
+        api::v1::story_arc::get_next_in_story_arc,
+        api::v1::person::get_people,
+        api::v1::person::get_person_by_id,
+        api::v1::person::get_person_media,
+        api::v1::person::merge_into_person,
+        api::v1::person::sync_people_credits,
+        api::v1::collection::get_collections,
+        api::v1::collection::create_collection,
+        api::v1::collection::get_collection_by_id,
+        api::v1::collection::update_collection,
+        api::v1::collection::delete_collection,
+        api::v1::collection::get_collection_items,
+        api::v1::collection::get_collection_thumbnail,
         api::v1::series::get_next_in_series,
+        api::v1::series::scan_series_dry_run,
+        api::v1::series::match_series_comicvine,
+        api::v1::series::confirm_series_comicvine,
+        api::v1::series::put_series_metadata,
+        api::v1::series::put_series_metadata_locks,
         api::v1::user::get_users,
         api::v1::user::get_user_login_activity,
         api::v1::user::delete_user_login_activity,
@@ -133,22 +175,34 @@ use super::api::{
     components(
         schemas(
             Library, LibraryConfig, Media, ReadingList, ActiveReadingSession, FinishedReadingSession, Series, Tag, User,
-            UserPreferences, LibraryPattern, LibraryScanMode, LogLevel, ClaimResponse,
+            UserPreferences, LibraryPattern, LibraryWatchMode, MergeStrategy, LibraryScanMode, LogLevel, ClaimResponse,
             StumpVersion, FileStatus, PageableDirectoryListing, DirectoryListing,
             DirectoryListingFile, CursorInfo, PageInfo, PageableLibraries,
             PageableMedia, PageableSeries, LoginOrRegisterArgs, DirectoryListingInput,
             PageQuery, FilterableLibraryQuery, PaginationQuery, QueryOrder, LibraryFilter,
-            Direction, CreateLibrary, UpdateLibrary, APIError, MediaFilter, SeriesFilter,
+            Direction, CreateLibrary, UpdateLibrary, FilenamePatternKind, FilenamePattern, FilenamePatterns,
+            FilenameMetadata, PreviewFilenamePatterns, FilenamePatternResult, FilenamePatternPreview, ScanDryRunResponse, APIError, MediaFilter, SeriesFilter,
             FilterableMediaQuery, FilterableSeriesQuery, LibraryStats,
             JobStatus, SeriesQueryRelation, CreateReadingList, UpdateUserPreferences, UpdateUser,
-            CreateTags, CleanLibraryResponse, MediaIsComplete, SeriesIsComplete, PutMediaCompletionStatus,
+            CreateTags, CleanLibraryResponse, MediaIsComplete, MediaMetadataConflict, SimilarMediaQuery,
+            SimilarMedia, SimilarMediaCluster,
+            MetadataConflictResolution, ResolveMetadataConflict, SeriesIsComplete, PutMediaCompletionStatus,
             SmartList, SmartListMeta, SmartListItems, SmartListView, CreateOrUpdateSmartList,
             CreateOrUpdateSmartListView, SmartListItemGrouping, SmartFilter, FilterJoin, EntityVisibility,
             SmartListViewConfig, ReactTableColumnSort, ReactTableGlobalSort,
             MediaSmartFilter, MediaMetadataSmartFilter, SeriesSmartFilter, SeriesMetadataSmartFilter,
-            LibrarySmartFilter, Notifier, CreateOrUpdateNotifier, PatchNotifier, LibraryBaseFilter, LibraryRelationFilter,
+            LibrarySmartFilter, StoryArcSmartFilter, StoryArcMemberSmartFilter, Notifier, CreateOrUpdateNotifier, PatchNotifier, LibraryBaseFilter, LibraryRelationFilter,
             MediaBaseFilter, MediaRelationFilter, SeriesBaseFilter, SeriesRelationFilter, NotifierConfig, NotifierType,
-            ReadingListItem, ReadingListVisibility, SeriesMetadataFilter
+            ReadingListItem, ReadingListVisibility, SeriesMetadataFilter, JobSchedule, ScheduledJobKind,
+            CreateOrUpdateJobSchedule, QueuedJob, JobPriority, NotifierJobSubscription,
+            MetadataProviderKind, MetadataProviderConfig, MetadataProviderConfigInput,
+            ComicVineMatchRequest, ConfirmComicVineMatch, ComicVineMatchResponse,
+            IsbnEnrichmentRequest, IsbnEnrichmentResponse, MetadataProviderPriority,
+            MetadataCandidate, ApplyMetadataCandidate, MetadataLocks, MetadataTransferFormat,
+            MetadataExportQuery, MetadataImportResponse, StoryArc, StoryArcMember,
+            Person, PersonRoleCount, MediaCredit, CreatorRole, PeopleQuery, PersonMediaQuery,
+            MergePeople, SyncPeopleCreditsResponse, Collection, CollectionItem,
+            CollectionItemInput, CreateOrUpdateCollection
         )
     ),
     tags(
@@ -162,6 +216,9 @@ use super::api::{
         (name = "media", description = "Media API"),
         (name = "series", description = "Series API"),
         (name = "tag", description = "Tag API"),
+        (name = "story-arc", description = "Story Arc API"),
+        (name = "person", description = "Person API"),
+        (name = "collection", description = "Collection API"),
         (name = "reading-list", description = "Reading List API"),
         (name = "user", description = "User API"),
         (name = "opds", description = "OPDS API"),
@@ -7,8 +7,11 @@ use std::{
 	io,
 	path::{Path, PathBuf},
 };
-use stump_core::filesystem::ContentType;
-use tokio::fs::File;
+use stump_core::{
+	config::StumpConfig,
+	filesystem::{storage::resolve_local_path, ContentType, FileError},
+};
+use tokio::{fs::File, task::spawn_blocking};
 use tokio_util::io::ReaderStream;
 use tracing::error;
 
@@ -162,6 +165,25 @@ impl NamedFile {
 			file,
 		})
 	}
+
+	/// Opens a file from whichever storage backend holds it. Files in object storage are
+	/// served from their local copy in the cache directory, but keep their original name.
+	pub async fn open_from_storage<P: AsRef<Path>>(
+		path: P,
+		config: &StumpConfig,
+	) -> Result<Self, FileError> {
+		let path_buf = path.as_ref().to_path_buf();
+		let local_path = spawn_blocking({
+			let path = path_buf.clone();
+			let config = config.clone();
+			move || resolve_local_path(&path, &config)
+		})
+		.await
+		.map_err(|e| FileError::UnknownError(e.to_string()))??;
+		let file = File::open(local_path).await?;
+
+		Ok(Self { path_buf, file })
+	}
 }
 
 impl IntoResponse for NamedFile {
@@ -189,6 +211,28 @@ impl IntoResponse for NamedFile {
 	}
 }
 
+/// [`AttachmentResponse`] is used for returning a buffer which was generated on the fly as a
+/// download, e.g. an export. Unlike [`NamedFile`], the data doesn't exist on disk, so the
+/// file name and content type are provided by the caller.
+pub struct AttachmentResponse {
+	pub file_name: String,
+	pub content_type: String,
+	pub data: Vec<u8>,
+}
+
+impl IntoResponse for AttachmentResponse {
+	fn into_response(self) -> Response {
+		Response::builder()
+			.header(header::CONTENT_TYPE, self.content_type)
+			.header(
+				header::CONTENT_DISPOSITION,
+				format!("attachment; filename=\"{}\"", self.file_name),
+			)
+			.body(Body::from(self.data))
+			.unwrap_or_else(|e| unexpected_error(e).into_response())
+	}
+}
+
 #[cfg(test)]
 mod tests {
 	use super::*;
@@ -262,4 +306,25 @@ mod tests {
 			))
 		);
 	}
+
+	#[test]
+	fn test_attachment_response() {
+		let response = AttachmentResponse {
+			file_name: "metadata.csv".to_string(),
+			content_type: "text/csv".to_string(),
+			data: b"kind,id,path".to_vec(),
+		};
+		let axum_response = response.into_response();
+
+		assert_eq!(
+			axum_response.headers().get(header::CONTENT_TYPE),
+			Some(&HeaderValue::from_static("text/csv"))
+		);
+		assert_eq!(
+			axum_response.headers().get(header::CONTENT_DISPOSITION),
+			Some(&HeaderValue::from_static(
+				"attachment; filename=\"metadata.csv\""
+			))
+		);
+	}
 }
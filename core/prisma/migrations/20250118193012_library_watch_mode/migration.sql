@@ -0,0 +1,2 @@
+-- AlterTable
+ALTER TABLE "library_configs" ADD COLUMN "watch_mode" TEXT NOT NULL DEFAULT 'DISABLED';
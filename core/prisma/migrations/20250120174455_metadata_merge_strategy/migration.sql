@@ -0,0 +1,16 @@
+-- AlterTable
+ALTER TABLE "library_configs" ADD COLUMN "merge_strategy" TEXT NOT NULL DEFAULT 'REPLACE';
+
+-- CreateTable
+CREATE TABLE "media_metadata_conflicts" (
+    "id" TEXT NOT NULL PRIMARY KEY,
+    "field" TEXT NOT NULL,
+    "current_value" TEXT,
+    "incoming_value" TEXT,
+    "created_at" DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    "media_id" TEXT NOT NULL,
+    CONSTRAINT "media_metadata_conflicts_media_id_fkey" FOREIGN KEY ("media_id") REFERENCES "media" ("id") ON DELETE CASCADE ON UPDATE CASCADE
+);
+
+-- CreateIndex
+CREATE UNIQUE INDEX "media_metadata_conflicts_media_id_field_key" ON "media_metadata_conflicts"("media_id", "field");
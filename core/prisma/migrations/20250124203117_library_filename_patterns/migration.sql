@@ -0,0 +1,2 @@
+-- AlterTable
+ALTER TABLE "library_configs" ADD COLUMN "filename_patterns" BLOB;
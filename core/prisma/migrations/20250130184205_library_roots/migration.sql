@@ -0,0 +1,11 @@
+-- CreateTable
+CREATE TABLE "library_roots" (
+    "id" TEXT NOT NULL PRIMARY KEY,
+    "path" TEXT NOT NULL,
+    "created_at" DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    "library_id" TEXT NOT NULL,
+    CONSTRAINT "library_roots_library_id_fkey" FOREIGN KEY ("library_id") REFERENCES "libraries" ("id") ON DELETE CASCADE ON UPDATE CASCADE
+);
+
+-- CreateIndex
+CREATE UNIQUE INDEX "library_roots_path_key" ON "library_roots"("path");
@@ -0,0 +1,84 @@
+-- CreateTable
+CREATE TABLE "job_schedules" (
+    "id" TEXT NOT NULL PRIMARY KEY,
+    "name" TEXT NOT NULL,
+    "cron_expression" TEXT NOT NULL,
+    "job_kind" TEXT NOT NULL,
+    "enabled" BOOLEAN NOT NULL DEFAULT true,
+    "last_run_at" DATETIME,
+    "next_run_at" DATETIME,
+    "created_at" DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    "updated_at" DATETIME NOT NULL
+);
+
+-- CreateTable
+CREATE TABLE "_JobScheduleToLibrary" (
+    "A" TEXT NOT NULL,
+    "B" TEXT NOT NULL,
+    CONSTRAINT "_JobScheduleToLibrary_A_fkey" FOREIGN KEY ("A") REFERENCES "job_schedules" ("id") ON DELETE CASCADE ON UPDATE CASCADE,
+    CONSTRAINT "_JobScheduleToLibrary_B_fkey" FOREIGN KEY ("B") REFERENCES "libraries" ("id") ON DELETE CASCADE ON UPDATE CASCADE
+);
+
+-- Migrate the existing interval-based scan schedule, if any, to an equivalent cron schedule
+INSERT INTO "job_schedules" ("id", "name", "cron_expression", "job_kind", "enabled", "updated_at")
+SELECT
+    "c"."id",
+    'Scheduled library scan',
+    CASE
+        WHEN "c"."interval_secs" < 3600 THEN '*/' || MAX("c"."interval_secs" / 60, 1) || ' * * * *'
+        WHEN "c"."interval_secs" < 86400 THEN '0 */' || ("c"."interval_secs" / 3600) || ' * * *'
+        WHEN "c"."interval_secs" < 172800 THEN '0 0 * * *'
+        ELSE '0 0 */' || MIN("c"."interval_secs" / 86400, 31) || ' * *'
+    END,
+    'SCAN',
+    true,
+    CURRENT_TIMESTAMP
+FROM "job_schedule_configs" "c"
+INNER JOIN "server_config" "s" ON "s"."job_schedule_config_id" = "c"."id";
+
+-- Previously, libraries were excluded from the schedule. Now they are targeted explicitly, so only
+-- the libraries which were not excluded are carried over (and none when nothing was excluded)
+INSERT INTO "_JobScheduleToLibrary" ("A", "B")
+SELECT "j"."id", "l"."id"
+FROM "job_schedules" "j"
+INNER JOIN "libraries" "l" ON "l"."job_schedule_config_id" IS NULL OR "l"."job_schedule_config_id" != "j"."id"
+WHERE EXISTS (SELECT 1 FROM "libraries" "e" WHERE "e"."job_schedule_config_id" = "j"."id");
+
+-- RedefineTables
+PRAGMA foreign_keys=OFF;
+CREATE TABLE "new_libraries" (
+    "id" TEXT NOT NULL PRIMARY KEY,
+    "name" TEXT NOT NULL,
+    "description" TEXT,
+    "path" TEXT NOT NULL,
+    "status" TEXT NOT NULL DEFAULT 'READY',
+    "updated_at" DATETIME NOT NULL,
+    "created_at" DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    "emoji" TEXT,
+    "config_id" TEXT NOT NULL,
+    CONSTRAINT "libraries_config_id_fkey" FOREIGN KEY ("config_id") REFERENCES "library_configs" ("id") ON DELETE RESTRICT ON UPDATE CASCADE
+);
+INSERT INTO "new_libraries" ("config_id", "created_at", "description", "emoji", "id", "name", "path", "status", "updated_at") SELECT "config_id", "created_at", "description", "emoji", "id", "name", "path", "status", "updated_at" FROM "libraries";
+DROP TABLE "libraries";
+ALTER TABLE "new_libraries" RENAME TO "libraries";
+CREATE UNIQUE INDEX "libraries_name_key" ON "libraries"("name");
+CREATE UNIQUE INDEX "libraries_path_key" ON "libraries"("path");
+CREATE UNIQUE INDEX "libraries_config_id_key" ON "libraries"("config_id");
+CREATE TABLE "new_server_config" (
+    "id" TEXT NOT NULL PRIMARY KEY,
+    "public_url" TEXT,
+    "initial_wal_setup_complete" BOOLEAN NOT NULL DEFAULT false,
+    "encryption_key" TEXT
+);
+INSERT INTO "new_server_config" ("encryption_key", "id", "initial_wal_setup_complete", "public_url") SELECT "encryption_key", "id", "initial_wal_setup_complete", "public_url" FROM "server_config";
+DROP TABLE "server_config";
+ALTER TABLE "new_server_config" RENAME TO "server_config";
+DROP TABLE "job_schedule_configs";
+PRAGMA foreign_key_check;
+PRAGMA foreign_keys=ON;
+
+-- CreateIndex
+CREATE UNIQUE INDEX "_JobScheduleToLibrary_AB_unique" ON "_JobScheduleToLibrary"("A", "B");
+
+-- CreateIndex
+CREATE INDEX "_JobScheduleToLibrary_B_index" ON "_JobScheduleToLibrary"("B");
@@ -0,0 +1,3 @@
+-- AlterTable
+ALTER TABLE "jobs" ADD COLUMN "attempts" INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE "jobs" ADD COLUMN "next_retry_at" DATETIME;
@@ -0,0 +1,22 @@
+-- RedefineTables
+PRAGMA foreign_keys=OFF;
+CREATE TABLE "new_jobs" (
+    "id" TEXT NOT NULL PRIMARY KEY,
+    "name" TEXT NOT NULL,
+    "description" TEXT,
+    "status" TEXT NOT NULL DEFAULT 'QUEUED',
+    "save_state" BLOB,
+    "output_data" BLOB,
+    "ms_elapsed" BIGINT NOT NULL DEFAULT 0,
+    "attempts" INTEGER NOT NULL DEFAULT 0,
+    "next_retry_at" DATETIME,
+    "parent_id" TEXT,
+    "created_at" DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    "completed_at" DATETIME,
+    CONSTRAINT "jobs_parent_id_fkey" FOREIGN KEY ("parent_id") REFERENCES "jobs" ("id") ON DELETE SET NULL ON UPDATE CASCADE
+);
+INSERT INTO "new_jobs" ("attempts", "completed_at", "created_at", "description", "id", "ms_elapsed", "name", "next_retry_at", "output_data", "save_state", "status") SELECT "attempts", "completed_at", "created_at", "description", "id", "ms_elapsed", "name", "next_retry_at", "output_data", "save_state", "status" FROM "jobs";
+DROP TABLE "jobs";
+ALTER TABLE "new_jobs" RENAME TO "jobs";
+PRAGMA foreign_key_check;
+PRAGMA foreign_keys=ON;
@@ -0,0 +1,6 @@
+-- CreateTable
+CREATE TABLE "metadata_provider_configs" (
+    "provider" TEXT NOT NULL PRIMARY KEY,
+    "enabled" BOOLEAN NOT NULL DEFAULT true,
+    "encrypted_api_key" TEXT
+);
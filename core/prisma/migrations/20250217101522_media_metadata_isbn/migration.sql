@@ -0,0 +1,2 @@
+-- AlterTable
+ALTER TABLE "media_metadata" ADD COLUMN "isbn" TEXT;
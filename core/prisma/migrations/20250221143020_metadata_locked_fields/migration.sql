@@ -0,0 +1,5 @@
+-- AlterTable
+ALTER TABLE "media_metadata" ADD COLUMN "locked_fields" TEXT;
+
+-- AlterTable
+ALTER TABLE "series_metadata" ADD COLUMN "locked_fields" TEXT;
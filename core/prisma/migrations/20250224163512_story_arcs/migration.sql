@@ -0,0 +1,22 @@
+-- CreateTable
+CREATE TABLE "story_arcs" (
+    "id" TEXT NOT NULL PRIMARY KEY,
+    "name" TEXT NOT NULL,
+    "created_at" DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    "updated_at" DATETIME NOT NULL
+);
+
+-- CreateTable
+CREATE TABLE "story_arc_members" (
+    "position" REAL,
+    "story_arc_id" TEXT NOT NULL,
+    "media_id" TEXT NOT NULL,
+    CONSTRAINT "story_arc_members_story_arc_id_fkey" FOREIGN KEY ("story_arc_id") REFERENCES "story_arcs" ("id") ON DELETE CASCADE ON UPDATE CASCADE,
+    CONSTRAINT "story_arc_members_media_id_fkey" FOREIGN KEY ("media_id") REFERENCES "media" ("id") ON DELETE CASCADE ON UPDATE CASCADE
+);
+
+-- CreateIndex
+CREATE UNIQUE INDEX "story_arcs_name_key" ON "story_arcs"("name");
+
+-- CreateIndex
+CREATE UNIQUE INDEX "story_arc_members_story_arc_id_media_id_key" ON "story_arc_members"("story_arc_id", "media_id");
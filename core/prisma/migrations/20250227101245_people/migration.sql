@@ -0,0 +1,36 @@
+-- CreateTable
+CREATE TABLE "people" (
+    "id" TEXT NOT NULL PRIMARY KEY,
+    "name" TEXT NOT NULL,
+    "normalized_name" TEXT NOT NULL,
+    "created_at" DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    "updated_at" DATETIME NOT NULL
+);
+
+-- CreateTable
+CREATE TABLE "person_aliases" (
+    "id" TEXT NOT NULL PRIMARY KEY,
+    "name" TEXT NOT NULL,
+    "normalized_name" TEXT NOT NULL,
+    "person_id" TEXT NOT NULL,
+    CONSTRAINT "person_aliases_person_id_fkey" FOREIGN KEY ("person_id") REFERENCES "people" ("id") ON DELETE CASCADE ON UPDATE CASCADE
+);
+
+-- CreateTable
+CREATE TABLE "media_credits" (
+    "id" TEXT NOT NULL PRIMARY KEY,
+    "role" TEXT NOT NULL,
+    "person_id" TEXT NOT NULL,
+    "media_id" TEXT NOT NULL,
+    CONSTRAINT "media_credits_person_id_fkey" FOREIGN KEY ("person_id") REFERENCES "people" ("id") ON DELETE CASCADE ON UPDATE CASCADE,
+    CONSTRAINT "media_credits_media_id_fkey" FOREIGN KEY ("media_id") REFERENCES "media" ("id") ON DELETE CASCADE ON UPDATE CASCADE
+);
+
+-- CreateIndex
+CREATE UNIQUE INDEX "people_normalized_name_key" ON "people"("normalized_name");
+
+-- CreateIndex
+CREATE UNIQUE INDEX "person_aliases_normalized_name_key" ON "person_aliases"("normalized_name");
+
+-- CreateIndex
+CREATE UNIQUE INDEX "media_credits_media_id_person_id_role_key" ON "media_credits"("media_id", "person_id", "role");
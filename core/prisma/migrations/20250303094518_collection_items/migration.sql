@@ -0,0 +1,17 @@
+-- CreateTable
+CREATE TABLE "collection_items" (
+    "id" TEXT NOT NULL PRIMARY KEY,
+    "position" INTEGER NOT NULL,
+    "collection_id" TEXT NOT NULL,
+    "series_id" TEXT,
+    "media_id" TEXT,
+    CONSTRAINT "collection_items_collection_id_fkey" FOREIGN KEY ("collection_id") REFERENCES "collections" ("id") ON DELETE CASCADE ON UPDATE CASCADE,
+    CONSTRAINT "collection_items_series_id_fkey" FOREIGN KEY ("series_id") REFERENCES "series" ("id") ON DELETE CASCADE ON UPDATE CASCADE,
+    CONSTRAINT "collection_items_media_id_fkey" FOREIGN KEY ("media_id") REFERENCES "media" ("id") ON DELETE CASCADE ON UPDATE CASCADE
+);
+
+-- CreateIndex
+CREATE UNIQUE INDEX "collection_items_collection_id_series_id_key" ON "collection_items"("collection_id", "series_id");
+
+-- CreateIndex
+CREATE UNIQUE INDEX "collection_items_collection_id_media_id_key" ON "collection_items"("collection_id", "media_id");
@@ -32,9 +32,15 @@ pub mod env_keys {
 	pub const SESSION_EXPIRY_INTERVAL_KEY: &str = "SESSION_EXPIRY_CLEANUP_INTERVAL";
 	pub const MAX_SCANNER_CONCURRENCY_KEY: &str = "STUMP_MAX_SCANNER_CONCURRENCY";
 	pub const MAX_THUMBNAIL_CONCURRENCY_KEY: &str = "STUMP_MAX_THUMBNAIL_CONCURRENCY";
+	pub const MAX_CONCURRENT_JOBS_KEY: &str = "STUMP_MAX_CONCURRENT_JOBS";
 	pub const MAX_IMAGE_UPLOAD_SIZE_KEY: &str = "STUMP_MAX_IMAGE_UPLOAD_SIZE";
 	pub const ENABLE_UPLOAD_KEY: &str = "STUMP_ENABLE_UPLOAD";
 	pub const MAX_FILE_UPLOAD_SIZE_KEY: &str = "STUMP_MAX_FILE_UPLOAD_SIZE";
+	pub const PERCEPTUAL_HASH_PAGES_KEY: &str = "STUMP_PERCEPTUAL_HASH_PAGES";
+	pub const S3_ENDPOINT_KEY: &str = "STUMP_S3_ENDPOINT";
+	pub const S3_REGION_KEY: &str = "STUMP_S3_REGION";
+	pub const S3_ACCESS_KEY_ID_KEY: &str = "STUMP_S3_ACCESS_KEY_ID";
+	pub const S3_SECRET_ACCESS_KEY_KEY: &str = "STUMP_S3_SECRET_ACCESS_KEY";
 }
 use env_keys::*;
 
@@ -45,9 +51,12 @@ pub mod defaults {
 	pub const DEFAULT_SESSION_EXPIRY_CLEANUP_INTERVAL: u64 = 60 * 60 * 24; // 24 hours
 	pub const DEFAULT_MAX_SCANNER_CONCURRENCY: usize = 200;
 	pub const DEFAULT_MAX_THUMBNAIL_CONCURRENCY: usize = 50;
+	pub const DEFAULT_MAX_CONCURRENT_JOBS: usize = 2;
 	pub const DEFAULT_MAX_IMAGE_UPLOAD_SIZE: usize = 20 * 1024 * 1024; // 20 MB
 	pub const DEFAULT_ENABLE_UPLOAD: bool = false;
 	pub const DEFAULT_MAX_FILE_UPLOAD_SIZE: usize = 20 * 1024 * 1024; // 20 MB
+	pub const DEFAULT_PERCEPTUAL_HASH_PAGES: usize = 0;
+	pub const DEFAULT_S3_REGION: &str = "us-east-1";
 }
 use defaults::*;
 
@@ -179,6 +188,12 @@ pub struct StumpConfig {
 	#[env_key(MAX_THUMBNAIL_CONCURRENCY_KEY)]
 	pub max_thumbnail_concurrency: usize,
 
+	/// The maximum number of jobs which may run at the same time. Any other jobs wait in the
+	/// queue until a worker is free, with interactive jobs taking precedence over background ones.
+	#[default_value(DEFAULT_MAX_CONCURRENT_JOBS)]
+	#[env_key(MAX_CONCURRENT_JOBS_KEY)]
+	pub max_concurrent_jobs: usize,
+
 	/// The maximum file size, in bytes, of images that can be uploaded, e.g., as thumbnails for users,
 	/// libraries, series, or media.
 	#[default_value(DEFAULT_MAX_IMAGE_UPLOAD_SIZE)]
@@ -194,6 +209,35 @@ pub struct StumpConfig {
 	#[default_value(DEFAULT_MAX_FILE_UPLOAD_SIZE)]
 	#[env_key(MAX_FILE_UPLOAD_SIZE_KEY)]
 	pub max_file_upload_size: usize,
+
+	/// The number of interior pages, in addition to the cover, which are hashed when computing
+	/// the perceptual hash of a book during analysis. Sampling more pages reduces false positives
+	/// when detecting duplicates (e.g. issues which share a variant cover), at the cost of
+	/// slower analysis.
+	#[default_value(DEFAULT_PERCEPTUAL_HASH_PAGES)]
+	#[env_key(PERCEPTUAL_HASH_PAGES_KEY)]
+	pub perceptual_hash_pages: usize,
+
+	/// The endpoint of an S3-compatible object store, e.g. a self-hosted MinIO instance. When
+	/// unset, AWS S3 is used. Custom endpoints are addressed using path-style requests.
+	#[default_value(None)]
+	#[env_key(S3_ENDPOINT_KEY)]
+	pub s3_endpoint: Option<String>,
+
+	/// The region of the object store used for libraries with `s3://` paths
+	#[default_value(DEFAULT_S3_REGION.to_string())]
+	#[env_key(S3_REGION_KEY)]
+	pub s3_region: String,
+
+	/// The access key ID used to authenticate with the object store
+	#[default_value(None)]
+	#[env_key(S3_ACCESS_KEY_ID_KEY)]
+	pub s3_access_key_id: Option<String>,
+
+	/// The secret access key used to authenticate with the object store
+	#[default_value(None)]
+	#[env_key(S3_SECRET_ACCESS_KEY_KEY)]
+	pub s3_secret_access_key: Option<String>,
 }
 
 impl StumpConfig {
@@ -337,9 +381,15 @@ mod tests {
 			expired_session_cleanup_interval: None,
 			max_scanner_concurrency: None,
 			max_thumbnail_concurrency: None,
+			max_concurrent_jobs: None,
 			max_image_upload_size: None,
 			enable_upload: None,
 			max_file_upload_size: None,
+			perceptual_hash_pages: None,
+			s3_endpoint: Some("http://localhost:9000".to_string()),
+			s3_region: None,
+			s3_access_key_id: None,
+			s3_secret_access_key: None,
 		};
 		partial_config.apply_to_config(&mut config);
 
@@ -376,9 +426,15 @@ mod tests {
 				),
 				max_scanner_concurrency: Some(DEFAULT_MAX_SCANNER_CONCURRENCY),
 				max_thumbnail_concurrency: Some(DEFAULT_MAX_THUMBNAIL_CONCURRENCY),
+				max_concurrent_jobs: Some(DEFAULT_MAX_CONCURRENT_JOBS),
 				max_image_upload_size: Some(DEFAULT_MAX_IMAGE_UPLOAD_SIZE),
 				enable_upload: Some(DEFAULT_ENABLE_UPLOAD),
-				max_file_upload_size: Some(DEFAULT_MAX_FILE_UPLOAD_SIZE)
+				max_file_upload_size: Some(DEFAULT_MAX_FILE_UPLOAD_SIZE),
+				perceptual_hash_pages: Some(DEFAULT_PERCEPTUAL_HASH_PAGES),
+				s3_endpoint: Some("http://localhost:9000".to_string()),
+				s3_region: Some(DEFAULT_S3_REGION.to_string()),
+				s3_access_key_id: None,
+				s3_secret_access_key: None,
 			}
 		);
 
@@ -430,9 +486,15 @@ mod tests {
 						custom_templates_dir: None,
 						max_scanner_concurrency: DEFAULT_MAX_SCANNER_CONCURRENCY,
 						max_thumbnail_concurrency: DEFAULT_MAX_THUMBNAIL_CONCURRENCY,
+						max_concurrent_jobs: DEFAULT_MAX_CONCURRENT_JOBS,
 						max_image_upload_size: DEFAULT_MAX_IMAGE_UPLOAD_SIZE,
 						enable_upload: DEFAULT_ENABLE_UPLOAD,
 						max_file_upload_size: DEFAULT_MAX_FILE_UPLOAD_SIZE,
+						perceptual_hash_pages: DEFAULT_PERCEPTUAL_HASH_PAGES,
+						s3_endpoint: None,
+						s3_region: DEFAULT_S3_REGION.to_string(),
+						s3_access_key_id: None,
+						s3_secret_access_key: None,
 					}
 				);
 			},
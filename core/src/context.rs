@@ -1,6 +1,5 @@
 use std::sync::Arc;
 
-use prisma_client_rust::not;
 use tokio::sync::{
 	broadcast::{channel, Receiver, Sender},
 	mpsc::error::SendError,
@@ -10,9 +9,9 @@ use crate::{
 	config::StumpConfig,
 	db,
 	event::CoreEvent,
-	job::{Executor, JobController, JobControllerCommand},
-	prisma::{self, server_config},
-	CoreError, CoreResult,
+	filesystem::scanner::LibraryWatcher,
+	job::{Executor, JobController, JobControllerCommand, JobScheduler},
+	prisma, utils, CoreResult,
 };
 
 type EventChannel = (Sender<CoreEvent>, Receiver<CoreEvent>);
@@ -26,6 +25,8 @@ pub struct Ctx {
 	pub db: Arc<prisma::PrismaClient>,
 	pub job_controller: Arc<JobController>,
 	pub event_channel: Arc<EventChannel>,
+	pub library_watcher: Arc<LibraryWatcher>,
+	pub job_scheduler: Arc<JobScheduler>,
 }
 
 impl Ctx {
@@ -51,12 +52,16 @@ impl Ctx {
 
 		let job_controller =
 			JobController::new(db.clone(), config.clone(), event_channel.0.clone());
+		let library_watcher = LibraryWatcher::new(db.clone(), job_controller.clone());
+		let job_scheduler = JobScheduler::new(db.clone(), job_controller.clone());
 
 		Ctx {
 			config,
 			db,
 			job_controller,
 			event_channel,
+			library_watcher,
+			job_scheduler,
 		}
 	}
 
@@ -76,12 +81,16 @@ impl Ctx {
 		// Create job manager
 		let job_controller =
 			JobController::new(db.clone(), config.clone(), event_channel.0.clone());
+		let library_watcher = LibraryWatcher::new(db.clone(), job_controller.clone());
+		let job_scheduler = JobScheduler::new(db.clone(), job_controller.clone());
 
 		Ctx {
 			config,
 			db,
 			job_controller,
 			event_channel,
+			library_watcher,
+			job_scheduler,
 		}
 	}
 
@@ -98,12 +107,16 @@ impl Ctx {
 		// Create job manager
 		let job_controller =
 			JobController::new(db.clone(), config.clone(), event_channel.0.clone());
+		let library_watcher = LibraryWatcher::new(db.clone(), job_controller.clone());
+		let job_scheduler = JobScheduler::new(db.clone(), job_controller.clone());
 
 		let ctx = Ctx {
 			config,
 			db,
 			job_controller,
 			event_channel,
+			library_watcher,
+			job_scheduler,
 		};
 
 		(ctx, mock)
@@ -205,17 +218,6 @@ impl Ctx {
 	}
 
 	pub async fn get_encryption_key(&self) -> CoreResult<String> {
-		let server_config = self
-			.db
-			.server_config()
-			.find_first(vec![not![server_config::encryption_key::equals(None)]])
-			.exec()
-			.await?;
-
-		let encryption_key = server_config
-			.and_then(|config| config.encryption_key)
-			.ok_or(CoreError::EncryptionKeyNotSet)?;
-
-		Ok(encryption_key)
+		utils::get_encryption_key(&self.db).await
 	}
 }
@@ -0,0 +1,193 @@
+use serde::{Deserialize, Serialize};
+use specta::Type;
+use utoipa::ToSchema;
+
+use crate::{
+	db::entity::{common::Cursor, Media, Series},
+	prisma::{collection, collection_item},
+};
+
+///////////////////////////////////////////////
+//////////////////// MODELS ///////////////////
+///////////////////////////////////////////////
+
+/// A manually ordered collection of series and individual books, which may come from any
+/// number of libraries
+#[derive(Debug, Clone, Serialize, Deserialize, Type, ToSchema)]
+pub struct Collection {
+	pub id: String,
+	/// The name of the collection. ex: "Spider-Man Comics"
+	pub name: String,
+	/// The description of the collection, if any
+	pub description: Option<String>,
+	pub updated_at: String,
+	/// The items in the collection, in order. Will be `None` only if the relation is not
+	/// loaded.
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub items: Option<Vec<CollectionItem>>,
+}
+
+impl Cursor for Collection {
+	fn cursor(&self) -> String {
+		self.id.clone()
+	}
+}
+
+/// An item in a collection, which is either an entire series or a single book. Exactly one
+/// of `series_id` and `media_id` is set
+#[derive(Debug, Clone, Serialize, Deserialize, Type, ToSchema)]
+pub struct CollectionItem {
+	pub id: String,
+	/// The position of the item in the collection, starting at 0
+	pub position: i32,
+	pub collection_id: String,
+	pub series_id: Option<String>,
+	pub media_id: Option<String>,
+	/// The series. Will be `None` if the item is a book or the relation is not loaded.
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub series: Option<Series>,
+	/// The book. Will be `None` if the item is a series or the relation is not loaded.
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub media: Option<Media>,
+}
+
+/// A reference to a series or book to add to a collection
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Type, ToSchema)]
+#[serde(untagged)]
+pub enum CollectionItemInput {
+	Series { series_id: String },
+	Media { media_id: String },
+}
+
+impl CollectionItemInput {
+	/// Remove any repeated items from the given list, keeping the position of their first
+	/// occurrence
+	pub fn dedupe(items: Vec<CollectionItemInput>) -> Vec<CollectionItemInput> {
+		items.into_iter().fold(Vec::new(), |mut acc, item| {
+			if !acc.contains(&item) {
+				acc.push(item);
+			}
+			acc
+		})
+	}
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Type, ToSchema)]
+pub struct CreateOrUpdateCollection {
+	/// The name of the collection. ex: "Spider-Man Comics"
+	pub name: String,
+	/// The description of the collection, if any
+	#[serde(default)]
+	pub description: Option<String>,
+	/// The items of the collection, in order. If omitted on update, the items are unchanged
+	#[serde(default)]
+	pub items: Option<Vec<CollectionItemInput>>,
+}
+
+///////////////////////////////////////////////
+////////////////// CONVERSIONS ////////////////
+///////////////////////////////////////////////
+
+impl From<collection::Data> for Collection {
+	fn from(data: collection::Data) -> Collection {
+		let items = data.items().ok().map(|items| {
+			let mut items = items
+				.iter()
+				.cloned()
+				.map(CollectionItem::from)
+				.collect::<Vec<_>>();
+			items.sort_by_key(|item| item.position);
+			items
+		});
+
+		Collection {
+			id: data.id,
+			name: data.name,
+			description: data.description,
+			updated_at: data.updated_at.to_rfc3339(),
+			items,
+		}
+	}
+}
+
+impl From<collection_item::Data> for CollectionItem {
+	fn from(data: collection_item::Data) -> CollectionItem {
+		let series = data
+			.series()
+			.ok()
+			.flatten()
+			.map(|series| Series::from(series.to_owned()));
+		let media = data
+			.media()
+			.ok()
+			.flatten()
+			.map(|media| Media::from(media.to_owned()));
+
+		CollectionItem {
+			id: data.id,
+			position: data.position,
+			collection_id: data.collection_id,
+			series_id: data.series_id,
+			media_id: data.media_id,
+			series,
+			media,
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_collection_item_input_dedupe() {
+		let items = CollectionItemInput::dedupe(vec![
+			CollectionItemInput::Series {
+				series_id: "1".to_string(),
+			},
+			CollectionItemInput::Media {
+				media_id: "1".to_string(),
+			},
+			CollectionItemInput::Series {
+				series_id: "1".to_string(),
+			},
+			CollectionItemInput::Media {
+				media_id: "2".to_string(),
+			},
+		]);
+
+		assert_eq!(
+			items,
+			vec![
+				CollectionItemInput::Series {
+					series_id: "1".to_string(),
+				},
+				CollectionItemInput::Media {
+					media_id: "1".to_string(),
+				},
+				CollectionItemInput::Media {
+					media_id: "2".to_string(),
+				},
+			]
+		);
+	}
+
+	#[test]
+	fn test_collection_item_input_deserialize() {
+		let items: Vec<CollectionItemInput> =
+			serde_json::from_str(r#"[{ "series_id": "a" }, { "media_id": "b" }]"#)
+				.unwrap();
+
+		assert_eq!(
+			items,
+			vec![
+				CollectionItemInput::Series {
+					series_id: "a".to_string(),
+				},
+				CollectionItemInput::Media {
+					media_id: "b".to_string(),
+				},
+			]
+		);
+	}
+}
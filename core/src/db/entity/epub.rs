@@ -1,4 +1,9 @@
-use std::{collections::HashMap, fs::File, io::BufReader, path::PathBuf};
+use std::{
+	collections::HashMap,
+	fs::File,
+	io::BufReader,
+	path::{Path, PathBuf},
+};
 
 use epub::doc::{EpubDoc, NavPoint};
 use serde::{Deserialize, Serialize};
@@ -6,7 +11,11 @@ use specta::Type;
 use tracing::error;
 use utoipa::ToSchema;
 
-use crate::{filesystem::FileError, prisma::media};
+use crate::{
+	config::StumpConfig,
+	filesystem::{storage::resolve_local_path, FileError},
+	prisma::media,
+};
 
 use super::{media::Media, MediaAnnotation};
 
@@ -100,9 +109,11 @@ impl Epub {
 	}
 
 	/// Attempts to create an Epub from a media entity. Internally, this will attempt to open an [`EpubDoc`]
-	/// from the media's path. If this fails, it will return a [`FileError::EpubOpenError`].
-	pub fn try_from(media: media::Data) -> Result<Epub, FileError> {
-		let epub_file = EpubDoc::new(media.path.as_str()).map_err(|e| {
+	/// from the media's path, downloading it first if it lives in object storage. If this fails, it
+	/// will return a [`FileError::EpubOpenError`].
+	pub fn try_from(media: media::Data, config: &StumpConfig) -> Result<Epub, FileError> {
+		let local_path = resolve_local_path(Path::new(&media.path), config)?;
+		let epub_file = EpubDoc::new(local_path).map_err(|e| {
 			error!("Failed to open epub {}: {}", &media.path, e);
 			FileError::EpubOpenError(e.to_string())
 		})?;
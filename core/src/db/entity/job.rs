@@ -5,9 +5,10 @@ use utoipa::ToSchema;
 use crate::{
 	filesystem::{
 		image::ThumbnailGenerationOutput,
-		scanner::{LibraryScanOutput, SeriesScanOutput},
+		scanner::{LibraryScanOutput, ScanDryRunOutput, SeriesScanOutput},
 	},
 	job::JobStatus,
+	metadata::{ComicVineMatchOutput, IsbnEnrichmentOutput, MetadataImportOutput},
 	prisma::job,
 };
 
@@ -25,6 +26,10 @@ pub enum CoreJobOutput {
 	LibraryScan(LibraryScanOutput),
 	SeriesScan(SeriesScanOutput),
 	ThumbnailGeneration(ThumbnailGenerationOutput),
+	ScanDryRun(ScanDryRunOutput),
+	ComicVineMatch(ComicVineMatchOutput),
+	IsbnEnrichment(IsbnEnrichmentOutput),
+	MetadataImport(MetadataImportOutput),
 	External(ExternalJobOutput),
 }
 
@@ -42,6 +47,12 @@ pub struct PersistedJob {
 	pub output_data: Option<CoreJobOutput>,
 	/// The duration of the job in milliseconds
 	pub ms_elapsed: i64,
+	/// The number of times the job has been attempted, including any retries
+	pub attempts: i32,
+	/// The timestamp of when the job will next be retried, if a retry is pending
+	pub next_retry_at: Option<String>,
+	/// The ID of the job which declared this job as a follow-up, if any
+	pub parent_id: Option<String>,
 	// TODO(specta): replace with DateTime<FixedOffset>
 	/// The timestamp of when the job was created
 	pub created_at: String,
@@ -51,6 +62,10 @@ pub struct PersistedJob {
 	/// The persisted logs associated with this job, if any
 	#[serde(skip_serializing_if = "Option::is_none")]
 	pub logs: Option<Vec<Log>>,
+	/// The follow-up jobs of this job, if loaded. These form a tree, since each follow-up
+	/// may have follow-ups of its own
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub children: Option<Vec<PersistedJob>>,
 }
 
 impl Cursor for PersistedJob {
@@ -83,9 +98,13 @@ impl From<job::Data> for PersistedJob {
 			status: job.status.into(),
 			output_data,
 			ms_elapsed: job.ms_elapsed,
+			attempts: job.attempts,
+			next_retry_at: job.next_retry_at.map(|d| d.to_rfc3339()),
+			parent_id: job.parent_id,
 			created_at: job.created_at.to_rfc3339(),
 			completed_at: job.completed_at.map(|d| d.to_rfc3339()),
 			logs,
+			children: None,
 		}
 	}
 }
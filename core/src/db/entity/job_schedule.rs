@@ -0,0 +1,140 @@
+use std::{fmt, str::FromStr};
+
+use serde::{Deserialize, Serialize};
+use specta::Type;
+use utoipa::ToSchema;
+
+use crate::{prisma::job_schedule, CoreError};
+
+use super::Library;
+
+/// The kinds of jobs which may be run on a schedule
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Type, ToSchema)]
+pub enum ScheduledJobKind {
+	#[serde(rename = "SCAN")]
+	Scan,
+	#[serde(rename = "THUMBNAIL_GENERATION")]
+	ThumbnailGeneration,
+	#[serde(rename = "MEDIA_ANALYSIS")]
+	MediaAnalysis,
+	#[serde(rename = "DATABASE_MAINTENANCE")]
+	DatabaseMaintenance,
+	#[serde(rename = "SESSION_CLEANUP")]
+	SessionCleanup,
+}
+
+impl ScheduledJobKind {
+	/// Whether the job operates on individual libraries. Jobs which don't will ignore the
+	/// target libraries of their schedule
+	pub fn targets_libraries(&self) -> bool {
+		matches!(
+			self,
+			ScheduledJobKind::Scan
+				| ScheduledJobKind::ThumbnailGeneration
+				| ScheduledJobKind::MediaAnalysis
+		)
+	}
+}
+
+impl fmt::Display for ScheduledJobKind {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			ScheduledJobKind::Scan => write!(f, "SCAN"),
+			ScheduledJobKind::ThumbnailGeneration => write!(f, "THUMBNAIL_GENERATION"),
+			ScheduledJobKind::MediaAnalysis => write!(f, "MEDIA_ANALYSIS"),
+			ScheduledJobKind::DatabaseMaintenance => write!(f, "DATABASE_MAINTENANCE"),
+			ScheduledJobKind::SessionCleanup => write!(f, "SESSION_CLEANUP"),
+		}
+	}
+}
+
+impl FromStr for ScheduledJobKind {
+	type Err = String;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		match s.to_uppercase().as_str() {
+			"SCAN" => Ok(ScheduledJobKind::Scan),
+			"THUMBNAIL_GENERATION" => Ok(ScheduledJobKind::ThumbnailGeneration),
+			"MEDIA_ANALYSIS" => Ok(ScheduledJobKind::MediaAnalysis),
+			"DATABASE_MAINTENANCE" => Ok(ScheduledJobKind::DatabaseMaintenance),
+			"SESSION_CLEANUP" => Ok(ScheduledJobKind::SessionCleanup),
+			_ => Err(format!("Invalid ScheduledJobKind: {s}")),
+		}
+	}
+}
+
+/// A persisted schedule which the [`crate::job::JobScheduler`] uses to enqueue jobs
+#[derive(Debug, Clone, Serialize, Deserialize, Type, ToSchema)]
+pub struct JobSchedule {
+	pub id: String,
+	/// A friendly name for the schedule
+	pub name: String,
+	/// The 5-field cron expression which determines when the job runs, e.g. `0 3 * * *`
+	pub cron_expression: String,
+	/// The kind of job which is enqueued when the schedule fires
+	pub job_kind: ScheduledJobKind,
+	/// Whether the schedule is currently active
+	pub enabled: bool,
+	/// The libraries targeted by the job. An empty list targets every library
+	pub target_libraries: Vec<Library>,
+	// TODO(specta): replace with DateTime<FixedOffset>
+	/// The timestamp of the last time the schedule fired, if ever
+	pub last_run_at: Option<String>,
+	/// The timestamp of the next time the schedule will fire, if known
+	pub next_run_at: Option<String>,
+	pub created_at: String,
+	pub updated_at: String,
+}
+
+impl TryFrom<job_schedule::Data> for JobSchedule {
+	type Error = CoreError;
+
+	fn try_from(data: job_schedule::Data) -> Result<Self, Self::Error> {
+		let job_kind = ScheduledJobKind::from_str(&data.job_kind)
+			.map_err(CoreError::InternalError)?;
+		let target_libraries = data
+			.target_libraries()
+			.cloned()
+			.unwrap_or_default()
+			.into_iter()
+			.map(Library::from)
+			.collect();
+
+		Ok(Self {
+			id: data.id,
+			name: data.name,
+			cron_expression: data.cron_expression,
+			job_kind,
+			enabled: data.enabled,
+			target_libraries,
+			last_run_at: data.last_run_at.map(|dt| dt.to_rfc3339()),
+			next_run_at: data.next_run_at.map(|dt| dt.to_rfc3339()),
+			created_at: data.created_at.to_rfc3339(),
+			updated_at: data.updated_at.to_rfc3339(),
+		})
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_scheduled_job_kind_round_trip() {
+		for kind in [
+			ScheduledJobKind::Scan,
+			ScheduledJobKind::ThumbnailGeneration,
+			ScheduledJobKind::MediaAnalysis,
+			ScheduledJobKind::DatabaseMaintenance,
+			ScheduledJobKind::SessionCleanup,
+		] {
+			assert_eq!(ScheduledJobKind::from_str(&kind.to_string()), Ok(kind));
+			assert_eq!(serde_json::to_string(&kind).unwrap(), format!("\"{kind}\""));
+		}
+		assert_eq!(
+			ScheduledJobKind::from_str("media_analysis"),
+			Ok(ScheduledJobKind::MediaAnalysis)
+		);
+		assert!(ScheduledJobKind::from_str("EMAIL").is_err());
+	}
+}
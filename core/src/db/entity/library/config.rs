@@ -5,12 +5,18 @@ use specta::Type;
 use utoipa::ToSchema;
 
 use crate::{
-	db::entity::common::{ReadingDirection, ReadingImageScaleFit, ReadingMode},
+	db::entity::{
+		common::{ReadingDirection, ReadingImageScaleFit, ReadingMode},
+		MergeStrategy,
+	},
 	filesystem::image::ImageProcessorOptions,
 	prisma::library_config,
 };
 
-use super::{IgnoreRules, LibraryPattern};
+use super::{
+	FilenamePatterns, IgnoreRules, LibraryPattern, LibraryWatchMode,
+	MetadataProviderPriority,
+};
 
 #[derive(Debug, Clone, Deserialize, Serialize, Type, ToSchema, Default)]
 pub struct LibraryConfig {
@@ -31,6 +37,17 @@ pub struct LibraryConfig {
 	pub default_reading_image_scale_fit: ReadingImageScaleFit,
 	#[serde(default)]
 	pub ignore_rules: IgnoreRules,
+	#[serde(default)]
+	pub watch_mode: LibraryWatchMode,
+	/// Patterns used to infer metadata from file and directory names when it is missing
+	#[serde(default)]
+	pub filename_patterns: FilenamePatterns,
+	/// The default strategy for reconciling metadata read from files with existing metadata
+	#[serde(default)]
+	pub merge_strategy: MergeStrategy,
+	/// The providers used to identify books, in priority order
+	#[serde(default)]
+	pub metadata_providers: MetadataProviderPriority,
 	// TODO(prisma-nested-create): Refactor once nested create is supported
 	// https://github.com/Brendonovich/prisma-client-rust/issues/44
 	#[specta(optional)]
@@ -74,6 +91,19 @@ impl From<library_config::Data> for LibraryConfig {
 				.map_or_else(IgnoreRules::default, |rules| {
 					IgnoreRules::try_from(rules).unwrap_or_default()
 				}),
+			watch_mode: LibraryWatchMode::from(data.watch_mode),
+			filename_patterns: data
+				.filename_patterns
+				.map_or_else(FilenamePatterns::default, |patterns| {
+					FilenamePatterns::try_from(patterns).unwrap_or_default()
+				}),
+			merge_strategy: MergeStrategy::from(data.merge_strategy),
+			metadata_providers: data.metadata_providers.map_or_else(
+				MetadataProviderPriority::default,
+				|providers| {
+					MetadataProviderPriority::try_from(providers).unwrap_or_default()
+				},
+			),
 			library_id: data.library_id,
 		}
 	}
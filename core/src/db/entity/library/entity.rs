@@ -22,6 +22,9 @@ pub struct Library {
 	pub emoji: Option<String>,
 	/// The path to the library. ex: "/home/user/Library"
 	pub path: String,
+	/// Any root paths of the library in addition to `path`, e.g. for a library which is split
+	/// across multiple disks. Will be `None` only if the relation is not loaded.
+	pub additional_roots: Option<Vec<String>>,
 	/// The status of the library since last scan or access. ex: "READY" or "MISSING"
 	pub status: String,
 	// The date in which the library was last updated. This is usually after a scan. ex: "2022-04-20 04:20:69"
@@ -35,6 +38,15 @@ pub struct Library {
 	pub config: LibraryConfig,
 }
 
+impl Library {
+	/// All of the root paths of the library, starting with the primary path
+	pub fn root_paths(&self) -> Vec<String> {
+		std::iter::once(self.path.clone())
+			.chain(self.additional_roots.clone().unwrap_or_default())
+			.collect()
+	}
+}
+
 impl Cursor for Library {
 	fn cursor(&self) -> String {
 		self.id.clone()
@@ -85,6 +97,62 @@ impl fmt::Display for LibraryPattern {
 	}
 }
 
+/// How (if at all) a library should be watched for changes on disk. When enabled, changes
+/// are debounced and only the affected series are scanned.
+#[derive(
+	Serialize, Deserialize, Debug, Default, PartialEq, Eq, Copy, Clone, Type, ToSchema,
+)]
+pub enum LibraryWatchMode {
+	#[default]
+	#[serde(rename = "DISABLED")]
+	Disabled,
+	/// Use the native filesystem notification API of the host (e.g. inotify)
+	#[serde(rename = "NATIVE")]
+	Native,
+	/// Periodically poll the library for changes. This is intended for network mounts, where
+	/// native notifications are unreliable or unsupported
+	#[serde(rename = "POLLING")]
+	Polling,
+}
+
+impl LibraryWatchMode {
+	pub fn is_enabled(&self) -> bool {
+		*self != LibraryWatchMode::Disabled
+	}
+}
+
+impl FromStr for LibraryWatchMode {
+	type Err = String;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		let uppercase = s.to_uppercase();
+
+		match uppercase.as_str() {
+			"DISABLED" => Ok(LibraryWatchMode::Disabled),
+			"NATIVE" => Ok(LibraryWatchMode::Native),
+			"POLLING" => Ok(LibraryWatchMode::Polling),
+			"" => Ok(LibraryWatchMode::default()),
+			_ => Err(format!("Invalid library watch mode: {s}")),
+		}
+	}
+}
+
+impl From<String> for LibraryWatchMode {
+	fn from(s: String) -> Self {
+		LibraryWatchMode::from_str(&s).unwrap_or_default()
+	}
+}
+
+impl fmt::Display for LibraryWatchMode {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			LibraryWatchMode::Disabled => write!(f, "DISABLED"),
+			LibraryWatchMode::Native => write!(f, "NATIVE"),
+			LibraryWatchMode::Polling => write!(f, "POLLING"),
+		}
+	}
+}
+
 #[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Copy, Clone, Type, ToSchema)]
 pub enum LibraryScanMode {
 	#[serde(rename = "DEFAULT")]
@@ -141,6 +209,11 @@ impl From<library::Data> for Library {
 			.ok()
 			.map(|tags| tags.iter().map(|tag| tag.to_owned().into()).collect());
 
+		let additional_roots = data
+			.additional_roots()
+			.ok()
+			.map(|roots| roots.iter().map(|root| root.path.clone()).collect());
+
 		let config = data.config().map_or_else(
 			|_| LibraryConfig::default(),
 			|config| config.to_owned().into(),
@@ -152,6 +225,7 @@ impl From<library::Data> for Library {
 			description: data.description,
 			emoji: data.emoji,
 			path: data.path,
+			additional_roots,
 			status: data.status,
 			updated_at: data.updated_at.to_rfc3339(),
 			series,
@@ -173,12 +247,18 @@ impl From<(library::Data, library_config::Data)> for Library {
 			Err(_e) => None,
 		};
 
+		let additional_roots = match library.additional_roots() {
+			Ok(roots) => Some(roots.iter().map(|root| root.path.clone()).collect()),
+			Err(_e) => None,
+		};
+
 		Library {
 			id: library.id,
 			name: library.name,
 			description: library.description,
 			emoji: library.emoji,
 			path: library.path,
+			additional_roots,
 			status: library.status,
 			updated_at: library.updated_at.to_rfc3339(),
 			series,
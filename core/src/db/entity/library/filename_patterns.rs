@@ -0,0 +1,328 @@
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use specta::Type;
+use utoipa::ToSchema;
+
+use crate::{db::entity::MediaMetadata, CoreError, CoreResult};
+
+/// The names of the capture groups which are recognized when extracting metadata from a name
+const CAPTURE_NAMES: [&str; 5] = ["series", "title", "volume", "number", "year"];
+
+/// The kind of a [FilenamePattern], which determines how its `pattern` is interpreted
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Type, ToSchema)]
+pub enum FilenamePatternKind {
+	/// A template with placeholders, e.g. `{series} v{volume} #{number} ({year})`. The supported
+	/// placeholders are `{series}`, `{title}`, `{volume}`, `{number}`, `{year}` and `{_}`, which
+	/// matches anything without capturing it.
+	#[serde(rename = "TEMPLATE")]
+	Template,
+	/// A regular expression with named capture groups, e.g. `(?P<series>.+) - (?P<number>\d+)`.
+	/// At least one of the recognized group names must be used.
+	#[serde(rename = "REGEX")]
+	Regex,
+}
+
+/// A pattern used to infer metadata from the name of a file (without its extension) or
+/// directory, for when the metadata is otherwise missing
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Type, ToSchema)]
+pub struct FilenamePattern {
+	pub kind: FilenamePatternKind,
+	pub pattern: String,
+}
+
+impl FilenamePattern {
+	pub fn template(pattern: &str) -> Self {
+		Self {
+			kind: FilenamePatternKind::Template,
+			pattern: pattern.to_string(),
+		}
+	}
+
+	pub fn regex(pattern: &str) -> Self {
+		Self {
+			kind: FilenamePatternKind::Regex,
+			pattern: pattern.to_string(),
+		}
+	}
+
+	/// Compile the pattern into a [Regex], validating that it captures something useful
+	pub fn compile(&self) -> CoreResult<Regex> {
+		let regex = match self.kind {
+			FilenamePatternKind::Template => template_to_regex(&self.pattern)?,
+			FilenamePatternKind::Regex => Regex::new(&self.pattern).map_err(|e| {
+				CoreError::BadRequest(format!("Invalid filename pattern: {e}"))
+			})?,
+		};
+
+		let has_known_capture = regex
+			.capture_names()
+			.flatten()
+			.any(|name| CAPTURE_NAMES.contains(&name));
+		if !has_known_capture {
+			return Err(CoreError::BadRequest(format!(
+				"Filename pattern must capture at least one of {CAPTURE_NAMES:?}: {}",
+				self.pattern
+			)));
+		}
+
+		Ok(regex)
+	}
+
+	/// Compile the pattern and extract metadata from the given name. This is intended for
+	/// one-off use (e.g. previews), otherwise [FilenamePatterns::build] should be preferred.
+	pub fn extract(&self, name: &str) -> CoreResult<Option<FilenameMetadata>> {
+		Ok(extract_with(&self.compile()?, name))
+	}
+}
+
+/// Converts a template pattern into an anchored regular expression. Literal text is escaped,
+/// and runs of whitespace match any (non-zero) amount of whitespace.
+fn template_to_regex(template: &str) -> CoreResult<Regex> {
+	let mut expression = String::from("^");
+	let mut remaining = template;
+
+	while let Some(start) = remaining.find('{') {
+		push_literal(&mut expression, &remaining[..start]);
+
+		let end = remaining[start..].find('}').ok_or_else(|| {
+			CoreError::BadRequest(format!("Unclosed placeholder in pattern: {template}"))
+		})? + start;
+		let placeholder = &remaining[start + 1..end];
+		let group = match placeholder {
+			"series" | "title" => format!(r"(?P<{placeholder}>.+?)"),
+			"volume" => r"(?P<volume>\d+)".to_string(),
+			"number" => r"(?P<number>\d+(?:\.\d+)?)".to_string(),
+			"year" => r"(?P<year>\d{4})".to_string(),
+			"_" => r".*?".to_string(),
+			_ => {
+				return Err(CoreError::BadRequest(format!(
+					"Unknown placeholder {{{placeholder}}} in pattern: {template}"
+				)))
+			},
+		};
+		expression.push_str(&group);
+		remaining = &remaining[end + 1..];
+	}
+	push_literal(&mut expression, remaining);
+	expression.push('$');
+
+	Regex::new(&expression)
+		.map_err(|e| CoreError::BadRequest(format!("Invalid filename pattern: {e}")))
+}
+
+fn push_literal(expression: &mut String, literal: &str) {
+	let mut parts = literal.split_whitespace().peekable();
+	if literal.starts_with(char::is_whitespace) {
+		expression.push_str(r"\s+");
+	}
+	while let Some(part) = parts.next() {
+		expression.push_str(&regex::escape(part));
+		if parts.peek().is_some() {
+			expression.push_str(r"\s+");
+		}
+	}
+	if literal.ends_with(char::is_whitespace) && !literal.trim().is_empty() {
+		expression.push_str(r"\s+");
+	}
+}
+
+/// The metadata which was extracted from a name using a [FilenamePattern]
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, Type, ToSchema)]
+pub struct FilenameMetadata {
+	pub series: Option<String>,
+	pub title: Option<String>,
+	pub volume: Option<i32>,
+	pub number: Option<f64>,
+	pub year: Option<i32>,
+}
+
+impl FilenameMetadata {
+	pub fn is_empty(&self) -> bool {
+		*self == Self::default()
+	}
+
+	/// Fills in any of the fields of the given metadata which are missing, leaving existing
+	/// values untouched
+	pub fn fill_missing(self, metadata: &mut MediaMetadata) {
+		metadata.series = metadata.series.take().or(self.series);
+		metadata.title = metadata.title.take().or(self.title);
+		metadata.volume = metadata.volume.or(self.volume);
+		metadata.number = metadata.number.or(self.number);
+		metadata.year = metadata.year.or(self.year);
+	}
+}
+
+// Note: Like IgnoreRules, the patterns are validated upon creation and the vector is not
+// public to enforce that
+/// An ordered list of [FilenamePattern]s configured for a library. The first pattern which
+/// matches a name is used.
+#[derive(Default, Debug, Clone, Serialize, Deserialize, Type, ToSchema)]
+pub struct FilenamePatterns(Vec<FilenamePattern>);
+
+impl FilenamePatterns {
+	/// Create a new set of patterns, validating that each one compiles
+	pub fn new(patterns: Vec<FilenamePattern>) -> CoreResult<Self> {
+		let this = Self(patterns);
+		this.build()?;
+		Ok(this)
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.0.is_empty()
+	}
+
+	pub fn patterns(&self) -> &[FilenamePattern] {
+		&self.0
+	}
+
+	/// Serialize the patterns to a byte vector, which gets dumped into the database
+	pub fn as_bytes(&self) -> CoreResult<Vec<u8>> {
+		serde_json::to_vec(self).map_err(|error| {
+			tracing::error!(?error, "Failed to serialize filename patterns");
+			error.into()
+		})
+	}
+
+	/// Compile the patterns so they may be matched against names
+	pub fn build(&self) -> CoreResult<CompiledFilenamePatterns> {
+		self.0
+			.iter()
+			.map(FilenamePattern::compile)
+			.collect::<CoreResult<Vec<_>>>()
+			.map(CompiledFilenamePatterns)
+	}
+}
+
+impl TryFrom<Vec<u8>> for FilenamePatterns {
+	type Error = CoreError;
+
+	fn try_from(value: Vec<u8>) -> Result<Self, Self::Error> {
+		serde_json::from_slice(&value).map_err(|error| {
+			tracing::error!(?error, "Failed to deserialize filename patterns");
+			error.into()
+		})
+	}
+}
+
+/// A compiled set of [FilenamePatterns]
+#[derive(Debug, Clone, Default)]
+pub struct CompiledFilenamePatterns(Vec<Regex>);
+
+impl CompiledFilenamePatterns {
+	/// Extract metadata from the given name using the first pattern which matches it
+	pub fn extract(&self, name: &str) -> Option<FilenameMetadata> {
+		self.0.iter().find_map(|regex| extract_with(regex, name))
+	}
+}
+
+fn extract_with(regex: &Regex, name: &str) -> Option<FilenameMetadata> {
+	let captures = regex.captures(name.trim())?;
+	let get = |group: &str| {
+		captures
+			.name(group)
+			.map(|m| m.as_str().trim().to_string())
+			.filter(|s| !s.is_empty())
+	};
+
+	let metadata = FilenameMetadata {
+		series: get("series"),
+		title: get("title"),
+		volume: get("volume").and_then(|v| v.parse().ok()),
+		number: get("number").and_then(|n| n.parse().ok()),
+		year: get("year").and_then(|y| y.parse().ok()),
+	};
+
+	(!metadata.is_empty()).then_some(metadata)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_template_extraction() {
+		let patterns = FilenamePatterns::new(vec![FilenamePattern::template(
+			"{series} v{volume} #{number} ({year})",
+		)])
+		.unwrap()
+		.build()
+		.unwrap();
+
+		assert_eq!(
+			patterns.extract("Berserk v01 #003 (1990)"),
+			Some(FilenameMetadata {
+				series: Some("Berserk".to_string()),
+				volume: Some(1),
+				number: Some(3.0),
+				year: Some(1990),
+				..Default::default()
+			})
+		);
+		assert_eq!(patterns.extract("Berserk 003"), None);
+	}
+
+	#[test]
+	fn test_first_matching_pattern_wins() {
+		let patterns = FilenamePatterns::new(vec![
+			FilenamePattern::template("{series} v{volume}"),
+			FilenamePattern::template("{series} #{number}{_}"),
+		])
+		.unwrap()
+		.build()
+		.unwrap();
+
+		assert_eq!(
+			patterns.extract("One Piece #1045.5 (Digital)"),
+			Some(FilenameMetadata {
+				series: Some("One Piece".to_string()),
+				number: Some(1045.5),
+				..Default::default()
+			})
+		);
+	}
+
+	#[test]
+	fn test_regex_extraction() {
+		let patterns = FilenamePatterns::new(vec![FilenamePattern::regex(
+			r"^(?P<series>.+?)_c(?P<number>\d+)$",
+		)])
+		.unwrap()
+		.build()
+		.unwrap();
+
+		let metadata = patterns.extract("Vagabond_c012").unwrap();
+		assert_eq!(metadata.series.as_deref(), Some("Vagabond"));
+		assert_eq!(metadata.number, Some(12.0));
+	}
+
+	#[test]
+	fn test_invalid_patterns() {
+		assert!(
+			FilenamePatterns::new(vec![FilenamePattern::template("{series")]).is_err()
+		);
+		assert!(
+			FilenamePatterns::new(vec![FilenamePattern::template("{unknown}")]).is_err()
+		);
+		assert!(FilenamePatterns::new(vec![FilenamePattern::regex(r"(\d+)")]).is_err());
+		assert!(
+			FilenamePatterns::new(vec![FilenamePattern::regex(r"(?P<number>")]).is_err()
+		);
+	}
+
+	#[test]
+	fn test_fill_missing() {
+		let mut metadata = MediaMetadata {
+			title: Some("Existing".to_string()),
+			..Default::default()
+		};
+		FilenameMetadata {
+			title: Some("From name".to_string()),
+			number: Some(2.0),
+			..Default::default()
+		}
+		.fill_missing(&mut metadata);
+
+		assert_eq!(metadata.title.as_deref(), Some("Existing"));
+		assert_eq!(metadata.number, Some(2.0));
+	}
+}
@@ -0,0 +1,99 @@
+use serde::{Deserialize, Serialize};
+use specta::Type;
+use utoipa::ToSchema;
+
+use crate::{db::entity::MetadataProviderKind, CoreError, CoreResult};
+
+/// The metadata providers used to identify the books of a library, in priority order.
+/// Candidates from earlier providers are preferred when they score equally well.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Type, ToSchema)]
+pub struct MetadataProviderPriority(Vec<MetadataProviderKind>);
+
+impl Default for MetadataProviderPriority {
+	fn default() -> Self {
+		Self(vec![
+			MetadataProviderKind::Sidecar,
+			MetadataProviderKind::OpenLibrary,
+			MetadataProviderKind::GoogleBooks,
+		])
+	}
+}
+
+impl MetadataProviderPriority {
+	/// Create a new priority order, validating that every provider can identify books and
+	/// is only listed once
+	pub fn new(providers: Vec<MetadataProviderKind>) -> CoreResult<Self> {
+		let this = Self(providers);
+		this.validate()?;
+		Ok(this)
+	}
+
+	pub fn validate(&self) -> CoreResult<()> {
+		for (index, provider) in self.0.iter().enumerate() {
+			if !provider.identifies_media() {
+				return Err(CoreError::BadRequest(format!(
+					"The {provider} provider cannot be used to identify books"
+				)));
+			}
+			if self.0[..index].contains(provider) {
+				return Err(CoreError::BadRequest(format!(
+					"The {provider} provider is listed more than once"
+				)));
+			}
+		}
+		Ok(())
+	}
+
+	pub fn providers(&self) -> &[MetadataProviderKind] {
+		&self.0
+	}
+
+	/// Serialize the providers to a byte vector, which gets dumped into the database
+	pub fn as_bytes(&self) -> CoreResult<Vec<u8>> {
+		serde_json::to_vec(self).map_err(|error| {
+			tracing::error!(?error, "Failed to serialize metadata provider priority");
+			error.into()
+		})
+	}
+}
+
+impl TryFrom<Vec<u8>> for MetadataProviderPriority {
+	type Error = CoreError;
+
+	fn try_from(value: Vec<u8>) -> Result<Self, Self::Error> {
+		serde_json::from_slice(&value).map_err(|error| {
+			tracing::error!(?error, "Failed to deserialize metadata provider priority");
+			error.into()
+		})
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_validate_priority() {
+		assert!(MetadataProviderPriority::default().validate().is_ok());
+		assert!(MetadataProviderPriority::new(vec![]).is_ok());
+		assert!(
+			MetadataProviderPriority::new(vec![MetadataProviderKind::ComicVine]).is_err()
+		);
+		assert!(MetadataProviderPriority::new(vec![
+			MetadataProviderKind::OpenLibrary,
+			MetadataProviderKind::OpenLibrary,
+		])
+		.is_err());
+	}
+
+	#[test]
+	fn test_priority_round_trip() {
+		let priority = MetadataProviderPriority::default();
+		let bytes = priority.as_bytes().unwrap();
+		assert_eq!(
+			String::from_utf8(bytes.clone()).unwrap(),
+			r#"["SIDECAR","OPEN_LIBRARY","GOOGLE_BOOKS"]"#
+		);
+		assert_eq!(MetadataProviderPriority::try_from(bytes).unwrap(), priority);
+	}
+}
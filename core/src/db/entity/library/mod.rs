@@ -1,9 +1,13 @@
 mod config;
 mod entity;
+mod filename_patterns;
+mod metadata_providers;
 pub(crate) mod prisma_macros;
 mod rules;
 pub(crate) mod utils;
 
 pub use config::*;
 pub use entity::*;
+pub use filename_patterns::*;
+pub use metadata_providers::*;
 pub use rules::*;
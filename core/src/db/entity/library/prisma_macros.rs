@@ -1,4 +1,4 @@
-use crate::prisma::library;
+use crate::{filesystem::scanner::LibraryRoots, prisma::library};
 
 library::select!(library_tags_select {
 	id
@@ -11,8 +11,21 @@ library::select!(library_tags_select {
 library::select!(library_path_with_options_select {
 	path
 	config
+	additional_roots: select {
+		path
+	}
 });
 
+impl library_path_with_options_select::Data {
+	/// The root paths of the library
+	pub fn roots(&self) -> LibraryRoots {
+		LibraryRoots::new(
+			&self.path,
+			self.additional_roots.iter().map(|root| &root.path),
+		)
+	}
+}
+
 library::include!(library_series_ids_media_ids_include {
 	series: include {
 		media: select { id }
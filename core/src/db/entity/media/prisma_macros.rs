@@ -11,6 +11,16 @@ media::select!(media_path_modified_at_select {
    status
 });
 
+media::select!(media_relocation_candidate_select {
+   id
+   name
+   size
+   extension
+   hash
+   koreader_hash
+   path
+});
+
 media::select!(media_thumbnail {
    id
    path
@@ -0,0 +1,47 @@
+use serde::{Deserialize, Serialize};
+use specta::Type;
+use utoipa::ToSchema;
+
+use crate::prisma::media_metadata_conflict;
+
+/// A recorded difference between the stored metadata of a media and the metadata which was
+/// read from its file, pending review. These are only created for libraries which use the
+/// `CONFLICT` merge strategy.
+#[derive(Debug, Clone, Serialize, Deserialize, Type, ToSchema)]
+pub struct MediaMetadataConflict {
+	pub id: String,
+	/// The name of the metadata field which differs, e.g. `title`
+	pub field: String,
+	/// The value currently stored for the field
+	pub current_value: Option<serde_json::Value>,
+	/// The value which was read from the file
+	pub incoming_value: Option<serde_json::Value>,
+	/// The id of the media the conflict belongs to
+	pub media_id: String,
+	pub created_at: String,
+}
+
+impl From<media_metadata_conflict::Data> for MediaMetadataConflict {
+	fn from(data: media_metadata_conflict::Data) -> Self {
+		let parse_value = |value: Option<String>| {
+			value.and_then(|v| {
+				serde_json::from_str(&v).map_or_else(
+					|error| {
+						tracing::error!(?error, "Failed to parse conflict value");
+						None
+					},
+					Some,
+				)
+			})
+		};
+
+		Self {
+			id: data.id,
+			field: data.field,
+			current_value: parse_value(data.current_value),
+			incoming_value: parse_value(data.incoming_value),
+			media_id: data.media_id,
+			created_at: data.created_at.to_rfc3339(),
+		}
+	}
+}
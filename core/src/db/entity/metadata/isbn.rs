@@ -0,0 +1,117 @@
+/// Normalize an ISBN to its digits (and a trailing `X` for ISBN-10s), e.g. from
+/// `urn:isbn:978-0-14-103614-4` to `9780141036144`. Returns [None] if the value is not a
+/// valid ISBN-10 or ISBN-13
+pub fn normalize_isbn(value: &str) -> Option<String> {
+	let lowercase = value.trim().to_lowercase();
+	let stripped = lowercase
+		.strip_prefix("urn:isbn:")
+		.or_else(|| lowercase.strip_prefix("isbn:"))
+		.or_else(|| lowercase.strip_prefix("isbn"))
+		.unwrap_or(&lowercase);
+
+	let isbn = stripped
+		.chars()
+		.filter(|c| !matches!(c, '-' | ' '))
+		.map(|c| c.to_ascii_uppercase())
+		.collect::<String>();
+
+	is_valid_isbn(&isbn).then_some(isbn)
+}
+
+fn is_valid_isbn(isbn: &str) -> bool {
+	let chars = isbn.chars().collect::<Vec<_>>();
+
+	match chars.len() {
+		10 => {
+			let Some(sum) = chars.iter().enumerate().try_fold(0, |sum, (i, c)| {
+				let value = match c {
+					'X' if i == 9 => 10,
+					c => c.to_digit(10)?,
+				};
+				Some(sum + value * (10 - i as u32))
+			}) else {
+				return false;
+			};
+			sum % 11 == 0
+		},
+		13 => {
+			let Some(sum) = chars.iter().enumerate().try_fold(0, |sum, (i, c)| {
+				let weight = if i % 2 == 0 { 1 } else { 3 };
+				Some(sum + c.to_digit(10)? * weight)
+			}) else {
+				return false;
+			};
+			sum % 10 == 0
+		},
+		_ => false,
+	}
+}
+
+/// Find the first valid ISBN in free text, e.g. the keywords of a PDF or the name of a file
+/// like `Nineteen Eighty-Four (978-0-14-103614-4).pdf`
+pub fn find_isbn(text: &str) -> Option<String> {
+	let mut candidate = String::new();
+
+	// Candidates are runs of digits, hyphens and spaces, which are checked once they end.
+	// The trailing sentinel makes sure the final run is checked too
+	for c in text.chars().chain(std::iter::once('\0')) {
+		if c.is_ascii_digit() || matches!(c, '-' | ' ' | 'X' | 'x') {
+			candidate.push(c);
+			if c.is_ascii_alphabetic() {
+				if let Some(isbn) = normalize_isbn(&candidate) {
+					return Some(isbn);
+				}
+				candidate.clear();
+			}
+			continue;
+		}
+
+		if let Some(isbn) = normalize_isbn(&candidate) {
+			return Some(isbn);
+		}
+		candidate.clear();
+	}
+
+	None
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_normalize_isbn() {
+		assert_eq!(
+			normalize_isbn("urn:isbn:978-0-14-103614-4"),
+			Some("9780141036144".to_string())
+		);
+		assert_eq!(
+			normalize_isbn("ISBN 0-14-103614-1"),
+			Some("0141036141".to_string())
+		);
+		assert_eq!(
+			normalize_isbn("0-8044-2957-x"),
+			Some("080442957X".to_string())
+		);
+	}
+
+	#[test]
+	fn test_normalize_invalid_isbn() {
+		assert_eq!(normalize_isbn("9780141036145"), None);
+		assert_eq!(normalize_isbn("urn:uuid:1f2a5e4c-0000"), None);
+		assert_eq!(normalize_isbn("12345"), None);
+	}
+
+	#[test]
+	fn test_find_isbn() {
+		assert_eq!(
+			find_isbn("Nineteen Eighty-Four (978-0-14-103614-4)"),
+			Some("9780141036144".to_string())
+		);
+		assert_eq!(
+			find_isbn("dystopia, 0-8044-2957-X, fiction"),
+			Some("080442957X".to_string())
+		);
+		assert_eq!(find_isbn("Volume 1 - 2012"), None);
+	}
+}
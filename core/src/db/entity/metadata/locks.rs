@@ -0,0 +1,81 @@
+//! Field-level locks on metadata. A locked field was edited manually, and so is kept as is
+//! when metadata is refreshed from a file or fetched from a provider.
+
+use crate::{CoreError, CoreResult};
+
+use super::common::comma_separated_list_to_vec;
+
+/// Validate the fields to lock against the fields which can be locked, removing duplicates
+pub(crate) fn validate_locked_fields(
+	fields: Vec<String>,
+	lockable: &[&str],
+) -> CoreResult<Vec<String>> {
+	let mut validated = Vec::with_capacity(fields.len());
+	for field in fields {
+		if !lockable.contains(&field.as_str()) {
+			return Err(CoreError::BadRequest(format!(
+				"The field {field} cannot be locked"
+			)));
+		}
+		if !validated.contains(&field) {
+			validated.push(field);
+		}
+	}
+	Ok(validated)
+}
+
+/// Add fields to a set of locked fields, keeping the existing order
+pub(crate) fn extend_locked_fields(locked: &mut Vec<String>, fields: Vec<String>) {
+	for field in fields {
+		if !locked.contains(&field) {
+			locked.push(field);
+		}
+	}
+}
+
+/// Convert locked fields to how they are stored in the database
+pub(crate) fn locked_fields_to_db(fields: &[String]) -> Option<String> {
+	(!fields.is_empty()).then(|| fields.join(", "))
+}
+
+/// Convert locked fields from how they are stored in the database
+pub(crate) fn locked_fields_from_db(fields: Option<String>) -> Vec<String> {
+	fields
+		.filter(|fields| !fields.trim().is_empty())
+		.map(comma_separated_list_to_vec)
+		.unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	const LOCKABLE: [&str; 3] = ["title", "summary", "writers"];
+
+	#[test]
+	fn test_validate_locked_fields() {
+		let fields = vec![
+			"title".to_string(),
+			"writers".to_string(),
+			"title".to_string(),
+		];
+		assert_eq!(
+			validate_locked_fields(fields, &LOCKABLE).unwrap(),
+			vec!["title", "writers"]
+		);
+		assert!(
+			validate_locked_fields(vec!["page_count".to_string()], &LOCKABLE).is_err()
+		);
+	}
+
+	#[test]
+	fn test_locked_fields_round_trip() {
+		let fields = vec!["title".to_string(), "summary".to_string()];
+		let stored = locked_fields_to_db(&fields);
+		assert_eq!(stored.as_deref(), Some("title, summary"));
+		assert_eq!(locked_fields_from_db(stored), fields);
+
+		assert_eq!(locked_fields_to_db(&[]), None);
+		assert!(locked_fields_from_db(Some(String::new())).is_empty());
+	}
+}
@@ -11,11 +11,16 @@ use utoipa::ToSchema;
 
 use crate::{
 	db::entity::{
-		metadata::common::{
-			age_rating_deserializer, comma_separated_list_to_vec, parse_age_restriction,
-			string_list_deserializer,
+		metadata::{
+			common::{
+				age_rating_deserializer, comma_separated_list_to_vec,
+				parse_age_restriction, string_list_deserializer,
+			},
+			isbn::{find_isbn, normalize_isbn},
+			locks::{locked_fields_from_db, locked_fields_to_db},
 		},
 		page_dimension::PageDimensionsEntity,
+		MediaStoryArc,
 	},
 	prisma::media_metadata,
 };
@@ -138,6 +143,9 @@ pub struct MediaMetadata {
 	/// The publisher of the associated media
 	#[serde(alias = "Publisher", skip_serializing_if = "Option::is_none")]
 	pub publisher: Option<String>,
+	/// The ISBN of the associated media, normalized to its digits
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub isbn: Option<String>,
 
 	/// Link(s) to the associated media, e.g. a comixology link
 	#[serde(
@@ -176,6 +184,15 @@ pub struct MediaMetadata {
 		skip_serializing_if = "Option::is_none"
 	)]
 	pub page_dimensions: Option<PageDimensionsEntity>,
+	/// The fields which were edited manually, and so are not overwritten by the scanner or
+	/// by metadata providers. Any of [MediaMetadata::MERGEABLE_FIELDS] may be locked.
+	#[serde(default, skip_deserializing, skip_serializing_if = "Vec::is_empty")]
+	pub locked_fields: Vec<String>,
+	/// The story arcs the media is part of, as read from its file. These are stored as
+	/// [StoryArcMember](crate::db::entity::StoryArcMember)s rather than with the rest of the
+	/// metadata, so this is always empty for metadata loaded from the database.
+	#[serde(skip)]
+	pub story_arcs: Vec<MediaStoryArc>,
 	//#[serde(alias = "Resolutions", default = "Option::default")]
 	//pub resolutions: Option<Vec<Resolution>>,
 	// TODO: pages, e.g. <Pages><Page Image="0" Type="FrontCover" ImageSize="741291" /></Pages>
@@ -203,12 +220,19 @@ impl MediaMetadata {
 			media_metadata::cover_artists::set(self.cover_artists.map(|v| v.join(", "))),
 			media_metadata::editors::set(self.editors.map(|v| v.join(", "))),
 			media_metadata::publisher::set(self.publisher),
+			media_metadata::isbn::set(self.isbn),
 			media_metadata::links::set(self.links.map(|v| v.join(", "))),
 			media_metadata::characters::set(self.characters.map(|v| v.join(", "))),
 			media_metadata::teams::set(self.teams.map(|v| v.join(", "))),
 			media_metadata::page_count::set(self.page_count),
 		]
 	}
+
+	/// The param which stores the locked fields. These are deliberately not part of
+	/// [MediaMetadata::into_prisma], so that only manual edits change them
+	pub fn locked_fields_param(&self) -> media_metadata::SetParam {
+		media_metadata::locked_fields::set(locked_fields_to_db(&self.locked_fields))
+	}
 }
 
 impl From<media_metadata::Data> for MediaMetadata {
@@ -239,11 +263,14 @@ impl From<media_metadata::Data> for MediaMetadata {
 			cover_artists: metadata.cover_artists.map(comma_separated_list_to_vec),
 			editors: metadata.editors.map(comma_separated_list_to_vec),
 			publisher: metadata.publisher,
+			isbn: metadata.isbn,
 			links: metadata.links.map(comma_separated_list_to_vec),
 			characters: metadata.characters.map(comma_separated_list_to_vec),
 			teams: metadata.teams.map(comma_separated_list_to_vec),
 			page_count: metadata.page_count,
 			page_dimensions,
+			locked_fields: locked_fields_from_db(metadata.locked_fields),
+			story_arcs: vec![],
 		}
 	}
 }
@@ -285,6 +312,10 @@ impl From<HashMap<String, Vec<String>>> for MediaMetadata {
 				"coverartists" => metadata.cover_artists = Some(value),
 				"editors" => metadata.editors = Some(value),
 				"publisher" => metadata.publisher = Some(value.join("\n").to_string()),
+				"identifier" | "isbn" => {
+					// EPUBs may have several identifiers, e.g. a UUID alongside the ISBN
+					metadata.isbn = value.iter().find_map(|v| normalize_isbn(v));
+				},
 				"links" => metadata.links = Some(value),
 				"characters" => metadata.characters = Some(value),
 				"teams" => metadata.teams = Some(value),
@@ -373,13 +404,20 @@ fn pdf_string_to_string(pdf_string: PdfString) -> Option<String> {
 
 impl From<InfoDict> for MediaMetadata {
 	fn from(dict: InfoDict) -> Self {
+		let subject = dict.subject.and_then(pdf_string_to_string);
+		let isbn = subject
+			.iter()
+			.chain(dict.keywords.and_then(pdf_string_to_string).iter())
+			.find_map(|v| find_isbn(v));
+
 		MediaMetadata {
 			title: dict.title.and_then(pdf_string_to_string),
-			genre: dict.subject.and_then(pdf_string_to_string).map(|v| vec![v]),
+			genre: subject.map(|v| vec![v]),
 			year: dict.creation_date.as_ref().map(|date| date.year as i32),
 			month: dict.creation_date.as_ref().map(|date| date.month as i32),
 			day: dict.creation_date.as_ref().map(|date| date.day as i32),
 			writers: dict.author.and_then(pdf_string_to_string).map(|v| vec![v]),
+			isbn,
 			..Default::default()
 		}
 	}
@@ -428,6 +466,23 @@ mod tests {
 		assert_eq!(metadata.summary, Some("A book, you know?".to_string()));
 	}
 
+	#[test]
+	fn test_isbn_from_hashmap() {
+		let mut map = HashMap::new();
+
+		map.insert(
+			"identifier".to_string(),
+			vec![
+				String::from("urn:uuid:a8a3f4d8-3c5e-4b5e-9d9f-3a5c1c0e7b1a"),
+				String::from("urn:isbn:978-0-14-103614-4"),
+			],
+		);
+
+		let metadata = MediaMetadata::from(map);
+
+		assert_eq!(metadata.isbn, Some("9780141036144".to_string()));
+	}
+
 	#[test]
 	fn test_resolve_multiple_age_ratings() {
 		let mut map = HashMap::new();
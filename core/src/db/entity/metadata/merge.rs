@@ -0,0 +1,403 @@
+use std::{fmt, str::FromStr};
+
+use serde::{Deserialize, Serialize};
+use specta::Type;
+use utoipa::ToSchema;
+
+use crate::{CoreError, CoreResult};
+
+use super::{
+	locks::{extend_locked_fields, validate_locked_fields},
+	MediaMetadata,
+};
+
+/// The strategy used to reconcile metadata read from a file with the metadata already stored
+/// for it, e.g. when a book is rebuilt during a scan
+#[derive(
+	Serialize, Deserialize, Debug, Default, PartialEq, Eq, Copy, Clone, Type, ToSchema,
+)]
+pub enum MergeStrategy {
+	/// Overwrite the stored metadata with whatever was read from the file
+	#[default]
+	#[serde(rename = "REPLACE")]
+	Replace,
+	/// Only fill in fields which are currently empty, keeping any existing values
+	#[serde(rename = "MERGE")]
+	Merge,
+	/// Fill in empty fields, but record any differing values as conflicts to be reviewed
+	/// instead of overwriting them
+	#[serde(rename = "CONFLICT")]
+	Conflict,
+}
+
+impl FromStr for MergeStrategy {
+	type Err = String;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		let uppercase = s.to_uppercase();
+
+		match uppercase.as_str() {
+			"REPLACE" => Ok(MergeStrategy::Replace),
+			"MERGE" => Ok(MergeStrategy::Merge),
+			"CONFLICT" => Ok(MergeStrategy::Conflict),
+			"" => Ok(MergeStrategy::default()),
+			_ => Err(format!("Invalid merge strategy: {s}")),
+		}
+	}
+}
+
+impl From<String> for MergeStrategy {
+	fn from(s: String) -> Self {
+		MergeStrategy::from_str(&s).unwrap_or_default()
+	}
+}
+
+impl fmt::Display for MergeStrategy {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			MergeStrategy::Replace => write!(f, "REPLACE"),
+			MergeStrategy::Merge => write!(f, "MERGE"),
+			MergeStrategy::Conflict => write!(f, "CONFLICT"),
+		}
+	}
+}
+
+/// A single field which differs between the stored and the incoming metadata
+#[derive(Debug, Clone, PartialEq)]
+pub struct MetadataFieldConflict {
+	pub field: String,
+	pub current_value: serde_json::Value,
+	pub incoming_value: serde_json::Value,
+}
+
+/// The result of [MediaMetadata::merge]
+#[derive(Debug)]
+pub struct MetadataMergeResult {
+	/// The metadata which should be persisted
+	pub metadata: MediaMetadata,
+	/// The fields which could not be merged, only populated for [MergeStrategy::Conflict]
+	pub conflicts: Vec<MetadataFieldConflict>,
+}
+
+fn merge_field<T: PartialEq + Serialize>(
+	field: &str,
+	current: Option<T>,
+	incoming: Option<T>,
+	strategy: MergeStrategy,
+	conflicts: &mut Vec<MetadataFieldConflict>,
+) -> Option<T> {
+	match strategy {
+		MergeStrategy::Replace => incoming,
+		MergeStrategy::Merge => current.or(incoming),
+		MergeStrategy::Conflict => match (current, incoming) {
+			(Some(current), Some(incoming)) if current != incoming => {
+				conflicts.push(MetadataFieldConflict {
+					field: field.to_string(),
+					current_value: serde_json::to_value(&current).unwrap_or_default(),
+					incoming_value: serde_json::to_value(&incoming).unwrap_or_default(),
+				});
+				Some(current)
+			},
+			(current, incoming) => current.or(incoming),
+		},
+	}
+}
+
+/// Generates the field-wise operations for the metadata fields which are subject to a
+/// [MergeStrategy]. Fields which are derived from the file itself (e.g. `page_count`) are
+/// intentionally excluded.
+macro_rules! mergeable_fields {
+	($($field:ident),* $(,)?) => {
+		impl MediaMetadata {
+			/// The names of the fields which are subject to a [MergeStrategy]
+			pub const MERGEABLE_FIELDS: &'static [&'static str] = &[$(stringify!($field)),*];
+
+			fn merge_fields(
+				&mut self,
+				incoming: MediaMetadata,
+				strategy: MergeStrategy,
+				conflicts: &mut Vec<MetadataFieldConflict>,
+			) {
+				$(
+					// Locked fields were edited manually, so they are kept as they are
+					if !self.is_locked(stringify!($field)) {
+						self.$field = merge_field(
+							stringify!($field),
+							self.$field.take(),
+							incoming.$field,
+							strategy,
+							conflicts,
+						);
+					}
+				)*
+			}
+
+			/// The names of the mergeable fields whose values differ from those of `other`
+			pub fn changed_fields(&self, other: &MediaMetadata) -> Vec<String> {
+				let mut changed = vec![];
+				$(
+					if self.$field != other.$field {
+						changed.push(stringify!($field).to_string());
+					}
+				)*
+				changed
+			}
+
+			/// Sets a single mergeable field from its JSON representation, e.g. when accepting
+			/// a previously recorded conflict
+			pub fn set_field_value(
+				&mut self,
+				field: &str,
+				value: serde_json::Value,
+			) -> CoreResult<()> {
+				match field {
+					$(stringify!($field) => self.$field = serde_json::from_value(value)?,)*
+					_ => {
+						return Err(CoreError::BadRequest(format!(
+							"Unknown metadata field: {field}"
+						)))
+					},
+				}
+				Ok(())
+			}
+		}
+	};
+}
+
+mergeable_fields!(
+	title,
+	series,
+	number,
+	volume,
+	summary,
+	notes,
+	age_rating,
+	genre,
+	year,
+	month,
+	day,
+	writers,
+	pencillers,
+	inkers,
+	colorists,
+	letterers,
+	cover_artists,
+	editors,
+	publisher,
+	isbn,
+	links,
+	characters,
+	teams,
+);
+
+impl MediaMetadata {
+	/// Reconciles newly read metadata with the metadata currently stored for a media,
+	/// according to the given [MergeStrategy]
+	pub fn merge(
+		current: Option<MediaMetadata>,
+		incoming: MediaMetadata,
+		strategy: MergeStrategy,
+	) -> MetadataMergeResult {
+		let Some(mut current) = current else {
+			return MetadataMergeResult {
+				metadata: incoming,
+				conflicts: vec![],
+			};
+		};
+
+		let mut conflicts = vec![];
+		let page_count = incoming.page_count.or(current.page_count);
+		current.merge_fields(incoming, strategy, &mut conflicts);
+		current.page_count = page_count;
+
+		MetadataMergeResult {
+			metadata: current,
+			conflicts,
+		}
+	}
+
+	/// Whether a field was edited manually, and so must not be overwritten
+	pub fn is_locked(&self, field: &str) -> bool {
+		self.locked_fields.iter().any(|locked| locked == field)
+	}
+
+	/// Replace the locked fields, erroring if any of them can't be locked
+	pub fn set_locked_fields(&mut self, fields: Vec<String>) -> CoreResult<()> {
+		self.locked_fields = validate_locked_fields(fields, Self::MERGEABLE_FIELDS)?;
+		Ok(())
+	}
+
+	/// Apply a manual edit, locking every field which it changes so that the edit is not
+	/// lost the next time the media is scanned or enriched
+	pub fn apply_manual_edit(
+		current: Option<MediaMetadata>,
+		edited: MediaMetadata,
+	) -> Self {
+		let mut locked_fields = current
+			.as_ref()
+			.map(|current| current.locked_fields.clone())
+			.unwrap_or_default();
+		let changed = edited.changed_fields(&current.unwrap_or_default());
+		extend_locked_fields(&mut locked_fields, changed);
+
+		MediaMetadata {
+			locked_fields,
+			..edited
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn stored() -> MediaMetadata {
+		MediaMetadata {
+			title: Some("Manually edited title".to_string()),
+			summary: None,
+			writers: Some(vec!["Alan Moore".to_string()]),
+			..Default::default()
+		}
+	}
+
+	fn incoming() -> MediaMetadata {
+		MediaMetadata {
+			title: Some("Title from ComicInfo".to_string()),
+			summary: Some("A summary".to_string()),
+			writers: Some(vec!["Alan Moore".to_string()]),
+			page_count: Some(24),
+			..Default::default()
+		}
+	}
+
+	#[test]
+	fn test_merge_strategy_from_str() {
+		assert_eq!(
+			MergeStrategy::from_str("conflict").unwrap(),
+			MergeStrategy::Conflict
+		);
+		assert_eq!(MergeStrategy::from(String::new()), MergeStrategy::Replace);
+		assert!(MergeStrategy::from_str("invalid").is_err());
+	}
+
+	#[test]
+	fn test_replace() {
+		let result =
+			MediaMetadata::merge(Some(stored()), incoming(), MergeStrategy::Replace);
+		assert_eq!(
+			result.metadata.title.as_deref(),
+			Some("Title from ComicInfo")
+		);
+		assert_eq!(result.metadata.summary.as_deref(), Some("A summary"));
+		assert!(result.conflicts.is_empty());
+	}
+
+	#[test]
+	fn test_merge_only_fills_empty_fields() {
+		let result =
+			MediaMetadata::merge(Some(stored()), incoming(), MergeStrategy::Merge);
+		assert_eq!(
+			result.metadata.title.as_deref(),
+			Some("Manually edited title")
+		);
+		assert_eq!(result.metadata.summary.as_deref(), Some("A summary"));
+		assert_eq!(result.metadata.page_count, Some(24));
+		assert!(result.conflicts.is_empty());
+	}
+
+	#[test]
+	fn test_conflict_records_differences() {
+		let result =
+			MediaMetadata::merge(Some(stored()), incoming(), MergeStrategy::Conflict);
+		assert_eq!(
+			result.metadata.title.as_deref(),
+			Some("Manually edited title")
+		);
+		assert_eq!(result.metadata.summary.as_deref(), Some("A summary"));
+		assert_eq!(
+			result.conflicts,
+			vec![MetadataFieldConflict {
+				field: "title".to_string(),
+				current_value: serde_json::json!("Manually edited title"),
+				incoming_value: serde_json::json!("Title from ComicInfo"),
+			}]
+		);
+	}
+
+	#[test]
+	fn test_merge_without_existing_metadata() {
+		let result = MediaMetadata::merge(None, incoming(), MergeStrategy::Conflict);
+		assert_eq!(
+			result.metadata.title.as_deref(),
+			Some("Title from ComicInfo")
+		);
+		assert!(result.conflicts.is_empty());
+	}
+
+	#[test]
+	fn test_locked_fields_are_kept() {
+		let current = MediaMetadata {
+			locked_fields: vec!["title".to_string()],
+			..stored()
+		};
+
+		for strategy in [
+			MergeStrategy::Replace,
+			MergeStrategy::Merge,
+			MergeStrategy::Conflict,
+		] {
+			let result =
+				MediaMetadata::merge(Some(current.clone()), incoming(), strategy);
+			assert_eq!(
+				result.metadata.title.as_deref(),
+				Some("Manually edited title")
+			);
+			assert_eq!(result.metadata.summary.as_deref(), Some("A summary"));
+			assert_eq!(result.metadata.locked_fields, vec!["title"]);
+			assert!(result.conflicts.is_empty());
+		}
+	}
+
+	#[test]
+	fn test_manual_edit_locks_changed_fields() {
+		let current = MediaMetadata {
+			locked_fields: vec!["summary".to_string()],
+			..stored()
+		};
+		let edited = MediaMetadata {
+			title: Some("Fixed title".to_string()),
+			..stored()
+		};
+
+		let metadata = MediaMetadata::apply_manual_edit(Some(current), edited);
+		assert_eq!(metadata.title.as_deref(), Some("Fixed title"));
+		assert_eq!(metadata.locked_fields, vec!["summary", "title"]);
+
+		let metadata = MediaMetadata::apply_manual_edit(None, stored());
+		assert_eq!(metadata.locked_fields, vec!["title", "writers"]);
+	}
+
+	#[test]
+	fn test_set_locked_fields() {
+		let mut metadata = stored();
+		metadata
+			.set_locked_fields(vec!["title".to_string(), "isbn".to_string()])
+			.unwrap();
+		assert_eq!(metadata.locked_fields, vec!["title", "isbn"]);
+		assert!(metadata
+			.set_locked_fields(vec!["page_count".to_string()])
+			.is_err());
+	}
+
+	#[test]
+	fn test_set_field_value() {
+		let mut metadata = stored();
+		metadata
+			.set_field_value("writers", serde_json::json!(["Grant Morrison"]))
+			.unwrap();
+		assert_eq!(metadata.writers, Some(vec!["Grant Morrison".to_string()]));
+		assert!(metadata
+			.set_field_value("not_a_field", serde_json::Value::Null)
+			.is_err());
+	}
+}
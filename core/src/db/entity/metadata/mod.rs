@@ -1,10 +1,21 @@
 mod common;
+mod conflict;
+mod isbn;
+mod locks;
 mod media_metadata;
+mod merge;
 pub mod page_dimension;
 pub(crate) mod prisma_macros;
+mod provider;
 mod series_metadata;
 
-pub use common::{age_rating_deserializer, parse_age_restriction};
+pub use common::{
+	age_rating_deserializer, comma_separated_list_to_vec, parse_age_restriction,
+};
+pub use conflict::*;
+pub use isbn::{find_isbn, normalize_isbn};
 pub use media_metadata::*;
+pub use merge::*;
 pub use page_dimension::{PageDimension, PageDimensionsEntity};
+pub use provider::*;
 pub use series_metadata::*;
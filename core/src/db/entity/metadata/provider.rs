@@ -0,0 +1,197 @@
+use std::{fmt, str::FromStr};
+
+use serde::{Deserialize, Serialize};
+use specta::Type;
+use utoipa::ToSchema;
+
+use crate::{
+	prisma::{metadata_provider_config, PrismaClient},
+	utils::{decrypt_string, encrypt_string, get_encryption_key},
+	CoreError, CoreResult, Ctx,
+};
+
+/// An external service which metadata can be fetched from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema, Type)]
+pub enum MetadataProviderKind {
+	#[serde(rename = "COMICVINE")]
+	ComicVine,
+	#[serde(rename = "GOOGLE_BOOKS")]
+	GoogleBooks,
+	#[serde(rename = "OPEN_LIBRARY")]
+	OpenLibrary,
+	/// Metadata files stored next to books, e.g. `.opf` files written by Calibre
+	#[serde(rename = "SIDECAR")]
+	Sidecar,
+}
+
+impl MetadataProviderKind {
+	/// Every provider which can be configured
+	pub const ALL: [MetadataProviderKind; 4] = [
+		MetadataProviderKind::ComicVine,
+		MetadataProviderKind::GoogleBooks,
+		MetadataProviderKind::OpenLibrary,
+		MetadataProviderKind::Sidecar,
+	];
+
+	/// Whether the provider can only be used once an API key has been set
+	pub fn requires_api_key(&self) -> bool {
+		matches!(
+			self,
+			MetadataProviderKind::ComicVine | MetadataProviderKind::GoogleBooks
+		)
+	}
+
+	/// Whether the provider can identify individual books. ComicVine is only used to match
+	/// whole series
+	pub fn identifies_media(&self) -> bool {
+		*self != MetadataProviderKind::ComicVine
+	}
+}
+
+impl fmt::Display for MetadataProviderKind {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			MetadataProviderKind::ComicVine => write!(f, "COMICVINE"),
+			MetadataProviderKind::GoogleBooks => write!(f, "GOOGLE_BOOKS"),
+			MetadataProviderKind::OpenLibrary => write!(f, "OPEN_LIBRARY"),
+			MetadataProviderKind::Sidecar => write!(f, "SIDECAR"),
+		}
+	}
+}
+
+impl FromStr for MetadataProviderKind {
+	type Err = String;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		let uppercase = s.to_uppercase();
+
+		match uppercase.as_str() {
+			"COMICVINE" => Ok(MetadataProviderKind::ComicVine),
+			"GOOGLE_BOOKS" => Ok(MetadataProviderKind::GoogleBooks),
+			"OPEN_LIBRARY" => Ok(MetadataProviderKind::OpenLibrary),
+			"SIDECAR" => Ok(MetadataProviderKind::Sidecar),
+			_ => Err(format!("Invalid metadata provider: {s}")),
+		}
+	}
+}
+
+/// The stored configuration of a metadata provider. The API key is encrypted at rest and
+/// is never sent back to clients, only whether one has been set
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema, Type)]
+pub struct MetadataProviderConfig {
+	pub provider: MetadataProviderKind,
+	pub enabled: bool,
+	pub has_api_key: bool,
+}
+
+impl MetadataProviderConfig {
+	/// The config of a provider which has not been configured yet. Providers which don't
+	/// need an API key are enabled until they are explicitly disabled
+	pub fn unconfigured(provider: MetadataProviderKind) -> Self {
+		Self {
+			provider,
+			enabled: !provider.requires_api_key(),
+			has_api_key: false,
+		}
+	}
+}
+
+impl TryFrom<metadata_provider_config::Data> for MetadataProviderConfig {
+	type Error = CoreError;
+
+	fn try_from(data: metadata_provider_config::Data) -> Result<Self, Self::Error> {
+		Ok(MetadataProviderConfig {
+			provider: MetadataProviderKind::from_str(&data.provider)
+				.map_err(CoreError::InternalError)?,
+			enabled: data.enabled,
+			has_api_key: data.encrypted_api_key.is_some(),
+		})
+	}
+}
+
+#[derive(Debug, Deserialize, ToSchema, Type)]
+pub struct MetadataProviderConfigInput {
+	/// Whether the provider may be used. Defaults to the current value
+	pub enabled: Option<bool>,
+	/// The API key to use for the provider, which is encrypted before being stored. An
+	/// empty string will clear the current key
+	pub api_key: Option<String>,
+}
+
+impl MetadataProviderConfigInput {
+	pub async fn into_params(
+		self,
+		ctx: &Ctx,
+	) -> CoreResult<Vec<metadata_provider_config::SetParam>> {
+		let mut params = vec![];
+
+		if let Some(enabled) = self.enabled {
+			params.push(metadata_provider_config::enabled::set(enabled));
+		}
+
+		match self.api_key.as_deref().map(str::trim) {
+			Some("") => {
+				params.push(metadata_provider_config::encrypted_api_key::set(None))
+			},
+			Some(api_key) => {
+				let encryption_key = ctx.get_encryption_key().await?;
+				let encrypted_api_key = encrypt_string(api_key, &encryption_key)?;
+				params.push(metadata_provider_config::encrypted_api_key::set(Some(
+					encrypted_api_key,
+				)));
+			},
+			None => {},
+		}
+
+		Ok(params)
+	}
+}
+
+/// Get the decrypted API key of a provider, erroring if the provider is disabled or has not
+/// been given a key
+pub async fn get_provider_api_key(
+	client: &PrismaClient,
+	provider: MetadataProviderKind,
+) -> CoreResult<String> {
+	let config = client
+		.metadata_provider_config()
+		.find_unique(metadata_provider_config::provider::equals(
+			provider.to_string(),
+		))
+		.exec()
+		.await?
+		.filter(|config| config.enabled)
+		.ok_or_else(|| {
+			CoreError::BadRequest(format!("The {provider} provider is not enabled"))
+		})?;
+
+	let encrypted_api_key = config.encrypted_api_key.ok_or_else(|| {
+		CoreError::BadRequest(format!("The {provider} provider has no API key set"))
+	})?;
+	let encryption_key = get_encryption_key(client).await?;
+
+	decrypt_string(&encrypted_api_key, &encryption_key)
+}
+
+/// Whether a provider may be used. Providers which need an API key must be configured
+/// first, while the others are enabled until they are explicitly disabled
+pub async fn is_provider_enabled(
+	client: &PrismaClient,
+	provider: MetadataProviderKind,
+) -> CoreResult<bool> {
+	let config = client
+		.metadata_provider_config()
+		.find_unique(metadata_provider_config::provider::equals(
+			provider.to_string(),
+		))
+		.exec()
+		.await?;
+
+	Ok(match config {
+		Some(config) => {
+			config.enabled
+				&& (!provider.requires_api_key() || config.encrypted_api_key.is_some())
+		},
+		None => !provider.requires_api_key(),
+	})
+}
@@ -2,11 +2,17 @@ use serde::{Deserialize, Serialize};
 use specta::Type;
 use utoipa::ToSchema;
 
-use crate::prisma::series_metadata;
+use crate::{prisma::series_metadata, CoreResult};
 
-use super::common::age_rating_deserializer;
+use super::{
+	common::age_rating_deserializer,
+	locks::{
+		extend_locked_fields, locked_fields_from_db, locked_fields_to_db,
+		validate_locked_fields,
+	},
+};
 
-#[derive(Debug, Clone, Deserialize, Serialize, Type, ToSchema)]
+#[derive(Debug, Clone, Default, Deserialize, Serialize, Type, ToSchema)]
 pub struct SeriesMetadata {
 	/// The type of series. ex: "comicSeries"
 	#[serde(alias = "type")]
@@ -30,8 +36,48 @@ pub struct SeriesMetadata {
 	pub age_rating: Option<i32>,
 	/// The status of the associated series, e.g. Continuing, Ended
 	pub status: Option<String>,
+	/// The fields which were edited manually, and so are not overwritten by metadata
+	/// providers. Any of [SeriesMetadata::LOCKABLE_FIELDS] may be locked.
+	#[serde(default, skip_deserializing)]
+	pub locked_fields: Vec<String>,
 }
 
+/// Generates the operations for the fields of [SeriesMetadata] which can be locked
+macro_rules! lockable_fields {
+	($($field:ident),* $(,)?) => {
+		impl SeriesMetadata {
+			/// The names of the fields which can be locked
+			pub const LOCKABLE_FIELDS: &'static [&'static str] = &[$(stringify!($field)),*];
+
+			/// The names of the lockable fields whose values differ from those of `other`
+			pub fn changed_fields(&self, other: &SeriesMetadata) -> Vec<String> {
+				let mut changed = vec![];
+				$(
+					if self.$field != other.$field {
+						changed.push(stringify!($field).to_string());
+					}
+				)*
+				changed
+			}
+
+			/// Restore the values of the fields which are locked in `current`, e.g. after
+			/// filling the metadata from a provider
+			pub fn keep_locked(&mut self, current: &SeriesMetadata) {
+				$(
+					if current.is_locked(stringify!($field)) {
+						self.$field = current.$field.clone();
+					}
+				)*
+				self.locked_fields = current.locked_fields.clone();
+			}
+		}
+	};
+}
+
+lockable_fields!(
+	title, summary, publisher, imprint, comicid, volume, booktype, age_rating, status,
+);
+
 pub type SeriesMetadataCreateAction = (String, Vec<series_metadata::SetParam>);
 
 impl SeriesMetadata {
@@ -51,6 +97,43 @@ impl SeriesMetadata {
 			],
 		)
 	}
+
+	/// The param which stores the locked fields. These are deliberately not part of
+	/// [SeriesMetadata::create_action], so that only manual edits change them
+	pub fn locked_fields_param(&self) -> series_metadata::SetParam {
+		series_metadata::locked_fields::set(locked_fields_to_db(&self.locked_fields))
+	}
+
+	/// Whether a field was edited manually, and so must not be overwritten
+	pub fn is_locked(&self, field: &str) -> bool {
+		self.locked_fields.iter().any(|locked| locked == field)
+	}
+
+	/// Replace the locked fields, erroring if any of them can't be locked
+	pub fn set_locked_fields(&mut self, fields: Vec<String>) -> CoreResult<()> {
+		self.locked_fields = validate_locked_fields(fields, Self::LOCKABLE_FIELDS)?;
+		Ok(())
+	}
+
+	/// Apply a manual edit, locking every field which it changes
+	pub fn apply_manual_edit(
+		current: Option<SeriesMetadata>,
+		edited: SeriesMetadata,
+	) -> Self {
+		let (mut locked_fields, changed) = match current {
+			Some(current) => (
+				current.locked_fields.clone(),
+				edited.changed_fields(&current),
+			),
+			None => (vec![], edited.changed_fields(&SeriesMetadata::default())),
+		};
+		extend_locked_fields(&mut locked_fields, changed);
+
+		SeriesMetadata {
+			locked_fields,
+			..edited
+		}
+	}
 }
 
 impl From<series_metadata::Data> for SeriesMetadata {
@@ -66,6 +149,49 @@ impl From<series_metadata::Data> for SeriesMetadata {
 			booktype: metadata.booktype,
 			age_rating: metadata.age_rating,
 			status: metadata.status,
+			locked_fields: locked_fields_from_db(metadata.locked_fields),
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn stored() -> SeriesMetadata {
+		SeriesMetadata {
+			_type: "comicSeries".to_string(),
+			title: Some("Saga".to_string()),
+			publisher: Some("Image".to_string()),
+			..Default::default()
 		}
 	}
+
+	#[test]
+	fn test_manual_edit_locks_changed_fields() {
+		let edited = SeriesMetadata {
+			summary: Some("A space opera".to_string()),
+			..stored()
+		};
+		let metadata = SeriesMetadata::apply_manual_edit(Some(stored()), edited);
+		assert_eq!(metadata.locked_fields, vec!["summary"]);
+	}
+
+	#[test]
+	fn test_keep_locked() {
+		let current = SeriesMetadata {
+			locked_fields: vec!["title".to_string()],
+			..stored()
+		};
+		let mut updated = SeriesMetadata {
+			title: Some("Saga (2012)".to_string()),
+			publisher: Some("Image Comics".to_string()),
+			..stored()
+		};
+
+		updated.keep_locked(&current);
+		assert_eq!(updated.title.as_deref(), Some("Saga"));
+		assert_eq!(updated.publisher.as_deref(), Some("Image Comics"));
+		assert_eq!(updated.locked_fields, vec!["title"]);
+	}
 }
@@ -1,18 +1,22 @@
 mod api_key;
 mod book_club;
+mod collection;
 pub(crate) mod common;
 mod emailer;
 mod epub;
 mod job;
+mod job_schedule;
 mod library;
 mod log;
 mod media;
 mod metadata;
 mod notifier;
+mod person;
 mod reading_list;
 mod series;
 mod server_config;
 mod smart_list;
+mod story_arc;
 mod tag;
 mod user;
 
@@ -21,16 +25,20 @@ pub use self::log::*;
 
 pub use api_key::*;
 pub use book_club::*;
+pub use collection::*;
 pub use emailer::*;
 pub use job::*;
+pub use job_schedule::*;
 pub use library::*;
 pub use media::*;
 pub use metadata::*;
 pub use notifier::*;
+pub use person::*;
 pub use reading_list::*;
 pub use series::*;
 pub use server_config::*;
 pub use smart_list::*;
+pub use story_arc::*;
 pub use tag::*;
 pub use user::*;
 
@@ -1,4 +1,12 @@
-use crate::{prisma::notifier, utils::encrypt_string, CoreError, CoreResult, Ctx};
+use crate::{
+	prisma::{notifier, PrismaClient},
+	utils::{decrypt_string, encrypt_string, get_encryption_key},
+	CoreError, CoreResult, Ctx,
+};
+use integrations::{
+	DiscordClient, Notifier as NotifierClient, NotifierEvent, TelegramClient,
+	WebhookClient,
+};
 use serde::{Deserialize, Serialize};
 use specta::Type;
 use std::fmt;
@@ -16,6 +24,127 @@ pub struct Notifier {
 	/// The config is stored as bytes in the DB, and is deserialized into the correct type when
 	/// needed. If there are sensitive fields, they should be encrypted before being stored.
 	config: NotifierConfig,
+	/// The jobs which the notifier is sent events for. Notifiers which have never been
+	/// configured are subscribed to the completion of scans.
+	subscriptions: Vec<NotifierJobSubscription>,
+}
+
+impl Notifier {
+	/// Whether the notifier has opted in to the given event of a job
+	pub fn is_subscribed(&self, job_name: &str, event: &NotifierEvent) -> bool {
+		let is_failure = matches!(event, NotifierEvent::JobFailed { .. });
+		self.subscriptions.iter().any(|subscription| {
+			subscription.job_name == job_name
+				&& if is_failure {
+					subscription.on_failed
+				} else {
+					subscription.on_completed
+				}
+		})
+	}
+
+	/// Send an event through the notifier. The encryption key is required to decrypt
+	/// sensitive fields of the config, e.g. the token of a Telegram bot
+	pub async fn send_event(
+		&self,
+		event: NotifierEvent,
+		encryption_key: Option<&String>,
+	) -> CoreResult<()> {
+		let result = match &self.config {
+			NotifierConfig::Discord(config) => {
+				DiscordClient::new(config.webhook_url.clone())
+					.send_message(event)
+					.await
+			},
+			NotifierConfig::Telegram(config) => {
+				let encryption_key =
+					encryption_key.ok_or(CoreError::EncryptionKeyNotSet)?;
+				let token = decrypt_string(&config.encrypted_token, encryption_key)?;
+				TelegramClient::new(token, config.chat_id.clone())
+					.send_message(event)
+					.await
+			},
+			NotifierConfig::Webhook(config) => {
+				WebhookClient::new(config.url.clone())
+					.send_message(event)
+					.await
+			},
+		};
+
+		result.map_err(|error| CoreError::InternalError(error.to_string()))
+	}
+}
+
+/// Send an event for a job through every notifier which is subscribed to it. Failures are logged
+/// rather than returned, since an unreachable notifier should not fail the job
+pub async fn send_notifier_event(
+	client: &PrismaClient,
+	job_name: &str,
+	event: NotifierEvent,
+) {
+	let notifiers = match client.notifier().find_many(vec![]).exec().await {
+		Ok(notifiers) => notifiers,
+		Err(error) => {
+			tracing::error!(?error, "Failed to fetch notifiers");
+			return;
+		},
+	};
+
+	if notifiers.is_empty() {
+		tracing::trace!("No notifiers configured");
+		return;
+	}
+
+	let encryption_key = get_encryption_key(client).await.ok();
+	for data in notifiers {
+		let notifier_id = data.id;
+		let result = match Notifier::try_from(data) {
+			Ok(notifier) if notifier.is_subscribed(job_name, &event) => {
+				notifier
+					.send_event(event.clone(), encryption_key.as_ref())
+					.await
+			},
+			Ok(_) => Ok(()),
+			Err(error) => Err(error),
+		};
+
+		if let Err(error) = result {
+			tracing::error!(?error, notifier_id, "Failed to send notifier event");
+		}
+	}
+}
+
+/// A notifier's opt-in to the events of a kind of job
+#[derive(Clone, Debug, Serialize, Deserialize, ToSchema, Type)]
+pub struct NotifierJobSubscription {
+	/// The name of the job, e.g. `thumbnail_generation`
+	pub job_name: String,
+	/// Whether to send the output of the job once it completes. Not every job describes its
+	/// output, in which case nothing is sent
+	#[serde(default)]
+	pub on_completed: bool,
+	/// Whether to send a message when the job fails
+	#[serde(default)]
+	pub on_failed: bool,
+}
+
+impl NotifierJobSubscription {
+	/// The subscriptions of a notifier which has never been configured, which only covers
+	/// the completion of scans
+	pub fn defaults() -> Vec<Self> {
+		["library_scan", "series_scan"]
+			.into_iter()
+			.map(|job_name| Self {
+				job_name: job_name.to_string(),
+				on_completed: true,
+				on_failed: false,
+			})
+			.collect()
+	}
+
+	pub fn into_bytes(subscriptions: Vec<Self>) -> Result<Vec<u8>, CoreError> {
+		Ok(serde_json::to_vec(&subscriptions)?)
+	}
 }
 
 /// The config for a Discord notifier
@@ -35,11 +164,19 @@ pub struct TelegramConfig {
 	pub chat_id: String,
 }
 
+/// The config for a generic webhook notifier, which is sent a JSON payload for each event
+#[derive(Serialize, Deserialize, ToSchema, Type)]
+pub struct WebhookConfig {
+	/// The URL to send to
+	pub url: String,
+}
+
 #[derive(Serialize, Deserialize, ToSchema, Type)]
 #[serde(untagged)]
 pub enum NotifierConfig {
 	Discord(DiscordConfig),
 	Telegram(TelegramConfig),
+	Webhook(WebhookConfig),
 }
 
 impl NotifierConfig {
@@ -59,12 +196,14 @@ pub struct TelegramConfigInput {
 pub enum NotifierConfigInput {
 	Discord(DiscordConfig),
 	Telegram(TelegramConfigInput),
+	Webhook(WebhookConfig),
 }
 
 impl NotifierConfigInput {
 	pub async fn into_config(self, ctx: &Ctx) -> CoreResult<NotifierConfig> {
 		match self {
 			NotifierConfigInput::Discord(config) => Ok(NotifierConfig::Discord(config)),
+			NotifierConfigInput::Webhook(config) => Ok(NotifierConfig::Webhook(config)),
 			NotifierConfigInput::Telegram(config) => {
 				let encryption_key = ctx.get_encryption_key().await?;
 				let encrypted_token = encrypt_string(&config.token, &encryption_key)?;
@@ -83,6 +222,8 @@ pub enum NotifierType {
 	Discord,
 	#[serde(rename = "TELEGRAM")]
 	Telegram,
+	#[serde(rename = "WEBHOOK")]
+	Webhook,
 }
 
 impl fmt::Display for NotifierType {
@@ -90,6 +231,7 @@ impl fmt::Display for NotifierType {
 		match self {
 			NotifierType::Discord => write!(f, "DISCORD"),
 			NotifierType::Telegram => write!(f, "TELEGRAM"),
+			NotifierType::Webhook => write!(f, "WEBHOOK"),
 		}
 	}
 }
@@ -103,6 +245,7 @@ impl FromStr for NotifierType {
 		match uppercase.as_str() {
 			"DISCORD" => Ok(NotifierType::Discord),
 			"TELEGRAM" => Ok(NotifierType::Telegram),
+			"WEBHOOK" => Ok(NotifierType::Webhook),
 			_ => Err(format!("Invalid NotifierType: {s}")),
 		}
 	}
@@ -116,7 +259,65 @@ impl TryFrom<notifier::Data> for Notifier {
 			_type: NotifierType::from_str(&value.r#type)
 				.map_err(|e| CoreError::InternalError(e.to_string()))?,
 			config: serde_json::from_slice(&value.config)?,
+			subscriptions: value
+				.subscriptions
+				.map(|bytes| serde_json::from_slice(&bytes))
+				.transpose()?
+				.unwrap_or_else(NotifierJobSubscription::defaults),
 			id: value.id,
 		})
 	}
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn notifier_with(subscriptions: Vec<NotifierJobSubscription>) -> Notifier {
+		Notifier {
+			id: 1,
+			_type: NotifierType::Webhook,
+			config: NotifierConfig::Webhook(WebhookConfig {
+				url: String::from("http://localhost"),
+			}),
+			subscriptions,
+		}
+	}
+
+	#[test]
+	fn test_is_subscribed() {
+		let notifier = notifier_with(vec![NotifierJobSubscription {
+			job_name: String::from("thumbnail_generation"),
+			on_completed: false,
+			on_failed: true,
+		}]);
+		let failed = NotifierEvent::JobFailed {
+			job_name: String::from("thumbnail_generation"),
+			description: None,
+			error: String::from("Disk full"),
+		};
+
+		assert!(notifier.is_subscribed("thumbnail_generation", &failed));
+		assert!(!notifier.is_subscribed("analyze_media", &failed));
+		assert!(!notifier.is_subscribed(
+			"thumbnail_generation",
+			&NotifierEvent::ScanCompleted {
+				books_added: 1,
+				library_name: String::from("test_library"),
+			}
+		));
+	}
+
+	#[test]
+	fn test_default_subscriptions() {
+		let notifier = notifier_with(NotifierJobSubscription::defaults());
+		let event = NotifierEvent::SeriesScanCompleted {
+			books_added: 1,
+			books_updated: 0,
+			series_name: String::from("test_series"),
+		};
+
+		assert!(notifier.is_subscribed("series_scan", &event));
+		assert!(!notifier.is_subscribed("thumbnail_generation", &event));
+	}
+}
@@ -0,0 +1,237 @@
+use std::{fmt, str::FromStr};
+
+use serde::{Deserialize, Serialize};
+use specta::Type;
+use utoipa::ToSchema;
+
+use crate::{
+	db::entity::{common::Cursor, Media, MediaMetadata},
+	prisma::{media_credit, person},
+	CoreError,
+};
+
+///////////////////////////////////////////////
+//////////////////// MODELS ///////////////////
+///////////////////////////////////////////////
+
+/// The role a person had in the creation of a book
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema, Type)]
+pub enum CreatorRole {
+	#[serde(rename = "WRITER")]
+	Writer,
+	#[serde(rename = "PENCILLER")]
+	Penciller,
+	#[serde(rename = "INKER")]
+	Inker,
+	#[serde(rename = "COLORIST")]
+	Colorist,
+	#[serde(rename = "LETTERER")]
+	Letterer,
+	#[serde(rename = "COVER_ARTIST")]
+	CoverArtist,
+	#[serde(rename = "EDITOR")]
+	Editor,
+}
+
+impl CreatorRole {
+	/// Every role which is read from the metadata of a book
+	pub const ALL: [CreatorRole; 7] = [
+		CreatorRole::Writer,
+		CreatorRole::Penciller,
+		CreatorRole::Inker,
+		CreatorRole::Colorist,
+		CreatorRole::Letterer,
+		CreatorRole::CoverArtist,
+		CreatorRole::Editor,
+	];
+
+	/// The names of the people credited with this role in the given metadata
+	pub fn names_in<'a>(&self, metadata: &'a MediaMetadata) -> &'a [String] {
+		let names = match self {
+			CreatorRole::Writer => &metadata.writers,
+			CreatorRole::Penciller => &metadata.pencillers,
+			CreatorRole::Inker => &metadata.inkers,
+			CreatorRole::Colorist => &metadata.colorists,
+			CreatorRole::Letterer => &metadata.letterers,
+			CreatorRole::CoverArtist => &metadata.cover_artists,
+			CreatorRole::Editor => &metadata.editors,
+		};
+
+		names.as_deref().unwrap_or_default()
+	}
+}
+
+impl fmt::Display for CreatorRole {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			CreatorRole::Writer => write!(f, "WRITER"),
+			CreatorRole::Penciller => write!(f, "PENCILLER"),
+			CreatorRole::Inker => write!(f, "INKER"),
+			CreatorRole::Colorist => write!(f, "COLORIST"),
+			CreatorRole::Letterer => write!(f, "LETTERER"),
+			CreatorRole::CoverArtist => write!(f, "COVER_ARTIST"),
+			CreatorRole::Editor => write!(f, "EDITOR"),
+		}
+	}
+}
+
+impl FromStr for CreatorRole {
+	type Err = String;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		let uppercase = s.to_uppercase();
+
+		match uppercase.as_str() {
+			"WRITER" => Ok(CreatorRole::Writer),
+			"PENCILLER" => Ok(CreatorRole::Penciller),
+			"INKER" => Ok(CreatorRole::Inker),
+			"COLORIST" => Ok(CreatorRole::Colorist),
+			"LETTERER" => Ok(CreatorRole::Letterer),
+			"COVER_ARTIST" => Ok(CreatorRole::CoverArtist),
+			"EDITOR" => Ok(CreatorRole::Editor),
+			_ => Err(format!("Invalid creator role: {s}")),
+		}
+	}
+}
+
+/// A person credited on one or more books, e.g. a writer or colorist. People are
+/// deduplicated by their normalized name, so "Jack Kirby" and "jack  kirby" are the same
+/// person
+#[derive(Debug, Clone, Serialize, Deserialize, Type, ToSchema)]
+pub struct Person {
+	pub id: String,
+	/// The display name of the person. ex: "Jack Kirby"
+	pub name: String,
+	/// Other names the person is credited under. Will be `None` only if the relation is
+	/// not loaded.
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub aliases: Option<Vec<String>>,
+	/// The number of books the person is credited on, per role. Will be `None` only if it
+	/// was not requested.
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub credit_counts: Option<Vec<PersonRoleCount>>,
+}
+
+impl Cursor for Person {
+	fn cursor(&self) -> String {
+		self.id.clone()
+	}
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Type, ToSchema)]
+pub struct PersonRoleCount {
+	pub role: CreatorRole,
+	pub count: i64,
+}
+
+/// The credit of a person on a book, in a specific role
+#[derive(Debug, Clone, Serialize, Deserialize, Type, ToSchema)]
+pub struct MediaCredit {
+	pub role: CreatorRole,
+	pub person_id: String,
+	pub media_id: String,
+	/// The person. Will be `None` only if the relation is not loaded.
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub person: Option<Person>,
+	/// The book. Will be `None` only if the relation is not loaded.
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub media: Option<Media>,
+}
+
+/// Normalize the name of a person for deduplication, ignoring case, punctuation and
+/// whitespace. ex: "Kirby,  Jack" and "kirby jack" both normalize to "kirby jack"
+pub fn normalize_person_name(name: &str) -> String {
+	name.to_lowercase()
+		.split(|c: char| !c.is_alphanumeric())
+		.filter(|part| !part.is_empty())
+		.collect::<Vec<_>>()
+		.join(" ")
+}
+
+///////////////////////////////////////////////
+////////////////// CONVERSIONS ////////////////
+///////////////////////////////////////////////
+
+impl From<person::Data> for Person {
+	fn from(data: person::Data) -> Person {
+		let aliases = data.aliases().ok().map(|aliases| {
+			aliases
+				.iter()
+				.map(|alias| alias.name.clone())
+				.collect::<Vec<_>>()
+		});
+
+		Person {
+			id: data.id,
+			name: data.name,
+			aliases,
+			credit_counts: None,
+		}
+	}
+}
+
+impl TryFrom<media_credit::Data> for MediaCredit {
+	type Error = CoreError;
+
+	fn try_from(data: media_credit::Data) -> Result<Self, Self::Error> {
+		let person = data
+			.person()
+			.ok()
+			.map(|person| Person::from(person.to_owned()));
+		let media = data.media().ok().map(|media| Media::from(media.to_owned()));
+
+		Ok(MediaCredit {
+			role: CreatorRole::from_str(&data.role).map_err(CoreError::InternalError)?,
+			person_id: data.person_id,
+			media_id: data.media_id,
+			person,
+			media,
+		})
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_normalize_person_name() {
+		assert_eq!(normalize_person_name("Jack Kirby"), "jack kirby");
+		assert_eq!(normalize_person_name("  jack   KIRBY "), "jack kirby");
+		assert_eq!(normalize_person_name("Kirby, Jack"), "kirby jack");
+		assert_eq!(normalize_person_name("J.M. DeMatteis"), "j m dematteis");
+		assert_eq!(normalize_person_name("Moebius!"), "moebius");
+		assert_eq!(normalize_person_name(" , "), "");
+	}
+
+	#[test]
+	fn test_creator_role_round_trip() {
+		for role in CreatorRole::ALL {
+			assert_eq!(CreatorRole::from_str(&role.to_string()), Ok(role));
+		}
+		assert_eq!(
+			CreatorRole::from_str("cover_artist"),
+			Ok(CreatorRole::CoverArtist)
+		);
+		assert!(CreatorRole::from_str("PAINTER").is_err());
+	}
+
+	#[test]
+	fn test_creator_role_names_in() {
+		let metadata = MediaMetadata {
+			writers: Some(vec!["Stan Lee".to_string()]),
+			cover_artists: Some(vec![
+				"Jack Kirby".to_string(),
+				"Steve Ditko".to_string(),
+			]),
+			..Default::default()
+		};
+
+		assert_eq!(CreatorRole::Writer.names_in(&metadata), ["Stan Lee"]);
+		assert_eq!(
+			CreatorRole::CoverArtist.names_in(&metadata),
+			["Jack Kirby", "Steve Ditko"]
+		);
+		assert!(CreatorRole::Inker.names_in(&metadata).is_empty());
+	}
+}
@@ -0,0 +1,173 @@
+use std::cmp::Ordering;
+
+use serde::{Deserialize, Serialize};
+use specta::Type;
+use utoipa::ToSchema;
+
+use crate::{
+	db::entity::{common::Cursor, Media},
+	prisma::{story_arc, story_arc_member},
+};
+
+///////////////////////////////////////////////
+//////////////////// MODELS ///////////////////
+///////////////////////////////////////////////
+
+/// A story arc, e.g. "Civil War", which groups books across any number of series and
+/// libraries
+#[derive(Debug, Clone, Serialize, Deserialize, Type, ToSchema)]
+pub struct StoryArc {
+	pub id: String,
+	/// The name of the story arc. ex: "Civil War"
+	pub name: String,
+}
+
+impl Cursor for StoryArc {
+	fn cursor(&self) -> String {
+		self.id.clone()
+	}
+}
+
+/// The membership of a book in a story arc
+#[derive(Debug, Clone, Serialize, Deserialize, Type, ToSchema)]
+pub struct StoryArcMember {
+	/// The position of the book in the story arc, e.g. 3 for its third part. Books without a
+	/// position are ordered after those with one
+	pub position: Option<f64>,
+	pub story_arc_id: String,
+	pub media_id: String,
+	/// The book. Will be `None` only if the relation is not loaded.
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub media: Option<Media>,
+	/// The story arc. Will be `None` only if the relation is not loaded.
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub story_arc: Option<StoryArc>,
+}
+
+/// A story arc as it was read from the metadata of a file, before it is stored as a
+/// [StoryArcMember]
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MediaStoryArc {
+	pub name: String,
+	pub position: Option<f64>,
+}
+
+impl MediaStoryArc {
+	/// Pair the comma separated arc names and positions of a ComicInfo.xml file, e.g.
+	/// `Civil War, Road to Civil War` and `3, 1`. An arc without a matching (or numeric)
+	/// position has none, and repeated arcs only keep their first position.
+	pub fn from_comic_info(names: &str, positions: Option<&str>) -> Vec<Self> {
+		let positions = positions
+			.map(|value| value.split(',').map(str::trim).collect::<Vec<_>>())
+			.unwrap_or_default();
+
+		names
+			.split(',')
+			.map(str::trim)
+			.enumerate()
+			.map(|(index, name)| MediaStoryArc {
+				name: name.to_string(),
+				position: positions.get(index).and_then(|value| value.parse().ok()),
+			})
+			.fold(Vec::new(), |mut arcs: Vec<MediaStoryArc>, arc| {
+				if !arc.name.is_empty() && !arcs.iter().any(|a| a.name == arc.name) {
+					arcs.push(arc);
+				}
+				arcs
+			})
+	}
+}
+
+/// Compare the positions of two books in a story arc, ordering books without a position last
+pub fn cmp_story_arc_positions(a: Option<f64>, b: Option<f64>) -> Ordering {
+	match (a, b) {
+		(Some(a), Some(b)) => a.partial_cmp(&b).unwrap_or(Ordering::Equal),
+		(Some(_), None) => Ordering::Less,
+		(None, Some(_)) => Ordering::Greater,
+		(None, None) => Ordering::Equal,
+	}
+}
+
+///////////////////////////////////////////////
+////////////////// CONVERSIONS ////////////////
+///////////////////////////////////////////////
+
+impl From<story_arc::Data> for StoryArc {
+	fn from(data: story_arc::Data) -> StoryArc {
+		StoryArc {
+			id: data.id,
+			name: data.name,
+		}
+	}
+}
+
+impl From<story_arc_member::Data> for StoryArcMember {
+	fn from(data: story_arc_member::Data) -> StoryArcMember {
+		let media = data.media().ok().map(|media| Media::from(media.to_owned()));
+		let story_arc = data
+			.story_arc()
+			.ok()
+			.map(|story_arc| StoryArc::from(story_arc.to_owned()));
+
+		StoryArcMember {
+			position: data.position,
+			story_arc_id: data.story_arc_id,
+			media_id: data.media_id,
+			media,
+			story_arc,
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_from_comic_info() {
+		let arcs =
+			MediaStoryArc::from_comic_info("Civil War, Road to Civil War", Some("3, 1"));
+		assert_eq!(
+			arcs,
+			vec![
+				MediaStoryArc {
+					name: "Civil War".to_string(),
+					position: Some(3.0),
+				},
+				MediaStoryArc {
+					name: "Road to Civil War".to_string(),
+					position: Some(1.0),
+				},
+			]
+		);
+	}
+
+	#[test]
+	fn test_from_comic_info_missing_positions() {
+		let arcs = MediaStoryArc::from_comic_info(
+			"Civil War, , Civil War, Fallout",
+			Some("2.5, x"),
+		);
+		assert_eq!(
+			arcs,
+			vec![
+				MediaStoryArc {
+					name: "Civil War".to_string(),
+					position: Some(2.5),
+				},
+				MediaStoryArc {
+					name: "Fallout".to_string(),
+					position: None,
+				},
+			]
+		);
+		assert!(MediaStoryArc::from_comic_info("", None).is_empty());
+	}
+
+	#[test]
+	fn test_cmp_story_arc_positions() {
+		let mut positions = vec![None, Some(2.0), Some(1.5), None, Some(1.0)];
+		positions.sort_by(|a, b| cmp_story_arc_positions(*a, *b));
+		assert_eq!(positions, vec![Some(1.0), Some(1.5), Some(2.0), None, None]);
+	}
+}
@@ -9,7 +9,9 @@ use serde::{Deserialize, Serialize};
 use specta::Type;
 use utoipa::ToSchema;
 
-use crate::prisma::{library, media, media_metadata, series, series_metadata};
+use crate::prisma::{
+	library, media, media_metadata, series, series_metadata, story_arc, story_arc_member,
+};
 use smart_filter_gen::generate_smart_filter;
 
 // TODO: This rough implementation is not very great. It is very verbose and not very ergonomic. It _technically_
@@ -354,21 +356,61 @@ pub enum MediaMetadataSmartFilter {
 	Day { day: i32 },
 }
 
+#[generate_smart_filter]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Type, ToSchema)]
+#[serde(untagged)]
+#[prisma_table("story_arc")]
+pub enum StoryArcSmartFilter {
+	Name { name: String },
+}
+
+#[generate_smart_filter]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Type, ToSchema)]
+#[serde(untagged)]
+#[prisma_table("story_arc_member")]
+pub enum StoryArcMemberSmartFilter {
+	StoryArc { story_arc: StoryArcSmartFilter },
+}
+
 #[generate_smart_filter]
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Type, ToSchema)]
 #[serde(untagged)]
 #[prisma_table("media")]
 pub enum MediaSmartFilter {
-	Name { name: String },
-	Size { size: i64 },
-	Extension { extension: String },
-	CreatedAt { created_at: DateTime<FixedOffset> },
-	UpdatedAt { updated_at: DateTime<FixedOffset> },
-	Status { status: String },
-	Path { path: String },
-	Pages { pages: i32 },
-	Metadata { metadata: MediaMetadataSmartFilter },
-	Series { series: SeriesSmartFilter },
+	Name {
+		name: String,
+	},
+	Size {
+		size: i64,
+	},
+	Extension {
+		extension: String,
+	},
+	CreatedAt {
+		created_at: DateTime<FixedOffset>,
+	},
+	UpdatedAt {
+		updated_at: DateTime<FixedOffset>,
+	},
+	Status {
+		status: String,
+	},
+	Path {
+		path: String,
+	},
+	Pages {
+		pages: i32,
+	},
+	Metadata {
+		metadata: MediaMetadataSmartFilter,
+	},
+	Series {
+		series: SeriesSmartFilter,
+	},
+	#[is_many]
+	StoryArcs {
+		story_arcs: StoryArcMemberSmartFilter,
+	},
 }
 
 #[cfg(test)]
@@ -479,6 +521,29 @@ mod tests {
 		);
 	}
 
+	#[test]
+	fn it_deserializes_story_arcs_correctly() {
+		let json =
+			r#"{"and":[{"story_arcs":{"story_arc":{"name":{"equals":"Civil War"}}}}]}"#;
+
+		let filter: FilterGroup<MediaSmartFilter> = serde_json::from_str(json).unwrap();
+
+		assert_eq!(
+			filter,
+			FilterGroup::And {
+				and: vec![MediaSmartFilter::StoryArcs {
+					story_arcs: StoryArcMemberSmartFilter::StoryArc {
+						story_arc: StoryArcSmartFilter::Name {
+							name: Filter::Equals {
+								equals: "Civil War".to_string(),
+							},
+						},
+					},
+				}],
+			}
+		);
+	}
+
 	#[test]
 	fn it_serializes_range_correctly() {
 		let filter: FilterGroup<MediaSmartFilter> = FilterGroup::And {
@@ -539,12 +604,16 @@ mod tests {
 			book_club_member_favorite_book: None,
 			book_club_suggestions: None,
 			bookmarks: None,
+			collection_items: None,
 			created_at: Utc::now().into(),
+			credits: None,
 			deleted_at: None,
 			extension: "CBZ".to_string(),
 			hash: None,
 			koreader_hash: None,
+			perceptual_hash: None,
 			metadata: None,
+			metadata_conflicts: None,
 			modified_at: None,
 			pages: 30,
 			path: "test-path".to_string(),
@@ -553,6 +622,7 @@ mod tests {
 			reading_list_items: None,
 			size: 100,
 			status: "READY".to_string(),
+			story_arcs: None,
 			tags: None,
 			updated_at: Utc::now().into(),
 		}
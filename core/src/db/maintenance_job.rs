@@ -0,0 +1,125 @@
+use std::collections::VecDeque;
+
+use prisma_client_rust::raw;
+use serde::{Deserialize, Serialize};
+
+use crate::job::{
+	error::JobError, JobExecuteLog, JobExt, JobOutputExt, JobTaskOutput, WorkerCtx,
+	WorkingState, WrappedJob,
+};
+
+pub const DATABASE_MAINTENANCE_JOB_NAME: &str = "database_maintenance";
+
+/// The operations performed by a [`DatabaseMaintenanceJob`], in the order they are run
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub enum DatabaseMaintenanceTask {
+	/// Let SQLite refresh the statistics used by the query planner
+	Optimize,
+	/// Move the contents of the write-ahead log into the database file and truncate it
+	CheckpointWal,
+	/// Rebuild the database file, reclaiming the space left by deleted records
+	Vacuum,
+}
+
+impl DatabaseMaintenanceTask {
+	fn statement(&self) -> &'static str {
+		match self {
+			DatabaseMaintenanceTask::Optimize => "PRAGMA optimize;",
+			DatabaseMaintenanceTask::CheckpointWal => "PRAGMA wal_checkpoint(TRUNCATE);",
+			DatabaseMaintenanceTask::Vacuum => "VACUUM;",
+		}
+	}
+}
+
+/// The data that is collected and updated during the execution of a database maintenance job
+#[derive(Serialize, Deserialize, Default, Debug)]
+pub struct DatabaseMaintenanceJobOutput {
+	/// The number of maintenance operations which completed successfully
+	completed_operations: u64,
+	/// The number of maintenance operations which failed
+	failed_operations: u64,
+}
+
+impl JobOutputExt for DatabaseMaintenanceJobOutput {
+	fn update(&mut self, updated: Self) {
+		self.completed_operations += updated.completed_operations;
+		self.failed_operations += updated.failed_operations;
+	}
+}
+
+/// A job which performs routine upkeep of the SQLite database. Each operation is attempted
+/// even if a previous one failed, since they are independent of each other.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct DatabaseMaintenanceJob;
+
+impl DatabaseMaintenanceJob {
+	pub fn new() -> Box<WrappedJob<DatabaseMaintenanceJob>> {
+		WrappedJob::new(Self)
+	}
+}
+
+#[async_trait::async_trait]
+impl JobExt for DatabaseMaintenanceJob {
+	const NAME: &'static str = DATABASE_MAINTENANCE_JOB_NAME;
+
+	type Output = DatabaseMaintenanceJobOutput;
+	type Task = DatabaseMaintenanceTask;
+
+	fn description(&self) -> Option<String> {
+		None
+	}
+
+	async fn init(
+		&mut self,
+		_: &WorkerCtx,
+	) -> Result<WorkingState<Self::Output, Self::Task>, JobError> {
+		Ok(WorkingState {
+			output: Some(Self::Output::default()),
+			tasks: VecDeque::from([
+				DatabaseMaintenanceTask::Optimize,
+				DatabaseMaintenanceTask::CheckpointWal,
+				DatabaseMaintenanceTask::Vacuum,
+			]),
+			completed_tasks: 0,
+			logs: vec![],
+		})
+	}
+
+	async fn execute_task(
+		&self,
+		ctx: &WorkerCtx,
+		task: Self::Task,
+	) -> Result<JobTaskOutput<Self>, JobError> {
+		let mut output = Self::Output::default();
+		let mut logs = vec![];
+
+		// The pragmas return a row, so every statement is run as a query rather than with `_execute_raw`
+		let result = ctx
+			.db
+			._query_raw::<serde_json::Value>(raw!(task.statement()))
+			.exec()
+			.await
+			.map(|_| ());
+
+		match result {
+			Ok(_) => {
+				tracing::debug!(?task, "Completed database maintenance operation");
+				output.completed_operations += 1;
+			},
+			Err(error) => {
+				tracing::error!(?error, ?task, "Database maintenance operation failed");
+				logs.push(JobExecuteLog::error(format!(
+					"Failed to run {:?}: {}",
+					task, error
+				)));
+				output.failed_operations += 1;
+			},
+		}
+
+		Ok(JobTaskOutput {
+			output,
+			subtasks: vec![],
+			logs,
+		})
+	}
+}
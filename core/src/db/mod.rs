@@ -3,8 +3,10 @@ mod common;
 pub(crate) mod dao;
 pub mod entity;
 pub mod filter;
+mod maintenance_job;
 pub mod migration;
 pub mod query;
+mod session_cleanup_job;
 
 pub use dao::*;
 
@@ -13,3 +15,9 @@ pub use common::{
 	CountQueryReturn, DBPragma, JournalMode, JournalModeQueryResult, PrismaCountTrait,
 };
 pub use entity::FileStatus;
+pub use maintenance_job::{
+	DatabaseMaintenanceJob, DatabaseMaintenanceJobOutput, DATABASE_MAINTENANCE_JOB_NAME,
+};
+pub use session_cleanup_job::{
+	SessionCleanupJob, SessionCleanupJobOutput, SESSION_CLEANUP_JOB_NAME,
+};
@@ -2,7 +2,8 @@ use std::collections::VecDeque;
 
 use prisma_client_rust::chrono::Utc;
 use serde::{Deserialize, Serialize};
-use stump_core::{
+
+use crate::{
 	job::{
 		error::JobError, JobExecuteLog, JobExt, JobOutputExt, JobTaskOutput, WorkerCtx,
 		WorkingState, WrappedJob,
@@ -12,7 +13,7 @@ use stump_core::{
 
 pub const SESSION_CLEANUP_JOB_NAME: &str = "session_cleanup";
 
-/// The data that is collected and updated during the execution of a library scan job
+/// The data that is collected and updated during the execution of a session cleanup job
 #[derive(Serialize, Deserialize, Default, Debug)]
 pub struct SessionCleanupJobOutput {
 	/// The number of removed sessions
@@ -21,7 +22,8 @@ pub struct SessionCleanupJobOutput {
 
 impl JobOutputExt for SessionCleanupJobOutput {}
 
-#[derive(Clone)]
+/// A job which deletes any expired sessions from the database
+#[derive(Clone, Serialize, Deserialize)]
 pub struct SessionCleanupJob;
 
 impl SessionCleanupJob {
@@ -32,6 +32,8 @@ pub enum CoreError {
 	RelationNotLoaded(#[from] RelationNotFetchedError),
 	#[error("Migration error: {0}")]
 	MigrationError(String),
+	#[error("Failed to watch filesystem: {0}")]
+	WatcherError(#[from] notify::Error),
 	#[error("Failed to parse regex patterns into globset: {0}")]
 	GlobSetError(#[from] globset::Error),
 	#[error("Requested resource could not be found: {0}")]
@@ -63,3 +65,14 @@ impl From<prisma_client_rust::QueryError> for CoreError {
 		Self::QueryError(Box::new(error))
 	}
 }
+
+impl From<integrations::MetadataProviderError> for CoreError {
+	fn from(error: integrations::MetadataProviderError) -> Self {
+		match error {
+			integrations::MetadataProviderError::InvalidId(_) => {
+				Self::BadRequest(error.to_string())
+			},
+			_ => Self::InternalError(error.to_string()),
+		}
+	}
+}
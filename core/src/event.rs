@@ -3,7 +3,7 @@ use specta::Type;
 
 use crate::{
 	db::entity::CoreJobOutput,
-	job::{JobUpdate, WorkerSend, WorkerSendExt},
+	job::{JobUpdate, QueuedJob, WorkerSend, WorkerSendExt},
 };
 
 /// An event that is emitted by the core and consumed by a client
@@ -13,6 +13,8 @@ pub enum CoreEvent {
 	JobStarted(String),
 	JobUpdate(JobUpdate),
 	JobOutput { id: String, output: CoreJobOutput },
+	JobQueueUpdate { queue: Vec<QueuedJob> },
+	JobFailed { id: String, message: String },
 	DiscoveredMissingLibrary(String),
 	CreatedMedia { id: String, series_id: String },
 	CreatedManySeries { count: u64, library_id: String },
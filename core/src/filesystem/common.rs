@@ -1,4 +1,3 @@
-use globset::GlobSet;
 use std::{
 	ffi::OsStr,
 	path::{Path, PathBuf},
@@ -8,7 +7,9 @@ use tokio::{fs, io};
 use tracing::error;
 use walkdir::WalkDir;
 
-use super::{image::ImageFormat, media::is_accepted_cover_name, ContentType};
+use super::{
+	image::ImageFormat, media::is_accepted_cover_name, ContentType, IgnoreMatcher,
+};
 
 pub const ACCEPTED_IMAGE_EXTENSIONS: [&str; 8] =
 	["jpg", "png", "jpeg", "jxl", "webp", "gif", "avif", "heif"];
@@ -123,10 +124,10 @@ pub trait PathUtils {
 	fn is_thumbnail_img(&self) -> bool;
 	/// Returns true if the directory has any media files in it. This is a shallow
 	/// check, and will not check subdirectories.
-	fn dir_has_media(&self, ignore_rules: &GlobSet) -> bool;
+	fn dir_has_media(&self, ignore_rules: &IgnoreMatcher) -> bool;
 	/// Returns true if the directory has any media files in it. This is a deep
 	/// check, and will check *all* subdirectories.
-	fn dir_has_media_deep(&self, ignore_rules: &GlobSet) -> bool;
+	fn dir_has_media_deep(&self, ignore_rules: &IgnoreMatcher) -> bool;
 }
 
 impl PathUtils for Path {
@@ -228,7 +229,7 @@ impl PathUtils for Path {
 		is_accepted_cover_name(&file_stem)
 	}
 
-	fn dir_has_media(&self, ignore_rules: &GlobSet) -> bool {
+	fn dir_has_media(&self, ignore_rules: &IgnoreMatcher) -> bool {
 		if !self.is_dir() {
 			return false;
 		}
@@ -241,7 +242,8 @@ impl PathUtils for Path {
 				.filter(|item| item.path() != self)
 				.any(|f| {
 					let path = f.path();
-					!path.is_default_ignored() && !ignore_rules.is_match(path)
+					!path.is_default_ignored()
+						&& !ignore_rules.is_ignored(&path, path.is_dir())
 				}),
 			Err(e) => {
 				error!(
@@ -254,18 +256,21 @@ impl PathUtils for Path {
 		}
 	}
 
-	fn dir_has_media_deep(&self, ignore_rules: &GlobSet) -> bool {
+	fn dir_has_media_deep(&self, ignore_rules: &IgnoreMatcher) -> bool {
 		if !self.is_dir() {
 			return false;
 		}
 
 		WalkDir::new(self)
 			.into_iter()
+			// Ignored directories are skipped entirely, since nothing within them can be
+			// re-included
+			.filter_entry(|item| {
+				item.path() == self
+					|| !ignore_rules.is_ignored(item.path(), item.file_type().is_dir())
+			})
 			.filter_map(Result::ok)
 			.filter(|item| item.path() != self)
-			.any(|f| {
-				let path = f.path();
-				!path.is_default_ignored() && !ignore_rules.is_match(path)
-			})
+			.any(|f| !f.path().is_default_ignored())
 	}
 }
@@ -52,6 +52,8 @@ pub enum FileError {
 	UnknownError(String),
 	#[error("Failed to read directory")]
 	DirectoryReadError,
+	#[error("Object storage error: {0}")]
+	ObjectStorageError(String),
 }
 
 impl From<FileError> for CoreError {
@@ -1,5 +1,6 @@
 mod error;
 mod generic;
+mod perceptual_hash;
 mod process;
 mod thumbnail;
 mod webp;
@@ -9,6 +10,9 @@ mod webp;
 pub use self::webp::WebpProcessor;
 pub use error::ProcessorError;
 pub use generic::GenericImageProcessor;
+pub use perceptual_hash::{
+	cluster_by_similarity, dhash, hash_similarity, PerceptualHash,
+};
 pub use process::{
 	ImageFormat, ImageProcessor, ImageProcessorOptions, ImageResizeMode,
 	ImageResizeOptions,
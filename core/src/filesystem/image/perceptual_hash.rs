@@ -0,0 +1,269 @@
+use std::{collections::HashMap, fmt, str::FromStr};
+
+use image::{imageops::FilterType, DynamicImage};
+
+use crate::{CoreError, CoreResult};
+
+/// The width of the grayscale image used to compute a difference hash. It is one pixel wider
+/// than it is tall, since each bit compares a pixel with its right neighbour.
+const HASH_WIDTH: u32 = 9;
+const HASH_HEIGHT: u32 = 8;
+
+/// Computes a 64-bit difference hash (dHash) of an image. Visually similar images, e.g. the
+/// same page at a different resolution or compression level, produce hashes with a small
+/// hamming distance.
+pub fn dhash(image: &DynamicImage) -> u64 {
+	let reduced = image
+		.resize_exact(HASH_WIDTH, HASH_HEIGHT, FilterType::Triangle)
+		.into_luma8();
+
+	let mut hash = 0u64;
+	for y in 0..HASH_HEIGHT {
+		for x in 0..HASH_WIDTH - 1 {
+			let left = reduced.get_pixel(x, y).0[0];
+			let right = reduced.get_pixel(x + 1, y).0[0];
+			hash = (hash << 1) | u64::from(left > right);
+		}
+	}
+	hash
+}
+
+/// Computes the similarity of two hashes as a value between `0.0` (entirely different) and
+/// `1.0` (identical)
+pub fn hash_similarity(a: u64, b: u64) -> f64 {
+	1.0 - f64::from((a ^ b).count_ones()) / 64.0
+}
+
+/// The perceptual hashes of a media: the hash of its cover followed by the hashes of any
+/// interior pages which were sampled
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PerceptualHash(Vec<u64>);
+
+impl PerceptualHash {
+	pub fn new(cover: u64, interior: Vec<u64>) -> Self {
+		Self(std::iter::once(cover).chain(interior).collect())
+	}
+
+	pub fn cover(&self) -> u64 {
+		self.0[0]
+	}
+
+	/// Computes the similarity of two media as a value between `0.0` and `1.0`. The covers are
+	/// always compared, and interior pages are compared positionally when both media have them.
+	pub fn similarity(&self, other: &Self) -> f64 {
+		let scores = self
+			.0
+			.iter()
+			.zip(other.0.iter())
+			.map(|(a, b)| hash_similarity(*a, *b))
+			.collect::<Vec<_>>();
+		scores.iter().sum::<f64>() / scores.len() as f64
+	}
+}
+
+impl fmt::Display for PerceptualHash {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		let hashes = self
+			.0
+			.iter()
+			.map(|hash| format!("{hash:016x}"))
+			.collect::<Vec<_>>();
+		write!(f, "{}", hashes.join(","))
+	}
+}
+
+impl FromStr for PerceptualHash {
+	type Err = CoreError;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		let hashes = s
+			.split(',')
+			.map(|hash| u64::from_str_radix(hash.trim(), 16))
+			.collect::<Result<Vec<_>, _>>()
+			.map_err(|e| {
+				CoreError::InternalError(format!("Invalid perceptual hash {s}: {e}"))
+			})?;
+
+		if hashes.is_empty() {
+			return Err(CoreError::InternalError(
+				"Perceptual hash must contain at least one hash".to_string(),
+			));
+		}
+
+		Ok(Self(hashes))
+	}
+}
+
+/// Groups hashes into clusters of likely duplicates, where every member of a cluster is
+/// connected to another member by a similarity of at least `threshold`. Only clusters with
+/// more than one member are returned, as indices into `hashes`.
+///
+/// Rather than comparing every pair of hashes, they are bucketed by bands of their cover
+/// hash. Two covers which differ in `n` bits share at least one of `n + 1` bands, so every
+/// pair whose covers are at least `threshold` similar is compared. Pairs whose covers are
+/// less similar are never clustered, even if their interior pages would make up for it.
+pub fn cluster_by_similarity(
+	hashes: &[PerceptualHash],
+	threshold: f64,
+) -> Vec<Vec<usize>> {
+	fn find(parents: &mut [usize], index: usize) -> usize {
+		let mut root = index;
+		while parents[root] != root {
+			root = parents[root];
+		}
+		// Compress the path so later lookups are cheaper
+		let mut current = index;
+		while parents[current] != root {
+			let next = parents[current];
+			parents[current] = root;
+			current = next;
+		}
+		root
+	}
+
+	fn cover_band(cover: u64, band: u32, band_count: u32) -> u64 {
+		let start = band * 64 / band_count;
+		let width = (band + 1) * 64 / band_count - start;
+		let mask = if width == 64 {
+			u64::MAX
+		} else {
+			(1 << width) - 1
+		};
+		(cover >> start) & mask
+	}
+
+	// The small epsilon guards against rounding down a distance which is exactly at the
+	// threshold. An extra band only costs a few extra comparisons.
+	let max_distance = ((1.0 - threshold.clamp(0.0, 1.0)) * 64.0 + 1e-9).floor() as u32;
+	let band_count = (max_distance + 1).min(64);
+
+	let mut buckets = HashMap::<(u32, u64), Vec<usize>>::new();
+	for (index, hash) in hashes.iter().enumerate() {
+		for band in 0..band_count {
+			buckets
+				.entry((band, cover_band(hash.cover(), band, band_count)))
+				.or_default()
+				.push(index);
+		}
+	}
+
+	let mut parents = (0..hashes.len()).collect::<Vec<_>>();
+	for members in buckets.values() {
+		for (position, &i) in members.iter().enumerate() {
+			for &j in &members[position + 1..] {
+				let (a, b) = (find(&mut parents, i), find(&mut parents, j));
+				if a == b {
+					continue;
+				}
+
+				let (left, right) = (&hashes[i], &hashes[j]);
+				if hash_similarity(left.cover(), right.cover()) >= threshold
+					&& left.similarity(right) >= threshold
+				{
+					parents[b] = a;
+				}
+			}
+		}
+	}
+
+	let mut clusters = HashMap::<usize, Vec<usize>>::new();
+	for index in 0..hashes.len() {
+		let root = find(&mut parents, index);
+		clusters.entry(root).or_default().push(index);
+	}
+
+	// The roots depend on the order the buckets were visited in, so the clusters are ordered
+	// by their first member instead
+	let mut clusters = clusters
+		.into_values()
+		.filter(|members| members.len() > 1)
+		.collect::<Vec<_>>();
+	clusters.sort_by_key(|members| members[0]);
+	clusters
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::filesystem::image::tests::{get_test_jpg_path, get_test_png_path};
+
+	fn open(path: &str) -> DynamicImage {
+		image::open(path).unwrap()
+	}
+
+	#[test]
+	fn test_resized_image_is_similar() {
+		let image = open(&get_test_jpg_path());
+		let resized =
+			image.resize(image.width() / 3, image.height() / 3, FilterType::Nearest);
+
+		assert!(hash_similarity(dhash(&image), dhash(&resized)) > 0.9);
+	}
+
+	#[test]
+	fn test_different_images_are_dissimilar() {
+		let jpg = dhash(&open(&get_test_jpg_path()));
+		let png = dhash(&open(&get_test_png_path()));
+
+		assert!(hash_similarity(jpg, png) < hash_similarity(jpg, jpg));
+	}
+
+	#[test]
+	fn test_similarity_compares_shared_pages() {
+		let a = PerceptualHash::new(0, vec![u64::MAX]);
+		let b = PerceptualHash::new(0, vec![]);
+		assert_eq!(a.similarity(&b), 1.0);
+
+		let c = PerceptualHash::new(0, vec![0]);
+		assert_eq!(a.similarity(&c), 0.5);
+	}
+
+	#[test]
+	fn test_cluster_by_similarity() {
+		let hashes = vec![
+			PerceptualHash::new(0, vec![]),
+			PerceptualHash::new(u64::MAX, vec![]),
+			PerceptualHash::new(0b1, vec![]),
+			PerceptualHash::new(0b11, vec![]),
+			PerceptualHash::new(u64::MAX >> 32, vec![]),
+		];
+
+		assert_eq!(cluster_by_similarity(&hashes, 0.97), vec![vec![0, 2, 3]]);
+		assert!(cluster_by_similarity(&hashes, 1.0).is_empty());
+	}
+
+	#[test]
+	fn test_cluster_by_similarity_requires_similar_covers() {
+		// The interior pages are identical, which brings the overall similarity to ~0.97,
+		// but the covers alone are only ~0.94 similar
+		let hashes = vec![
+			PerceptualHash::new(0, vec![0]),
+			PerceptualHash::new(0b1111, vec![0]),
+		];
+
+		assert!(cluster_by_similarity(&hashes, 0.95).is_empty());
+		assert_eq!(cluster_by_similarity(&hashes, 0.9), vec![vec![0, 1]]);
+	}
+
+	#[test]
+	fn test_cluster_by_similarity_compares_across_bands() {
+		// Each cover differs from the first in a different band, so they are only found by
+		// being bucketed together with the first
+		let hashes = vec![
+			PerceptualHash::new(0, vec![]),
+			PerceptualHash::new(1, vec![]),
+			PerceptualHash::new(1 << 63, vec![]),
+			PerceptualHash::new(u64::MAX, vec![]),
+		];
+
+		assert_eq!(cluster_by_similarity(&hashes, 0.98), vec![vec![0, 1, 2]]);
+	}
+
+	#[test]
+	fn test_round_trip() {
+		let hash = PerceptualHash::new(0xdead_beef, vec![1, u64::MAX]);
+		let parsed = hash.to_string().parse::<PerceptualHash>().unwrap();
+		assert_eq!(hash, parsed);
+		assert!("not-a-hash".parse::<PerceptualHash>().is_err());
+	}
+}
@@ -1,5 +1,6 @@
 mod task_analyze_dimensions;
 mod task_page_count;
+mod task_perceptual_hash;
 mod utils;
 
 use serde::{Deserialize, Serialize};
@@ -7,8 +8,8 @@ use specta::Type;
 
 use crate::{
 	job::{
-		error::JobError, JobExt, JobOutputExt, JobTaskOutput, WorkerCtx, WorkingState,
-		WrappedJob,
+		error::JobError, JobExecuteLog, JobExt, JobOutputExt, JobTaskOutput, WorkerCtx,
+		WorkingState, WrappedJob,
 	},
 	prisma::{media, series},
 };
@@ -17,7 +18,7 @@ type MediaID = String;
 type SeriesID = String;
 type LibraryID = String;
 
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize)]
 pub enum AnalyzeMediaJobVariant {
 	/// Analyze an individual media item, specified by ID.
 	AnalyzeSingleItem(MediaID),
@@ -35,8 +36,11 @@ pub enum AnalyzeMediaTask {
 	UpdatePageCount(MediaID),
 	/// Analyze and store dimensions for each page of a media item specified by an ID.
 	AnalyzePageDimensions(MediaID),
-	/// Performs both [`UpdatePageCount`] and then [`AnalyzePageDimensions`] in sequence for
-	/// the media item specified by an ID.
+	/// Compute and store a perceptual hash of the cover (and optionally some interior pages)
+	/// of a media item specified by an ID, used to detect likely duplicates.
+	ComputePerceptualHash(MediaID),
+	/// Performs [`UpdatePageCount`], [`AnalyzePageDimensions`] and then
+	/// [`ComputePerceptualHash`] in sequence for the media item specified by an ID.
 	FullAnalysis(MediaID),
 }
 
@@ -48,6 +52,9 @@ pub struct AnalyzeMediaOutput {
 	image_dimensions_analyzed: u64,
 	/// The number of media item updates performed.
 	media_updated: u64,
+	/// The number of perceptual hashes computed.
+	#[serde(default)]
+	perceptual_hashes_computed: u64,
 }
 
 impl JobOutputExt for AnalyzeMediaOutput {
@@ -55,12 +62,13 @@ impl JobOutputExt for AnalyzeMediaOutput {
 		self.page_counts_analyzed += updated.page_counts_analyzed;
 		self.image_dimensions_analyzed += updated.image_dimensions_analyzed;
 		self.media_updated += updated.media_updated;
+		self.perceptual_hashes_computed += updated.perceptual_hashes_computed;
 	}
 }
 
 /// A job that analyzes a media item and updates the database
 /// with information from the analysis.
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct AnalyzeMediaJob {
 	pub variant: AnalyzeMediaJobVariant,
 }
@@ -188,6 +196,7 @@ impl JobExt for AnalyzeMediaJob {
 		task: Self::Task,
 	) -> Result<JobTaskOutput<Self>, JobError> {
 		let mut output = Self::Output::default();
+		let mut logs = vec![];
 
 		match task {
 			AnalyzeMediaTask::UpdatePageCount(id) => {
@@ -196,19 +205,39 @@ impl JobExt for AnalyzeMediaJob {
 			AnalyzeMediaTask::AnalyzePageDimensions(id) => {
 				task_analyze_dimensions::execute(id, ctx, &mut output).await?;
 			},
+			AnalyzeMediaTask::ComputePerceptualHash(id) => {
+				task_perceptual_hash::execute(id, ctx, &mut output).await?;
+			},
 			AnalyzeMediaTask::FullAnalysis(id) => {
 				// TODO This is suboptimal because it buffers the file twice, this should be improved later.
 				// First page count needs to be updated
 				task_page_count::execute(id.clone(), ctx, &mut output).await?;
 				// Then we can do the dimensions analysis
-				task_analyze_dimensions::execute(id, ctx, &mut output).await?;
+				task_analyze_dimensions::execute(id.clone(), ctx, &mut output).await?;
+				// Finally, the perceptual hash (which relies on the page count). It is only used
+				// to find likely duplicates, so a failure shouldn't fail the rest of the analysis
+				if let Err(error) =
+					task_perceptual_hash::execute(id.clone(), ctx, &mut output).await
+				{
+					tracing::error!(
+						?error,
+						media_id = id,
+						"Failed to compute perceptual hash"
+					);
+					logs.push(
+						JobExecuteLog::error(format!(
+							"Failed to compute perceptual hash: {error}"
+						))
+						.with_ctx(format!("Media ID: {id}")),
+					);
+				}
 			},
 		}
 
 		Ok(JobTaskOutput {
 			output,
 			subtasks: vec![],
-			logs: vec![],
+			logs,
 		})
 	}
 }
@@ -0,0 +1,99 @@
+use tokio::task::spawn_blocking;
+
+use crate::{
+	filesystem::{
+		analyze_media_job::{utils::fetch_media_with_metadata, AnalyzeMediaOutput},
+		image::{dhash, PerceptualHash},
+		media::process::get_page,
+	},
+	job::{error::JobError, WorkerCtx},
+	prisma::media,
+};
+
+/// The logic for [`super::AnalyzeMediaTask::ComputePerceptualHash`].
+///
+/// Computes a perceptual hash of the cover of a media item, along with a number of evenly
+/// spaced interior pages (as configured by `perceptual_hash_pages`), then writes it to the
+/// database so that likely duplicates can be found.
+///
+/// # Arguments
+/// * `id` - The id for the media item being analyzed
+/// * `ctx` - A reference to the [`WorkerCtx`] for the job
+/// * `output` - A mutable reference to the job output
+pub(crate) async fn execute(
+	id: String,
+	ctx: &WorkerCtx,
+	output: &mut AnalyzeMediaOutput,
+) -> Result<(), JobError> {
+	let media_item = fetch_media_with_metadata(&id, ctx).await?;
+
+	let page_count = media_item
+		.metadata
+		.and_then(|metadata| metadata.page_count)
+		.unwrap_or(media_item.pages);
+	let pages = interior_pages(page_count, ctx.config.perceptual_hash_pages);
+	let path = media_item.path.clone();
+	let config = ctx.config.clone();
+
+	// Reading and decoding the pages is blocking work, so it is kept off the async runtime
+	let perceptual_hash = spawn_blocking(move || {
+		let hash_page = |page: i32| -> Result<u64, JobError> {
+			let (content_type, page_data) = get_page(&path, page, &config)?;
+			let image_format = content_type.try_into()?;
+			let image = image::load_from_memory_with_format(&page_data, image_format)
+				.map_err(|e| {
+					JobError::TaskFailed(format!("Error loading image data: {e}"))
+				})?;
+			Ok(dhash(&image))
+		};
+
+		let cover = hash_page(1)?;
+		let interior = pages
+			.into_iter()
+			.map(hash_page)
+			.collect::<Result<Vec<_>, _>>()?;
+		Ok::<_, JobError>(PerceptualHash::new(cover, interior))
+	})
+	.await
+	.map_err(|e| {
+		JobError::TaskFailed(format!("Failed to compute perceptual hash: {e}"))
+	})??;
+
+	ctx.db
+		.media()
+		.update(
+			media::id::equals(media_item.id),
+			vec![media::perceptual_hash::set(Some(
+				perceptual_hash.to_string(),
+			))],
+		)
+		.exec()
+		.await?;
+	output.perceptual_hashes_computed += 1;
+
+	Ok(())
+}
+
+/// Selects `count` evenly spaced pages from the interior of a book, excluding the cover
+fn interior_pages(page_count: i32, count: usize) -> Vec<i32> {
+	let available = (page_count - 1).max(0) as usize;
+	let count = count.min(available);
+	let mut pages = (1..=count)
+		.map(|i| 1 + (i * available).div_ceil(count + 1) as i32)
+		.collect::<Vec<_>>();
+	pages.dedup();
+	pages
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_interior_pages() {
+		assert_eq!(interior_pages(20, 0), Vec::<i32>::new());
+		assert_eq!(interior_pages(1, 3), Vec::<i32>::new());
+		assert_eq!(interior_pages(21, 3), vec![6, 11, 16]);
+		assert_eq!(interior_pages(3, 5), vec![2, 3]);
+	}
+}
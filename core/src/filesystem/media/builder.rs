@@ -4,8 +4,14 @@ use prisma_client_rust::chrono::{DateTime, FixedOffset, Utc};
 
 use crate::{
 	config::StumpConfig,
-	db::entity::{LibraryConfig, Media, Series},
-	filesystem::{process, FileParts, PathUtils, SeriesJson},
+	db::entity::{
+		CompiledFilenamePatterns, LibraryConfig, Media, MediaMetadata, Series,
+		SeriesMetadata,
+	},
+	filesystem::{
+		process, storage::storage_for_path, FileParts, FileProcessorOptions, PathUtils,
+		SeriesJson,
+	},
 	CoreError, CoreResult,
 };
 
@@ -14,6 +20,7 @@ pub struct MediaBuilder {
 	series_id: String,
 	library_config: LibraryConfig,
 	config: StumpConfig,
+	filename_patterns: CompiledFilenamePatterns,
 }
 
 impl MediaBuilder {
@@ -28,9 +35,21 @@ impl MediaBuilder {
 			series_id: series_id.to_string(),
 			library_config,
 			config: config.clone(),
+			filename_patterns: CompiledFilenamePatterns::default(),
 		}
 	}
 
+	/// Use the given (compiled) filename patterns of the library to infer metadata which is
+	/// missing from the file. The patterns are compiled by the caller so that they can be
+	/// shared by every book built during a scan
+	pub fn with_filename_patterns(
+		mut self,
+		filename_patterns: CompiledFilenamePatterns,
+	) -> Self {
+		self.filename_patterns = filename_patterns;
+		self
+	}
+
 	pub fn rebuild(self, media: &Media) -> CoreResult<Media> {
 		let generated = self.build()?;
 		Ok(Media {
@@ -39,6 +58,61 @@ impl MediaBuilder {
 		})
 	}
 
+	/// Rebuilds only the hashes and/or metadata of an existing media, leaving every other field
+	/// untouched. Values which could not be generated (e.g. hashing is disabled for the library)
+	/// are kept as they were.
+	pub fn rebuild_partial(
+		self,
+		media: &Media,
+		hashes: bool,
+		metadata: bool,
+	) -> CoreResult<Media> {
+		let options = FileProcessorOptions {
+			convert_rar_to_zip: false,
+			delete_conversion_source: false,
+			generate_file_hashes: hashes && self.library_config.generate_file_hashes,
+			generate_koreader_hashes: hashes
+				&& self.library_config.generate_koreader_hashes,
+			process_metadata: metadata && self.library_config.process_metadata,
+		};
+		let mut processed_entry = process(&self.path, options, &self.config)?;
+
+		tracing::trace!(?processed_entry, "Partially processed entry");
+
+		if metadata {
+			let inferred = self
+				.filename_patterns
+				.extract(&self.path.file_parts().file_stem);
+			if let Some(inferred) = inferred {
+				inferred.fill_missing(
+					processed_entry
+						.metadata
+						.get_or_insert_with(MediaMetadata::default),
+				);
+			}
+		}
+
+		let mut rebuilt = media.clone();
+		if hashes {
+			rebuilt.hash = processed_entry.hash.or(rebuilt.hash);
+			rebuilt.koreader_hash =
+				processed_entry.koreader_hash.or(rebuilt.koreader_hash);
+		}
+		if let Some(mut generated_metadata) =
+			processed_entry.metadata.filter(|_| metadata)
+		{
+			if generated_metadata
+				.page_count
+				.is_some_and(|count| count != media.pages)
+			{
+				generated_metadata.page_count = Some(media.pages);
+			}
+			rebuilt.metadata = Some(generated_metadata);
+		}
+
+		Ok(rebuilt)
+	}
+
 	pub fn build(self) -> CoreResult<Media> {
 		let mut processed_entry =
 			process(&self.path, self.library_config.into(), &self.config)?;
@@ -50,17 +124,28 @@ impl MediaBuilder {
 
 		let FileParts {
 			file_name,
+			file_stem,
 			extension,
-			..
 		} = path.file_parts();
 		let path_str = path.to_str().unwrap_or_default().to_string();
 
-		let (raw_size, last_modified_at) = path.metadata().map(|m| {
-			let datetime: Option<DateTime<Utc>> = m.modified().ok().map(|t| t.into());
-			let last_modified_at: Option<DateTime<FixedOffset>> =
-				datetime.map(|dt| dt.into());
-			(m.len(), last_modified_at)
-		})?;
+		if let Some(inferred) = self.filename_patterns.extract(&file_stem) {
+			tracing::trace!(?inferred, "Inferred metadata from file name");
+			inferred.fill_missing(
+				processed_entry
+					.metadata
+					.get_or_insert_with(MediaMetadata::default),
+			);
+		}
+
+		let (raw_size, last_modified_at) = storage_for_path(path, &self.config)?
+			.metadata(path)
+			.map(|entry| {
+				let datetime: Option<DateTime<Utc>> = entry.modified_at.map(|t| t.into());
+				let last_modified_at: Option<DateTime<FixedOffset>> =
+					datetime.map(|dt| dt.into());
+				(entry.size, last_modified_at)
+			})?;
 		let size = raw_size.try_into().unwrap_or_else(|_| {
 			tracing::error!(?raw_size, ?path, "Failed to convert file size to i64");
 			0
@@ -96,9 +181,21 @@ impl MediaBuilder {
 	}
 }
 
+/// Compiles the filename patterns of a library, logging (and ignoring) any which are invalid
+pub(crate) fn compile_filename_patterns(
+	library_config: &LibraryConfig,
+) -> CompiledFilenamePatterns {
+	library_config
+		.filename_patterns
+		.build()
+		.map_err(|error| tracing::error!(?error, "Failed to compile filename patterns"))
+		.unwrap_or_default()
+}
+
 pub struct SeriesBuilder {
 	path: PathBuf,
 	library_id: String,
+	filename_patterns: CompiledFilenamePatterns,
 }
 
 impl SeriesBuilder {
@@ -106,9 +203,20 @@ impl SeriesBuilder {
 		Self {
 			path: path.to_path_buf(),
 			library_id: library_id.to_string(),
+			filename_patterns: CompiledFilenamePatterns::default(),
 		}
 	}
 
+	/// Use the given (compiled) filename patterns of the library to infer metadata from the
+	/// series directory name, when there is no series.json
+	pub fn with_filename_patterns(
+		mut self,
+		filename_patterns: CompiledFilenamePatterns,
+	) -> Self {
+		self.filename_patterns = filename_patterns;
+		self
+	}
+
 	pub fn build(self) -> CoreResult<Series> {
 		let path = self.path.as_path();
 
@@ -124,7 +232,26 @@ impl SeriesBuilder {
 				.ok_or(CoreError::InternalError(
 					"Could not convert series path to string".to_string(),
 				))?;
-		let metadata = SeriesJson::from_folder(path).map(|json| json.metadata).ok();
+		let metadata = SeriesJson::from_folder(path)
+			.map(|json| json.metadata)
+			.ok()
+			.or_else(|| {
+				self.filename_patterns.extract(&file_name).map(|inferred| {
+					SeriesMetadata {
+						_type: "comicSeries".to_string(),
+						title: inferred.series.or(inferred.title),
+						volume: inferred.volume,
+						summary: None,
+						publisher: None,
+						imprint: None,
+						comicid: None,
+						booktype: None,
+						age_rating: None,
+						status: None,
+						locked_fields: vec![],
+					}
+				})
+			});
 
 		tracing::debug!(file_name, path_str, ?metadata, "Parsed series information");
 
@@ -141,9 +268,12 @@ impl SeriesBuilder {
 #[cfg(test)]
 mod tests {
 	use super::*;
-	use crate::filesystem::media::tests::{
-		get_test_cbz_path, get_test_epub_path, get_test_pdf_path, get_test_rar_path,
-		get_test_zip_path,
+	use crate::{
+		db::entity::{FilenamePattern, FilenamePatterns},
+		filesystem::media::tests::{
+			get_test_cbz_path, get_test_epub_path, get_test_pdf_path, get_test_rar_path,
+			get_test_zip_path,
+		},
 	};
 
 	#[test]
@@ -187,6 +317,56 @@ mod tests {
 		assert_eq!(media.extension, "pdf");
 	}
 
+	#[test]
+	fn test_rebuild_partial_preserves_fields() {
+		let path = get_test_zip_path();
+		let existing = Media {
+			id: "existing_id".to_string(),
+			name: "custom name".to_string(),
+			pages: 42,
+			hash: Some("existing_hash".to_string()),
+			..Default::default()
+		};
+		let library_config = LibraryConfig {
+			generate_file_hashes: true,
+			..Default::default()
+		};
+
+		let media = MediaBuilder::new(
+			Path::new(&path),
+			"series_id",
+			library_config,
+			&StumpConfig::debug(),
+		)
+		.rebuild_partial(&existing, true, false)
+		.expect("Failed to partially rebuild media");
+
+		assert_eq!(media.id, "existing_id");
+		assert_eq!(media.name, "custom name");
+		assert_eq!(media.pages, 42);
+		assert!(media.hash.is_some());
+		assert_ne!(media.hash.as_deref(), Some("existing_hash"));
+	}
+
+	#[test]
+	fn test_build_series_with_filename_patterns() {
+		let filename_patterns =
+			FilenamePatterns::new(vec![FilenamePattern::template("{series} ({year})")])
+				.unwrap()
+				.build()
+				.unwrap();
+
+		let series = SeriesBuilder::new(Path::new("/comics/Saga (2012)"), "library_id")
+			.with_filename_patterns(filename_patterns)
+			.build()
+			.expect("Failed to build series");
+
+		let metadata = series.metadata.expect("Metadata should be inferred");
+		assert_eq!(metadata.title.as_deref(), Some("Saga"));
+		// The year is not a volume, so it should not be used as one
+		assert_eq!(metadata.volume, None);
+	}
+
 	fn build_media_test_helper(path: String) -> Result<Media, CoreError> {
 		let path = Path::new(&path);
 		let library_config = LibraryConfig {
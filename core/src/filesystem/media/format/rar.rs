@@ -19,7 +19,7 @@ use crate::{
 			process::{
 				FileConverter, FileProcessor, FileProcessorOptions, ProcessedFile,
 			},
-			utils::metadata_from_buf,
+			utils::{metadata_from_buf, with_metron_info_story_arcs},
 			zip::ZipProcessor,
 		},
 		FileParts, PathUtils,
@@ -125,6 +125,7 @@ impl FileProcessor for RarProcessor {
 		let mut archive = RarProcessor::open_for_processing(path)?;
 		let mut pages = 0;
 		let mut metadata_buf = None;
+		let mut metron_info_buf = None;
 
 		while let Ok(Some(header)) = archive.read_header() {
 			let entry = header.entry();
@@ -143,6 +144,12 @@ impl FileProcessor for RarProcessor {
 				let (data, rest) = header.read()?;
 				metadata_buf = Some(data);
 				archive = rest;
+			} else if entry.filename.as_os_str() == "MetronInfo.xml"
+				&& options.process_metadata
+			{
+				let (data, rest) = header.read()?;
+				metron_info_buf = Some(data);
+				archive = rest;
 			} else {
 				// If the entry is not an image then it cannot be a valid page
 				if entry.filename.is_img() {
@@ -152,13 +159,18 @@ impl FileProcessor for RarProcessor {
 			}
 		}
 
-		let metadata = if let Some(buf) = metadata_buf {
+		let mut metadata = if let Some(buf) = metadata_buf {
 			let content_str = std::str::from_utf8(&buf)?;
 			metadata_from_buf(content_str)
 		} else {
 			None
 		};
 
+		if let Some(buf) = metron_info_buf {
+			let content_str = std::str::from_utf8(&buf)?;
+			metadata = with_metron_info_story_arcs(metadata, content_str);
+		}
+
 		Ok(ProcessedFile {
 			path: PathBuf::from(path),
 			hash,
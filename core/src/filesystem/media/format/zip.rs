@@ -9,7 +9,7 @@ use crate::{
 		hash,
 		media::{
 			process::{FileProcessor, FileProcessorOptions, ProcessedFile},
-			utils::{metadata_from_buf, sort_file_names},
+			utils::{metadata_from_buf, sort_file_names, with_metron_info_story_arcs},
 		},
 		FileParts, PathUtils,
 	},
@@ -69,6 +69,7 @@ impl FileProcessor for ZipProcessor {
 		let mut archive = zip::ZipArchive::new(zip_file)?;
 
 		let mut metadata = None;
+		let mut metron_info = None;
 		let mut pages = 0;
 
 		let hash = generate_file_hashes.then(|| Self::hash(path)).flatten();
@@ -102,11 +103,20 @@ impl FileProcessor for ZipProcessor {
 				let contents = String::from_utf8_lossy(&contents).to_string();
 				trace!(contents_len = contents.len(), "Read ComicInfo.xml");
 				metadata = metadata_from_buf(&contents);
+			} else if file_name == "MetronInfo.xml" && process_metadata {
+				trace!("Found MetronInfo.xml");
+				let mut contents = Vec::new();
+				file.read_to_end(&mut contents)?;
+				metron_info = Some(String::from_utf8_lossy(&contents).to_string());
 			} else if content_type.is_image() {
 				pages += 1;
 			}
 		}
 
+		if let Some(contents) = metron_info {
+			metadata = with_metron_info_story_arcs(metadata, &contents);
+		}
+
 		Ok(ProcessedFile {
 			path: PathBuf::from(path),
 			hash,
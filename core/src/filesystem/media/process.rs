@@ -13,8 +13,12 @@ use crate::{
 	config::StumpConfig,
 	db::entity::{LibraryConfig, MediaMetadata, SeriesMetadata},
 	filesystem::{
-		content_type::ContentType, epub::EpubProcessor, error::FileError,
-		image::ImageFormat, pdf::PdfProcessor,
+		content_type::ContentType,
+		epub::EpubProcessor,
+		error::FileError,
+		image::ImageFormat,
+		pdf::PdfProcessor,
+		storage::{is_object_storage_path, resolve_local_path, storage_for_path},
 	},
 };
 
@@ -142,17 +146,30 @@ pub struct ProcessedFile {
 /// A function to process a file in a blocking manner. This will call the appropriate
 /// [`FileProcessor::process`] implementation based on the file's mime type, or return an
 /// error if the file type is not supported.
+///
+/// Files which live in object storage are processed from a local copy in the cache directory,
+/// and are never converted since the converted file could not be written back in place.
 pub fn process(
 	path: &Path,
-	options: FileProcessorOptions,
+	mut options: FileProcessorOptions,
 	config: &StumpConfig,
 ) -> Result<ProcessedFile, FileError> {
 	debug!(?path, ?options, "Processing entry");
-	let mime = ContentType::from_path(path).mime_type();
+	let storage = storage_for_path(path, config)?;
+	let local_path = storage.local_path(path)?;
+	if !storage.is_local() && options.convert_rar_to_zip {
+		tracing::warn!(
+			?path,
+			"Skipping RAR conversion for a file in object storage"
+		);
+		options.convert_rar_to_zip = false;
+	}
 
-	let path_str = path.to_str().unwrap_or_default();
+	let mime = ContentType::from_path(&local_path).mime_type();
 
-	match mime.as_str() {
+	let path_str = local_path.to_str().unwrap_or_default();
+
+	let mut processed = match mime.as_str() {
 		"application/zip" | "application/vnd.comicbook+zip" => {
 			ZipProcessor::process(path_str, options, config)
 		},
@@ -162,7 +179,13 @@ pub fn process(
 		"application/epub+zip" => EpubProcessor::process(path_str, options, config),
 		"application/pdf" => PdfProcessor::process(path_str, options, config),
 		_ => Err(FileError::UnsupportedFileType(path.display().to_string())),
+	}?;
+
+	if !storage.is_local() {
+		processed.path = path.to_path_buf();
 	}
+
+	Ok(processed)
 }
 
 /// A function to process a file in the context of a spawned, blocking task. This will call the
@@ -210,6 +233,8 @@ pub fn get_page(
 	page: i32,
 	config: &StumpConfig,
 ) -> Result<(ContentType, Vec<u8>), FileError> {
+	let local_path = resolve_local_path(Path::new(path), config)?;
+	let path = local_path.to_str().unwrap_or_default();
 	let mime = ContentType::from_file(path).mime_type();
 
 	match mime.as_str() {
@@ -266,6 +291,8 @@ pub async fn get_page_async(
 /// Get the number of pages in a file. This will call the appropriate [`FileProcessor::get_page_count`]
 /// implementation based on the file's mime type, or return an error if the file type is not supported.
 pub fn get_page_count(path: &str, config: &StumpConfig) -> Result<i32, FileError> {
+	let local_path = resolve_local_path(Path::new(path), config)?;
+	let path = local_path.to_str().unwrap_or_default();
 	let mime = ContentType::from_file(path).mime_type();
 
 	match mime.as_str() {
@@ -321,10 +348,17 @@ pub async fn get_page_count_async(
 /// Get the content types of a list of pages of a file. This will call the appropriate
 /// [`FileProcessor::get_page_content_types`] implementation based on the file's mime type, or return an
 /// error if the file type is not supported.
+///
+/// Files in object storage are not downloaded just to determine their page types, so an empty
+/// map is returned for them.
 pub fn get_content_types_for_pages(
 	path: &str,
 	pages: Vec<i32>,
 ) -> Result<HashMap<i32, ContentType>, FileError> {
+	if is_object_storage_path(Path::new(path)) {
+		return Ok(HashMap::default());
+	}
+
 	let mime = ContentType::from_file(path).mime_type();
 
 	match mime.as_str() {
@@ -345,10 +379,17 @@ pub fn get_content_types_for_pages(
 /// # Arguments
 /// * `path` - The path to the file
 /// * `page` - The page number to get the content type for, 1-indexed
+///
+/// Files in object storage are not downloaded just to determine their page types, so
+/// [`ContentType::UNKNOWN`] is returned for them.
 fn get_content_type_for_page_sync(
 	path: &str,
 	page: i32,
 ) -> Result<ContentType, FileError> {
+	if is_object_storage_path(Path::new(path)) {
+		return Ok(ContentType::UNKNOWN);
+	}
+
 	let mime = ContentType::from_file(path).mime_type();
 
 	let result = match mime.as_str() {
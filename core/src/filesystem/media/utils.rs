@@ -1,6 +1,7 @@
+use serde::Deserialize;
 use tracing::error;
 
-use crate::db::entity::MediaMetadata;
+use crate::db::entity::{MediaMetadata, MediaStoryArc};
 
 pub fn is_accepted_cover_name(name: &str) -> bool {
 	let cover_file_names = ["cover", "thumbnail", "folder"];
@@ -18,7 +19,10 @@ pub(crate) fn metadata_from_buf(contents: &str) -> Option<MediaMetadata> {
 	}
 
 	match serde_xml_rs::from_str(adjusted) {
-		Ok(meta) => Some(meta),
+		Ok(meta) => Some(MediaMetadata {
+			story_arcs: story_arcs_from_comic_info(adjusted),
+			..meta
+		}),
 		Err(err) => {
 			println!("Failed to parse metadata from buf: {err}");
 			error!(error = ?err, content = adjusted, "Failed to parse metadata from buf");
@@ -27,6 +31,84 @@ pub(crate) fn metadata_from_buf(contents: &str) -> Option<MediaMetadata> {
 	}
 }
 
+/// The story arcs of a ComicInfo.xml file, which are parallel comma separated lists
+#[derive(Default, Deserialize)]
+struct ComicInfoStoryArcs {
+	#[serde(rename = "StoryArc", default)]
+	story_arc: Option<String>,
+	#[serde(rename = "StoryArcNumber", default)]
+	story_arc_number: Option<String>,
+}
+
+fn story_arcs_from_comic_info(contents: &str) -> Vec<MediaStoryArc> {
+	serde_xml_rs::from_str::<ComicInfoStoryArcs>(contents)
+		.ok()
+		.and_then(|arcs| {
+			arcs.story_arc.map(|names| {
+				MediaStoryArc::from_comic_info(&names, arcs.story_arc_number.as_deref())
+			})
+		})
+		.unwrap_or_default()
+}
+
+/// The story arcs of a MetronInfo.xml file, e.g.
+/// `<Arcs><Arc><Name>Civil War</Name><Number>3</Number></Arc></Arcs>`
+#[derive(Default, Deserialize)]
+struct MetronInfoStoryArcs {
+	#[serde(rename = "Arcs", default)]
+	arcs: MetronInfoArcs,
+}
+
+#[derive(Default, Deserialize)]
+struct MetronInfoArcs {
+	#[serde(rename = "Arc", default)]
+	arc: Vec<MetronInfoArc>,
+}
+
+#[derive(Deserialize)]
+struct MetronInfoArc {
+	#[serde(rename = "Name")]
+	name: String,
+	#[serde(rename = "Number", default)]
+	number: Option<String>,
+}
+
+/// Fill in the story arcs of the metadata read from a ComicInfo.xml with those of a
+/// MetronInfo.xml, if the ComicInfo.xml doesn't list any. The rest of a MetronInfo.xml is
+/// not read (yet), so it is ignored when a file has no ComicInfo.xml.
+pub(crate) fn with_metron_info_story_arcs(
+	metadata: Option<MediaMetadata>,
+	contents: &str,
+) -> Option<MediaMetadata> {
+	let metadata = metadata?;
+	if !metadata.story_arcs.is_empty() {
+		return Some(metadata);
+	}
+
+	match serde_xml_rs::from_str::<MetronInfoStoryArcs>(contents.trim()) {
+		Ok(metron_info) => {
+			let story_arcs = metron_info
+				.arcs
+				.arc
+				.into_iter()
+				.map(|arc| MediaStoryArc {
+					name: arc.name.trim().to_string(),
+					position: arc.number.and_then(|number| number.trim().parse().ok()),
+				})
+				.filter(|arc| !arc.name.is_empty())
+				.collect();
+			Some(MediaMetadata {
+				story_arcs,
+				..metadata
+			})
+		},
+		Err(err) => {
+			error!(error = ?err, "Failed to parse story arcs from MetronInfo.xml");
+			Some(metadata)
+		},
+	}
+}
+
 pub(crate) fn sort_file_names<S>(file_names: &mut [S])
 where
 	S: AsRef<str>,
@@ -80,6 +162,49 @@ mod tests {
 		assert_eq!(metadata.volume, Some(2016));
 	}
 
+	#[test]
+	fn test_parse_comic_info_story_arcs() {
+		let contents = "<?xml version=\"1.0\"?>\n<ComicInfo>\n  <Series>Captain America</Series>\n  <Number>22</Number>\n  <StoryArc>Civil War, The Death of Captain America</StoryArc>\n  <StoryArcNumber>4, 1</StoryArcNumber>\n</ComicInfo>";
+		let metadata = metadata_from_buf(contents).unwrap();
+
+		assert_eq!(
+			metadata.story_arcs,
+			vec![
+				MediaStoryArc {
+					name: "Civil War".to_string(),
+					position: Some(4.0),
+				},
+				MediaStoryArc {
+					name: "The Death of Captain America".to_string(),
+					position: Some(1.0),
+				},
+			]
+		);
+	}
+
+	#[test]
+	fn test_parse_metron_info_story_arcs() {
+		let contents = "<?xml version=\"1.0\"?>\n<MetronInfo>\n  <Arcs>\n    <Arc id=\"1\">\n      <Name>Civil War</Name>\n      <Number>4</Number>\n    </Arc>\n    <Arc>\n      <Name>Heroic Age</Name>\n    </Arc>\n  </Arcs>\n</MetronInfo>";
+		let metadata =
+			with_metron_info_story_arcs(Some(MediaMetadata::default()), contents)
+				.unwrap();
+
+		assert_eq!(
+			metadata.story_arcs,
+			vec![
+				MediaStoryArc {
+					name: "Civil War".to_string(),
+					position: Some(4.0),
+				},
+				MediaStoryArc {
+					name: "Heroic Age".to_string(),
+					position: None,
+				},
+			]
+		);
+		assert!(with_metron_info_story_arcs(None, contents).is_none());
+	}
+
 	#[test]
 	fn test_malformed_media_xml() {
 		// An empty string
@@ -9,6 +9,8 @@ mod hash;
 pub mod image;
 pub mod media;
 pub mod scanner;
+pub mod storage;
+mod stumpignore;
 
 pub use common::*;
 pub use content_type::ContentType;
@@ -17,3 +19,4 @@ pub use directory_listing::{
 };
 pub use error::FileError;
 pub use media::*;
+pub use stumpignore::{IgnoreMatcher, STUMPIGNORE_FILENAME};
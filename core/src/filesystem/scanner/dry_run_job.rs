@@ -0,0 +1,611 @@
+use std::{collections::VecDeque, path::PathBuf};
+
+use serde::{Deserialize, Serialize};
+use specta::Type;
+
+use crate::{
+	db::entity::{
+		macros::library_path_with_options_select, CoreJobOutput, LibraryConfig,
+	},
+	filesystem::IgnoreMatcher,
+	job::{
+		error::JobError, Executor, JobExt, JobOutputExt, JobProgress, JobTaskOutput,
+		WorkerCtx, WorkingState, WrappedJob,
+	},
+	prisma::{library, library_config, media, series},
+	CoreEvent,
+};
+
+use super::{
+	utils::get_library_roots, walk_library, walk_series, LibraryRoots, ScanOptions,
+	WalkedLibrary, WalkedSeries, WalkerCtx,
+};
+
+/// The entity which a [`ScanDryRunJob`] targets
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ScanDryRunTarget {
+	Library,
+	Series,
+}
+
+/// The task variants that are used to perform a dry-run scan
+#[derive(Serialize, Deserialize)]
+pub enum ScanDryRunTask {
+	WalkSeries(PathBuf),
+}
+
+/// A job which performs the discovery steps of a [`super::LibraryScanJob`] or
+/// [`super::SeriesScanJob`] without writing anything to the database, reporting what a real
+/// scan would have done instead
+#[derive(Clone, Serialize, Deserialize)]
+pub struct ScanDryRunJob {
+	pub id: String,
+	pub path: String,
+	pub target: ScanDryRunTarget,
+	pub config: Option<LibraryConfig>,
+	pub options: ScanOptions,
+	/// The roots of the library the target belongs to, which are resolved during init
+	roots: Option<LibraryRoots>,
+}
+
+impl ScanDryRunJob {
+	/// Create a dry-run of a [`super::LibraryScanJob`] for the library with the given id and path
+	pub fn library(
+		id: String,
+		path: String,
+		options: Option<ScanOptions>,
+	) -> Box<WrappedJob<ScanDryRunJob>> {
+		Self::new(id, path, ScanDryRunTarget::Library, options)
+	}
+
+	/// Create a dry-run of a [`super::SeriesScanJob`] for the series with the given id and path
+	pub fn series(
+		id: String,
+		path: String,
+		options: Option<ScanOptions>,
+	) -> Box<WrappedJob<ScanDryRunJob>> {
+		Self::new(id, path, ScanDryRunTarget::Series, options)
+	}
+
+	fn new(
+		id: String,
+		path: String,
+		target: ScanDryRunTarget,
+		options: Option<ScanOptions>,
+	) -> Box<WrappedJob<ScanDryRunJob>> {
+		WrappedJob::new(Self {
+			id,
+			path,
+			target,
+			config: None,
+			options: options.unwrap_or_default(),
+			roots: None,
+		})
+	}
+}
+
+/// The changes a scan would make to a single series
+#[derive(Clone, Serialize, Deserialize, Default, Debug, Type)]
+pub struct SeriesDryRunReport {
+	/// The path of the series on disk
+	path: String,
+	/// Whether the series is missing from disk, in which case it and all of its media would be
+	/// marked as missing
+	is_missing: bool,
+	/// The paths of media which would be created
+	media_to_create: Vec<String>,
+	/// The paths of media which would be visited and updated
+	media_to_update: Vec<String>,
+	/// The paths of media which would be marked as missing
+	media_to_mark_missing: Vec<String>,
+	/// The paths of media which were previously missing and would be restored
+	media_to_restore: Vec<String>,
+	/// The paths of files which were excluded by ignore rules or `.stumpignore` files
+	ignored_files: Vec<String>,
+	/// The number of files which would be skipped because they have not changed since the last scan
+	skipped_files: u64,
+}
+
+/// The report produced by a dry-run scan, describing the changes a real scan would make
+#[derive(Clone, Serialize, Deserialize, Default, Debug, Type)]
+pub struct ScanDryRunOutput {
+	/// Whether the library is missing from disk, in which case it and all of its contents would
+	/// be marked as missing
+	library_is_missing: bool,
+	/// The paths of series which would be created
+	series_to_create: Vec<String>,
+	/// The paths of series which would be marked as missing
+	series_to_mark_missing: Vec<String>,
+	/// The paths of series which were previously missing and would be restored
+	series_to_restore: Vec<String>,
+	/// The paths of directories which were excluded by ignore rules or `.stumpignore` files
+	ignored_directories: Vec<String>,
+	/// The changes which would be made within each series which was walked
+	series: Vec<SeriesDryRunReport>,
+}
+
+impl JobOutputExt for ScanDryRunOutput {
+	fn update(&mut self, updated: Self) {
+		self.library_is_missing |= updated.library_is_missing;
+		self.series_to_create.extend(updated.series_to_create);
+		self.series_to_mark_missing
+			.extend(updated.series_to_mark_missing);
+		self.series_to_restore.extend(updated.series_to_restore);
+		self.ignored_directories.extend(updated.ignored_directories);
+		self.series.extend(updated.series);
+	}
+}
+
+fn paths_to_strings(paths: Vec<PathBuf>) -> Vec<String> {
+	paths
+		.into_iter()
+		.map(|path| path.to_string_lossy().to_string())
+		.collect()
+}
+
+#[async_trait::async_trait]
+impl JobExt for ScanDryRunJob {
+	const NAME: &'static str = "scan_dry_run";
+
+	type Output = ScanDryRunOutput;
+	type Task = ScanDryRunTask;
+
+	fn description(&self) -> Option<String> {
+		Some(self.path.clone())
+	}
+
+	async fn init(
+		&mut self,
+		ctx: &WorkerCtx,
+	) -> Result<WorkingState<Self::Output, Self::Task>, JobError> {
+		let mut output = Self::Output::default();
+
+		if self.target == ScanDryRunTarget::Series {
+			let library = ctx
+				.db
+				.library()
+				.find_first(vec![library::series::some(vec![
+					series::id::equals(self.id.clone()),
+					series::path::equals(self.path.clone()),
+				])])
+				.select(library_path_with_options_select::select())
+				.exec()
+				.await?
+				.ok_or(JobError::InitFailed(
+					"Associated library not found".to_string(),
+				))?;
+			self.roots = Some(library.roots());
+			self.config = Some(LibraryConfig::from(library.config));
+
+			return Ok(WorkingState {
+				output: Some(output),
+				tasks: VecDeque::from([ScanDryRunTask::WalkSeries(PathBuf::from(
+					&self.path,
+				))]),
+				completed_tasks: 0,
+				logs: vec![],
+			});
+		}
+
+		let library_config = ctx
+			.db
+			.library_config()
+			.find_first(vec![library_config::library::is(vec![
+				library::id::equals(self.id.clone()),
+				library::path::equals(self.path.clone()),
+			])])
+			.exec()
+			.await?
+			.map(LibraryConfig::from)
+			.ok_or(JobError::InitFailed("Library not found".to_string()))?;
+		let is_collection_based = library_config.is_collection_based();
+		let roots = get_library_roots(&ctx.db, &self.id, &self.path).await?;
+		let ignore_rules = IgnoreMatcher::with_roots(
+			roots.paths(),
+			library_config.ignore_rules.build()?,
+		);
+
+		self.config = Some(library_config);
+		self.roots = Some(roots.clone());
+
+		ctx.report_progress(JobProgress::msg("Performing task discovery"));
+		let WalkedLibrary {
+			series_to_create,
+			recovered_series,
+			series_to_visit,
+			missing_series,
+			library_is_missing,
+			ignored_by_rules,
+			..
+		} = walk_library(WalkerCtx {
+			config: ctx.config.clone(),
+			db: ctx.db.clone(),
+			ignore_rules,
+			max_depth: is_collection_based.then_some(1),
+			options: self.options.clone(),
+			roots,
+		})
+		.await?;
+
+		output.library_is_missing = library_is_missing;
+		output.ignored_directories = paths_to_strings(ignored_by_rules);
+		output.series_to_mark_missing = paths_to_strings(missing_series);
+		output.series_to_create = paths_to_strings(series_to_create.clone());
+		if !recovered_series.is_empty() {
+			output.series_to_restore = ctx
+				.db
+				.series()
+				.find_many(vec![series::id::in_vec(recovered_series)])
+				.select(series::select!({ path }))
+				.exec()
+				.await?
+				.into_iter()
+				.map(|s| s.path)
+				.collect();
+		}
+
+		let tasks = series_to_visit
+			.into_iter()
+			.chain(series_to_create)
+			.map(ScanDryRunTask::WalkSeries)
+			.collect::<VecDeque<_>>();
+
+		ctx.report_progress(JobProgress::msg("Init complete!"));
+
+		Ok(WorkingState {
+			output: Some(output),
+			tasks,
+			completed_tasks: 0,
+			logs: vec![],
+		})
+	}
+
+	async fn cleanup(
+		&self,
+		ctx: &WorkerCtx,
+		output: &Self::Output,
+	) -> Result<Vec<Box<dyn Executor>>, JobError> {
+		ctx.send_core_event(CoreEvent::JobOutput {
+			id: ctx.job_id.clone(),
+			output: CoreJobOutput::ScanDryRun(output.clone()),
+		});
+		Ok(vec![])
+	}
+
+	async fn execute_task(
+		&self,
+		ctx: &WorkerCtx,
+		task: Self::Task,
+	) -> Result<JobTaskOutput<Self>, JobError> {
+		let mut output = Self::Output::default();
+
+		match task {
+			ScanDryRunTask::WalkSeries(path_buf) => {
+				ctx.report_progress(JobProgress::msg(&format!(
+					"Walking series at {}",
+					path_buf.display()
+				)));
+
+				let roots = self.roots.clone().unwrap_or_else(|| {
+					LibraryRoots::new(&self.path, Vec::<PathBuf>::new())
+				});
+				// See the equivalent logic in the library scan job for an explanation of
+				// the max depth
+				let mut max_depth = self
+					.config
+					.as_ref()
+					.and_then(|o| (!o.is_collection_based()).then_some(1));
+				if roots.is_root(&path_buf) {
+					max_depth = Some(1);
+				}
+
+				let Some(Ok(ignore_rules)) =
+					self.config.as_ref().map(|o| o.ignore_rules.build())
+				else {
+					return Err(JobError::TaskFailed(
+						"Failed to build ignore rules. Check that the rules are valid."
+							.to_string(),
+					));
+				};
+
+				let WalkedSeries {
+					series_is_missing,
+					media_to_create,
+					media_to_visit,
+					recovered_media,
+					missing_media,
+					ignored_by_rules,
+					skipped_files,
+					..
+				} = walk_series(
+					path_buf.as_path(),
+					WalkerCtx {
+						config: ctx.config.clone(),
+						db: ctx.db.clone(),
+						ignore_rules: IgnoreMatcher::with_roots(
+							roots.paths(),
+							ignore_rules,
+						),
+						max_depth,
+						options: self.options.clone(),
+						roots,
+					},
+				)
+				.await?;
+
+				let media_to_restore = if recovered_media.is_empty() {
+					vec![]
+				} else {
+					ctx.db
+						.media()
+						.find_many(vec![media::id::in_vec(recovered_media)])
+						.select(media::select!({ path }))
+						.exec()
+						.await?
+						.into_iter()
+						.map(|m| m.path)
+						.collect()
+				};
+
+				output.series.push(SeriesDryRunReport {
+					path: path_buf.to_string_lossy().to_string(),
+					is_missing: series_is_missing,
+					media_to_create: paths_to_strings(media_to_create),
+					media_to_update: paths_to_strings(media_to_visit),
+					media_to_mark_missing: paths_to_strings(missing_media),
+					media_to_restore,
+					ignored_files: paths_to_strings(ignored_by_rules),
+					skipped_files,
+				});
+			},
+		}
+
+		Ok(JobTaskOutput {
+			output,
+			subtasks: vec![],
+			logs: vec![],
+		})
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::{fs, path::Path};
+
+	use prisma_client_rust::operator::or;
+	use serde_json::json;
+
+	use super::*;
+	use crate::{
+		config::StumpConfig,
+		db::{entity::macros::media_path_modified_at_select, FileStatus},
+		filesystem::STUMPIGNORE_FILENAME,
+		prisma::{library_root, PrismaClient},
+	};
+
+	fn setup() -> tempfile::TempDir {
+		let root = tempfile::tempdir().unwrap();
+		for dir in ["Existing Series/Extras", "New Series", "Restored Series"] {
+			fs::create_dir_all(root.path().join(dir)).unwrap();
+		}
+		for file in [
+			"Existing Series/restored.cbz",
+			"Existing Series/notes.txt",
+			"Existing Series/Extras/bonus.cbz",
+			"New Series/001.cbz",
+			"Restored Series/001.cbz",
+		] {
+			fs::write(root.path().join(file), b"").unwrap();
+		}
+		fs::write(root.path().join(STUMPIGNORE_FILENAME), "Extras/\n*.txt\n").unwrap();
+		root
+	}
+
+	fn path_string(path: &Path) -> String {
+		path.to_string_lossy().to_string()
+	}
+
+	fn library_config(library_id: &str) -> library_config::Data {
+		library_config::Data {
+			id: "config_id".to_string(),
+			convert_rar_to_zip: false,
+			hard_delete_conversions: false,
+			default_reading_dir: "ltr".to_string(),
+			default_reading_mode: "paged".to_string(),
+			default_reading_image_scale_fit: "height".to_string(),
+			generate_file_hashes: false,
+			generate_koreader_hashes: false,
+			process_metadata: true,
+			library_pattern: "SERIES_BASED".to_string(),
+			watch_mode: "DISABLED".to_string(),
+			merge_strategy: "REPLACE".to_string(),
+			thumbnail_config: None,
+			ignore_rules: None,
+			filename_patterns: None,
+			metadata_providers: None,
+			library_id: Some(library_id.to_string()),
+			library: None,
+		}
+	}
+
+	/// Sets up the mock with the reads the dry run is expected to make. There are no writes
+	/// expected, so the mock would fail any query which tried to write to the database.
+	async fn expect_reads(
+		client: &PrismaClient,
+		mock: &prisma_client_rust::MockStore,
+		root: &Path,
+	) {
+		let path = |p: &str| path_string(&root.join(p));
+
+		mock.expect(
+			client
+				.library_config()
+				.find_first(vec![library_config::library::is(vec![
+					library::id::equals("library_id".to_string()),
+					library::path::equals(path_string(root)),
+				])]),
+			Some(library_config("library_id")),
+		)
+		.await;
+		mock.expect(
+			client
+				.library_root()
+				.find_many(vec![library_root::library_id::equals(
+					"library_id".to_string(),
+				)]),
+			vec![],
+		)
+		.await;
+		mock.expect(
+			client
+				.series()
+				.find_many(vec![or(vec![series::path::starts_with(path_string(root))])])
+				.select(series::select!({ id path status })),
+			serde_json::from_value(json!([
+				{
+					"id": "existing_series",
+					"path": path("Existing Series"),
+					"status": FileStatus::Ready.to_string(),
+				},
+				{
+					"id": "restored_series",
+					"path": path("Restored Series"),
+					"status": FileStatus::Missing.to_string(),
+				},
+				{
+					"id": "gone_series",
+					"path": path("Gone Series"),
+					"status": FileStatus::Ready.to_string(),
+				},
+			]))
+			.unwrap(),
+		)
+		.await;
+		mock.expect(
+			client
+				.series()
+				.find_many(vec![series::id::in_vec(
+					vec!["restored_series".to_string()],
+				)])
+				.select(series::select!({ path })),
+			serde_json::from_value(json!([{ "path": path("Restored Series") }])).unwrap(),
+		)
+		.await;
+
+		for (series_path, existing_media) in [
+			(
+				"Existing Series",
+				vec![
+					media_path_modified_at_select::Data {
+						id: "restored_media".to_string(),
+						path: path("Existing Series/restored.cbz"),
+						modified_at: None,
+						status: FileStatus::Missing.to_string(),
+					},
+					media_path_modified_at_select::Data {
+						id: "gone_media".to_string(),
+						path: path("Existing Series/gone.cbz"),
+						modified_at: None,
+						status: FileStatus::Ready.to_string(),
+					},
+				],
+			),
+			("New Series", vec![]),
+			("Restored Series", vec![]),
+		] {
+			mock.expect(
+				client
+					.media()
+					.find_many(vec![media::series::is(vec![series::path::equals(path(
+						series_path,
+					))])])
+					.select(media_path_modified_at_select::select()),
+				existing_media,
+			)
+			.await;
+		}
+		mock.expect(
+			client
+				.media()
+				.find_many(vec![media::id::in_vec(vec!["restored_media".to_string()])])
+				.select(media::select!({ path })),
+			serde_json::from_value(json!([
+				{ "path": path("Existing Series/restored.cbz") }
+			]))
+			.unwrap(),
+		)
+		.await;
+	}
+
+	#[tokio::test]
+	async fn test_library_dry_run() {
+		let root = setup();
+		let path = |p: &str| path_string(&root.path().join(p));
+
+		let (client, mock) = PrismaClient::_mock();
+		expect_reads(&client, &mock, root.path()).await;
+		let ctx = WorkerCtx::mock(client, StumpConfig::debug());
+
+		let mut job = ScanDryRunJob {
+			id: "library_id".to_string(),
+			path: path_string(root.path()),
+			target: ScanDryRunTarget::Library,
+			config: None,
+			options: ScanOptions::default(),
+			roots: None,
+		};
+		let WorkingState { output, tasks, .. } = job.init(&ctx).await.unwrap();
+		let mut output = output.unwrap();
+		for task in tasks {
+			output.update(job.execute_task(&ctx, task).await.unwrap().output);
+		}
+
+		assert!(!output.library_is_missing);
+		assert_eq!(output.series_to_create, vec![path("New Series")]);
+		assert_eq!(output.series_to_mark_missing, vec![path("Gone Series")]);
+		assert_eq!(output.series_to_restore, vec![path("Restored Series")]);
+		assert_eq!(
+			output.ignored_directories,
+			vec![path("Existing Series/Extras")]
+		);
+
+		let report = |series_path: &str| {
+			output
+				.series
+				.iter()
+				.find(|report| report.path == path(series_path))
+				.unwrap_or_else(|| panic!("No report for {series_path}"))
+		};
+		assert_eq!(output.series.len(), 3);
+
+		let existing = report("Existing Series");
+		assert!(!existing.is_missing);
+		assert!(existing.media_to_create.is_empty());
+		assert_eq!(
+			existing.media_to_update,
+			vec![path("Existing Series/restored.cbz")]
+		);
+		assert_eq!(
+			existing.media_to_restore,
+			vec![path("Existing Series/restored.cbz")]
+		);
+		assert_eq!(
+			existing.media_to_mark_missing,
+			vec![path("Existing Series/gone.cbz")]
+		);
+		assert_eq!(
+			existing.ignored_files,
+			vec![path("Existing Series/notes.txt")]
+		);
+
+		for series_path in ["New Series", "Restored Series"] {
+			let report = report(series_path);
+			assert_eq!(
+				report.media_to_create,
+				vec![path(&format!("{series_path}/001.cbz"))]
+			);
+			assert!(report.media_to_mark_missing.is_empty());
+			assert!(report.media_to_restore.is_empty());
+		}
+	}
+}
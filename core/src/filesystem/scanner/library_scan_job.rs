@@ -1,5 +1,10 @@
-use std::{collections::VecDeque, path::PathBuf};
+use std::{
+	collections::VecDeque,
+	path::PathBuf,
+	sync::{Arc, OnceLock},
+};
 
+use integrations::NotifierEvent;
 use serde::{Deserialize, Serialize};
 use specta::Type;
 
@@ -10,14 +15,20 @@ use specta::Type;
 
 use crate::{
 	db::{
-		entity::{CoreJobOutput, LibraryConfig},
+		entity::{CompiledFilenamePatterns, CoreJobOutput, LibraryConfig},
 		FileStatus, SeriesDAO, DAO,
 	},
-	filesystem::image::{ThumbnailGenerationJob, ThumbnailGenerationJobParams},
+	filesystem::{
+		image::{ThumbnailGenerationJob, ThumbnailGenerationJobParams},
+		media::compile_filename_patterns,
+		IgnoreMatcher,
+	},
 	job::{
 		error::JobError, Executor, JobExecuteLog, JobExt, JobOutputExt, JobProgress,
-		JobTaskOutput, WorkerCtx, WorkerSendExt, WorkingState, WrappedJob,
+		JobRetryPolicy, JobTaskOutput, WorkerCtx, WorkerSendExt, WorkingState,
+		WrappedJob,
 	},
+	metadata::remove_orphaned_people,
 	prisma::{library, library_config, media, series, PrismaClient},
 	utils::chain_optional_iter,
 	CoreEvent,
@@ -26,11 +37,13 @@ use crate::{
 use super::{
 	series_scan_job::SeriesScanTask,
 	utils::{
-		handle_missing_media, handle_missing_series, handle_restored_media,
-		safely_build_and_insert_media, safely_build_series, visit_and_update_media,
-		MediaBuildOperation, MediaOperationOutput, MissingSeriesOutput,
+		get_library_roots, handle_missing_media, handle_missing_series,
+		handle_restored_media, safely_build_and_insert_media, safely_build_series,
+		visit_and_update_media, MediaBuildOperation, MediaOperationOutput,
+		MissingSeriesOutput, RelocationCandidates,
 	},
-	walk_library, walk_series, ScanOptions, WalkedLibrary, WalkedSeries, WalkerCtx,
+	walk_library, walk_series, LibraryRoots, ScanOptions, WalkedLibrary, WalkedSeries,
+	WalkerCtx,
 };
 
 /// The task variants that are used to scan a library
@@ -54,12 +67,21 @@ pub struct InitTaskInput {
 }
 
 /// A job that scans a library and updates the database with the results
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct LibraryScanJob {
 	pub id: String,
 	pub path: String,
 	pub config: Option<LibraryConfig>,
 	pub options: ScanOptions,
+	/// The roots of the library, which are resolved during init
+	pub roots: Option<LibraryRoots>,
+	/// The filename patterns of the library, compiled once for the whole scan
+	#[serde(skip)]
+	filename_patterns: OnceLock<CompiledFilenamePatterns>,
+	/// The media which new books may have been moved or renamed from, loaded once for the
+	/// whole scan
+	#[serde(skip)]
+	relocation_candidates: Arc<RelocationCandidates>,
 }
 
 impl LibraryScanJob {
@@ -73,8 +95,24 @@ impl LibraryScanJob {
 			path,
 			config: None,
 			options: options.unwrap_or_default(),
+			roots: None,
+			filename_patterns: OnceLock::new(),
+			relocation_candidates: Arc::default(),
 		})
 	}
+
+	/// The compiled filename patterns of the library, which are compiled the first time they
+	/// are needed rather than for every book (or series) built during the scan
+	fn filename_patterns(&self) -> CompiledFilenamePatterns {
+		self.filename_patterns
+			.get_or_init(|| {
+				self.config
+					.as_ref()
+					.map(compile_filename_patterns)
+					.unwrap_or_default()
+			})
+			.clone()
+	}
 }
 
 /// The data that is collected and updated during the execution of a library scan job
@@ -118,6 +156,9 @@ impl JobOutputExt for LibraryScanOutput {
 #[async_trait::async_trait]
 impl JobExt for LibraryScanJob {
 	const NAME: &'static str = "library_scan";
+	// Scans read from disks which may be briefly unavailable, e.g. a NAS which is waking up
+	const MAX_RETRIES: JobRetryPolicy = JobRetryPolicy::Count(3);
+	const MAX_TASK_RETRIES: JobRetryPolicy = JobRetryPolicy::Count(3);
 
 	type Output = LibraryScanOutput;
 	type Task = LibraryScanTask;
@@ -126,6 +167,10 @@ impl JobExt for LibraryScanJob {
 		Some(self.path.clone())
 	}
 
+	fn library_lock(&self) -> Option<String> {
+		Some(self.id.clone())
+	}
+
 	async fn init(
 		&mut self,
 		ctx: &WorkerCtx,
@@ -146,9 +191,14 @@ impl JobExt for LibraryScanJob {
 			.map(LibraryConfig::from)
 			.ok_or(JobError::InitFailed("Library not found".to_string()))?;
 		let is_collection_based = library_config.is_collection_based();
-		let ignore_rules = library_config.ignore_rules.build()?;
+		let roots = get_library_roots(&ctx.db, &self.id, &self.path).await?;
+		let ignore_rules = IgnoreMatcher::with_roots(
+			roots.paths(),
+			library_config.ignore_rules.build()?,
+		);
 
 		self.config = Some(library_config);
+		self.roots = Some(roots.clone());
 
 		ctx.report_progress(JobProgress::msg("Performing task discovery"));
 		let WalkedLibrary {
@@ -159,15 +209,15 @@ impl JobExt for LibraryScanJob {
 			library_is_missing,
 			ignored_directories,
 			seen_directories,
-		} = walk_library(
-			&self.path,
-			WalkerCtx {
-				db: ctx.db.clone(),
-				ignore_rules,
-				max_depth: is_collection_based.then_some(1),
-				options: self.options.clone(),
-			},
-		)
+			..
+		} = walk_library(WalkerCtx {
+			config: ctx.config.clone(),
+			db: ctx.db.clone(),
+			ignore_rules,
+			max_depth: is_collection_based.then_some(1),
+			options: self.options.clone(),
+			roots,
+		})
 		.await?;
 		tracing::debug!(
 			series_to_create = series_to_create.len(),
@@ -230,11 +280,17 @@ impl JobExt for LibraryScanJob {
 		&self,
 		ctx: &WorkerCtx,
 		output: &Self::Output,
-	) -> Result<Option<Box<dyn Executor>>, JobError> {
+	) -> Result<Vec<Box<dyn Executor>>, JobError> {
 		ctx.send_core_event(CoreEvent::JobOutput {
 			id: ctx.job_id.clone(),
 			output: CoreJobOutput::LibraryScan(output.clone()),
 		});
+		if output.updated_media > 0 {
+			// Updated books may no longer credit someone, and the people left without credits
+			// are only removed once the whole scan is done rather than after each book
+			let removed_people = remove_orphaned_people(&ctx.db).await?;
+			tracing::debug!(removed_people, "Removed people without any credits");
+		}
 
 		let did_create = output.created_series > 0 || output.created_media > 0;
 		let did_update = output.updated_series > 0 || output.updated_media > 0;
@@ -243,24 +299,51 @@ impl JobExt for LibraryScanJob {
 			.as_ref()
 			.and_then(|o| o.thumbnail_config.clone());
 
+		let force_regenerate = self.options.should_regen_thumbnails();
+
 		match image_options {
-			Some(options) if did_create | did_update => {
+			Some(options) if did_create | did_update | force_regenerate => {
 				tracing::trace!("Thumbnail generation job should be enqueued");
-				Ok(Some(WrappedJob::new(ThumbnailGenerationJob {
+				Ok(vec![WrappedJob::new(ThumbnailGenerationJob {
 					options,
 					params: ThumbnailGenerationJobParams::single_library(
 						self.id.clone(),
-						false,
+						force_regenerate,
 					),
-				})))
+				})])
 			},
 			_ => {
 				tracing::debug!("No cleanup required for library scan job");
-				Ok(None)
+				Ok(vec![])
 			},
 		}
 	}
 
+	async fn notify_output(
+		&self,
+		ctx: &WorkerCtx,
+		output: &Self::Output,
+	) -> Option<NotifierEvent> {
+		if output.created_media == 0 {
+			return None;
+		}
+
+		let library_name = ctx
+			.db
+			.library()
+			.find_unique(library::id::equals(self.id.clone()))
+			.exec()
+			.await
+			.ok()
+			.flatten()
+			.map_or_else(|| self.id.clone(), |library| library.name);
+
+		Some(NotifierEvent::ScanCompleted {
+			books_added: output.created_media,
+			library_name,
+		})
+	}
+
 	async fn execute_task(
 		&self,
 		ctx: &WorkerCtx,
@@ -381,6 +464,7 @@ impl JobExt for LibraryScanJob {
 					let (built_series, failure_logs) = safely_build_series(
 						&self.id,
 						series_to_create,
+						&self.filename_patterns(),
 						ctx.config.as_ref(),
 						|position| {
 							ctx.report_progress(JobProgress::subtask_position(
@@ -451,11 +535,14 @@ impl JobExt for LibraryScanJob {
 				// If the library is not collection-priority, each subdirectory is its own series.
 				// Therefore, we only scan one level deep when walking a series whose library is not
 				// collection-priority to avoid scanning duplicates which are part of other series
+				let roots = self.roots.clone().unwrap_or_else(|| {
+					LibraryRoots::new(&self.path, Vec::<PathBuf>::new())
+				});
 				let mut max_depth = self
 					.config
 					.as_ref()
 					.and_then(|o| (!o.is_collection_based()).then_some(1));
-				if path_buf == PathBuf::from(&self.path) {
+				if roots.is_root(&path_buf) {
 					// The exception is when the series "is" the libray (i.e. the root of the library contains
 					// books). This is kind of an anti-pattern wrt collection-priority, but it needs to be handled
 					// in order to avoid the scanner re-scanning the entire library...
@@ -475,10 +562,15 @@ impl JobExt for LibraryScanJob {
 				let walk_result = walk_series(
 					path_buf.as_path(),
 					WalkerCtx {
+						config: ctx.config.clone(),
 						db: ctx.db.clone(),
-						ignore_rules,
+						ignore_rules: IgnoreMatcher::with_roots(
+							roots.paths(),
+							ignore_rules,
+						),
 						max_depth,
 						options: self.options.clone(),
+						roots,
 					},
 				)
 				.await;
@@ -492,6 +584,7 @@ impl JobExt for LibraryScanJob {
 					seen_files,
 					ignored_files,
 					skipped_files,
+					..
 				} = match walk_result {
 					Ok(walked_series) => walked_series,
 					Err(core_error) => {
@@ -621,6 +714,9 @@ impl JobExt for LibraryScanJob {
 						MediaBuildOperation {
 							series_id: series_id.clone(),
 							library_config: self.config.clone().unwrap_or_default(),
+							filename_patterns: self.filename_patterns(),
+							relocation_candidates: self.relocation_candidates.clone(),
+							options: self.options.clone(),
 							max_concurrency,
 						},
 						ctx,
@@ -651,6 +747,9 @@ impl JobExt for LibraryScanJob {
 						MediaBuildOperation {
 							series_id: series_id.clone(),
 							library_config: self.config.clone().unwrap_or_default(),
+							filename_patterns: self.filename_patterns(),
+							relocation_candidates: self.relocation_candidates.clone(),
+							options: self.options.clone(),
 							max_concurrency,
 						},
 						ctx,
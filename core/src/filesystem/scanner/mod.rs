@@ -1,10 +1,19 @@
+mod dry_run_job;
 mod library_scan_job;
 mod options;
+mod roots;
 mod series_scan_job;
 mod utils;
 mod walk;
+mod watcher;
 
+pub use dry_run_job::{
+	ScanDryRunJob, ScanDryRunOutput, ScanDryRunTarget, SeriesDryRunReport,
+};
 pub use library_scan_job::{LibraryScanJob, LibraryScanOutput};
-pub use options::ScanOptions;
+pub use options::{BookVisitOperation, ScanOptions};
+pub use roots::LibraryRoots;
 pub use series_scan_job::{SeriesScanJob, SeriesScanOutput};
+pub(crate) use utils::{get_library_roots, merge_and_upsert_metadata};
 pub use walk::{walk_library, walk_series, WalkedLibrary, WalkedSeries, WalkerCtx};
+pub use watcher::LibraryWatcher;
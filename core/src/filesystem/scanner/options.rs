@@ -1,27 +1,13 @@
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use specta::Type;
 use utoipa::ToSchema;
 
-// TODO(granular-scans/metadata-merge): Support merge strategies for metadata at some point
-/*
-enum MergeStrategy {
-   Replace,
-   Merge,
-   // A third option to record the difference to a table and allow the user to manually resolve conflicts? A bit complex! but would be neat. Prolly not viable.
-}
-
-let scan_options: ScanOptions = {
-   merge_strategy: MergeStrategy::Replace,
-   ..Default::default()
-};
-
-See also https://docs.rs/merge/latest/merge/ for potentially useful crate
-*/
+use crate::db::entity::MergeStrategy;
 
 /// The override options for a scan job. These options are used to override the default behavior, which generally
 /// means that the scanner will visit books it otherwise would not. How much extra work is done depends on the
 /// specific options.
-#[derive(Debug, Default, Clone, Deserialize, Type, ToSchema)]
+#[derive(Debug, Default, Clone, Serialize, Deserialize, Type, ToSchema)]
 pub struct ScanOptions {
 	/// Whether a scan should forcibly rebuild each book it visits and issue an update to the database.
 	/// This is somewhat dangerous, as it can overwrite metadata which was manually set against the database
@@ -32,16 +18,57 @@ pub struct ScanOptions {
 	/// for books which have not been modified and have hashes already stored.
 	#[serde(default)]
 	regen_hashes: bool,
+	/// Whether a scan should re-read the embedded metadata (e.g. ComicInfo.xml or OPF) of each book it
+	/// visits. Only the metadata is updated, the rest of the book is left as-is.
+	#[serde(default)]
+	regen_metadata: bool,
+	/// Whether the thumbnail generation which follows a scan should regenerate existing thumbnails. This
+	/// does not cause the scan itself to visit any additional books.
+	#[serde(default)]
+	regen_thumbnails: bool,
+	/// The strategy used to reconcile metadata read from visited books with existing metadata. If not
+	/// set, the library's configured strategy is used.
+	#[serde(default)]
+	merge_strategy: Option<MergeStrategy>,
+}
+
+/// The work a scan should do for a book which it visits but which has not changed on disk since
+/// it was last scanned. Books which have changed on disk are always fully rebuilt.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BookVisitOperation {
+	/// Rebuild the entire book and overwrite it in the database
+	Rebuild,
+	/// Only rebuild the requested parts of the book, leaving the rest untouched
+	Partial { hashes: bool, metadata: bool },
 }
 
 impl ScanOptions {
-	// TODO(granular-scans): This currently will trigger a full rebuild. This should be changed to actually use
-	// the options. The changes for this are not in this file, but walk.rs and utils.rs
 	/// Whether a scan should visit books which otherwise would not be visited (e.g., because they
 	/// have not been updated since the last scan).
 	pub fn should_visit_books(&self) -> bool {
-		// If any of the options are set, we should visit books
-		self.force_rebuild || self.regen_hashes
+		self.force_rebuild || self.regen_hashes || self.regen_metadata
+	}
+
+	/// The operation to perform for a book which is visited but has not changed on disk
+	pub fn visit_operation(&self) -> BookVisitOperation {
+		if self.force_rebuild {
+			BookVisitOperation::Rebuild
+		} else {
+			BookVisitOperation::Partial {
+				hashes: self.regen_hashes,
+				metadata: self.regen_metadata,
+			}
+		}
+	}
+
+	/// The merge strategy to use for the scan, falling back to the given (library) default
+	pub fn merge_strategy(&self, default: MergeStrategy) -> MergeStrategy {
+		self.merge_strategy.unwrap_or(default)
+	}
+
+	/// Whether existing thumbnails should be regenerated once the scan completes
+	pub fn should_regen_thumbnails(&self) -> bool {
+		self.regen_thumbnails
 	}
 }
 
@@ -61,6 +88,50 @@ mod tests {
 		assert!(options.should_visit_books());
 	}
 
+	#[test]
+	fn should_not_visit_books_for_thumbnails() {
+		let options = ScanOptions {
+			regen_thumbnails: true,
+			..ScanOptions::default()
+		};
+		assert!(!options.should_visit_books());
+		assert!(options.should_regen_thumbnails());
+	}
+
+	#[test]
+	fn visit_operation_is_partial_unless_forced() {
+		let options = ScanOptions {
+			regen_hashes: true,
+			..ScanOptions::default()
+		};
+		assert_eq!(
+			options.visit_operation(),
+			BookVisitOperation::Partial {
+				hashes: true,
+				metadata: false
+			}
+		);
+
+		let options = ScanOptions {
+			regen_metadata: true,
+			..ScanOptions::default()
+		};
+		assert_eq!(
+			options.visit_operation(),
+			BookVisitOperation::Partial {
+				hashes: false,
+				metadata: true
+			}
+		);
+
+		let options = ScanOptions {
+			force_rebuild: true,
+			regen_hashes: true,
+			..ScanOptions::default()
+		};
+		assert_eq!(options.visit_operation(), BookVisitOperation::Rebuild);
+	}
+
 	#[test]
 	fn should_deserialize() {
 		let options = r#"{"regen_hashes":false}"#;
@@ -68,6 +139,20 @@ mod tests {
 		assert!(!options.should_visit_books());
 	}
 
+	#[test]
+	fn should_deserialize_merge_strategy() {
+		let options = r#"{"merge_strategy":"CONFLICT"}"#;
+		let options: ScanOptions = serde_json::from_str(options).unwrap();
+		assert_eq!(
+			options.merge_strategy(MergeStrategy::Replace),
+			MergeStrategy::Conflict
+		);
+		assert_eq!(
+			ScanOptions::default().merge_strategy(MergeStrategy::Merge),
+			MergeStrategy::Merge
+		);
+	}
+
 	#[test]
 	fn should_deserialize_empty() {
 		let options = r#"{}"#;
@@ -0,0 +1,136 @@
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+/// The root paths of a library. A library always has a primary root (its `path`), and may have
+/// any number of additional roots, e.g. for a collection which is split across multiple disks.
+///
+/// A series is identified by its path relative to the root it was found in, so the same series
+/// folder existing under more than one root is treated as a single series. That series keeps
+/// the path of the first root it was created from, and the equivalent folders under the other
+/// roots are walked as part of it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LibraryRoots(Vec<PathBuf>);
+
+impl LibraryRoots {
+	/// Create the roots for a library from its primary path and any additional roots
+	pub fn new(
+		primary: impl Into<PathBuf>,
+		additional: impl IntoIterator<Item = impl Into<PathBuf>>,
+	) -> Self {
+		Self(
+			std::iter::once(primary.into())
+				.chain(additional.into_iter().map(Into::into))
+				.collect(),
+		)
+	}
+
+	/// The primary root of the library
+	pub fn primary(&self) -> &Path {
+		&self.0[0]
+	}
+
+	/// All of the roots, starting with the primary root
+	pub fn paths(&self) -> &[PathBuf] {
+		&self.0
+	}
+
+	/// Whether the path is one of the roots
+	pub fn is_root(&self, path: &Path) -> bool {
+		self.0.iter().any(|root| root == path)
+	}
+
+	/// Splits a path into the root it belongs to and its path relative to that root. When roots
+	/// are nested, the deepest root is used.
+	pub fn split<'a>(&self, path: &'a Path) -> Option<(&Path, &'a Path)> {
+		self.0
+			.iter()
+			.filter_map(|root| {
+				path.strip_prefix(root)
+					.ok()
+					.map(|relative| (root.as_path(), relative))
+			})
+			.max_by_key(|(root, _)| root.components().count())
+	}
+
+	/// Returns the path relative to the root it belongs to, which is how a series is identified
+	/// across roots. Paths outside of every root are returned as-is.
+	pub fn relative<'a>(&self, path: &'a Path) -> &'a Path {
+		self.split(path).map_or(path, |(_, relative)| relative)
+	}
+
+	/// Returns the directories under each root which are equivalent to the given path and which
+	/// exist, according to `exists`. For a path outside of every root, only the path itself is
+	/// considered.
+	pub fn equivalents(
+		&self,
+		path: &Path,
+		exists: impl Fn(&Path) -> bool,
+	) -> Vec<PathBuf> {
+		match self.split(path) {
+			Some((_, relative)) => self
+				.0
+				.iter()
+				.map(|root| root.join(relative))
+				.filter(|candidate| exists(candidate))
+				.collect(),
+			None => exists(path)
+				.then(|| vec![path.to_path_buf()])
+				.unwrap_or_default(),
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::fs;
+
+	use super::*;
+
+	#[test]
+	fn test_split_uses_deepest_root() {
+		let roots =
+			LibraryRoots::new("/disk1/comics", ["/disk2/comics", "/disk1/comics/extra"]);
+
+		assert_eq!(
+			roots.split(Path::new("/disk2/comics/Batman")),
+			Some((Path::new("/disk2/comics"), Path::new("Batman")))
+		);
+		assert_eq!(
+			roots.split(Path::new("/disk1/comics/extra/Batman")),
+			Some((Path::new("/disk1/comics/extra"), Path::new("Batman")))
+		);
+		assert_eq!(roots.split(Path::new("/elsewhere/Batman")), None);
+		assert_eq!(
+			roots.relative(Path::new("/elsewhere/Batman")),
+			Path::new("/elsewhere/Batman")
+		);
+	}
+
+	#[test]
+	fn test_equivalents_across_roots() {
+		let primary = tempfile::tempdir().unwrap();
+		let secondary = tempfile::tempdir().unwrap();
+		fs::create_dir_all(primary.path().join("Batman")).unwrap();
+		fs::create_dir_all(secondary.path().join("Batman")).unwrap();
+		fs::create_dir_all(secondary.path().join("Superman")).unwrap();
+
+		let roots = LibraryRoots::new(primary.path(), [secondary.path()]);
+
+		assert_eq!(
+			roots.equivalents(&primary.path().join("Batman"), Path::exists),
+			vec![
+				primary.path().join("Batman"),
+				secondary.path().join("Batman")
+			]
+		);
+		assert_eq!(
+			roots.equivalents(&primary.path().join("Superman"), Path::exists),
+			vec![secondary.path().join("Superman")]
+		);
+		assert!(roots
+			.equivalents(&primary.path().join("Robin"), Path::exists)
+			.is_empty());
+		assert!(roots.is_root(secondary.path()));
+	}
+}
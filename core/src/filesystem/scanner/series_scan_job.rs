@@ -1,20 +1,31 @@
-use std::{collections::VecDeque, path::PathBuf};
+use std::{
+	collections::VecDeque,
+	path::PathBuf,
+	sync::{Arc, OnceLock},
+};
 
+use integrations::NotifierEvent;
 use serde::{Deserialize, Serialize};
 use specta::Type;
 
 use crate::{
 	db::{
 		entity::{
-			macros::library_path_with_options_select, CoreJobOutput, LibraryConfig,
+			macros::library_path_with_options_select, CompiledFilenamePatterns,
+			CoreJobOutput, LibraryConfig,
 		},
 		FileStatus,
 	},
-	filesystem::image::{ThumbnailGenerationJob, ThumbnailGenerationJobParams},
+	filesystem::{
+		image::{ThumbnailGenerationJob, ThumbnailGenerationJobParams},
+		media::compile_filename_patterns,
+		IgnoreMatcher,
+	},
 	job::{
-		error::JobError, Executor, JobExt, JobOutputExt, JobProgress, JobTaskOutput,
-		WorkerCtx, WorkerSendExt, WorkingState, WrappedJob,
+		error::JobError, Executor, JobExt, JobOutputExt, JobProgress, JobRetryPolicy,
+		JobTaskOutput, WorkerCtx, WorkerSendExt, WorkingState, WrappedJob,
 	},
+	metadata::remove_orphaned_people,
 	prisma::{library, media, series, PrismaClient},
 	utils::chain_optional_iter,
 	CoreEvent,
@@ -24,6 +35,7 @@ use super::{
 	utils::{
 		handle_missing_media, handle_restored_media, safely_build_and_insert_media,
 		visit_and_update_media, MediaBuildOperation, MediaOperationOutput,
+		RelocationCandidates,
 	},
 	walk_series, ScanOptions, WalkedSeries, WalkerCtx,
 };
@@ -37,31 +49,56 @@ pub enum SeriesScanTask {
 	VisitMedia(Vec<PathBuf>),
 }
 
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct SeriesScanJob {
 	pub id: String,
+	/// The ID of the library the series belongs to, which is locked while the series is scanned
+	pub library_id: String,
 	pub path: String,
 	pub config: Option<LibraryConfig>,
 	pub options: ScanOptions,
+	/// The filename patterns of the library, compiled once for the whole scan
+	#[serde(skip)]
+	filename_patterns: OnceLock<CompiledFilenamePatterns>,
+	/// The media which new books may have been moved or renamed from, loaded once for the
+	/// whole scan
+	#[serde(skip)]
+	relocation_candidates: Arc<RelocationCandidates>,
 }
 
 impl SeriesScanJob {
 	pub fn new(
 		id: String,
+		library_id: String,
 		path: String,
 		options: Option<ScanOptions>,
 	) -> Box<WrappedJob<SeriesScanJob>> {
 		WrappedJob::new(Self {
 			id,
+			library_id,
 			path,
 			config: None,
 			options: options.unwrap_or_default(),
+			filename_patterns: OnceLock::new(),
+			relocation_candidates: Arc::default(),
 		})
 	}
-}
 
-// TODO: emit progress events. This job isn't exposed in the UI yet, so it's not a big deal for now
+	/// The compiled filename patterns of the library, which are compiled the first time they
+	/// are needed rather than for every book built during the scan
+	fn filename_patterns(&self) -> CompiledFilenamePatterns {
+		self.filename_patterns
+			.get_or_init(|| {
+				self.config
+					.as_ref()
+					.map(compile_filename_patterns)
+					.unwrap_or_default()
+			})
+			.clone()
+	}
+}
 
+/// The output of a series scan, which is reported to clients once the scan completes
 #[derive(Clone, Serialize, Deserialize, Default, Debug, Type)]
 pub struct SeriesScanOutput {
 	/// The number of files to scan relative to the series root
@@ -73,8 +110,12 @@ pub struct SeriesScanOutput {
 	skipped_files: u64,
 	/// The number of media entities that were created
 	created_media: u64,
-	/// The number of media entities that were updated
+	/// The number of media entities that were updated after being visited on disk
 	updated_media: u64,
+	/// The number of media entities that were marked as missing
+	missing_media: u64,
+	/// The number of previously missing media entities that were restored
+	restored_media: u64,
 }
 
 impl JobOutputExt for SeriesScanOutput {
@@ -84,12 +125,17 @@ impl JobOutputExt for SeriesScanOutput {
 		self.skipped_files += updated.skipped_files;
 		self.created_media += updated.created_media;
 		self.updated_media += updated.updated_media;
+		self.missing_media += updated.missing_media;
+		self.restored_media += updated.restored_media;
 	}
 }
 
 #[async_trait::async_trait]
 impl JobExt for SeriesScanJob {
 	const NAME: &'static str = "series_scan";
+	// Scans read from disks which may be briefly unavailable, e.g. a NAS which is waking up
+	const MAX_RETRIES: JobRetryPolicy = JobRetryPolicy::Count(3);
+	const MAX_TASK_RETRIES: JobRetryPolicy = JobRetryPolicy::Count(3);
 
 	type Output = SeriesScanOutput;
 	type Task = SeriesScanTask;
@@ -98,12 +144,17 @@ impl JobExt for SeriesScanJob {
 		Some(self.path.clone())
 	}
 
+	fn library_lock(&self) -> Option<String> {
+		Some(self.library_id.clone())
+	}
+
 	async fn init(
 		&mut self,
 		ctx: &WorkerCtx,
 	) -> Result<WorkingState<Self::Output, Self::Task>, JobError> {
 		let mut output = Self::Output::default();
 		let path_buf = PathBuf::from(self.path.clone());
+		ctx.report_progress(JobProgress::msg("Loading library configuration"));
 		let library = ctx
 			.db
 			.library()
@@ -117,8 +168,12 @@ impl JobExt for SeriesScanJob {
 			.ok_or(JobError::InitFailed(
 				"Associated library not found".to_string(),
 			))?;
+		let roots = library.roots();
 		let library_config = LibraryConfig::from(library.config);
-		let ignore_rules = library_config.ignore_rules.build()?;
+		let ignore_rules = IgnoreMatcher::with_roots(
+			roots.paths(),
+			library_config.ignore_rules.build()?,
+		);
 
 		// If the library is collection-priority, any child directories are 'ignored' and their
 		// files are part of / folded into the top-most folder (series).
@@ -126,7 +181,7 @@ impl JobExt for SeriesScanJob {
 		// Therefore, we only scan one level deep when walking a series whose library is not
 		// collection-priority to avoid scanning duplicates which are part of other series
 		let mut max_depth = (!library_config.is_collection_based()).then_some(1);
-		if path_buf == PathBuf::from(&library.path) {
+		if roots.is_root(&path_buf) {
 			// The exception is when the series "is" the libray (i.e. the root of the library contains
 			// books). This is kind of an anti-pattern wrt collection-priority, but it needs to be handled
 			// in order to avoid the scanner re-scanning the entire library...
@@ -135,6 +190,7 @@ impl JobExt for SeriesScanJob {
 
 		self.config = Some(library_config);
 
+		ctx.report_progress(JobProgress::msg("Performing task discovery"));
 		let WalkedSeries {
 			series_is_missing,
 			media_to_create,
@@ -144,18 +200,22 @@ impl JobExt for SeriesScanJob {
 			seen_files,
 			ignored_files,
 			skipped_files,
+			..
 		} = walk_series(
 			PathBuf::from(self.path.clone()).as_path(),
 			WalkerCtx {
+				config: ctx.config.clone(),
 				db: ctx.db.clone(),
 				ignore_rules,
 				max_depth,
 				options: self.options.clone(),
+				roots,
 			},
 		)
 		.await?;
 
 		if series_is_missing {
+			ctx.report_progress(JobProgress::msg("Failed to find series on disk"));
 			let _ = handle_missing_series(&ctx.db, self.path.as_str()).await;
 			return Err(JobError::InitFailed(
 				"Series could not be found on disk".to_string(),
@@ -171,6 +231,7 @@ impl JobExt for SeriesScanJob {
 		output.ignored_files = ignored_files;
 		output.skipped_files = skipped_files;
 
+		ctx.report_progress(JobProgress::msg("Building tasks"));
 		let tasks = VecDeque::from(chain_optional_iter(
 			[],
 			[
@@ -185,6 +246,8 @@ impl JobExt for SeriesScanJob {
 			],
 		));
 
+		ctx.report_progress(JobProgress::msg("Init complete!"));
+
 		Ok(WorkingState {
 			output: Some(output),
 			tasks,
@@ -197,36 +260,71 @@ impl JobExt for SeriesScanJob {
 		&self,
 		ctx: &WorkerCtx,
 		output: &Self::Output,
-	) -> Result<Option<Box<dyn Executor>>, JobError> {
+	) -> Result<Vec<Box<dyn Executor>>, JobError> {
 		ctx.send_core_event(CoreEvent::JobOutput {
 			id: ctx.job_id.clone(),
 			output: CoreJobOutput::SeriesScan(output.clone()),
 		});
+		if output.updated_media > 0 {
+			// Updated books may no longer credit someone, and the people left without credits
+			// are only removed once the whole scan is done rather than after each book
+			let removed_people = remove_orphaned_people(&ctx.db).await?;
+			tracing::debug!(removed_people, "Removed people without any credits");
+		}
 		let did_create = output.created_media > 0;
-		let did_update = output.updated_media > 0;
+		let did_update = output.updated_media > 0 || output.restored_media > 0;
 		let image_options = self
 			.config
 			.as_ref()
 			.and_then(|o| o.thumbnail_config.clone());
 
+		let force_regenerate = self.options.should_regen_thumbnails();
+
 		match image_options {
-			Some(options) if did_create | did_update => {
+			Some(options) if did_create | did_update | force_regenerate => {
 				tracing::trace!("Thumbnail generation job should be enqueued");
-				Ok(Some(WrappedJob::new(ThumbnailGenerationJob {
+				Ok(vec![WrappedJob::new(ThumbnailGenerationJob {
 					options,
 					params: ThumbnailGenerationJobParams::single_series(
 						self.id.clone(),
-						false,
+						force_regenerate,
 					),
-				})))
+				})])
 			},
 			_ => {
 				tracing::trace!("No cleanup required for series scan job");
-				Ok(None)
+				Ok(vec![])
 			},
 		}
 	}
 
+	async fn notify_output(
+		&self,
+		ctx: &WorkerCtx,
+		output: &Self::Output,
+	) -> Option<NotifierEvent> {
+		let books_updated = output.updated_media + output.restored_media;
+		if output.created_media == 0 && books_updated == 0 {
+			return None;
+		}
+
+		let series_name = ctx
+			.db
+			.series()
+			.find_unique(series::id::equals(self.id.clone()))
+			.exec()
+			.await
+			.ok()
+			.flatten()
+			.map_or_else(|| self.id.clone(), |series| series.name);
+
+		Some(NotifierEvent::SeriesScanCompleted {
+			books_added: output.created_media,
+			books_updated,
+			series_name,
+		})
+	}
+
 	async fn execute_task(
 		&self,
 		ctx: &WorkerCtx,
@@ -253,7 +351,7 @@ impl JobExt for SeriesScanJob {
 					}
 					.into_worker_send(),
 				]);
-				output.updated_media += updated_media;
+				output.restored_media += updated_media;
 				logs.extend(new_logs);
 			},
 			SeriesScanTask::MarkMissingMedia(paths) => {
@@ -271,7 +369,7 @@ impl JobExt for SeriesScanJob {
 					}
 					.into_worker_send(),
 				]);
-				output.updated_media += updated_media;
+				output.missing_media += updated_media;
 				logs.extend(new_logs);
 			},
 			SeriesScanTask::CreateMedia(paths) => {
@@ -286,6 +384,9 @@ impl JobExt for SeriesScanJob {
 					MediaBuildOperation {
 						series_id: self.id.clone(),
 						library_config: self.config.clone().unwrap_or_default(),
+						filename_patterns: self.filename_patterns(),
+						relocation_candidates: self.relocation_candidates.clone(),
+						options: self.options.clone(),
 						max_concurrency,
 					},
 					ctx,
@@ -315,6 +416,9 @@ impl JobExt for SeriesScanJob {
 					MediaBuildOperation {
 						series_id: self.id.clone(),
 						library_config: self.config.clone().unwrap_or_default(),
+						filename_patterns: self.filename_patterns(),
+						relocation_candidates: self.relocation_candidates.clone(),
+						options: self.options.clone(),
 						max_concurrency,
 					},
 					ctx,
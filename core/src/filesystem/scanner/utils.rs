@@ -1,5 +1,5 @@
 use std::{
-	collections::VecDeque,
+	collections::{HashMap, VecDeque},
 	path::{Path, PathBuf},
 	pin::pin,
 	sync::{
@@ -10,39 +10,72 @@ use std::{
 };
 
 use futures::{stream::FuturesUnordered, StreamExt};
+use itertools::Itertools;
 use prisma_client_rust::{
 	chrono::{DateTime, Utc},
 	QueryError,
 };
 use tokio::{
-	sync::{oneshot, Semaphore},
+	sync::{oneshot, Mutex, Semaphore},
 	task::spawn_blocking,
 };
-use walkdir::DirEntry;
+
+use super::{BookVisitOperation, LibraryRoots, ScanOptions};
 
 use crate::{
 	config::StumpConfig,
 	db::{
-		entity::{LibraryConfig, Media, Series},
+		entity::{
+			macros::{media_id_select, media_relocation_candidate_select},
+			CompiledFilenamePatterns, LibraryConfig, Media, MediaMetadata, MediaStoryArc,
+			MergeStrategy, MetadataFieldConflict, MetadataMergeResult, Series,
+		},
 		FileStatus,
 	},
 	error::{CoreError, CoreResult},
-	filesystem::{MediaBuilder, SeriesBuilder},
+	filesystem::{
+		storage::{path_exists, storage_for_path, StorageEntry},
+		MediaBuilder, SeriesBuilder,
+	},
 	job::{error::JobError, JobExecuteLog, JobProgress, WorkerCtx, WorkerSendExt},
-	prisma::{media, media_metadata, series, PrismaClient},
+	metadata::{credit_media, credited_names, resolve_people, ResolvedPeople},
+	prisma::{
+		library_root, media, media_metadata, media_metadata_conflict, series, story_arc,
+		story_arc_member, PrismaClient,
+	},
 	utils::chain_optional_iter,
 	CoreEvent,
 };
 
+/// Fetches the roots of a library, given its id and primary path
+pub(crate) async fn get_library_roots(
+	db: &PrismaClient,
+	library_id: &str,
+	path: &str,
+) -> CoreResult<LibraryRoots> {
+	let additional_roots = db
+		.library_root()
+		.find_many(vec![library_root::library_id::equals(
+			library_id.to_string(),
+		)])
+		.exec()
+		.await?;
+
+	Ok(LibraryRoots::new(
+		path,
+		additional_roots.into_iter().map(|root| root.path),
+	))
+}
+
 pub(crate) fn file_updated_since_scan(
-	entry: &DirEntry,
+	entry: &StorageEntry,
 	last_modified_at: String,
 ) -> CoreResult<bool> {
-	if let Ok(Ok(system_time)) = entry.metadata().map(|m| m.modified()) {
+	if let Some(system_time) = entry.modified_at {
 		let media_modified_at =
 			last_modified_at.parse::<DateTime<Utc>>().map_err(|e| {
 				tracing::error!(
-					path = ?entry.path(),
+					path = ?entry.path,
 					error = ?e,
 					"Error occurred trying to read modified date for media",
 				);
@@ -59,7 +92,7 @@ pub(crate) fn file_updated_since_scan(
 		Ok(false)
 	} else {
 		tracing::error!(
-			path = ?entry.path(),
+			path = ?entry.path,
 			"Error occurred trying to read modified date for media",
 		);
 
@@ -67,46 +100,58 @@ pub(crate) fn file_updated_since_scan(
 	}
 }
 
-// TODO(granular-scans): Use this to determine if Full is needed?
-// pub(crate) fn book_updated_since_scan(book: &Media) -> bool {
-// 	let modified_at_result = book
-// 		.modified_at
-// 		.clone()
-// 		.map(|m| m.parse::<DateTime<Utc>>())
-// 		.transpose();
-
-// 	let modified_at = match modified_at_result {
-// 		Ok(Some(modified_at)) => modified_at,
-// 		Ok(None) => {
-// 			tracing::trace!("Modified_at is None");
-// 			return false;
-// 		},
-// 		Err(err) => {
-// 			tracing::error!(error = ?err, "Failed to parse modified_at");
-// 			return false;
-// 		},
-// 	};
-
-// 	let underlying_file = PathBuf::from(&book.path);
-// 	if let Ok(Ok(system_time)) = underlying_file.metadata().map(|m| m.modified()) {
-// 		let system_time_converted: DateTime<Utc> = system_time.into();
-// 		tracing::trace!(?system_time_converted, ?modified_at, "Comparing dates");
-
-// 		if system_time_converted > modified_at {
-// 			return true;
-// 		}
-// 	}
-
-// 	false
-// }
+/// Whether the book on disk has been modified since it was last scanned, according to
+/// its stored `modified_at` timestamp
+pub(crate) fn book_updated_since_scan(book: &Media, config: &StumpConfig) -> bool {
+	let modified_at_result = book
+		.modified_at
+		.clone()
+		.map(|m| m.parse::<DateTime<Utc>>())
+		.transpose();
+
+	let modified_at = match modified_at_result {
+		Ok(Some(modified_at)) => modified_at,
+		Ok(None) => {
+			tracing::trace!("Modified_at is None");
+			return false;
+		},
+		Err(err) => {
+			tracing::error!(error = ?err, "Failed to parse modified_at");
+			return false;
+		},
+	};
+
+	let underlying_file = PathBuf::from(&book.path);
+	let modified_on_disk = storage_for_path(&underlying_file, config)
+		.and_then(|storage| storage.metadata(&underlying_file))
+		.ok()
+		.and_then(|entry| entry.modified_at);
+	if let Some(system_time) = modified_on_disk {
+		let system_time_converted: DateTime<Utc> = system_time.into();
+		tracing::trace!(?system_time_converted, ?modified_at, "Comparing dates");
+
+		if system_time_converted > modified_at {
+			return true;
+		}
+	}
+
+	false
+}
 
 pub(crate) async fn create_media(
 	db: &PrismaClient,
 	generated: Media,
+	people: &ResolvedPeople,
 ) -> CoreResult<Media> {
 	let result: Result<Media, QueryError> = db
 		._transaction()
 		.run(|client| async move {
+			let story_arcs = generated
+				.metadata
+				.as_ref()
+				.map(|metadata| metadata.story_arcs.clone())
+				.unwrap_or_default();
+			let credited_metadata = generated.metadata.clone();
 			let created_metadata = if let Some(metadata) = generated.metadata {
 				let params = metadata.into_prisma();
 				let created_metadata =
@@ -136,6 +181,11 @@ pub(crate) async fn create_media(
 				.await?;
 			tracing::trace!(?created_media, "Media inserted");
 
+			sync_story_arcs(&client, &created_media.id, story_arcs).await?;
+			if let Some(metadata) = credited_metadata {
+				credit_media(&client, &created_media.id, &metadata, people).await?;
+			}
+
 			if let Some(media_metadata) = created_metadata {
 				let updated_media = client
 					.media()
@@ -159,31 +209,430 @@ pub(crate) async fn create_media(
 	Ok(result?)
 }
 
-pub(crate) async fn update_media(db: &PrismaClient, media: Media) -> CoreResult<Media> {
+/// The media of a library which newly built books may have been moved or renamed from. The
+/// media are loaded the first time a book is checked, i.e. once per scan, and books are matched
+/// against them in memory.
+///
+/// A media matches a book by either of its hashes or by its size, name and extension, and is
+/// only considered moved if its recorded path no longer exists. This intentionally does not
+/// require the media to already be marked as missing, since the series it was moved from might
+/// not have been scanned yet.
+#[derive(Debug, Default)]
+pub(crate) struct RelocationCandidates(Mutex<Option<RelocationIndex>>);
+
+impl RelocationCandidates {
+	/// Finds the media which the book was moved or renamed from, if any. A media is only ever
+	/// returned once, so that it can't be claimed by multiple books
+	pub async fn claim(
+		&self,
+		db: &PrismaClient,
+		library_id: Option<&str>,
+		book: &Media,
+		config: &Arc<StumpConfig>,
+	) -> CoreResult<Option<Media>> {
+		let mut index = self.0.lock().await;
+		if index.is_none() {
+			*index = Some(RelocationIndex::load(db, library_id).await?);
+		}
+		let Some(index) = index.as_mut() else {
+			return Ok(None);
+		};
+
+		for candidate in index.matches(book) {
+			let exists = spawn_blocking({
+				let path = PathBuf::from(&candidate.path);
+				let config = Arc::clone(config);
+				move || path_exists(&path, &config)
+			})
+			.await
+			.map_err(|e| CoreError::InternalError(e.to_string()))?;
+
+			if !exists {
+				tracing::debug!(
+					id = candidate.id,
+					from = candidate.path,
+					to = book.path,
+					"Detected relocated media"
+				);
+				return Ok(index.remove(&candidate.id));
+			}
+		}
+
+		Ok(None)
+	}
+}
+
+/// An in-memory index of [`RelocationCandidates`], keyed by each of the ways a book may be
+/// matched to them
+#[derive(Debug, Default)]
+struct RelocationIndex {
+	media: HashMap<String, Media>,
+	by_hash: HashMap<String, Vec<String>>,
+	by_koreader_hash: HashMap<String, Vec<String>>,
+	by_file: HashMap<(i64, String, String), Vec<String>>,
+}
+
+impl RelocationIndex {
+	async fn load(db: &PrismaClient, library_id: Option<&str>) -> CoreResult<Self> {
+		let media = db
+			.media()
+			.find_many(chain_optional_iter(
+				[],
+				[library_id.map(|id| {
+					media::series::is(vec![series::library_id::equals(Some(
+						id.to_string(),
+					))])
+				})],
+			))
+			.select(media_relocation_candidate_select::select())
+			.exec()
+			.await?
+			.into_iter()
+			.map(|candidate| Media {
+				id: candidate.id,
+				name: candidate.name,
+				size: candidate.size,
+				extension: candidate.extension,
+				hash: candidate.hash,
+				koreader_hash: candidate.koreader_hash,
+				path: candidate.path,
+				..Default::default()
+			});
+		Ok(Self::new(media))
+	}
+
+	fn new(media: impl IntoIterator<Item = Media>) -> Self {
+		let mut index = Self::default();
+		for media in media {
+			if let Some(hash) = media.hash.clone() {
+				index
+					.by_hash
+					.entry(hash)
+					.or_default()
+					.push(media.id.clone());
+			}
+			if let Some(hash) = media.koreader_hash.clone() {
+				index
+					.by_koreader_hash
+					.entry(hash)
+					.or_default()
+					.push(media.id.clone());
+			}
+			index
+				.by_file
+				.entry((media.size, media.name.clone(), media.extension.clone()))
+				.or_default()
+				.push(media.id.clone());
+			index.media.insert(media.id.clone(), media);
+		}
+		index
+	}
+
+	/// The media which match the book, excluding the media already at the path of the book
+	fn matches(&self, book: &Media) -> Vec<Media> {
+		let by_hash = book.hash.as_ref().and_then(|hash| self.by_hash.get(hash));
+		let by_koreader_hash = book
+			.koreader_hash
+			.as_ref()
+			.and_then(|hash| self.by_koreader_hash.get(hash));
+		let by_file =
+			self.by_file
+				.get(&(book.size, book.name.clone(), book.extension.clone()));
+
+		[by_hash, by_koreader_hash, by_file]
+			.into_iter()
+			.flatten()
+			.flatten()
+			.unique()
+			.filter_map(|id| self.media.get(id))
+			.filter(|media| media.path != book.path)
+			.cloned()
+			.collect()
+	}
+
+	/// Removes the media from the index, so that it is no longer matched
+	fn remove(&mut self, id: &str) -> Option<Media> {
+		self.media.remove(id)
+	}
+}
+
+/// Re-points an existing media to the location of a newly built book, preserving the ID of
+/// the existing media and therefore everything which references it (e.g. reading sessions,
+/// bookmarks, reading lists)
+pub(crate) async fn relocate_media(
+	db: &PrismaClient,
+	existing_id: &str,
+	mut book: Media,
+	merge_strategy: MergeStrategy,
+	people: &ResolvedPeople,
+) -> CoreResult<()> {
+	let result: Result<(), QueryError> = db
+		._transaction()
+		.run(|client| async move {
+			let metadata_id = match book.metadata.take() {
+				Some(metadata) => Some(
+					upsert_file_metadata(
+						&client,
+						existing_id,
+						metadata,
+						merge_strategy,
+						people,
+					)
+					.await?,
+				),
+				_ => None,
+			};
+
+			repoint_media(&client, existing_id, book, metadata_id).await
+		})
+		.await;
+
+	Ok(result?)
+}
+
+/// Updates the row of an existing media with the file details of the book it was relocated
+/// to, including the path and series it now lives in
+async fn repoint_media(
+	client: &PrismaClient,
+	existing_id: &str,
+	book: Media,
+	metadata_id: Option<String>,
+) -> Result<(), QueryError> {
+	let modified_at = book
+		.modified_at
+		.as_deref()
+		.and_then(|modified_at| DateTime::parse_from_rfc3339(modified_at).ok());
+
+	let relocated_media = client
+		.media()
+		.update(
+			media::id::equals(existing_id.to_string()),
+			chain_optional_iter(
+				[
+					media::name::set(book.name),
+					media::size::set(book.size),
+					media::extension::set(book.extension),
+					media::pages::set(book.pages),
+					media::hash::set(book.hash),
+					media::koreader_hash::set(book.koreader_hash),
+					media::path::set(book.path),
+					media::modified_at::set(modified_at),
+					media::status::set(FileStatus::Ready.to_string()),
+					media::series::connect(series::id::equals(book.series_id)),
+				],
+				[metadata_id
+					.map(|id| media::metadata::connect(media_metadata::id::equals(id)))],
+			),
+		)
+		.select(media_id_select::select())
+		.exec()
+		.await?;
+	tracing::trace!(?relocated_media, "Media relocated");
+
+	Ok(())
+}
+
+/// Reconciles the given metadata with the metadata currently stored for a media according to
+/// the `merge_strategy`, upserting the result and recording any conflicts. Fields which were
+/// locked by a manual edit are never overwritten, and the credits of the media are synced with
+/// the result using the people resolved for its batch (see [credit_media]). Returns the ID of
+/// the upserted metadata.
+pub(crate) async fn merge_and_upsert_metadata(
+	client: &PrismaClient,
+	media_id: &str,
+	metadata: MediaMetadata,
+	merge_strategy: MergeStrategy,
+	people: &ResolvedPeople,
+) -> Result<String, QueryError> {
+	// The existing metadata is needed even when replacing it, since locked fields are kept
+	let existing_metadata = client
+		.media_metadata()
+		.find_unique(media_metadata::media_id::equals(media_id.to_string()))
+		.exec()
+		.await?
+		.map(MediaMetadata::from);
+
+	let MetadataMergeResult {
+		metadata,
+		conflicts,
+	} = MediaMetadata::merge(existing_metadata, metadata, merge_strategy);
+
+	let params = metadata
+		.into_prisma()
+		.into_iter()
+		.chain(vec![media_metadata::media_id::set(Some(
+			media_id.to_string(),
+		))])
+		.collect::<Vec<_>>();
+	let updated_metadata = client
+		.media_metadata()
+		.upsert(
+			media_metadata::media_id::equals(media_id.to_string()),
+			params.clone(),
+			params,
+		)
+		.exec()
+		.await?;
+	tracing::trace!(?updated_metadata, "Metadata upserted");
+	credit_media(
+		client,
+		media_id,
+		&MediaMetadata::from(updated_metadata.clone()),
+		people,
+	)
+	.await?;
+
+	if merge_strategy == MergeStrategy::Conflict {
+		// Any previously recorded conflicts which no longer differ are stale
+		let removed_conflicts = client
+			.media_metadata_conflict()
+			.delete_many(vec![
+				media_metadata_conflict::media_id::equals(media_id.to_string()),
+				media_metadata_conflict::field::not_in_vec(
+					conflicts.iter().map(|c| c.field.clone()).collect(),
+				),
+			])
+			.exec()
+			.await?;
+		tracing::trace!(removed_conflicts, "Removed stale metadata conflicts");
+
+		for MetadataFieldConflict {
+			field,
+			current_value,
+			incoming_value,
+		} in conflicts
+		{
+			let params = vec![
+				media_metadata_conflict::current_value::set(Some(
+					current_value.to_string(),
+				)),
+				media_metadata_conflict::incoming_value::set(Some(
+					incoming_value.to_string(),
+				)),
+			];
+			client
+				.media_metadata_conflict()
+				.upsert(
+					media_metadata_conflict::media_id_field(
+						media_id.to_string(),
+						field.clone(),
+					),
+					(
+						field,
+						media::id::equals(media_id.to_string()),
+						params.clone(),
+					),
+					params,
+				)
+				.exec()
+				.await?;
+		}
+	}
+
+	Ok(updated_metadata.id)
+}
+
+/// Upserts the metadata read from the file of a media (see [merge_and_upsert_metadata]), and
+/// replaces its story arc memberships with those listed in the file
+async fn upsert_file_metadata(
+	client: &PrismaClient,
+	media_id: &str,
+	mut metadata: MediaMetadata,
+	merge_strategy: MergeStrategy,
+	people: &ResolvedPeople,
+) -> Result<String, QueryError> {
+	let story_arcs = std::mem::take(&mut metadata.story_arcs);
+	let metadata_id =
+		merge_and_upsert_metadata(client, media_id, metadata, merge_strategy, people)
+			.await?;
+	sync_story_arcs(client, media_id, story_arcs).await?;
+	Ok(metadata_id)
+}
+
+/// Replaces the story arc memberships of a media with the given arcs, creating any arcs which
+/// don't exist yet. Arcs are matched by name, so books in different series and libraries end up
+/// in the same arc. Arcs which are left without any books are removed.
+async fn sync_story_arcs(
+	client: &PrismaClient,
+	media_id: &str,
+	story_arcs: Vec<MediaStoryArc>,
+) -> Result<(), QueryError> {
+	let mut story_arc_ids = Vec::with_capacity(story_arcs.len());
+	for MediaStoryArc { name, position } in story_arcs {
+		let story_arc = client
+			.story_arc()
+			.upsert(
+				story_arc::name::equals(name.clone()),
+				(name, vec![]),
+				vec![],
+			)
+			.exec()
+			.await?;
+
+		let params = vec![story_arc_member::position::set(position)];
+		client
+			.story_arc_member()
+			.upsert(
+				story_arc_member::story_arc_id_media_id(
+					story_arc.id.clone(),
+					media_id.to_string(),
+				),
+				(
+					story_arc::id::equals(story_arc.id.clone()),
+					media::id::equals(media_id.to_string()),
+					params.clone(),
+				),
+				params,
+			)
+			.exec()
+			.await?;
+		story_arc_ids.push(story_arc.id);
+	}
+
+	let removed_members = client
+		.story_arc_member()
+		.delete_many(vec![
+			story_arc_member::media_id::equals(media_id.to_string()),
+			story_arc_member::story_arc_id::not_in_vec(story_arc_ids),
+		])
+		.exec()
+		.await?;
+	if removed_members > 0 {
+		let removed_story_arcs = client
+			.story_arc()
+			.delete_many(vec![story_arc::members::none(vec![])])
+			.exec()
+			.await?;
+		tracing::trace!(
+			removed_members,
+			removed_story_arcs,
+			"Removed story arc members"
+		);
+	}
+
+	Ok(())
+}
+
+pub(crate) async fn update_media(
+	db: &PrismaClient,
+	media: Media,
+	merge_strategy: MergeStrategy,
+	people: &ResolvedPeople,
+) -> CoreResult<Media> {
 	let result: Result<Media, QueryError> = db
 		._transaction()
 		.run(|client| async move {
 			let metadata_id = match media.metadata {
-				Some(metadata) => {
-					let params = metadata
-						.into_prisma()
-						.into_iter()
-						.chain(vec![media_metadata::media_id::set(Some(
-							media.id.clone(),
-						))])
-						.collect::<Vec<_>>();
-					let updated_metadata = client
-						.media_metadata()
-						.upsert(
-							media_metadata::media_id::equals(media.id.clone()),
-							params.clone(),
-							params,
-						)
-						.exec()
-						.await?;
-					tracing::trace!(?updated_metadata, "Metadata upserted");
-					Some(updated_metadata.id)
-				},
+				Some(metadata) => Some(
+					upsert_file_metadata(
+						&client,
+						&media.id,
+						metadata,
+						merge_strategy,
+						people,
+					)
+					.await?,
+				),
 				_ => None,
 			};
 
@@ -219,6 +668,65 @@ pub(crate) async fn update_media(db: &PrismaClient, media: Media) -> CoreResult<
 	Ok(result?)
 }
 
+/// Updates only the hashes and/or metadata of a media in the database, as opposed to
+/// [update_media] which overwrites every field
+pub(crate) async fn update_media_partial(
+	db: &PrismaClient,
+	media: Media,
+	hashes: bool,
+	metadata: bool,
+	merge_strategy: MergeStrategy,
+	people: &ResolvedPeople,
+) -> CoreResult<()> {
+	let result: Result<(), QueryError> = db
+		._transaction()
+		.run(|client| async move {
+			let metadata_id = match media.metadata.filter(|_| metadata) {
+				Some(metadata) => Some(
+					upsert_file_metadata(
+						&client,
+						&media.id,
+						metadata,
+						merge_strategy,
+						people,
+					)
+					.await?,
+				),
+				_ => None,
+			};
+
+			let params = chain_optional_iter(
+				[],
+				[
+					hashes.then(|| media::hash::set(media.hash.clone())),
+					hashes
+						.then(|| media::koreader_hash::set(media.koreader_hash.clone())),
+					metadata_id.map(|id| {
+						media::metadata::connect(media_metadata::id::equals(id))
+					}),
+				],
+			);
+
+			if params.is_empty() {
+				tracing::trace!(id = ?media.id, "Nothing to update for media");
+				return Ok(());
+			}
+
+			let updated_media = client
+				.media()
+				.update(media::id::equals(media.id.clone()), params)
+				.select(media::select!({ id }))
+				.exec()
+				.await?;
+			tracing::trace!(?updated_media, "Media partially updated");
+
+			Ok(())
+		})
+		.await;
+
+	Ok(result?)
+}
+
 #[derive(Default)]
 pub(crate) struct MissingSeriesOutput {
 	pub updated_series: u64,
@@ -398,16 +906,26 @@ pub(crate) async fn handle_restored_media(
 /// # Arguments
 /// * `for_library` - The library ID to associate the series with
 /// * `path` - The path to the series on disk
-async fn build_series(for_library: &str, path: &Path) -> CoreResult<Series> {
+/// * `filename_patterns` - The compiled filename patterns of the library
+async fn build_series(
+	for_library: &str,
+	path: &Path,
+	filename_patterns: &CompiledFilenamePatterns,
+) -> CoreResult<Series> {
 	let (tx, rx) = oneshot::channel();
 
 	// Spawn a blocking task to handle the IO-intensive operations:
 	let handle = spawn_blocking({
 		let path = path.to_path_buf();
 		let for_library = for_library.to_string();
+		let filename_patterns = filename_patterns.clone();
 
 		move || {
-			let send_result = tx.send(SeriesBuilder::new(&path, &for_library).build());
+			let send_result = tx.send(
+				SeriesBuilder::new(&path, &for_library)
+					.with_filename_patterns(filename_patterns)
+					.build(),
+			);
 			tracing::trace!(
 				is_err = send_result.is_err(),
 				"Sending build result to channel"
@@ -435,11 +953,13 @@ async fn build_series(for_library: &str, path: &Path) -> CoreResult<Series> {
 /// # Arguments
 /// * `for_library` - The library ID to associate the series with
 /// * `paths` - A list of paths to build series from
+/// * `filename_patterns` - The compiled filename patterns of the library
 /// * `core_config` - The core configuration
 /// * `reporter` - A function to report progress to the UI
 pub(crate) async fn safely_build_series(
 	for_library: &str,
 	paths: Vec<PathBuf>,
+	filename_patterns: &CompiledFilenamePatterns,
 	core_config: &StumpConfig,
 	reporter: impl Fn(usize),
 ) -> (Vec<Series>, Vec<JobExecuteLog>) {
@@ -468,7 +988,7 @@ pub(crate) async fn safely_build_series(
 					.await
 					.map_err(|e| (CoreError::Unknown(e.to_string()), path.clone()))?;
 				tracing::trace!(?path, "Acquired permit for series creation");
-				build_series(&library_id, &path)
+				build_series(&library_id, &path, filename_patterns)
 					.await
 					.map_err(|e| (e, path.clone()))
 			}
@@ -507,53 +1027,155 @@ pub(crate) async fn safely_build_series(
 	(created_series, logs)
 }
 
-// TODO(granular-scans): intake ScanOptions
 pub(crate) struct MediaBuildOperation {
 	pub series_id: String,
 	pub library_config: LibraryConfig,
+	/// The filename patterns of the library, which are compiled once per scan
+	pub filename_patterns: CompiledFilenamePatterns,
+	/// The media which new books may have been moved or renamed from, which are shared by
+	/// every task of a scan
+	pub relocation_candidates: Arc<RelocationCandidates>,
+	pub options: ScanOptions,
 	pub max_concurrency: usize,
 }
 
-// TODO(granular-scans): build_book_partial? build_partial_book?
-/*
-	A suboptimal approach could be:
-	```rust
-	struct PartialBook {
+/// The result of visiting an existing book on disk, which determines how much of the
+/// book is written back to the database
+#[derive(Debug)]
+pub(crate) enum BookUpdate {
+	/// The book was fully rebuilt, and every field should be updated
+	Full(Media),
+	/// Only the requested parts of the book were rebuilt, and only those should be updated
+	Partial {
 		book: Media,
-		fields_to_update: Option<Vec<String>>, // None means full update, 0 len is err(?)
-	}
-	```
-	This would be the easiest, but would involve some wasted work. Maybe an emum?
-	```rust
-	enum BookUpdate {
-		Full(Media),
-		// TODO(granular-scans): Legacy metadata doesn't strictly set the ID to the associated
-		// media's ID. This is a problem in that the book's id would need to be provided.
-		Metadata(MediaMetadata),
-		Hashes {
-			id: String,
-			hash: Option<String>,
-			koreader_hash: Option<String>,
-		},
-	}
-	```
-*/
+		hashes: bool,
+		metadata: bool,
+	},
+	/// Only the requested parts of the book were rebuilt, but they match what is already
+	/// stored so nothing needs to be written back
+	Unchanged,
+}
 
 /// Builds a media from the given path
 ///
 /// # Arguments
 /// * `path` - The path to the media on disk
 /// * `series_id` - The series ID to associate the media with
-/// * `existing_book` - An optional existing media to rebuild
 /// * `library_config` - The library configuration
+/// * `filename_patterns` - The compiled filename patterns of the library
 /// * `config` - The core configuration
 async fn build_book(
 	path: &Path,
 	series_id: &str,
-	existing_book: Option<Media>,
 	library_config: LibraryConfig,
+	filename_patterns: CompiledFilenamePatterns,
 	config: &StumpConfig,
 ) -> CoreResult<Media> {
+	run_media_builder(
+		path,
+		series_id,
+		library_config,
+		filename_patterns,
+		config,
+		|builder| builder.build(),
+	)
+	.await
+}
+
+/// Rebuilds an existing media from the given path. A full rebuild is only performed if the
+/// book has changed on disk (or is missing), otherwise the `operation` determines what is rebuilt
+///
+/// # Arguments
+/// * `path` - The path to the media on disk
+/// * `series_id` - The series ID to associate the media with
+/// * `existing_book` - The existing media to rebuild
+/// * `operation` - The operation to perform if the book has not changed on disk
+/// * `library_config` - The library configuration
+/// * `filename_patterns` - The compiled filename patterns of the library
+/// * `config` - The core configuration
+async fn visit_book(
+	path: &Path,
+	series_id: &str,
+	existing_book: Media,
+	operation: BookVisitOperation,
+	library_config: LibraryConfig,
+	filename_patterns: CompiledFilenamePatterns,
+	config: &StumpConfig,
+) -> CoreResult<BookUpdate> {
+	let requires_full_rebuild = existing_book.status == FileStatus::Missing
+		|| book_updated_since_scan(&existing_book, config);
+	let operation = if requires_full_rebuild {
+		BookVisitOperation::Rebuild
+	} else {
+		operation
+	};
+	tracing::trace!(?path, ?operation, "Visiting book");
+
+	run_media_builder(
+		path,
+		series_id,
+		library_config,
+		filename_patterns,
+		config,
+		move |builder| match operation {
+			BookVisitOperation::Rebuild => {
+				builder.rebuild(&existing_book).map(BookUpdate::Full)
+			},
+			BookVisitOperation::Partial { hashes, metadata } => builder
+				.rebuild_partial(&existing_book, hashes, metadata)
+				.map(|book| {
+					if partial_rebuild_changed(&existing_book, &book, hashes, metadata) {
+						BookUpdate::Partial {
+							book,
+							hashes,
+							metadata,
+						}
+					} else {
+						BookUpdate::Unchanged
+					}
+				}),
+		},
+	)
+	.await
+}
+
+/// Whether a partial rebuild of a book differs from the stored book in any of the rebuilt
+/// parts. Metadata is compared using the mergeable fields, since the IDs of a freshly
+/// built metadata will never match the stored one
+fn partial_rebuild_changed(
+	existing_book: &Media,
+	rebuilt_book: &Media,
+	hashes: bool,
+	metadata: bool,
+) -> bool {
+	let hashes_changed = hashes
+		&& (existing_book.hash != rebuilt_book.hash
+			|| existing_book.koreader_hash != rebuilt_book.koreader_hash);
+	let metadata_changed = metadata
+		&& match (&existing_book.metadata, &rebuilt_book.metadata) {
+			(Some(existing), Some(rebuilt)) => {
+				!existing.changed_fields(rebuilt).is_empty()
+			},
+			(None, Some(_)) => true,
+			_ => false,
+		};
+	hashes_changed || metadata_changed
+}
+
+/// Runs the given operation against a [MediaBuilder] on a blocking thread, since building
+/// media is IO-intensive
+async fn run_media_builder<T, F>(
+	path: &Path,
+	series_id: &str,
+	library_config: LibraryConfig,
+	filename_patterns: CompiledFilenamePatterns,
+	config: &StumpConfig,
+	operation: F,
+) -> CoreResult<T>
+where
+	T: Send + 'static,
+	F: FnOnce(MediaBuilder) -> CoreResult<T> + Send + 'static,
+{
 	let (tx, rx) = oneshot::channel();
 
 	// Spawn a blocking task to handle the IO-intensive operations:
@@ -564,12 +1186,9 @@ async fn build_book(
 		let config = config.clone();
 
 		move || {
-			let builder = MediaBuilder::new(&path, &series_id, library_config, &config);
-			let send_result = tx.send(if let Some(existing_book) = existing_book {
-				builder.rebuild(&existing_book)
-			} else {
-				builder.build()
-			});
+			let builder = MediaBuilder::new(&path, &series_id, library_config, &config)
+				.with_filename_patterns(filename_patterns);
+			let send_result = tx.send(operation(builder));
 			tracing::trace!(
 				is_err = send_result.is_err(),
 				"Sending build result to channel"
@@ -602,6 +1221,9 @@ pub(crate) async fn safely_build_and_insert_media(
 	MediaBuildOperation {
 		series_id,
 		library_config,
+		filename_patterns,
+		relocation_candidates,
+		options,
 		max_concurrency,
 	}: MediaBuildOperation,
 	worker_ctx: &WorkerCtx,
@@ -628,6 +1250,7 @@ pub(crate) async fn safely_build_and_insert_media(
 			let semaphore = semaphore.clone();
 			let series_id = series_id.clone();
 			let library_config = library_config.clone();
+			let filename_patterns = filename_patterns.clone();
 			let path = path.clone();
 
 			async move {
@@ -639,9 +1262,15 @@ pub(crate) async fn safely_build_and_insert_media(
 					.await
 					.map_err(|e| (CoreError::Unknown(e.to_string()), path.clone()))?;
 				tracing::trace!(?path, "Acquired permit for media creation");
-				build_book(&path, &series_id, None, library_config, &worker_ctx.config)
-					.await
-					.map_err(|e| (e, path.clone()))
+				build_book(
+					&path,
+					&series_id,
+					library_config,
+					filename_patterns,
+					&worker_ctx.config,
+				)
+				.await
+				.map_err(|e| (e, path.clone()))
 			}
 		})
 		.collect::<FuturesUnordered<_>>();
@@ -688,10 +1317,74 @@ pub(crate) async fn safely_build_and_insert_media(
 
 	let atomic_cursor = Arc::new(AtomicUsize::new(1));
 
+	let merge_strategy = options.merge_strategy(library_config.merge_strategy);
+	// The people credited by the books are resolved once for the whole batch
+	let people = resolve_people(
+		&worker_ctx.db,
+		books
+			.iter()
+			.filter_map(|book| book.metadata.as_ref())
+			.flat_map(credited_names),
+	)
+	.await?;
+
 	// TODO: consider small batches of _batch instead?
 	while let Some(book) = books.pop_front() {
 		let path = book.path.clone();
-		match create_media(&worker_ctx.db, book).await {
+
+		// Books which were moved or renamed are re-pointed rather than created, so that
+		// reading history, bookmarks, etc are not lost
+		let relocated = match relocation_candidates
+			.claim(
+				&worker_ctx.db,
+				library_config.library_id.as_deref(),
+				&book,
+				&worker_ctx.config,
+			)
+			.await
+		{
+			Ok(relocated) => relocated,
+			Err(error) => {
+				tracing::error!(?error, ?path, "Failed to check for relocated media");
+				None
+			},
+		};
+		if let Some(existing) = relocated {
+			match relocate_media(
+				&worker_ctx.db,
+				&existing.id,
+				book,
+				merge_strategy,
+				&people,
+			)
+			.await
+			{
+				Ok(_) => {
+					output.updated_media += 1;
+					worker_ctx.report_progress(JobProgress::subtask_position(
+						atomic_cursor.fetch_add(1, Ordering::SeqCst) as i32,
+						task_count,
+					));
+				},
+				Err(e) => {
+					worker_ctx.report_progress(JobProgress::subtask_position(
+						atomic_cursor.fetch_add(1, Ordering::SeqCst) as i32,
+						task_count,
+					));
+					tracing::error!(error = ?e, ?path, "Failed to relocate media");
+					logs.push(
+						JobExecuteLog::error(format!(
+							"Failed to relocate media: {:?}",
+							e.to_string()
+						))
+						.with_ctx(path),
+					);
+				},
+			}
+			continue;
+		}
+
+		match create_media(&worker_ctx.db, book, &people).await {
 			Ok(created_media) => {
 				output.created_media += 1;
 				worker_ctx.send_batch(vec![
@@ -725,9 +1418,11 @@ pub(crate) async fn safely_build_and_insert_media(
 	}
 
 	let success_count = output.created_media;
+	let relocated_count = output.updated_media;
 	let error_count = logs.len() - error_count; // Subtract the errors from the previous step
-	tracing::debug!(success_count, error_count, elapsed = ?start.elapsed(), "Inserted books into database");
+	tracing::debug!(success_count, relocated_count, error_count, elapsed = ?start.elapsed(), "Inserted books into database");
 
+	output.logs = logs;
 	Ok(output)
 }
 
@@ -742,7 +1437,10 @@ pub(crate) async fn visit_and_update_media(
 	MediaBuildOperation {
 		series_id,
 		library_config,
+		filename_patterns,
+		options,
 		max_concurrency,
+		..
 	}: MediaBuildOperation,
 	worker_ctx: &WorkerCtx,
 	paths: Vec<PathBuf>,
@@ -766,6 +1464,8 @@ pub(crate) async fn visit_and_update_media(
 			),
 			media::series_id::equals(Some(series_id.clone())),
 		])
+		// The stored metadata is needed to tell whether a partial visit changed anything
+		.with(media::metadata::fetch())
 		.exec()
 		.await?
 		.into_iter()
@@ -785,16 +1485,15 @@ pub(crate) async fn visit_and_update_media(
 	let task_count = media.len() as i32;
 	let start = Instant::now();
 
-	// TODO(granular-scans): This needs to be aware of ScanOptions and build books more selectively.
-	// For example, if regen_hashes is the only option set, and the book wasn't updated,
-	// we don't need to rebuild it. We would just need to regen the hash. The only time
-	// we would need a full rebuild is if the book was updated on disk?
+	let operation = options.visit_operation();
+	let merge_strategy = options.merge_strategy(library_config.merge_strategy);
 	let futures = media
 		.into_iter()
 		.map(|existing_book| {
 			let semaphore = semaphore.clone();
 			let series_id = series_id.clone();
 			let library_config = library_config.clone();
+			let filename_patterns = filename_patterns.clone();
 			let path = PathBuf::from(existing_book.path.as_str());
 
 			async move {
@@ -806,11 +1505,13 @@ pub(crate) async fn visit_and_update_media(
 					.await
 					.map_err(|e| (CoreError::Unknown(e.to_string()), path.clone()))?;
 				tracing::trace!(?path, "Acquired permit for media visit");
-				build_book(
+				visit_book(
 					path.as_path(),
 					&series_id,
-					Some(existing_book),
+					existing_book,
+					operation,
 					library_config,
+					filename_patterns,
 					&worker_ctx.config,
 				)
 				.await
@@ -857,14 +1558,61 @@ pub(crate) async fn visit_and_update_media(
 
 	let atomic_cursor = Arc::new(AtomicUsize::new(1));
 
+	// The people credited by the books are resolved once for the whole batch. Books may keep
+	// people from their stored metadata too, which are looked up as they are updated
+	let people = resolve_people(
+		client,
+		books
+			.iter()
+			.filter_map(|book| match book {
+				BookUpdate::Full(book)
+				| BookUpdate::Partial {
+					book,
+					metadata: true,
+					..
+				} => book.metadata.as_ref(),
+				_ => None,
+			})
+			.flat_map(credited_names),
+	)
+	.await?;
+
 	// TODO: We don't use the updated book, so chunk these and update_many?
 	while let Some(book) = books.pop_front() {
-		let path = book.path.clone();
-		match update_media(&worker_ctx.db, book).await {
-			Ok(_) => {
+		let result = match book {
+			BookUpdate::Full(book) => {
+				let path = book.path.clone();
+				update_media(&worker_ctx.db, book, merge_strategy, &people)
+					.await
+					.map(|_| true)
+					.map_err(|e| (e, path))
+			},
+			BookUpdate::Partial {
+				book,
+				hashes,
+				metadata,
+			} => {
+				let path = book.path.clone();
+				update_media_partial(
+					&worker_ctx.db,
+					book,
+					hashes,
+					metadata,
+					merge_strategy,
+					&people,
+				)
+				.await
+				.map(|_| true)
+				.map_err(|e| (e, path))
+			},
+			BookUpdate::Unchanged => Ok(false),
+		};
+		match result {
+			Ok(true) => {
 				output.updated_media += 1;
 			},
-			Err(e) => {
+			Ok(false) => {},
+			Err((e, path)) => {
 				tracing::error!(error = ?e, ?path, "Failed to update media");
 				output.logs.push(
 					JobExecuteLog::error(format!(
@@ -888,3 +1636,188 @@ pub(crate) async fn visit_and_update_media(
 
 	Ok(output)
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn book_with_title(title: &str) -> Media {
+		Media {
+			hash: Some("hash".to_string()),
+			metadata: Some(MediaMetadata {
+				id: "stored".to_string(),
+				title: Some(title.to_string()),
+				..Default::default()
+			}),
+			..Default::default()
+		}
+	}
+
+	#[test]
+	fn test_partial_rebuild_changed() {
+		let existing = book_with_title("Title");
+
+		let mut rebuilt = book_with_title("Title");
+		rebuilt.metadata.as_mut().unwrap().id = "fresh".to_string();
+		assert!(!partial_rebuild_changed(&existing, &rebuilt, true, true));
+
+		let rebuilt = book_with_title("Another title");
+		assert!(partial_rebuild_changed(&existing, &rebuilt, false, true));
+		assert!(!partial_rebuild_changed(&existing, &rebuilt, true, false));
+
+		let mut rebuilt = book_with_title("Title");
+		rebuilt.hash = Some("another hash".to_string());
+		assert!(partial_rebuild_changed(&existing, &rebuilt, true, false));
+		assert!(!partial_rebuild_changed(&existing, &rebuilt, false, true));
+	}
+
+	fn relocation_candidate(id: &str, path: &str) -> Media {
+		Media {
+			id: id.to_string(),
+			name: "Batman 001".to_string(),
+			size: 1024,
+			extension: "cbz".to_string(),
+			path: path.to_string(),
+			..Default::default()
+		}
+	}
+
+	fn relocated_book(path: &str) -> Media {
+		Media {
+			name: "Batman 001".to_string(),
+			size: 1024,
+			extension: "cbz".to_string(),
+			path: path.to_string(),
+			series_id: "new_series".to_string(),
+			..Default::default()
+		}
+	}
+
+	#[test]
+	fn test_relocation_matches_by_hash() {
+		let index = RelocationIndex::new([
+			Media {
+				hash: Some("hash".to_string()),
+				name: "Renamed".to_string(),
+				..relocation_candidate("1", "/old/Renamed.cbz")
+			},
+			Media {
+				hash: Some("other".to_string()),
+				name: "Other".to_string(),
+				..relocation_candidate("2", "/old/Other.cbz")
+			},
+		]);
+		let book = Media {
+			hash: Some("hash".to_string()),
+			..relocated_book("/new/Batman 001.cbz")
+		};
+
+		let matches = index.matches(&book);
+		assert_eq!(matches.len(), 1);
+		assert_eq!(matches[0].id, "1");
+	}
+
+	#[test]
+	fn test_relocation_matches_by_koreader_hash() {
+		let index = RelocationIndex::new([Media {
+			koreader_hash: Some("koreader_hash".to_string()),
+			name: "Renamed".to_string(),
+			..relocation_candidate("1", "/old/Renamed.cbz")
+		}]);
+		let book = Media {
+			koreader_hash: Some("koreader_hash".to_string()),
+			..relocated_book("/new/Batman 001.cbz")
+		};
+
+		let matches = index.matches(&book);
+		assert_eq!(matches.len(), 1);
+		assert_eq!(matches[0].id, "1");
+	}
+
+	#[test]
+	fn test_relocation_matches_by_size_and_name() {
+		let index = RelocationIndex::new([
+			relocation_candidate("1", "/old/Batman 001.cbz"),
+			Media {
+				size: 2048,
+				..relocation_candidate("2", "/old/Batman 001.cbz")
+			},
+			// The book itself is never a candidate
+			relocation_candidate("3", "/new/Batman 001.cbz"),
+		]);
+
+		let matches = index.matches(&relocated_book("/new/Batman 001.cbz"));
+		assert_eq!(matches.len(), 1);
+		assert_eq!(matches[0].id, "1");
+	}
+
+	#[test]
+	fn test_relocation_candidate_is_only_claimed_once() {
+		let mut index = RelocationIndex::new([Media {
+			hash: Some("hash".to_string()),
+			..relocation_candidate("1", "/old/Batman 001.cbz")
+		}]);
+		let book = Media {
+			hash: Some("hash".to_string()),
+			..relocated_book("/new/Batman 001.cbz")
+		};
+
+		// A candidate matched by more than one key is only returned once
+		assert_eq!(index.matches(&book).len(), 1);
+		assert!(index.remove("1").is_some());
+		assert!(index.matches(&book).is_empty());
+	}
+
+	#[tokio::test]
+	async fn test_repoint_media() {
+		let (client, mock) = PrismaClient::_mock();
+		let book = Media {
+			pages: 20,
+			hash: Some("hash".to_string()),
+			modified_at: Some("2024-01-02T03:04:05+00:00".to_string()),
+			..relocated_book("/new/Batman 001.cbz")
+		};
+
+		mock.expect(
+			client
+				.media()
+				.update(
+					media::id::equals("existing_id".to_string()),
+					vec![
+						media::name::set("Batman 001".to_string()),
+						media::size::set(1024),
+						media::extension::set("cbz".to_string()),
+						media::pages::set(20),
+						media::hash::set(Some("hash".to_string())),
+						media::koreader_hash::set(None),
+						media::path::set("/new/Batman 001.cbz".to_string()),
+						media::modified_at::set(Some(
+							DateTime::parse_from_rfc3339("2024-01-02T03:04:05+00:00")
+								.unwrap(),
+						)),
+						media::status::set(FileStatus::Ready.to_string()),
+						media::series::connect(series::id::equals(
+							"new_series".to_string(),
+						)),
+						media::metadata::connect(media_metadata::id::equals(
+							"metadata_id".to_string(),
+						)),
+					],
+				)
+				.select(media_id_select::select()),
+			media_id_select::Data {
+				id: "existing_id".to_string(),
+			},
+		)
+		.await;
+
+		let result = repoint_media(
+			&client,
+			"existing_id",
+			book,
+			Some("metadata_id".to_string()),
+		)
+		.await;
+		assert!(result.is_ok(), "Failed to repoint media: {result:?}");
+	}
+}
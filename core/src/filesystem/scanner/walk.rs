@@ -1,34 +1,40 @@
 use std::{
-	collections::HashMap,
+	collections::{HashMap, HashSet},
 	path::{Path, PathBuf},
 	sync::Arc,
 };
 
-use globset::GlobSet;
-use itertools::Either;
-use rayon::iter::{
-	IntoParallelIterator, IntoParallelRefIterator, ParallelBridge, ParallelIterator,
-};
-use walkdir::{DirEntry, WalkDir};
-
 use crate::{
+	config::StumpConfig,
 	db::{entity::macros::media_path_modified_at_select, FileStatus},
-	filesystem::{scanner::utils::file_updated_since_scan, PathUtils},
+	filesystem::{
+		scanner::utils::file_updated_since_scan,
+		storage::{path_exists, storage_for_path, StorageEntry},
+		IgnoreMatcher, PathUtils,
+	},
 	prisma::{media, series, PrismaClient},
-	CoreResult,
+	CoreError, CoreResult,
 };
+use itertools::{Either, Itertools};
+use prisma_client_rust::operator::or;
+use rayon::iter::{IntoParallelIterator, IntoParallelRefIterator, ParallelIterator};
+use tokio::task::spawn_blocking;
 
-use super::ScanOptions;
+use super::{LibraryRoots, ScanOptions};
 
 pub struct WalkerCtx {
+	/// The core configuration, which is used to access the storage the library lives in
+	pub config: Arc<StumpConfig>,
 	/// A reference to the Prisma client
 	pub db: Arc<PrismaClient>,
-	/// The globset of ignore rules to apply during the walk
-	pub ignore_rules: GlobSet,
+	/// The ignore rules to apply during the walk, which includes any `.stumpignore` files
+	pub ignore_rules: IgnoreMatcher,
 	// Will be 1 if the library is collection based, None
 	pub max_depth: Option<usize>,
 	/// The scan options to apply during the walk
 	pub options: ScanOptions,
+	/// The root paths of the library being walked
+	pub roots: LibraryRoots,
 }
 
 /// The output of walking a library
@@ -38,6 +44,8 @@ pub struct WalkedLibrary {
 	pub seen_directories: u64,
 	/// The number of directories that were ignored via ignore rules or common ignore patterns
 	pub ignored_directories: u64,
+	/// The paths for directories that were excluded by ignore rules or `.stumpignore` files
+	pub ignored_by_rules: Vec<PathBuf>,
 	/// The paths for series that need to be created
 	pub series_to_create: Vec<PathBuf>,
 	/// A list of series IDs that were previously marked as missing but have been found on disk
@@ -60,140 +68,213 @@ impl WalkedLibrary {
 	}
 }
 
+/// Lists the entries beneath a directory on a blocking thread, since listing object storage
+/// makes blocking network requests (one for each page of results)
+async fn list_entries(
+	dir: &Path,
+	max_depth: Option<usize>,
+	config: &Arc<StumpConfig>,
+) -> CoreResult<Vec<StorageEntry>> {
+	let dir = dir.to_path_buf();
+	let config = Arc::clone(config);
+	let entries =
+		spawn_blocking(move || storage_for_path(&dir, &config)?.list(&dir, max_depth))
+			.await
+			.map_err(|e| CoreError::InternalError(e.to_string()))??;
+	Ok(entries)
+}
+
 pub async fn walk_library(
-	path: &str,
 	WalkerCtx {
+		config,
 		db,
 		ignore_rules,
 		max_depth,
+		roots,
 		..
 	}: WalkerCtx,
 ) -> CoreResult<WalkedLibrary> {
-	let library_is_missing = !PathBuf::from(path).exists();
+	let (existing_roots, missing_roots): (Vec<PathBuf>, Vec<PathBuf>) = {
+		let paths = roots.paths().to_vec();
+		let config = Arc::clone(&config);
+		spawn_blocking(move || {
+			paths
+				.into_iter()
+				.partition(|root| path_exists(root, &config))
+		})
+		.await
+		.map_err(|e| CoreError::InternalError(e.to_string()))?
+	};
+	for root in &missing_roots {
+		tracing::warn!("Library root {} is missing or inaccessible", root.display());
+	}
+	// The library is only considered missing once none of its roots can be found. Otherwise,
+	// the series under a missing root will be marked as missing individually below
+	let library_is_missing = existing_roots.is_empty();
 	if library_is_missing {
-		tracing::error!("Failed to walk: {} is missing or inaccessible", path);
+		tracing::error!(
+			"Failed to walk: {} is missing or inaccessible",
+			roots.primary().display()
+		);
 		return Ok(WalkedLibrary::missing());
 	}
 
-	let mut walkdir = WalkDir::new(path);
-	if let Some(num) = max_depth {
-		walkdir = walkdir.max_depth(num);
-	}
-
 	let walk_start = std::time::Instant::now();
 	let is_collection_based = max_depth.is_some_and(|d| d == 1);
 	tracing::debug!(
-		?path,
+		?roots,
 		max_depth,
 		is_collection_based,
 		?ignore_rules,
 		"Walking library",
 	);
 
-	let (valid_entries, ignored_entries) = walkdir
-		// Set min_depth to 0 so we include the library path itself,
-		// which allows us to add it as a series when there are media items in it
-		.min_depth(0)
-		.into_iter()
-		.filter_entry(|e| e.path().is_dir())
-		.filter_map(Result::ok)
-		.par_bridge()
-		.partition_map::<Vec<DirEntry>, Vec<DirEntry>, _, _, _>(|entry| {
-			let entry_path = entry.path();
-			let entry_path_str = entry_path.as_os_str().to_string_lossy().to_string();
-			let check_deep = is_collection_based && entry_path_str != path;
-
-			let should_ignore = ignore_rules.is_match(entry.path());
-			// If we're doing a top level scan, we need to check that the path
-			// has media deeply nested. Exception for when the path is the library path,
-			// then we only need to check if it has media in it directly
-			//
-			// If we're doing a bottom up scan, we need to check that the path has
-			// media directly in it.
-			let is_valid = !should_ignore
-				&& (check_deep && entry_path.dir_has_media_deep(&ignore_rules)
-					|| (!check_deep && entry_path.dir_has_media(&ignore_rules)));
-
-			tracing::trace!(?is_valid, ?entry_path_str);
-
-			if is_valid {
-				Either::Left(entry)
-			} else {
-				Either::Right(entry)
+	let mut valid_entries = Vec::<PathBuf>::new();
+	let mut ignored_entries = Vec::<PathBuf>::new();
+	let mut listed_directories = HashSet::<PathBuf>::new();
+	for root in &existing_roots {
+		// The whole root is listed up front, rather than reading each directory as it is
+		// visited, since listing is a single (paginated) request for object storage
+		let entries = list_entries(root, None, &config).await?;
+		let (directories, files): (Vec<&StorageEntry>, Vec<&StorageEntry>) =
+			entries.iter().partition(|entry| entry.is_dir);
+
+		// Files are grouped by the directory they are directly within for the shallow check,
+		// and by the top level directory they are within for the deep check
+		let mut files_by_parent = HashMap::<&Path, Vec<&Path>>::new();
+		let mut files_by_top_level = HashMap::<PathBuf, Vec<&Path>>::new();
+		for file in files {
+			let path = file.path.as_path();
+			if let Some(parent) = path.parent() {
+				files_by_parent.entry(parent).or_default().push(path);
 			}
-		});
+			if let Some(top_level) = path
+				.strip_prefix(root)
+				.ok()
+				.and_then(|relative| relative.components().next())
+			{
+				files_by_top_level
+					.entry(root.join(top_level))
+					.or_default()
+					.push(path);
+			}
+		}
+
+		// Include the library path itself, which allows us to add it as a series when there
+		// are media items in it
+		let candidates = std::iter::once(root.clone())
+			.chain(
+				directories
+					.iter()
+					.filter(|entry| {
+						max_depth
+							.map_or(true, |max| depth_within(root, &entry.path) <= max)
+					})
+					.map(|entry| entry.path.clone()),
+			)
+			.collect::<Vec<PathBuf>>();
+
+		let (valid, ignored) = candidates
+			.into_par_iter()
+			.partition_map::<Vec<PathBuf>, Vec<PathBuf>, _, _, _>(|entry_path| {
+				let check_deep = is_collection_based && entry_path != *root;
+
+				let should_ignore = ignore_rules.is_ignored(&entry_path, true);
+				// If we're doing a top level scan, we need to check that the path
+				// has media deeply nested. Exception for when the path is the library path,
+				// then we only need to check if it has media in it directly
+				//
+				// If we're doing a bottom up scan, we need to check that the path has
+				// media directly in it.
+				let is_valid = !should_ignore
+					&& if check_deep {
+						files_by_top_level.get(&entry_path).is_some_and(|files| {
+							has_media_deep(&entry_path, files, &ignore_rules)
+						})
+					} else {
+						files_by_parent
+							.get(&entry_path.as_path())
+							.is_some_and(|files| has_media(files, &ignore_rules))
+					};
+
+				tracing::trace!(?is_valid, ?entry_path);
+
+				if is_valid {
+					Either::Left(entry_path)
+				} else {
+					Either::Right(entry_path)
+				}
+			});
+		// Keep the entries in a stable order, so that the first root a series is found in
+		// deterministically becomes its path
+		valid_entries.extend(valid.into_iter().sorted());
+		ignored_entries.extend(ignored);
+		listed_directories.insert(root.clone());
+		listed_directories
+			.extend(directories.into_iter().map(|entry| entry.path.clone()));
+	}
 
 	let ignored_directories = ignored_entries.len() as u64;
 	let seen_directories = valid_entries.len() as u64 + ignored_directories;
+	let ignored_by_rules = ignored_entries
+		.into_par_iter()
+		.filter(|path| ignore_rules.is_ignored(path, true))
+		.collect::<Vec<PathBuf>>();
 
 	tracing::debug!(
 		seen_directories,
-		ignored_entries = ignored_entries.len(),
+		ignored_directories,
 		"Walk finished in {}ms",
 		walk_start.elapsed().as_millis()
 	);
 
 	let computation_start = std::time::Instant::now();
-	let (series_to_create, missing_series, recovered_series, series_to_visit) = {
-		let existing_records = db
-			.series()
-			.find_many(vec![series::path::starts_with(path.to_string())])
-			.select(series::select!({ id path status }))
-			.exec()
-			.await?;
-		if existing_records.is_empty() {
-			tracing::debug!(
-				"No existing series found in the database, all series are new"
-			);
-			let series_to_create = valid_entries
-				.into_iter()
-				.map(|e| e.path().to_owned())
-				.collect::<Vec<PathBuf>>();
-			(series_to_create, vec![], vec![], vec![])
-		} else {
-			let existing_series_map = existing_records
-				.iter()
-				.map(|s| (s.path.clone(), s.clone()))
-				.collect::<HashMap<String, _>>();
-
-			let missing_series = existing_series_map
-				.iter()
-				.filter(|(path, _)| !PathBuf::from(path).exists())
-				.map(|(path, _)| PathBuf::from(path))
-				.collect::<Vec<PathBuf>>();
-
-			let recovered_series = existing_records
-				.into_iter()
-				.filter(|s| {
-					s.status == FileStatus::Missing.to_string()
-						&& PathBuf::from(path).exists()
-				})
-				.map(|s| s.id)
-				.collect::<Vec<String>>();
-
-			let (series_to_create, series_to_visit) = valid_entries
-				.par_iter()
-				.filter(|e| !missing_series.contains(&e.path().to_path_buf()))
-				.map(|e| e.path().to_owned())
-				.partition_map::<Vec<PathBuf>, Vec<PathBuf>, _, _, _>(|path| {
-					let already_exists =
-						existing_series_map.contains_key(path.to_string_lossy().as_ref());
-
-					if already_exists {
-						Either::Right(path)
-					} else {
-						Either::Left(path)
-					}
-				});
-
-			(
-				series_to_create,
-				missing_series,
-				recovered_series,
-				series_to_visit,
-			)
-		}
-	};
+	let existing_records = db
+		.series()
+		.find_many(vec![or(roots
+			.paths()
+			.iter()
+			.map(|root| series::path::starts_with(root.to_string_lossy().to_string()))
+			.collect())])
+		.select(series::select!({ id path status }))
+		.exec()
+		.await?;
+
+	// Series are identified by their path relative to the root they are in, so that the same
+	// series folder under multiple roots is only ever created once
+	let existing_series_map = existing_records
+		.iter()
+		.map(|s| (roots.relative(Path::new(&s.path)).to_path_buf(), s))
+		.collect::<HashMap<PathBuf, _>>();
+
+	// Series directories are almost always found by the walk above, so only those which
+	// weren't need to be checked against the storage directly
+	let exists =
+		|path: &Path| listed_directories.contains(path) || path_exists(path, &config);
+	let (missing_series, found_series): (Vec<_>, Vec<_>) = existing_records
+		.iter()
+		.partition(|s| roots.equivalents(Path::new(&s.path), exists).is_empty());
+	let missing_series = missing_series
+		.into_iter()
+		.map(|s| PathBuf::from(&s.path))
+		.collect::<Vec<PathBuf>>();
+
+	let recovered_series = found_series
+		.into_iter()
+		.filter(|s| s.status == FileStatus::Missing.to_string())
+		.map(|s| s.id.clone())
+		.collect::<Vec<String>>();
+
+	let mut seen_series = HashSet::new();
+	let (series_to_create, series_to_visit) = valid_entries
+		.into_iter()
+		.filter(|path| seen_series.insert(roots.relative(path).to_path_buf()))
+		.partition_map::<Vec<PathBuf>, Vec<PathBuf>, _, _, _>(|path| {
+			match existing_series_map.get(roots.relative(&path)) {
+				Some(existing) => Either::Right(PathBuf::from(&existing.path)),
+				None => Either::Left(path),
+			}
+		});
 
 	let to_create = series_to_create.len();
 	tracing::trace!(?series_to_create, "Found {to_create} series to create");
@@ -212,6 +293,7 @@ pub async fn walk_library(
 	Ok(WalkedLibrary {
 		seen_directories,
 		ignored_directories,
+		ignored_by_rules,
 		series_to_create,
 		recovered_series,
 		series_to_visit,
@@ -220,6 +302,35 @@ pub async fn walk_library(
 	})
 }
 
+/// The number of components between `root` and `path`, i.e. 1 for direct children of `root`
+fn depth_within(root: &Path, path: &Path) -> usize {
+	path.strip_prefix(root)
+		.map(|relative| relative.components().count())
+		.unwrap_or_default()
+}
+
+/// Returns true if any of the files directly within a directory are media which aren't ignored
+fn has_media(files: &[&Path], ignore_rules: &IgnoreMatcher) -> bool {
+	files
+		.iter()
+		.any(|file| !file.is_default_ignored() && !ignore_rules.is_ignored(file, false))
+}
+
+/// Returns true if any of the files nested within `dir` are media which aren't ignored. Files
+/// within an ignored directory are skipped entirely, since nothing within them can be
+/// re-included
+fn has_media_deep(dir: &Path, files: &[&Path], ignore_rules: &IgnoreMatcher) -> bool {
+	files.iter().any(|file| {
+		!file.is_default_ignored()
+			&& !ignore_rules.is_ignored(file, false)
+			&& !file
+				.ancestors()
+				.skip(1)
+				.take_while(|ancestor| *ancestor != dir)
+				.any(|ancestor| ignore_rules.is_ignored(ancestor, true))
+	})
+}
+
 /// The output of walking a series
 #[derive(Default)]
 pub struct WalkedSeries {
@@ -228,6 +339,8 @@ pub struct WalkedSeries {
 	/// The number of files that were either ignored via ignore rules or common ignore patterns
 	/// such as `.DS_Store`
 	pub ignored_files: u64,
+	/// The paths for files that were excluded by ignore rules or `.stumpignore` files
+	pub ignored_by_rules: Vec<PathBuf>,
 	/// The number of files which exist in the database but have not been updated since the last scan
 	pub skipped_files: u64,
 	/// The paths for media that need to be created
@@ -255,13 +368,27 @@ impl WalkedSeries {
 pub async fn walk_series(
 	path: &Path,
 	WalkerCtx {
+		config,
 		db,
 		ignore_rules,
 		max_depth,
 		options,
+		roots,
 	}: WalkerCtx,
 ) -> CoreResult<WalkedSeries> {
-	if !path.exists() {
+	// The same series folder may exist under multiple roots of the library, in which case
+	// they are all walked as part of the one series
+	let directories = {
+		let path = path.to_path_buf();
+		let roots = roots.clone();
+		let config = Arc::clone(&config);
+		spawn_blocking(move || {
+			roots.equivalents(&path, |path| path_exists(path, &config))
+		})
+		.await
+		.map_err(|e| CoreError::InternalError(e.to_string()))?
+	};
+	if directories.is_empty() {
 		tracing::error!(
 			"Failed to walk: {} is missing or inaccessible",
 			path.display()
@@ -269,22 +396,24 @@ pub async fn walk_series(
 		return Ok(WalkedSeries::missing());
 	}
 
-	tracing::debug!("Walking series at {}", path.display());
+	tracing::debug!(?directories, "Walking series at {}", path.display());
 
-	let mut walker = WalkDir::new(path);
-	if let Some(num) = max_depth {
-		walker = walker.max_depth(num);
+	let walk_start = std::time::Instant::now();
+	let mut files = Vec::<StorageEntry>::new();
+	for directory in &directories {
+		let entries = list_entries(directory, max_depth, &config).await?;
+		files.extend(entries.into_iter().filter(|entry| !entry.is_dir));
 	}
+	let listed_files = files
+		.iter()
+		.map(|entry| entry.path.clone())
+		.collect::<HashSet<PathBuf>>();
 
-	let walk_start = std::time::Instant::now();
-	let (valid_entries, ignored_entries) = walker
-		.into_iter()
-		.filter_map(Result::ok)
-		.filter_map(|e| e.path().is_file().then_some(e))
-		.par_bridge()
-		.partition_map::<Vec<DirEntry>, Vec<DirEntry>, _, _, _>(|entry| {
-			let entry_path = entry.path();
-			let matches_ignore_rule = ignore_rules.is_match(entry.path());
+	let (valid_entries, ignored_entries) = files
+		.into_par_iter()
+		.partition_map::<Vec<StorageEntry>, Vec<StorageEntry>, _, _, _>(|entry| {
+			let entry_path = entry.path.as_path();
+			let matches_ignore_rule = ignore_rules.is_ignored(entry_path, false);
 
 			if matches_ignore_rule || entry_path.is_default_ignored() {
 				Either::Right(entry)
@@ -296,9 +425,14 @@ pub async fn walk_series(
 	let valid_entries_len = valid_entries.len() as u64;
 	let ignored_files = ignored_entries.len() as u64;
 	let seen_files = valid_entries_len + ignored_files;
+	let ignored_by_rules = ignored_entries
+		.into_par_iter()
+		.map(|entry| entry.path)
+		.filter(|path| ignore_rules.is_ignored(path, false))
+		.collect::<Vec<PathBuf>>();
 	tracing::debug!(
 		seen_files,
-		ignored_entries = ignored_entries.len(),
+		ignored_files,
 		"Walk finished in {}ms",
 		walk_start.elapsed().as_millis()
 	);
@@ -334,7 +468,7 @@ pub async fn walk_series(
 				return true;
 			}
 
-			let entry_path = entry.path();
+			let entry_path = entry.path.as_path();
 			let entry_path_str = entry_path.to_string_lossy().to_string();
 
 			// TODO: support force re-scans
@@ -363,7 +497,7 @@ pub async fn walk_series(
 			}
 		})
 		.partition_map::<Vec<PathBuf>, Vec<PathBuf>, _, _, _>(|entry| {
-			let entry_path = entry.path();
+			let entry_path = entry.path.as_path();
 			let entry_path_str = entry_path.to_string_lossy().to_string();
 
 			// At this point, anything that's left in the iterator is either new or has been modified
@@ -376,18 +510,20 @@ pub async fn walk_series(
 			}
 		});
 
-	let missing_media = existing_media_map
-		.par_iter()
-		.filter(|(path, _)| !PathBuf::from(path).exists())
+	// Media which weren't found by the walk above (e.g. because they are nested deeper than
+	// the max depth) are checked against the storage directly
+	let exists = |path: &Path| listed_files.contains(path) || path_exists(path, &config);
+	let (missing_media, found_media): (Vec<_>, Vec<_>) = existing_media_map
+		.into_par_iter()
+		.partition(|(path, _)| !exists(Path::new(path)));
+	let missing_media = missing_media
+		.into_iter()
 		.map(|(path, _)| PathBuf::from(path))
 		.collect::<Vec<PathBuf>>();
 
-	let recovered_media = existing_media_map
-		.into_par_iter()
-		.filter(|(path, media)| {
-			media.status == FileStatus::Missing.to_string()
-				&& PathBuf::from(path).exists()
-		})
+	let recovered_media = found_media
+		.into_iter()
+		.filter(|(_, media)| media.status == FileStatus::Missing.to_string())
 		.map(|(_, media)| media.id)
 		.collect::<Vec<String>>();
 
@@ -417,6 +553,7 @@ pub async fn walk_series(
 	Ok(WalkedSeries {
 		seen_files,
 		ignored_files,
+		ignored_by_rules,
 		skipped_files,
 		media_to_create,
 		recovered_media,
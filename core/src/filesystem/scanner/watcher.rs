@@ -0,0 +1,600 @@
+use std::{
+	collections::{HashMap, HashSet},
+	path::{Path, PathBuf},
+	sync::Arc,
+	time::{Duration, Instant},
+};
+
+use notify::{
+	event::ModifyKind, Config, Event, EventKind, PollWatcher, RecommendedWatcher,
+	RecursiveMode, Watcher,
+};
+use tokio::{
+	sync::{mpsc, Mutex},
+	task::{spawn_blocking, JoinHandle},
+};
+
+use crate::{
+	db::{
+		entity::{LibraryConfig, LibraryWatchMode, Series},
+		SeriesDAO, DAO,
+	},
+	filesystem::{
+		media::compile_filename_patterns, storage::is_object_storage_path, IgnoreMatcher,
+		PathUtils, SeriesBuilder, STUMPIGNORE_FILENAME,
+	},
+	job::{JobController, JobControllerCommand},
+	prisma::{library, series, PrismaClient},
+	CoreError, CoreResult,
+};
+
+use super::{LibraryRoots, LibraryScanJob, SeriesScanJob};
+
+/// The amount of time without any new events before the pending changes for a library are
+/// flushed into scan jobs
+const DEBOUNCE_DURATION: Duration = Duration::from_secs(5);
+/// The maximum amount of time changes will be held before being flushed, even if events are
+/// still being received (e.g. a large copy operation)
+const MAX_DEBOUNCE_DURATION: Duration = Duration::from_secs(60);
+/// The interval at which libraries configured with [`LibraryWatchMode::Polling`] are polled
+const POLL_INTERVAL: Duration = Duration::from_secs(60);
+
+/// A handle to an active watcher for a single library. Dropping the handle will stop the
+/// underlying watcher and abort the debounce loop.
+struct LibraryWatchHandle {
+	roots: Vec<PathBuf>,
+	mode: LibraryWatchMode,
+	_watcher: Box<dyn Watcher + Send>,
+	debounce_handle: JoinHandle<()>,
+}
+
+impl Drop for LibraryWatchHandle {
+	fn drop(&mut self) {
+		self.debounce_handle.abort();
+	}
+}
+
+/// A struct which manages filesystem watchers for libraries which have opted in via their
+/// [`LibraryConfig::watch_mode`]. Events are debounced per library, and once settled they are
+/// resolved to the series they affect so that targeted [`SeriesScanJob`]s can be enqueued
+/// instead of full library scans.
+pub struct LibraryWatcher {
+	db: Arc<PrismaClient>,
+	job_controller: Arc<JobController>,
+	watchers: Mutex<HashMap<String, LibraryWatchHandle>>,
+}
+
+impl LibraryWatcher {
+	pub fn new(db: Arc<PrismaClient>, job_controller: Arc<JobController>) -> Arc<Self> {
+		Arc::new(Self {
+			db,
+			job_controller,
+			watchers: Mutex::new(HashMap::new()),
+		})
+	}
+
+	/// Start watching every library which has a watch mode configured
+	pub async fn initialize(&self) -> CoreResult<()> {
+		let libraries = self
+			.db
+			.library()
+			.find_many(vec![])
+			.with(library::config::fetch())
+			.with(library::additional_roots::fetch(vec![]))
+			.exec()
+			.await?;
+
+		for library in libraries {
+			let config = library.config().ok().map(LibraryConfig::from);
+			let Some(mode) = config.map(|c| c.watch_mode).filter(|m| m.is_enabled())
+			else {
+				continue;
+			};
+
+			if let Err(error) =
+				self.watch(&library.id, library_roots(&library), mode).await
+			{
+				tracing::error!(?error, library_id = ?library.id, "Failed to watch library");
+			}
+		}
+
+		Ok(())
+	}
+
+	/// Reconcile the watcher for a library with its current state in the database. This should
+	/// be called whenever a library is created, updated or deleted so that changes take effect
+	/// without a restart.
+	pub async fn sync_library(&self, library_id: &str) -> CoreResult<()> {
+		let library = self
+			.db
+			.library()
+			.find_unique(library::id::equals(library_id.to_string()))
+			.with(library::config::fetch())
+			.with(library::additional_roots::fetch(vec![]))
+			.exec()
+			.await?;
+
+		let Some(library) = library else {
+			self.unwatch(library_id).await;
+			return Ok(());
+		};
+		let mode = library
+			.config()
+			.ok()
+			.map(LibraryConfig::from)
+			.map(|c| c.watch_mode)
+			.unwrap_or_default();
+
+		if !mode.is_enabled() {
+			self.unwatch(library_id).await;
+			return Ok(());
+		}
+
+		let roots = library_roots(&library);
+		let is_unchanged =
+			self.watchers
+				.lock()
+				.await
+				.get(library_id)
+				.is_some_and(|handle| {
+					handle.mode == mode && handle.roots.as_slice() == roots.paths()
+				});
+		if is_unchanged {
+			tracing::trace!(library_id, "Library watcher is already up to date");
+			return Ok(());
+		}
+
+		self.watch(library_id, roots, mode).await
+	}
+
+	/// Stop watching a library, if it is currently being watched
+	pub async fn unwatch(&self, library_id: &str) {
+		if self.watchers.lock().await.remove(library_id).is_some() {
+			tracing::debug!(library_id, "Stopped watching library");
+		}
+	}
+
+	/// Stop watching all libraries
+	pub async fn shutdown(&self) {
+		self.watchers.lock().await.clear();
+	}
+
+	async fn watch(
+		&self,
+		library_id: &str,
+		roots: LibraryRoots,
+		mode: LibraryWatchMode,
+	) -> CoreResult<()> {
+		let (tx, rx) = mpsc::unbounded_channel();
+		let handler = move |result: notify::Result<Event>| {
+			let _ = tx.send(result);
+		};
+
+		let mut watcher: Box<dyn Watcher + Send> = match mode {
+			LibraryWatchMode::Native => {
+				Box::new(RecommendedWatcher::new(handler, Config::default())?)
+			},
+			LibraryWatchMode::Polling => Box::new(PollWatcher::new(
+				handler,
+				Config::default().with_poll_interval(POLL_INTERVAL),
+			)?),
+			LibraryWatchMode::Disabled => {
+				return Err(CoreError::BadRequest(
+					"Cannot watch a library with watching disabled".to_string(),
+				))
+			},
+		};
+		for root in roots.paths() {
+			// Object stores don't emit filesystem events, so those roots are only picked up
+			// by scans
+			if is_object_storage_path(root) {
+				tracing::debug!(?root, "Skipping watch of a root in object storage");
+				continue;
+			}
+			// A missing root shouldn't prevent the rest of the library from being watched
+			if let Err(error) = watcher.watch(root, RecursiveMode::Recursive) {
+				tracing::error!(?error, ?root, "Failed to watch library root");
+			}
+		}
+
+		let root_paths = roots.paths().to_vec();
+		let debounce_handle = tokio::spawn(debounce_loop(
+			DebounceCtx {
+				db: self.db.clone(),
+				job_controller: self.job_controller.clone(),
+				library_id: library_id.to_string(),
+				roots,
+			},
+			rx,
+		));
+
+		tracing::info!(
+			library_id,
+			?root_paths,
+			?mode,
+			"Watching library for changes"
+		);
+		// Note: Replacing an existing handle will drop (and therefore stop) the old watcher
+		self.watchers.lock().await.insert(
+			library_id.to_string(),
+			LibraryWatchHandle {
+				roots: root_paths,
+				mode,
+				_watcher: watcher,
+				debounce_handle,
+			},
+		);
+
+		Ok(())
+	}
+}
+
+fn library_roots(library: &library::Data) -> LibraryRoots {
+	LibraryRoots::new(
+		&library.path,
+		library
+			.additional_roots()
+			.map(|roots| roots.iter().map(|root| root.path.clone()).collect())
+			.unwrap_or_else(|_| Vec::<String>::new()),
+	)
+}
+
+struct DebounceCtx {
+	db: Arc<PrismaClient>,
+	job_controller: Arc<JobController>,
+	library_id: String,
+	roots: LibraryRoots,
+}
+
+/// Collects the paths from incoming events until the library has been quiet for
+/// [`DEBOUNCE_DURATION`] (or [`MAX_DEBOUNCE_DURATION`] has elapsed), and then flushes them
+async fn debounce_loop(
+	ctx: DebounceCtx,
+	mut rx: mpsc::UnboundedReceiver<notify::Result<Event>>,
+) {
+	let mut pending = HashSet::<PathBuf>::new();
+	let mut first_event_at: Option<Instant> = None;
+
+	loop {
+		let received = if pending.is_empty() {
+			rx.recv().await
+		} else {
+			let should_flush = first_event_at
+				.is_some_and(|start| start.elapsed() >= MAX_DEBOUNCE_DURATION);
+			let next = if should_flush {
+				Err(())
+			} else {
+				tokio::time::timeout(DEBOUNCE_DURATION, rx.recv())
+					.await
+					.map_err(|_| ())
+			};
+
+			match next {
+				Ok(received) => received,
+				Err(_) => {
+					let paths = pending.drain().collect::<Vec<_>>();
+					first_event_at = None;
+					if let Err(error) = flush_changes(&ctx, paths).await {
+						tracing::error!(
+							?error,
+							library_id = ?ctx.library_id,
+							"Failed to handle library changes"
+						);
+					}
+					continue;
+				},
+			}
+		};
+
+		match received {
+			Some(Ok(event)) if is_relevant_event(&event.kind) => {
+				first_event_at.get_or_insert_with(Instant::now);
+				pending.extend(event.paths);
+			},
+			Some(Ok(_)) => {},
+			Some(Err(error)) => {
+				tracing::error!(?error, library_id = ?ctx.library_id, "Watcher error");
+			},
+			None => {
+				tracing::debug!(library_id = ?ctx.library_id, "Watcher channel closed");
+				break;
+			},
+		}
+	}
+}
+
+/// Whether an event kind could result in a change to the library. Access and metadata-only
+/// events are ignored, since they don't affect what the scanner would produce.
+fn is_relevant_event(kind: &EventKind) -> bool {
+	match kind {
+		EventKind::Create(_) | EventKind::Remove(_) => true,
+		EventKind::Modify(ModifyKind::Metadata(_)) => false,
+		EventKind::Modify(_) => true,
+		_ => false,
+	}
+}
+
+/// Resolves the changed paths to the series they belong to and enqueues a [`SeriesScanJob`]
+/// for each. Directories which are not yet known series are created first, so they may be
+/// scanned without a full library scan.
+async fn flush_changes(ctx: &DebounceCtx, paths: Vec<PathBuf>) -> CoreResult<()> {
+	let library_config = ctx
+		.db
+		.library()
+		.find_unique(library::id::equals(ctx.library_id.clone()))
+		.with(library::config::fetch())
+		.exec()
+		.await?
+		.and_then(|library| library.config().ok().map(LibraryConfig::from))
+		.ok_or_else(|| {
+			CoreError::NotFound(format!("Library {} not found", ctx.library_id))
+		})?;
+	let ignore_rules = IgnoreMatcher::with_roots(
+		ctx.roots.paths(),
+		library_config.ignore_rules.build()?,
+	);
+
+	let existing_series = ctx
+		.db
+		.series()
+		.find_many(vec![series::library_id::equals(Some(
+			ctx.library_id.clone(),
+		))])
+		.select(series::select!({ id path }))
+		.exec()
+		.await?
+		.into_iter()
+		.map(|s| (PathBuf::from(s.path), s.id))
+		.collect::<HashMap<_, _>>();
+	// The same series folder may exist under multiple roots, so changes under any of them are
+	// resolved to the series which was created first
+	let existing_series_by_relative_path = existing_series
+		.keys()
+		.map(|path| (ctx.roots.relative(path).to_path_buf(), path.clone()))
+		.collect::<HashMap<_, _>>();
+
+	let affected_series_paths = paths
+		.into_iter()
+		// Changes to a .stumpignore should rescan the series it affects
+		.filter(|path| {
+			!ignore_rules.is_ignored(path, path.is_dir())
+				&& (!path.is_hidden_file()
+					|| path.file_name() == Some(STUMPIGNORE_FILENAME.as_ref()))
+		})
+		.filter_map(|path| {
+			let (root, _) = ctx.roots.split(&path)?;
+			resolve_series_path(root, &path, library_config.is_collection_based(), |p| {
+				existing_series_by_relative_path.contains_key(ctx.roots.relative(p))
+			})
+		})
+		.map(|path| {
+			existing_series_by_relative_path
+				.get(ctx.roots.relative(&path))
+				.cloned()
+				.unwrap_or(path)
+		})
+		.collect::<HashSet<PathBuf>>();
+	tracing::debug!(
+		library_id = ?ctx.library_id,
+		?affected_series_paths,
+		"Resolved changes to affected series"
+	);
+
+	let (known, unknown): (Vec<_>, Vec<_>) = affected_series_paths
+		.into_iter()
+		.partition(|path| existing_series.contains_key(path));
+
+	let mut to_scan = known
+		.into_iter()
+		.filter_map(|path| existing_series.get(&path).map(|id| (id.clone(), path)))
+		.collect::<Vec<_>>();
+
+	// A series directory which no longer exists (e.g. it was deleted or renamed) can't be
+	// scanned on its own, so the library is scanned instead which marks it as missing and
+	// picks up any new series
+	if to_scan.iter().any(|(_, path)| !path.exists()) {
+		let job = LibraryScanJob::new(
+			ctx.library_id.clone(),
+			ctx.roots.primary().to_string_lossy().to_string(),
+			None,
+		);
+		if let Err(error) = ctx
+			.job_controller
+			.push_command(JobControllerCommand::EnqueueJob(job))
+		{
+			tracing::error!(?error, "Failed to enqueue library scan job");
+		}
+		return Ok(());
+	}
+
+	let series_to_create = unknown
+		.into_iter()
+		.filter(|path| {
+			if library_config.is_collection_based() && !ctx.roots.is_root(path) {
+				path.dir_has_media_deep(&ignore_rules)
+			} else {
+				path.dir_has_media(&ignore_rules)
+			}
+		})
+		.collect::<Vec<_>>();
+	if !series_to_create.is_empty() {
+		let created_series =
+			create_series(ctx, series_to_create, &library_config).await?;
+		to_scan.extend(
+			created_series
+				.into_iter()
+				.map(|s| (s.id, PathBuf::from(s.path))),
+		);
+	}
+
+	for (id, path) in to_scan {
+		let job = SeriesScanJob::new(
+			id,
+			ctx.library_id.clone(),
+			path.to_string_lossy().to_string(),
+			None,
+		);
+		if let Err(error) = ctx
+			.job_controller
+			.push_command(JobControllerCommand::EnqueueJob(job))
+		{
+			tracing::error!(?error, ?path, "Failed to enqueue series scan job");
+		}
+	}
+
+	Ok(())
+}
+
+async fn create_series(
+	ctx: &DebounceCtx,
+	paths: Vec<PathBuf>,
+	library_config: &LibraryConfig,
+) -> CoreResult<Vec<Series>> {
+	tracing::debug!(?paths, "Creating series discovered by watcher");
+	let library_id = ctx.library_id.clone();
+	let filename_patterns = compile_filename_patterns(library_config);
+	let built_series = spawn_blocking(move || {
+		paths
+			.iter()
+			.filter_map(|path| {
+				SeriesBuilder::new(path, &library_id)
+					.with_filename_patterns(filename_patterns.clone())
+					.build()
+					.map_err(|error| {
+						tracing::error!(?error, ?path, "Failed to build series");
+					})
+					.ok()
+			})
+			.collect::<Vec<Series>>()
+	})
+	.await
+	.map_err(|e| CoreError::Unknown(e.to_string()))?;
+
+	SeriesDAO::new(ctx.db.clone())
+		.create_many(built_series)
+		.await
+}
+
+/// Determines the series directory which a changed path belongs to, if any.
+///
+/// For collection-based libraries, the series is always the top-most directory beneath the
+/// library root. For series-based libraries, the series is the directory itself (when the path
+/// is a known series or an existing directory) or otherwise the parent of the changed file.
+fn resolve_series_path(
+	library_path: &Path,
+	changed_path: &Path,
+	is_collection_based: bool,
+	is_known_series: impl Fn(&Path) -> bool,
+) -> Option<PathBuf> {
+	let relative = changed_path.strip_prefix(library_path).ok()?;
+	let mut components = relative.components();
+	let first_component = components.next();
+
+	if is_collection_based {
+		return match (first_component, components.next()) {
+			(Some(first), Some(_)) => Some(library_path.join(first)),
+			(Some(first), None) => {
+				let candidate = library_path.join(first);
+				if candidate.is_dir() || is_known_series(&candidate) {
+					Some(candidate)
+				} else {
+					Some(library_path.to_path_buf())
+				}
+			},
+			// The library root itself changed, which a library scan is better suited for
+			(None, _) => None,
+		};
+	}
+
+	first_component?;
+	if changed_path.is_dir() || is_known_series(changed_path) {
+		Some(changed_path.to_path_buf())
+	} else {
+		changed_path.parent().map(Path::to_path_buf)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_resolve_series_path_series_based() {
+		let library_path = Path::new("/library");
+
+		assert_eq!(
+			resolve_series_path(
+				library_path,
+				Path::new("/library/Batman/Batman 001.cbz"),
+				false,
+				|_| false
+			),
+			Some(PathBuf::from("/library/Batman"))
+		);
+		assert_eq!(
+			resolve_series_path(
+				library_path,
+				Path::new("/library/Batman/Year One"),
+				false,
+				|p| p == Path::new("/library/Batman/Year One")
+			),
+			Some(PathBuf::from("/library/Batman/Year One"))
+		);
+		assert_eq!(
+			resolve_series_path(library_path, Path::new("/library"), false, |_| false),
+			None
+		);
+		assert_eq!(
+			resolve_series_path(
+				library_path,
+				Path::new("/elsewhere/a.cbz"),
+				false,
+				|_| { false }
+			),
+			None
+		);
+	}
+
+	#[test]
+	fn test_resolve_series_path_collection_based() {
+		let library_path = Path::new("/library");
+
+		assert_eq!(
+			resolve_series_path(
+				library_path,
+				Path::new("/library/Batman/Year One/Batman 001.cbz"),
+				true,
+				|_| false
+			),
+			Some(PathBuf::from("/library/Batman"))
+		);
+		assert_eq!(
+			resolve_series_path(
+				library_path,
+				Path::new("/library/loose-book.cbz"),
+				true,
+				|_| false
+			),
+			Some(PathBuf::from("/library"))
+		);
+		assert_eq!(
+			resolve_series_path(library_path, Path::new("/library/Batman"), true, |p| {
+				p == Path::new("/library/Batman")
+			}),
+			Some(PathBuf::from("/library/Batman"))
+		);
+	}
+
+	#[test]
+	fn test_is_relevant_event() {
+		use notify::event::{AccessKind, CreateKind, MetadataKind, RemoveKind};
+
+		assert!(is_relevant_event(&EventKind::Create(CreateKind::File)));
+		assert!(is_relevant_event(&EventKind::Remove(RemoveKind::Any)));
+		assert!(is_relevant_event(&EventKind::Modify(ModifyKind::Any)));
+		assert!(!is_relevant_event(&EventKind::Modify(
+			ModifyKind::Metadata(MetadataKind::Any)
+		)));
+		assert!(!is_relevant_event(&EventKind::Access(AccessKind::Any)));
+	}
+}
@@ -0,0 +1,138 @@
+use std::{
+	fs,
+	path::{Path, PathBuf},
+};
+
+use walkdir::WalkDir;
+
+use crate::filesystem::FileError;
+
+use super::{StorageBackend, StorageEntry};
+
+/// A [`StorageBackend`] for files on the local disk
+#[derive(Debug, Default, Clone, Copy)]
+pub struct LocalStorage;
+
+impl LocalStorage {
+	fn entry(path: PathBuf, metadata: &fs::Metadata) -> StorageEntry {
+		StorageEntry {
+			path,
+			is_dir: metadata.is_dir(),
+			size: if metadata.is_dir() { 0 } else { metadata.len() },
+			modified_at: metadata.modified().ok(),
+		}
+	}
+}
+
+impl StorageBackend for LocalStorage {
+	fn exists(&self, path: &Path) -> Result<bool, FileError> {
+		Ok(path.exists())
+	}
+
+	fn metadata(&self, path: &Path) -> Result<StorageEntry, FileError> {
+		let metadata = fs::metadata(path)?;
+		Ok(Self::entry(path.to_path_buf(), &metadata))
+	}
+
+	fn list(
+		&self,
+		dir: &Path,
+		max_depth: Option<usize>,
+	) -> Result<Vec<StorageEntry>, FileError> {
+		if !dir.is_dir() {
+			return Err(FileError::DirectoryReadError);
+		}
+
+		let mut walker = WalkDir::new(dir).min_depth(1);
+		if let Some(depth) = max_depth {
+			walker = walker.max_depth(depth);
+		}
+
+		Ok(walker
+			.into_iter()
+			.filter_map(Result::ok)
+			.filter_map(|entry| {
+				let metadata = entry.metadata().ok()?;
+				Some(Self::entry(entry.into_path(), &metadata))
+			})
+			.collect())
+	}
+
+	fn read(&self, path: &Path) -> Result<Vec<u8>, FileError> {
+		Ok(fs::read(path)?)
+	}
+
+	fn write(&self, path: &Path, content: &[u8]) -> Result<(), FileError> {
+		if let Some(parent) = path.parent() {
+			fs::create_dir_all(parent)?;
+		}
+		Ok(fs::write(path, content)?)
+	}
+
+	fn upload(&self, source: &Path, path: &Path) -> Result<(), FileError> {
+		if let Some(parent) = path.parent() {
+			fs::create_dir_all(parent)?;
+		}
+		fs::copy(source, path)?;
+		Ok(())
+	}
+
+	fn delete(&self, path: &Path) -> Result<(), FileError> {
+		Ok(fs::remove_file(path)?)
+	}
+
+	fn local_path(&self, path: &Path) -> Result<PathBuf, FileError> {
+		Ok(path.to_path_buf())
+	}
+
+	fn is_local(&self) -> bool {
+		true
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_list_respects_max_depth() {
+		let dir = tempfile::tempdir().unwrap();
+		let storage = LocalStorage;
+		storage
+			.write(&dir.path().join("Batman/Batman 001.cbz"), b"one")
+			.unwrap();
+		storage
+			.write(&dir.path().join("Batman/Annuals/Annual 001.cbz"), b"two")
+			.unwrap();
+
+		let shallow = storage.list(&dir.path().join("Batman"), Some(1)).unwrap();
+		assert_eq!(shallow.len(), 2);
+		assert!(shallow
+			.iter()
+			.any(|e| e.is_dir && e.path == dir.path().join("Batman/Annuals")));
+
+		let deep = storage.list(dir.path(), None).unwrap();
+		assert_eq!(deep.iter().filter(|e| !e.is_dir).count(), 2);
+
+		let file = storage
+			.metadata(&dir.path().join("Batman/Batman 001.cbz"))
+			.unwrap();
+		assert_eq!(file.size, 3);
+		assert!(!file.is_dir);
+	}
+
+	#[test]
+	fn test_upload_and_delete() {
+		let dir = tempfile::tempdir().unwrap();
+		let storage = LocalStorage;
+		let source = dir.path().join("source.cbz");
+		fs::write(&source, b"content").unwrap();
+
+		let destination = dir.path().join("nested/destination.cbz");
+		storage.upload(&source, &destination).unwrap();
+		assert_eq!(storage.read(&destination).unwrap(), b"content");
+
+		storage.delete(&destination).unwrap();
+		assert!(!storage.exists(&destination).unwrap());
+	}
+}
@@ -0,0 +1,110 @@
+//! Storage backends which abstract over where the files of a library live. Library paths are
+//! local paths by default, and paths with the `s3://bucket/prefix` scheme are served from an
+//! S3-compatible object store.
+
+mod local;
+mod s3;
+
+use std::{
+	fmt::Debug,
+	path::{Path, PathBuf},
+	sync::Arc,
+	time::SystemTime,
+};
+
+use crate::config::StumpConfig;
+
+use super::FileError;
+
+pub use local::LocalStorage;
+pub use s3::S3Storage;
+
+/// The scheme used to denote paths which live in an S3-compatible object store
+pub const S3_SCHEME: &str = "s3://";
+
+/// A file or directory within a [`StorageBackend`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StorageEntry {
+	/// The full path of the entry, including the scheme for object storage
+	pub path: PathBuf,
+	/// Whether the entry is a directory. Object stores don't have real directories, so these
+	/// are inferred from the keys of the objects within them
+	pub is_dir: bool,
+	/// The size of the entry in bytes. Always 0 for directories
+	pub size: u64,
+	/// When the entry was last modified, if known
+	pub modified_at: Option<SystemTime>,
+}
+
+/// A blocking API for accessing the files of a library, regardless of where they are stored.
+/// Implementations are expected to be cheap to construct.
+pub trait StorageBackend: Send + Sync + Debug {
+	/// Returns true if a file or directory exists at the path
+	fn exists(&self, path: &Path) -> Result<bool, FileError>;
+
+	/// Returns the [`StorageEntry`] for the file or directory at the path
+	fn metadata(&self, path: &Path) -> Result<StorageEntry, FileError>;
+
+	/// Lists the entries beneath a directory, excluding the directory itself. A `max_depth` of 1
+	/// only includes the direct children of the directory
+	fn list(
+		&self,
+		dir: &Path,
+		max_depth: Option<usize>,
+	) -> Result<Vec<StorageEntry>, FileError>;
+
+	/// Reads the entire contents of a file
+	fn read(&self, path: &Path) -> Result<Vec<u8>, FileError>;
+
+	/// Writes the contents of a file, creating any missing parent directories
+	fn write(&self, path: &Path, content: &[u8]) -> Result<(), FileError>;
+
+	/// Copies a file from the local disk into the storage at `path`
+	fn upload(&self, source: &Path, path: &Path) -> Result<(), FileError>;
+
+	/// Deletes a file
+	fn delete(&self, path: &Path) -> Result<(), FileError>;
+
+	/// Returns a path on the local disk which holds the contents of the file. For remote
+	/// backends, the file is downloaded into the cache directory when it is missing or stale.
+	fn local_path(&self, path: &Path) -> Result<PathBuf, FileError>;
+
+	/// Whether the paths of this backend are paths on the local disk
+	fn is_local(&self) -> bool;
+}
+
+/// Returns true if the path points into an S3-compatible object store
+pub fn is_object_storage_path(path: &Path) -> bool {
+	path.to_string_lossy().starts_with(S3_SCHEME)
+}
+
+/// Returns the [`StorageBackend`] responsible for the given path
+pub fn storage_for_path(
+	path: &Path,
+	config: &StumpConfig,
+) -> Result<Arc<dyn StorageBackend>, FileError> {
+	if is_object_storage_path(path) {
+		Ok(Arc::new(S3Storage::for_path(path, config)?))
+	} else {
+		Ok(Arc::new(LocalStorage))
+	}
+}
+
+/// Returns true if the path exists in its backend, treating any error as the path not existing
+pub fn path_exists(path: &Path, config: &StumpConfig) -> bool {
+	storage_for_path(path, config)
+		.and_then(|storage| storage.exists(path))
+		.unwrap_or_else(|error| {
+			tracing::error!(?error, ?path, "Failed to check if path exists");
+			false
+		})
+}
+
+/// Returns a path on the local disk which holds the contents of the file at the given path.
+/// Local paths are returned as-is.
+pub fn resolve_local_path(
+	path: &Path,
+	config: &StumpConfig,
+) -> Result<PathBuf, FileError> {
+	storage_for_path(path, config)?.local_path(path)
+}
@@ -0,0 +1,445 @@
+use std::{
+	collections::HashSet,
+	fs,
+	path::{Component, Path, PathBuf},
+	time::{Duration, SystemTime},
+};
+
+use prisma_client_rust::chrono::{DateTime, Utc};
+use s3::{creds::Credentials, error::S3Error, serde_types::Object, Bucket, Region};
+
+use crate::{config::StumpConfig, filesystem::FileError};
+
+use super::{StorageBackend, StorageEntry, S3_SCHEME};
+
+/// How long a freshly downloaded object is trusted without checking the store for changes,
+/// so that reading the same book repeatedly (e.g. page by page) doesn't make a request each time
+const CACHE_TTL: Duration = Duration::from_secs(60);
+
+/// A [`StorageBackend`] for an S3-compatible object store, configured using the `s3_*` options
+/// of the [`StumpConfig`]. Paths take the form `s3://bucket/key`.
+///
+/// Files are processed from the local disk, so objects are downloaded into the cache directory
+/// on demand (see [`StorageBackend::local_path`]) and re-downloaded when they change.
+#[derive(Debug)]
+pub struct S3Storage {
+	bucket: Box<Bucket>,
+	cache_dir: PathBuf,
+}
+
+/// Splits an `s3://bucket/key` path into its bucket and key. The key of the bucket itself is
+/// an empty string.
+pub(crate) fn parse_s3_path(path: &Path) -> Option<(String, String)> {
+	let path = path.to_string_lossy();
+	let location = path.strip_prefix(S3_SCHEME)?;
+	let (bucket, key) = location.split_once('/').unwrap_or((location, ""));
+	if bucket.is_empty() {
+		return None;
+	}
+	Some((bucket.to_string(), key.trim_matches('/').to_string()))
+}
+
+/// Whether the key can be safely joined onto the cache directory, i.e. it only contains
+/// normal path segments and can't escape the directory (e.g. `../../etc/passwd`)
+fn is_safe_cache_key(key: &str) -> bool {
+	!key.is_empty()
+		&& Path::new(key)
+			.components()
+			.all(|component| matches!(component, Component::Normal(_)))
+}
+
+fn storage_error(error: S3Error) -> FileError {
+	FileError::ObjectStorageError(error.to_string())
+}
+
+fn is_not_found(error: &S3Error) -> bool {
+	matches!(error, S3Error::HttpFailWithBody(404, _))
+}
+
+impl S3Storage {
+	/// Create the backend for the bucket referenced by the given `s3://` path
+	pub fn for_path(path: &Path, config: &StumpConfig) -> Result<Self, FileError> {
+		let (bucket_name, _) = parse_s3_path(path).ok_or_else(|| {
+			FileError::ObjectStorageError(format!(
+				"{} is not a valid object storage path",
+				path.display()
+			))
+		})?;
+
+		let region = match config.s3_endpoint.as_ref() {
+			Some(endpoint) => Region::Custom {
+				region: config.s3_region.clone(),
+				endpoint: endpoint.clone(),
+			},
+			None => config
+				.s3_region
+				.parse::<Region>()
+				.map_err(|e| FileError::ObjectStorageError(e.to_string()))?,
+		};
+		let credentials = Credentials::new(
+			config.s3_access_key_id.as_deref(),
+			config.s3_secret_access_key.as_deref(),
+			None,
+			None,
+			None,
+		)
+		.map_err(|e| FileError::ObjectStorageError(e.to_string()))?;
+
+		let mut bucket =
+			Bucket::new(&bucket_name, region, credentials).map_err(storage_error)?;
+		// Self-hosted stores such as MinIO generally don't support virtual-hosted buckets
+		if config.s3_endpoint.is_some() {
+			bucket = bucket.with_path_style();
+		}
+
+		Ok(Self {
+			bucket,
+			cache_dir: config.get_cache_dir().join("storage").join(bucket_name),
+		})
+	}
+
+	fn key(&self, path: &Path) -> Result<String, FileError> {
+		parse_s3_path(path)
+			.filter(|(bucket, _)| *bucket == self.bucket.name())
+			.map(|(_, key)| key)
+			.ok_or_else(|| {
+				FileError::ObjectStorageError(format!(
+					"{} is not within the bucket {}",
+					path.display(),
+					self.bucket.name()
+				))
+			})
+	}
+
+	fn path_for_key(&self, key: &str) -> PathBuf {
+		PathBuf::from(format!("{S3_SCHEME}{}/{key}", self.bucket.name()))
+	}
+
+	/// The prefix which all keys beneath the given key start with
+	fn dir_prefix(key: &str) -> String {
+		if key.is_empty() {
+			String::new()
+		} else {
+			format!("{key}/")
+		}
+	}
+
+	fn dir_entry(&self, key: &str) -> StorageEntry {
+		StorageEntry {
+			path: self.path_for_key(key),
+			is_dir: true,
+			size: 0,
+			modified_at: None,
+		}
+	}
+
+	fn file_entry(&self, object: &Object) -> StorageEntry {
+		StorageEntry {
+			path: self.path_for_key(&object.key),
+			is_dir: false,
+			size: object.size,
+			modified_at: parse_modified_at(&object.last_modified),
+		}
+	}
+
+	/// Lists every object beneath the prefix, inferring the directories from their keys
+	fn list_all(&self, prefix: String) -> Result<Vec<StorageEntry>, FileError> {
+		let results = self
+			.bucket
+			.list(prefix.clone(), None)
+			.map_err(storage_error)?;
+
+		let mut directories = HashSet::new();
+		let mut entries = Vec::new();
+		for object in results.iter().flat_map(|result| &result.contents) {
+			let Some(relative) = object.key.strip_prefix(&prefix) else {
+				continue;
+			};
+			// Keys ending in a slash are placeholders some tools create for empty folders
+			let relative = relative.trim_end_matches('/');
+			if relative.is_empty() {
+				continue;
+			}
+
+			let segments = relative.split('/').collect::<Vec<_>>();
+			let is_placeholder = object.key.ends_with('/');
+
+			// Every ancestor between the listed directory and the object is a directory
+			let ancestors = if is_placeholder {
+				segments.len()
+			} else {
+				segments.len() - 1
+			};
+			for i in 1..=ancestors {
+				let directory = segments[..i].join("/");
+				if directories.insert(directory.clone()) {
+					entries.push(self.dir_entry(&format!("{prefix}{directory}")));
+				}
+			}
+
+			if !is_placeholder {
+				entries.push(self.file_entry(object));
+			}
+		}
+
+		Ok(entries)
+	}
+
+	/// Lists the objects beneath the prefix one level at a time, using a delimiter so that the
+	/// store only returns the objects (and the directories) directly within each level. This
+	/// avoids fetching every object beneath the prefix when only the first few levels are needed
+	fn list_to_depth(
+		&self,
+		prefix: String,
+		max_depth: usize,
+	) -> Result<Vec<StorageEntry>, FileError> {
+		let mut entries = Vec::new();
+		let mut level = vec![prefix];
+		for _ in 0..max_depth {
+			let mut next_level = Vec::new();
+			for prefix in level {
+				let results = self
+					.bucket
+					.list(prefix.clone(), Some("/".to_string()))
+					.map_err(storage_error)?;
+				for result in results {
+					for common_prefix in result.common_prefixes.unwrap_or_default() {
+						entries.push(
+							self.dir_entry(common_prefix.prefix.trim_end_matches('/')),
+						);
+						next_level.push(common_prefix.prefix);
+					}
+					// The placeholder some tools create for an empty folder is listed within
+					// the folder itself, and is skipped since the folder was already listed
+					entries.extend(
+						result
+							.contents
+							.iter()
+							.filter(|object| !object.key.ends_with('/'))
+							.map(|object| self.file_entry(object)),
+					);
+				}
+			}
+			level = next_level;
+		}
+
+		Ok(entries)
+	}
+}
+
+fn parse_modified_at(value: &str) -> Option<SystemTime> {
+	DateTime::parse_from_rfc3339(value)
+		.or_else(|_| DateTime::parse_from_rfc2822(value))
+		.ok()
+		.map(|dt| dt.with_timezone(&Utc).into())
+}
+
+impl StorageBackend for S3Storage {
+	fn exists(&self, path: &Path) -> Result<bool, FileError> {
+		let key = self.key(path)?;
+		if !key.is_empty() {
+			match self.bucket.head_object(&key) {
+				Ok(_) => return Ok(true),
+				Err(e) if is_not_found(&e) => {},
+				Err(e) => return Err(storage_error(e)),
+			}
+		}
+
+		// There are no real directories in an object store, so a directory exists as long as
+		// there is at least one object beneath it
+		let (page, _) = self
+			.bucket
+			.list_page(Self::dir_prefix(&key), None, None, None, Some(1))
+			.map_err(storage_error)?;
+		Ok(!page.contents.is_empty())
+	}
+
+	fn metadata(&self, path: &Path) -> Result<StorageEntry, FileError> {
+		let key = self.key(path)?;
+		match self.bucket.head_object(&key) {
+			Ok((head, _)) => Ok(StorageEntry {
+				path: path.to_path_buf(),
+				is_dir: false,
+				size: head.content_length.unwrap_or_default().max(0) as u64,
+				modified_at: head.last_modified.as_deref().and_then(parse_modified_at),
+			}),
+			Err(e) if is_not_found(&e) && self.exists(path)? => Ok(StorageEntry {
+				path: path.to_path_buf(),
+				is_dir: true,
+				size: 0,
+				modified_at: None,
+			}),
+			Err(e) => Err(storage_error(e)),
+		}
+	}
+
+	fn list(
+		&self,
+		dir: &Path,
+		max_depth: Option<usize>,
+	) -> Result<Vec<StorageEntry>, FileError> {
+		let prefix = Self::dir_prefix(&self.key(dir)?);
+		match max_depth {
+			Some(max_depth) => self.list_to_depth(prefix, max_depth),
+			None => self.list_all(prefix),
+		}
+	}
+
+	fn read(&self, path: &Path) -> Result<Vec<u8>, FileError> {
+		let response = self
+			.bucket
+			.get_object(self.key(path)?)
+			.map_err(storage_error)?;
+		Ok(response.bytes().to_vec())
+	}
+
+	fn write(&self, path: &Path, content: &[u8]) -> Result<(), FileError> {
+		self.bucket
+			.put_object(self.key(path)?, content)
+			.map_err(storage_error)?;
+		Ok(())
+	}
+
+	fn upload(&self, source: &Path, path: &Path) -> Result<(), FileError> {
+		let mut file = fs::File::open(source)?;
+		self.bucket
+			.put_object_stream(&mut file, self.key(path)?)
+			.map_err(storage_error)?;
+		Ok(())
+	}
+
+	fn delete(&self, path: &Path) -> Result<(), FileError> {
+		self.bucket
+			.delete_object(self.key(path)?)
+			.map_err(storage_error)?;
+		Ok(())
+	}
+
+	fn local_path(&self, path: &Path) -> Result<PathBuf, FileError> {
+		let key = self.key(path)?;
+		if !is_safe_cache_key(&key) {
+			return Err(FileError::ObjectStorageError(format!(
+				"{} cannot be cached locally",
+				path.display()
+			)));
+		}
+		let cached_path = self.cache_dir.join(&key);
+
+		let recently_downloaded = fs::metadata(&cached_path)
+			.and_then(|local| local.modified())
+			.is_ok_and(|modified| {
+				modified.elapsed().is_ok_and(|elapsed| elapsed < CACHE_TTL)
+			});
+		if recently_downloaded {
+			return Ok(cached_path);
+		}
+
+		let remote = self.metadata(path)?;
+
+		let is_fresh = fs::metadata(&cached_path).is_ok_and(|local| {
+			let downloaded_after_change = match (local.modified(), remote.modified_at) {
+				(Ok(local_modified), Some(remote_modified)) => {
+					local_modified >= remote_modified
+				},
+				_ => false,
+			};
+			local.len() == remote.size && downloaded_after_change
+		});
+		if is_fresh {
+			return Ok(cached_path);
+		}
+
+		tracing::debug!(?path, ?cached_path, "Downloading object into the cache");
+		if let Some(parent) = cached_path.parent() {
+			fs::create_dir_all(parent)?;
+		}
+		// Download to a temporary file first, so concurrent readers never see a partial file
+		let partial_path =
+			cached_path.with_extension(format!("{}.part", uuid::Uuid::new_v4()));
+		let mut file = fs::File::create(&partial_path)?;
+		let result = self.bucket.get_object_to_writer(&key, &mut file);
+		drop(file);
+		if let Err(e) = result {
+			let _ = fs::remove_file(&partial_path);
+			return Err(storage_error(e));
+		}
+		fs::rename(&partial_path, &cached_path)?;
+
+		Ok(cached_path)
+	}
+
+	fn is_local(&self) -> bool {
+		false
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_parse_s3_path() {
+		assert_eq!(
+			parse_s3_path(Path::new("s3://comics/Batman/Batman 001.cbz")),
+			Some(("comics".to_string(), "Batman/Batman 001.cbz".to_string()))
+		);
+		assert_eq!(
+			parse_s3_path(&Path::new("s3://comics").join("Batman")),
+			Some(("comics".to_string(), "Batman".to_string()))
+		);
+		assert_eq!(
+			parse_s3_path(Path::new("s3://comics/")),
+			Some(("comics".to_string(), String::new()))
+		);
+		assert_eq!(parse_s3_path(Path::new("/data/comics")), None);
+		assert_eq!(parse_s3_path(Path::new("s3://")), None);
+	}
+
+	#[test]
+	fn test_is_safe_cache_key() {
+		assert!(is_safe_cache_key("Batman/Batman 001.cbz"));
+		assert!(!is_safe_cache_key(""));
+		assert!(!is_safe_cache_key("../Batman 001.cbz"));
+		assert!(!is_safe_cache_key("Batman/../../Batman 001.cbz"));
+		assert!(!is_safe_cache_key("/etc/passwd"));
+		assert!(!is_safe_cache_key("./Batman 001.cbz"));
+	}
+
+	#[test]
+	fn test_parse_modified_at() {
+		assert!(parse_modified_at("2024-01-02T03:04:05.000Z").is_some());
+		assert!(parse_modified_at("Tue, 02 Jan 2024 03:04:05 GMT").is_some());
+		assert!(parse_modified_at("yesterday").is_none());
+	}
+
+	/// Runs against a local S3-compatible store, e.g.
+	/// `docker run -p 9000:9000 minio/minio server /data`, with a bucket named by
+	/// `STUMP_TEST_S3_BUCKET` and credentials in the usual `STUMP_S3_*` variables
+	#[test]
+	#[ignore]
+	fn test_round_trip_against_local_store() {
+		let bucket = std::env::var("STUMP_TEST_S3_BUCKET").unwrap();
+		let config_dir = tempfile::tempdir().unwrap();
+		let config = StumpConfig::new(config_dir.path().to_string_lossy().to_string())
+			.with_environment()
+			.unwrap();
+
+		let root = PathBuf::from(format!("{S3_SCHEME}{bucket}/stump-test"));
+		let storage = S3Storage::for_path(&root, &config).unwrap();
+		let book = root.join("Batman/Batman 001.cbz");
+		storage.write(&book, b"content").unwrap();
+
+		assert!(storage.exists(&root.join("Batman")).unwrap());
+		assert!(storage.metadata(&root.join("Batman")).unwrap().is_dir);
+		let entries = storage.list(&root, Some(1)).unwrap();
+		assert_eq!(entries.len(), 1);
+		assert!(entries[0].is_dir);
+		assert_eq!(storage.list(&root, None).unwrap().len(), 2);
+
+		let local = storage.local_path(&book).unwrap();
+		assert_eq!(fs::read(local).unwrap(), b"content");
+
+		storage.delete(&book).unwrap();
+		assert!(!storage.exists(&book).unwrap());
+	}
+}
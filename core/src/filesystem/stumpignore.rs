@@ -0,0 +1,233 @@
+use std::{
+	collections::HashMap,
+	path::{Path, PathBuf},
+	sync::{Arc, RwLock},
+};
+
+use globset::GlobSet;
+use ignore::{
+	gitignore::{Gitignore, GitignoreBuilder},
+	Match,
+};
+
+/// The name of the files which may be placed anywhere within a library to exclude files
+/// and directories from scans, using gitignore syntax
+pub const STUMPIGNORE_FILENAME: &str = ".stumpignore";
+
+/// Determines whether paths within a library should be ignored during a scan. This combines the
+/// library-level ignore rules (see [crate::db::entity::IgnoreRules]) with any `.stumpignore` files
+/// found in the library, which follow gitignore semantics:
+///
+/// - Patterns are relative to the directory containing the `.stumpignore` file
+/// - Patterns may be negated with `!` to re-include something a parent file excluded
+/// - The `.stumpignore` closest to a path takes precedence over those further up the tree
+/// - Nothing within an ignored directory can be re-included
+///
+/// A library with multiple roots considers the `.stumpignore` files beneath whichever root
+/// contains the path. The parsed `.stumpignore` files are cached, so a single matcher should be
+/// reused for the duration of a walk.
+#[derive(Debug)]
+pub struct IgnoreMatcher {
+	roots: Vec<PathBuf>,
+	rules: GlobSet,
+	stumpignores: RwLock<HashMap<PathBuf, Option<Arc<Gitignore>>>>,
+}
+
+impl IgnoreMatcher {
+	/// Create a new matcher for the library at `root`, using the library-level `rules`
+	pub fn new(root: impl AsRef<Path>, rules: GlobSet) -> Self {
+		Self::with_roots([root], rules)
+	}
+
+	/// Create a new matcher for a library with multiple `roots`, using the library-level `rules`
+	pub fn with_roots(
+		roots: impl IntoIterator<Item = impl AsRef<Path>>,
+		rules: GlobSet,
+	) -> Self {
+		Self {
+			roots: roots
+				.into_iter()
+				.map(|root| root.as_ref().to_path_buf())
+				.collect(),
+			rules,
+			stumpignores: RwLock::new(HashMap::new()),
+		}
+	}
+
+	/// Returns true if the path should be ignored, either because it matches the library-level
+	/// rules or because it is excluded by a `.stumpignore` file
+	pub fn is_ignored(&self, path: &Path, is_dir: bool) -> bool {
+		self.rules.is_match(path) || self.is_excluded(path, is_dir)
+	}
+
+	/// Returns true if the path, or any directory between the root and the path, is excluded
+	/// by a `.stumpignore` file
+	fn is_excluded(&self, path: &Path, is_dir: bool) -> bool {
+		// When roots are nested, the deepest one containing the path applies
+		let Some((root, relative)) = self
+			.roots
+			.iter()
+			.filter_map(|root| path.strip_prefix(root).ok().map(|rel| (root, rel)))
+			.max_by_key(|(root, _)| root.components().count())
+		else {
+			return false;
+		};
+
+		let mut current = root.clone();
+		let mut components = relative.components().peekable();
+		while let Some(component) = components.next() {
+			current.push(component);
+			let is_last = components.peek().is_none();
+			if self.matched(root, &current, !is_last || is_dir).is_ignore() {
+				return true;
+			}
+		}
+
+		false
+	}
+
+	/// Checks the path against the `.stumpignore` files of each of its ancestors (up to the
+	/// root), starting with the closest. The first file with a matching pattern decides.
+	fn matched(&self, root: &Path, path: &Path, is_dir: bool) -> Match<()> {
+		for dir in path.ancestors().skip(1) {
+			if let Some(gitignore) = self.stumpignore(dir) {
+				match gitignore.matched(path, is_dir) {
+					Match::None => {},
+					Match::Ignore(_) => return Match::Ignore(()),
+					Match::Whitelist(_) => return Match::Whitelist(()),
+				}
+			}
+
+			if dir == root {
+				break;
+			}
+		}
+
+		Match::None
+	}
+
+	fn stumpignore(&self, dir: &Path) -> Option<Arc<Gitignore>> {
+		if let Some(cached) = self
+			.stumpignores
+			.read()
+			.ok()
+			.and_then(|cache| cache.get(dir).cloned())
+		{
+			return cached;
+		}
+
+		let loaded = load_stumpignore(dir).map(Arc::new);
+		if let Ok(mut cache) = self.stumpignores.write() {
+			cache.insert(dir.to_path_buf(), loaded.clone());
+		}
+		loaded
+	}
+}
+
+fn load_stumpignore(dir: &Path) -> Option<Gitignore> {
+	let path = dir.join(STUMPIGNORE_FILENAME);
+	if !path.is_file() {
+		return None;
+	}
+
+	let mut builder = GitignoreBuilder::new(dir);
+	if let Some(error) = builder.add(&path) {
+		tracing::warn!(?error, ?path, "Encountered invalid lines in ignore file");
+	}
+
+	builder.build().map_or_else(
+		|error| {
+			tracing::error!(?error, ?path, "Failed to parse ignore file");
+			None
+		},
+		Some,
+	)
+}
+
+#[cfg(test)]
+mod tests {
+	use std::fs;
+
+	use globset::{Glob, GlobSetBuilder};
+
+	use super::*;
+
+	fn setup() -> tempfile::TempDir {
+		let root = tempfile::tempdir().unwrap();
+		for dir in ["Series A/Extras", "Series A/Notes", "Series B/Scans"] {
+			fs::create_dir_all(root.path().join(dir)).unwrap();
+		}
+		fs::write(root.path().join(STUMPIGNORE_FILENAME), "Extras/\n*.txt\n").unwrap();
+		fs::write(
+			root.path().join("Series A").join(STUMPIGNORE_FILENAME),
+			"!credits.txt\n/Notes\n",
+		)
+		.unwrap();
+		fs::write(
+			root.path()
+				.join("Series B/Scans")
+				.join(STUMPIGNORE_FILENAME),
+			"*.cbz\n!keep.cbz\n",
+		)
+		.unwrap();
+		root
+	}
+
+	#[test]
+	fn test_nested_precedence() {
+		let root = setup();
+		let matcher = IgnoreMatcher::new(root.path(), GlobSet::empty());
+		let path = |p: &str| root.path().join(p);
+
+		// Excluded by the root file, anywhere in the tree
+		assert!(matcher.is_ignored(&path("Series A/Extras"), true));
+		assert!(matcher.is_ignored(&path("Series B/readme.txt"), false));
+		// Re-included by the closer file
+		assert!(!matcher.is_ignored(&path("Series A/credits.txt"), false));
+		// Anchored to the directory of the file which declares it
+		assert!(matcher.is_ignored(&path("Series A/Notes"), true));
+		assert!(!matcher.is_ignored(&path("Series B/Notes"), true));
+		assert!(matcher.is_ignored(&path("Series B/Scans/001.cbz"), false));
+		assert!(!matcher.is_ignored(&path("Series B/Scans/keep.cbz"), false));
+		assert!(!matcher.is_ignored(&path("Series B/001.cbz"), false));
+	}
+
+	#[test]
+	fn test_ignored_directory_contents_are_ignored() {
+		let root = setup();
+		let matcher = IgnoreMatcher::new(root.path(), GlobSet::empty());
+
+		assert!(matcher.is_ignored(&root.path().join("Series A/Extras/001.cbz"), false));
+		assert!(
+			matcher.is_ignored(&root.path().join("Series A/Notes/credits.txt"), false)
+		);
+	}
+
+	#[test]
+	fn test_library_rules_still_apply() {
+		let root = setup();
+		let rules = GlobSetBuilder::new()
+			.add(Glob::new("**/*.epub").unwrap())
+			.build()
+			.unwrap();
+		let matcher = IgnoreMatcher::new(root.path(), rules);
+
+		assert!(matcher.is_ignored(&root.path().join("Series B/book.epub"), false));
+		assert!(!matcher.is_ignored(&root.path().join("Series B/book.cbz"), false));
+	}
+
+	#[test]
+	fn test_stumpignores_apply_per_root() {
+		let root = setup();
+		let other = tempfile::tempdir().unwrap();
+		fs::create_dir_all(other.path().join("Series A")).unwrap();
+		fs::write(other.path().join(STUMPIGNORE_FILENAME), "*.cbr\n").unwrap();
+		let matcher =
+			IgnoreMatcher::with_roots([root.path(), other.path()], GlobSet::empty());
+
+		assert!(matcher.is_ignored(&other.path().join("Series A/001.cbr"), false));
+		assert!(!matcher.is_ignored(&other.path().join("Series A/readme.txt"), false));
+		assert!(matcher.is_ignored(&root.path().join("Series A/readme.txt"), false));
+		assert!(!matcher.is_ignored(&root.path().join("Series A/001.cbr"), false));
+	}
+}
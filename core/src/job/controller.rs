@@ -6,7 +6,9 @@ use tokio::sync::{
 	oneshot,
 };
 
-use super::{Executor, JobManager, JobManagerResult, WorkerSend, WorkerSendExt};
+use super::{
+	Executor, JobManager, JobManagerResult, QueuedJob, WorkerSend, WorkerSendExt,
+};
 use crate::{config::StumpConfig, event::CoreEvent, prisma::PrismaClient};
 
 /// Input for commands that require an acknowledgement when they are completed
@@ -88,7 +90,7 @@ impl JobController {
 						);
 					},
 					JobControllerCommand::CompleteJob(id) => {
-						self.manager.clone().complete(id).await;
+						self.manager.clone().complete(id, vec![]).await;
 					},
 					JobControllerCommand::CancelJob(cmd) => {
 						let result = self.manager.clone().cancel(cmd.id).await;
@@ -142,6 +144,11 @@ impl JobController {
 		});
 	}
 
+	/// Get the jobs which are waiting in the queue, in the order they will be started
+	pub async fn get_queued_jobs(&self) -> Vec<QueuedJob> {
+		self.manager.get_queued_jobs().await
+	}
+
 	/// Pushes a command to the main watcher loop
 	pub fn push_command(
 		&self,
@@ -12,6 +12,8 @@ pub enum JobError {
 	StateSaveFailed(String),
 	#[error("Job was cancelled")]
 	Cancelled(oneshot::Sender<()>),
+	#[error("Job was interrupted")]
+	Interrupted(oneshot::Sender<()>),
 	#[error("A task experienced a critical error while executing: {0}")]
 	TaskFailed(String),
 	#[error("A query error occurred: {0}")]
@@ -0,0 +1,29 @@
+use integrations::NotifierEvent;
+
+use crate::{db::entity::send_notifier_event, event::CoreEvent};
+
+use super::WorkerCtx;
+
+/// Publish the outcome of a job once it has resolved. The event is sent to every notifier
+/// (including webhooks) which is subscribed to the job, and failures are also sent to the
+/// event stream so that clients can surface them.
+///
+/// Notifiers are sent to in the background, so that a slow or unreachable notifier doesn't
+/// hold up the completion of the job.
+pub(crate) fn publish_job_outcome(
+	ctx: &WorkerCtx,
+	job_name: &'static str,
+	event: NotifierEvent,
+) {
+	if matches!(event, NotifierEvent::JobFailed { .. }) {
+		ctx.send_core_event(CoreEvent::JobFailed {
+			id: ctx.job_id.clone(),
+			message: event.clone().into_message(),
+		});
+	}
+
+	let db = ctx.db.clone();
+	tokio::spawn(async move {
+		send_notifier_event(&db, job_name, event).await;
+	});
+}
@@ -1,30 +1,60 @@
 use std::{
-	collections::{HashMap, VecDeque},
+	collections::{HashMap, HashSet, VecDeque},
 	sync::Arc,
 	time::Duration,
 };
 
 use futures::future::join_all;
+use prisma_client_rust::{and, chrono::Utc, or};
+use serde::{Deserialize, Serialize};
+use specta::Type;
 use tokio::sync::{broadcast, mpsc, RwLock};
+use utoipa::ToSchema;
+use uuid::Uuid;
 
 use super::{
-	error::JobManagerError, handle_do_cancel, Executor, JobControllerCommand, Worker,
+	error::{JobError, JobManagerError},
+	handle_cancel_pending_children, handle_do_cancel, Executor, JobControllerCommand,
+	JobExt, JobPriority, Worker, WrappedJob,
 };
 use crate::{
 	config::StumpConfig,
+	db::{DatabaseMaintenanceJob, SessionCleanupJob},
 	event::CoreEvent,
+	filesystem::{
+		image::ThumbnailGenerationJob,
+		media::analyze_media_job::AnalyzeMediaJob,
+		scanner::{LibraryScanJob, ScanDryRunJob, SeriesScanJob},
+	},
 	job::JobStatus,
+	metadata::{
+		ComicVineMatchJob, IsbnEnrichmentJob, MediaCreditsSyncJob, MetadataImportJob,
+	},
 	prisma::{job, PrismaClient},
 };
 
 pub type JobManagerResult<T> = Result<T, JobManagerError>;
 
+/// A job which is waiting in the queue for a worker to become available
+#[derive(Clone, Debug, Serialize, Deserialize, Type, ToSchema)]
+pub struct QueuedJob {
+	pub id: String,
+	pub name: String,
+	pub description: Option<String>,
+	pub priority: JobPriority,
+	/// The position of the job in the queue, where 1 is the next job to be started
+	pub position: usize,
+}
+
 /// A helper struct that holds the job queue and a list of [`Worker`]s.
 pub struct JobManager {
-	/// Queue of jobs waiting to be run in a worker thread
+	/// Queue of jobs waiting to be run in a worker thread, ordered by priority
 	queue: RwLock<VecDeque<Box<dyn Executor>>>,
 	/// Worker threads with a running job
 	workers: RwLock<HashMap<String, Arc<Worker>>>,
+	/// Jobs which failed and are waiting for their retry delay to pass before they are
+	/// enqueued again
+	retries: RwLock<HashMap<String, Box<dyn Executor>>>,
 	/// A channel to send shutdown signals to the parent [`JobManager`]
 	job_controller_tx: mpsc::UnboundedSender<JobControllerCommand>,
 	/// A channel to emit core events
@@ -46,6 +76,7 @@ impl JobManager {
 		Self {
 			queue: RwLock::new(VecDeque::new()),
 			workers: RwLock::new(HashMap::new()),
+			retries: RwLock::new(HashMap::new()),
 			job_controller_tx,
 			core_event_tx,
 			client,
@@ -63,40 +94,78 @@ impl JobManager {
 		self.core_event_tx.clone()
 	}
 
-	/// Initialize the job manager. This will attempt to cancel any islanded jobs and re-enqueue
-	/// any paused jobs
+	/// Initialize the job manager. This will re-enqueue any jobs which were paused or interrupted
+	/// by a shutdown, so they continue from their restore point, along with any jobs which were
+	/// still queued during the shutdown. Any other islanded jobs (including those which were
+	/// waiting to be retried) are cancelled
 	pub async fn initialize(self: Arc<Self>) -> JobManagerResult<()> {
-		// Find islanded jobs and attempt to cancel them
-		let islanded_jobs = self
+		let unresolved_jobs = self
 			.client
 			.job()
-			.find_many(vec![job::status::equals(JobStatus::Running.to_string())])
+			.find_many(vec![or![
+				job::status::in_vec(vec![
+					JobStatus::Running.to_string(),
+					JobStatus::Paused.to_string(),
+				]),
+				and![
+					job::status::equals(JobStatus::Queued.to_string()),
+					or![job::next_retry_at::not(None), job::save_state::not(None)],
+				],
+			]])
 			.exec()
 			.await?;
 
-		tracing::debug!(?islanded_jobs, "Found islanded jobs");
+		tracing::debug!(?unresolved_jobs, "Found unresolved jobs");
+
+		for job in unresolved_jobs {
+			let restored_job = job.save_state.as_deref().map(|save_state| {
+				restore_job(&job.id, &job.name, save_state, job.attempts as usize)
+			});
+
+			match restored_job {
+				// The pending follow-ups of the job are restored along with it
+				Some(Ok(executor)) => {
+					// A job which was waiting to be retried waits out the rest of its delay
+					let retry_delay = job.next_retry_at.and_then(|retry_at| {
+						retry_at.signed_duration_since(Utc::now()).to_std().ok()
+					});
+					if let Some(delay) = retry_delay {
+						tracing::info!(job_id = ?job.id, name = ?job.name, ?delay, "Rescheduling job retry");
+						self.clone().schedule_retry(executor, delay).await;
+						continue;
+					}
+					tracing::info!(job_id = ?job.id, name = ?job.name, "Re-enqueuing job from its restore point");
+					match self.clone().enqueue(executor).await {
+						Ok(_) => continue,
+						Err(error) => {
+							tracing::error!(?error, job_id = ?job.id, "Failed to re-enqueue job");
+						},
+					}
+				},
+				Some(Err(error)) => {
+					tracing::warn!(?error, job_id = ?job.id, "Failed to restore job");
+				},
+				None => {
+					tracing::debug!(job_id = ?job.id, "Job has no restore point");
+				},
+			}
 
-		for job in islanded_jobs {
-			let job_id = job.id.clone();
+			handle_cancel_pending_children(job.id.clone(), &self.client, vec![]).await?;
 			handle_do_cancel(
-				job_id,
+				job.id.clone(),
 				&self.client,
 				Duration::from_millis(job.ms_elapsed as u64),
 			)
 			.await?;
 		}
 
-		// TODO: Re-enqueue paused jobs
-
 		Ok(())
 	}
 
-	// FIXME: there is a bug in here I haven't been able to track down,
-	// it only presents when I enable thumbnails and the scanner auto-enqueues
-	// a follow-up job
-
-	/// Add a job to the queue. If there are no running jobs (i.e. no workers),
-	/// then a worker will be created and immediately spawned
+	/// Add a job to the queue, behind any queued jobs with the same or a higher priority. If
+	/// a worker is available, the job is started immediately (see [`JobManager::dispatch`]).
+	/// Any follow-up jobs the job declares are persisted as pending children, so the whole
+	/// pipeline is visible
 	pub async fn enqueue(
 		self: Arc<Self>,
 		job: Box<dyn Executor>,
@@ -116,7 +185,7 @@ impl JobManager {
 				(
 					job.id().to_string(),
 					job.name().to_string(),
-					vec![job::description::set(job.description())],
+					job_create_params(job.as_ref()),
 				),
 				vec![job::status::set(JobStatus::Queued.to_string())],
 			)
@@ -125,11 +194,70 @@ impl JobManager {
 			.map_err(|err| JobManagerError::JobPersistFailed(err.to_string()))?;
 		tracing::trace!(?created_job, "Persisted job to database");
 
+		self.persist_follow_ups(job.as_ref()).await?;
+
+		insert_by_priority(&mut *self.queue.write().await, job, false);
+		self.dispatch().await;
+
+		Ok(())
+	}
+
+	/// Persist the follow-up jobs declared by a job (and their own follow-ups) as pending
+	/// children of the job. Follow-ups which already exist are left as-is
+	async fn persist_follow_ups(&self, job: &dyn Executor) -> JobManagerResult<()> {
+		let mut follow_ups = job.follow_ups().iter().collect::<VecDeque<_>>();
+		let mut creates = vec![];
+		while let Some(follow_up) = follow_ups.pop_front() {
+			creates.push(self.client.job().upsert(
+				job::id::equals(follow_up.id().to_string()),
+				(
+					follow_up.id().to_string(),
+					follow_up.name().to_string(),
+					job_create_params(follow_up.as_ref()),
+				),
+				vec![],
+			));
+			follow_ups.extend(follow_up.follow_ups());
+		}
+
+		if !creates.is_empty() {
+			let persisted_follow_ups = self
+				.client
+				._batch(creates)
+				.await
+				.map_err(|err| JobManagerError::JobPersistFailed(err.to_string()))?;
+			tracing::trace!(
+				?persisted_follow_ups,
+				"Persisted follow-up jobs to database"
+			);
+		}
+
+		Ok(())
+	}
+
+	/// Start queued jobs until either the queue is empty or the configured maximum number of
+	/// jobs are running. Jobs are started in queue order, except that a job which locks a
+	/// library is skipped while another job holding the same lock is running.
+	///
+	/// The positions of the jobs left in the queue are broadcast to clients afterwards
+	async fn dispatch(self: &Arc<Self>) {
 		let mut workers = self.workers.write().await;
-		// If there are no running workers, just start the job
-		if workers.is_empty() {
+		let mut queue = self.queue.write().await;
+		let max_workers = self.config.max_concurrent_jobs.max(1);
+
+		while workers.len() < max_workers {
+			let locked_libraries = workers
+				.values()
+				.filter_map(|worker| worker.library_lock())
+				.collect::<HashSet<_>>();
+			let Some(job) = next_dispatchable_index(&queue, &locked_libraries)
+				.and_then(|index| queue.remove(index))
+			else {
+				break;
+			};
+
 			let job_id = job.id().to_string();
-			let worker = Worker::create_and_spawn(
+			match Worker::create_and_spawn(
 				job,
 				self.clone(),
 				self.client.clone(),
@@ -137,40 +265,59 @@ impl JobManager {
 				self.get_event_tx(),
 				self.job_controller_tx.clone(),
 			)
-			.await?;
-			workers.insert(job_id, worker);
-			tracing::trace!("Worker created and added to workers map");
-		} else {
-			// Enqueue the job
-			self.queue.write().await.push_back(job);
+			.await
+			{
+				Ok(worker) => {
+					workers.insert(job_id, worker);
+					tracing::trace!("Worker created and added to workers map");
+				},
+				Err(error) => {
+					tracing::error!(?error, ?job_id, "Failed to start job!");
+				},
+			}
 		}
 
+		let queued_jobs = queued_jobs(&queue);
+		drop(queue);
 		drop(workers);
-		Ok(())
-	}
 
-	/// Attempts to enqueue the next job in the queue (if one exists)
-	pub async fn auto_enqueue(self: Arc<Self>) {
-		if let Some(next) = self.queue.write().await.pop_front() {
-			self.job_controller_tx
-				.send(JobControllerCommand::EnqueueJob(next))
-				.map_or_else(
-					|error| {
-						tracing::error!(?error, "Failed to send event to job manager");
-					},
-					|_| tracing::trace!("Sent event to job manager to enqueue next job"),
-				);
-		} else {
-			tracing::trace!("No jobs in queue to auto enqueue");
+		if let Err(send_error) = self
+			.get_event_tx()
+			.send(CoreEvent::JobQueueUpdate { queue: queued_jobs })
+		{
+			tracing::error!(?send_error, "Failed to send queue update event");
 		}
 	}
 
+	/// Get the jobs which are waiting in the queue, in the order they will be started
+	pub async fn get_queued_jobs(&self) -> Vec<QueuedJob> {
+		queued_jobs(&self.queue.read().await)
+	}
+
 	/// Remove a worker by its associated job ID. This should only be called when a job
 	/// is complete, regardless of its finalized status. If the job is already queued,
 	/// nothing will happen.
 	///
+	/// The follow-up jobs of a successful job are placed ahead of any queued jobs with the
+	/// same priority, so that a pipeline runs to completion before other queued jobs. They
+	/// are queued before the worker is removed, so they can't race with the next job being
+	/// dispatched.
+	///
 	/// Will attempt to dispatch the next job in the queue if one exists
-	pub async fn complete(self: Arc<Self>, job_id: String) {
+	pub async fn complete(
+		self: Arc<Self>,
+		job_id: String,
+		follow_ups: Vec<Box<dyn Executor>>,
+	) {
+		if !follow_ups.is_empty() {
+			let mut queue = self.queue.write().await;
+			// Follow-ups are inserted in reverse so that those with the same priority keep
+			// the order they were declared in
+			for follow_up in follow_ups.into_iter().rev() {
+				insert_by_priority(&mut queue, follow_up, true);
+			}
+		}
+
 		self.workers.write().await.remove(&job_id).map_or_else(
 			|| {
 				tracing::error!(
@@ -181,30 +328,34 @@ impl JobManager {
 			|_| tracing::trace!("Removed worker for job from workers map"),
 		);
 
-		self.auto_enqueue().await;
+		self.dispatch().await;
 	}
 
-	/// Cancel a job by ID. If the job is not running but in the queue, it will be removed. If
-	/// the job is running, it will be sent a shutdown signal. Otherwise, an error will be
-	/// returned
+	/// Cancel a job by ID. If the job is not running but in the queue (or waiting to be
+	/// retried), it will be removed. If the job is running, it will be sent a shutdown signal.
+	/// Otherwise, an error will be returned. Any pending follow-ups of the job are cancelled
+	/// as well
 	pub async fn cancel(self: Arc<Self>, job_id: String) -> JobManagerResult<()> {
 		let mut workers = self.workers.write().await;
 
 		if let Some(worker) = workers.remove(&job_id) {
 			worker.cancel().await;
 			drop(workers);
-			self.auto_enqueue().await;
-		} else if let Some(index) = self.get_queued_job_index(&job_id).await {
+			self.dispatch().await;
+		} else if self.take_queued_job(&job_id).await.is_some() {
+			drop(workers);
+			tracing::trace!(?job_id, "Removed job from queue");
 			handle_do_cancel(job_id.clone(), &self.client, Duration::from_secs(0))
 				.await?;
-			self.queue.write().await.remove(index).map_or_else(
-				|| {
-					tracing::warn!(index, job_id, "Unexpected result: failed to remove job with existing index precondition");
-				},
-				|_| {
-                    tracing::trace!(index, job_id, "Removed job from queue");
-                },
-			);
+			handle_cancel_pending_children(job_id.clone(), &self.client, vec![]).await?;
+			// Let clients know the positions of the remaining jobs
+			self.dispatch().await;
+		} else if self.retries.write().await.remove(&job_id).is_some() {
+			drop(workers);
+			tracing::trace!(?job_id, "Removed job from retries");
+			handle_do_cancel(job_id.clone(), &self.client, Duration::from_secs(0))
+				.await?;
+			handle_cancel_pending_children(job_id.clone(), &self.client, vec![]).await?;
 		} else {
 			let islanded_job = self
 				.client
@@ -232,6 +383,30 @@ impl JobManager {
 		Ok(())
 	}
 
+	/// Hold a failed job until its retry delay has passed, after which it is enqueued again.
+	/// Until then, the job may still be cancelled (see [`JobManager::cancel`])
+	pub async fn schedule_retry(
+		self: Arc<Self>,
+		job: Box<dyn Executor>,
+		delay: Duration,
+	) {
+		let job_id = job.id().to_string();
+		self.retries.write().await.insert(job_id.clone(), job);
+
+		tokio::spawn(async move {
+			tokio::time::sleep(delay).await;
+			// The job will be gone if it was cancelled, or the server is shutting down
+			let job = self.retries.write().await.remove(&job_id);
+			let Some(job) = job else {
+				tracing::trace!(?job_id, "Job is no longer waiting to be retried");
+				return;
+			};
+			if let Err(error) = self.clone().enqueue(job).await {
+				tracing::error!(?error, ?job_id, "Failed to enqueue job for retry");
+			}
+		});
+	}
+
 	/// Pause a job by ID. This operation does not check the queue
 	pub async fn pause(self: Arc<Self>, job_id: String) -> JobManagerResult<()> {
 		let worker = self.get_worker(&job_id).await?;
@@ -255,14 +430,61 @@ impl JobManager {
 	}
 
 	/// Shutdown all workers and the job manager. This will be called when the application
-	/// is shutting down
+	/// is shutting down. Running jobs are interrupted rather than cancelled, and queued jobs are
+	/// persisted, so that they can be resumed by [`JobManager::initialize`] the next time the
+	/// server starts. Jobs waiting to be retried were persisted when they failed
 	pub async fn shutdown(self: Arc<Self>) {
+		// Clear the queues first, otherwise interrupted workers would dispatch the next job
+		self.retries.write().await.clear();
+		let queued_jobs = self.queue.write().await.drain(..).collect::<Vec<_>>();
 		let workers = self.workers.read().await;
-		join_all(workers.values().map(|worker| worker.cancel())).await;
+		join_all(workers.values().map(|worker| worker.interrupt())).await;
+		drop(workers);
+
+		self.persist_queued_jobs(queued_jobs).await;
+	}
+
+	/// Persist the save state of jobs which were queued but never started, so that they are
+	/// re-enqueued by [`JobManager::initialize`]. Jobs which already have a save state (e.g.
+	/// a restore point from before they were queued) keep it
+	async fn persist_queued_jobs(&self, jobs: Vec<Box<dyn Executor>>) {
+		let updates = jobs
+			.iter()
+			.filter_map(|job| {
+				match job.save_state().and_then(|save_state| {
+					serde_json::to_vec(&save_state)
+						.map_err(|error| JobError::StateSaveFailed(error.to_string()))
+				}) {
+					Ok(save_state) => Some(self.client.job().update_many(
+						vec![
+							job::id::equals(job.id().to_string()),
+							job::save_state::equals(None),
+						],
+						vec![job::save_state::set(Some(save_state))],
+					)),
+					Err(error) => {
+						tracing::error!(?error, job_id = ?job.id(), "Failed to save queued job");
+						None
+					},
+				}
+			})
+			.collect::<Vec<_>>();
+
+		if updates.is_empty() {
+			return;
+		}
+
+		match self.client._batch(updates).await {
+			Ok(persisted) => {
+				tracing::debug!(count = persisted.len(), "Persisted queued jobs");
+			},
+			Err(error) => tracing::error!(?error, "Failed to persist queued jobs"),
+		}
 	}
 
 	async fn job_already_exists(&self, job_id: &str) -> bool {
 		self.workers.read().await.contains_key(job_id)
+			|| self.retries.read().await.contains_key(job_id)
 			|| self.get_queued_job_index(job_id).await.is_some()
 	}
 
@@ -274,4 +496,266 @@ impl JobManager {
 			.iter()
 			.position(|job| job.id().to_string() == job_id)
 	}
+
+	/// Removes a job from the pending queue by ID, if it is queued
+	async fn take_queued_job(&self, job_id: &str) -> Option<Box<dyn Executor>> {
+		let mut queue = self.queue.write().await;
+		queue
+			.iter()
+			.position(|job| job.id().to_string() == job_id)
+			.and_then(|index| queue.remove(index))
+	}
+}
+
+/// Insert a job into a queue which is ordered by priority. The job is placed behind any
+/// queued jobs with the same priority, unless `ahead_of_peers` is set
+fn insert_by_priority(
+	queue: &mut VecDeque<Box<dyn Executor>>,
+	job: Box<dyn Executor>,
+	ahead_of_peers: bool,
+) {
+	let priority = job.priority();
+	let index = queue
+		.iter()
+		.position(|queued| {
+			if ahead_of_peers {
+				queued.priority() <= priority
+			} else {
+				queued.priority() < priority
+			}
+		})
+		.unwrap_or(queue.len());
+	queue.insert(index, job);
+}
+
+/// Find the index of the first job in the queue which can be started, i.e. one which does
+/// not lock any of the given libraries
+fn next_dispatchable_index(
+	queue: &VecDeque<Box<dyn Executor>>,
+	locked_libraries: &HashSet<&str>,
+) -> Option<usize> {
+	queue.iter().position(|job| {
+		job.library_lock().map_or(true, |library_id| {
+			!locked_libraries.contains(library_id.as_str())
+		})
+	})
+}
+
+/// Build the list of [`QueuedJob`]s for a queue
+fn queued_jobs(queue: &VecDeque<Box<dyn Executor>>) -> Vec<QueuedJob> {
+	queue
+		.iter()
+		.enumerate()
+		.map(|(index, job)| QueuedJob {
+			id: job.id().to_string(),
+			name: job.name().to_string(),
+			description: job.description(),
+			priority: job.priority(),
+			position: index + 1,
+		})
+		.collect()
+}
+
+/// Build the optional parameters used to create the DB record of a job
+fn job_create_params(job: &dyn Executor) -> Vec<job::SetParam> {
+	let mut params = vec![job::description::set(job.description())];
+	if let Some(parent_id) = job.parent_id() {
+		params.push(job::parent::connect(job::id::equals(parent_id.to_string())));
+	}
+	params
+}
+
+/// Rebuild a job from the save state it persisted when it was paused or interrupted, using the
+/// name of the job to determine its type
+fn restore_job(
+	id: &str,
+	name: &str,
+	save_state: &[u8],
+	attempts: usize,
+) -> Result<Box<dyn Executor>, JobError> {
+	let id = Uuid::parse_str(id)
+		.map_err(|error| JobError::StateLoadFailed(error.to_string()))?;
+	let save_state = serde_json::from_slice(save_state)
+		.map_err(|error| JobError::StateLoadFailed(error.to_string()))?;
+
+	restore_saved_job(id, name, save_state, attempts)
+}
+
+/// Rebuild a job from its deserialized save state, using the name of the job to determine its
+/// type. This is also used to rebuild the follow-ups saved with a job
+pub(super) fn restore_saved_job(
+	id: Uuid,
+	name: &str,
+	save_state: serde_json::Value,
+	attempts: usize,
+) -> Result<Box<dyn Executor>, JobError> {
+	let executor: Box<dyn Executor> = match name {
+		LibraryScanJob::NAME => {
+			WrappedJob::<LibraryScanJob>::restore(id, save_state, attempts)?
+		},
+		SeriesScanJob::NAME => {
+			WrappedJob::<SeriesScanJob>::restore(id, save_state, attempts)?
+		},
+		ScanDryRunJob::NAME => {
+			WrappedJob::<ScanDryRunJob>::restore(id, save_state, attempts)?
+		},
+		ThumbnailGenerationJob::NAME => {
+			WrappedJob::<ThumbnailGenerationJob>::restore(id, save_state, attempts)?
+		},
+		AnalyzeMediaJob::NAME => {
+			WrappedJob::<AnalyzeMediaJob>::restore(id, save_state, attempts)?
+		},
+		DatabaseMaintenanceJob::NAME => {
+			WrappedJob::<DatabaseMaintenanceJob>::restore(id, save_state, attempts)?
+		},
+		SessionCleanupJob::NAME => {
+			WrappedJob::<SessionCleanupJob>::restore(id, save_state, attempts)?
+		},
+		ComicVineMatchJob::NAME => {
+			WrappedJob::<ComicVineMatchJob>::restore(id, save_state, attempts)?
+		},
+		IsbnEnrichmentJob::NAME => {
+			WrappedJob::<IsbnEnrichmentJob>::restore(id, save_state, attempts)?
+		},
+		MetadataImportJob::NAME => {
+			WrappedJob::<MetadataImportJob>::restore(id, save_state, attempts)?
+		},
+		MediaCreditsSyncJob::NAME => {
+			WrappedJob::<MediaCreditsSyncJob>::restore(id, save_state, attempts)?
+		},
+		_ => {
+			return Err(JobError::StateLoadFailed(format!(
+				"Job {name} cannot be restored"
+			)))
+		},
+	};
+
+	Ok(executor)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_restore_job() {
+		let id = Uuid::new_v4();
+		let save_state = serde_json::to_vec(&serde_json::json!({
+			"job": {
+				"id": "library-id",
+				"path": "/books",
+				"config": null,
+				"options": {},
+				"roots": ["/books", "/more-books"],
+			},
+			"output": {},
+			"tasks": [{ "WalkSeries": "/books/series" }],
+			"completed_tasks": 10,
+			"logs": [],
+		}))
+		.unwrap();
+
+		let executor = restore_job(&id.to_string(), LibraryScanJob::NAME, &save_state, 2)
+			.expect("Failed to restore job");
+		assert_eq!(executor.id(), id);
+		assert_eq!(executor.name(), LibraryScanJob::NAME);
+		assert_eq!(executor.attempts(), 2);
+
+		assert!(restore_job(&id.to_string(), "unknown_job", &save_state, 0).is_err());
+		assert!(restore_job(
+			&id.to_string(),
+			ThumbnailGenerationJob::NAME,
+			&save_state,
+			0
+		)
+		.is_err());
+		assert!(restore_job("not-a-uuid", LibraryScanJob::NAME, &save_state, 0).is_err());
+	}
+
+	#[test]
+	fn test_restore_queued_job() {
+		let follow_up = SessionCleanupJob::new();
+		let follow_up_id = follow_up.id();
+		let job =
+			LibraryScanJob::new("library-id".to_string(), "/books".to_string(), None)
+				.with_priority(JobPriority::Interactive)
+				.then(follow_up);
+		let save_state =
+			serde_json::to_vec(&job.save_state().expect("Failed to save job")).unwrap();
+
+		let executor =
+			restore_job(&job.id().to_string(), LibraryScanJob::NAME, &save_state, 0)
+				.expect("Failed to restore job");
+		assert_eq!(executor.id(), job.id());
+		assert_eq!(executor.library_lock(), Some("library-id".to_string()));
+		assert_eq!(executor.priority(), JobPriority::Interactive);
+		assert_eq!(executor.parent_id(), None);
+
+		let follow_ups = executor.follow_ups();
+		assert_eq!(follow_ups.len(), 1);
+		assert_eq!(follow_ups[0].id(), follow_up_id);
+		assert_eq!(follow_ups[0].name(), SessionCleanupJob::NAME);
+		assert_eq!(follow_ups[0].parent_id(), Some(job.id()));
+	}
+
+	#[test]
+	fn test_insert_by_priority() {
+		let mut queue: VecDeque<Box<dyn Executor>> = VecDeque::new();
+		let first_background = DatabaseMaintenanceJob::new();
+		let second_background = SessionCleanupJob::new();
+		let interactive =
+			SessionCleanupJob::new().with_priority(JobPriority::Interactive);
+		let follow_up = DatabaseMaintenanceJob::new();
+		let ids = [
+			first_background.id(),
+			second_background.id(),
+			interactive.id(),
+			follow_up.id(),
+		];
+
+		insert_by_priority(&mut queue, first_background, false);
+		insert_by_priority(&mut queue, second_background, false);
+		insert_by_priority(&mut queue, interactive, false);
+		insert_by_priority(&mut queue, follow_up, true);
+
+		let order = queue.iter().map(|job| job.id()).collect::<Vec<_>>();
+		assert_eq!(order, vec![ids[2], ids[3], ids[0], ids[1]]);
+
+		let queued = queued_jobs(&queue);
+		assert_eq!(queued[0].priority, JobPriority::Interactive);
+		assert_eq!(
+			queued.iter().map(|job| job.position).collect::<Vec<_>>(),
+			vec![1, 2, 3, 4]
+		);
+	}
+
+	#[test]
+	fn test_next_dispatchable_index() {
+		let queue: VecDeque<Box<dyn Executor>> = VecDeque::from(vec![
+			LibraryScanJob::new("library-1".to_string(), "/books".to_string(), None)
+				as Box<dyn Executor>,
+			SeriesScanJob::new(
+				"series-1".to_string(),
+				"library-1".to_string(),
+				"/books/series".to_string(),
+				None,
+			),
+			LibraryScanJob::new("library-2".to_string(), "/comics".to_string(), None),
+			SessionCleanupJob::new(),
+		]);
+
+		assert_eq!(next_dispatchable_index(&queue, &HashSet::new()), Some(0));
+		assert_eq!(
+			next_dispatchable_index(&queue, &HashSet::from(["library-1"])),
+			Some(2)
+		);
+		assert_eq!(
+			next_dispatchable_index(&queue, &HashSet::from(["library-1", "library-2"])),
+			Some(3)
+		);
+		assert_eq!(
+			next_dispatchable_index(&VecDeque::new(), &HashSet::new()),
+			None
+		);
+	}
 }
@@ -15,11 +15,13 @@
 // - https://github.com/Nukesor/pueue
 use std::{collections::VecDeque, fmt::Debug, sync::Arc, time::Duration};
 
+use integrations::NotifierEvent;
 use prisma_client_rust::chrono::{self, DateTime, Utc};
 use serde::{de, Deserialize, Serialize};
 
 mod controller;
 pub mod error;
+mod hooks;
 mod manager;
 mod progress;
 mod scheduler;
@@ -28,7 +30,7 @@ mod worker;
 
 use error::JobError;
 pub use progress::*;
-pub use scheduler::JobScheduler;
+pub use scheduler::{next_scheduled_run, parse_cron_expression, JobScheduler};
 use specta::Type;
 pub use task::JobTaskOutput;
 use task::{job_task_handler, JobTaskHandlerOutput};
@@ -41,7 +43,7 @@ use uuid::Uuid;
 
 use crate::{
 	db::entity::LogLevel,
-	prisma::{job, log},
+	prisma::{job, log, PrismaClient},
 };
 
 #[derive(
@@ -58,6 +60,10 @@ pub enum JobStatus {
 	Cancelled,
 	#[serde(rename = "FAILED")]
 	Failed,
+	/// The job failed on every attempt its [`JobRetryPolicy`] allowed, and will not be
+	/// retried again
+	#[serde(rename = "DEAD_LETTER")]
+	DeadLetter,
 	#[default]
 	#[serde(rename = "QUEUED")]
 	Queued,
@@ -65,11 +71,14 @@ pub enum JobStatus {
 
 impl JobStatus {
 	/// A helper function to determine if a job status is resolved. A job is considered
-	/// resolved if it is in a final state (Completed, Cancelled, or Failed).
+	/// resolved if it is in a final state (Completed, Cancelled, Failed, or DeadLetter).
 	pub fn is_resolved(&self) -> bool {
 		matches!(
 			self,
-			JobStatus::Completed | JobStatus::Cancelled | JobStatus::Failed
+			JobStatus::Completed
+				| JobStatus::Cancelled
+				| JobStatus::Failed
+				| JobStatus::DeadLetter
 		)
 	}
 
@@ -97,6 +106,7 @@ impl std::fmt::Display for JobStatus {
 			JobStatus::Completed => write!(f, "COMPLETED"),
 			JobStatus::Cancelled => write!(f, "CANCELLED"),
 			JobStatus::Failed => write!(f, "FAILED"),
+			JobStatus::DeadLetter => write!(f, "DEAD_LETTER"),
 			JobStatus::Queued => write!(f, "QUEUED"),
 		}
 	}
@@ -110,6 +120,7 @@ impl From<&str> for JobStatus {
 			"COMPLETED" => JobStatus::Completed,
 			"CANCELLED" => JobStatus::Cancelled,
 			"FAILED" => JobStatus::Failed,
+			"DEAD_LETTER" => JobStatus::DeadLetter,
 			"QUEUED" => JobStatus::Queued,
 			_ => unreachable!(),
 		}
@@ -122,6 +133,32 @@ impl From<String> for JobStatus {
 	}
 }
 
+/// The priority of a job, which determines the order in which queued jobs are started. A job
+/// is started before any queued jobs with a lower priority, regardless of when it was queued.
+#[derive(
+	Clone,
+	Copy,
+	Debug,
+	Default,
+	Serialize,
+	Deserialize,
+	PartialEq,
+	Eq,
+	PartialOrd,
+	Ord,
+	Type,
+	ToSchema,
+)]
+pub enum JobPriority {
+	/// A job which nobody is waiting on, e.g. a scheduled scan or a follow-up job
+	#[default]
+	#[serde(rename = "BACKGROUND")]
+	Background,
+	/// A job which was started by a user, who is likely waiting for it to finish
+	#[serde(rename = "INTERACTIVE")]
+	Interactive,
+}
+
 /// The retry policy for a job. This is used to determine if a job should be requeued after
 /// a non-critical failure.
 #[derive(Debug, Clone, Deserialize, Serialize)]
@@ -132,6 +169,36 @@ pub enum JobRetryPolicy {
 	Count(usize),
 }
 
+/// The delay before the first retry of a failed job or task. Each subsequent retry waits twice
+/// as long as the previous one, up to [`MAX_RETRY_DELAY`]
+const BASE_RETRY_DELAY: Duration = Duration::from_secs(5);
+/// The longest a failed job or task will wait before being retried
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(5 * 60);
+
+impl JobRetryPolicy {
+	/// Whether another attempt should be made after the given number of failed attempts
+	pub fn should_retry(&self, failed_attempts: usize) -> bool {
+		match self {
+			JobRetryPolicy::Infinite => true,
+			JobRetryPolicy::Count(count) => failed_attempts <= *count,
+		}
+	}
+
+	/// Whether the policy allows at least one retry, i.e. a retry policy was configured
+	pub fn allows_retries(&self) -> bool {
+		self.should_retry(1)
+	}
+
+	/// The delay before retrying after the given number of failed attempts, which grows
+	/// exponentially with each failure
+	pub fn backoff(failed_attempts: usize) -> Duration {
+		let exponent = failed_attempts.saturating_sub(1).min(u32::MAX as usize) as u32;
+		2u32.checked_pow(exponent)
+			.and_then(|factor| BASE_RETRY_DELAY.checked_mul(factor))
+			.map_or(MAX_RETRY_DELAY, |delay| delay.min(MAX_RETRY_DELAY))
+	}
+}
+
 /// A trait to extend the output type for a job with a common interface. Job output starts
 /// in an 'empty' state (Default) and is frequently updated during execution.
 ///
@@ -238,13 +305,49 @@ impl<O, T> Default for WorkingState<O, T> {
 	}
 }
 
+/// Wait for the given delay before a failed task is retried. Commands sent to the worker are
+/// still handled while waiting, so that the job can be paused, cancelled or interrupted
+async fn wait_for_retry(
+	ctx: &WorkerCtx,
+	commands_rx: &async_channel::Receiver<WorkerCommand>,
+	delay: Duration,
+) -> Result<(), JobError> {
+	let retry_at = tokio::time::Instant::now() + delay;
+	loop {
+		tokio::select! {
+			_ = tokio::time::sleep_until(retry_at) => return Ok(()),
+			Ok(cmd) = commands_rx.recv() => match cmd {
+				WorkerCommand::Cancel(return_sender) => {
+					return Err(JobError::Cancelled(return_sender));
+				},
+				WorkerCommand::Interrupt(return_sender) => {
+					return Err(JobError::Interrupted(return_sender));
+				},
+				WorkerCommand::Pause => ctx.pause().await,
+				WorkerCommand::Resume => ctx.resume().await,
+			},
+		}
+	}
+}
+
 /// A trait that defines the behavior and data types of a job. Jobs are responsible for
 /// initialization and individual task execution. Jobs are managed by an [Executor], which
 /// is responsible for the main run loop of a job.
+///
+/// The job itself is persisted alongside its working state whenever a restore point is created,
+/// so it must implement [Serialize] and [`de::DeserializeOwned`] in order to be rebuilt after a
+/// restart.
 #[async_trait::async_trait]
-pub trait JobExt: Send + Sync + Sized + Clone + 'static {
+pub trait JobExt:
+	Send + Sync + Sized + Clone + Serialize + de::DeserializeOwned + 'static
+{
 	const NAME: &'static str;
+	/// The retry policy for the job as a whole, used when the job fails with a critical error
 	const MAX_RETRIES: JobRetryPolicy = JobRetryPolicy::Count(0);
+	/// The retry policy for each individual task of the job. A task which fails is retried
+	/// (after a delay) before the failure is recorded against the job. When a policy is
+	/// configured and exhausted the job fails, otherwise the job completes with an error log
+	const MAX_TASK_RETRIES: JobRetryPolicy = JobRetryPolicy::Count(0);
 
 	/// The output type for the job. This is the data that will be persisted to the DB when the
 	/// job completes. All jobs should have a user-friendly representation of their output.
@@ -265,6 +368,13 @@ pub trait JobExt: Send + Sync + Sized + Clone + 'static {
 	/// The description of the job, if any
 	fn description(&self) -> Option<String>;
 
+	/// The ID of the library which the job requires exclusive access to, if any. Two jobs
+	/// which lock the same library will never run at the same time, e.g. two scans of the
+	/// same library. The default implementation does not lock any library.
+	fn library_lock(&self) -> Option<String> {
+		None
+	}
+
 	/// A function that will be called in [Executor::execute] to initialize the job state with
 	/// existing data from the DB (if any). Used to support pausing/resuming jobs.
 	///
@@ -288,7 +398,17 @@ pub trait JobExt: Send + Sync + Sized + Clone + 'static {
 				if let Some(save_state) = job.save_state {
 					// If the job has a save state and it is invalid, we should fail
 					// as to not attempt potentially undefined behaviors
-					let state = serde_json::from_slice(&save_state)
+					let save_state =
+						serde_json::from_slice::<serde_json::Value>(&save_state)
+							.map_err(|error| {
+								JobError::StateLoadFailed(error.to_string())
+							})?;
+					// A job which was saved before it started (e.g. it was still queued
+					// during a shutdown) has no working state to restore
+					if save_state.get("tasks").is_none() {
+						return Ok(None);
+					}
+					let state = serde_json::from_value(save_state)
 						.map_err(|error| JobError::StateLoadFailed(error.to_string()))?;
 					Ok(Some(state))
 				} else {
@@ -306,53 +426,6 @@ pub trait JobExt: Send + Sync + Sized + Clone + 'static {
 		}
 	}
 
-	/// A function to persist the current working state of the job to the DB. This is called
-	/// when the job is paused in the event that a shutdown occurs before it can be resumed.
-	///
-	/// Note that when defining a new job, you should not need to invoke this function directly. The
-	/// [Executor] will handle this for you whenever the job is paused.
-	#[tracing::instrument(level = "debug", err, skip(self, ctx, tasks, logs))]
-	async fn persist_restore_point(
-		&self,
-		ctx: &WorkerCtx,
-		output: &Self::Output,
-		tasks: &VecDeque<Self::Task>,
-		completed_tasks: usize,
-		logs: &Vec<JobExecuteLog>,
-	) -> Result<(), JobError> {
-		let db = ctx.db.clone();
-		let job_id = ctx.job_id.clone();
-
-		let json_output = serde_json::to_value(output)
-			.map_err(|error| JobError::StateSaveFailed(error.to_string()))?;
-		let json_tasks = serde_json::to_value(tasks)
-			.map_err(|error| JobError::StateSaveFailed(error.to_string()))?;
-		let json_logs = serde_json::to_value(logs)
-			.map_err(|error| JobError::StateSaveFailed(error.to_string()))?;
-		let working_state = serde_json::json!({
-			"output": json_output,
-			"tasks": json_tasks,
-			"completed_tasks": completed_tasks,
-			"logs": json_logs,
-		});
-
-		let save_state = serde_json::to_vec(&working_state)
-			.map_err(|error| JobError::StateSaveFailed(error.to_string()))?;
-
-		let persisted_job = db
-			.job()
-			.update(
-				job::id::equals(job_id),
-				vec![job::save_state::set(Some(save_state))],
-			)
-			.exec()
-			.await
-			.map_err(|error| JobError::StateSaveFailed(error.to_string()))?;
-		tracing::trace!(?persisted_job, "Persisted job save state to DB");
-
-		Ok(())
-	}
-
 	/// A function that is called before Self::execute to initialize the job and gather the
 	/// required tasks
 	async fn init(
@@ -363,27 +436,37 @@ pub trait JobExt: Send + Sync + Sized + Clone + 'static {
 	/// An optional function to perform any cleanup or finalization after the job has
 	/// finished its run loop. This is called after the job has completed (when [Executor::execute]
 	/// returns an Ok).
+	///
+	/// Any jobs which are returned are run as follow-ups of this job, in addition to those
+	/// declared up front with [`WrappedJob::then`]. This is useful when whether a follow-up is
+	/// needed depends on the output of the job.
 	async fn cleanup(
 		&self,
 		_: &WorkerCtx,
 		_: &Self::Output,
-	) -> Result<Option<Box<dyn Executor>>, JobError> {
-		Ok(None)
+	) -> Result<Vec<Box<dyn Executor>>, JobError> {
+		Ok(vec![])
 	}
 
-	// TODO: notify_output(&self, output: &Self::Output) -> Result<(), JobError> { Ok(()) }
+	/// An optional function to describe the output of the job as a [`NotifierEvent`] once it
+	/// has completed. The event is published to any notifiers which are subscribed to the job.
+	/// Failures are published for every job, regardless of this function.
+	async fn notify_output(
+		&self,
+		_: &WorkerCtx,
+		_: &Self::Output,
+	) -> Option<NotifierEvent> {
+		None
+	}
 
 	/// An optional function to determine if a task should be requeued. This is called after
-	/// a job fully completes in a non-successful state, but not after critical task errors.
-	/// The default implementation is to requeue per the job's retry policy. This can be
+	/// a job fails, including when a task fails on every attempt a configured
+	/// [`JobExt::MAX_TASK_RETRIES`] allows. The default implementation is to requeue per the job's retry policy. This can be
 	/// overridden to provide custom requeue logic.
 	///
 	/// Note that the exception to the above is that it **won't** be called after a manual cancellation
 	fn should_requeue(&self, attempts: usize) -> bool {
-		match Self::MAX_RETRIES {
-			JobRetryPolicy::Infinite => true,
-			JobRetryPolicy::Count(count) => attempts < count,
-		}
+		Self::MAX_RETRIES.should_retry(attempts)
 	}
 
 	/// A function to execute a specific task. This will be called repeatedly until all
@@ -411,6 +494,13 @@ pub struct WrappedJob<J: JobExt> {
 	/// The number of attempts the job has made thus far. This is used to determine if the job
 	/// should be requeued. Requeue logic is defined externally in the job's implementation.
 	attempts: usize,
+	/// The ID of the job which declared this job as a follow-up, if any
+	parent_id: Option<Uuid>,
+	/// The priority of the job, used to order the queue
+	priority: JobPriority,
+	/// The jobs which should run once this job has completed successfully. These are dropped
+	/// (and cancelled) if the job fails or is cancelled.
+	follow_ups: Vec<Box<dyn Executor>>,
 }
 
 impl<J: JobExt> WrappedJob<J> {
@@ -426,16 +516,182 @@ impl<J: JobExt> WrappedJob<J> {
 				logs: vec![],
 			}),
 			attempts: 0,
+			parent_id: None,
+			priority: JobPriority::default(),
+			follow_ups: vec![],
+		})
+	}
+
+	/// Set the priority of the job, e.g. [`JobPriority::Interactive`] for a job which was
+	/// started by a user
+	pub fn with_priority(mut self: Box<Self>, priority: JobPriority) -> Box<Self> {
+		self.priority = priority;
+		self
+	}
+
+	/// Declare a job which should run once this job has completed successfully, e.g. a
+	/// thumbnail generation job after a scan. Follow-ups may declare their own follow-ups to
+	/// build a longer pipeline.
+	pub fn then(mut self: Box<Self>, mut job: Box<dyn Executor>) -> Box<Self> {
+		job.set_parent_id(self.id);
+		self.follow_ups.push(job);
+		self
+	}
+
+	/// Rebuild a [`WrappedJob`] from the save state of a job which was paused or interrupted,
+	/// keeping its original ID, parent, priority, follow-ups and the number of attempts it has
+	/// made. The working state is not loaded here, since the job will restore it from the DB
+	/// once it is executed (see [`JobExt::attempt_restore`]).
+	pub fn restore(
+		id: Uuid,
+		save_state: serde_json::Value,
+		attempts: usize,
+	) -> Result<Box<Self>, JobError> {
+		let SavedJob {
+			job,
+			parent_id,
+			priority,
+			follow_ups,
+		} = serde_json::from_value::<SavedJob<J>>(save_state)
+			.map_err(|error| JobError::StateLoadFailed(error.to_string()))?;
+
+		let parent_id = parent_id
+			.map(|parent_id| Uuid::parse_str(&parent_id))
+			.transpose()
+			.map_err(|error| JobError::StateLoadFailed(error.to_string()))?;
+		// Follow-ups have not started yet, so they have made no attempts
+		let follow_ups = follow_ups
+			.into_iter()
+			.map(|follow_up| {
+				let follow_up_id = Uuid::parse_str(&follow_up.id)
+					.map_err(|error| JobError::StateLoadFailed(error.to_string()))?;
+				manager::restore_saved_job(
+					follow_up_id,
+					&follow_up.name,
+					follow_up.save_state,
+					0,
+				)
+			})
+			.collect::<Result<Vec<_>, _>>()?;
+
+		Ok(Box::new(Self {
+			id,
+			inner_job: Some(job),
+			initial_state: Some(WorkingState::default()),
+			attempts,
+			parent_id,
+			priority,
+			follow_ups,
+		}))
+	}
+
+	/// Build the part of the save state which is used to rebuild the job, see
+	/// [`WrappedJob::restore`]
+	fn saved_job(&self, job: &J) -> Result<serde_json::Value, JobError> {
+		let follow_ups = self
+			.follow_ups
+			.iter()
+			.map(|follow_up| {
+				Ok(SavedFollowUp {
+					id: follow_up.id().to_string(),
+					name: follow_up.name().to_string(),
+					save_state: follow_up.save_state()?,
+				})
+			})
+			.collect::<Result<Vec<_>, JobError>>()?;
+
+		serde_json::to_value(SavedJob {
+			job,
+			parent_id: self.parent_id.map(|id| id.to_string()),
+			priority: self.priority,
+			follow_ups,
 		})
+		.map_err(|error| JobError::StateSaveFailed(error.to_string()))
+	}
+
+	/// A function to persist the current working state of the job to the DB. This is called
+	/// when the job is paused or interrupted, so that it can be resumed after a restart. The job
+	/// itself is stored alongside the working state, see [`WrappedJob::restore`].
+	#[tracing::instrument(
+		level = "debug",
+		err,
+		skip(self, ctx, job, output, tasks, logs)
+	)]
+	async fn persist_restore_point(
+		&self,
+		ctx: &WorkerCtx,
+		job: &J,
+		output: &J::Output,
+		tasks: &VecDeque<J::Task>,
+		completed_tasks: usize,
+		logs: &Vec<JobExecuteLog>,
+	) -> Result<(), JobError> {
+		let db = ctx.db.clone();
+		let job_id = ctx.job_id.clone();
+
+		let json_output = serde_json::to_value(output)
+			.map_err(|error| JobError::StateSaveFailed(error.to_string()))?;
+		let json_tasks = serde_json::to_value(tasks)
+			.map_err(|error| JobError::StateSaveFailed(error.to_string()))?;
+		let json_logs = serde_json::to_value(logs)
+			.map_err(|error| JobError::StateSaveFailed(error.to_string()))?;
+		let mut working_state = self.saved_job(job)?;
+		if let Some(state) = working_state.as_object_mut() {
+			state.insert("output".to_string(), json_output);
+			state.insert("tasks".to_string(), json_tasks);
+			state.insert("completed_tasks".to_string(), completed_tasks.into());
+			state.insert("logs".to_string(), json_logs);
+		}
+
+		let save_state = serde_json::to_vec(&working_state)
+			.map_err(|error| JobError::StateSaveFailed(error.to_string()))?;
+
+		let persisted_job = db
+			.job()
+			.update(
+				job::id::equals(job_id),
+				vec![job::save_state::set(Some(save_state))],
+			)
+			.exec()
+			.await
+			.map_err(|error| JobError::StateSaveFailed(error.to_string()))?;
+		tracing::trace!(?persisted_job, "Persisted job save state to DB");
+
+		Ok(())
 	}
 }
 
+/// The parts of a [`WrappedJob`] which are persisted in its save state, so that the job can be
+/// rebuilt after a restart (see [`WrappedJob::restore`]). The working state of the job, if any,
+/// is stored alongside these fields
+#[derive(Serialize, Deserialize)]
+struct SavedJob<J> {
+	job: J,
+	#[serde(default)]
+	parent_id: Option<String>,
+	#[serde(default)]
+	priority: JobPriority,
+	#[serde(default)]
+	follow_ups: Vec<SavedFollowUp>,
+}
+
+/// A follow-up job which has not started yet. Follow-ups are otherwise only held in memory, so
+/// they are saved with the job which declared them
+#[derive(Serialize, Deserialize)]
+struct SavedFollowUp {
+	id: String,
+	name: String,
+	save_state: serde_json::Value,
+}
+
 /// The output of a job's execution. To avoid the need for a generic type, the output data is serialized
 /// _prior_ to being returned from the [`Executor::execute`] function.
 pub struct ExecutorOutput {
 	pub output: Option<serde_json::Value>,
 	pub logs: Vec<JobExecuteLog>,
-	pub next_job: Option<Box<dyn Executor>>,
+	pub follow_ups: Vec<Box<dyn Executor>>,
+	/// The event to publish for the outcome of the job, if any
+	pub notification: Option<NotifierEvent>,
 }
 
 impl Debug for ExecutorOutput {
@@ -443,11 +699,44 @@ impl Debug for ExecutorOutput {
 		f.debug_struct("ExecutorOutput")
 			.field("output", &self.output)
 			.field("logs", &self.logs)
-			.field("next_job", &self.next_job.as_ref().map(|j| j.name()))
+			.field(
+				"follow_ups",
+				&self.follow_ups.iter().map(|j| j.name()).collect::<Vec<_>>(),
+			)
+			.field("notification", &self.notification)
 			.finish()
 	}
 }
 
+/// Persist the logs of a job to the DB. Failures are logged rather than returned, since they
+/// shouldn't prevent the job from being finalized
+async fn persist_logs(db: &PrismaClient, job_id: Uuid, logs: Vec<JobExecuteLog>) {
+	let expected_logs = logs.len();
+	if expected_logs == 0 {
+		return;
+	}
+
+	let creates = logs
+		.into_iter()
+		.map(|log| log.into_prisma(job_id.to_string()))
+		.map(|(msg, params)| db.log().create(msg, params));
+	let persisted_logs = db._batch(creates).await.map_or_else(
+		|error| {
+			tracing::error!(?error, "Failed to persist job logs!");
+			0
+		},
+		|logs| logs.len(),
+	);
+
+	if persisted_logs != expected_logs {
+		tracing::warn!(
+			?persisted_logs,
+			?expected_logs,
+			"Failed to persist all job logs!"
+		);
+	}
+}
+
 /// A trait that defines the behavior of a job executor. Executors are responsible for the main
 /// run loop of a job, including task execution and state management. Executors are managed
 /// by the [JobManager].
@@ -459,11 +748,30 @@ pub trait Executor: Send + Sync {
 	fn name(&self) -> &'static str;
 	/// The optional description for the internal job
 	fn description(&self) -> Option<String>;
+	/// The ID of the job which declared this job as a follow-up, if any
+	fn parent_id(&self) -> Option<Uuid>;
+	/// Set the ID of the job which declared this job as a follow-up
+	fn set_parent_id(&mut self, parent_id: Uuid);
+	/// The jobs which should run once this job has completed successfully
+	fn follow_ups(&self) -> &[Box<dyn Executor>];
+	/// The priority of the job, used to order the queue
+	fn priority(&self) -> JobPriority;
+	/// The ID of the library which the job requires exclusive access to, if any
+	fn library_lock(&self) -> Option<String>;
 	/// A function to determine if a job should be requeued. This is called after
-	/// a job fully completes in a non-successful state, but not after critical task errors.
+	/// a job fails, including when a task fails on every attempt a configured task retry
+	/// policy allows.
 	///
 	/// Note that the exception to the above is that it **won't** be called after a manual cancellation
 	fn should_requeue(&self) -> bool;
+	/// The number of times the job has been executed thus far
+	fn attempts(&self) -> usize;
+	/// Whether the job has a retry policy which it has used up, i.e. it was retried as many
+	/// times as the policy allows. A job without a retry policy never exhausts its retries
+	fn retries_exhausted(&self) -> bool;
+	/// Serialize the job so that it can be rebuilt before it has started, e.g. when it is
+	/// still queued during a shutdown. Unlike a restore point, this holds no working state
+	fn save_state(&self) -> Result<serde_json::Value, JobError>;
 	/// A function to persist the data of the job to the DB. This is called immediately before the job
 	/// would otherwise complete (at the end of [Executor::execute]).
 	async fn persist_output(
@@ -475,29 +783,7 @@ pub trait Executor: Send + Sync {
 		let db = ctx.db.clone();
 		let job_id = self.id();
 
-		let expected_logs = output.logs.len();
-		if expected_logs > 0 {
-			let creates = output
-				.logs
-				.into_iter()
-				.map(|log| log.into_prisma(job_id.to_string()))
-				.map(|(msg, params)| db.log().create(msg, params));
-			let persisted_logs = db._batch(creates).await.map_or_else(
-				|error| {
-					tracing::error!(?error, "Failed to persist job logs!");
-					0
-				},
-				|logs| logs.len(),
-			);
-
-			if persisted_logs != expected_logs {
-				tracing::warn!(
-					?persisted_logs,
-					?expected_logs,
-					"Failed to persist all job logs!"
-				);
-			}
-		}
+		persist_logs(&db, job_id, output.logs).await;
 
 		let output_data = serde_json::to_vec(&output.output)
 			.map_err(|error| JobError::StateSaveFailed(error.to_string()))?;
@@ -600,12 +886,47 @@ impl<J: JobExt> Executor for WrappedJob<J> {
 		self.inner_job.as_ref().and_then(JobExt::description)
 	}
 
+	fn parent_id(&self) -> Option<Uuid> {
+		self.parent_id
+	}
+
+	fn set_parent_id(&mut self, parent_id: Uuid) {
+		self.parent_id = Some(parent_id);
+	}
+
+	fn follow_ups(&self) -> &[Box<dyn Executor>] {
+		&self.follow_ups
+	}
+
+	fn priority(&self) -> JobPriority {
+		self.priority
+	}
+
+	fn library_lock(&self) -> Option<String> {
+		self.inner_job.as_ref().and_then(JobExt::library_lock)
+	}
+
 	fn should_requeue(&self) -> bool {
 		self.inner_job
 			.as_ref()
 			.map_or(false, |job| job.should_requeue(self.attempts))
 	}
 
+	fn attempts(&self) -> usize {
+		self.attempts
+	}
+
+	fn retries_exhausted(&self) -> bool {
+		J::MAX_RETRIES.allows_retries() && !J::MAX_RETRIES.should_retry(self.attempts)
+	}
+
+	fn save_state(&self) -> Result<serde_json::Value, JobError> {
+		let job = self.inner_job.as_ref().ok_or_else(|| {
+			JobError::StateSaveFailed("Job was unexpectedly None".to_string())
+		})?;
+		self.saved_job(job)
+	}
+
 	async fn execute(&mut self, ctx: WorkerCtx) -> Result<ExecutorOutput, JobError> {
 		ctx.report_progress(JobProgress::status_msg(
 			JobStatus::Running,
@@ -618,21 +939,28 @@ impl<J: JobExt> Executor for WrappedJob<J> {
 		let commands_rx = ctx.commands_rx.clone();
 		tracing::info!(?job_id, ?job_name, "Starting job");
 
-		let mut inner_job = self.inner_job.take().ok_or_else(|| {
+		// The job is cloned, rather than taken, so that it remains in the wrapper if the job
+		// fails and is requeued
+		let mut inner_job = self.inner_job.clone().ok_or_else(|| {
 			JobError::InitFailed("Job was unexpectedly None".to_string())
 		})?;
+		// The state is replaced with defaults, to ensure there is a reset in the event of
+		// a requeue
 		let WorkingState {
 			output,
 			mut tasks,
 			mut completed_tasks,
 			mut logs,
-		} = self.initial_state.take().unwrap_or_else(|| {
-			tracing::warn!(
-				current_attempt = self.attempts,
-				"Initial state was not defined for job. This is a bug!"
-			);
-			WorkingState::default()
-		});
+		} = self
+			.initial_state
+			.replace(WorkingState::default())
+			.unwrap_or_else(|| {
+				tracing::warn!(
+					current_attempt = self.attempts,
+					"Initial state was not defined for job. This is a bug!"
+				);
+				WorkingState::default()
+			});
 
 		let mut working_output = if let Some(initial_data) = output {
 			tracing::debug!(?initial_data, "Job started with initial state");
@@ -674,6 +1002,8 @@ impl<J: JobExt> Executor for WrappedJob<J> {
 		// Setup our references since the loop would otherwise take ownership
 		let job = Arc::new(inner_job.clone());
 		let mut ctx = Arc::new(ctx);
+		// The number of consecutive failed attempts of the current task
+		let mut failed_task_attempts = 0;
 
 		tracing::debug!(task_count = tasks.len(), "Starting tasks");
 
@@ -684,9 +1014,10 @@ impl<J: JobExt> Executor for WrappedJob<J> {
 			let mut worker_status = ctx.get_status().await;
 			if worker_status == WorkerStatus::Paused {
 				ctx.report_progress(JobProgress::msg("Paused acknowledged"));
-				let save_result = job
+				let save_result = self
 					.persist_restore_point(
 						&ctx,
+						&job,
 						&working_output,
 						&tasks,
 						completed_tasks,
@@ -696,12 +1027,27 @@ impl<J: JobExt> Executor for WrappedJob<J> {
 				tracing::debug!(?save_result, "Persisted restore point?");
 				while worker_status == WorkerStatus::Paused {
 					tracing::debug!("Job is paused. Waiting for resume...");
+					// Commands are otherwise only received while a task is running, so they
+					// must be handled here. Wait for a reasonable amount of time for one before
+					// checking the status again
+					let command =
+						tokio::time::timeout(Duration::from_secs(5), commands_rx.recv())
+							.await;
+					match command {
+						Ok(Ok(WorkerCommand::Resume)) => ctx.resume().await,
+						// The restore point was already persisted when the job was paused
+						Ok(Ok(WorkerCommand::Interrupt(return_sender))) => {
+							return Err(JobError::Interrupted(return_sender));
+						},
+						Ok(Ok(WorkerCommand::Cancel(return_sender))) => {
+							return Err(JobError::Cancelled(return_sender));
+						},
+						_ => {},
+					}
+					worker_status = ctx.get_status().await;
 					if worker_status == WorkerStatus::Running {
 						ctx.report_progress(JobProgress::msg("Resume acknowledged"));
 					}
-					// Wait for a reasonable amount of time before checking again
-					tokio::time::sleep(Duration::from_secs(5)).await;
-					worker_status = ctx.get_status().await;
 				}
 			}
 
@@ -717,6 +1063,10 @@ impl<J: JobExt> Executor for WrappedJob<J> {
 					"No tasks unexpectedly remain! This doesn't make sense!".to_string(),
 				)
 			})?;
+			// A snapshot of the task is kept so it can be put back into the queue if the job is
+			// interrupted while the task is running
+			let task_snapshot = serde_json::to_value(&next_task)
+				.map_err(|error| JobError::StateSaveFailed(error.to_string()))?;
 
 			let task_handle = {
 				let ctx = Arc::clone(&ctx);
@@ -727,15 +1077,94 @@ impl<J: JobExt> Executor for WrappedJob<J> {
 			let JobTaskHandlerOutput {
 				output,
 				returned_ctx,
-			} = match job_task_handler::<J>(ctx, task_handle, commands_rx.clone()).await {
+			} = match job_task_handler::<J>(
+				Arc::clone(&ctx),
+				task_handle,
+				commands_rx.clone(),
+			)
+			.await
+			{
 				Ok(r) => r,
+				Err(JobError::Interrupted(return_sender)) => {
+					match serde_json::from_value(task_snapshot) {
+						Ok(task) => tasks.push_front(task),
+						Err(error) => {
+							tracing::error!(?error, "Failed to restore interrupted task");
+						},
+					}
+					let save_result = self
+						.persist_restore_point(
+							&ctx,
+							&job,
+							&working_output,
+							&tasks,
+							completed_tasks,
+							&logs,
+						)
+						.await;
+					tracing::debug!(?save_result, "Persisted restore point?");
+					return Err(JobError::Interrupted(return_sender));
+				},
+				Err(JobError::Cancelled(return_sender)) => {
+					return Err(JobError::Cancelled(return_sender));
+				},
+				Err(e) if J::MAX_TASK_RETRIES.should_retry(failed_task_attempts + 1) => {
+					failed_task_attempts += 1;
+					let delay = JobRetryPolicy::backoff(failed_task_attempts);
+					tracing::warn!(
+						?e,
+						?delay,
+						failed_task_attempts,
+						"Task failed, retrying"
+					);
+					logs.push(JobExecuteLog::warn(&format!(
+						"Task failed, retrying in {}s: {e}",
+						delay.as_secs()
+					)));
+
+					let task = serde_json::from_value(task_snapshot)
+						.map_err(|error| JobError::StateLoadFailed(error.to_string()))?;
+					tasks.push_front(task);
+
+					if let Err(error) = wait_for_retry(&ctx, &commands_rx, delay).await {
+						if matches!(error, JobError::Interrupted(_)) {
+							let save_result = self
+								.persist_restore_point(
+									&ctx,
+									&job,
+									&working_output,
+									&tasks,
+									completed_tasks,
+									&logs,
+								)
+								.await;
+							tracing::debug!(?save_result, "Persisted restore point?");
+						}
+						return Err(error);
+					}
+					continue;
+				},
+				Err(e) if J::MAX_TASK_RETRIES.allows_retries() => {
+					tracing::error!(?e, "Task failed on every allowed attempt");
+					// The job is failed (or requeued) by the worker, so the logs are persisted
+					// here since there is no output to persist them with
+					logs.push(JobExecuteLog::error(format!("Critical task error: {e}")));
+					persist_logs(&ctx.db, job_id, logs).await;
+					return Err(JobError::TaskFailed(e.to_string()));
+				},
 				Err(e) => {
 					tracing::error!(?e, "Task handler failed");
-					logs.push(JobExecuteLog::error(format!("Critical task error: {e}")));
+					let error = format!("Critical task error: {e}");
+					logs.push(JobExecuteLog::error(error.clone()));
 					return Ok(ExecutorOutput {
 						output: working_output.into_json(),
 						logs,
-						next_job: None,
+						follow_ups: vec![],
+						notification: Some(NotifierEvent::JobFailed {
+							job_name: job_name.to_string(),
+							description: inner_job.description(),
+							error,
+						}),
 					});
 				},
 			};
@@ -747,6 +1176,7 @@ impl<J: JobExt> Executor for WrappedJob<J> {
 
 			// Update our working data and any logs with the new data/logs from the
 			// completed task. Then increment the task index
+			failed_task_attempts = 0;
 			working_output.update(task_output);
 			logs.extend(task_logs);
 			completed_tasks += 1;
@@ -789,21 +1219,24 @@ impl<J: JobExt> Executor for WrappedJob<J> {
 
 		let logs_count = logs.len();
 		tracing::debug!(?logs_count, "All tasks completed");
-		let next_job = match job.cleanup(&ctx, &working_output).await {
-			Ok(next_job) => next_job,
+		let cleanup_jobs = match job.cleanup(&ctx, &working_output).await {
+			Ok(cleanup_jobs) => cleanup_jobs,
 			Err(e) => {
 				tracing::error!(?e, "Cleanup failed");
 				logs.push(JobExecuteLog::error(format!(
 					"Cleanup failed: {:?}",
 					e.to_string()
 				)));
-				None
+				vec![]
 			},
 		};
+		let notification = job.notify_output(&ctx, &working_output).await;
+		let mut follow_ups = std::mem::take(&mut self.follow_ups);
+		follow_ups.extend(cleanup_jobs.into_iter().map(|mut follow_up| {
+			follow_up.set_parent_id(job_id);
+			follow_up
+		}));
 
-		// Replace the state with defaults. This is to ensure there is a reset in the
-		// event of a requeue
-		self.initial_state = Some(WorkingState::default());
 		// Put the inner job back into the WrappedJob
 		self.inner_job = Some(inner_job);
 
@@ -812,7 +1245,68 @@ impl<J: JobExt> Executor for WrappedJob<J> {
 		Ok(ExecutorOutput {
 			output: working_output.into_json(),
 			logs,
-			next_job,
+			follow_ups,
+			notification,
 		})
 	}
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{
+		db::{DatabaseMaintenanceJob, SessionCleanupJob},
+		filesystem::scanner::LibraryScanJob,
+	};
+
+	#[test]
+	fn test_follow_ups() {
+		let last_job = SessionCleanupJob::new();
+		let last_job_id = last_job.id();
+		let middle_job = DatabaseMaintenanceJob::new().then(last_job);
+		let middle_job_id = middle_job.id();
+		let first_job = SessionCleanupJob::new().then(middle_job);
+
+		assert_eq!(first_job.parent_id(), None);
+		assert_eq!(first_job.follow_ups().len(), 1);
+
+		let follow_up = &first_job.follow_ups()[0];
+		assert_eq!(follow_up.id(), middle_job_id);
+		assert_eq!(follow_up.parent_id(), Some(first_job.id()));
+		assert_eq!(follow_up.follow_ups()[0].id(), last_job_id);
+		assert_eq!(follow_up.follow_ups()[0].parent_id(), Some(middle_job_id));
+	}
+
+	#[test]
+	fn test_retry_policy() {
+		assert!(!JobRetryPolicy::Count(0).should_retry(1));
+		assert!(JobRetryPolicy::Count(3).should_retry(3));
+		assert!(!JobRetryPolicy::Count(3).should_retry(4));
+		assert!(JobRetryPolicy::Infinite.should_retry(1000));
+		assert!(!JobRetryPolicy::Count(0).allows_retries());
+		assert!(JobRetryPolicy::Count(1).allows_retries());
+	}
+
+	#[test]
+	fn test_retries_exhausted() {
+		// A job without a retry policy is never dead-lettered, however often it ran
+		let mut job = SessionCleanupJob::new();
+		job.attempts = 5;
+		assert!(!job.retries_exhausted());
+
+		let mut job = LibraryScanJob::new("id".to_string(), "/library".to_string(), None);
+		job.attempts = 2;
+		assert!(!job.retries_exhausted());
+		job.attempts = 4;
+		assert!(job.retries_exhausted());
+	}
+
+	#[test]
+	fn test_retry_backoff() {
+		assert_eq!(JobRetryPolicy::backoff(1), Duration::from_secs(5));
+		assert_eq!(JobRetryPolicy::backoff(2), Duration::from_secs(10));
+		assert_eq!(JobRetryPolicy::backoff(4), Duration::from_secs(40));
+		assert_eq!(JobRetryPolicy::backoff(7), MAX_RETRY_DELAY);
+		assert_eq!(JobRetryPolicy::backoff(usize::MAX), MAX_RETRY_DELAY);
+	}
+}
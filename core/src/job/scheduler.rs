@@ -1,113 +1,300 @@
-use std::sync::Arc;
+use std::{str::FromStr, sync::Arc, time::Duration};
+
+use croner::Cron;
+use prisma_client_rust::chrono::{DateTime, Local, TimeZone, Utc};
+use tokio::{
+	sync::{Mutex, Notify},
+	task::JoinHandle,
+};
 
 use crate::{
-	db::entity::LibraryConfig,
-	filesystem::scanner::LibraryScanJob,
-	job::WrappedJob,
-	prisma::{job_schedule_config, library},
-	CoreResult, Ctx,
+	db::{
+		entity::{LibraryConfig, ScheduledJobKind},
+		DatabaseMaintenanceJob, SessionCleanupJob,
+	},
+	filesystem::{
+		image::{ThumbnailGenerationJob, ThumbnailGenerationJobParams},
+		media::analyze_media_job::AnalyzeMediaJob,
+		scanner::LibraryScanJob,
+	},
+	job::{Executor, JobController, JobControllerCommand},
+	prisma::{job_schedule, library, PrismaClient},
+	CoreError, CoreResult,
 };
 
-// TODO: refactor this!
-// 1. Schedule multiple job types (complex config)
-// 2. Last run timestamp, so on boot we don't immediately trigger the scheduled tasks
+/// The longest the scheduler will sleep before re-evaluating the schedules, so that changes to
+/// the system clock are picked up in a reasonable amount of time
+const MAX_SLEEP_DURATION: Duration = Duration::from_secs(60 * 60);
+
+/// Parses a standard 5-field cron expression, e.g. `0 3 * * *`
+pub fn parse_cron_expression(expression: &str) -> CoreResult<Cron> {
+	Cron::new(expression).parse().map_err(|error| {
+		CoreError::BadRequest(format!("Invalid cron expression {expression}: {error}"))
+	})
+}
+
+/// Returns the first time after `after` at which a job with the given cron expression should
+/// run. Cron expressions are evaluated in the local timezone of the server.
+pub fn next_scheduled_run(
+	expression: &str,
+	after: DateTime<Utc>,
+) -> CoreResult<DateTime<Utc>> {
+	next_occurrence(expression, &after.with_timezone(&Local))
+		.map(|next_run_at| next_run_at.with_timezone(&Utc))
+}
+
+fn next_occurrence<Tz: TimeZone>(
+	expression: &str,
+	after: &DateTime<Tz>,
+) -> CoreResult<DateTime<Tz>> {
+	parse_cron_expression(expression)?
+		.find_next_occurrence(after, false)
+		.map_err(|error| {
+			CoreError::BadRequest(format!(
+				"Failed to determine the next run of {expression}: {error}"
+			))
+		})
+}
 
+/// A struct which enqueues jobs according to the persisted job schedules. Each schedule stores
+/// when it should next run, so runs are neither repeated nor lost across restarts: a schedule
+/// which was due while the server was down runs once on boot, then resumes its cadence.
+///
+/// The scheduler should be reloaded (see [`JobScheduler::reload`]) whenever the schedules are
+/// changed, so that changes take effect without a restart.
 pub struct JobScheduler {
-	pub scheduler_handle: Option<tokio::task::JoinHandle<()>>,
+	db: Arc<PrismaClient>,
+	job_controller: Arc<JobController>,
+	reload_notify: Notify,
+	handle: Mutex<Option<JoinHandle<()>>>,
 }
 
 impl JobScheduler {
-	pub async fn init(core_ctx: Arc<Ctx>) -> CoreResult<Arc<Self>> {
-		let client = core_ctx.db.clone();
+	pub fn new(db: Arc<PrismaClient>, job_controller: Arc<JobController>) -> Arc<Self> {
+		Arc::new(Self {
+			db,
+			job_controller,
+			reload_notify: Notify::new(),
+			handle: Mutex::new(None),
+		})
+	}
+
+	/// Start the scheduler loop. Calling this more than once has no effect.
+	pub async fn initialize(self: &Arc<Self>) {
+		let mut handle = self.handle.lock().await;
+		if handle.is_some() {
+			tracing::warn!("Job scheduler has already been initialized");
+			return;
+		}
+
+		let scheduler = self.clone();
+		*handle = Some(tokio::spawn(async move {
+			loop {
+				let sleep_duration = match scheduler.run_due_schedules().await {
+					Ok(Some(next_run_at)) => (next_run_at - Utc::now())
+						.to_std()
+						.unwrap_or_default()
+						.min(MAX_SLEEP_DURATION),
+					Ok(None) => MAX_SLEEP_DURATION,
+					Err(error) => {
+						tracing::error!(?error, "Failed to run scheduled jobs");
+						MAX_SLEEP_DURATION
+					},
+				};
 
-		let result = client
-			.job_schedule_config()
-			.find_first(vec![])
-			.with(job_schedule_config::excluded_libraries::fetch(vec![]))
+				tracing::trace!(?sleep_duration, "Job scheduler sleeping");
+				tokio::select! {
+					_ = tokio::time::sleep(sleep_duration) => {},
+					_ = scheduler.reload_notify.notified() => {
+						tracing::debug!("Reloading job schedules");
+					},
+				}
+			}
+		}));
+	}
+
+	/// Re-read the schedules from the database, e.g. after one has been created or updated
+	pub fn reload(&self) {
+		self.reload_notify.notify_one();
+	}
+
+	/// Enqueue the jobs for every enabled schedule which is due, and record when each should
+	/// next run. Returns the earliest upcoming run, if any.
+	async fn run_due_schedules(&self) -> CoreResult<Option<DateTime<Utc>>> {
+		let schedules = self
+			.db
+			.job_schedule()
+			.find_many(vec![job_schedule::enabled::equals(true)])
+			.with(job_schedule::target_libraries::fetch(vec![]))
 			.exec()
 			.await?;
 
-		if let Some(schedule_config) = result {
-			tracing::info!(
-				?schedule_config,
-				"Found schedule config. Initializing scheduler."
+		let now = Utc::now();
+		let mut earliest_run: Option<DateTime<Utc>> = None;
+
+		for schedule in schedules {
+			let next_run_at = match schedule.next_run_at {
+				Some(next_run_at) if next_run_at > now => next_run_at.into(),
+				Some(_) => {
+					if let Err(error) = self.enqueue_scheduled_job(&schedule).await {
+						tracing::error!(?error, schedule_id = ?schedule.id, "Failed to enqueue scheduled job");
+					}
+					match self.record_run(&schedule, Some(now), now).await {
+						Ok(next_run_at) => next_run_at,
+						Err(error) => {
+							tracing::error!(?error, schedule_id = ?schedule.id, "Failed to update job schedule");
+							continue;
+						},
+					}
+				},
+				// The schedule was just created or changed, so it only needs its next run
+				None => match self.record_run(&schedule, None, now).await {
+					Ok(next_run_at) => next_run_at,
+					Err(error) => {
+						tracing::error!(?error, schedule_id = ?schedule.id, "Failed to update job schedule");
+						continue;
+					},
+				},
+			};
+
+			earliest_run = Some(
+				earliest_run.map_or(next_run_at, |earliest| earliest.min(next_run_at)),
 			);
+		}
+
+		Ok(earliest_run)
+	}
 
-			let excluded_library_ids = schedule_config
-				.excluded_libraries()
-				.cloned()
-				.unwrap_or_else(|e| {
-					tracing::error!(?e, "Failed to fetch excluded libraries");
+	/// Persist the next run of a schedule (and the last run, if it just fired)
+	async fn record_run(
+		&self,
+		schedule: &job_schedule::Data,
+		last_run_at: Option<DateTime<Utc>>,
+		now: DateTime<Utc>,
+	) -> CoreResult<DateTime<Utc>> {
+		let next_run_at = next_scheduled_run(&schedule.cron_expression, now)?;
+
+		let mut params = vec![job_schedule::next_run_at::set(Some(next_run_at.into()))];
+		if let Some(last_run_at) = last_run_at {
+			params.push(job_schedule::last_run_at::set(Some(last_run_at.into())));
+		}
+
+		self.db
+			.job_schedule()
+			.update(job_schedule::id::equals(schedule.id.clone()), params)
+			.exec()
+			.await?;
+
+		Ok(next_run_at)
+	}
+
+	async fn enqueue_scheduled_job(
+		&self,
+		schedule: &job_schedule::Data,
+	) -> CoreResult<()> {
+		let job_kind = ScheduledJobKind::from_str(&schedule.job_kind)
+			.map_err(CoreError::InternalError)?;
+		tracing::info!(schedule_id = ?schedule.id, %job_kind, "Running scheduled job");
+
+		let jobs: Vec<Box<dyn Executor>> = match job_kind {
+			ScheduledJobKind::DatabaseMaintenance => vec![DatabaseMaintenanceJob::new()],
+			ScheduledJobKind::SessionCleanup => vec![SessionCleanupJob::new()],
+			ScheduledJobKind::Scan
+			| ScheduledJobKind::ThumbnailGeneration
+			| ScheduledJobKind::MediaAnalysis => {
+				let target_ids = schedule
+					.target_libraries()
+					.map(|libraries| {
+						libraries.iter().map(|l| l.id.clone()).collect::<Vec<_>>()
+					})
+					.unwrap_or_default();
+				// An empty list of targets means that every library is targeted
+				let where_params = if target_ids.is_empty() {
 					vec![]
-				})
-				.into_iter()
-				.map(|l| l.id)
-				.collect::<Vec<String>>();
-
-			let interval_secs: u64 = schedule_config
-				.interval_secs
-				.try_into()
-				.unwrap_or_else(|e| {
-					tracing::error!(
-						?e,
-						"Failed to convert configured interval_secs to u64"
-					);
-					86400
-				});
-
-			let handle = tokio::spawn(async move {
-				let scheduler_ctx = core_ctx.clone();
-				let client = scheduler_ctx.db.clone();
-				let mut interval =
-					tokio::time::interval(std::time::Duration::from_secs(interval_secs));
-
-				// TODO: Support persisting/resuming the scheduler state so that it can be resumed
-				loop {
-					interval.tick().await;
-
-					tracing::info!("Scanning libraries on schedule");
-					// TODO: optimize query with select!/include!
-					let libraries_to_scan = client
-						.library()
-						.find_many(vec![library::id::not_in_vec(
-							excluded_library_ids.clone(),
-						)])
-						.with(library::config::fetch())
-						.exec()
-						.await
-						.unwrap_or_else(|e| {
-							tracing::error!(?e, "Failed to fetch libraries to scan");
-							vec![]
-						});
-
-					for library in &libraries_to_scan {
-						let library_path = library.path.clone();
-						let config = library.config().ok().take();
-						let result =
-							scheduler_ctx.enqueue_job(WrappedJob::new(LibraryScanJob {
-								id: library.id.clone(),
-								path: library_path,
-								config: config.map(LibraryConfig::from),
-								options: Default::default(),
-							}));
-						if result.is_err() {
-							tracing::error!(
-								?library,
-								"Failed to dispatch scan job for library"
-							);
-						}
-					}
-				}
-			});
-
-			Ok(Arc::new(Self {
-				scheduler_handle: Some(handle),
-			}))
-		} else {
-			tracing::info!("No schedule config found. Scheduling is disabled.");
-			Ok(Arc::new(Self {
-				scheduler_handle: None,
-			}))
+				} else {
+					vec![library::id::in_vec(target_ids)]
+				};
+				let libraries = self
+					.db
+					.library()
+					.find_many(where_params)
+					.with(library::config::fetch())
+					.exec()
+					.await?;
+
+				libraries
+					.into_iter()
+					.filter_map(|library| {
+						let config = library.config().ok().map(LibraryConfig::from);
+						library_job(job_kind, library.id, library.path, config)
+					})
+					.collect()
+			},
+		};
+
+		for job in jobs {
+			self.job_controller
+				.push_command(JobControllerCommand::EnqueueJob(job))
+				.map_err(|e| CoreError::InternalError(e.to_string()))?;
 		}
+
+		Ok(())
+	}
+}
+
+/// Create the job for a [`ScheduledJobKind`] which operates on individual libraries,
+/// or `None` if the kind does not operate on libraries
+fn library_job(
+	job_kind: ScheduledJobKind,
+	library_id: String,
+	library_path: String,
+	config: Option<LibraryConfig>,
+) -> Option<Box<dyn Executor>> {
+	match job_kind {
+		// The config of the library is loaded when the scan starts
+		ScheduledJobKind::Scan => {
+			Some(LibraryScanJob::new(library_id, library_path, None))
+		},
+		ScheduledJobKind::ThumbnailGeneration => Some(ThumbnailGenerationJob::new(
+			config
+				.and_then(|config| config.thumbnail_config)
+				.unwrap_or_default(),
+			ThumbnailGenerationJobParams::single_library(library_id, false),
+		)),
+		ScheduledJobKind::MediaAnalysis => {
+			Some(AnalyzeMediaJob::analyze_library(library_id))
+		},
+		ScheduledJobKind::DatabaseMaintenance | ScheduledJobKind::SessionCleanup => None,
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_next_occurrence() {
+		let after = Utc.with_ymd_and_hms(2025, 2, 3, 12, 30, 0).unwrap();
+
+		assert_eq!(
+			next_occurrence("0 3 * * *", &after).unwrap(),
+			Utc.with_ymd_and_hms(2025, 2, 4, 3, 0, 0).unwrap()
+		);
+		assert_eq!(
+			next_occurrence("*/15 * * * *", &after).unwrap(),
+			Utc.with_ymd_and_hms(2025, 2, 3, 12, 45, 0).unwrap()
+		);
+		// A run at exactly the given time has already happened
+		assert_eq!(
+			next_occurrence("30 12 * * *", &after).unwrap(),
+			Utc.with_ymd_and_hms(2025, 2, 4, 12, 30, 0).unwrap()
+		);
+	}
+
+	#[test]
+	fn test_invalid_cron_expressions() {
+		assert!(parse_cron_expression("every day").is_err());
+		assert!(parse_cron_expression("61 * * * *").is_err());
+		assert!(parse_cron_expression("0 3 * *").is_err());
 	}
 }
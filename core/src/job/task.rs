@@ -75,6 +75,12 @@ pub(crate) async fn job_task_handler<J: JobExt>(
 						let _ = task_handle.await;
 						return Err(JobError::Cancelled(return_sender));
 					},
+					WorkerCommand::Interrupt(return_sender) => {
+						tracing::info!("Interrupt signal received! Aborting task");
+						task_handle.abort();
+						let _ = task_handle.await;
+						return Err(JobError::Interrupted(return_sender));
+					},
 					WorkerCommand::Pause => {
 						worker_ctx.pause().await;
 					}
@@ -3,16 +3,21 @@ use std::{
 	time::{Duration, Instant},
 };
 
+use integrations::NotifierEvent;
+use prisma_client_rust::chrono::{self, Utc};
 use tokio::sync::{broadcast, mpsc, oneshot};
 
 use crate::{
 	config::StumpConfig,
 	event::CoreEvent,
-	job::{JobError, JobStatus},
+	job::{JobError, JobRetryPolicy, JobStatus},
 	prisma::{job, PrismaClient},
 };
 
-use super::{Executor, JobControllerCommand, JobManager, JobProgress, JobUpdate};
+use super::{
+	hooks::publish_job_outcome, Executor, JobControllerCommand, JobManager, JobProgress,
+	JobUpdate,
+};
 
 /// An enum representing the various types of _external_ messages that a worker can send, excluding
 /// internal commands and state events.
@@ -44,11 +49,14 @@ pub enum WorkerStatusEvent {
 	StatusRequested(oneshot::Sender<WorkerStatus>),
 }
 
-/// Commands that the worker can send/receive internally. Every command will kill
-/// the Future that effectively runs the job. Currently, the only command is `Cancel`.
+/// Commands that the worker can send/receive internally. The `Cancel` and `Interrupt`
+/// commands will kill the Future that effectively runs the job.
 #[derive(Debug)]
 pub enum WorkerCommand {
 	Cancel(oneshot::Sender<()>),
+	/// Stop the job without resolving it, persisting its state so that it may be resumed
+	/// the next time the server starts
+	Interrupt(oneshot::Sender<()>),
 	Pause,
 	Resume,
 }
@@ -210,18 +218,43 @@ impl WorkerCtx {
 	}
 }
 
+#[cfg(test)]
+impl WorkerCtx {
+	/// Create a context for running the steps of a job directly in tests. Nothing listens to
+	/// its channels, so any events or commands it sends are dropped.
+	pub(crate) fn mock(db: PrismaClient, config: StumpConfig) -> Self {
+		let (core_event_tx, _) = broadcast::channel(1);
+		let (_, commands_rx) = async_channel::unbounded();
+		let (job_controller_tx, _) = mpsc::unbounded_channel();
+		let (status_tx, _) = async_channel::unbounded();
+
+		Self {
+			job_id: "test_job".to_string(),
+			db: Arc::new(db),
+			config: Arc::new(config),
+			core_event_tx,
+			commands_rx,
+			job_controller_tx,
+			status_tx,
+		}
+	}
+}
+
 /// An instance of a running job, represented by a worker. The worker is responsible for
 /// kicking off the job and managing its lifecycle.
 pub struct Worker {
 	/// The sender through which the worker can send commands to itself. The corresponding
 	/// receiver is stored in the worker context.
 	commands_tx: async_channel::Sender<WorkerCommand>,
+	/// The ID of the library the running job has locked, if any
+	library_lock: Option<String>,
 }
 
 impl Worker {
 	/// Create a new worker instance and its context
 	fn new(
 		job_id: &str,
+		library_lock: Option<String>,
 		db: Arc<PrismaClient>,
 		config: Arc<StumpConfig>,
 		core_event_tx: broadcast::Sender<CoreEvent>,
@@ -241,7 +274,14 @@ impl Worker {
 			status_tx,
 		};
 
-		Ok((Self { commands_tx }, worker_ctx, status_rx))
+		Ok((
+			Self {
+				commands_tx,
+				library_lock,
+			},
+			worker_ctx,
+			status_rx,
+		))
 	}
 
 	/// Create a new [Worker] instance and immediately spawn it. This is the main entry point
@@ -260,6 +300,7 @@ impl Worker {
 		let job_id = job.id().to_string();
 		let (worker, worker_ctx, status_rx) = Worker::new(
 			job_id.as_str(),
+			job.library_lock(),
 			db,
 			config,
 			core_event_tx,
@@ -284,6 +325,11 @@ impl Worker {
 		Arc::new(self)
 	}
 
+	/// The ID of the library the running job has locked, if any
+	pub fn library_lock(&self) -> Option<&str> {
+		self.library_lock.as_deref()
+	}
+
 	/// Send a pause command to the worker manager
 	pub async fn pause(&self) {
 		self.commands_tx
@@ -340,6 +386,34 @@ impl Worker {
 			tracing::error!("Failed to send cancel signal to worker");
 		}
 	}
+
+	/// Interrupts the job running in the worker, e.g. during a shutdown. The job will persist
+	/// a restore point so that it can be resumed the next time the server starts
+	pub async fn interrupt(&self) {
+		let (tx, rx) = oneshot::channel();
+
+		let send_received = self
+			.commands_tx
+			.send(WorkerCommand::Interrupt(tx))
+			.await
+			.is_ok();
+
+		if send_received {
+			rx.await.map_or_else(
+				|error| {
+					tracing::error!(
+						?error,
+						"Error while waiting for interrupt confirmation"
+					);
+				},
+				|_| {
+					tracing::trace!("Received interrupt confirmation");
+				},
+			);
+		} else {
+			tracing::error!("Failed to send interrupt signal to worker");
+		}
+	}
 }
 
 /// A helper struct to manage the worker's status and lifecycle. This struct is responsible for
@@ -382,6 +456,8 @@ impl WorkerManager {
 			tokio::select! {
 				executor_result = &mut executor_handle => {
 					let elapsed = start.elapsed();
+					// The follow-up jobs to run, which is only populated if the job succeeded
+					let mut follow_ups = vec![];
 					match executor_result {
 						Ok((returned_executor, result)) => {
 							tracing::debug!(
@@ -393,14 +469,79 @@ impl WorkerManager {
 								Ok(mut output) => {
 									tracing::info!("Job completed successfully!");
 									finalizer_ctx.report_progress(JobProgress::finished());
-									let next_job = output.next_job.take();
+									follow_ups = std::mem::take(&mut output.follow_ups);
+									let notification = output.notification.take();
 									let result = returned_executor
 											.persist_output(finalizer_ctx.clone(), output, elapsed)
 											.await;
 									tracing::trace!(?result, "Output persisted?");
-									if let Some(next_job) = next_job {
-										finalizer_ctx.send_manager_command(JobControllerCommand::EnqueueJob(next_job));
+									if let Some(event) = notification {
+										publish_job_outcome(&finalizer_ctx, returned_executor.name(), event);
 									}
+									// Any declared follow-ups which won't run, e.g. because a task failed, are cancelled
+									let follow_up_ids = follow_ups.iter().map(|job| job.id().to_string()).collect();
+									let result = handle_cancel_pending_children(job_id.clone(), &finalizer_ctx.db, follow_up_ids).await;
+									tracing::trace!(?result, "Pending children cancelled?");
+								},
+								Err(JobError::Interrupted(return_tx)) => {
+									tracing::info!("Job interrupted, it will be resumed on the next startup");
+									// The job is left as paused, rather than resolved, so that it is
+									// picked up by [JobManager::initialize]
+									let _ = handle_failure_status(job_id.clone(), JobStatus::Paused, &finalizer_ctx.db, elapsed).await;
+									return_tx.send(()).map_or_else(
+										|error| {
+											tracing::error!(
+												?error,
+												"Failed to send interrupt confirmation"
+											);
+										},
+										|_| {
+											tracing::trace!("Interrupt confirmation sent");
+										},
+									);
+								},
+								Err(JobError::Cancelled(return_tx)) => {
+									tracing::info!("Job cancelled");
+									let result = handle_cancel_pending_children(job_id.clone(), &finalizer_ctx.db, vec![]).await;
+									tracing::trace!(?result, "Pending children cancelled?");
+									let result = returned_executor
+										.persist_failure(
+											finalizer_ctx,
+											JobStatus::Cancelled,
+											elapsed,
+										)
+										.await;
+									tracing::trace!(?result, "Cancellation persisted?");
+									return_tx.send(()).map_or_else(
+										|error| {
+											tracing::error!(
+												?error,
+												"Failed to send cancel confirmation"
+											);
+										},
+										|_| {
+											tracing::trace!("Cancel confirmation sent");
+										},
+									);
+								},
+								Err(error) if returned_executor.should_requeue() => {
+									let delay = JobRetryPolicy::backoff(returned_executor.attempts());
+									tracing::warn!(?error, ?delay, "Job failed with critical error, requeuing");
+									finalizer_ctx.report_progress(JobProgress::status_msg(
+										JobStatus::Queued,
+										&format!("Job failed, retrying in {}s: {error}", delay.as_secs()),
+									));
+
+									// The job is saved so that the retry survives a restart, see [JobManager::initialize]
+									let save_state = returned_executor.save_state().and_then(|save_state| {
+										serde_json::to_vec(&save_state).map_err(|error| JobError::StateSaveFailed(error.to_string()))
+									}).map_err(|error| {
+										tracing::error!(?error, "Failed to save job for retry");
+									}).ok();
+									let result = handle_retry_status(job_id.clone(), delay, save_state, &finalizer_ctx.db, elapsed).await;
+									tracing::trace!(?result, "Retry persisted?");
+
+									self.manager.clone().schedule_retry(returned_executor, delay).await;
 								},
 								Err(error) => {
 									tracing::error!(?error, "Job failed with critical error");
@@ -409,30 +550,28 @@ impl WorkerManager {
 										&format!("Job failed: {error}"),
 									));
 
+									// A job which has exhausted its retries is dead-lettered, so that it
+									// can be told apart from a job which was never retried
+									let status = if returned_executor.retries_exhausted() {
+										JobStatus::DeadLetter
+									} else {
+										JobStatus::Failed
+									};
+									let result = handle_cancel_pending_children(job_id.clone(), &finalizer_ctx.db, vec![]).await;
+									tracing::trace!(?result, "Pending children cancelled?");
+									publish_job_outcome(
+										&finalizer_ctx,
+										returned_executor.name(),
+										NotifierEvent::JobFailed {
+											job_name: returned_executor.name().to_string(),
+											description: returned_executor.description(),
+											error: error.to_string(),
+										},
+									);
 									let result = returned_executor
-										.persist_failure(
-											finalizer_ctx,
-											JobStatus::Failed,
-											elapsed,
-										)
+										.persist_failure(finalizer_ctx, status, elapsed)
 										.await;
 									tracing::trace!(?result, "Failure persisted?");
-
-									if let JobError::Cancelled(return_tx) = error {
-										return_tx.send(()).map_or_else(
-											|error| {
-												tracing::error!(
-													?error,
-													"Failed to send cancel confirmation"
-												);
-											},
-											|_| {
-												tracing::trace!("Cancel confirmation sent");
-											},
-										);
-									} else if returned_executor.should_requeue() {
-										// TODO: requeue the job
-									}
 								},
 							}
 						},
@@ -444,9 +583,10 @@ impl WorkerManager {
 								&format!("Job failed: {join_error}"),
 							));
 							let _ = handle_failure_status(job_id.clone(), JobStatus::Failed, &finalizer_ctx.db, elapsed).await;
+							let _ = handle_cancel_pending_children(job_id.clone(), &finalizer_ctx.db, vec![]).await;
 						}
 					}
-					return self.manager.complete(job_id).await;
+					return self.manager.complete(job_id, follow_ups).await;
 				},
 
 				Ok(status_event) = &mut status_rx_fut => {
@@ -513,6 +653,34 @@ pub(crate) async fn handle_failure_status(
 	Ok(())
 }
 
+/// Update the job to reflect that it failed and will be retried after the given delay. The
+/// save state of the job is replaced, so that the retry starts from the beginning
+async fn handle_retry_status(
+	job_id: String,
+	delay: Duration,
+	save_state: Option<Vec<u8>>,
+	client: &PrismaClient,
+	elapsed: Duration,
+) -> Result<(), JobError> {
+	let next_retry_at = Utc::now()
+		+ chrono::Duration::from_std(delay).unwrap_or_else(|_| chrono::Duration::zero());
+	client
+		.job()
+		.update(
+			job::id::equals(job_id),
+			vec![
+				job::status::set(JobStatus::Queued.to_string()),
+				job::next_retry_at::set(Some(next_retry_at.into())),
+				job::save_state::set(save_state),
+				job::ms_elapsed::set(elapsed.as_millis().try_into().unwrap_or(i64::MAX)),
+			],
+		)
+		.exec()
+		.await?;
+
+	Ok(())
+}
+
 /// Cancel a job by its ID
 pub(crate) async fn handle_do_cancel(
 	job_id: String,
@@ -522,13 +690,55 @@ pub(crate) async fn handle_do_cancel(
 	handle_failure_status(job_id, JobStatus::Cancelled, client, elapsed).await
 }
 
+/// Cancel the pending jobs which descend from a job, e.g. because the job failed or was
+/// cancelled and so its follow-ups will never run. Jobs which have already started, and those
+/// with an ID in `excluded_ids`, are left alone.
+pub(crate) async fn handle_cancel_pending_children(
+	job_id: String,
+	client: &PrismaClient,
+	excluded_ids: Vec<String>,
+) -> Result<(), JobError> {
+	let mut parent_ids = vec![job_id];
+
+	while !parent_ids.is_empty() {
+		let pending_children = client
+			.job()
+			.find_many(vec![
+				job::parent_id::in_vec(parent_ids),
+				job::status::equals(JobStatus::Queued.to_string()),
+				job::id::not_in_vec(excluded_ids.clone()),
+			])
+			.exec()
+			.await?;
+		parent_ids = pending_children.into_iter().map(|job| job.id).collect();
+
+		if !parent_ids.is_empty() {
+			let cancelled_count = client
+				.job()
+				.update_many(
+					vec![job::id::in_vec(parent_ids.clone())],
+					vec![job::status::set(JobStatus::Cancelled.to_string())],
+				)
+				.exec()
+				.await?;
+			tracing::debug!(cancelled_count, "Cancelled pending child jobs");
+		}
+	}
+
+	Ok(())
+}
+
 /// Update the job status to `Running` in the database
 async fn handle_job_start(client: &PrismaClient, job_id: String) -> Result<(), JobError> {
 	let started_job = client
 		.job()
 		.update(
 			job::id::equals(job_id),
-			vec![job::status::set(JobStatus::Running.to_string())],
+			vec![
+				job::status::set(JobStatus::Running.to_string()),
+				job::attempts::increment(1),
+				job::next_retry_at::set(None),
+			],
 		)
 		.exec()
 		.await?;
@@ -13,6 +13,7 @@ pub mod db;
 mod event;
 pub mod filesystem;
 pub mod job;
+pub mod metadata;
 pub mod opds;
 mod utils;
 
@@ -26,7 +27,7 @@ pub mod prisma;
 use config::logging::STUMP_SHADOW_TEXT;
 use config::StumpConfig;
 use db::{DBPragma, JournalMode};
-use job::{JobController, JobScheduler};
+use job::JobController;
 use prisma::server_config;
 
 pub use context::Ctx;
@@ -228,8 +229,14 @@ impl StumpCore {
 		}
 	}
 
-	pub async fn init_scheduler(&self) -> Result<Arc<JobScheduler>, CoreError> {
-		JobScheduler::init(self.ctx.arced()).await
+	/// Starts the scheduler, which enqueues jobs according to the persisted job schedules
+	pub async fn init_scheduler(&self) {
+		self.ctx.job_scheduler.initialize().await
+	}
+
+	/// Starts watching any libraries which have opted into filesystem watching
+	pub async fn init_library_watcher(&self) -> Result<(), CoreError> {
+		self.ctx.library_watcher.initialize().await
 	}
 }
 
@@ -252,6 +259,7 @@ mod tests {
 		},
 		filesystem::{image::*, scanner::*, *},
 		job::*,
+		metadata::*,
 		CoreEvent,
 	};
 
@@ -293,13 +301,15 @@ mod tests {
 		// file.write_all(format!("{}\n\n", ts_export::<CoreJobOutput>()?).as_bytes())?;
 		// TODO: Fix this... Must move all job defs to the core... Otherwise, the `unknown` type swallows the others in the union
 		file.write_all(
-			"export type CoreJobOutput = LibraryScanOutput | SeriesScanOutput | ThumbnailGenerationOutput\n\n".to_string()
+			"export type CoreJobOutput = LibraryScanOutput | SeriesScanOutput | ThumbnailGenerationOutput | ScanDryRunOutput | ComicVineMatchOutput | IsbnEnrichmentOutput | MetadataImportOutput\n\n".to_string()
 			.as_bytes(),
 		)?;
 		file.write_all(format!("{}\n\n", ts_export::<JobUpdate>()?).as_bytes())?;
 		file.write_all(format!("{}\n\n", ts_export::<JobProgress>()?).as_bytes())?;
 		file.write_all(format!("{}\n\n", ts_export::<LibraryScanOutput>()?).as_bytes())?;
 		file.write_all(format!("{}\n\n", ts_export::<SeriesScanOutput>()?).as_bytes())?;
+		file.write_all(format!("{}\n\n", ts_export::<SeriesDryRunReport>()?).as_bytes())?;
+		file.write_all(format!("{}\n\n", ts_export::<ScanDryRunOutput>()?).as_bytes())?;
 		file.write_all(
 			format!("{}\n\n", ts_export::<ThumbnailGenerationJobVariant>()?).as_bytes(),
 		)?;
@@ -309,6 +319,26 @@ mod tests {
 		file.write_all(
 			format!("{}\n\n", ts_export::<ThumbnailGenerationOutput>()?).as_bytes(),
 		)?;
+		file.write_all(format!("{}\n\n", ts_export::<ComicVineCandidate>()?).as_bytes())?;
+		file.write_all(
+			format!("{}\n\n", ts_export::<ComicVineMatchOutput>()?).as_bytes(),
+		)?;
+		file.write_all(
+			format!("{}\n\n", ts_export::<IsbnEnrichmentOutput>()?).as_bytes(),
+		)?;
+		file.write_all(format!("{}\n\n", ts_export::<MetadataRecordKind>()?).as_bytes())?;
+		file.write_all(
+			format!("{}\n\n", ts_export::<MetadataFieldChange>()?).as_bytes(),
+		)?;
+		file.write_all(
+			format!("{}\n\n", ts_export::<MetadataImportChange>()?).as_bytes(),
+		)?;
+		file.write_all(
+			format!("{}\n\n", ts_export::<MetadataImportOutput>()?).as_bytes(),
+		)?;
+		file.write_all(
+			format!("{}\n\n", ts_export::<MetadataTransferFormat>()?).as_bytes(),
+		)?;
 
 		file.write_all(format!("{}\n\n", ts_export::<User>()?).as_bytes())?;
 		file.write_all(format!("{}\n\n", ts_export::<PartialUser>()?).as_bytes())?;
@@ -359,17 +389,55 @@ mod tests {
 		file.write_all(format!("{}\n\n", ts_export::<FileStatus>()?).as_bytes())?;
 		file.write_all(format!("{}\n\n", ts_export::<Library>()?).as_bytes())?;
 		file.write_all(format!("{}\n\n", ts_export::<LibraryPattern>()?).as_bytes())?;
+		file.write_all(format!("{}\n\n", ts_export::<LibraryWatchMode>()?).as_bytes())?;
 		file.write_all(format!("{}\n\n", ts_export::<LibraryScanMode>()?).as_bytes())?;
 		file.write_all(format!("{}\n\n", ts_export::<IgnoreRules>()?).as_bytes())?;
+		file.write_all(
+			format!("{}\n\n", ts_export::<FilenamePatternKind>()?).as_bytes(),
+		)?;
+		file.write_all(format!("{}\n\n", ts_export::<FilenamePattern>()?).as_bytes())?;
+		file.write_all(format!("{}\n\n", ts_export::<FilenamePatterns>()?).as_bytes())?;
+		file.write_all(
+			format!("{}\n\n", ts_export::<MetadataProviderPriority>()?).as_bytes(),
+		)?;
+		file.write_all(format!("{}\n\n", ts_export::<FilenameMetadata>()?).as_bytes())?;
 		file.write_all(format!("{}\n\n", ts_export::<LibraryConfig>()?).as_bytes())?;
 		file.write_all(format!("{}\n\n", ts_export::<LibraryStats>()?).as_bytes())?;
 
 		file.write_all(format!("{}\n\n", ts_export::<SeriesMetadata>()?).as_bytes())?;
 		file.write_all(format!("{}\n\n", ts_export::<Series>()?).as_bytes())?;
 		file.write_all(format!("{}\n\n", ts_export::<MediaMetadata>()?).as_bytes())?;
+		file.write_all(format!("{}\n\n", ts_export::<MergeStrategy>()?).as_bytes())?;
+		file.write_all(
+			format!("{}\n\n", ts_export::<MediaMetadataConflict>()?).as_bytes(),
+		)?;
+		file.write_all(
+			format!("{}\n\n", ts_export::<MetadataProviderKind>()?).as_bytes(),
+		)?;
+		file.write_all(
+			format!("{}\n\n", ts_export::<MetadataProviderConfig>()?).as_bytes(),
+		)?;
+		file.write_all(
+			format!("{}\n\n", ts_export::<MetadataProviderConfigInput>()?).as_bytes(),
+		)?;
+		file.write_all(format!("{}\n\n", ts_export::<MetadataCandidate>()?).as_bytes())?;
 		file.write_all(format!("{}\n\n", ts_export::<Media>()?).as_bytes())?;
 		file.write_all(format!("{}\n\n", ts_export::<Bookmark>()?).as_bytes())?;
 		file.write_all(format!("{}\n\n", ts_export::<MediaAnnotation>()?).as_bytes())?;
+		file.write_all(format!("{}\n\n", ts_export::<StoryArc>()?).as_bytes())?;
+		file.write_all(format!("{}\n\n", ts_export::<StoryArcMember>()?).as_bytes())?;
+		file.write_all(format!("{}\n\n", ts_export::<CreatorRole>()?).as_bytes())?;
+		file.write_all(format!("{}\n\n", ts_export::<Person>()?).as_bytes())?;
+		file.write_all(format!("{}\n\n", ts_export::<PersonRoleCount>()?).as_bytes())?;
+		file.write_all(format!("{}\n\n", ts_export::<MediaCredit>()?).as_bytes())?;
+		file.write_all(format!("{}\n\n", ts_export::<Collection>()?).as_bytes())?;
+		file.write_all(format!("{}\n\n", ts_export::<CollectionItem>()?).as_bytes())?;
+		file.write_all(
+			format!("{}\n\n", ts_export::<CollectionItemInput>()?).as_bytes(),
+		)?;
+		file.write_all(
+			format!("{}\n\n", ts_export::<CreateOrUpdateCollection>()?).as_bytes(),
+		)?;
 		file.write_all(
 			format!("{}\n\n", ts_export::<ActiveReadingSession>()?).as_bytes(),
 		)?;
@@ -414,6 +482,12 @@ mod tests {
 		)?;
 		file.write_all(format!("{}\n\n", ts_export::<SeriesSmartFilter>()?).as_bytes())?;
 		file.write_all(format!("{}\n\n", ts_export::<LibrarySmartFilter>()?).as_bytes())?;
+		file.write_all(
+			format!("{}\n\n", ts_export::<StoryArcSmartFilter>()?).as_bytes(),
+		)?;
+		file.write_all(
+			format!("{}\n\n", ts_export::<StoryArcMemberSmartFilter>()?).as_bytes(),
+		)?;
 		file.write_all(format!("{}\n\n", ts_export::<SmartListView>()?).as_bytes())?;
 
 		file.write_all(format!("{}\n\n", ts_export::<BookClub>()?).as_bytes())?;
@@ -455,7 +529,10 @@ mod tests {
 		file.write_all(format!("{}\n\n", ts_export::<EpubContent>()?).as_bytes())?;
 
 		file.write_all(format!("{}\n\n", ts_export::<JobStatus>()?).as_bytes())?;
-		file.write_all(format!("{}\n\n", ts_export::<JobSchedulerConfig>()?).as_bytes())?;
+		file.write_all(format!("{}\n\n", ts_export::<ScheduledJobKind>()?).as_bytes())?;
+		file.write_all(format!("{}\n\n", ts_export::<JobSchedule>()?).as_bytes())?;
+		file.write_all(format!("{}\n\n", ts_export::<JobPriority>()?).as_bytes())?;
+		file.write_all(format!("{}\n\n", ts_export::<QueuedJob>()?).as_bytes())?;
 
 		file.write_all(format!("{}\n\n", ts_export::<ReadingListItem>()?).as_bytes())?;
 		file.write_all(
@@ -0,0 +1,722 @@
+use std::collections::VecDeque;
+
+use integrations::{ComicVineClient, ComicVineError, ComicVineVolume};
+use serde::{Deserialize, Serialize};
+use specta::Type;
+
+use crate::{
+	db::entity::{CoreJobOutput, LibraryConfig, MergeStrategy, SeriesMetadata},
+	job::{
+		error::JobError, Executor, JobExecuteLog, JobExt, JobOutputExt, JobProgress,
+		JobRetryPolicy, JobTaskOutput, WorkerCtx, WorkingState, WrappedJob,
+	},
+	metadata::{apply_provider_metadata, remove_orphaned_people},
+	prisma::{library, media, series, series_metadata},
+	CoreEvent,
+};
+
+use super::{get_comicvine_client, issue_to_metadata, score_volume, strip_html};
+
+/// The maximum number of candidate volumes reported when a series has not been matched yet
+const MAX_CANDIDATES: usize = 10;
+
+#[derive(Serialize, Deserialize)]
+pub enum ComicVineMatchTask {
+	/// Fill the metadata of a media using the ComicVine issue it was matched to
+	MatchIssue { media_id: String, issue_id: i64 },
+}
+
+/// A job which matches a series to a ComicVine volume and fills the metadata of the series
+/// and its books from it.
+///
+/// When the series already knows its ComicVine ID (`comicid`), the volume is fetched directly
+/// and each book is matched to an issue by its number. Otherwise, ComicVine is searched using
+/// the name and year of the series and the closest volumes are reported as candidates, one of
+/// which must be confirmed before any metadata is written.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct ComicVineMatchJob {
+	pub series_id: String,
+	/// The strategy used to reconcile the fetched metadata with the existing metadata. When
+	/// not provided, the merge strategy of the library is used
+	pub merge_strategy: Option<MergeStrategy>,
+	/// The publisher of the matched volume, which ComicVine only provides per volume
+	#[serde(default)]
+	publisher: Option<String>,
+}
+
+impl ComicVineMatchJob {
+	pub fn new(
+		series_id: String,
+		merge_strategy: Option<MergeStrategy>,
+	) -> Box<WrappedJob<ComicVineMatchJob>> {
+		WrappedJob::new(Self {
+			series_id,
+			merge_strategy,
+			publisher: None,
+		})
+	}
+
+	fn strategy(&self) -> MergeStrategy {
+		self.merge_strategy.unwrap_or_default()
+	}
+
+	/// Match the series to a ComicVine volume, see [ComicVineMatchJob] for how the volume is
+	/// found. Returns the tasks which fill the metadata of each book from its issue
+	async fn match_series(
+		&mut self,
+		ctx: &WorkerCtx,
+		client: &ComicVineClient,
+	) -> Result<WorkingState<ComicVineMatchOutput, ComicVineMatchTask>, JobError> {
+		let mut output = ComicVineMatchOutput::default();
+
+		ctx.report_progress(JobProgress::msg("Loading series"));
+		let series = ctx
+			.db
+			.series()
+			.find_unique(series::id::equals(self.series_id.clone()))
+			.with(series::metadata::fetch())
+			.with(series::library::fetch().with(library::config::fetch()))
+			.exec()
+			.await?
+			.ok_or(JobError::InitFailed("Series not found".to_string()))?;
+
+		if self.merge_strategy.is_none() {
+			self.merge_strategy = series
+				.library()
+				.ok()
+				.flatten()
+				.and_then(|library| library.config().ok())
+				.map(|config| LibraryConfig::from(config).merge_strategy);
+		}
+
+		let series_metadata = series
+			.metadata()
+			.ok()
+			.flatten()
+			.map(|metadata| SeriesMetadata::from(metadata.to_owned()));
+		let media = ctx
+			.db
+			.media()
+			.find_many(vec![media::series_id::equals(Some(self.series_id.clone()))])
+			.with(media::metadata::fetch())
+			.exec()
+			.await?;
+
+		let Some(volume_id) = series_metadata.as_ref().and_then(|m| m.comicid) else {
+			ctx.report_progress(JobProgress::msg("Searching ComicVine"));
+			let name = series_metadata
+				.as_ref()
+				.and_then(|m| m.title.clone())
+				.unwrap_or_else(|| series.name.clone());
+			let year = series_metadata
+				.as_ref()
+				.and_then(|m| m.volume)
+				.filter(|volume| *volume >= 1900)
+				.or_else(|| {
+					media
+						.iter()
+						.filter_map(|m| m.metadata().ok().flatten())
+						.filter_map(|metadata| metadata.year)
+						.min()
+				});
+
+			let volumes = client
+				.search_volumes(&name)
+				.await
+				.map_err(|error| JobError::InitFailed(error.to_string()))?;
+			let mut candidates = volumes
+				.into_iter()
+				.map(|volume| {
+					let score = score_volume(&volume, &name, year, Some(media.len()));
+					ComicVineCandidate {
+						volume_id: volume.id as i32,
+						start_year: volume.year(),
+						publisher: volume.publisher.map(|publisher| publisher.name),
+						issue_count: volume.count_of_issues.map(|count| count as i32),
+						site_detail_url: volume.site_detail_url,
+						name: volume.name,
+						score,
+					}
+				})
+				.collect::<Vec<_>>();
+			candidates.sort_by(|a, b| b.score.total_cmp(&a.score));
+			candidates.truncate(MAX_CANDIDATES);
+
+			tracing::debug!(
+				candidates = candidates.len(),
+				"Found ComicVine candidates for series"
+			);
+			ctx.report_progress(JobProgress::msg(
+				format!("Found {} candidate volumes", candidates.len()).as_str(),
+			));
+			output.candidates = candidates;
+
+			return Ok(WorkingState {
+				output: Some(output),
+				tasks: VecDeque::new(),
+				completed_tasks: 0,
+				logs: vec![],
+			});
+		};
+
+		ctx.report_progress(JobProgress::msg("Fetching ComicVine volume"));
+		let volume = client
+			.get_volume(i64::from(volume_id))
+			.await
+			.map_err(|error| JobError::InitFailed(error.to_string()))?;
+		self.publisher = volume.publisher.as_ref().map(|p| p.name.clone());
+		self.update_series_metadata(ctx, series_metadata, &volume)
+			.await?;
+		output.matched_volume_id = Some(volume_id);
+
+		ctx.report_progress(JobProgress::msg("Fetching ComicVine issues"));
+		let issues = client
+			.get_volume_issues(volume.id)
+			.await
+			.map_err(|error| JobError::InitFailed(error.to_string()))?;
+
+		let mut tasks = VecDeque::new();
+		for book in media {
+			let number = book.metadata().ok().flatten().and_then(|m| m.number);
+			let issue = number.and_then(|number| {
+				issues.iter().find(|issue| {
+					issue
+						.number()
+						.map_or(false, |n| (n - number).abs() < f64::EPSILON)
+				})
+			});
+
+			match issue {
+				Some(issue) => tasks.push_back(ComicVineMatchTask::MatchIssue {
+					media_id: book.id,
+					issue_id: issue.id,
+				}),
+				None => output.unmatched_media += 1,
+			}
+		}
+
+		ctx.report_progress(JobProgress::msg(
+			format!("Matched {} books to ComicVine issues", tasks.len()).as_str(),
+		));
+
+		Ok(WorkingState {
+			output: Some(output),
+			tasks,
+			completed_tasks: 0,
+			logs: vec![],
+		})
+	}
+
+	/// Fill the metadata of a media from the ComicVine issue it was matched to
+	async fn match_issue(
+		&self,
+		ctx: &WorkerCtx,
+		client: &ComicVineClient,
+		media_id: &str,
+		issue_id: i64,
+	) -> Result<JobTaskOutput<Self>, JobError> {
+		let mut output = ComicVineMatchOutput::default();
+		let mut logs = vec![];
+
+		let issue = match client.get_issue(issue_id).await {
+			Ok(issue) => issue,
+			Err(ComicVineError::NotFound) => {
+				logs.push(JobExecuteLog::warn(
+					format!("ComicVine issue {issue_id} no longer exists").as_str(),
+				));
+				output.unmatched_media += 1;
+				return Ok(JobTaskOutput {
+					output,
+					logs,
+					subtasks: vec![],
+				});
+			},
+			Err(error) => return Err(JobError::TaskFailed(error.to_string())),
+		};
+		ctx.report_progress(JobProgress::msg(
+			format!("Updating metadata from issue #{}", issue.id).as_str(),
+		));
+
+		let metadata = issue_to_metadata(&issue, self.publisher.clone());
+		apply_provider_metadata(&ctx.db, media_id, metadata, self.strategy()).await?;
+		output.matched_media += 1;
+
+		Ok(JobTaskOutput {
+			output,
+			logs,
+			subtasks: vec![],
+		})
+	}
+
+	/// Fill the metadata of the series from the matched volume. Series metadata does not record
+	/// conflicts, so anything other than [MergeStrategy::Replace] only fills empty fields
+	async fn update_series_metadata(
+		&self,
+		ctx: &WorkerCtx,
+		existing: Option<SeriesMetadata>,
+		volume: &ComicVineVolume,
+	) -> Result<(), JobError> {
+		let replace = self.strategy() == MergeStrategy::Replace;
+		let pick = |current: Option<String>, incoming: Option<String>| {
+			let incoming = incoming.filter(|value| !value.trim().is_empty());
+			if replace {
+				incoming.or(current)
+			} else {
+				current.or(incoming)
+			}
+		};
+
+		let summary = volume
+			.description
+			.as_deref()
+			.map(strip_html)
+			.or_else(|| volume.deck.clone());
+		let metadata = match existing {
+			Some(existing) => {
+				let mut metadata = SeriesMetadata {
+					title: pick(existing.title.clone(), Some(volume.name.clone())),
+					summary: pick(existing.summary.clone(), summary),
+					publisher: pick(existing.publisher.clone(), self.publisher.clone()),
+					comicid: Some(volume.id as i32),
+					volume: if replace {
+						volume.year().or(existing.volume)
+					} else {
+						existing.volume.or(volume.year())
+					},
+					..existing.clone()
+				};
+				// Fields which were edited manually are never overwritten by a match
+				metadata.keep_locked(&existing);
+				metadata
+			},
+			None => SeriesMetadata {
+				_type: "comicSeries".to_string(),
+				title: Some(volume.name.clone()),
+				summary,
+				publisher: self.publisher.clone(),
+				imprint: None,
+				comicid: Some(volume.id as i32),
+				volume: volume.year(),
+				booktype: None,
+				age_rating: None,
+				status: None,
+				locked_fields: vec![],
+			},
+		};
+
+		let (meta_type, params) = metadata.create_action();
+		ctx.db
+			.series_metadata()
+			.upsert(
+				series_metadata::series_id::equals(self.series_id.clone()),
+				(
+					meta_type,
+					series::id::equals(self.series_id.clone()),
+					params.clone(),
+				),
+				params,
+			)
+			.exec()
+			.await?;
+
+		Ok(())
+	}
+}
+
+/// A ComicVine volume which could be the series being matched
+#[derive(Clone, Serialize, Deserialize, Debug, Type)]
+pub struct ComicVineCandidate {
+	pub volume_id: i32,
+	pub name: String,
+	pub start_year: Option<i32>,
+	pub publisher: Option<String>,
+	pub issue_count: Option<i32>,
+	pub site_detail_url: Option<String>,
+	/// How closely the volume matches the series, from 0 to 1
+	pub score: f32,
+}
+
+/// The output of a ComicVine match, which is reported to clients once the match completes
+#[derive(Clone, Serialize, Deserialize, Default, Debug, Type)]
+pub struct ComicVineMatchOutput {
+	/// The volumes which could be the series, ordered from the closest match. These are only
+	/// reported when the series has not been matched to a volume yet
+	candidates: Vec<ComicVineCandidate>,
+	/// The ComicVine ID of the volume the series was matched to, if any
+	matched_volume_id: Option<i32>,
+	/// The number of media whose metadata was filled from a ComicVine issue
+	matched_media: u64,
+	/// The number of media which could not be matched to a ComicVine issue
+	unmatched_media: u64,
+}
+
+impl JobOutputExt for ComicVineMatchOutput {
+	fn update(&mut self, updated: Self) {
+		self.candidates.extend(updated.candidates);
+		self.matched_volume_id = updated.matched_volume_id.or(self.matched_volume_id);
+		self.matched_media += updated.matched_media;
+		self.unmatched_media += updated.unmatched_media;
+	}
+}
+
+#[async_trait::async_trait]
+impl JobExt for ComicVineMatchJob {
+	const NAME: &'static str = "comicvine_match";
+	// ComicVine is rate limited and occasionally unavailable, so failed lookups are retried
+	const MAX_TASK_RETRIES: JobRetryPolicy = JobRetryPolicy::Count(3);
+
+	type Output = ComicVineMatchOutput;
+	type Task = ComicVineMatchTask;
+
+	fn description(&self) -> Option<String> {
+		Some(self.series_id.clone())
+	}
+
+	async fn init(
+		&mut self,
+		ctx: &WorkerCtx,
+	) -> Result<WorkingState<Self::Output, Self::Task>, JobError> {
+		let client = get_comicvine_client(&ctx.db)
+			.await
+			.map_err(|error| JobError::InitFailed(error.to_string()))?;
+		self.match_series(ctx, &client).await
+	}
+
+	async fn cleanup(
+		&self,
+		ctx: &WorkerCtx,
+		output: &Self::Output,
+	) -> Result<Vec<Box<dyn Executor>>, JobError> {
+		ctx.send_core_event(CoreEvent::JobOutput {
+			id: ctx.job_id.clone(),
+			output: CoreJobOutput::ComicVineMatch(output.clone()),
+		});
+		if output.matched_media > 0 {
+			// The people left without credits are only removed once every book was matched,
+			// rather than after each one
+			let removed_people = remove_orphaned_people(&ctx.db).await?;
+			tracing::debug!(removed_people, "Removed people without any credits");
+			ctx.send_core_event(CoreEvent::CreatedOrUpdatedManyMedia {
+				count: output.matched_media,
+				series_id: self.series_id.clone(),
+			});
+		}
+		Ok(vec![])
+	}
+
+	async fn execute_task(
+		&self,
+		ctx: &WorkerCtx,
+		task: Self::Task,
+	) -> Result<JobTaskOutput<Self>, JobError> {
+		match task {
+			ComicVineMatchTask::MatchIssue { media_id, issue_id } => {
+				let client = get_comicvine_client(&ctx.db)
+					.await
+					.map_err(|error| JobError::TaskFailed(error.to_string()))?;
+				self.match_issue(ctx, &client, &media_id, issue_id).await
+			},
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::time::Duration;
+
+	use integrations::test_utils::StubServer;
+	use prisma_client_rust::MockStore;
+	use serde_json::json;
+
+	use super::*;
+	use crate::{
+		config::StumpConfig, db::entity::LogLevel,
+		metadata::tests::expect_apply_provider_metadata, prisma::PrismaClient,
+	};
+
+	fn comicvine_client(server: &StubServer) -> ComicVineClient {
+		ComicVineClient::with_base_url(String::from("test_key"), server.url.clone())
+			.with_request_interval(Duration::ZERO)
+	}
+
+	fn ok(results: serde_json::Value, total: usize) -> serde_json::Value {
+		json!({
+			"error": "OK",
+			"status_code": 1,
+			"number_of_total_results": total,
+			"results": results,
+		})
+	}
+
+	fn volume() -> serde_json::Value {
+		json!({
+			"id": 4050,
+			"name": "Saga",
+			"start_year": "2012",
+			"publisher": { "id": 1, "name": "Image" },
+			"count_of_issues": 2,
+			"deck": "An epic space opera",
+		})
+	}
+
+	fn issue() -> serde_json::Value {
+		json!({
+			"id": 1001,
+			"name": "Chapter One",
+			"issue_number": "1",
+			"cover_date": "2012-03-14",
+			"volume": { "id": 4050, "name": "Saga" },
+		})
+	}
+
+	/// Expect the series and its books to be loaded, where the books are numbered 1 and 3
+	async fn expect_series(
+		client: &PrismaClient,
+		mock: &MockStore,
+		metadata: serde_json::Value,
+	) {
+		mock.expect(
+			client
+				.series()
+				.find_unique(series::id::equals("series_id".to_string()))
+				.with(series::metadata::fetch())
+				.with(series::library::fetch().with(library::config::fetch())),
+			serde_json::from_value(json!({
+				"id": "series_id",
+				"name": "Saga",
+				"description": null,
+				"updated_at": "2024-01-01T00:00:00Z",
+				"created_at": "2024-01-01T00:00:00Z",
+				"path": "/comics/Saga",
+				"status": "READY",
+				"library_id": "library_id",
+				"metadata": metadata,
+				"library": null,
+			}))
+			.unwrap(),
+		)
+		.await;
+
+		let media = [1.0, 3.0]
+			.iter()
+			.enumerate()
+			.map(|(index, number)| {
+				json!({
+					"id": format!("media_{}", index + 1),
+					"name": format!("Saga {number:03}"),
+					"size": 1024,
+					"extension": "cbz",
+					"pages": 20,
+					"updated_at": "2024-01-01T00:00:00Z",
+					"created_at": "2024-01-01T00:00:00Z",
+					"modified_at": null,
+					"deleted_at": null,
+					"hash": null,
+					"koreader_hash": null,
+					"perceptual_hash": null,
+					"path": format!("/comics/Saga/Saga {number:03}.cbz"),
+					"status": "READY",
+					"series_id": "series_id",
+					"metadata": {
+						"id": format!("metadata_{}", index + 1),
+						"media_id": format!("media_{}", index + 1),
+						"number": number,
+						"year": 2012,
+					},
+				})
+			})
+			.collect::<Vec<_>>();
+		mock.expect(
+			client
+				.media()
+				.find_many(vec![media::series_id::equals(Some(
+					"series_id".to_string(),
+				))])
+				.with(media::metadata::fetch()),
+			serde_json::from_value(json!(media)).unwrap(),
+		)
+		.await;
+	}
+
+	#[tokio::test]
+	async fn test_match_series_reports_candidates() {
+		let server = StubServer::start(|target| {
+			assert!(target.starts_with("/search/"));
+			assert!(target.contains("query=Saga"));
+			ok(
+				json!([
+					{ "id": 1, "name": "Saga of the Swamp Thing", "start_year": "1982" },
+					volume(),
+				]),
+				2,
+			)
+		});
+		// Nothing is written until one of the candidates is confirmed, so no writes are
+		// expected by the mock
+		let (client, mock) = PrismaClient::_mock();
+		expect_series(&client, &mock, json!(null)).await;
+		let ctx = WorkerCtx::mock(client, StumpConfig::debug());
+
+		let mut job = ComicVineMatchJob {
+			series_id: "series_id".to_string(),
+			merge_strategy: Some(MergeStrategy::Replace),
+			publisher: None,
+		};
+		let WorkingState { output, tasks, .. } = job
+			.match_series(&ctx, &comicvine_client(&server))
+			.await
+			.unwrap();
+		let output = output.unwrap();
+
+		assert!(tasks.is_empty());
+		assert_eq!(output.matched_volume_id, None);
+		assert_eq!(output.candidates.len(), 2);
+		assert_eq!(output.candidates[0].volume_id, 4050);
+		assert_eq!(output.candidates[0].publisher.as_deref(), Some("Image"));
+		assert!(output.candidates[0].score > output.candidates[1].score);
+	}
+
+	#[tokio::test]
+	async fn test_match_series_with_confirmed_volume() {
+		let server = StubServer::start(|target| {
+			if target.starts_with("/volume/4050-4050/") {
+				ok(volume(), 1)
+			} else if target.starts_with("/issues/") {
+				ok(
+					json!([
+						issue(),
+						{ "id": 1002, "issue_number": "2" },
+					]),
+					2,
+				)
+			} else {
+				panic!("Unexpected request: {target}");
+			}
+		});
+		let (client, mock) = PrismaClient::_mock();
+		expect_series(
+			&client,
+			&mock,
+			json!({
+				"meta_type": "comicSeries",
+				"comicid": 4050,
+				"series_id": "series_id",
+			}),
+		)
+		.await;
+		let (meta_type, params) = SeriesMetadata {
+			_type: "comicSeries".to_string(),
+			title: Some("Saga".to_string()),
+			summary: Some("An epic space opera".to_string()),
+			publisher: Some("Image".to_string()),
+			imprint: None,
+			comicid: Some(4050),
+			volume: Some(2012),
+			booktype: None,
+			age_rating: None,
+			status: None,
+			locked_fields: vec![],
+		}
+		.create_action();
+		mock.expect(
+			client.series_metadata().upsert(
+				series_metadata::series_id::equals("series_id".to_string()),
+				(
+					meta_type,
+					series::id::equals("series_id".to_string()),
+					params.clone(),
+				),
+				params,
+			),
+			serde_json::from_value(json!({
+				"meta_type": "comicSeries",
+				"title": "Saga",
+				"comicid": 4050,
+				"series_id": "series_id",
+			}))
+			.unwrap(),
+		)
+		.await;
+		let ctx = WorkerCtx::mock(client, StumpConfig::debug());
+
+		let mut job = ComicVineMatchJob {
+			series_id: "series_id".to_string(),
+			merge_strategy: Some(MergeStrategy::Replace),
+			publisher: None,
+		};
+		let WorkingState { output, tasks, .. } = job
+			.match_series(&ctx, &comicvine_client(&server))
+			.await
+			.unwrap();
+		let output = output.unwrap();
+
+		assert_eq!(output.matched_volume_id, Some(4050));
+		assert!(output.candidates.is_empty());
+		// Only the first book has a matching issue
+		assert_eq!(output.unmatched_media, 1);
+		assert_eq!(tasks.len(), 1);
+		let ComicVineMatchTask::MatchIssue { media_id, issue_id } = &tasks[0];
+		assert_eq!(media_id, "media_1");
+		assert_eq!(*issue_id, 1001);
+		assert_eq!(job.publisher.as_deref(), Some("Image"));
+	}
+
+	#[tokio::test]
+	async fn test_match_issue() {
+		let server = StubServer::start(|target| {
+			assert!(target.starts_with("/issue/4000-1001/"));
+			ok(issue(), 1)
+		});
+		let comicvine = comicvine_client(&server);
+		let issue = comicvine.get_issue(1001).await.unwrap();
+
+		let (client, mock) = PrismaClient::_mock();
+		expect_apply_provider_metadata(
+			&client,
+			&mock,
+			"media_1",
+			issue_to_metadata(&issue, Some("Image".to_string())),
+		)
+		.await;
+		let ctx = WorkerCtx::mock(client, StumpConfig::debug());
+
+		let job = ComicVineMatchJob {
+			series_id: "series_id".to_string(),
+			merge_strategy: Some(MergeStrategy::Replace),
+			publisher: Some("Image".to_string()),
+		};
+		let JobTaskOutput { output, logs, .. } = job
+			.match_issue(&ctx, &comicvine, "media_1", 1001)
+			.await
+			.unwrap();
+
+		assert!(logs.is_empty());
+		assert_eq!(output.matched_media, 1);
+		assert_eq!(output.unmatched_media, 0);
+	}
+
+	#[tokio::test]
+	async fn test_match_issue_not_found() {
+		let server = StubServer::start(
+			|_| json!({ "error": "Object Not Found", "status_code": 101, "results": [] }),
+		);
+		// The media is left as is, so no queries are expected
+		let (client, _mock) = PrismaClient::_mock();
+		let ctx = WorkerCtx::mock(client, StumpConfig::debug());
+
+		let job = ComicVineMatchJob {
+			series_id: "series_id".to_string(),
+			merge_strategy: Some(MergeStrategy::Replace),
+			publisher: None,
+		};
+		let JobTaskOutput { output, logs, .. } = job
+			.match_issue(&ctx, &comicvine_client(&server), "media_1", 1001)
+			.await
+			.unwrap();
+
+		assert_eq!(output.matched_media, 0);
+		assert_eq!(output.unmatched_media, 1);
+		assert_eq!(logs.len(), 1);
+		assert!(matches!(logs[0].level, LogLevel::Warn));
+	}
+}
@@ -0,0 +1,335 @@
+mod match_job;
+
+use std::sync::{Arc, Mutex};
+
+use integrations::{ComicVineClient, ComicVineIssue, ComicVineVolume};
+
+use crate::{
+	db::entity::{get_provider_api_key, MediaMetadata, MetadataProviderKind},
+	prisma::PrismaClient,
+	CoreError, CoreResult,
+};
+
+use super::name_similarity;
+
+pub use match_job::{ComicVineCandidate, ComicVineMatchJob, ComicVineMatchOutput};
+
+/// The client shared by every ComicVine job, so that the rate limit and response cache are
+/// respected across jobs rather than per job
+static CLIENT: Mutex<Option<Arc<ComicVineClient>>> = Mutex::new(None);
+
+/// Get the shared ComicVine client, creating it if it does not exist yet or if the configured
+/// API key has changed since it was created
+pub async fn get_comicvine_client(
+	client: &PrismaClient,
+) -> CoreResult<Arc<ComicVineClient>> {
+	let api_key = get_provider_api_key(client, MetadataProviderKind::ComicVine).await?;
+
+	let mut shared = CLIENT.lock().map_err(|_| {
+		CoreError::InternalError("The ComicVine client lock was poisoned".to_string())
+	})?;
+
+	match shared.as_ref() {
+		Some(existing) if existing.api_key() == api_key => Ok(existing.clone()),
+		_ => {
+			let created = Arc::new(ComicVineClient::new(api_key));
+			*shared = Some(created.clone());
+			Ok(created)
+		},
+	}
+}
+
+/// The weight of each factor when scoring how closely a volume matches a series
+const NAME_WEIGHT: f32 = 0.6;
+const YEAR_WEIGHT: f32 = 0.25;
+const ISSUE_COUNT_WEIGHT: f32 = 0.15;
+
+/// Score how closely a ComicVine volume matches a series, from 0 to 1. The score is based on
+/// the similarity of the names, how close the start year is to the year of the series and
+/// whether the volume has enough issues to cover the books of the series
+pub(crate) fn score_volume(
+	volume: &ComicVineVolume,
+	name: &str,
+	year: Option<i32>,
+	book_count: Option<usize>,
+) -> f32 {
+	let name_score = name_similarity(name, &volume.name);
+
+	let year_score = match (year, volume.year()) {
+		(Some(expected), Some(actual)) => match (expected - actual).abs() {
+			0 => 1.0,
+			1 => 0.5,
+			_ => 0.0,
+		},
+		_ => 0.0,
+	};
+
+	// A volume with fewer issues than there are books is unlikely to be the series, but
+	// it is common to only have some of the issues of a volume
+	let issue_count_score = match (book_count, volume.count_of_issues) {
+		(Some(books), Some(issues)) if books > 0 && issues > 0 => {
+			(issues as f32 / books as f32).min(1.0)
+		},
+		_ => 0.0,
+	};
+
+	name_score * NAME_WEIGHT
+		+ year_score * YEAR_WEIGHT
+		+ issue_count_score * ISSUE_COUNT_WEIGHT
+}
+
+/// HTML elements which separate blocks of text, and so are replaced with whitespace rather
+/// than removed outright
+const BLOCK_ELEMENTS: [&str; 10] = [
+	"p",
+	"br",
+	"div",
+	"li",
+	"h1",
+	"h2",
+	"h3",
+	"h4",
+	"tr",
+	"blockquote",
+];
+
+/// Strip the HTML tags from a ComicVine description, decoding the most common entities and
+/// collapsing whitespace
+pub(crate) fn strip_html(value: &str) -> String {
+	let mut text = String::with_capacity(value.len());
+	let mut tag: Option<String> = None;
+
+	for c in value.chars() {
+		match (c, tag.as_mut()) {
+			('<', None) => tag = Some(String::new()),
+			('>', Some(name)) => {
+				let element = name
+					.trim_start_matches('/')
+					.split(|c: char| c.is_whitespace() || c == '/')
+					.next()
+					.unwrap_or_default()
+					.to_lowercase();
+				if BLOCK_ELEMENTS.contains(&element.as_str()) {
+					text.push(' ');
+				}
+				tag = None;
+			},
+			(_, Some(name)) => name.push(c),
+			(_, None) => text.push(c),
+		}
+	}
+
+	text.replace("&nbsp;", " ")
+		.replace("&quot;", "\"")
+		.replace("&#39;", "'")
+		.replace("&lt;", "<")
+		.replace("&gt;", ">")
+		.replace("&amp;", "&")
+		.split_whitespace()
+		.collect::<Vec<_>>()
+		.join(" ")
+}
+
+/// Parse a ComicVine date, e.g. `2011-09-01`, into its year, month and day
+fn parse_cover_date(date: &str) -> (Option<i32>, Option<i32>, Option<i32>) {
+	let mut parts = date.trim().split('-').map(|part| part.parse::<i32>().ok());
+	let year = parts.next().flatten();
+	let month = parts.next().flatten().filter(|m| (1..=12).contains(m));
+	let day = parts.next().flatten().filter(|d| (1..=31).contains(d));
+	(year, month, day)
+}
+
+fn non_empty(values: Vec<String>) -> Option<Vec<String>> {
+	(!values.is_empty()).then_some(values)
+}
+
+/// Build the metadata of a media from a ComicVine issue. The publisher is only known by the
+/// volume of the issue, so it must be provided separately
+pub(crate) fn issue_to_metadata(
+	issue: &ComicVineIssue,
+	publisher: Option<String>,
+) -> MediaMetadata {
+	let (year, month, day) = issue
+		.cover_date
+		.as_deref()
+		.map(parse_cover_date)
+		.unwrap_or_default();
+	let summary = issue
+		.description
+		.as_deref()
+		.map(strip_html)
+		.or_else(|| issue.deck.clone())
+		.filter(|summary| !summary.is_empty());
+
+	MediaMetadata {
+		title: issue.name.clone().filter(|name| !name.trim().is_empty()),
+		series: issue.volume.as_ref().map(|volume| volume.name.clone()),
+		number: issue.number(),
+		summary,
+		year,
+		month,
+		day,
+		writers: non_empty(issue.people_with_role(&["writer"])),
+		pencillers: non_empty(issue.people_with_role(&[
+			"penciler",
+			"penciller",
+			"artist",
+		])),
+		inkers: non_empty(issue.people_with_role(&["inker"])),
+		colorists: non_empty(issue.people_with_role(&["colorist"])),
+		letterers: non_empty(issue.people_with_role(&["letterer"])),
+		cover_artists: non_empty(issue.people_with_role(&["cover"])),
+		editors: non_empty(issue.people_with_role(&["editor"])),
+		publisher,
+		links: issue.site_detail_url.clone().map(|url| vec![url]),
+		characters: non_empty(
+			issue
+				.character_credits
+				.iter()
+				.map(|credit| credit.name.clone())
+				.collect(),
+		),
+		teams: non_empty(
+			issue
+				.team_credits
+				.iter()
+				.map(|credit| credit.name.clone())
+				.collect(),
+		),
+		..Default::default()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use integrations::ComicVineCredit;
+
+	use super::*;
+
+	fn volume(
+		name: &str,
+		start_year: Option<&str>,
+		issues: Option<i64>,
+	) -> ComicVineVolume {
+		ComicVineVolume {
+			id: 1,
+			name: name.to_string(),
+			start_year: start_year.map(String::from),
+			publisher: None,
+			count_of_issues: issues,
+			deck: None,
+			description: None,
+			site_detail_url: None,
+		}
+	}
+
+	fn credit(name: &str, role: Option<&str>) -> ComicVineCredit {
+		ComicVineCredit {
+			id: None,
+			name: name.to_string(),
+			role: role.map(String::from),
+		}
+	}
+
+	#[test]
+	fn test_score_exact_match() {
+		let volume = volume("Saga", Some("2012"), Some(66));
+		let score = score_volume(&volume, "Saga", Some(2012), Some(54));
+		assert!((score - 1.0).abs() < 1e-6);
+	}
+
+	#[test]
+	fn test_score_ignores_leading_article_and_punctuation() {
+		let volume = volume("The Amazing Spider-Man", Some("1963"), Some(441));
+		let score = score_volume(&volume, "Amazing Spider Man", Some(1963), Some(10));
+		assert!((score - 1.0).abs() < 1e-6);
+	}
+
+	#[test]
+	fn test_score_prefers_closer_year() {
+		let original = volume("Batman", Some("1940"), Some(713));
+		let relaunch = volume("Batman", Some("2016"), Some(158));
+
+		let original_score = score_volume(&original, "Batman", Some(2016), Some(20));
+		let relaunch_score = score_volume(&relaunch, "Batman", Some(2016), Some(20));
+		assert!(relaunch_score > original_score);
+	}
+
+	#[test]
+	fn test_score_partial_name_and_missing_issues() {
+		let volume = volume("Saga of the Swamp Thing", None, Some(5));
+		let score = score_volume(&volume, "Swamp Thing", None, Some(10));
+
+		// 2 of the 5 distinct words are shared, and the volume covers half of the books
+		let expected = 0.4 * NAME_WEIGHT + 0.5 * ISSUE_COUNT_WEIGHT;
+		assert!((score - expected).abs() < 1e-6);
+	}
+
+	#[test]
+	fn test_score_unrelated_volume() {
+		let volume = volume("Hellboy", Some("1994"), Some(5));
+		assert_eq!(score_volume(&volume, "Saga", Some(2012), None), 0.0);
+	}
+
+	#[test]
+	fn test_strip_html() {
+		assert_eq!(
+			strip_html("<p>Alana &amp; Marko <em>flee</em>.</p><p>Book&nbsp;one</p>"),
+			"Alana & Marko flee. Book one"
+		);
+		assert_eq!(strip_html("No markup"), "No markup");
+	}
+
+	#[test]
+	fn test_parse_cover_date() {
+		assert_eq!(
+			parse_cover_date("2012-03-01"),
+			(Some(2012), Some(3), Some(1))
+		);
+		assert_eq!(parse_cover_date("2012"), (Some(2012), None, None));
+		assert_eq!(parse_cover_date("2012-13-00"), (Some(2012), None, None));
+	}
+
+	#[test]
+	fn test_issue_to_metadata() {
+		let issue = ComicVineIssue {
+			id: 1,
+			name: Some("Chapter One".to_string()),
+			issue_number: Some("1".to_string()),
+			cover_date: Some("2012-03-01".to_string()),
+			deck: Some("The deck".to_string()),
+			description: Some("<p>The description</p>".to_string()),
+			volume: Some(credit("Saga", None)),
+			person_credits: vec![
+				credit("Brian K. Vaughan", Some("writer")),
+				credit("Fiona Staples", Some("artist, cover, colorist")),
+				credit("Fonografiks", Some("letterer")),
+			],
+			character_credits: vec![credit("Alana", None)],
+			team_credits: vec![],
+			site_detail_url: Some("https://comicvine.gamespot.com/saga-1/".to_string()),
+		};
+
+		let metadata = issue_to_metadata(&issue, Some("Image".to_string()));
+		assert_eq!(metadata.title.as_deref(), Some("Chapter One"));
+		assert_eq!(metadata.series.as_deref(), Some("Saga"));
+		assert_eq!(metadata.number, Some(1.0));
+		assert_eq!(metadata.summary.as_deref(), Some("The description"));
+		assert_eq!(
+			(metadata.year, metadata.month, metadata.day),
+			(Some(2012), Some(3), Some(1))
+		);
+		assert_eq!(metadata.writers, Some(vec!["Brian K. Vaughan".to_string()]));
+		assert_eq!(metadata.pencillers, Some(vec!["Fiona Staples".to_string()]));
+		assert_eq!(
+			metadata.cover_artists,
+			Some(vec!["Fiona Staples".to_string()])
+		);
+		assert_eq!(metadata.colorists, Some(vec!["Fiona Staples".to_string()]));
+		assert_eq!(metadata.letterers, Some(vec!["Fonografiks".to_string()]));
+		assert_eq!(metadata.inkers, None);
+		assert_eq!(metadata.characters, Some(vec!["Alana".to_string()]));
+		assert_eq!(metadata.teams, None);
+		assert_eq!(metadata.publisher.as_deref(), Some("Image"));
+	}
+}
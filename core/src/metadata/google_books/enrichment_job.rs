@@ -0,0 +1,393 @@
+use std::collections::VecDeque;
+
+use integrations::{GoogleBooksClient, ProviderBook};
+use serde::{Deserialize, Serialize};
+use specta::Type;
+
+use crate::{
+	db::entity::{find_isbn, CoreJobOutput, LibraryConfig, MergeStrategy},
+	job::{
+		error::JobError, Executor, JobExecuteLog, JobExt, JobOutputExt, JobProgress,
+		JobRetryPolicy, JobTaskOutput, WorkerCtx, WorkingState, WrappedJob,
+	},
+	metadata::{
+		apply_provider_metadata, identify::book_to_metadata, remove_orphaned_people,
+	},
+	prisma::{library, media, series},
+	CoreEvent,
+};
+
+use super::get_google_books_client;
+
+/// The file extensions of the books which can be enriched. Comics rarely have an ISBN, so
+/// they are left to providers like ComicVine
+const ENRICHABLE_EXTENSIONS: [&str; 2] = ["epub", "pdf"];
+
+#[derive(Serialize, Deserialize)]
+pub enum IsbnEnrichmentTask {
+	/// Fill the metadata of a media using the Google Books volume with the given ISBN
+	EnrichMedia { media_id: String, isbn: String },
+}
+
+/// A job which fills the metadata of the books in a library from Google Books, using the ISBN
+/// found in the metadata of each book (e.g. the identifier of an EPUB) or its file name
+#[derive(Clone, Serialize, Deserialize)]
+pub struct IsbnEnrichmentJob {
+	pub library_id: String,
+	/// The strategy used to reconcile the fetched metadata with the existing metadata. When
+	/// not provided, the merge strategy of the library is used
+	pub merge_strategy: Option<MergeStrategy>,
+}
+
+impl IsbnEnrichmentJob {
+	pub fn new(
+		library_id: String,
+		merge_strategy: Option<MergeStrategy>,
+	) -> Box<WrappedJob<IsbnEnrichmentJob>> {
+		WrappedJob::new(Self {
+			library_id,
+			merge_strategy,
+		})
+	}
+
+	/// Fill the metadata of a media from the Google Books volume with the given ISBN
+	async fn enrich_media(
+		&self,
+		ctx: &WorkerCtx,
+		client: &GoogleBooksClient,
+		media_id: &str,
+		isbn: String,
+	) -> Result<JobTaskOutput<Self>, JobError> {
+		let mut output = IsbnEnrichmentOutput::default();
+		let mut logs = vec![];
+
+		ctx.report_progress(JobProgress::msg(format!("Looking up ISBN {isbn}").as_str()));
+		let volume = client
+			.get_book_by_isbn(&isbn)
+			.await
+			.map_err(|error| JobError::TaskFailed(error.to_string()))?;
+		let Some(volume) = volume else {
+			logs.push(JobExecuteLog::warn(
+				format!("Google Books does not know ISBN {isbn}").as_str(),
+			));
+			output.not_found_media += 1;
+			return Ok(JobTaskOutput {
+				output,
+				logs,
+				subtasks: vec![],
+			});
+		};
+
+		let mut metadata = book_to_metadata(&ProviderBook::from(volume));
+		metadata.isbn = Some(isbn);
+		apply_provider_metadata(
+			&ctx.db,
+			media_id,
+			metadata,
+			self.merge_strategy.unwrap_or_default(),
+		)
+		.await?;
+		output.enriched_media += 1;
+
+		Ok(JobTaskOutput {
+			output,
+			logs,
+			subtasks: vec![],
+		})
+	}
+}
+
+/// Create a task for each book which can be enriched and has an ISBN, counting the books
+/// which are missing one
+fn enrichment_tasks(
+	library_media: Vec<media::Data>,
+	output: &mut IsbnEnrichmentOutput,
+) -> VecDeque<IsbnEnrichmentTask> {
+	let mut tasks = VecDeque::new();
+	for book in library_media {
+		let extension = book.extension.to_lowercase();
+		if !ENRICHABLE_EXTENSIONS.contains(&extension.as_str()) {
+			continue;
+		}
+
+		let isbn = book
+			.metadata()
+			.ok()
+			.flatten()
+			.and_then(|metadata| metadata.isbn.clone())
+			.or_else(|| find_isbn(&book.name));
+		match isbn {
+			Some(isbn) => tasks.push_back(IsbnEnrichmentTask::EnrichMedia {
+				media_id: book.id,
+				isbn,
+			}),
+			None => output.unidentified_media += 1,
+		}
+	}
+
+	tasks
+}
+
+/// The output of an ISBN enrichment, which is reported to clients once it completes
+#[derive(Clone, Serialize, Deserialize, Default, Debug, Type)]
+pub struct IsbnEnrichmentOutput {
+	/// The number of media whose metadata was filled from Google Books
+	enriched_media: u64,
+	/// The number of media with an ISBN which Google Books does not know
+	not_found_media: u64,
+	/// The number of media skipped because no ISBN could be found for them
+	unidentified_media: u64,
+}
+
+impl JobOutputExt for IsbnEnrichmentOutput {
+	fn update(&mut self, updated: Self) {
+		self.enriched_media += updated.enriched_media;
+		self.not_found_media += updated.not_found_media;
+		self.unidentified_media += updated.unidentified_media;
+	}
+}
+
+#[async_trait::async_trait]
+impl JobExt for IsbnEnrichmentJob {
+	const NAME: &'static str = "isbn_enrichment";
+	// Google Books enforces a quota, so lookups which were rate limited are retried
+	const MAX_TASK_RETRIES: JobRetryPolicy = JobRetryPolicy::Count(3);
+
+	type Output = IsbnEnrichmentOutput;
+	type Task = IsbnEnrichmentTask;
+
+	fn description(&self) -> Option<String> {
+		Some(self.library_id.clone())
+	}
+
+	async fn init(
+		&mut self,
+		ctx: &WorkerCtx,
+	) -> Result<WorkingState<Self::Output, Self::Task>, JobError> {
+		let mut output = Self::Output::default();
+		// Fail early rather than once per book if the provider isn't configured
+		get_google_books_client(&ctx.db)
+			.await
+			.map_err(|error| JobError::InitFailed(error.to_string()))?;
+
+		if self.merge_strategy.is_none() {
+			let library = ctx
+				.db
+				.library()
+				.find_unique(library::id::equals(self.library_id.clone()))
+				.with(library::config::fetch())
+				.exec()
+				.await?
+				.ok_or(JobError::InitFailed("Library not found".to_string()))?;
+			self.merge_strategy = library
+				.config()
+				.ok()
+				.map(|config| LibraryConfig::from(config).merge_strategy);
+		}
+
+		ctx.report_progress(JobProgress::msg("Finding ISBNs"));
+		let library_media = ctx
+			.db
+			.media()
+			.find_many(vec![media::series::is(vec![series::library_id::equals(
+				Some(self.library_id.clone()),
+			)])])
+			.with(media::metadata::fetch())
+			.exec()
+			.await?;
+
+		let tasks = enrichment_tasks(library_media, &mut output);
+
+		ctx.report_progress(JobProgress::msg(
+			format!("Found {} books with an ISBN", tasks.len()).as_str(),
+		));
+
+		Ok(WorkingState {
+			output: Some(output),
+			tasks,
+			completed_tasks: 0,
+			logs: vec![],
+		})
+	}
+
+	async fn cleanup(
+		&self,
+		ctx: &WorkerCtx,
+		output: &Self::Output,
+	) -> Result<Vec<Box<dyn Executor>>, JobError> {
+		ctx.send_core_event(CoreEvent::JobOutput {
+			id: ctx.job_id.clone(),
+			output: CoreJobOutput::IsbnEnrichment(output.clone()),
+		});
+		if output.enriched_media > 0 {
+			// The people left without credits are only removed once every book was enriched,
+			// rather than after each one
+			let removed_people = remove_orphaned_people(&ctx.db).await?;
+			tracing::debug!(removed_people, "Removed people without any credits");
+		}
+		Ok(vec![])
+	}
+
+	async fn execute_task(
+		&self,
+		ctx: &WorkerCtx,
+		task: Self::Task,
+	) -> Result<JobTaskOutput<Self>, JobError> {
+		match task {
+			IsbnEnrichmentTask::EnrichMedia { media_id, isbn } => {
+				let client = get_google_books_client(&ctx.db)
+					.await
+					.map_err(|error| JobError::TaskFailed(error.to_string()))?;
+				self.enrich_media(ctx, &client, &media_id, isbn).await
+			},
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use integrations::test_utils::StubServer;
+	use serde_json::json;
+
+	use super::*;
+	use crate::{
+		config::StumpConfig, db::entity::LogLevel,
+		metadata::tests::expect_apply_provider_metadata, prisma::PrismaClient,
+	};
+
+	fn google_books_client(server: &StubServer) -> GoogleBooksClient {
+		GoogleBooksClient::with_base_url(String::from("test_key"), server.url.clone())
+	}
+
+	fn job() -> IsbnEnrichmentJob {
+		IsbnEnrichmentJob {
+			library_id: "library_id".to_string(),
+			merge_strategy: Some(MergeStrategy::Replace),
+		}
+	}
+
+	fn book(id: &str, name: &str, extension: &str, isbn: Option<&str>) -> media::Data {
+		serde_json::from_value(json!({
+			"id": id,
+			"name": name,
+			"size": 1024,
+			"extension": extension,
+			"pages": 300,
+			"updated_at": "2024-01-01T00:00:00Z",
+			"created_at": "2024-01-01T00:00:00Z",
+			"modified_at": null,
+			"deleted_at": null,
+			"hash": null,
+			"koreader_hash": null,
+			"perceptual_hash": null,
+			"path": format!("/books/{name}.{extension}"),
+			"status": "READY",
+			"series_id": "series_id",
+			"metadata": isbn.map(|isbn| json!({
+				"id": format!("{id}_metadata"),
+				"media_id": id,
+				"isbn": isbn,
+			})),
+		}))
+		.unwrap()
+	}
+
+	#[test]
+	fn test_enrichment_tasks() {
+		let mut output = IsbnEnrichmentOutput::default();
+		let tasks = enrichment_tasks(
+			vec![
+				book(
+					"epub",
+					"Nineteen Eighty-Four",
+					"epub",
+					Some("9780141036144"),
+				),
+				book("pdf", "Brave New World (978-0-09-947446-3)", "PDF", None),
+				book("unknown", "Fahrenheit 451", "epub", None),
+				book("comic", "Saga 001 (9781607066019)", "cbz", None),
+			],
+			&mut output,
+		);
+
+		let tasks = tasks
+			.into_iter()
+			.map(|IsbnEnrichmentTask::EnrichMedia { media_id, isbn }| (media_id, isbn))
+			.collect::<Vec<_>>();
+		// Comics are skipped entirely, rather than counted as unidentified
+		assert_eq!(
+			tasks,
+			vec![
+				("epub".to_string(), "9780141036144".to_string()),
+				("pdf".to_string(), "9780099474463".to_string()),
+			]
+		);
+		assert_eq!(output.unidentified_media, 1);
+	}
+
+	#[tokio::test]
+	async fn test_enrich_media() {
+		let server = StubServer::start(|target| {
+			assert!(target.contains("q=isbn%3A9780141036144"));
+			json!({
+				"kind": "books#volumes",
+				"totalItems": 1,
+				"items": [{
+					"id": "kotPYEqx7kMC",
+					"volumeInfo": {
+						"title": "Nineteen Eighty-Four",
+						"publisher": "Penguin UK",
+						"publishedDate": "2008-03",
+						"description": "A dystopian novel",
+						"pageCount": 400,
+					}
+				}]
+			})
+		});
+		let google_books = google_books_client(&server);
+		let volume = google_books
+			.get_book_by_isbn("9780141036144")
+			.await
+			.unwrap()
+			.unwrap();
+		let mut metadata = book_to_metadata(&ProviderBook::from(volume));
+		metadata.isbn = Some("9780141036144".to_string());
+
+		let (client, mock) = PrismaClient::_mock();
+		expect_apply_provider_metadata(&client, &mock, "media_id", metadata).await;
+		let ctx = WorkerCtx::mock(client, StumpConfig::debug());
+
+		let JobTaskOutput { output, logs, .. } = job()
+			.enrich_media(&ctx, &google_books, "media_id", "9780141036144".to_string())
+			.await
+			.unwrap();
+
+		assert!(logs.is_empty());
+		assert_eq!(output.enriched_media, 1);
+		assert_eq!(output.not_found_media, 0);
+	}
+
+	#[tokio::test]
+	async fn test_enrich_media_not_found() {
+		let server =
+			StubServer::start(|_| json!({ "kind": "books#volumes", "totalItems": 0 }));
+		// The media is left as is, so no queries are expected
+		let (client, _mock) = PrismaClient::_mock();
+		let ctx = WorkerCtx::mock(client, StumpConfig::debug());
+
+		let JobTaskOutput { output, logs, .. } = job()
+			.enrich_media(
+				&ctx,
+				&google_books_client(&server),
+				"media_id",
+				"0000000000".to_string(),
+			)
+			.await
+			.unwrap();
+
+		assert_eq!(output.enriched_media, 0);
+		assert_eq!(output.not_found_media, 1);
+		assert_eq!(logs.len(), 1);
+		assert!(matches!(logs[0].level, LogLevel::Warn));
+	}
+}
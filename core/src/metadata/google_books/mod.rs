@@ -0,0 +1,19 @@
+mod enrichment_job;
+
+use integrations::GoogleBooksClient;
+
+use crate::{
+	db::entity::{get_provider_api_key, MetadataProviderKind},
+	prisma::PrismaClient,
+	CoreResult,
+};
+
+pub use enrichment_job::{IsbnEnrichmentJob, IsbnEnrichmentOutput};
+
+/// Create a Google Books client using the configured API key
+pub async fn get_google_books_client(
+	client: &PrismaClient,
+) -> CoreResult<GoogleBooksClient> {
+	let api_key = get_provider_api_key(client, MetadataProviderKind::GoogleBooks).await?;
+	Ok(GoogleBooksClient::new(api_key))
+}
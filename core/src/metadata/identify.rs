@@ -0,0 +1,385 @@
+use std::path::Path;
+
+use integrations::{
+	MetadataProvider, MetadataQuery, OpenLibraryClient, ProviderBook, SidecarProvider,
+};
+use serde::{Deserialize, Serialize};
+use specta::Type;
+use utoipa::ToSchema;
+
+use crate::{
+	db::entity::{
+		find_isbn, is_provider_enabled, normalize_isbn, LibraryConfig, MediaMetadata,
+		MergeStrategy, MetadataProviderKind,
+	},
+	prisma::{library, media, media_metadata, series, PrismaClient},
+	CoreError, CoreResult,
+};
+
+use super::{
+	apply_provider_metadata, get_google_books_client, name_similarity,
+	remove_orphaned_people,
+};
+
+/// The maximum number of candidates returned when identifying a media
+const MAX_CANDIDATES: usize = 20;
+/// The weight of each factor when scoring how closely a book matches a media
+const TITLE_WEIGHT: f32 = 0.7;
+const AUTHOR_WEIGHT: f32 = 0.3;
+
+/// A book which a provider suggested for a media, along with how closely it matches
+#[derive(Debug, Clone, Serialize, Deserialize, Type, ToSchema)]
+pub struct MetadataCandidate {
+	/// The provider which suggested the book
+	pub provider: MetadataProviderKind,
+	/// The ID of the book with the provider, used to apply the candidate
+	pub id: String,
+	/// How closely the book matches the media, from 0 to 1
+	pub score: f32,
+	/// The metadata which would be applied to the media
+	pub metadata: MediaMetadata,
+	pub cover_url: Option<String>,
+}
+
+/// The parts of a media needed to identify it
+struct MediaContext {
+	path: String,
+	query: MetadataQuery,
+	library_config: LibraryConfig,
+}
+
+async fn get_media_context(
+	client: &PrismaClient,
+	media_id: &str,
+) -> CoreResult<MediaContext> {
+	let book = client
+		.media()
+		.find_unique(media::id::equals(media_id.to_string()))
+		.with(media::metadata::fetch())
+		.with(
+			media::series::fetch()
+				.with(series::library::fetch().with(library::config::fetch())),
+		)
+		.exec()
+		.await?
+		.ok_or_else(|| CoreError::NotFound(format!("Media {media_id} not found")))?;
+
+	let metadata = book
+		.metadata()
+		.ok()
+		.flatten()
+		.cloned()
+		.map(MediaMetadata::from);
+	let library_config = book
+		.series()
+		.ok()
+		.flatten()
+		.and_then(|series| series.library().ok().flatten())
+		.and_then(|library| library.config().ok())
+		.map(LibraryConfig::from)
+		.unwrap_or_default();
+
+	let query = MetadataQuery {
+		title: metadata
+			.as_ref()
+			.and_then(|metadata| metadata.title.clone())
+			.or_else(|| Some(book.name.clone())),
+		authors: metadata
+			.as_ref()
+			.and_then(|metadata| metadata.writers.clone())
+			.unwrap_or_default(),
+		isbn: metadata
+			.as_ref()
+			.and_then(|metadata| metadata.isbn.clone())
+			.or_else(|| find_isbn(&book.name)),
+	};
+
+	Ok(MediaContext {
+		path: book.path,
+		query,
+		library_config,
+	})
+}
+
+/// Create the provider of the given kind for a media, returning [None] if the provider is
+/// disabled or can't identify books
+async fn get_provider(
+	client: &PrismaClient,
+	kind: MetadataProviderKind,
+	media_path: &Path,
+) -> CoreResult<Option<Box<dyn MetadataProvider>>> {
+	if !kind.identifies_media() || !is_provider_enabled(client, kind).await? {
+		return Ok(None);
+	}
+
+	let provider: Box<dyn MetadataProvider> = match kind {
+		MetadataProviderKind::GoogleBooks => {
+			Box::new(get_google_books_client(client).await?)
+		},
+		MetadataProviderKind::OpenLibrary => Box::new(OpenLibraryClient::new()),
+		MetadataProviderKind::Sidecar => Box::new(SidecarProvider::for_book(media_path)),
+		MetadataProviderKind::ComicVine => return Ok(None),
+	};
+
+	Ok(Some(provider))
+}
+
+/// Get an enabled provider, erroring if it can't be used
+async fn get_enabled_provider(
+	client: &PrismaClient,
+	kind: MetadataProviderKind,
+	media_path: &Path,
+) -> CoreResult<Box<dyn MetadataProvider>> {
+	get_provider(client, kind, media_path)
+		.await?
+		.ok_or_else(|| {
+			CoreError::BadRequest(format!("The {kind} provider cannot be used"))
+		})
+}
+
+fn non_empty(values: Vec<String>) -> Option<Vec<String>> {
+	(!values.is_empty()).then_some(values)
+}
+
+/// Build the metadata of a media from a book described by a provider
+pub(crate) fn book_to_metadata(book: &ProviderBook) -> MediaMetadata {
+	let (year, month, day) = book.published_date_parts();
+	let links = book
+		.link
+		.iter()
+		.chain(book.cover_url.iter())
+		.cloned()
+		.collect::<Vec<_>>();
+
+	MediaMetadata {
+		title: book.title.clone().filter(|title| !title.trim().is_empty()),
+		summary: book
+			.description
+			.clone()
+			.filter(|summary| !summary.trim().is_empty()),
+		series: book.series.clone(),
+		number: book.number,
+		genre: non_empty(book.subjects.clone()),
+		year,
+		month,
+		day,
+		writers: non_empty(book.authors.clone()),
+		publisher: book.publisher.clone(),
+		isbn: book.isbn.as_deref().and_then(normalize_isbn),
+		links: non_empty(links),
+		page_count: book.page_count.filter(|count| *count > 0),
+		..Default::default()
+	}
+}
+
+/// Score how closely a book matches what is known about a media, from 0 to 1. A matching
+/// ISBN is considered certain, otherwise the title and authors are compared
+pub(crate) fn score_book(book: &ProviderBook, query: &MetadataQuery) -> f32 {
+	let expected_isbn = query.isbn.as_deref().and_then(normalize_isbn);
+	let actual_isbn = book.isbn.as_deref().and_then(normalize_isbn);
+	if expected_isbn.is_some() && expected_isbn == actual_isbn {
+		return 1.0;
+	}
+
+	let title_score = match (query.title.as_deref(), book.title.as_deref()) {
+		(Some(expected), Some(actual)) => name_similarity(expected, actual),
+		_ => 0.0,
+	};
+
+	if query.authors.is_empty() {
+		return title_score;
+	}
+
+	let author_score = query
+		.authors
+		.iter()
+		.flat_map(|expected| {
+			book.authors
+				.iter()
+				.map(move |actual| name_similarity(expected, actual))
+		})
+		.fold(0.0, f32::max);
+
+	title_score * TITLE_WEIGHT + author_score * AUTHOR_WEIGHT
+}
+
+/// Search every provider configured for the library of a media for books which could be
+/// the media, ranked by how closely they match. Providers which fail are skipped, so that
+/// one provider being unavailable doesn't prevent identifying a media
+pub async fn identify_media(
+	client: &PrismaClient,
+	media_id: &str,
+) -> CoreResult<Vec<MetadataCandidate>> {
+	let context = get_media_context(client, media_id).await?;
+	let media_path = Path::new(&context.path);
+
+	let mut candidates = vec![];
+	for kind in context.library_config.metadata_providers.providers() {
+		let provider = match get_provider(client, *kind, media_path).await {
+			Ok(Some(provider)) => provider,
+			Ok(None) => continue,
+			Err(error) => {
+				tracing::warn!(?error, %kind, "Failed to create metadata provider");
+				continue;
+			},
+		};
+
+		match provider.search(&context.query).await {
+			Ok(books) => {
+				candidates.extend(books.into_iter().map(|book| MetadataCandidate {
+					provider: *kind,
+					score: score_book(&book, &context.query),
+					metadata: book_to_metadata(&book),
+					cover_url: book.cover_url,
+					id: book.id,
+				}))
+			},
+			Err(error) => {
+				tracing::warn!(?error, %kind, "Failed to search metadata provider");
+			},
+		}
+	}
+
+	// The sort is stable, so equally scored candidates keep the priority of their provider
+	candidates.sort_by(|a, b| b.score.total_cmp(&a.score));
+	candidates.truncate(MAX_CANDIDATES);
+
+	Ok(candidates)
+}
+
+/// Apply the metadata of a book chosen from the candidates of [identify_media] to a media.
+/// When no strategy is given, the merge strategy of the library is used
+pub async fn apply_identified_metadata(
+	client: &PrismaClient,
+	media_id: &str,
+	kind: MetadataProviderKind,
+	id: &str,
+	strategy: Option<MergeStrategy>,
+) -> CoreResult<MediaMetadata> {
+	let context = get_media_context(client, media_id).await?;
+	let provider = get_enabled_provider(client, kind, Path::new(&context.path)).await?;
+
+	let book = provider.fetch(id).await?.ok_or_else(|| {
+		CoreError::NotFound(format!("The {kind} provider does not know book {id}"))
+	})?;
+	let strategy = strategy.unwrap_or(context.library_config.merge_strategy);
+	apply_provider_metadata(client, media_id, book_to_metadata(&book), strategy).await?;
+	remove_orphaned_people(client).await?;
+
+	client
+		.media_metadata()
+		.find_unique(media_metadata::media_id::equals(media_id.to_string()))
+		.exec()
+		.await?
+		.map(MediaMetadata::from)
+		.ok_or_else(|| CoreError::NotFound(format!("Metadata of {media_id} not found")))
+}
+
+/// Fetch the cover of a book chosen from the candidates of [identify_media], returning
+/// [None] if the provider has no cover for it
+pub async fn fetch_identified_cover(
+	client: &PrismaClient,
+	media_id: &str,
+	kind: MetadataProviderKind,
+	id: &str,
+) -> CoreResult<Option<Vec<u8>>> {
+	let context = get_media_context(client, media_id).await?;
+	let provider = get_enabled_provider(client, kind, Path::new(&context.path)).await?;
+	Ok(provider.fetch_cover(id).await?)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn book(title: &str, authors: &[&str], isbn: Option<&str>) -> ProviderBook {
+		ProviderBook {
+			id: title.to_string(),
+			title: Some(title.to_string()),
+			authors: authors.iter().map(|author| author.to_string()).collect(),
+			isbn: isbn.map(String::from),
+			..Default::default()
+		}
+	}
+
+	#[test]
+	fn test_book_to_metadata() {
+		let book = ProviderBook {
+			id: "OL1168083W".to_string(),
+			title: Some("Nineteen Eighty-Four".to_string()),
+			authors: vec!["George Orwell".to_string()],
+			publisher: Some("Penguin UK".to_string()),
+			published_date: Some("2008-03-04".to_string()),
+			description: Some("A dystopian novel".to_string()),
+			subjects: vec!["Fiction".to_string()],
+			isbn: Some("978-0-14-103614-4".to_string()),
+			page_count: Some(400),
+			link: Some("https://openlibrary.org/works/OL1168083W".to_string()),
+			cover_url: Some("https://covers.openlibrary.org/b/id/1-L.jpg".to_string()),
+			..Default::default()
+		};
+
+		let metadata = book_to_metadata(&book);
+		assert_eq!(metadata.title.as_deref(), Some("Nineteen Eighty-Four"));
+		assert_eq!(metadata.writers, Some(vec!["George Orwell".to_string()]));
+		assert_eq!(metadata.publisher.as_deref(), Some("Penguin UK"));
+		assert_eq!(
+			(metadata.year, metadata.month, metadata.day),
+			(Some(2008), Some(3), Some(4))
+		);
+		assert_eq!(metadata.summary.as_deref(), Some("A dystopian novel"));
+		assert_eq!(metadata.genre, Some(vec!["Fiction".to_string()]));
+		assert_eq!(metadata.isbn.as_deref(), Some("9780141036144"));
+		assert_eq!(metadata.page_count, Some(400));
+		assert_eq!(
+			metadata.links,
+			Some(vec![
+				"https://openlibrary.org/works/OL1168083W".to_string(),
+				"https://covers.openlibrary.org/b/id/1-L.jpg".to_string(),
+			])
+		);
+	}
+
+	#[test]
+	fn test_sparse_book_to_metadata() {
+		let book = ProviderBook {
+			title: Some("Untitled".to_string()),
+			published_date: Some("1949".to_string()),
+			page_count: Some(0),
+			..Default::default()
+		};
+
+		let metadata = book_to_metadata(&book);
+		assert_eq!((metadata.year, metadata.month), (Some(1949), None));
+		assert_eq!(metadata.writers, None);
+		assert_eq!(metadata.links, None);
+		assert_eq!(metadata.page_count, None);
+	}
+
+	#[test]
+	fn test_score_matching_isbn() {
+		let query = MetadataQuery {
+			title: Some("1984".to_string()),
+			isbn: Some("0141036141".to_string()),
+			..Default::default()
+		};
+		let candidate = book("Nineteen Eighty-Four", &[], Some("9780141036144"));
+		assert_eq!(score_book(&candidate, &query), 1.0);
+	}
+
+	#[test]
+	fn test_score_prefers_matching_author() {
+		let query = MetadataQuery {
+			title: Some("The Hobbit".to_string()),
+			authors: vec!["J.R.R. Tolkien".to_string()],
+			isbn: None,
+		};
+		let matching = book("The Hobbit", &["J. R. R. Tolkien"], None);
+		let other = book("The Hobbit", &["Someone Else"], None);
+		let unrelated = book("Dune", &["Frank Herbert"], None);
+
+		assert!(score_book(&matching, &query) > score_book(&other, &query));
+		assert!(score_book(&other, &query) > score_book(&unrelated, &query));
+		assert_eq!(score_book(&unrelated, &query), 0.0);
+	}
+}
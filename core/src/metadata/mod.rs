@@ -0,0 +1,172 @@
+//! Support for enriching the metadata of series and media using external providers, e.g.
+//! ComicVine
+
+mod comicvine;
+mod google_books;
+mod identify;
+mod people;
+mod transfer;
+
+use prisma_client_rust::QueryError;
+
+use crate::{
+	db::entity::{MediaMetadata, MergeStrategy},
+	filesystem::scanner::merge_and_upsert_metadata,
+	prisma::{media_metadata, PrismaClient},
+};
+
+pub use comicvine::{
+	get_comicvine_client, ComicVineCandidate, ComicVineMatchJob, ComicVineMatchOutput,
+};
+pub use google_books::{
+	get_google_books_client, IsbnEnrichmentJob, IsbnEnrichmentOutput,
+};
+pub use identify::{
+	apply_identified_metadata, fetch_identified_cover, identify_media, MetadataCandidate,
+};
+pub(crate) use people::{
+	credit_media, credited_names, remove_orphaned_people, resolve_people, ResolvedPeople,
+};
+pub use people::{
+	merge_people, sync_media_credits, MediaCreditsSyncJob, MediaCreditsSyncOutput,
+};
+pub use transfer::{
+	export_library_metadata, MetadataFieldChange, MetadataImportChange,
+	MetadataImportJob, MetadataImportOutput, MetadataRecord, MetadataRecordKind,
+	MetadataTransferFormat,
+};
+
+/// Split a name into lowercase alphanumeric words, ignoring a leading "the" so that e.g.
+/// "The Walking Dead" and "Walking Dead" are considered the same
+fn normalize_name(name: &str) -> Vec<String> {
+	let mut words = name
+		.to_lowercase()
+		.split(|c: char| !c.is_alphanumeric())
+		.filter(|word| !word.is_empty())
+		.map(String::from)
+		.collect::<Vec<_>>();
+
+	if words.len() > 1 && words[0] == "the" {
+		words.remove(0);
+	}
+
+	words
+}
+
+/// Score how similar two names are, from 0 to 1, by the share of words they have in common
+pub(crate) fn name_similarity(expected: &str, actual: &str) -> f32 {
+	let expected = normalize_name(expected);
+	let actual = normalize_name(actual);
+
+	if expected.is_empty() || actual.is_empty() {
+		0.0
+	} else if expected == actual {
+		1.0
+	} else {
+		let shared = expected.iter().filter(|word| actual.contains(word)).count();
+		let total = expected.len() + actual.len() - shared;
+		shared as f32 / total as f32
+	}
+}
+
+/// Reconcile metadata fetched from a provider with the metadata stored for a media, and store
+/// the result.
+///
+/// Providers don't know everything stored for a book, e.g. the age rating, so under
+/// [MergeStrategy::Replace] existing values are kept for any fields the provider does not
+/// provide. The page count detected by the file processor always takes priority over the
+/// one reported by the provider. People left without credits are not removed, see
+/// [remove_orphaned_people].
+pub(crate) async fn apply_provider_metadata(
+	client: &PrismaClient,
+	media_id: &str,
+	mut incoming: MediaMetadata,
+	strategy: MergeStrategy,
+) -> Result<String, QueryError> {
+	let existing = client
+		.media_metadata()
+		.find_unique(media_metadata::media_id::equals(media_id.to_string()))
+		.exec()
+		.await?
+		.map(MediaMetadata::from);
+
+	if let Some(existing) = existing {
+		if existing.page_count.is_some() {
+			incoming.page_count = None;
+		}
+		if strategy == MergeStrategy::Replace {
+			incoming =
+				MediaMetadata::merge(Some(incoming), existing, MergeStrategy::Merge)
+					.metadata;
+		}
+	}
+
+	merge_and_upsert_metadata(
+		client,
+		media_id,
+		incoming,
+		strategy,
+		&ResolvedPeople::new(),
+	)
+	.await
+}
+
+#[cfg(test)]
+mod tests {
+	use prisma_client_rust::MockStore;
+	use serde_json::json;
+
+	use super::*;
+	use crate::prisma::media_credit;
+
+	/// Expect the queries made by [apply_provider_metadata] to store the metadata of a media
+	/// which doesn't have any yet
+	pub async fn expect_apply_provider_metadata(
+		client: &PrismaClient,
+		mock: &MockStore,
+		media_id: &str,
+		metadata: MediaMetadata,
+	) {
+		// The existing metadata is read once to reconcile it with the provider, and once
+		// more when it is merged and stored
+		for _ in 0..2 {
+			mock.expect(
+				client
+					.media_metadata()
+					.find_unique(media_metadata::media_id::equals(media_id.to_string())),
+				None,
+			)
+			.await;
+		}
+
+		let params = metadata
+			.into_prisma()
+			.into_iter()
+			.chain(vec![media_metadata::media_id::set(Some(
+				media_id.to_string(),
+			))])
+			.collect::<Vec<_>>();
+		mock.expect(
+			client.media_metadata().upsert(
+				media_metadata::media_id::equals(media_id.to_string()),
+				params.clone(),
+				params,
+			),
+			serde_json::from_value(json!({
+				"id": format!("{media_id}_metadata"),
+				"media_id": media_id,
+			}))
+			.unwrap(),
+		)
+		.await;
+		// The stored metadata doesn't credit anyone, so any existing credits are cleared
+		mock.expect(
+			client.media_credit().delete_many(vec![
+				media_credit::media_id::equals(media_id.to_string()),
+				media_credit::id::not_in_vec(vec![]),
+			]),
+			0,
+		)
+		.await;
+	}
+}
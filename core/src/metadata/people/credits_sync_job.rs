@@ -0,0 +1,321 @@
+use std::collections::VecDeque;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+	db::entity::MediaMetadata,
+	job::{
+		error::JobError, Executor, JobExecuteLog, JobExt, JobOutputExt, JobProgress,
+		JobTaskOutput, WorkerCtx, WorkingState, WrappedJob,
+	},
+	prisma::{media, media_metadata},
+};
+
+use super::{
+	credited_names, remove_orphaned_people, replace_media_credits, resolve_people,
+};
+
+/// The number of media whose credits are synced by a single task
+const SYNC_BATCH_SIZE: usize = 100;
+
+#[derive(Serialize, Deserialize)]
+pub enum MediaCreditsSyncTask {
+	/// Sync the credits of the given media with their stored metadata
+	SyncMedia { media_ids: Vec<String> },
+}
+
+/// The data that is collected and updated during the execution of a credits sync
+#[derive(Serialize, Deserialize, Default, Debug)]
+pub struct MediaCreditsSyncOutput {
+	/// The number of media whose credits were synced
+	synced_media: u64,
+	/// The number of media which could not be synced
+	failed_media: u64,
+}
+
+impl JobOutputExt for MediaCreditsSyncOutput {
+	fn update(&mut self, updated: Self) {
+		self.synced_media += updated.synced_media;
+		self.failed_media += updated.failed_media;
+	}
+}
+
+/// A job which rebuilds the credits of every media from its stored metadata. Credits are
+/// otherwise only synced when the metadata of a media is written, so this is used to credit
+/// the books which were added before people were tracked.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct MediaCreditsSyncJob;
+
+impl MediaCreditsSyncJob {
+	pub fn new() -> Box<WrappedJob<MediaCreditsSyncJob>> {
+		WrappedJob::new(Self)
+	}
+}
+
+#[async_trait::async_trait]
+impl JobExt for MediaCreditsSyncJob {
+	const NAME: &'static str = "media_credits_sync";
+
+	type Output = MediaCreditsSyncOutput;
+	type Task = MediaCreditsSyncTask;
+
+	fn description(&self) -> Option<String> {
+		None
+	}
+
+	async fn init(
+		&mut self,
+		ctx: &WorkerCtx,
+	) -> Result<WorkingState<Self::Output, Self::Task>, JobError> {
+		let media_ids = ctx
+			.db
+			.media()
+			.find_many(vec![])
+			.select(media::select!({ id }))
+			.exec()
+			.await?
+			.into_iter()
+			.map(|media| media.id)
+			.collect::<Vec<_>>();
+
+		ctx.report_progress(JobProgress::msg(
+			format!("Found {} books to sync", media_ids.len()).as_str(),
+		));
+
+		Ok(WorkingState {
+			output: Some(Self::Output::default()),
+			tasks: media_ids
+				.chunks(SYNC_BATCH_SIZE)
+				.map(|chunk| MediaCreditsSyncTask::SyncMedia {
+					media_ids: chunk.to_vec(),
+				})
+				.collect::<VecDeque<_>>(),
+			completed_tasks: 0,
+			logs: vec![],
+		})
+	}
+
+	async fn cleanup(
+		&self,
+		ctx: &WorkerCtx,
+		_: &Self::Output,
+	) -> Result<Vec<Box<dyn Executor>>, JobError> {
+		// The people left without credits are only removed once every media was synced,
+		// rather than after each one
+		let removed_people = remove_orphaned_people(&ctx.db).await?;
+		tracing::debug!(removed_people, "Removed people without any credits");
+
+		Ok(vec![])
+	}
+
+	async fn execute_task(
+		&self,
+		ctx: &WorkerCtx,
+		task: Self::Task,
+	) -> Result<JobTaskOutput<Self>, JobError> {
+		let mut output = Self::Output::default();
+		let mut logs = vec![];
+
+		match task {
+			MediaCreditsSyncTask::SyncMedia { media_ids } => {
+				let metadata = ctx
+					.db
+					.media_metadata()
+					.find_many(vec![media_metadata::media_id::in_vec(media_ids.clone())])
+					.exec()
+					.await?;
+
+				// Media without any metadata still have their credits cleared
+				let metadata = media_ids
+					.into_iter()
+					.map(|media_id| {
+						let stored_metadata = metadata
+							.iter()
+							.find(|data| {
+								data.media_id.as_deref() == Some(media_id.as_str())
+							})
+							.cloned()
+							.map(MediaMetadata::from)
+							.unwrap_or_default();
+						(media_id, stored_metadata)
+					})
+					.collect::<Vec<_>>();
+
+				// The people credited across the batch are resolved together, rather than
+				// looking them up again for every media they are credited on
+				let people = resolve_people(
+					&ctx.db,
+					metadata
+						.iter()
+						.flat_map(|(_, metadata)| credited_names(metadata)),
+				)
+				.await?;
+
+				for (media_id, stored_metadata) in metadata {
+					match replace_media_credits(
+						&ctx.db,
+						&media_id,
+						&stored_metadata,
+						&people,
+					)
+					.await
+					{
+						Ok(_) => output.synced_media += 1,
+						Err(error) => {
+							tracing::error!(
+								?error,
+								media_id,
+								"Failed to sync media credits"
+							);
+							logs.push(JobExecuteLog::error(format!(
+								"Failed to sync the credits of media {media_id}: {error}"
+							)));
+							output.failed_media += 1;
+						},
+					}
+				}
+			},
+		}
+
+		Ok(JobTaskOutput {
+			output,
+			logs,
+			subtasks: vec![],
+		})
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use serde_json::json;
+
+	use super::*;
+	use crate::{
+		config::StumpConfig,
+		db::entity::CreatorRole,
+		metadata::people::tests::{alias, credit, person},
+		prisma::{media_credit, person, person_alias, PrismaClient},
+	};
+
+	#[tokio::test]
+	async fn test_sync_media_batch() {
+		let (client, mock) = PrismaClient::_mock();
+		let robert = person("robert", "Robert Smith");
+		let jane = person("jane", "Jane Doe");
+		let media_ids = vec!["media_1".to_string(), "media_2".to_string()];
+
+		mock.expect(
+			client
+				.media_metadata()
+				.find_many(vec![media_metadata::media_id::in_vec(media_ids.clone())]),
+			serde_json::from_value(json!([
+				{
+					"id": "metadata_1",
+					"media_id": "media_1",
+					"writers": "Jane Doe, Bob Smith",
+				},
+				{
+					"id": "metadata_2",
+					"media_id": "media_2",
+					"writers": "jane doe",
+				},
+			]))
+			.unwrap(),
+		)
+		.await;
+		// The people are resolved once for the whole batch, with Bob Smith matched to Robert
+		// by an alias from an earlier merge
+		mock.expect(
+			client
+				.person_alias()
+				.find_many(vec![person_alias::normalized_name::in_vec(vec![
+					"bob smith".to_string(),
+					"jane doe".to_string(),
+				])])
+				.with(person_alias::person::fetch()),
+			vec![alias(&robert, "Bob Smith")],
+		)
+		.await;
+		mock.expect(
+			client
+				.person()
+				.find_many(vec![person::normalized_name::in_vec(vec![
+					"jane doe".to_string()
+				])]),
+			vec![jane.clone()],
+		)
+		.await;
+
+		for (credit_id, media_id, person_id) in [
+			("credit_1", "media_1", &jane.id),
+			("credit_2", "media_1", &robert.id),
+			("credit_3", "media_2", &jane.id),
+		] {
+			mock.expect(
+				client.media_credit().upsert(
+					media_credit::media_id_person_id_role(
+						media_id.to_string(),
+						person_id.to_string(),
+						CreatorRole::Writer.to_string(),
+					),
+					(
+						CreatorRole::Writer.to_string(),
+						person::id::equals(person_id.to_string()),
+						media::id::equals(media_id.to_string()),
+						vec![],
+					),
+					vec![],
+				),
+				credit(credit_id, media_id, person_id),
+			)
+			.await;
+		}
+		for (media_id, credit_ids) in [
+			("media_1", vec!["credit_1", "credit_2"]),
+			("media_2", vec!["credit_3"]),
+		] {
+			mock.expect(
+				client.media_credit().delete_many(vec![
+					media_credit::media_id::equals(media_id.to_string()),
+					media_credit::id::not_in_vec(
+						credit_ids.into_iter().map(String::from).collect(),
+					),
+				]),
+				1,
+			)
+			.await;
+		}
+
+		// No orphan cleanup is expected here, even though credits were removed, since it is
+		// left to the end of the job
+		let ctx = WorkerCtx::mock(client, StumpConfig::debug());
+		let JobTaskOutput { output, logs, .. } = MediaCreditsSyncJob
+			.execute_task(&ctx, MediaCreditsSyncTask::SyncMedia { media_ids })
+			.await
+			.unwrap();
+
+		assert_eq!(output.synced_media, 2);
+		assert_eq!(output.failed_media, 0);
+		assert!(logs.is_empty());
+	}
+
+	#[tokio::test]
+	async fn test_cleanup_removes_orphans() {
+		let (client, mock) = PrismaClient::_mock();
+		mock.expect(
+			client.person().delete_many(vec![
+				person::credits::none(vec![]),
+				person::aliases::none(vec![]),
+			]),
+			2,
+		)
+		.await;
+
+		let ctx = WorkerCtx::mock(client, StumpConfig::debug());
+		let follow_ups = MediaCreditsSyncJob
+			.cleanup(&ctx, &MediaCreditsSyncOutput::default())
+			.await
+			.unwrap();
+		assert!(follow_ups.is_empty());
+	}
+}
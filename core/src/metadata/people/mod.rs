@@ -0,0 +1,651 @@
+mod credits_sync_job;
+
+use std::collections::{BTreeMap, HashMap};
+
+use prisma_client_rust::QueryError;
+
+use crate::{
+	db::entity::{normalize_person_name, CreatorRole, MediaMetadata},
+	prisma::{media, media_credit, person, person_alias, PrismaClient},
+	CoreError, CoreResult,
+};
+
+pub use credits_sync_job::{MediaCreditsSyncJob, MediaCreditsSyncOutput};
+
+/// The people resolved for a batch of media, keyed by normalized name
+pub(crate) type ResolvedPeople = HashMap<String, person::Data>;
+
+/// The names of everyone credited in the given metadata, across every [CreatorRole]
+pub(crate) fn credited_names(metadata: &MediaMetadata) -> impl Iterator<Item = &String> {
+	CreatorRole::ALL
+		.into_iter()
+		.flat_map(|role| role.names_in(metadata))
+}
+
+/// Find the people with the given names, matching on their normalized names or any of their
+/// aliases, creating any who don't exist yet. The people are keyed by normalized name, and
+/// names which normalize to nothing (e.g. a lone comma) are skipped.
+pub(crate) async fn resolve_people<'a>(
+	client: &PrismaClient,
+	names: impl IntoIterator<Item = &'a String>,
+) -> Result<ResolvedPeople, QueryError> {
+	// The first spelling of a name is used as the display name of anyone who is created
+	let mut names_by_normalized = BTreeMap::<String, &str>::new();
+	for name in names {
+		let normalized_name = normalize_person_name(name);
+		if !normalized_name.is_empty() {
+			names_by_normalized
+				.entry(normalized_name)
+				.or_insert(name.trim());
+		}
+	}
+	if names_by_normalized.is_empty() {
+		return Ok(HashMap::new());
+	}
+
+	let mut people = client
+		.person_alias()
+		.find_many(vec![person_alias::normalized_name::in_vec(
+			names_by_normalized.keys().cloned().collect(),
+		)])
+		.with(person_alias::person::fetch())
+		.exec()
+		.await?
+		.into_iter()
+		.filter_map(|alias| {
+			let person = alias.person().ok()?.clone();
+			Some((alias.normalized_name, person))
+		})
+		.collect::<HashMap<_, _>>();
+
+	let unmatched_names = names_by_normalized
+		.keys()
+		.filter(|name| !people.contains_key(*name))
+		.cloned()
+		.collect::<Vec<_>>();
+	if !unmatched_names.is_empty() {
+		people.extend(
+			client
+				.person()
+				.find_many(vec![person::normalized_name::in_vec(unmatched_names)])
+				.exec()
+				.await?
+				.into_iter()
+				.map(|person| (person.normalized_name.clone(), person)),
+		);
+	}
+
+	for (normalized_name, name) in names_by_normalized {
+		if people.contains_key(&normalized_name) {
+			continue;
+		}
+		// An upsert is used in case the person was created since they were looked up above
+		let person = client
+			.person()
+			.upsert(
+				person::normalized_name::equals(normalized_name.clone()),
+				(name.to_string(), normalized_name.clone(), vec![]),
+				vec![],
+			)
+			.exec()
+			.await?;
+		people.insert(normalized_name, person);
+	}
+
+	Ok(people)
+}
+
+/// Replaces the credits of a media with the people listed in its metadata, who must have been
+/// resolved with [resolve_people]. Returns the number of credits which were removed.
+async fn replace_media_credits(
+	client: &PrismaClient,
+	media_id: &str,
+	metadata: &MediaMetadata,
+	people: &ResolvedPeople,
+) -> Result<i64, QueryError> {
+	let mut credit_ids = Vec::new();
+	for role in CreatorRole::ALL {
+		for name in role.names_in(metadata) {
+			let Some(person) = people.get(&normalize_person_name(name)) else {
+				continue;
+			};
+
+			let credit = client
+				.media_credit()
+				.upsert(
+					media_credit::media_id_person_id_role(
+						media_id.to_string(),
+						person.id.clone(),
+						role.to_string(),
+					),
+					(
+						role.to_string(),
+						person::id::equals(person.id.clone()),
+						media::id::equals(media_id.to_string()),
+						vec![],
+					),
+					vec![],
+				)
+				.exec()
+				.await?;
+			credit_ids.push(credit.id);
+		}
+	}
+
+	client
+		.media_credit()
+		.delete_many(vec![
+			media_credit::media_id::equals(media_id.to_string()),
+			media_credit::id::not_in_vec(credit_ids),
+		])
+		.exec()
+		.await
+}
+
+/// Replaces the credits of a media with the people listed in its metadata, using the people
+/// resolved for its batch (see [resolve_people]) and only looking up the names which are
+/// missing from them. People left without credits are not removed, since that is done once
+/// per job with [remove_orphaned_people]. Returns the number of credits which were removed.
+pub(crate) async fn credit_media(
+	client: &PrismaClient,
+	media_id: &str,
+	metadata: &MediaMetadata,
+	people: &ResolvedPeople,
+) -> Result<i64, QueryError> {
+	let unresolved_names = credited_names(metadata)
+		.filter(|name| {
+			let normalized_name = normalize_person_name(name);
+			!normalized_name.is_empty() && !people.contains_key(&normalized_name)
+		})
+		.collect::<Vec<_>>();
+	if unresolved_names.is_empty() {
+		return replace_media_credits(client, media_id, metadata, people).await;
+	}
+
+	let mut people = people.clone();
+	people.extend(resolve_people(client, unresolved_names).await?);
+	replace_media_credits(client, media_id, metadata, &people).await
+}
+
+/// Removes the people who are left without any credits, unless they have aliases from a
+/// previous merge. Returns the number of people removed.
+pub(crate) async fn remove_orphaned_people(
+	client: &PrismaClient,
+) -> Result<i64, QueryError> {
+	client
+		.person()
+		.delete_many(vec![
+			person::credits::none(vec![]),
+			person::aliases::none(vec![]),
+		])
+		.exec()
+		.await
+}
+
+/// Replaces the credits of a media with the people listed in its metadata, for each
+/// [CreatorRole]. People are matched by their normalized name or aliases, so the same person
+/// is shared by every book they are credited on. People who are left without any credits are
+/// removed, unless they have aliases from a previous merge.
+pub async fn sync_media_credits(
+	client: &PrismaClient,
+	media_id: &str,
+	metadata: &MediaMetadata,
+) -> Result<(), QueryError> {
+	let removed_credits =
+		credit_media(client, media_id, metadata, &ResolvedPeople::new()).await?;
+	if removed_credits > 0 {
+		let removed_people = remove_orphaned_people(client).await?;
+		tracing::trace!(removed_credits, removed_people, "Removed media credits");
+	}
+
+	Ok(())
+}
+
+/// Merge duplicate people into the target person. The credits of each source person are moved
+/// to the target, and their names (including any aliases) become aliases of the target, so
+/// that books crediting them are matched to the target from then on. The source people are
+/// then removed.
+pub async fn merge_people(
+	db: &PrismaClient,
+	target_id: &str,
+	source_ids: Vec<String>,
+) -> CoreResult<person::Data> {
+	let mut source_ids = source_ids
+		.into_iter()
+		.filter(|id| id != target_id)
+		.collect::<Vec<_>>();
+	source_ids.sort();
+	source_ids.dedup();
+	if source_ids.is_empty() {
+		return Err(CoreError::BadRequest(String::from(
+			"At least one person other than the target must be merged",
+		)));
+	}
+
+	db._transaction()
+		.run(|client| async move { merge_into(&client, target_id, source_ids).await })
+		.await
+}
+
+/// The steps of [merge_people], which are run within a transaction
+async fn merge_into(
+	client: &PrismaClient,
+	target_id: &str,
+	source_ids: Vec<String>,
+) -> CoreResult<person::Data> {
+	let target = client
+		.person()
+		.find_unique(person::id::equals(target_id.to_string()))
+		.exec()
+		.await?
+		.ok_or(CoreError::NotFound(format!(
+			"Person with id {target_id} not found"
+		)))?;
+
+	let sources = client
+		.person()
+		.find_many(vec![person::id::in_vec(source_ids.clone())])
+		.with(person::credits::fetch(vec![]))
+		.exec()
+		.await?;
+	if sources.len() != source_ids.len() {
+		return Err(CoreError::NotFound(String::from(
+			"One or more people to merge were not found",
+		)));
+	}
+
+	for source in sources {
+		for credit in source.credits().cloned().unwrap_or_default() {
+			client
+				.media_credit()
+				.upsert(
+					media_credit::media_id_person_id_role(
+						credit.media_id.clone(),
+						target.id.clone(),
+						credit.role.clone(),
+					),
+					(
+						credit.role,
+						person::id::equals(target.id.clone()),
+						media::id::equals(credit.media_id),
+						vec![],
+					),
+					vec![],
+				)
+				.exec()
+				.await?;
+		}
+
+		client
+			.person_alias()
+			.update_many(
+				vec![person_alias::person_id::equals(source.id.clone())],
+				vec![person_alias::person_id::set(target.id.clone())],
+			)
+			.exec()
+			.await?;
+		client
+			.person_alias()
+			.create(
+				source.name,
+				source.normalized_name,
+				person::id::equals(target.id.clone()),
+				vec![],
+			)
+			.exec()
+			.await?;
+	}
+
+	let removed_people = client
+		.person()
+		.delete_many(vec![person::id::in_vec(source_ids)])
+		.exec()
+		.await?;
+	tracing::debug!(removed_people, target_id, "Merged people");
+
+	client
+		.person()
+		.find_unique(person::id::equals(target.id))
+		.with(person::aliases::fetch(vec![]))
+		.exec()
+		.await?
+		.ok_or(CoreError::NotFound(format!(
+			"Person with id {target_id} not found"
+		)))
+}
+
+#[cfg(test)]
+mod tests {
+	use prisma_client_rust::chrono::DateTime;
+
+	use super::*;
+
+	pub fn person(id: &str, name: &str) -> person::Data {
+		let created_at =
+			DateTime::parse_from_rfc3339("2024-01-01T00:00:00+00:00").unwrap();
+		person::Data {
+			id: id.to_string(),
+			name: name.to_string(),
+			normalized_name: normalize_person_name(name),
+			created_at,
+			updated_at: created_at,
+			credits: None,
+			aliases: None,
+		}
+	}
+
+	pub fn alias(person: &person::Data, name: &str) -> person_alias::Data {
+		person_alias::Data {
+			id: format!("{}_{}", person.id, normalize_person_name(name)),
+			name: name.to_string(),
+			normalized_name: normalize_person_name(name),
+			person_id: person.id.clone(),
+			person: Some(Box::new(person.clone())),
+		}
+	}
+
+	pub fn credit(id: &str, media_id: &str, person_id: &str) -> media_credit::Data {
+		media_credit::Data {
+			id: id.to_string(),
+			role: CreatorRole::Writer.to_string(),
+			person_id: person_id.to_string(),
+			person: None,
+			media_id: media_id.to_string(),
+			media: None,
+		}
+	}
+
+	#[tokio::test]
+	async fn test_resolve_people_matches_aliases() {
+		let (client, mock) = PrismaClient::_mock();
+		let robert = person("robert", "Robert Smith");
+		let jane = person("jane", "Jane Doe");
+		let new_person = person("new", "New Person");
+
+		mock.expect(
+			client
+				.person_alias()
+				.find_many(vec![person_alias::normalized_name::in_vec(vec![
+					"bob smith".to_string(),
+					"jane doe".to_string(),
+					"new person".to_string(),
+				])])
+				.with(person_alias::person::fetch()),
+			vec![alias(&robert, "Bob Smith")],
+		)
+		.await;
+		mock.expect(
+			client
+				.person()
+				.find_many(vec![person::normalized_name::in_vec(vec![
+					"jane doe".to_string(),
+					"new person".to_string(),
+				])]),
+			vec![jane.clone()],
+		)
+		.await;
+		mock.expect(
+			client.person().upsert(
+				person::normalized_name::equals("new person".to_string()),
+				("New Person".to_string(), "new person".to_string(), vec![]),
+				vec![],
+			),
+			new_person.clone(),
+		)
+		.await;
+
+		let names =
+			["Bob Smith", "jane doe", " New Person ", "Jane Doe", ","].map(String::from);
+		let people = resolve_people(&client, names.iter()).await.unwrap();
+
+		assert_eq!(people.len(), 3);
+		assert_eq!(people["bob smith"].id, robert.id);
+		assert_eq!(people["jane doe"].id, jane.id);
+		assert_eq!(people["new person"].id, new_person.id);
+	}
+
+	#[tokio::test]
+	async fn test_resolve_people_without_names() {
+		// Nothing is queried when there is nobody to resolve
+		let (client, _mock) = PrismaClient::_mock();
+		let names = [",".to_string()];
+		assert!(resolve_people(&client, names.iter())
+			.await
+			.unwrap()
+			.is_empty());
+	}
+
+	#[tokio::test]
+	async fn test_sync_media_credits_removes_orphans() {
+		let (client, mock) = PrismaClient::_mock();
+		let jane = person("jane", "Jane Doe");
+
+		mock.expect(
+			client
+				.person_alias()
+				.find_many(vec![person_alias::normalized_name::in_vec(vec![
+					"jane doe".to_string(),
+				])])
+				.with(person_alias::person::fetch()),
+			vec![],
+		)
+		.await;
+		mock.expect(
+			client
+				.person()
+				.find_many(vec![person::normalized_name::in_vec(vec![
+					"jane doe".to_string()
+				])]),
+			vec![jane.clone()],
+		)
+		.await;
+		mock.expect(
+			client.media_credit().upsert(
+				media_credit::media_id_person_id_role(
+					"media".to_string(),
+					jane.id.clone(),
+					CreatorRole::Writer.to_string(),
+				),
+				(
+					CreatorRole::Writer.to_string(),
+					person::id::equals(jane.id.clone()),
+					media::id::equals("media".to_string()),
+					vec![],
+				),
+				vec![],
+			),
+			credit("credit", "media", &jane.id),
+		)
+		.await;
+		mock.expect(
+			client.media_credit().delete_many(vec![
+				media_credit::media_id::equals("media".to_string()),
+				media_credit::id::not_in_vec(vec!["credit".to_string()]),
+			]),
+			1,
+		)
+		.await;
+		mock.expect(
+			client.person().delete_many(vec![
+				person::credits::none(vec![]),
+				person::aliases::none(vec![]),
+			]),
+			1,
+		)
+		.await;
+
+		let metadata = MediaMetadata {
+			writers: Some(vec!["Jane Doe".to_string()]),
+			..Default::default()
+		};
+		let result = sync_media_credits(&client, "media", &metadata).await;
+		assert!(result.is_ok(), "Failed to sync credits: {result:?}");
+	}
+
+	#[tokio::test]
+	async fn test_credit_media_with_resolved_people() {
+		let (client, mock) = PrismaClient::_mock();
+		let jane = person("jane", "Jane Doe");
+		let new_person = person("new", "New Person");
+
+		// Jane was resolved for the batch, so only the other writer is looked up
+		mock.expect(
+			client
+				.person_alias()
+				.find_many(vec![person_alias::normalized_name::in_vec(vec![
+					"new person".to_string(),
+				])])
+				.with(person_alias::person::fetch()),
+			vec![],
+		)
+		.await;
+		mock.expect(
+			client
+				.person()
+				.find_many(vec![person::normalized_name::in_vec(vec![
+					"new person".to_string()
+				])]),
+			vec![new_person.clone()],
+		)
+		.await;
+		for writer in [&jane, &new_person] {
+			mock.expect(
+				client.media_credit().upsert(
+					media_credit::media_id_person_id_role(
+						"media".to_string(),
+						writer.id.clone(),
+						CreatorRole::Writer.to_string(),
+					),
+					(
+						CreatorRole::Writer.to_string(),
+						person::id::equals(writer.id.clone()),
+						media::id::equals("media".to_string()),
+						vec![],
+					),
+					vec![],
+				),
+				credit(&format!("{}_credit", writer.id), "media", &writer.id),
+			)
+			.await;
+		}
+		// The people left without credits are not removed, so no delete of people is expected
+		mock.expect(
+			client.media_credit().delete_many(vec![
+				media_credit::media_id::equals("media".to_string()),
+				media_credit::id::not_in_vec(vec![
+					"jane_credit".to_string(),
+					"new_credit".to_string(),
+				]),
+			]),
+			1,
+		)
+		.await;
+
+		let metadata = MediaMetadata {
+			writers: Some(vec!["Jane Doe".to_string(), "New Person".to_string()]),
+			..Default::default()
+		};
+		let people = ResolvedPeople::from([(jane.normalized_name.clone(), jane.clone())]);
+		let removed_credits = credit_media(&client, "media", &metadata, &people)
+			.await
+			.unwrap();
+		assert_eq!(removed_credits, 1);
+	}
+
+	#[tokio::test]
+	async fn test_merge_people_requires_another_person() {
+		let (client, _mock) = PrismaClient::_mock();
+		let result = merge_people(&client, "target", vec!["target".to_string()]).await;
+		assert!(matches!(result, Err(CoreError::BadRequest(_))));
+	}
+
+	#[tokio::test]
+	async fn test_merge_into() {
+		let (client, mock) = PrismaClient::_mock();
+		let target = person("target", "Jack Kirby");
+		let source = person::Data {
+			credits: Some(vec![credit("credit", "media", "source")]),
+			..person("source", "J. Kirby")
+		};
+
+		mock.expect(
+			client
+				.person()
+				.find_unique(person::id::equals(target.id.clone())),
+			Some(target.clone()),
+		)
+		.await;
+		mock.expect(
+			client
+				.person()
+				.find_many(vec![person::id::in_vec(vec![source.id.clone()])])
+				.with(person::credits::fetch(vec![])),
+			vec![source.clone()],
+		)
+		.await;
+		// The credit of the source is moved to the target
+		mock.expect(
+			client.media_credit().upsert(
+				media_credit::media_id_person_id_role(
+					"media".to_string(),
+					target.id.clone(),
+					CreatorRole::Writer.to_string(),
+				),
+				(
+					CreatorRole::Writer.to_string(),
+					person::id::equals(target.id.clone()),
+					media::id::equals("media".to_string()),
+					vec![],
+				),
+				vec![],
+			),
+			credit("moved_credit", "media", &target.id),
+		)
+		.await;
+		// As are any aliases of the source, and its own name
+		mock.expect(
+			client.person_alias().update_many(
+				vec![person_alias::person_id::equals(source.id.clone())],
+				vec![person_alias::person_id::set(target.id.clone())],
+			),
+			0,
+		)
+		.await;
+		let source_alias = alias(&target, "J. Kirby");
+		mock.expect(
+			client.person_alias().create(
+				source.name.clone(),
+				source.normalized_name.clone(),
+				person::id::equals(target.id.clone()),
+				vec![],
+			),
+			source_alias.clone(),
+		)
+		.await;
+		mock.expect(
+			client
+				.person()
+				.delete_many(vec![person::id::in_vec(vec![source.id.clone()])]),
+			1,
+		)
+		.await;
+		mock.expect(
+			client
+				.person()
+				.find_unique(person::id::equals(target.id.clone()))
+				.with(person::aliases::fetch(vec![])),
+			Some(person::Data {
+				aliases: Some(vec![source_alias]),
+				..target.clone()
+			}),
+		)
+		.await;
+
+		let merged = merge_into(&client, &target.id, vec![source.id.clone()])
+			.await
+			.unwrap();
+		let aliases = merged.aliases().unwrap();
+		assert_eq!(aliases.len(), 1);
+		assert_eq!(aliases[0].normalized_name, "j kirby");
+	}
+}
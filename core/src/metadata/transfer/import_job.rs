@@ -0,0 +1,461 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use serde::{Deserialize, Serialize};
+use specta::Type;
+
+use crate::{
+	db::entity::{CoreJobOutput, MediaMetadata, SeriesMetadata},
+	job::{
+		error::JobError, Executor, JobExecuteLog, JobExt, JobOutputExt, JobProgress,
+		JobTaskOutput, WorkerCtx, WorkingState, WrappedJob,
+	},
+	metadata::{credit_media, remove_orphaned_people, ResolvedPeople},
+	prisma::{media_metadata, series, series_metadata, PrismaClient},
+	CoreEvent, CoreResult,
+};
+
+use super::{
+	export_library_metadata, MetadataFieldChange, MetadataRecord, MetadataRecordKind,
+};
+
+/// The number of records which are applied by a single task
+const IMPORT_BATCH_SIZE: usize = 50;
+
+/// A record which changes the metadata of an entity, pending being applied
+#[derive(Clone, Serialize, Deserialize)]
+pub struct PendingRecord {
+	/// The number of the record in the imported file, starting from 1
+	number: u64,
+	/// The ID of the media or series the record applies to
+	id: String,
+	record: MetadataRecord,
+}
+
+#[derive(Serialize, Deserialize)]
+pub enum MetadataImportTask {
+	/// Apply a batch of records to the metadata of the entities they refer to
+	ApplyBatch(Vec<PendingRecord>),
+}
+
+/// A job which applies the records of an imported metadata file to the series and media of a
+/// library. Every record is validated, and the records which would change nothing are skipped.
+///
+/// Imported values are treated as manual edits, so the fields they change are locked. When
+/// `dry_run` is set, the changes are only reported in the output of the job.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct MetadataImportJob {
+	pub library_id: String,
+	/// The records read from the imported file
+	pub records: Vec<MetadataRecord>,
+	/// Whether to only report the changes the import would make, without applying them
+	pub dry_run: bool,
+}
+
+impl MetadataImportJob {
+	pub fn new(
+		library_id: String,
+		records: Vec<MetadataRecord>,
+		dry_run: bool,
+	) -> Box<WrappedJob<MetadataImportJob>> {
+		WrappedJob::new(Self {
+			library_id,
+			records,
+			dry_run,
+		})
+	}
+}
+
+/// The changes a single record of an import makes to the metadata of a media or series
+#[derive(Clone, Serialize, Deserialize, Debug, Type)]
+pub struct MetadataImportChange {
+	/// The number of the record in the imported file, starting from 1
+	record: u64,
+	kind: MetadataRecordKind,
+	/// The ID of the media or series the record applies to
+	id: String,
+	/// The path of the media or series, relative to the root of the library it is in
+	path: Option<String>,
+	fields: Vec<MetadataFieldChange>,
+}
+
+/// The output of a metadata import, which is reported to clients once it completes
+#[derive(Clone, Serialize, Deserialize, Default, Debug, Type)]
+pub struct MetadataImportOutput {
+	/// The number of media whose metadata was updated, or would be for a dry run
+	updated_media: u64,
+	/// The number of series whose metadata was updated, or would be for a dry run
+	updated_series: u64,
+	/// The number of records which match the current metadata
+	unchanged_records: u64,
+	/// The number of records which could not be applied, e.g. because the entity they refer
+	/// to doesn't exist. The reason for each is recorded in the logs of the job
+	failed_records: u64,
+	/// The changes the import makes, which are only reported for dry runs
+	changes: Vec<MetadataImportChange>,
+}
+
+impl MetadataImportOutput {
+	fn count_updated(&mut self, kind: MetadataRecordKind) {
+		match kind {
+			MetadataRecordKind::Media => self.updated_media += 1,
+			MetadataRecordKind::Series => self.updated_series += 1,
+		}
+	}
+}
+
+impl JobOutputExt for MetadataImportOutput {
+	fn update(&mut self, updated: Self) {
+		self.updated_media += updated.updated_media;
+		self.updated_series += updated.updated_series;
+		self.unchanged_records += updated.unchanged_records;
+		self.failed_records += updated.failed_records;
+		self.changes.extend(updated.changes);
+	}
+}
+
+/// The current records of a library for one kind of entity, indexed by how an imported record
+/// may refer to them
+#[derive(Default)]
+struct RecordIndex {
+	records: Vec<MetadataRecord>,
+	by_id: HashMap<String, usize>,
+	by_path: HashMap<String, Vec<usize>>,
+}
+
+impl RecordIndex {
+	fn push(&mut self, record: MetadataRecord) {
+		let index = self.records.len();
+		if let Some(id) = record.id.clone() {
+			self.by_id.insert(id, index);
+		}
+		if let Some(path) = record.path.clone() {
+			self.by_path.entry(path).or_default().push(index);
+		}
+		self.records.push(record);
+	}
+
+	/// Find the current record an imported record refers to. The ID takes priority, and the
+	/// path is only used when the record has no ID
+	fn resolve(&self, record: &MetadataRecord) -> Result<&MetadataRecord, String> {
+		let kind = record.kind;
+		if let Some(id) = &record.id {
+			return self
+				.by_id
+				.get(id)
+				.map(|index| &self.records[*index])
+				.ok_or_else(|| {
+					format!("No {kind} with the id {id} exists in the library")
+				});
+		}
+
+		let path = record.path.as_deref().unwrap_or_default();
+		match self.by_path.get(path).map(Vec::as_slice) {
+			Some([index]) => Ok(&self.records[*index]),
+			Some([_, _, ..]) => Err(format!(
+				"More than one {kind} has the path {path}, so an id is required"
+			)),
+			_ => Err(format!(
+				"No {kind} with the path {path} exists in the library"
+			)),
+		}
+	}
+}
+
+/// Apply a record to the metadata of a media, locking the fields it changes
+async fn apply_media_record(
+	client: &PrismaClient,
+	media_id: &str,
+	record: &MetadataRecord,
+) -> CoreResult<()> {
+	let existing = client
+		.media_metadata()
+		.find_unique(media_metadata::media_id::equals(media_id.to_string()))
+		.exec()
+		.await?
+		.map(MediaMetadata::from);
+	let imported = record.apply_to_media(existing.clone().unwrap_or_default());
+	let metadata = MediaMetadata::apply_manual_edit(existing, imported);
+
+	let locked_fields_param = metadata.locked_fields_param();
+	let params = metadata
+		.into_prisma()
+		.into_iter()
+		.chain([
+			locked_fields_param,
+			media_metadata::media_id::set(Some(media_id.to_string())),
+		])
+		.collect::<Vec<_>>();
+	let updated = client
+		.media_metadata()
+		.upsert(
+			media_metadata::media_id::equals(media_id.to_string()),
+			params.clone(),
+			params,
+		)
+		.exec()
+		.await?;
+	credit_media(
+		client,
+		media_id,
+		&MediaMetadata::from(updated),
+		&ResolvedPeople::new(),
+	)
+	.await?;
+
+	Ok(())
+}
+
+/// Apply a record to the metadata of a series, locking the fields it changes
+async fn apply_series_record(
+	client: &PrismaClient,
+	series_id: &str,
+	record: &MetadataRecord,
+) -> CoreResult<()> {
+	let existing = client
+		.series_metadata()
+		.find_unique(series_metadata::series_id::equals(series_id.to_string()))
+		.exec()
+		.await?
+		.map(SeriesMetadata::from);
+	let current = existing.clone().unwrap_or_else(|| SeriesMetadata {
+		_type: "comicSeries".to_string(),
+		..Default::default()
+	});
+	let metadata =
+		SeriesMetadata::apply_manual_edit(existing, record.apply_to_series(current));
+
+	let locked_fields_param = metadata.locked_fields_param();
+	let (meta_type, mut params) = metadata.create_action();
+	params.push(locked_fields_param);
+	client
+		.series_metadata()
+		.upsert(
+			series_metadata::series_id::equals(series_id.to_string()),
+			(
+				meta_type,
+				series::id::equals(series_id.to_string()),
+				params.clone(),
+			),
+			params,
+		)
+		.exec()
+		.await?;
+
+	Ok(())
+}
+
+#[async_trait::async_trait]
+impl JobExt for MetadataImportJob {
+	const NAME: &'static str = "metadata_import";
+
+	type Output = MetadataImportOutput;
+	type Task = MetadataImportTask;
+
+	fn description(&self) -> Option<String> {
+		Some(self.library_id.clone())
+	}
+
+	/// The library is locked so that a scan can't change the metadata being imported over
+	fn library_lock(&self) -> Option<String> {
+		Some(self.library_id.clone())
+	}
+
+	async fn init(
+		&mut self,
+		ctx: &WorkerCtx,
+	) -> Result<WorkingState<Self::Output, Self::Task>, JobError> {
+		let mut output = Self::Output::default();
+		let mut logs = vec![];
+
+		ctx.report_progress(JobProgress::msg("Loading the current metadata"));
+		let current_records = export_library_metadata(&ctx.db, &self.library_id)
+			.await
+			.map_err(|error| JobError::InitFailed(error.to_string()))?;
+		let mut media_index = RecordIndex::default();
+		let mut series_index = RecordIndex::default();
+		for record in current_records {
+			match record.kind {
+				MetadataRecordKind::Media => media_index.push(record),
+				MetadataRecordKind::Series => series_index.push(record),
+			}
+		}
+
+		ctx.report_progress(JobProgress::msg("Validating records"));
+		let mut seen_ids = HashSet::new();
+		let mut pending = vec![];
+		for (index, record) in self.records.iter().enumerate() {
+			let number = index as u64 + 1;
+			let record_index = match record.kind {
+				MetadataRecordKind::Media => &media_index,
+				MetadataRecordKind::Series => &series_index,
+			};
+			let changes = record
+				.validate()
+				.map_err(|error| error.to_string())
+				.and_then(|_| record_index.resolve(record))
+				.and_then(|current| {
+					let id = current.id.clone().unwrap_or_default();
+					if !seen_ids.insert(id.clone()) {
+						return Err(format!(
+							"The {} {id} is listed more than once",
+							record.kind
+						));
+					}
+					let fields = record
+						.changed_fields(current)
+						.map_err(|error| error.to_string())?;
+					Ok((id, current.path.clone(), fields))
+				});
+
+			match changes {
+				Err(error) => {
+					logs.push(
+						JobExecuteLog::error(error).with_ctx(format!("Record {number}")),
+					);
+					output.failed_records += 1;
+				},
+				Ok((_, _, fields)) if fields.is_empty() => {
+					output.unchanged_records += 1;
+				},
+				Ok((id, path, fields)) if self.dry_run => {
+					output.count_updated(record.kind);
+					output.changes.push(MetadataImportChange {
+						record: number,
+						kind: record.kind,
+						id,
+						path,
+						fields,
+					});
+				},
+				Ok((id, _, _)) => pending.push(PendingRecord {
+					number,
+					id,
+					record: record.clone(),
+				}),
+			}
+		}
+
+		let tasks = pending
+			.chunks(IMPORT_BATCH_SIZE)
+			.map(|batch| MetadataImportTask::ApplyBatch(batch.to_vec()))
+			.collect::<VecDeque<_>>();
+
+		ctx.report_progress(JobProgress::msg(
+			format!("Found {} records to apply", pending.len()).as_str(),
+		));
+
+		Ok(WorkingState {
+			output: Some(output),
+			tasks,
+			completed_tasks: 0,
+			logs,
+		})
+	}
+
+	async fn cleanup(
+		&self,
+		ctx: &WorkerCtx,
+		output: &Self::Output,
+	) -> Result<Vec<Box<dyn Executor>>, JobError> {
+		ctx.send_core_event(CoreEvent::JobOutput {
+			id: ctx.job_id.clone(),
+			output: CoreJobOutput::MetadataImport(output.clone()),
+		});
+		if !self.dry_run && output.updated_media > 0 {
+			// The people left without credits are only removed once every record was
+			// applied, rather than after each one
+			let removed_people = remove_orphaned_people(&ctx.db).await?;
+			tracing::debug!(removed_people, "Removed people without any credits");
+		}
+		Ok(vec![])
+	}
+
+	async fn execute_task(
+		&self,
+		ctx: &WorkerCtx,
+		task: Self::Task,
+	) -> Result<JobTaskOutput<Self>, JobError> {
+		let mut output = Self::Output::default();
+		let mut logs = vec![];
+
+		match task {
+			MetadataImportTask::ApplyBatch(batch) => {
+				ctx.report_progress(JobProgress::msg(
+					format!("Applying {} records", batch.len()).as_str(),
+				));
+
+				for PendingRecord { number, id, record } in batch {
+					let result = match record.kind {
+						MetadataRecordKind::Media => {
+							apply_media_record(&ctx.db, &id, &record).await
+						},
+						MetadataRecordKind::Series => {
+							apply_series_record(&ctx.db, &id, &record).await
+						},
+					};
+
+					match result {
+						Ok(()) => output.count_updated(record.kind),
+						Err(error) => {
+							tracing::error!(
+								?error,
+								number,
+								"Failed to apply metadata record"
+							);
+							logs.push(
+								JobExecuteLog::error(format!(
+									"Failed to apply the record: {error}"
+								))
+								.with_ctx(format!("Record {number}")),
+							);
+							output.failed_records += 1;
+						},
+					}
+				}
+			},
+		}
+
+		Ok(JobTaskOutput {
+			output,
+			logs,
+			subtasks: vec![],
+		})
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn record(id: Option<&str>, path: &str) -> MetadataRecord {
+		MetadataRecord {
+			kind: MetadataRecordKind::Media,
+			id: id.map(String::from),
+			path: Some(path.to_string()),
+			..Default::default()
+		}
+	}
+
+	#[test]
+	fn test_resolve_record() {
+		let mut index = RecordIndex::default();
+		index.push(record(Some("first"), "Saga/Saga 001.cbz"));
+		index.push(record(Some("second"), "Saga/Saga 002.cbz"));
+		// The same relative path under another root of the library
+		index.push(record(Some("third"), "Saga/Saga 002.cbz"));
+
+		let resolved = index.resolve(&record(None, "Saga/Saga 001.cbz")).unwrap();
+		assert_eq!(resolved.id.as_deref(), Some("first"));
+
+		// The ID takes priority over the path
+		let resolved = index
+			.resolve(&record(Some("third"), "Saga/Saga 001.cbz"))
+			.unwrap();
+		assert_eq!(resolved.id.as_deref(), Some("third"));
+
+		assert!(index.resolve(&record(None, "Saga/Saga 002.cbz")).is_err());
+		assert!(index
+			.resolve(&record(Some("missing"), "Saga/Saga 001.cbz"))
+			.is_err());
+	}
+}
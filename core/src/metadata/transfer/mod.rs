@@ -0,0 +1,216 @@
+//! Exporting the metadata of a library to a file, and importing corrections from one
+
+mod import_job;
+mod record;
+
+use std::path::Path;
+
+use prisma_client_rust::Direction;
+use serde::{Deserialize, Serialize};
+use specta::Type;
+use utoipa::ToSchema;
+
+use crate::{
+	db::entity::{MediaMetadata, SeriesMetadata},
+	filesystem::scanner::get_library_roots,
+	prisma::{library, media, series, PrismaClient},
+	CoreError, CoreResult,
+};
+
+pub use import_job::{MetadataImportChange, MetadataImportJob, MetadataImportOutput};
+pub use record::{MetadataFieldChange, MetadataRecord, MetadataRecordKind};
+
+/// The file formats which library metadata can be exported to and imported from
+#[derive(
+	Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, Type, ToSchema,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum MetadataTransferFormat {
+	#[default]
+	Csv,
+	Json,
+}
+
+impl MetadataTransferFormat {
+	/// Determine the format of a file from its extension
+	pub fn from_extension(extension: &str) -> Option<Self> {
+		match extension.to_lowercase().as_str() {
+			"csv" => Some(Self::Csv),
+			"json" => Some(Self::Json),
+			_ => None,
+		}
+	}
+
+	pub fn extension(&self) -> &'static str {
+		match self {
+			Self::Csv => "csv",
+			Self::Json => "json",
+		}
+	}
+
+	pub fn mime_type(&self) -> &'static str {
+		match self {
+			Self::Csv => "text/csv",
+			Self::Json => "application/json",
+		}
+	}
+
+	/// Write the records to a file in this format
+	pub fn write(&self, records: &[MetadataRecord]) -> CoreResult<Vec<u8>> {
+		match self {
+			Self::Csv => {
+				let mut writer = csv::Writer::from_writer(vec![]);
+				for record in records {
+					writer.serialize(record).map_err(|error| {
+						CoreError::InternalError(format!("Failed to write CSV: {error}"))
+					})?;
+				}
+				writer.into_inner().map_err(|error| {
+					CoreError::InternalError(format!("Failed to write CSV: {error}"))
+				})
+			},
+			Self::Json => Ok(serde_json::to_vec_pretty(records)?),
+		}
+	}
+
+	/// Read the records of a file in this format, erroring if the file is malformed or has
+	/// unknown columns
+	pub fn read(&self, data: &[u8]) -> CoreResult<Vec<MetadataRecord>> {
+		let records = match self {
+			Self::Csv => csv::Reader::from_reader(data)
+				.deserialize::<MetadataRecord>()
+				.collect::<Result<Vec<_>, _>>()
+				.map_err(|error| {
+					CoreError::BadRequest(format!("Invalid CSV: {error}"))
+				})?,
+			Self::Json => {
+				serde_json::from_slice::<Vec<MetadataRecord>>(data).map_err(|error| {
+					CoreError::BadRequest(format!("Invalid JSON: {error}"))
+				})?
+			},
+		};
+		Ok(records
+			.into_iter()
+			.map(MetadataRecord::normalized)
+			.collect())
+	}
+}
+
+/// Build a record for every series and media in a library, each series followed by its
+/// media. Entities are identified by their ID and their path relative to the library root
+/// they are in.
+pub async fn export_library_metadata(
+	client: &PrismaClient,
+	library_id: &str,
+) -> CoreResult<Vec<MetadataRecord>> {
+	let library = client
+		.library()
+		.find_unique(library::id::equals(library_id.to_string()))
+		.exec()
+		.await?
+		.ok_or(CoreError::NotFound(format!(
+			"Library {library_id} not found"
+		)))?;
+	let roots = get_library_roots(client, &library.id, &library.path).await?;
+	let relative_path = |path: &str| {
+		roots
+			.relative(Path::new(path))
+			.to_string_lossy()
+			.to_string()
+	};
+
+	let library_series = client
+		.series()
+		.find_many(vec![series::library_id::equals(Some(library.id.clone()))])
+		.with(series::metadata::fetch())
+		.with(
+			series::media::fetch(vec![])
+				.with(media::metadata::fetch())
+				.order_by(media::path::order(Direction::Asc)),
+		)
+		.order_by(series::path::order(Direction::Asc))
+		.exec()
+		.await?;
+
+	let mut records = vec![];
+	for series in library_series {
+		let metadata = series
+			.metadata()
+			.ok()
+			.flatten()
+			.cloned()
+			.map(SeriesMetadata::from);
+		records.push(MetadataRecord::from_series(
+			series.id.clone(),
+			relative_path(&series.path),
+			metadata,
+		));
+
+		for book in series.media().cloned().unwrap_or_default() {
+			let metadata = book
+				.metadata()
+				.ok()
+				.flatten()
+				.cloned()
+				.map(MediaMetadata::from);
+			records.push(MetadataRecord::from_media(
+				book.id.clone(),
+				relative_path(&book.path),
+				metadata,
+			));
+		}
+	}
+
+	Ok(records)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn records() -> Vec<MetadataRecord> {
+		vec![
+			MetadataRecord {
+				kind: MetadataRecordKind::Series,
+				id: Some("series-id".to_string()),
+				path: Some("Saga".to_string()),
+				title: Some("Saga".to_string()),
+				status: Some("Continuing".to_string()),
+				..Default::default()
+			},
+			MetadataRecord {
+				kind: MetadataRecordKind::Media,
+				id: Some("media-id".to_string()),
+				path: Some("Saga/Saga 001.cbz".to_string()),
+				title: Some("Chapter One".to_string()),
+				number: Some(1.0),
+				writers: Some("Brian K. Vaughan, Fiona Staples".to_string()),
+				..Default::default()
+			},
+		]
+	}
+
+	#[test]
+	fn test_csv_round_trip() {
+		let format = MetadataTransferFormat::Csv;
+		let data = format.write(&records()).unwrap();
+		let content = String::from_utf8(data.clone()).unwrap();
+		assert!(content.starts_with("kind,id,path,title,series,number"));
+		assert!(content.contains("\"Brian K. Vaughan, Fiona Staples\""));
+
+		assert_eq!(format.read(&data).unwrap(), records());
+	}
+
+	#[test]
+	fn test_json_round_trip() {
+		let format = MetadataTransferFormat::Json;
+		let data = format.write(&records()).unwrap();
+		assert_eq!(format.read(&data).unwrap(), records());
+	}
+
+	#[test]
+	fn test_read_rejects_unknown_columns() {
+		let data = b"kind,id,path,subtitle\nMEDIA,media-id,Saga/Saga 001.cbz,Oops\n";
+		assert!(MetadataTransferFormat::Csv.read(data).is_err());
+	}
+}
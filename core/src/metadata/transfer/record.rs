@@ -0,0 +1,445 @@
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+use specta::Type;
+
+use crate::{
+	db::entity::{comma_separated_list_to_vec, MediaMetadata, SeriesMetadata},
+	CoreError, CoreResult,
+};
+
+/// The kind of entity a [MetadataRecord] describes
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, Type)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum MetadataRecordKind {
+	#[default]
+	Media,
+	Series,
+}
+
+impl fmt::Display for MetadataRecordKind {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			MetadataRecordKind::Media => write!(f, "media"),
+			MetadataRecordKind::Series => write!(f, "series"),
+		}
+	}
+}
+
+/// A single row of an exported metadata file, describing the metadata of a media or a series.
+///
+/// The row is flat so that it can be written to CSV as is. Lists, e.g. `writers`, are comma
+/// separated the same way they are in a `ComicInfo.xml` file. An empty value clears the
+/// field when the row is imported.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct MetadataRecord {
+	pub kind: MetadataRecordKind,
+	/// The ID of the media or series. When empty, the entity is found by its path instead
+	pub id: Option<String>,
+	/// The path of the media or series, relative to the root of the library it is in
+	pub path: Option<String>,
+	pub title: Option<String>,
+	pub series: Option<String>,
+	pub number: Option<f64>,
+	pub volume: Option<i32>,
+	pub summary: Option<String>,
+	pub notes: Option<String>,
+	pub age_rating: Option<i32>,
+	pub genre: Option<String>,
+	pub year: Option<i32>,
+	pub month: Option<i32>,
+	pub day: Option<i32>,
+	pub writers: Option<String>,
+	pub pencillers: Option<String>,
+	pub inkers: Option<String>,
+	pub colorists: Option<String>,
+	pub letterers: Option<String>,
+	pub cover_artists: Option<String>,
+	pub editors: Option<String>,
+	pub publisher: Option<String>,
+	pub isbn: Option<String>,
+	pub links: Option<String>,
+	pub characters: Option<String>,
+	pub teams: Option<String>,
+	// The fields below only apply to series
+	pub imprint: Option<String>,
+	pub comicid: Option<i32>,
+	pub booktype: Option<String>,
+	pub status: Option<String>,
+}
+
+/// A metadata field which an import would change
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Type)]
+pub struct MetadataFieldChange {
+	/// The name of the field, e.g. `title`
+	pub field: String,
+	/// The value currently stored for the field
+	pub current_value: serde_json::Value,
+	/// The value in the imported file
+	pub imported_value: serde_json::Value,
+}
+
+/// The names of the fields of a [MetadataRecord] which identify the entity, rather than
+/// describe its metadata
+const IDENTIFYING_FIELDS: [&str; 3] = ["kind", "id", "path"];
+
+fn list_to_value(list: Option<Vec<String>>) -> Option<String> {
+	list.filter(|list| !list.is_empty())
+		.map(|list| list.join(", "))
+}
+
+fn value_to_list(value: &Option<String>) -> Option<Vec<String>> {
+	non_empty(value).map(|value| {
+		comma_separated_list_to_vec(value)
+			.into_iter()
+			.filter(|item| !item.is_empty())
+			.collect()
+	})
+}
+
+fn non_empty(value: &Option<String>) -> Option<String> {
+	value
+		.as_deref()
+		.map(str::trim)
+		.filter(|value| !value.is_empty())
+		.map(String::from)
+}
+
+impl MetadataRecord {
+	/// Create a record for a media, which has no metadata when `metadata` is [None]
+	pub fn from_media(id: String, path: String, metadata: Option<MediaMetadata>) -> Self {
+		let metadata = metadata.unwrap_or_default();
+		Self {
+			kind: MetadataRecordKind::Media,
+			id: Some(id),
+			path: Some(path),
+			title: metadata.title,
+			series: metadata.series,
+			number: metadata.number,
+			volume: metadata.volume,
+			summary: metadata.summary,
+			notes: metadata.notes,
+			age_rating: metadata.age_rating,
+			genre: list_to_value(metadata.genre),
+			year: metadata.year,
+			month: metadata.month,
+			day: metadata.day,
+			writers: list_to_value(metadata.writers),
+			pencillers: list_to_value(metadata.pencillers),
+			inkers: list_to_value(metadata.inkers),
+			colorists: list_to_value(metadata.colorists),
+			letterers: list_to_value(metadata.letterers),
+			cover_artists: list_to_value(metadata.cover_artists),
+			editors: list_to_value(metadata.editors),
+			publisher: metadata.publisher,
+			isbn: metadata.isbn,
+			links: list_to_value(metadata.links),
+			characters: list_to_value(metadata.characters),
+			teams: list_to_value(metadata.teams),
+			..Default::default()
+		}
+	}
+
+	/// Create a record for a series, which has no metadata when `metadata` is [None]
+	pub fn from_series(
+		id: String,
+		path: String,
+		metadata: Option<SeriesMetadata>,
+	) -> Self {
+		let metadata = metadata.unwrap_or_default();
+		Self {
+			kind: MetadataRecordKind::Series,
+			id: Some(id),
+			path: Some(path),
+			title: metadata.title,
+			volume: metadata.volume,
+			summary: metadata.summary,
+			age_rating: metadata.age_rating,
+			publisher: metadata.publisher,
+			imprint: metadata.imprint,
+			comicid: metadata.comicid,
+			booktype: metadata.booktype,
+			status: metadata.status,
+			..Default::default()
+		}
+	}
+
+	/// Normalize the record as it was read from a file, so that empty values are [None] and
+	/// lists are consistently separated
+	pub fn normalized(self) -> Self {
+		let list = |value: Option<String>| list_to_value(value_to_list(&value));
+		let text = |value: Option<String>| non_empty(&value);
+
+		Self {
+			kind: self.kind,
+			id: text(self.id),
+			path: text(self.path),
+			title: text(self.title),
+			series: text(self.series),
+			number: self.number,
+			volume: self.volume,
+			summary: text(self.summary),
+			notes: text(self.notes),
+			age_rating: self.age_rating,
+			genre: list(self.genre),
+			year: self.year,
+			month: self.month,
+			day: self.day,
+			writers: list(self.writers),
+			pencillers: list(self.pencillers),
+			inkers: list(self.inkers),
+			colorists: list(self.colorists),
+			letterers: list(self.letterers),
+			cover_artists: list(self.cover_artists),
+			editors: list(self.editors),
+			publisher: text(self.publisher),
+			isbn: text(self.isbn),
+			links: list(self.links),
+			characters: list(self.characters),
+			teams: list(self.teams),
+			imprint: text(self.imprint),
+			comicid: self.comicid,
+			booktype: text(self.booktype),
+			status: text(self.status),
+		}
+	}
+
+	/// Validate the values of the record, and that it only sets fields which apply to its kind
+	pub fn validate(&self) -> CoreResult<()> {
+		if self.id.is_none() && self.path.is_none() {
+			return Err(CoreError::BadRequest(
+				"Either an id or a path is required".to_string(),
+			));
+		}
+
+		let inapplicable_fields = match self.kind {
+			MetadataRecordKind::Media => vec![
+				("imprint", self.imprint.is_some()),
+				("comicid", self.comicid.is_some()),
+				("booktype", self.booktype.is_some()),
+				("status", self.status.is_some()),
+			],
+			MetadataRecordKind::Series => vec![
+				("series", self.series.is_some()),
+				("number", self.number.is_some()),
+				("notes", self.notes.is_some()),
+				("genre", self.genre.is_some()),
+				("year", self.year.is_some()),
+				("month", self.month.is_some()),
+				("day", self.day.is_some()),
+				("writers", self.writers.is_some()),
+				("pencillers", self.pencillers.is_some()),
+				("inkers", self.inkers.is_some()),
+				("colorists", self.colorists.is_some()),
+				("letterers", self.letterers.is_some()),
+				("cover_artists", self.cover_artists.is_some()),
+				("editors", self.editors.is_some()),
+				("isbn", self.isbn.is_some()),
+				("links", self.links.is_some()),
+				("characters", self.characters.is_some()),
+				("teams", self.teams.is_some()),
+			],
+		};
+		if let Some((field, _)) = inapplicable_fields.iter().find(|(_, is_set)| *is_set) {
+			return Err(CoreError::BadRequest(format!(
+				"The {field} field does not apply to {}",
+				self.kind
+			)));
+		}
+
+		if self.month.is_some_and(|month| !(1..=12).contains(&month)) {
+			return Err(CoreError::BadRequest(
+				"The month must be between 1 and 12".to_string(),
+			));
+		}
+		if self.day.is_some_and(|day| !(1..=31).contains(&day)) {
+			return Err(CoreError::BadRequest(
+				"The day must be between 1 and 31".to_string(),
+			));
+		}
+		if self.age_rating.is_some_and(|age_rating| age_rating < 0) {
+			return Err(CoreError::BadRequest(
+				"The age rating cannot be negative".to_string(),
+			));
+		}
+
+		Ok(())
+	}
+
+	/// The metadata fields whose values differ from those of the `current` record
+	pub fn changed_fields(
+		&self,
+		current: &MetadataRecord,
+	) -> CoreResult<Vec<MetadataFieldChange>> {
+		let serde_json::Value::Object(values) = serde_json::to_value(self)? else {
+			return Ok(vec![]);
+		};
+		let serde_json::Value::Object(mut current_values) =
+			serde_json::to_value(current)?
+		else {
+			return Ok(vec![]);
+		};
+
+		Ok(values
+			.into_iter()
+			.filter(|(field, _)| !IDENTIFYING_FIELDS.contains(&field.as_str()))
+			.filter_map(|(field, imported_value)| {
+				let current_value = current_values.remove(&field).unwrap_or_default();
+				(imported_value != current_value).then_some(MetadataFieldChange {
+					field,
+					current_value,
+					imported_value,
+				})
+			})
+			.collect())
+	}
+
+	/// Apply the record to the current metadata of a media. Every field of the record replaces
+	/// the current value, while everything which isn't part of a record (e.g. the page count)
+	/// is kept
+	pub fn apply_to_media(&self, current: MediaMetadata) -> MediaMetadata {
+		MediaMetadata {
+			title: non_empty(&self.title),
+			series: non_empty(&self.series),
+			number: self.number,
+			volume: self.volume,
+			summary: non_empty(&self.summary),
+			notes: non_empty(&self.notes),
+			age_rating: self.age_rating,
+			genre: value_to_list(&self.genre),
+			year: self.year,
+			month: self.month,
+			day: self.day,
+			writers: value_to_list(&self.writers),
+			pencillers: value_to_list(&self.pencillers),
+			inkers: value_to_list(&self.inkers),
+			colorists: value_to_list(&self.colorists),
+			letterers: value_to_list(&self.letterers),
+			cover_artists: value_to_list(&self.cover_artists),
+			editors: value_to_list(&self.editors),
+			publisher: non_empty(&self.publisher),
+			isbn: non_empty(&self.isbn),
+			links: value_to_list(&self.links),
+			characters: value_to_list(&self.characters),
+			teams: value_to_list(&self.teams),
+			..current
+		}
+	}
+
+	/// Apply the record to the current metadata of a series. Every field of the record
+	/// replaces the current value
+	pub fn apply_to_series(&self, current: SeriesMetadata) -> SeriesMetadata {
+		SeriesMetadata {
+			title: non_empty(&self.title),
+			summary: non_empty(&self.summary),
+			publisher: non_empty(&self.publisher),
+			imprint: non_empty(&self.imprint),
+			comicid: self.comicid,
+			volume: self.volume,
+			booktype: non_empty(&self.booktype),
+			age_rating: self.age_rating,
+			status: non_empty(&self.status),
+			..current
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn media_record() -> MetadataRecord {
+		MetadataRecord {
+			kind: MetadataRecordKind::Media,
+			id: Some("media-id".to_string()),
+			path: Some("Saga/Saga 001.cbz".to_string()),
+			title: Some("Chapter One".to_string()),
+			number: Some(1.0),
+			writers: Some("Brian K. Vaughan".to_string()),
+			..Default::default()
+		}
+	}
+
+	#[test]
+	fn test_normalized() {
+		let record = MetadataRecord {
+			title: Some("  ".to_string()),
+			writers: Some("Brian K. Vaughan,Fiona Staples , ".to_string()),
+			..media_record()
+		};
+		let record = record.normalized();
+		assert_eq!(record.title, None);
+		assert_eq!(
+			record.writers.as_deref(),
+			Some("Brian K. Vaughan, Fiona Staples")
+		);
+	}
+
+	#[test]
+	fn test_validate() {
+		assert!(media_record().validate().is_ok());
+
+		let series_record = MetadataRecord {
+			kind: MetadataRecordKind::Series,
+			..media_record()
+		};
+		assert!(series_record.validate().is_err());
+
+		let invalid_month = MetadataRecord {
+			month: Some(13),
+			..media_record()
+		};
+		assert!(invalid_month.validate().is_err());
+
+		let unidentified = MetadataRecord {
+			id: None,
+			path: None,
+			..media_record()
+		};
+		assert!(unidentified.validate().is_err());
+	}
+
+	#[test]
+	fn test_changed_fields() {
+		let current = media_record();
+		let imported = MetadataRecord {
+			id: None,
+			title: Some("The Beginning".to_string()),
+			..media_record()
+		};
+
+		let changes = imported.changed_fields(&current).unwrap();
+		assert_eq!(
+			changes,
+			vec![MetadataFieldChange {
+				field: "title".to_string(),
+				current_value: serde_json::json!("Chapter One"),
+				imported_value: serde_json::json!("The Beginning"),
+			}]
+		);
+	}
+
+	#[test]
+	fn test_media_round_trip() {
+		let metadata = MediaMetadata {
+			title: Some("Chapter One".to_string()),
+			writers: Some(vec!["Brian K. Vaughan".to_string()]),
+			page_count: Some(44),
+			..Default::default()
+		};
+		let record = MetadataRecord::from_media(
+			"media-id".to_string(),
+			"Saga/Saga 001.cbz".to_string(),
+			Some(metadata.clone()),
+		);
+
+		let applied = record.apply_to_media(MediaMetadata {
+			title: Some("Untitled".to_string()),
+			..metadata.clone()
+		});
+		assert_eq!(applied.title, metadata.title);
+		assert_eq!(applied.writers, metadata.writers);
+		assert_eq!(applied.page_count, Some(44));
+	}
+}
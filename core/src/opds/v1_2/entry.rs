@@ -16,7 +16,7 @@ use crate::filesystem::media::get_content_types_for_pages;
 use crate::filesystem::{ContentType, FileParts, PathUtils};
 use crate::{
 	opds::v1_2::link::OpdsStreamLink,
-	prisma::{library, media, series},
+	prisma::{collection, library, media, person, series, story_arc},
 };
 
 use super::{
@@ -175,6 +175,66 @@ impl IntoOPDSEntry for OPDSEntryBuilder<series::Data> {
 	}
 }
 
+impl IntoOPDSEntry for OPDSEntryBuilder<story_arc::Data> {
+	fn into_opds_entry(self) -> OpdsEntry {
+		let nav_link = OpdsLink::new(
+			OpdsLinkType::Navigation,
+			OpdsLinkRel::Subsection,
+			self.format_url(&format!("story-arcs/{}", self.data.id)),
+		);
+
+		OpdsEntry {
+			id: self.data.id.to_string(),
+			updated: self.data.updated_at,
+			title: self.data.name,
+			content: None,
+			authors: None,
+			links: vec![nav_link],
+			stream_link: None,
+		}
+	}
+}
+
+impl IntoOPDSEntry for OPDSEntryBuilder<person::Data> {
+	fn into_opds_entry(self) -> OpdsEntry {
+		let nav_link = OpdsLink::new(
+			OpdsLinkType::Navigation,
+			OpdsLinkRel::Subsection,
+			self.format_url(&format!("authors/{}", self.data.id)),
+		);
+
+		OpdsEntry {
+			id: self.data.id.to_string(),
+			updated: self.data.updated_at,
+			title: self.data.name,
+			content: None,
+			authors: None,
+			links: vec![nav_link],
+			stream_link: None,
+		}
+	}
+}
+
+impl IntoOPDSEntry for OPDSEntryBuilder<collection::Data> {
+	fn into_opds_entry(self) -> OpdsEntry {
+		let nav_link = OpdsLink::new(
+			OpdsLinkType::Navigation,
+			OpdsLinkRel::Subsection,
+			self.format_url(&format!("collections/{}", self.data.id)),
+		);
+
+		OpdsEntry {
+			id: self.data.id.to_string(),
+			updated: self.data.updated_at,
+			title: self.data.name,
+			content: self.data.description,
+			authors: None,
+			links: vec![nav_link],
+			stream_link: None,
+		}
+	}
+}
+
 impl IntoOPDSEntry for OPDSEntryBuilder<media::Data> {
 	fn into_opds_entry(self) -> OpdsEntry {
 		let base_url = self.format_url(&format!("books/{}", self.data.id));
@@ -7,7 +7,7 @@ use serde_with::skip_serializing_none;
 
 use crate::{
 	filesystem::ContentType,
-	prisma::{library, series},
+	prisma::{collection, library, person, series, story_arc},
 };
 
 use super::{
@@ -326,6 +326,48 @@ impl From<series::Data> for OPDSNavigationLink {
 	}
 }
 
+impl From<story_arc::Data> for OPDSNavigationLink {
+	fn from(story_arc: story_arc::Data) -> Self {
+		OPDSNavigationLink {
+			title: story_arc.name,
+			base_link: OPDSBaseLink {
+				href: format!("/opds/v2.0/story-arcs/{}", story_arc.id),
+				_type: Some(OPDSLinkType::OpdsJson),
+				rel: Some(OPDSLinkRel::Subsection.item()),
+				..Default::default()
+			},
+		}
+	}
+}
+
+impl From<person::Data> for OPDSNavigationLink {
+	fn from(person: person::Data) -> Self {
+		OPDSNavigationLink {
+			title: person.name,
+			base_link: OPDSBaseLink {
+				href: format!("/opds/v2.0/authors/{}", person.id),
+				_type: Some(OPDSLinkType::OpdsJson),
+				rel: Some(OPDSLinkRel::Subsection.item()),
+				..Default::default()
+			},
+		}
+	}
+}
+
+impl From<collection::Data> for OPDSNavigationLink {
+	fn from(collection: collection::Data) -> Self {
+		OPDSNavigationLink {
+			title: collection.name,
+			base_link: OPDSBaseLink {
+				href: format!("/opds/v2.0/collections/{}", collection.id),
+				_type: Some(OPDSLinkType::OpdsJson),
+				rel: Some(OPDSLinkRel::Subsection.item()),
+				..Default::default()
+			},
+		}
+	}
+}
+
 #[cfg(test)]
 mod tests {
 	use crate::opds::v2_0::properties::{OPDSDynamicProperties, OPDSPropertiesBuilder};
@@ -442,14 +484,14 @@ mod tests {
 	fn test_navigation_link_from_library_data() {
 		let library = library::Data {
 			id: "123".to_string(),
+			additional_roots: None,
 			name: "A library".to_string(),
 			created_at: chrono::Utc::now().into(),
 			updated_at: chrono::Utc::now().into(),
 			description: None,
 			emoji: None,
 			hidden_from_users: None,
-			job_schedule_config: None,
-			job_schedule_config_id: None,
+			job_schedules: None,
 			config: None,
 			config_id: String::default(),
 			path: String::default(),
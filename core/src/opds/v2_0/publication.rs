@@ -64,82 +64,77 @@ impl OPDSPublication {
 		"https://readium.org/webpub-manifest/context.jsonld".to_string()
 	}
 
+	/// Convert books into publications, in the order they were given. The books must be
+	/// loaded with their series.
 	pub async fn vec_from_books(
 		client: &PrismaClient,
 		finalizer: OPDSLinkFinalizer,
 		books: Vec<books_as_publications::Data>,
 	) -> CoreResult<Vec<Self>> {
-		let mut series_to_books_map = HashMap::new();
-		let mut series_id_to_series_map = HashMap::new();
+		let mut series_to_book_ids_map = HashMap::new();
 
-		for book in books {
+		for book in &books {
 			if let Some(series) = &book.series {
-				series_to_books_map
+				series_to_book_ids_map
 					.entry(series.id.clone())
 					.or_insert_with(Vec::new)
-					.push(book.clone());
-				series_id_to_series_map.insert(series.id.to_string(), series.clone());
+					.push(book.id.clone());
 			} else {
 				tracing::warn!(book_id = ?book.id, "Book has no series ID!");
 			}
 		}
 
+		let mut positions = HashMap::new();
+		for (series_id, book_ids) in series_to_book_ids_map {
+			positions.extend(client.book_positions_in_series(book_ids, series_id).await?);
+		}
+
 		let mut publications = vec![];
 
-		for (series_id, books) in series_to_books_map {
-			let series = series_id_to_series_map
-				.get(&series_id)
-				.ok_or_else(|| {
-					CoreError::InternalError("Series not found in series map".to_string())
-				})?
-				.clone();
-
-			let positions = client
-				.book_positions_in_series(
-					books.iter().map(|book| book.id.clone()).collect(),
-					series.id.clone(),
-				)
-				.await?;
-
-			for book in books {
-				let links = OPDSPublication::links_for_book(&book, &finalizer)?;
-				let images = OPDSPublication::images_for_book(&book, &finalizer).await?;
-
-				let position = positions.get(&book.id).copied();
-
-				let metadata = book
-					.metadata
-					.clone()
-					.map(MediaMetadata::from)
-					.unwrap_or_default();
-				let title = metadata.title.clone().unwrap_or(book.name);
-				let description = metadata.summary.clone();
-
-				// Unset the title and summary so they don't get serialized twice
-				let media_metadata = MediaMetadata {
-					title: None,
-					summary: None,
-					..metadata
-				};
-
-				let metadata = OPDSMetadataBuilder::default()
-					.title(title)
-					.modified(OPDSMetadata::generate_modified())
-					.description(description)
-					.belongs_to(OPDSEntryBelongsTo::from((series.clone(), position)))
-					.dynamic_metadata(OPDSDynamicMetadata(serde_json::to_value(
-						media_metadata,
-					)?))
-					.build()?;
-
-				let publication = OPDSPublicationBuilder::default()
-					.metadata(metadata)
-					.links(links)
-					.images(images)
-					.build()?;
-
-				publications.push(publication);
-			}
+		for book in books {
+			let Some(series) = book.series.clone() else {
+				continue;
+			};
+
+			let links = OPDSPublication::links_for_book(&book, &finalizer)?;
+			let images = OPDSPublication::images_for_book(&book, &finalizer).await?;
+
+			let position = positions.get(&book.id).copied();
+
+			let metadata = book
+				.metadata
+				.clone()
+				.map(MediaMetadata::from)
+				.unwrap_or_default();
+			let title = metadata.title.clone().unwrap_or(book.name);
+			let description = metadata.summary.clone();
+
+			// Unset the title and summary so they don't get serialized twice, and the
+			// locked fields since they only matter to Stump
+			let media_metadata = MediaMetadata {
+				title: None,
+				summary: None,
+				locked_fields: vec![],
+				..metadata
+			};
+
+			let metadata = OPDSMetadataBuilder::default()
+				.title(title)
+				.modified(OPDSMetadata::generate_modified())
+				.description(description)
+				.belongs_to(OPDSEntryBelongsTo::from((series, position)))
+				.dynamic_metadata(OPDSDynamicMetadata(serde_json::to_value(
+					media_metadata,
+				)?))
+				.build()?;
+
+			let publication = OPDSPublicationBuilder::default()
+				.metadata(metadata)
+				.links(links)
+				.images(images)
+				.build()?;
+
+			publications.push(publication);
 		}
 
 		Ok(publications)
@@ -199,10 +194,12 @@ impl OPDSPublication {
 			reading_order.push(OPDSLink::Image(image_link));
 		}
 
-		// Unset the title and summary so they don't get serialized twice
+		// Unset the title and summary so they don't get serialized twice, and the locked
+		// fields since they only matter to Stump
 		let media_metadata = MediaMetadata {
 			title: None,
 			summary: None,
+			locked_fields: vec![],
 			..metadata
 		};
 
@@ -319,6 +316,7 @@ mod tests {
 			status: FileStatus::Ready.to_string(),
 			hash: Some(String::from("hash")),
 			koreader_hash: None,
+			perceptual_hash: None,
 			series_id: Some("1".to_string()),
 			pages: 0,
 			modified_at: None,
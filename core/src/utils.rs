@@ -1,6 +1,10 @@
+use prisma_client_rust::not;
 use simple_crypt::{decrypt, encrypt};
 
-use crate::{CoreError, CoreResult};
+use crate::{
+	prisma::{server_config, PrismaClient},
+	CoreError, CoreResult,
+};
 
 pub fn chain_optional_iter<T>(
 	required: impl IntoIterator<Item = T>,
@@ -20,6 +24,22 @@ pub fn create_encryption_key() -> CoreResult<String> {
 	Ok(data_encoding::BASE64.encode(&random_bytes))
 }
 
+/// Get the encryption key of the server, which is used to encrypt sensitive data (e.g. passwords
+/// and tokens) before it is stored
+pub async fn get_encryption_key(client: &PrismaClient) -> CoreResult<String> {
+	let server_config = client
+		.server_config()
+		.find_first(vec![not![server_config::encryption_key::equals(None)]])
+		.exec()
+		.await?;
+
+	let encryption_key = server_config
+		.and_then(|config| config.encryption_key)
+		.ok_or(CoreError::EncryptionKeyNotSet)?;
+
+	Ok(encryption_key)
+}
+
 pub fn encrypt_string(str: &str, encryption_key: &String) -> CoreResult<String> {
 	let encrypted_bytes = encrypt(str.as_bytes(), encryption_key.as_bytes())
 		.map_err(|e| CoreError::EncryptionFailed(e.to_string()))?;
@@ -0,0 +1,17 @@
+pub type ComicVineResult<T> = Result<T, ComicVineError>;
+
+#[derive(Debug, thiserror::Error)]
+pub enum ComicVineError {
+	#[error("Request failed with error: {0}")]
+	ReqwestError(#[from] reqwest::Error),
+	#[error("Failed to parse response: {0}")]
+	ParseError(#[from] serde_json::Error),
+	#[error("The ComicVine API key is invalid")]
+	InvalidApiKey,
+	#[error("The requested resource was not found")]
+	NotFound,
+	#[error("The ComicVine rate limit was exceeded")]
+	RateLimited,
+	#[error("ComicVine returned an error: {0}")]
+	ApiError(String),
+}
@@ -0,0 +1,403 @@
+mod error;
+mod types;
+
+use std::{
+	collections::HashMap,
+	sync::Mutex,
+	time::{Duration, Instant},
+};
+
+use reqwest::header::USER_AGENT;
+use serde::de::DeserializeOwned;
+
+pub use error::{ComicVineError, ComicVineResult};
+use types::ComicVineResponse;
+pub use types::{ComicVineCredit, ComicVineIssue, ComicVineVolume};
+
+pub const COMICVINE_API_URL: &str = "https://comicvine.gamespot.com/api";
+
+/// ComicVine rejects requests without a user agent
+const CLIENT_USER_AGENT: &str = "Stump";
+/// ComicVine asks that clients make no more than one request per second, and will
+/// temporarily block clients which exceed this
+const DEFAULT_REQUEST_INTERVAL: Duration = Duration::from_secs(1);
+const DEFAULT_CACHE_TTL: Duration = Duration::from_secs(60 * 60);
+/// The maximum number of results ComicVine returns for a single list request
+const PAGE_SIZE: usize = 100;
+
+const VOLUME_FIELDS: &str =
+	"id,name,start_year,publisher,count_of_issues,deck,description,site_detail_url";
+const ISSUE_LIST_FIELDS: &str = "id,name,issue_number,cover_date,volume";
+const ISSUE_FIELDS: &str = "id,name,issue_number,cover_date,deck,description,volume,person_credits,character_credits,team_credits,site_detail_url";
+
+/// Spaces out requests so that at most one is sent per interval. Requests which arrive
+/// while another is waiting queue up behind it.
+struct RateLimiter {
+	interval: Duration,
+	next_request_at: tokio::sync::Mutex<Option<tokio::time::Instant>>,
+}
+
+impl RateLimiter {
+	fn new(interval: Duration) -> Self {
+		Self {
+			interval,
+			next_request_at: tokio::sync::Mutex::new(None),
+		}
+	}
+
+	async fn wait(&self) {
+		let mut next_request_at = self.next_request_at.lock().await;
+		if let Some(at) = *next_request_at {
+			tokio::time::sleep_until(at).await;
+		}
+		*next_request_at = Some(tokio::time::Instant::now() + self.interval);
+	}
+}
+
+/// An in-memory cache of successful responses, keyed by the request URL
+struct ResponseCache {
+	ttl: Duration,
+	entries: Mutex<HashMap<String, (Instant, serde_json::Value)>>,
+}
+
+impl ResponseCache {
+	fn new(ttl: Duration) -> Self {
+		Self {
+			ttl,
+			entries: Mutex::new(HashMap::new()),
+		}
+	}
+
+	fn get(&self, key: &str) -> Option<serde_json::Value> {
+		let entries = self.entries.lock().ok()?;
+		entries
+			.get(key)
+			.filter(|(cached_at, _)| cached_at.elapsed() < self.ttl)
+			.map(|(_, body)| body.clone())
+	}
+
+	fn insert(&self, key: String, body: serde_json::Value) {
+		if let Ok(mut entries) = self.entries.lock() {
+			entries.retain(|_, (cached_at, _)| cached_at.elapsed() < self.ttl);
+			entries.insert(key, (Instant::now(), body));
+		}
+	}
+}
+
+/// A client for the [ComicVine API](https://comicvine.gamespot.com/api/documentation).
+/// Requests are rate limited and successful responses are cached, so a single client should
+/// be shared for all requests made with an API key.
+pub struct ComicVineClient {
+	api_key: String,
+	base_url: String,
+	client: reqwest::Client,
+	rate_limiter: RateLimiter,
+	cache: ResponseCache,
+}
+
+impl ComicVineClient {
+	pub fn new(api_key: String) -> Self {
+		Self::with_base_url(api_key, COMICVINE_API_URL.to_string())
+	}
+
+	/// Create a client which sends requests to the given URL rather than the ComicVine API,
+	/// e.g. a local stub server
+	pub fn with_base_url(api_key: String, base_url: String) -> Self {
+		Self {
+			api_key,
+			base_url: base_url.trim_end_matches('/').to_string(),
+			client: reqwest::Client::new(),
+			rate_limiter: RateLimiter::new(DEFAULT_REQUEST_INTERVAL),
+			cache: ResponseCache::new(DEFAULT_CACHE_TTL),
+		}
+	}
+
+	/// Set the minimum time between two requests
+	pub fn with_request_interval(mut self, interval: Duration) -> Self {
+		self.rate_limiter = RateLimiter::new(interval);
+		self
+	}
+
+	/// Set how long successful responses are cached for
+	pub fn with_cache_ttl(mut self, ttl: Duration) -> Self {
+		self.cache = ResponseCache::new(ttl);
+		self
+	}
+
+	pub fn api_key(&self) -> &str {
+		&self.api_key
+	}
+
+	/// Search for volumes by name
+	pub async fn search_volumes(
+		&self,
+		query: &str,
+	) -> ComicVineResult<Vec<ComicVineVolume>> {
+		let response = self
+			.request::<Vec<ComicVineVolume>>(
+				"search/",
+				&[
+					("query", query),
+					("resources", "volume"),
+					("field_list", VOLUME_FIELDS),
+				],
+			)
+			.await?;
+		Ok(response.results)
+	}
+
+	/// Get a single volume by its ComicVine ID
+	pub async fn get_volume(&self, id: i64) -> ComicVineResult<ComicVineVolume> {
+		let response = self
+			.request::<ComicVineVolume>(
+				&format!("volume/4050-{id}/"),
+				&[("field_list", VOLUME_FIELDS)],
+			)
+			.await?;
+		Ok(response.results)
+	}
+
+	/// Get every issue of a volume. The issues only include the fields needed to match them
+	/// to books, see [`ComicVineClient::get_issue`] for the full details of an issue.
+	pub async fn get_volume_issues(
+		&self,
+		volume_id: i64,
+	) -> ComicVineResult<Vec<ComicVineIssue>> {
+		let filter = format!("volume:{volume_id}");
+		let limit = PAGE_SIZE.to_string();
+		let mut issues = vec![];
+
+		loop {
+			let offset = issues.len().to_string();
+			let response = self
+				.request::<Vec<ComicVineIssue>>(
+					"issues/",
+					&[
+						("filter", filter.as_str()),
+						("field_list", ISSUE_LIST_FIELDS),
+						("limit", limit.as_str()),
+						("offset", offset.as_str()),
+					],
+				)
+				.await?;
+			let page_size = response.results.len();
+			issues.extend(response.results);
+
+			if page_size == 0 || issues.len() >= response.number_of_total_results {
+				break;
+			}
+		}
+
+		Ok(issues)
+	}
+
+	/// Get the full details of a single issue by its ComicVine ID, including credits
+	pub async fn get_issue(&self, id: i64) -> ComicVineResult<ComicVineIssue> {
+		let response = self
+			.request::<ComicVineIssue>(
+				&format!("issue/4000-{id}/"),
+				&[("field_list", ISSUE_FIELDS)],
+			)
+			.await?;
+		Ok(response.results)
+	}
+
+	async fn request<T: DeserializeOwned>(
+		&self,
+		path: &str,
+		params: &[(&str, &str)],
+	) -> ComicVineResult<ComicVineResponse<T>> {
+		let url = format!("{}/{}", self.base_url, path);
+		// The API key is left out of the cache key, since it doesn't change the response
+		let cache_key = format!(
+			"{url}?{}",
+			params
+				.iter()
+				.map(|(key, value)| format!("{key}={value}"))
+				.collect::<Vec<_>>()
+				.join("&")
+		);
+
+		let body = match self.cache.get(&cache_key) {
+			Some(body) => body,
+			None => {
+				self.rate_limiter.wait().await;
+				let response = self
+					.client
+					.get(&url)
+					.header(USER_AGENT, CLIENT_USER_AGENT)
+					.query(&[("api_key", self.api_key.as_str()), ("format", "json")])
+					.query(params)
+					.send()
+					.await?;
+				let status = response.status();
+				let body = response.json::<serde_json::Value>().await?;
+				check_status_code(&body)?;
+				if !status.is_success() {
+					return Err(ComicVineError::ApiError(status.to_string()));
+				}
+				self.cache.insert(cache_key, body.clone());
+				body
+			},
+		};
+
+		Ok(serde_json::from_value(body)?)
+	}
+}
+
+/// ComicVine reports errors with a `status_code` in the body of the response, see
+/// <https://comicvine.gamespot.com/api/documentation#toc-0-0>
+fn check_status_code(body: &serde_json::Value) -> ComicVineResult<()> {
+	match body["status_code"].as_i64() {
+		Some(1) => Ok(()),
+		Some(100) => Err(ComicVineError::InvalidApiKey),
+		Some(101) => Err(ComicVineError::NotFound),
+		Some(107) => Err(ComicVineError::RateLimited),
+		_ => Err(ComicVineError::ApiError(
+			body["error"]
+				.as_str()
+				.unwrap_or("Unknown error")
+				.to_string(),
+		)),
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use serde_json::json;
+
+	use super::*;
+	use crate::test_utils::StubServer;
+
+	fn client(server: &StubServer) -> ComicVineClient {
+		ComicVineClient::with_base_url(String::from("test_key"), server.url.clone())
+			.with_request_interval(Duration::ZERO)
+	}
+
+	fn ok(results: serde_json::Value, total: usize) -> serde_json::Value {
+		json!({
+			"error": "OK",
+			"status_code": 1,
+			"number_of_total_results": total,
+			"results": results,
+		})
+	}
+
+	#[tokio::test]
+	async fn test_search_volumes() {
+		let server = StubServer::start(|target| {
+			assert!(target.starts_with("/search/"));
+			assert!(target.contains("api_key=test_key"));
+			assert!(target.contains("resources=volume"));
+			ok(
+				json!([{
+					"id": 4050,
+					"name": "Saga",
+					"start_year": "2012",
+					"publisher": { "id": 1, "name": "Image" },
+					"count_of_issues": 66,
+				}]),
+				1,
+			)
+		});
+
+		let volumes = client(&server).search_volumes("Saga").await.unwrap();
+		assert_eq!(volumes.len(), 1);
+		assert_eq!(volumes[0].name, "Saga");
+		assert_eq!(volumes[0].year(), Some(2012));
+		assert_eq!(
+			volumes[0].publisher.as_ref().map(|p| p.name.as_str()),
+			Some("Image")
+		);
+	}
+
+	#[tokio::test]
+	async fn test_responses_are_cached() {
+		let server = StubServer::start(|_| ok(json!({ "id": 1, "name": "Saga" }), 1));
+		let client = client(&server);
+
+		client.get_volume(1).await.unwrap();
+		client.get_volume(1).await.unwrap();
+		assert_eq!(server.request_count(), 1);
+
+		client.get_volume(2).await.unwrap();
+		assert_eq!(server.request_count(), 2);
+	}
+
+	#[tokio::test]
+	async fn test_requests_are_rate_limited() {
+		let server = StubServer::start(|_| ok(json!({ "id": 1, "name": "Saga" }), 1));
+		let client = client(&server).with_request_interval(Duration::from_millis(200));
+
+		let start = Instant::now();
+		client.get_volume(1).await.unwrap();
+		client.get_volume(2).await.unwrap();
+		assert!(start.elapsed() >= Duration::from_millis(200));
+	}
+
+	#[tokio::test]
+	async fn test_get_volume_issues_paginates() {
+		let server = StubServer::start(|target| {
+			let issues = if target.contains("offset=0") {
+				(1..=100)
+					.map(|n| json!({ "id": n, "issue_number": n.to_string() }))
+					.collect::<Vec<_>>()
+			} else {
+				vec![json!({ "id": 101, "issue_number": "101" })]
+			};
+			ok(json!(issues), 101)
+		});
+
+		let issues = client(&server).get_volume_issues(1).await.unwrap();
+		assert_eq!(issues.len(), 101);
+		assert_eq!(issues[100].number(), Some(101.0));
+		assert_eq!(server.request_count(), 2);
+	}
+
+	#[tokio::test]
+	async fn test_get_issue_credits() {
+		let server = StubServer::start(|_| {
+			ok(
+				json!({
+					"id": 1,
+					"issue_number": "1",
+					"person_credits": [
+						{ "id": 1, "name": "Brian K. Vaughan", "role": "writer" },
+						{ "id": 2, "name": "Fiona Staples", "role": "artist, cover" },
+					],
+					"character_credits": null,
+				}),
+				1,
+			)
+		});
+
+		let issue = client(&server).get_issue(1).await.unwrap();
+		assert_eq!(
+			issue.people_with_role(&["writer"]),
+			vec!["Brian K. Vaughan"]
+		);
+		assert_eq!(issue.people_with_role(&["cover"]), vec!["Fiona Staples"]);
+		assert!(issue.character_credits.is_empty());
+	}
+
+	#[tokio::test]
+	async fn test_error_status_codes() {
+		let server = StubServer::start(|target| {
+			if target.starts_with("/volume/") {
+				json!({ "error": "Invalid API Key", "status_code": 100, "results": [] })
+			} else {
+				json!({ "error": "Object Not Found", "status_code": 101, "results": [] })
+			}
+		});
+		let client = client(&server);
+
+		assert!(matches!(
+			client.get_volume(1).await,
+			Err(ComicVineError::InvalidApiKey)
+		));
+		assert!(matches!(
+			client.get_issue(1).await,
+			Err(ComicVineError::NotFound)
+		));
+	}
+}
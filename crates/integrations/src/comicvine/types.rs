@@ -0,0 +1,106 @@
+use serde::{Deserialize, Deserializer, Serialize};
+
+/// ComicVine returns `null` rather than an empty list for some list fields
+fn null_as_default<'de, D, T>(deserializer: D) -> Result<T, D::Error>
+where
+	D: Deserializer<'de>,
+	T: Default + Deserialize<'de>,
+{
+	Ok(Option::<T>::deserialize(deserializer)?.unwrap_or_default())
+}
+
+/// The envelope which every ComicVine API response is wrapped in
+#[derive(Debug, Deserialize)]
+pub(crate) struct ComicVineResponse<T> {
+	#[serde(default)]
+	pub number_of_total_results: usize,
+	pub results: T,
+}
+
+/// A reference to another ComicVine resource, e.g. the publisher of a volume or a person
+/// credited on an issue
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ComicVineCredit {
+	pub id: Option<i64>,
+	#[serde(default)]
+	pub name: String,
+	/// A comma separated list of roles, only present for person credits, e.g. `writer, cover`
+	pub role: Option<String>,
+}
+
+impl ComicVineCredit {
+	/// Whether the credit includes any of the given roles
+	pub fn has_role(&self, roles: &[&str]) -> bool {
+		self.role.as_deref().map_or(false, |role| {
+			role.split(',')
+				.map(|r| r.trim().to_lowercase())
+				.any(|r| roles.contains(&r.as_str()))
+		})
+	}
+}
+
+/// A ComicVine volume, which is what Stump refers to as a series
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ComicVineVolume {
+	pub id: i64,
+	#[serde(default)]
+	pub name: String,
+	/// The year the volume started, e.g. `2011`
+	pub start_year: Option<String>,
+	pub publisher: Option<ComicVineCredit>,
+	pub count_of_issues: Option<i64>,
+	/// A short summary of the volume
+	pub deck: Option<String>,
+	/// A long form description of the volume, formatted as HTML
+	pub description: Option<String>,
+	pub site_detail_url: Option<String>,
+}
+
+impl ComicVineVolume {
+	/// The year the volume started, if it is known
+	pub fn year(&self) -> Option<i32> {
+		self.start_year
+			.as_deref()
+			.and_then(|y| y.trim().parse().ok())
+	}
+}
+
+/// A single issue of a ComicVine volume
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ComicVineIssue {
+	pub id: i64,
+	pub name: Option<String>,
+	/// The number of the issue, which is not necessarily an integer, e.g. `1.MU`
+	pub issue_number: Option<String>,
+	/// The date on the cover of the issue, formatted as `YYYY-MM-DD`
+	pub cover_date: Option<String>,
+	pub deck: Option<String>,
+	/// A long form description of the issue, formatted as HTML
+	pub description: Option<String>,
+	pub volume: Option<ComicVineCredit>,
+	#[serde(default, deserialize_with = "null_as_default")]
+	pub person_credits: Vec<ComicVineCredit>,
+	#[serde(default, deserialize_with = "null_as_default")]
+	pub character_credits: Vec<ComicVineCredit>,
+	#[serde(default, deserialize_with = "null_as_default")]
+	pub team_credits: Vec<ComicVineCredit>,
+	pub site_detail_url: Option<String>,
+}
+
+impl ComicVineIssue {
+	/// The issue number as a float, if it can be parsed as one
+	pub fn number(&self) -> Option<f64> {
+		self.issue_number
+			.as_deref()
+			.and_then(|n| n.trim().parse().ok())
+	}
+
+	/// The names of the people credited with any of the given roles
+	pub fn people_with_role(&self, roles: &[&str]) -> Vec<String> {
+		self.person_credits
+			.iter()
+			.filter(|credit| credit.has_role(roles))
+			.map(|credit| credit.name.clone())
+			.collect()
+	}
+}
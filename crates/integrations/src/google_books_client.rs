@@ -1,28 +1,386 @@
+use reqwest::StatusCode;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use crate::metadata_provider::{
+	parse_date_parts, MetadataProvider, MetadataProviderError, MetadataProviderResult,
+	MetadataQuery, ProviderBook,
+};
+
+pub const GOOGLE_BOOKS_API_URL: &str = "https://www.googleapis.com/books/v1";
+
+pub type GoogleBooksResult<T> = Result<T, GoogleBooksError>;
+
+#[derive(Debug, thiserror::Error)]
+pub enum GoogleBooksError {
+	#[error("Request failed with error: {0}")]
+	ReqwestError(#[from] reqwest::Error),
+	#[error("The Google Books API key is invalid")]
+	InvalidApiKey,
+	#[error("The Google Books quota was exceeded")]
+	RateLimited,
+	#[error("Google Books returned an error: {0}")]
+	ApiError(String),
+}
+
+impl From<GoogleBooksError> for MetadataProviderError {
+	fn from(error: GoogleBooksError) -> Self {
+		match error {
+			GoogleBooksError::ReqwestError(error) => Self::ReqwestError(error),
+			error => Self::ProviderError(error.to_string()),
+		}
+	}
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GoogleBooksResponse {
+	#[serde(default)]
+	items: Vec<GoogleBooksVolume>,
+}
+
+#[derive(Debug, Deserialize)]
+struct GoogleBooksErrorResponse {
+	error: GoogleBooksErrorBody,
+}
+
+#[derive(Debug, Deserialize)]
+struct GoogleBooksErrorBody {
+	#[serde(default)]
+	message: String,
+}
+
+/// A book in the Google Books catalog
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GoogleBooksVolume {
+	pub id: String,
+	pub volume_info: GoogleBooksVolumeInfo,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GoogleBooksVolumeInfo {
+	pub title: Option<String>,
+	pub subtitle: Option<String>,
+	#[serde(default)]
+	pub authors: Vec<String>,
+	pub publisher: Option<String>,
+	/// The date the book was published, which may only be a year or a year and month, e.g.
+	/// `2004`, `2004-03` or `2004-03-15`
+	pub published_date: Option<String>,
+	pub description: Option<String>,
+	#[serde(default)]
+	pub industry_identifiers: Vec<GoogleBooksIdentifier>,
+	pub page_count: Option<i32>,
+	#[serde(default)]
+	pub categories: Vec<String>,
+	pub image_links: Option<GoogleBooksImageLinks>,
+	pub language: Option<String>,
+	pub info_link: Option<String>,
+}
+
+impl GoogleBooksVolumeInfo {
+	/// The year, month and day the book was published, as far as they are known
+	pub fn published_date_parts(&self) -> (Option<i32>, Option<i32>, Option<i32>) {
+		self.published_date
+			.as_deref()
+			.map(parse_date_parts)
+			.unwrap_or_default()
+	}
+
+	/// The URL of the largest cover image available
+	pub fn cover_url(&self) -> Option<String> {
+		let links = self.image_links.as_ref()?;
+		links
+			.large
+			.as_ref()
+			.or(links.medium.as_ref())
+			.or(links.small.as_ref())
+			.or(links.thumbnail.as_ref())
+			.or(links.small_thumbnail.as_ref())
+			.cloned()
+	}
+}
+
+/// An identifier of a book, e.g. its ISBN
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GoogleBooksIdentifier {
+	/// The kind of identifier, e.g. `ISBN_10` or `ISBN_13`
+	#[serde(rename = "type")]
+	pub kind: String,
+	pub identifier: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GoogleBooksImageLinks {
+	pub small_thumbnail: Option<String>,
+	pub thumbnail: Option<String>,
+	pub small: Option<String>,
+	pub medium: Option<String>,
+	pub large: Option<String>,
+}
+
+impl From<GoogleBooksVolume> for ProviderBook {
+	fn from(volume: GoogleBooksVolume) -> Self {
+		let info = volume.volume_info;
+		let cover_url = info.cover_url();
+		// Prefer the ISBN-13, since every ISBN-10 has an equivalent ISBN-13 but not vice versa
+		let isbn = ["ISBN_13", "ISBN_10"].iter().find_map(|kind| {
+			info.industry_identifiers
+				.iter()
+				.find(|identifier| identifier.kind == *kind)
+				.map(|identifier| identifier.identifier.clone())
+		});
+
+		ProviderBook {
+			id: volume.id,
+			title: info.title,
+			subtitle: info.subtitle,
+			authors: info.authors,
+			publisher: info.publisher,
+			published_date: info.published_date,
+			description: info.description,
+			subjects: info.categories,
+			isbn,
+			page_count: info.page_count,
+			language: info.language,
+			cover_url,
+			link: info.info_link,
+			..Default::default()
+		}
+	}
+}
+
+/// A client for the [Google Books API](https://developers.google.com/books/docs/v1/using)
 pub struct GoogleBooksClient {
 	pub api_key: String,
 	pub client: reqwest::Client,
+	base_url: String,
 }
 
 impl GoogleBooksClient {
 	pub fn new(api_key: String) -> Self {
-		let client = reqwest::Client::new();
-		Self { api_key, client }
+		Self::with_base_url(api_key, GOOGLE_BOOKS_API_URL.to_string())
+	}
+
+	/// Create a client which sends requests to the given URL rather than the Google Books
+	/// API, e.g. a local stub server
+	pub fn with_base_url(api_key: String, base_url: String) -> Self {
+		Self {
+			api_key,
+			client: reqwest::Client::new(),
+			base_url: base_url.trim_end_matches('/').to_string(),
+		}
 	}
 
+	/// Send a GET request, mapping error responses to a [GoogleBooksError]
+	async fn get<T: DeserializeOwned>(
+		&self,
+		path: &str,
+		query: &[(&str, &str)],
+	) -> GoogleBooksResult<Option<T>> {
+		let response = self
+			.client
+			.get(format!("{}{path}", self.base_url))
+			.query(query)
+			.query(&[("key", self.api_key.as_str())])
+			.send()
+			.await?;
+
+		let status = response.status();
+		if status == StatusCode::NOT_FOUND {
+			return Ok(None);
+		} else if !status.is_success() {
+			let message = response
+				.json::<GoogleBooksErrorResponse>()
+				.await
+				.map(|body| body.error.message)
+				.unwrap_or_else(|_| status.to_string());
+			return Err(match status.as_u16() {
+				429 => GoogleBooksError::RateLimited,
+				400 | 403 if message.to_lowercase().contains("api key") => {
+					GoogleBooksError::InvalidApiKey
+				},
+				_ => GoogleBooksError::ApiError(message),
+			});
+		}
+
+		Ok(Some(response.json::<T>().await?))
+	}
+
+	/// Search for books using the [query syntax](https://developers.google.com/books/docs/v1/using#PerformingSearch)
+	/// of Google Books, e.g. `intitle:dune+inauthor:herbert`
+	pub async fn search_volumes(
+		&self,
+		query: &str,
+	) -> GoogleBooksResult<Vec<GoogleBooksVolume>> {
+		let body = self
+			.get::<GoogleBooksResponse>("/volumes", &[("q", query)])
+			.await?;
+		Ok(body.map(|body| body.items).unwrap_or_default())
+	}
+
+	/// Look up a book by its ISBN, returning [None] if Google Books does not know it
 	pub async fn get_book_by_isbn(
 		&self,
 		isbn: &str,
-	) -> Result<(), Box<dyn std::error::Error>> {
-		let url = format!(
-			"https://www.googleapis.com/books/v1/volumes?q=isbn:{}&key={}",
-			isbn, self.api_key
+	) -> GoogleBooksResult<Option<GoogleBooksVolume>> {
+		let volumes = self.search_volumes(&format!("isbn:{isbn}")).await?;
+		Ok(volumes.into_iter().next())
+	}
+
+	/// Get a book by its Google Books ID, returning [None] if it does not exist
+	pub async fn get_volume(
+		&self,
+		id: &str,
+	) -> GoogleBooksResult<Option<GoogleBooksVolume>> {
+		if id.is_empty()
+			|| !id
+				.chars()
+				.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+		{
+			return Err(GoogleBooksError::ApiError(format!(
+				"Invalid volume ID: {id}"
+			)));
+		}
+		self.get(&format!("/volumes/{id}"), &[]).await
+	}
+}
+
+#[async_trait::async_trait]
+impl MetadataProvider for GoogleBooksClient {
+	async fn search(
+		&self,
+		query: &MetadataQuery,
+	) -> MetadataProviderResult<Vec<ProviderBook>> {
+		let search = match (&query.isbn, &query.title) {
+			(Some(isbn), _) => format!("isbn:{isbn}"),
+			(None, Some(title)) => match query.authors.first() {
+				Some(author) => format!("intitle:{title}+inauthor:{author}"),
+				None => format!("intitle:{title}"),
+			},
+			(None, None) => return Ok(vec![]),
+		};
+
+		let volumes = self.search_volumes(&search).await?;
+		Ok(volumes.into_iter().map(ProviderBook::from).collect())
+	}
+
+	async fn fetch(&self, id: &str) -> MetadataProviderResult<Option<ProviderBook>> {
+		Ok(self.get_volume(id).await?.map(ProviderBook::from))
+	}
+
+	async fn fetch_cover(&self, id: &str) -> MetadataProviderResult<Option<Vec<u8>>> {
+		let Some(url) = self
+			.get_volume(id)
+			.await?
+			.and_then(|volume| volume.volume_info.cover_url())
+		else {
+			return Ok(None);
+		};
+
+		let response = self.client.get(url).send().await?;
+		if !response.status().is_success() {
+			return Ok(None);
+		}
+		Ok(Some(response.bytes().await?.to_vec()))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use serde_json::json;
+
+	use super::*;
+	use crate::test_utils::StubServer;
+
+	fn client(server: &StubServer) -> GoogleBooksClient {
+		GoogleBooksClient::with_base_url(String::from("test_key"), server.url.clone())
+	}
+
+	#[tokio::test]
+	async fn test_get_book_by_isbn() {
+		let server = StubServer::start(|target| {
+			assert!(target.starts_with("/volumes?"));
+			assert!(target.contains("q=isbn%3A9780141036144"));
+			assert!(target.contains("key=test_key"));
+			json!({
+				"kind": "books#volumes",
+				"totalItems": 1,
+				"items": [{
+					"id": "kotPYEqx7kMC",
+					"volumeInfo": {
+						"title": "Nineteen Eighty-Four",
+						"authors": ["George Orwell"],
+						"publisher": "Penguin UK",
+						"publishedDate": "2008-03",
+						"description": "A dystopian novel",
+						"industryIdentifiers": [
+							{ "type": "ISBN_13", "identifier": "9780141036144" }
+						],
+						"pageCount": 400,
+						"categories": ["Fiction"],
+						"imageLinks": {
+							"smallThumbnail": "http://books.google.com/small",
+							"thumbnail": "http://books.google.com/thumbnail"
+						}
+					}
+				}]
+			})
+		});
+
+		let book = client(&server)
+			.get_book_by_isbn("9780141036144")
+			.await
+			.unwrap()
+			.expect("Book should be found");
+		let info = book.volume_info;
+		assert_eq!(info.title.as_deref(), Some("Nineteen Eighty-Four"));
+		assert_eq!(info.authors, vec!["George Orwell"]);
+		assert_eq!(info.page_count, Some(400));
+		assert_eq!(info.published_date_parts(), (Some(2008), Some(3), None));
+		assert_eq!(
+			info.cover_url().as_deref(),
+			Some("http://books.google.com/thumbnail")
 		);
-		let response = self.client.get(&url).send().await?;
-		let text = response.text().await?;
-		println!("Response text: {text}");
-		// let response = response.json::<GoogleBooksResponse>().await?;
-		// let book = response.into();
-		// Ok(book)
-		unimplemented!()
+	}
+
+	#[tokio::test]
+	async fn test_get_unknown_book() {
+		let server =
+			StubServer::start(|_| json!({ "kind": "books#volumes", "totalItems": 0 }));
+		let book = client(&server)
+			.get_book_by_isbn("0000000000")
+			.await
+			.unwrap();
+		assert!(book.is_none());
+	}
+
+	#[tokio::test]
+	async fn test_error_responses() {
+		let server = StubServer::start_with_status(|target| {
+			if target.contains("9780141036144") {
+				(
+					429,
+					json!({ "error": { "code": 429, "message": "Quota exceeded" } }),
+				)
+			} else {
+				(
+					400,
+					json!({ "error": { "code": 400, "message": "API key not valid" } }),
+				)
+			}
+		});
+		let client = client(&server);
+
+		assert!(matches!(
+			client.get_book_by_isbn("9780141036144").await,
+			Err(GoogleBooksError::RateLimited)
+		));
+		assert!(matches!(
+			client.get_book_by_isbn("0141036141").await,
+			Err(GoogleBooksError::InvalidApiKey)
+		));
 	}
 }
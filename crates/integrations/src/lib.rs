@@ -1,7 +1,25 @@
 #![warn(clippy::dbg_macro)]
 
+mod comicvine;
 mod google_books_client;
+mod metadata_provider;
 mod notifier;
+#[cfg(any(test, feature = "test-utils"))]
+pub mod test_utils;
 
-pub use google_books_client::GoogleBooksClient;
-pub use notifier::{DiscordClient, Notifier, TelegramClient};
+pub use comicvine::{
+	ComicVineClient, ComicVineCredit, ComicVineError, ComicVineIssue, ComicVineVolume,
+	COMICVINE_API_URL,
+};
+pub use google_books_client::{
+	GoogleBooksClient, GoogleBooksError, GoogleBooksIdentifier, GoogleBooksImageLinks,
+	GoogleBooksVolume, GoogleBooksVolumeInfo, GOOGLE_BOOKS_API_URL,
+};
+pub use metadata_provider::{
+	MetadataProvider, MetadataProviderError, MetadataProviderResult, MetadataQuery,
+	OpenLibraryClient, ProviderBook, SidecarProvider, OPEN_LIBRARY_API_URL,
+	OPEN_LIBRARY_COVERS_URL,
+};
+pub use notifier::{
+	DiscordClient, Notifier, NotifierError, NotifierEvent, TelegramClient, WebhookClient,
+};
@@ -0,0 +1,21 @@
+pub type MetadataProviderResult<T> = Result<T, MetadataProviderError>;
+
+#[derive(Debug, thiserror::Error)]
+pub enum MetadataProviderError {
+	#[error("Request failed with error: {0}")]
+	ReqwestError(#[from] reqwest::Error),
+	#[error("Failed to read file: {0}")]
+	IoError(#[from] std::io::Error),
+	#[error("Failed to parse metadata: {0}")]
+	ParseError(String),
+	#[error("Invalid ID: {0}")]
+	InvalidId(String),
+	#[error("The provider returned an error: {0}")]
+	ProviderError(String),
+}
+
+impl From<serde_json::Error> for MetadataProviderError {
+	fn from(error: serde_json::Error) -> Self {
+		Self::ParseError(error.to_string())
+	}
+}
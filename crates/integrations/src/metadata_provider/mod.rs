@@ -0,0 +1,104 @@
+mod error;
+mod open_library;
+mod sidecar;
+
+use serde::{Deserialize, Serialize};
+
+pub use error::{MetadataProviderError, MetadataProviderResult};
+pub use open_library::{
+	OpenLibraryClient, OPEN_LIBRARY_API_URL, OPEN_LIBRARY_COVERS_URL,
+};
+pub use sidecar::SidecarProvider;
+
+/// What is known about a book which metadata is being looked up for
+#[derive(Debug, Clone, Default)]
+pub struct MetadataQuery {
+	pub title: Option<String>,
+	pub authors: Vec<String>,
+	pub isbn: Option<String>,
+}
+
+/// A book as described by a metadata provider. Every provider maps its own response to this,
+/// so that callers don't need to know which provider a book came from
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ProviderBook {
+	/// The ID of the book with the provider, which can be used to fetch it again
+	#[serde(default)]
+	pub id: String,
+	pub title: Option<String>,
+	pub subtitle: Option<String>,
+	#[serde(default)]
+	pub authors: Vec<String>,
+	pub publisher: Option<String>,
+	/// The date the book was published, which may only be a year or a year and month, e.g.
+	/// `2004`, `2004-03` or `2004-03-15`
+	pub published_date: Option<String>,
+	pub description: Option<String>,
+	#[serde(default)]
+	pub subjects: Vec<String>,
+	pub isbn: Option<String>,
+	pub page_count: Option<i32>,
+	pub series: Option<String>,
+	pub number: Option<f64>,
+	pub language: Option<String>,
+	pub cover_url: Option<String>,
+	/// A link to the book on the website of the provider
+	pub link: Option<String>,
+}
+
+impl ProviderBook {
+	/// The year, month and day the book was published, as far as they are known
+	pub fn published_date_parts(&self) -> (Option<i32>, Option<i32>, Option<i32>) {
+		self.published_date
+			.as_deref()
+			.map(parse_date_parts)
+			.unwrap_or_default()
+	}
+}
+
+/// Parse a date which may be partial, e.g. `2004`, `2004-03` or `2004-03-15`, into its year,
+/// month and day. Anything after the day (e.g. a time) is ignored
+pub(crate) fn parse_date_parts(date: &str) -> (Option<i32>, Option<i32>, Option<i32>) {
+	let date = date.trim();
+	let date = date.get(..10).unwrap_or(date);
+	let mut parts = date.split('-').map(|part| part.trim().parse::<i32>().ok());
+	let year = parts.next().flatten();
+	let month = parts.next().flatten().filter(|m| (1..=12).contains(m));
+	let day = parts.next().flatten().filter(|d| (1..=31).contains(d));
+	(year, month, day)
+}
+
+/// A source of book metadata, e.g. a web service or files stored next to the books
+#[async_trait::async_trait]
+pub trait MetadataProvider: Send + Sync {
+	/// Search for books which could match the query. Results are ordered by relevance, as far
+	/// as the provider knows it
+	async fn search(
+		&self,
+		query: &MetadataQuery,
+	) -> MetadataProviderResult<Vec<ProviderBook>>;
+
+	/// Fetch a book by its ID with the provider, returning [None] if it does not exist
+	async fn fetch(&self, id: &str) -> MetadataProviderResult<Option<ProviderBook>>;
+
+	/// Fetch the cover image of a book by its ID with the provider, returning [None] if the
+	/// book has no cover
+	async fn fetch_cover(&self, id: &str) -> MetadataProviderResult<Option<Vec<u8>>>;
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_parse_date_parts() {
+		assert_eq!(parse_date_parts("2004"), (Some(2004), None, None));
+		assert_eq!(parse_date_parts("2004-03"), (Some(2004), Some(3), None));
+		assert_eq!(
+			parse_date_parts("2004-03-15T00:00:00+00:00"),
+			(Some(2004), Some(3), Some(15))
+		);
+		assert_eq!(parse_date_parts("2004-13-00"), (Some(2004), None, None));
+		assert_eq!(parse_date_parts("March 2004"), (None, None, None));
+	}
+}
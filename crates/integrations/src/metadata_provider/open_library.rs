@@ -0,0 +1,364 @@
+use reqwest::StatusCode;
+use serde::{de::DeserializeOwned, Deserialize};
+
+use super::{
+	MetadataProvider, MetadataProviderError, MetadataProviderResult, MetadataQuery,
+	ProviderBook,
+};
+
+pub const OPEN_LIBRARY_API_URL: &str = "https://openlibrary.org";
+pub const OPEN_LIBRARY_COVERS_URL: &str = "https://covers.openlibrary.org";
+
+/// The maximum number of results requested when searching
+const SEARCH_LIMIT: usize = 10;
+/// The maximum number of authors fetched for a work, since each requires its own request
+const MAX_AUTHORS: usize = 5;
+const SEARCH_FIELDS: &str = "key,title,subtitle,author_name,publisher,first_publish_year,isbn,number_of_pages_median,cover_i,subject,language";
+
+#[derive(Debug, Deserialize)]
+struct SearchResponse {
+	#[serde(default)]
+	docs: Vec<SearchDoc>,
+}
+
+#[derive(Debug, Deserialize)]
+struct SearchDoc {
+	key: String,
+	title: Option<String>,
+	subtitle: Option<String>,
+	#[serde(default)]
+	author_name: Vec<String>,
+	#[serde(default)]
+	publisher: Vec<String>,
+	first_publish_year: Option<i32>,
+	#[serde(default)]
+	isbn: Vec<String>,
+	number_of_pages_median: Option<i32>,
+	cover_i: Option<i64>,
+	#[serde(default)]
+	subject: Vec<String>,
+	#[serde(default)]
+	language: Vec<String>,
+}
+
+/// OpenLibrary returns some text either as a plain string or as a typed value
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum TextValue {
+	Plain(String),
+	Typed { value: String },
+}
+
+impl From<TextValue> for String {
+	fn from(value: TextValue) -> Self {
+		match value {
+			TextValue::Plain(value) | TextValue::Typed { value } => value,
+		}
+	}
+}
+
+#[derive(Debug, Deserialize)]
+struct Work {
+	key: String,
+	title: Option<String>,
+	subtitle: Option<String>,
+	description: Option<TextValue>,
+	#[serde(default)]
+	subjects: Vec<String>,
+	#[serde(default)]
+	covers: Vec<i64>,
+	first_publish_date: Option<String>,
+	#[serde(default)]
+	authors: Vec<WorkAuthor>,
+}
+
+#[derive(Debug, Deserialize)]
+struct WorkAuthor {
+	author: KeyRef,
+}
+
+#[derive(Debug, Deserialize)]
+struct KeyRef {
+	key: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct Author {
+	name: String,
+}
+
+/// A client for the [OpenLibrary API](https://openlibrary.org/developers/api). Books are
+/// identified by the ID of their work, e.g. `OL45883W`. No API key is required
+pub struct OpenLibraryClient {
+	client: reqwest::Client,
+	base_url: String,
+	covers_url: String,
+}
+
+impl Default for OpenLibraryClient {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+impl OpenLibraryClient {
+	pub fn new() -> Self {
+		Self::with_base_urls(
+			OPEN_LIBRARY_API_URL.to_string(),
+			OPEN_LIBRARY_COVERS_URL.to_string(),
+		)
+	}
+
+	/// Create a client which sends requests to the given URLs rather than OpenLibrary, e.g. a
+	/// local stub server
+	pub fn with_base_urls(base_url: String, covers_url: String) -> Self {
+		Self {
+			client: reqwest::Client::new(),
+			base_url: base_url.trim_end_matches('/').to_string(),
+			covers_url: covers_url.trim_end_matches('/').to_string(),
+		}
+	}
+
+	/// Send a GET request, returning [None] if the resource does not exist
+	async fn get<T: DeserializeOwned>(
+		&self,
+		url: String,
+		query: &[(&str, &str)],
+	) -> MetadataProviderResult<Option<T>> {
+		let response = self.client.get(url).query(query).send().await?;
+		match response.status() {
+			StatusCode::NOT_FOUND => Ok(None),
+			status if status.is_success() => Ok(Some(response.json::<T>().await?)),
+			status => Err(MetadataProviderError::ProviderError(format!(
+				"OpenLibrary responded with {status}"
+			))),
+		}
+	}
+
+	fn cover_url(&self, cover_id: i64) -> Option<String> {
+		// OpenLibrary uses -1 for works whose cover was removed
+		(cover_id > 0).then(|| format!("{}/b/id/{cover_id}-L.jpg", self.covers_url))
+	}
+
+	async fn fetch_work(&self, id: &str) -> MetadataProviderResult<Option<Work>> {
+		let is_work_id = id.starts_with("OL")
+			&& id.ends_with('W')
+			&& id.len() > 3
+			&& id[2..id.len() - 1].chars().all(|c| c.is_ascii_digit());
+		if !is_work_id {
+			return Err(MetadataProviderError::InvalidId(id.to_string()));
+		}
+
+		self.get(format!("{}/works/{id}.json", self.base_url), &[])
+			.await
+	}
+
+	fn doc_to_book(&self, doc: SearchDoc, isbn: Option<&str>) -> ProviderBook {
+		// Prefer the ISBN which was searched for, since a work has an ISBN per edition
+		let isbn = isbn.map(String::from).or_else(|| {
+			doc.isbn
+				.iter()
+				.find(|isbn| isbn.len() == 13)
+				.or(doc.isbn.first())
+				.cloned()
+		});
+
+		ProviderBook {
+			id: work_id(&doc.key),
+			link: Some(format!("{}{}", self.base_url, doc.key)),
+			title: doc.title,
+			subtitle: doc.subtitle,
+			authors: doc.author_name,
+			publisher: doc.publisher.into_iter().next(),
+			published_date: doc.first_publish_year.map(|year| year.to_string()),
+			subjects: doc.subject,
+			isbn,
+			page_count: doc.number_of_pages_median,
+			language: doc.language.into_iter().next(),
+			cover_url: doc.cover_i.and_then(|id| self.cover_url(id)),
+			..Default::default()
+		}
+	}
+}
+
+/// Strip the prefix of a work key, e.g. `/works/OL45883W` to `OL45883W`
+fn work_id(key: &str) -> String {
+	key.trim_start_matches("/works/").to_string()
+}
+
+#[async_trait::async_trait]
+impl MetadataProvider for OpenLibraryClient {
+	async fn search(
+		&self,
+		query: &MetadataQuery,
+	) -> MetadataProviderResult<Vec<ProviderBook>> {
+		let limit = SEARCH_LIMIT.to_string();
+		let isbn_query = query.isbn.as_ref().map(|isbn| format!("isbn:{isbn}"));
+		let mut params = vec![("fields", SEARCH_FIELDS), ("limit", limit.as_str())];
+
+		match (isbn_query.as_deref(), query.title.as_deref()) {
+			(Some(isbn), _) => params.push(("q", isbn)),
+			(None, Some(title)) => {
+				params.push(("title", title));
+				if let Some(author) = query.authors.first() {
+					params.push(("author", author.as_str()));
+				}
+			},
+			(None, None) => return Ok(vec![]),
+		}
+
+		let response = self
+			.get::<SearchResponse>(format!("{}/search.json", self.base_url), &params)
+			.await?;
+		Ok(response
+			.map(|response| response.docs)
+			.unwrap_or_default()
+			.into_iter()
+			.map(|doc| self.doc_to_book(doc, query.isbn.as_deref()))
+			.collect())
+	}
+
+	async fn fetch(&self, id: &str) -> MetadataProviderResult<Option<ProviderBook>> {
+		let Some(work) = self.fetch_work(id).await? else {
+			return Ok(None);
+		};
+
+		let mut authors = Vec::new();
+		for author in work.authors.iter().take(MAX_AUTHORS) {
+			let url = format!("{}{}.json", self.base_url, author.author.key);
+			if let Some(author) = self.get::<Author>(url, &[]).await? {
+				authors.push(author.name);
+			}
+		}
+
+		Ok(Some(ProviderBook {
+			id: work_id(&work.key),
+			link: Some(format!("{}{}", self.base_url, work.key)),
+			title: work.title,
+			subtitle: work.subtitle,
+			authors,
+			published_date: work.first_publish_date,
+			description: work.description.map(String::from),
+			subjects: work.subjects,
+			cover_url: work.covers.first().and_then(|id| self.cover_url(*id)),
+			..Default::default()
+		}))
+	}
+
+	async fn fetch_cover(&self, id: &str) -> MetadataProviderResult<Option<Vec<u8>>> {
+		let Some(url) = self
+			.fetch_work(id)
+			.await?
+			.and_then(|work| work.covers.first().and_then(|id| self.cover_url(*id)))
+		else {
+			return Ok(None);
+		};
+
+		let response = self.client.get(url).send().await?;
+		match response.status() {
+			StatusCode::NOT_FOUND => Ok(None),
+			status if status.is_success() => Ok(Some(response.bytes().await?.to_vec())),
+			status => Err(MetadataProviderError::ProviderError(format!(
+				"OpenLibrary responded with {status}"
+			))),
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use serde_json::json;
+
+	use super::*;
+	use crate::test_utils::StubServer;
+
+	fn client(server: &StubServer) -> OpenLibraryClient {
+		OpenLibraryClient::with_base_urls(server.url.clone(), server.url.clone())
+	}
+
+	#[tokio::test]
+	async fn test_search_by_title() {
+		let server = StubServer::start(|target| {
+			assert!(target.starts_with("/search.json?"));
+			assert!(target.contains("title=Nineteen+Eighty-Four"));
+			assert!(target.contains("author=George+Orwell"));
+			json!({
+				"numFound": 1,
+				"docs": [{
+					"key": "/works/OL1168083W",
+					"title": "Nineteen Eighty-Four",
+					"author_name": ["George Orwell"],
+					"publisher": ["Secker & Warburg"],
+					"first_publish_year": 1949,
+					"isbn": ["0141036141", "9780141036144"],
+					"number_of_pages_median": 326,
+					"cover_i": 12345
+				}]
+			})
+		});
+
+		let query = MetadataQuery {
+			title: Some("Nineteen Eighty-Four".to_string()),
+			authors: vec!["George Orwell".to_string()],
+			isbn: None,
+		};
+		let books = client(&server).search(&query).await.unwrap();
+		assert_eq!(books.len(), 1);
+
+		let book = &books[0];
+		assert_eq!(book.id, "OL1168083W");
+		assert_eq!(book.authors, vec!["George Orwell"]);
+		assert_eq!(book.published_date_parts(), (Some(1949), None, None));
+		assert_eq!(book.isbn.as_deref(), Some("9780141036144"));
+		assert_eq!(book.page_count, Some(326));
+		assert_eq!(
+			book.cover_url,
+			Some(format!("{}/b/id/12345-L.jpg", server.url))
+		);
+	}
+
+	#[tokio::test]
+	async fn test_search_without_title_or_isbn() {
+		let server = StubServer::start(|_| json!({ "docs": [] }));
+		let books = client(&server)
+			.search(&MetadataQuery::default())
+			.await
+			.unwrap();
+		assert!(books.is_empty());
+		assert_eq!(server.request_count(), 0);
+	}
+
+	#[tokio::test]
+	async fn test_fetch_work() {
+		let server = StubServer::start(|target| match target {
+			"/works/OL1168083W.json" => json!({
+				"key": "/works/OL1168083W",
+				"title": "Nineteen Eighty-Four",
+				"description": { "type": "/type/text", "value": "A dystopian novel" },
+				"subjects": ["Totalitarianism"],
+				"covers": [-1],
+				"authors": [{ "author": { "key": "/authors/OL118077A" } }]
+			}),
+			"/authors/OL118077A.json" => json!({ "name": "George Orwell" }),
+			_ => panic!("Unexpected request to {target}"),
+		});
+
+		let book = client(&server)
+			.fetch("OL1168083W")
+			.await
+			.unwrap()
+			.expect("Work should be found");
+		assert_eq!(book.title.as_deref(), Some("Nineteen Eighty-Four"));
+		assert_eq!(book.description.as_deref(), Some("A dystopian novel"));
+		assert_eq!(book.authors, vec!["George Orwell"]);
+		assert_eq!(book.cover_url, None);
+	}
+
+	#[tokio::test]
+	async fn test_fetch_invalid_id() {
+		let server = StubServer::start(|_| json!({}));
+		let result = client(&server).fetch("../authors/OL1A").await;
+		assert!(matches!(result, Err(MetadataProviderError::InvalidId(_))));
+		assert_eq!(server.request_count(), 0);
+	}
+}
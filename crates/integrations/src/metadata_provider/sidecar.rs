@@ -0,0 +1,277 @@
+use std::path::{Path, PathBuf};
+
+use xml::{attribute::OwnedAttribute, reader::XmlEvent, EventReader};
+
+use super::{
+	MetadataProvider, MetadataProviderError, MetadataProviderResult, MetadataQuery,
+	ProviderBook,
+};
+
+/// The extensions of the cover images which are looked for next to a book
+const COVER_EXTENSIONS: [&str; 4] = ["jpg", "jpeg", "png", "webp"];
+
+/// A provider which reads metadata from files stored next to a book, rather than from a web
+/// service. For a book at `/books/Book.epub`, the following files are read if they exist:
+///
+/// - `/books/Book.opf` or `/books/metadata.opf`, e.g. as written by Calibre
+/// - `/books/Book.json` or `/books/metadata.json`, in the format of [ProviderBook]
+///
+/// Each file is a candidate, identified by its file name. Covers are read from an image with
+/// the same name as the book or named `cover`, e.g. `/books/Book.jpg` or `/books/cover.png`
+pub struct SidecarProvider {
+	directory: PathBuf,
+	stem: String,
+}
+
+impl SidecarProvider {
+	/// Create a provider which reads the sidecar files of the book at the given path
+	pub fn for_book(path: &Path) -> Self {
+		Self {
+			directory: path.parent().map(Path::to_path_buf).unwrap_or_default(),
+			stem: path
+				.file_stem()
+				.map(|stem| stem.to_string_lossy().to_string())
+				.unwrap_or_default(),
+		}
+	}
+
+	/// The names of the files which may hold metadata for the book, in priority order
+	fn sidecar_names(&self) -> Vec<String> {
+		vec![
+			format!("{}.opf", self.stem),
+			"metadata.opf".to_string(),
+			format!("{}.json", self.stem),
+			"metadata.json".to_string(),
+		]
+	}
+
+	fn read_sidecar(&self, name: &str) -> MetadataProviderResult<Option<ProviderBook>> {
+		let path = self.directory.join(name);
+		if !path.is_file() {
+			return Ok(None);
+		}
+
+		let content = std::fs::read_to_string(&path)?;
+		let mut book = if name.ends_with(".opf") {
+			parse_opf(&content)?
+		} else {
+			serde_json::from_str::<ProviderBook>(&content)?
+		};
+		book.id = name.to_string();
+		Ok(Some(book))
+	}
+}
+
+fn attribute<'a>(attributes: &'a [OwnedAttribute], name: &str) -> Option<&'a str> {
+	attributes
+		.iter()
+		.find(|attribute| attribute.name.local_name == name)
+		.map(|attribute| attribute.value.as_str())
+}
+
+/// Parse the Dublin Core metadata of an OPF file
+pub(crate) fn parse_opf(content: &str) -> MetadataProviderResult<ProviderBook> {
+	let mut book = ProviderBook::default();
+	let mut in_metadata = false;
+	let mut element: Option<(String, Vec<OwnedAttribute>)> = None;
+	let mut text = String::new();
+
+	for event in EventReader::from_str(content) {
+		let event = event
+			.map_err(|error| MetadataProviderError::ParseError(error.to_string()))?;
+
+		match event {
+			XmlEvent::StartElement {
+				name, attributes, ..
+			} => {
+				if name.local_name == "metadata" {
+					in_metadata = true;
+				} else if in_metadata && name.local_name == "meta" {
+					// Calibre stores the series as <meta name="calibre:series" content="..."/>
+					let content = attribute(&attributes, "content").map(String::from);
+					match attribute(&attributes, "name") {
+						Some("calibre:series") => book.series = content,
+						Some("calibre:series_index") => {
+							book.number = content.and_then(|n| n.parse().ok())
+						},
+						_ => (),
+					}
+				} else if in_metadata {
+					element = Some((name.local_name, attributes));
+					text.clear();
+				}
+			},
+			XmlEvent::Characters(value) | XmlEvent::CData(value) => text.push_str(&value),
+			XmlEvent::EndElement { name } if name.local_name == "metadata" => break,
+			XmlEvent::EndElement { .. } => {
+				let Some((name, attributes)) = element.take() else {
+					continue;
+				};
+				let value = text.trim().to_string();
+				if value.is_empty() {
+					continue;
+				}
+
+				match name.as_str() {
+					"title" if book.title.is_none() => book.title = Some(value),
+					"creator" => {
+						// Only authors are kept, not e.g. illustrators or editors
+						let role = attribute(&attributes, "role");
+						if role.map_or(true, |role| role == "aut") {
+							book.authors.push(value);
+						}
+					},
+					"publisher" => book.publisher = Some(value),
+					"date" if book.published_date.is_none() => {
+						book.published_date = Some(value)
+					},
+					"description" => book.description = Some(value),
+					"subject" => book.subjects.push(value),
+					"language" if book.language.is_none() => book.language = Some(value),
+					"identifier" if book.isbn.is_none() => {
+						let scheme = attribute(&attributes, "scheme")
+							.map(|scheme| scheme.to_lowercase());
+						let lowercase = value.to_lowercase();
+						if scheme.as_deref() == Some("isbn")
+							|| lowercase.starts_with("urn:isbn:")
+							|| lowercase.starts_with("isbn:")
+						{
+							book.isbn = Some(value);
+						}
+					},
+					_ => (),
+				}
+			},
+			_ => (),
+		}
+	}
+
+	Ok(book)
+}
+
+#[async_trait::async_trait]
+impl MetadataProvider for SidecarProvider {
+	async fn search(
+		&self,
+		_query: &MetadataQuery,
+	) -> MetadataProviderResult<Vec<ProviderBook>> {
+		let mut books = Vec::new();
+		for name in self.sidecar_names() {
+			if let Some(book) = self.read_sidecar(&name)? {
+				books.push(book);
+			}
+		}
+		Ok(books)
+	}
+
+	async fn fetch(&self, id: &str) -> MetadataProviderResult<Option<ProviderBook>> {
+		// Only the known sidecar names are accepted, so that an ID can't be used to read
+		// arbitrary files
+		if !self.sidecar_names().iter().any(|name| name == id) {
+			return Err(MetadataProviderError::InvalidId(id.to_string()));
+		}
+		self.read_sidecar(id)
+	}
+
+	async fn fetch_cover(&self, id: &str) -> MetadataProviderResult<Option<Vec<u8>>> {
+		if !self.sidecar_names().iter().any(|name| name == id) {
+			return Err(MetadataProviderError::InvalidId(id.to_string()));
+		}
+
+		let cover = [self.stem.as_str(), "cover"]
+			.iter()
+			.flat_map(|name| {
+				COVER_EXTENSIONS.iter().map(move |extension| {
+					self.directory.join(format!("{name}.{extension}"))
+				})
+			})
+			.find(|path| path.is_file());
+
+		match cover {
+			Some(path) => Ok(Some(std::fs::read(path)?)),
+			None => Ok(None),
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	const OPF: &str = r#"<?xml version="1.0" encoding="utf-8"?>
+<package xmlns="http://www.idpf.org/2007/opf" version="2.0">
+	<metadata xmlns:dc="http://purl.org/dc/elements/1.1/" xmlns:opf="http://www.idpf.org/2007/opf">
+		<dc:title>The Way of Kings</dc:title>
+		<dc:creator opf:role="aut">Brandon Sanderson</dc:creator>
+		<dc:creator opf:role="ill">Isaac Stewart</dc:creator>
+		<dc:identifier opf:scheme="uuid">7d6b1a02-0000-0000-0000-000000000000</dc:identifier>
+		<dc:identifier opf:scheme="ISBN">9780765326355</dc:identifier>
+		<dc:publisher>Tor Books</dc:publisher>
+		<dc:date>2010-08-31T00:00:00+00:00</dc:date>
+		<dc:description>&lt;p&gt;Roshar is a world of stone and storms&lt;/p&gt;</dc:description>
+		<dc:subject>Fantasy</dc:subject>
+		<dc:subject>Epic</dc:subject>
+		<dc:language>en</dc:language>
+		<meta name="calibre:series" content="The Stormlight Archive"/>
+		<meta name="calibre:series_index" content="1.0"/>
+	</metadata>
+	<manifest/>
+</package>"#;
+
+	#[test]
+	fn test_parse_opf() {
+		let book = parse_opf(OPF).unwrap();
+		assert_eq!(book.title.as_deref(), Some("The Way of Kings"));
+		assert_eq!(book.authors, vec!["Brandon Sanderson"]);
+		assert_eq!(book.isbn.as_deref(), Some("9780765326355"));
+		assert_eq!(book.publisher.as_deref(), Some("Tor Books"));
+		assert_eq!(book.published_date_parts(), (Some(2010), Some(8), Some(31)));
+		assert_eq!(
+			book.description.as_deref(),
+			Some("<p>Roshar is a world of stone and storms</p>")
+		);
+		assert_eq!(book.subjects, vec!["Fantasy", "Epic"]);
+		assert_eq!(book.series.as_deref(), Some("The Stormlight Archive"));
+		assert_eq!(book.number, Some(1.0));
+	}
+
+	#[tokio::test]
+	async fn test_sidecar_files() {
+		let directory = tempfile::tempdir().unwrap();
+		let book_path = directory.path().join("The Way of Kings.epub");
+		std::fs::write(directory.path().join("metadata.opf"), OPF).unwrap();
+		std::fs::write(
+			directory.path().join("The Way of Kings.json"),
+			r#"{ "title": "The Way of Kings", "authors": ["Brandon Sanderson"] }"#,
+		)
+		.unwrap();
+		std::fs::write(directory.path().join("cover.jpg"), [0xFF, 0xD8, 0xFF]).unwrap();
+
+		let provider = SidecarProvider::for_book(&book_path);
+		let books = provider.search(&MetadataQuery::default()).await.unwrap();
+		let ids = books
+			.iter()
+			.map(|book| book.id.as_str())
+			.collect::<Vec<_>>();
+		assert_eq!(ids, vec!["metadata.opf", "The Way of Kings.json"]);
+
+		let book = provider
+			.fetch("The Way of Kings.json")
+			.await
+			.unwrap()
+			.unwrap();
+		assert_eq!(book.authors, vec!["Brandon Sanderson"]);
+
+		let cover = provider.fetch_cover("metadata.opf").await.unwrap();
+		assert_eq!(cover, Some(vec![0xFF, 0xD8, 0xFF]));
+	}
+
+	#[tokio::test]
+	async fn test_sidecar_rejects_other_files() {
+		let directory = tempfile::tempdir().unwrap();
+		let provider = SidecarProvider::for_book(&directory.path().join("Book.epub"));
+
+		let result = provider.fetch("../../etc/passwd").await;
+		assert!(matches!(result, Err(MetadataProviderError::InvalidId(_))));
+	}
+}
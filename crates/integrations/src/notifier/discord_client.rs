@@ -38,6 +38,24 @@ impl Notifier for DiscordClient {
 					"color" : 13605239,
 				}]
 			}),
+			NotifierEvent::SeriesScanCompleted { .. } => json!({
+				"username" : NOTIFIER_ID,
+				"avatar_url" : FAVICON_URL,
+				"embeds" : [{
+					"title" : "Series Scan Completed!",
+					"description": event.into_message(),
+					"color" : 13605239,
+				}]
+			}),
+			NotifierEvent::JobFailed { .. } => json!({
+				"username" : NOTIFIER_ID,
+				"avatar_url" : FAVICON_URL,
+				"embeds" : [{
+					"title" : "Job Failed",
+					"description": event.into_message(),
+					"color" : 15548997,
+				}]
+			}),
 		};
 		Ok(payload)
 	}
@@ -101,4 +119,36 @@ mod tests {
 			String::from("5 books added to test_library")
 		);
 	}
+
+	#[test]
+	fn test_series_scan_completed() {
+		let event = NotifierEvent::SeriesScanCompleted {
+			books_added: 1,
+			books_updated: 2,
+			series_name: String::from("test_series"),
+		};
+		let response = DiscordClient::payload_from_event(event).unwrap();
+		let embeds = response["embeds"].to_owned();
+		assert_eq!(embeds[0]["title"], String::from("Series Scan Completed!"));
+		assert_eq!(
+			embeds[0]["description"],
+			String::from("1 book added to test_series (2 updated)")
+		);
+	}
+
+	#[test]
+	fn test_job_failed() {
+		let event = NotifierEvent::JobFailed {
+			job_name: String::from("thumbnail_generation"),
+			description: None,
+			error: String::from("Disk full"),
+		};
+		let response = DiscordClient::payload_from_event(event).unwrap();
+		let embeds = response["embeds"].to_owned();
+		assert_eq!(embeds[0]["title"], String::from("Job Failed"));
+		assert_eq!(
+			embeds[0]["description"],
+			String::from("thumbnail_generation failed: Disk full")
+		);
+	}
 }
@@ -1,11 +1,32 @@
+#[derive(Debug, Clone)]
 pub enum NotifierEvent {
 	ScanCompleted {
 		books_added: u64,
 		library_name: String,
 	},
+	SeriesScanCompleted {
+		books_added: u64,
+		books_updated: u64,
+		series_name: String,
+	},
+	JobFailed {
+		job_name: String,
+		description: Option<String>,
+		error: String,
+	},
 }
 
 impl NotifierEvent {
+	/// A stable identifier for the kind of event, used by notifiers which send structured
+	/// payloads (e.g. webhooks)
+	pub fn kind(&self) -> &'static str {
+		match self {
+			NotifierEvent::ScanCompleted { .. } => "SCAN_COMPLETED",
+			NotifierEvent::SeriesScanCompleted { .. } => "SERIES_SCAN_COMPLETED",
+			NotifierEvent::JobFailed { .. } => "JOB_FAILED",
+		}
+	}
+
 	pub fn into_message(self) -> String {
 		match self {
 			NotifierEvent::ScanCompleted {
@@ -19,6 +40,26 @@ impl NotifierEvent {
 					books_added, book_or_books, library_name
 				)
 			},
+			NotifierEvent::SeriesScanCompleted {
+				books_added,
+				books_updated,
+				series_name,
+			} => {
+				let is_plural = books_added == 0 || books_added > 1;
+				let book_or_books = if is_plural { "books" } else { "book" };
+				format!(
+					"{} {} added to {} ({} updated)",
+					books_added, book_or_books, series_name, books_updated
+				)
+			},
+			NotifierEvent::JobFailed {
+				job_name,
+				description,
+				error,
+			} => {
+				let job = description.unwrap_or(job_name);
+				format!("{} failed: {}", job, error)
+			},
 		}
 	}
 }
@@ -2,12 +2,13 @@ mod discord_client;
 mod error;
 mod event;
 mod telegram_client;
+mod webhook_client;
 
 pub use discord_client::DiscordClient;
+pub use error::{NotifierError, NotifierResult};
 pub use event::NotifierEvent;
 pub use telegram_client::TelegramClient;
-
-use self::error::NotifierResult;
+pub use webhook_client::WebhookClient;
 
 pub const NOTIFIER_ID: &str = "Stump Notifier";
 pub const FAVICON_URL: &str = "https://stumpapp.dev/favicon.png";
@@ -0,0 +1,64 @@
+use serde_json::json;
+
+use super::{
+	error::{NotifierError, NotifierResult},
+	Notifier, NotifierEvent, NOTIFIER_ID,
+};
+
+/// A notifier which sends a JSON payload describing each event to an arbitrary URL
+pub struct WebhookClient {
+	pub url: String,
+	pub client: reqwest::Client,
+}
+
+impl WebhookClient {
+	pub fn new(url: String) -> Self {
+		let client = reqwest::Client::new();
+		Self { url, client }
+	}
+}
+
+#[async_trait::async_trait]
+impl Notifier for WebhookClient {
+	fn payload_from_event(event: NotifierEvent) -> NotifierResult<serde_json::Value> {
+		Ok(json!({
+			"source": NOTIFIER_ID,
+			"event": event.kind(),
+			"message": event.into_message(),
+		}))
+	}
+
+	async fn send_message(&self, event: NotifierEvent) -> NotifierResult<()> {
+		let body = Self::payload_from_event(event)?;
+		let response = self.client.post(&self.url).json(&body).send().await?;
+		if !response.status().is_success() {
+			let errmsg = response
+				.text()
+				.await
+				.unwrap_or_else(|_| "sendMessage failed".to_string());
+			Err(NotifierError::RequestFailed(errmsg))
+		} else {
+			Ok(())
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_job_failed_payload() {
+		let event = NotifierEvent::JobFailed {
+			job_name: String::from("analyze_media"),
+			description: Some(String::from("Analyze media in library")),
+			error: String::from("Database is locked"),
+		};
+		let payload = WebhookClient::payload_from_event(event).unwrap();
+		assert_eq!(payload["event"], String::from("JOB_FAILED"));
+		assert_eq!(
+			payload["message"],
+			String::from("Analyze media in library failed: Database is locked")
+		);
+	}
+}
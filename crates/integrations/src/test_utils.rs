@@ -0,0 +1,60 @@
+use std::{
+	io::{Read, Write},
+	net::TcpListener,
+	sync::{
+		atomic::{AtomicUsize, Ordering},
+		Arc,
+	},
+};
+
+/// A minimal HTTP server which answers every request with the status and JSON body returned
+/// by the handler for the request target (the path and query)
+pub struct StubServer {
+	pub url: String,
+	requests: Arc<AtomicUsize>,
+}
+
+impl StubServer {
+	/// Start a server which answers every request with a `200 OK`
+	pub fn start<F>(handler: F) -> Self
+	where
+		F: Fn(&str) -> serde_json::Value + Send + 'static,
+	{
+		Self::start_with_status(move |target| (200, handler(target)))
+	}
+
+	pub fn start_with_status<F>(handler: F) -> Self
+	where
+		F: Fn(&str) -> (u16, serde_json::Value) + Send + 'static,
+	{
+		let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+		let url = format!("http://{}", listener.local_addr().unwrap());
+		let requests = Arc::new(AtomicUsize::new(0));
+		let counter = requests.clone();
+
+		std::thread::spawn(move || {
+			for mut stream in listener.incoming().flatten() {
+				let mut buffer = [0; 4096];
+				let read = stream.read(&mut buffer).unwrap_or(0);
+				let request = String::from_utf8_lossy(&buffer[..read]);
+				let target = request.split_whitespace().nth(1).unwrap_or("/");
+				counter.fetch_add(1, Ordering::SeqCst);
+
+				let (status, body) = handler(target);
+				let body = body.to_string();
+				let response = format!(
+					"HTTP/1.1 {status} Stub\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+					body.len(),
+					body
+				);
+				let _ = stream.write_all(response.as_bytes());
+			}
+		});
+
+		Self { url, requests }
+	}
+
+	pub fn request_count(&self) -> usize {
+		self.requests.load(Ordering::SeqCst)
+	}
+}
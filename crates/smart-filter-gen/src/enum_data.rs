@@ -9,6 +9,7 @@ pub struct EnumVariant {
 	pub variable_inner_name: Ident,
 	pub variable_type: Type,
 	pub is_optional: bool,
+	pub is_many: bool,
 }
 
 pub struct DestructedEnum {
@@ -31,6 +32,7 @@ pub fn destructure_enum(
 		// Assuming that passed, we'll take the first field
 		let field: &syn::Field = variant.fields.iter().next().unwrap();
 		let is_optional = get_is_optional_attr(&variant.attrs);
+		let is_many = get_is_many_attr(&variant.attrs);
 
 		// Extract the variable inner name
 		let var_name = match &field.ident {
@@ -49,6 +51,7 @@ pub fn destructure_enum(
 			variable_inner_name: var_name.clone(),
 			variable_type: field.ty.clone(),
 			is_optional,
+			is_many,
 		});
 	}
 
@@ -65,6 +68,18 @@ fn get_is_optional_attr(attrs: &Vec<Attribute>) -> bool {
 	false
 }
 
+/// Whether the variant is a filter on a one-to-many relation, which matches when _some_ of the
+/// related records match the inner filter
+fn get_is_many_attr(attrs: &Vec<Attribute>) -> bool {
+	for attr in attrs {
+		if attr.path().is_ident("is_many") {
+			return true;
+		}
+	}
+
+	false
+}
+
 pub fn should_filter_type(ty: &Type) -> bool {
 	match ty {
 		Type::Path(type_path) => {
@@ -108,9 +108,15 @@ fn generate_filter_match_arm(
 		format_ident!("into_params")
 	};
 
+	let relation_fn = if variant_data.is_many {
+		format_ident!("some")
+	} else {
+		format_ident!("is")
+	};
+
 	quote! {
 	  #ident::#name { #inner_name } => {
-	  #prisma_table::#inner_name::is(vec![#inner_name.#into_fn()])
+	  #prisma_table::#inner_name::#relation_fn(vec![#inner_name.#into_fn()])
 	}
 	}
 }
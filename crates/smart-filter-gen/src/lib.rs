@@ -50,6 +50,9 @@ use crate::{enum_data::DestructedEnum, gen_match_arms::generate_match_arm};
 ///
 /// This is an attribute macro and thus will _replace_ the entire definition that
 /// it is applied to with its own generated content.
+///
+/// Variants which filter on a one-to-many relation should be marked with `#[is_many]`,
+/// so that they match when _some_ of the related records match the inner filter.
 #[proc_macro_attribute]
 pub fn generate_smart_filter(
 	_attrs: proc_macro::TokenStream,
@@ -3,9 +3,14 @@ import { Meta } from 'nextra'
 export default {
 	'api-keys': 'API Keys',
 	'book-clubs': 'Book Clubs',
+	collections: 'Collections',
 	email: 'Email',
 	'file-explorer': 'File Explorer',
 	upload: 'File Uploads',
+	'metadata-import': 'Metadata Import',
+	notifications: 'Notifications',
+	people: 'People',
 	'smart-list': 'Smart Lists',
 	'reading-list': 'Reading Lists',
+	'story-arcs': 'Story Arcs',
 } satisfies Meta
@@ -3,4 +3,7 @@ import { Meta } from 'nextra'
 export default {
 	koreader: 'KoReader',
 	kobo: 'Kobo',
+	comicvine: 'ComicVine',
+	'google-books': 'Google Books',
+	identify: 'Identifying Books',
 } satisfies Meta
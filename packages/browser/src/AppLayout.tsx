@@ -6,6 +6,7 @@ import { useOverlayScrollbars } from 'overlayscrollbars-react'
 import { Suspense, useCallback, useEffect, useMemo, useRef } from 'react'
 import Confetti from 'react-confetti'
 import { useErrorBoundary } from 'react-error-boundary'
+import toast from 'react-hot-toast'
 import { Outlet, useLocation, useNavigate } from 'react-router-dom'
 import { useMediaMatch, useWindowSize } from 'rooks'
 
@@ -139,7 +140,7 @@ export function AppLayout() {
 	const hideSidebar = hideAllNavigation || preferTopBar
 	const hideTopBar = isMobile || hideAllNavigation || !preferTopBar
 
-	useCoreEventHandler({ liveRefetch, onConnectionWithServerChanged })
+	useCoreEventHandler({ liveRefetch, onConnectionWithServerChanged, onJobFailed: toast.error })
 
 	/**
 	 * A callback to enforce a permission on the currently logged in user.
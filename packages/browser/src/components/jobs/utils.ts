@@ -12,5 +12,5 @@ export function readableKind(kind: string | null) {
 }
 
 export function formatJobStatus(status: JobStatus) {
-	return status.charAt(0).toUpperCase() + status.slice(1).toLowerCase()
+	return (status.charAt(0).toUpperCase() + status.slice(1).toLowerCase()).replace(/_/g, ' ')
 }
@@ -3,6 +3,7 @@ import { Alert, Button } from '@stump/components'
 import { Construction } from 'lucide-react'
 
 import { SceneContainer } from '@/components/container'
+import { useAppContext } from '@/context'
 
 import { useSeriesContext } from '../../context'
 import SeriesThumbnailSelector from './SeriesThumbnailSelector'
@@ -10,6 +11,13 @@ import SeriesThumbnailSelector from './SeriesThumbnailSelector'
 export default function SeriesSettingsScene() {
 	const { sdk } = useSDK()
 	const { series } = useSeriesContext()
+	const { checkPermission } = useAppContext()
+
+	const canScan = checkPermission('library:scan')
+
+	function handleScan() {
+		sdk.series.scan(series.id)
+	}
 
 	function handleAnalyze() {
 		sdk.series.analyze(series.id)
@@ -24,9 +32,17 @@ export default function SeriesSettingsScene() {
 					</Alert.Content>
 				</Alert>
 
-				<Button size="md" variant="primary" onClick={handleAnalyze}>
-					Analyze Media
-				</Button>
+				<div className="flex items-center gap-x-2">
+					{canScan && (
+						<Button size="md" variant="primary" onClick={handleScan}>
+							Scan Series
+						</Button>
+					)}
+
+					<Button size="md" variant="primary" onClick={handleAnalyze}>
+						Analyze Media
+					</Button>
+				</div>
 
 				<SeriesThumbnailSelector series={series} />
 			</div>
@@ -1,169 +1,250 @@
 import { zodResolver } from '@hookform/resolvers/zod'
-import { useJobSchedulerConfig, useLibraries } from '@stump/client'
-import { Alert, Button, ComboBox, Form, Input, Label, NativeSelect } from '@stump/components'
-import { Construction } from 'lucide-react'
-import { useEffect, useState } from 'react'
+import { useJobSchedules, useLibraries } from '@stump/client'
+import {
+	Button,
+	ComboBox,
+	Form,
+	Input,
+	Label,
+	NativeSelect,
+	RawSwitch,
+	Text,
+} from '@stump/components'
+import { JobSchedule, ScheduledJobKind } from '@stump/sdk'
+import dayjs from 'dayjs'
+import { Trash2 } from 'lucide-react'
 import { useForm } from 'react-hook-form'
 import toast from 'react-hot-toast'
 import { useMediaMatch } from 'rooks'
 import z from 'zod'
 
+const JOB_KINDS: { label: string; value: ScheduledJobKind }[] = [
+	{ label: 'Library scan', value: 'SCAN' },
+	{ label: 'Thumbnail generation', value: 'THUMBNAIL_GENERATION' },
+	{ label: 'Media analysis', value: 'MEDIA_ANALYSIS' },
+	{ label: 'Database maintenance', value: 'DATABASE_MAINTENANCE' },
+	{ label: 'Session cleanup', value: 'SESSION_CLEANUP' },
+]
+
+const LIBRARY_JOB_KINDS: ScheduledJobKind[] = ['SCAN', 'THUMBNAIL_GENERATION', 'MEDIA_ANALYSIS']
+
+const CRON_PRESETS = [
+	{ label: 'Every 6 hours', value: '0 */6 * * *' },
+	{ label: 'Every 12 hours', value: '0 */12 * * *' },
+	{ label: 'Once a day', value: '0 3 * * *' },
+	{ label: 'Once a week', value: '0 3 * * 0' },
+	{ label: 'Once a month', value: '0 3 1 * *' },
+]
+
 const schema = z.object({
-	excluded_library_ids: z.array(z.string()).optional(),
-	interval_secs: z
-		.number()
-		.positive()
-		.int()
-		.min(300, 'You cannot set an interval less than five minutes')
-		.optional(),
+	cron_expression: z
+		.string()
+		.min(1)
+		.refine((value) => value.trim().split(/\s+/).length === 5, {
+			message: 'A cron expression must have exactly five fields',
+		}),
+	job_kind: z.enum([
+		'SCAN',
+		'THUMBNAIL_GENERATION',
+		'MEDIA_ANALYSIS',
+		'DATABASE_MAINTENANCE',
+		'SESSION_CLEANUP',
+	]),
+	name: z.string().min(1),
+	target_library_ids: z.array(z.string()).optional(),
 })
 type FormValues = z.infer<typeof schema>
 
-const INTERVAL_PRESETS = [
-	{ label: 'Every 6 hours', value: 21600 },
-	{ label: 'Every 12 hours', value: 43200 },
-	{ label: 'Once a day', value: 86400 },
-	{ label: 'Once a week', value: 604800 },
-	{ label: 'Once a month', value: 2592000 },
-]
-
-const getCorrespondingPreset = (seconds: number) =>
-	INTERVAL_PRESETS.find((preset) => preset.value === seconds)
+const getJobKindLabel = (kind: ScheduledJobKind) =>
+	JOB_KINDS.find(({ value }) => value === kind)?.label ?? kind
 
 export default function JobScheduler() {
 	const { libraries } = useLibraries()
-	const { config, update } = useJobSchedulerConfig()
+	const { schedules, create, update, remove, isCreating } = useJobSchedules()
 
 	const isSmallViewport = useMediaMatch('(max-width: 768px)')
-	const [intervalPreset, setIntervalPreset] = useState(
-		getCorrespondingPreset(config?.interval_secs || -1)?.value ?? -1,
-	)
 
-	const form = useForm({
+	const form = useForm<FormValues>({
 		defaultValues: {
-			excluded_library_ids: config?.excluded_libraries.map((library) => library.id),
-			interval_secs: config?.interval_secs,
+			cron_expression: '0 3 * * *',
+			job_kind: 'SCAN',
+			name: '',
+			target_library_ids: [],
 		},
 		resolver: zodResolver(schema),
 	})
 
-	const [excluded_library_ids, interval_secs] = form.watch([
-		'excluded_library_ids',
-		'interval_secs',
+	const [jobKind, cronExpression, targetLibraryIds] = form.watch([
+		'job_kind',
+		'cron_expression',
+		'target_library_ids',
 	])
+	const targetsLibraries = LIBRARY_JOB_KINDS.includes(jobKind)
 
-	const handleSubmit = async ({ interval_secs, excluded_library_ids }: FormValues) => {
-		update(
+	const handleSubmit = ({ target_library_ids, ...values }: FormValues) => {
+		create(
 			{
-				excluded_library_ids: excluded_library_ids ?? null,
-				interval_secs: interval_secs ?? null,
+				...values,
+				target_library_ids: targetsLibraries ? target_library_ids : [],
 			},
 			{
 				onError: (error) => {
 					console.error(error)
-					toast.error('Failed to update job scheduler config')
+					toast.error('Failed to create job schedule')
 				},
 				onSuccess: () => {
-					toast.success('Scheduler config updated!')
+					toast.success('Job schedule created!')
+					form.reset()
 				},
 			},
 		)
 	}
 
-	const handleIntervalPresetChange = (value?: string) => {
-		if (!value) {
-			setIntervalPreset(-1)
-			return
-		}
-
-		const parsed = parseInt(value, 10)
-		if (!isNaN(parsed)) {
-			setIntervalPreset(parsed)
-		}
-	}
+	const handleToggle = (schedule: JobSchedule) =>
+		update(
+			{
+				id: schedule.id,
+				payload: {
+					cron_expression: schedule.cron_expression,
+					enabled: !schedule.enabled,
+					job_kind: schedule.job_kind,
+					name: schedule.name,
+					target_library_ids: schedule.target_libraries.map(({ id }) => id),
+				},
+			},
+			{
+				onError: (error) => {
+					console.error(error)
+					toast.error('Failed to update job schedule')
+				},
+			},
+		)
 
-	useEffect(() => {
-		if (intervalPreset !== undefined && intervalPreset !== -1) {
-			form.setValue('interval_secs', intervalPreset)
-		}
-	}, [form, intervalPreset])
-
-	useEffect(() => {
-		// if not preset matches the current interval, set it to custom
-		const preset = INTERVAL_PRESETS.find((preset) => preset.value === interval_secs)
-		setIntervalPreset(preset?.value ?? -1)
-	}, [interval_secs])
-
-	useEffect(() => {
-		if (config) {
-			setIntervalPreset(getCorrespondingPreset(config.interval_secs)?.value ?? -1)
-			form.setValue(
-				'excluded_library_ids',
-				config.excluded_libraries.map(({ id }) => id),
-			)
-			form.setValue('interval_secs', config.interval_secs)
-		}
-	}, [form, config])
+	const handleDelete = (id: string) =>
+		remove(id, {
+			onError: (error) => {
+				console.error(error)
+				toast.error('Failed to delete job schedule')
+			},
+		})
 
 	return (
 		<div className="my-2 flex flex-col gap-6">
-			<Alert level="warning" icon={Construction}>
-				<Alert.Content>
-					Stump currently only supports scheduling scanner jobs. This will be extended to support
-					other job types in the future.
-				</Alert.Content>
-			</Alert>
+			{schedules.length > 0 && (
+				<div className="flex w-full flex-col divide-y divide-edge rounded-lg border border-edge lg:w-2/3">
+					{schedules.map((schedule) => (
+						<div key={schedule.id} className="flex items-center justify-between gap-4 p-3">
+							<div className="flex flex-col gap-0.5">
+								<Text size="sm" className="font-medium">
+									{schedule.name}
+								</Text>
+								<Text size="xs" variant="muted">
+									{getJobKindLabel(schedule.job_kind)} · <code>{schedule.cron_expression}</code>
+									{schedule.target_libraries.length > 0 &&
+										` · ${schedule.target_libraries.map(({ name }) => name).join(', ')}`}
+								</Text>
+								<Text size="xs" variant="muted">
+									{schedule.enabled && schedule.next_run_at
+										? `Next run ${dayjs(schedule.next_run_at).format('LLL')}`
+										: 'Disabled'}
+									{schedule.last_run_at &&
+										` · Last run ${dayjs(schedule.last_run_at).format('LLL')}`}
+								</Text>
+							</div>
+
+							<div className="flex flex-shrink-0 items-center gap-2">
+								<RawSwitch
+									checked={schedule.enabled}
+									onClick={() => handleToggle(schedule)}
+									primaryRing
+									variant="primary"
+								/>
+								<Button
+									size="icon"
+									variant="ghost"
+									title="Delete schedule"
+									onClick={() => handleDelete(schedule.id)}
+								>
+									<Trash2 className="h-4 w-4" />
+								</Button>
+							</div>
+						</div>
+					))}
+				</div>
+			)}
 
 			<Form form={form} onSubmit={handleSubmit}>
 				<div className="flex w-full flex-col gap-2 md:flex-row md:items-end lg:w-2/3">
 					<Input
 						variant="primary"
-						type="number"
-						label="Interval"
-						description="How often the scheduler should initiate scans (in seconds). If left empty, the scheduler will be disabled"
-						descriptionPosition="top"
-						placeholder='e.g. "86400" for once a day'
+						label="Name"
+						placeholder="e.g. Nightly scan"
 						fullWidth
-						{...form.register('interval_secs', {
-							valueAsNumber: true,
-						})}
+						{...form.register('name')}
 					/>
 
 					<div className="flex-shrink-0">
-						<Label htmlFor="intervalPreset">Interval preset</Label>
+						<Label htmlFor="jobKind">Job</Label>
 						<NativeSelect
-							value={intervalPreset}
-							options={INTERVAL_PRESETS}
-							onChange={(e) => handleIntervalPresetChange(e.target.value)}
-							emptyOption={{ label: 'Custom', value: -1 }}
+							id="jobKind"
+							value={jobKind}
+							options={JOB_KINDS}
+							onChange={(e) => form.setValue('job_kind', e.target.value as ScheduledJobKind)}
 						/>
 					</div>
 				</div>
 
-				<div className="flex w-full flex-col gap-4 md:flex-row md:items-end md:justify-between lg:w-2/3">
-					<ComboBox
-						label="Excluded libraries"
-						description="Libraries that will be excluded from the scheduled scans"
+				<div className="flex w-full flex-col gap-2 md:flex-row md:items-end lg:w-2/3">
+					<Input
+						variant="primary"
+						label="Cron expression"
+						description="When the job should run, e.g. 0 3 * * * for every day at 3am"
 						descriptionPosition="top"
-						isMultiSelect
-						value={excluded_library_ids}
-						options={(libraries || []).map((library) => ({
-							label: library.name,
-							value: library.id,
-						}))}
-						onChange={(value) => (value ? form.setValue('excluded_library_ids', value) : null)}
-						size={isSmallViewport ? 'full' : 'default'}
+						fullWidth
+						errorMessage={form.formState.errors.cron_expression?.message}
+						{...form.register('cron_expression')}
 					/>
+
+					<div className="flex-shrink-0">
+						<Label htmlFor="cronPreset">Preset</Label>
+						<NativeSelect
+							id="cronPreset"
+							value={cronExpression}
+							options={CRON_PRESETS}
+							onChange={(e) =>
+								e.target.value ? form.setValue('cron_expression', e.target.value) : null
+							}
+							emptyOption={{ label: 'Custom', value: '' }}
+						/>
+					</div>
 				</div>
 
+				{targetsLibraries && (
+					<div className="flex w-full flex-col gap-4 md:flex-row md:items-end md:justify-between lg:w-2/3">
+						<ComboBox
+							label="Libraries"
+							description="The libraries the job will run for. If none are selected, it will run for every library"
+							descriptionPosition="top"
+							isMultiSelect
+							value={targetLibraryIds}
+							options={(libraries || []).map((library) => ({
+								label: library.name,
+								value: library.id,
+							}))}
+							onChange={(value) => (value ? form.setValue('target_library_ids', value) : null)}
+							size={isSmallViewport ? 'full' : 'default'}
+						/>
+					</div>
+				)}
+
 				<Button
 					type="submit"
 					variant="primary"
 					size="md"
-					disabled={form.formState.isSubmitting}
+					disabled={isCreating}
 					className="flex-shrink-0 md:w-32"
 				>
-					Save changes
+					Add schedule
 				</Button>
 			</Form>
 		</div>
@@ -1,3 +1,4 @@
+import { useJobStore } from '@stump/client'
 import { Badge, Card, Heading, Text } from '@stump/components'
 import { useLocaleContext } from '@stump/i18n'
 import { CoreJobOutput, JobStatus, PersistedJob } from '@stump/sdk'
@@ -8,6 +9,7 @@ import relativeTime from 'dayjs/plugin/relativeTime'
 import { CircleSlash2 } from 'lucide-react'
 import { useMemo, useState } from 'react'
 
+import { formatJobStatus } from '@/components/jobs/utils'
 import { Table } from '@/components/table'
 import { useAppContext } from '@/context'
 
@@ -28,6 +30,7 @@ export default function JobTable() {
 	const { isServerOwner } = useAppContext()
 	const { t } = useLocaleContext()
 	const { jobs, pagination, setPagination, pageCount } = useJobSettingsContext()
+	const queue = useJobStore((state) => state.queue)
 
 	const [inspectingData, setInspectingData] = useState<CoreJobOutput | null>()
 
@@ -79,7 +82,7 @@ export default function JobTable() {
 								return 'success'
 							} else if (status === 'CANCELLED') {
 								return 'warning'
-							} else if (status === 'FAILED') {
+							} else if (status === 'FAILED' || status === 'DEAD_LETTER') {
 								return 'error'
 							} else {
 								return 'primary'
@@ -87,11 +90,18 @@ export default function JobTable() {
 						}
 
 						const job = row.original
+						const queuePosition =
+							job.status === 'QUEUED'
+								? queue.find((queued) => queued.id === job.id)?.position
+								: undefined
 
 						return (
-							<Badge variant={getBadgeVariant(job.status)} size="xs">
-								{job.status.charAt(0).toUpperCase() + job.status.slice(1).toLowerCase()}
-							</Badge>
+							<span title={job.attempts > 1 ? `${job.attempts} attempts` : undefined}>
+								<Badge variant={getBadgeVariant(job.status)} size="xs">
+									{formatJobStatus(job.status)}
+									{queuePosition && ` #${queuePosition}`}
+								</Badge>
+							</span>
 						)
 					},
 					header: 'Status',
@@ -135,7 +145,7 @@ export default function JobTable() {
 					size: 28,
 				}),
 			] as ColumnDef<PersistedJob>[],
-		[t, isServerOwner],
+		[t, isServerOwner, queue],
 	)
 
 	return (
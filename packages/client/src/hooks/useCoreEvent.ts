@@ -1,4 +1,4 @@
-import type { CoreEvent, CoreJobOutput, LibraryScanOutput } from '@stump/sdk'
+import type { CoreEvent, CoreJobOutput, LibraryScanOutput, SeriesScanOutput } from '@stump/sdk'
 import { Api } from '@stump/sdk'
 import { useCallback } from 'react'
 
@@ -14,13 +14,19 @@ import { useStumpSse } from './useStumpSse'
 type Params = {
 	liveRefetch?: boolean
 	onConnectionWithServerChanged?: (connected: boolean) => void
+	onJobFailed?: (message: string) => void
 }
 
-export function useCoreEventHandler({ liveRefetch, onConnectionWithServerChanged }: Params = {}) {
+export function useCoreEventHandler({
+	liveRefetch,
+	onConnectionWithServerChanged,
+	onJobFailed,
+}: Params = {}) {
 	const { sdk } = useSDK()
-	const { addJob, upsertJob, removeJob } = useJobStore((state) => ({
+	const { addJob, upsertJob, removeJob, setQueue } = useJobStore((state) => ({
 		addJob: state.addJob,
 		removeJob: state.removeJob,
+		setQueue: state.setQueue,
 		upsertJob: state.upsertJob,
 	}))
 
@@ -53,6 +59,12 @@ export function useCoreEventHandler({ liveRefetch, onConnectionWithServerChanged
 				case 'JobOutput':
 					await handleJobOutput(event.output, sdk)
 					break
+				case 'JobQueueUpdate':
+					setQueue(event.queue)
+					break
+				case 'JobFailed':
+					onJobFailed?.(event.message)
+					break
 				case 'DiscoveredMissingLibrary':
 					await handleInvalidate(['library', 'series', 'media'])
 					break
@@ -78,7 +90,7 @@ export function useCoreEventHandler({ liveRefetch, onConnectionWithServerChanged
 					console.warn('Unhandled core event', event)
 			}
 		},
-		[addJob, handleInvalidate, liveRefetch, removeJob, upsertJob, sdk],
+		[addJob, handleInvalidate, liveRefetch, onJobFailed, removeJob, setQueue, upsertJob, sdk],
 	)
 
 	useStumpSse({ onConnectionWithServerChanged, onEvent: handleCoreEvent })
@@ -113,6 +125,27 @@ const handleJobOutput = async (output: CoreJobOutput, sdk: Api) => {
 				console.error('Failed to invalidate queries', e)
 			}
 		}
+	} else if (isSeriesScanOutput(output)) {
+		const { created_media, updated_media, missing_media, restored_media } = output
+		const requeryBooks = [created_media, updated_media, missing_media, restored_media].some(
+			(count) => count.valueOf() > 0,
+		)
+
+		if (requeryBooks) {
+			try {
+				await invalidateQueries({
+					keys: [
+						sdk.media.keys.recentlyAdded,
+						sdk.media.keys.get,
+						sdk.series.keys.getByID,
+						sdk.series.keys.getSeriesMedia,
+						sdk.library.keys.getStats,
+					],
+				})
+			} catch (e) {
+				console.error('Failed to invalidate queries', e)
+			}
+		}
 	} else {
 		console.warn('Unhandled job output', output)
 	}
@@ -122,3 +155,8 @@ const isLibraryScanOutput = (output: CoreJobOutput): output is LibraryScanOutput
 	const requiredKeys = ['created_media', 'updated_media', 'created_series', 'updated_series']
 	return requiredKeys.every((key) => key in output)
 }
+
+const isSeriesScanOutput = (output: CoreJobOutput): output is SeriesScanOutput => {
+	const requiredKeys = ['created_media', 'updated_media', 'missing_media', 'restored_media']
+	return requiredKeys.every((key) => key in output)
+}
@@ -1,6 +1,6 @@
-import type { PersistedJob, UpdateSchedulerConfig } from '@stump/sdk'
+import type { CreateOrUpdateJobSchedule, PersistedJob } from '@stump/sdk'
 
-import { PageQueryOptions, useMutation, usePageQuery, useQuery } from '../client'
+import { PageQueryOptions, queryClient, useMutation, usePageQuery, useQuery } from '../client'
 import { useSDK } from '../sdk'
 
 type UseJobsQueryParams = PageQueryOptions<PersistedJob> & {
@@ -27,19 +27,41 @@ export function useJobsQuery({ params, ...options }: UseJobsQueryParams = {}) {
 	}
 }
 
-export function useJobSchedulerConfig() {
+export function useJobSchedules() {
 	const { sdk } = useSDK()
-	const { data: config, ...restReturn } = useQuery([sdk.job.keys.getSchedulerConfig], () =>
-		sdk.job.getSchedulerConfig(),
+	const { data: schedules, ...restReturn } = useQuery([sdk.job.keys.getSchedules], () =>
+		sdk.job.getSchedules(),
 	)
 
-	const {
-		mutate: update,
-		isLoading: isUpdating,
-		isError: isUpdateError,
-	} = useMutation([sdk.job.keys.updateSchedulerConfig], (payload: UpdateSchedulerConfig) =>
-		sdk.job.updateSchedulerConfig(payload),
+	const onSuccess = () => queryClient.invalidateQueries([sdk.job.keys.getSchedules])
+
+	const { mutate: create, isLoading: isCreating } = useMutation(
+		[sdk.job.keys.createSchedule],
+		(payload: CreateOrUpdateJobSchedule) => sdk.job.createSchedule(payload),
+		{ onSuccess },
+	)
+
+	const { mutate: update, isLoading: isUpdating } = useMutation(
+		[sdk.job.keys.updateSchedule],
+		({ id, payload }: { id: string; payload: CreateOrUpdateJobSchedule }) =>
+			sdk.job.updateSchedule(id, payload),
+		{ onSuccess },
+	)
+
+	const { mutate: remove, isLoading: isDeleting } = useMutation(
+		[sdk.job.keys.deleteSchedule],
+		(id: string) => sdk.job.deleteSchedule(id),
+		{ onSuccess },
 	)
 
-	return { config, isUpdateError, isUpdating, update, ...restReturn }
+	return {
+		create,
+		isCreating,
+		isDeleting,
+		isUpdating,
+		remove,
+		schedules: schedules ?? [],
+		update,
+		...restReturn,
+	}
 }
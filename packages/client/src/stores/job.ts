@@ -1,4 +1,4 @@
-import { JobUpdate } from '@stump/sdk'
+import { JobUpdate, QueuedJob } from '@stump/sdk'
 import deepEqual from 'deep-equal'
 import { produce } from 'immer'
 import { createWithEqualityFn } from 'zustand/traditional'
@@ -6,9 +6,14 @@ import { createWithEqualityFn } from 'zustand/traditional'
 type JobID = string
 type JobStore = {
 	jobs: Record<JobID, JobUpdate>
+	/**
+	 * The jobs waiting to be started, in the order they will be started
+	 */
+	queue: QueuedJob[]
 	addJob: (id: JobID) => void
 	upsertJob: (job: JobUpdate) => void
 	removeJob: (jobId: JobID) => void
+	setQueue: (queue: QueuedJob[]) => void
 }
 
 /**
@@ -23,12 +28,14 @@ export const useJobStore = createWithEqualityFn<JobStore>(
 				}),
 			),
 		jobs: {} as Record<JobID, JobUpdate>,
+		queue: [],
 		removeJob: (id) =>
 			set((state) =>
 				produce(state, (draft) => {
 					delete draft.jobs[id]
 				}),
 			),
+		setQueue: (queue) => set({ queue }),
 		upsertJob: (job) => {
 			set((state) =>
 				produce(state, (draft) => {
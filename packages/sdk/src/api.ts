@@ -5,6 +5,7 @@ import {
 	APIKeyAPI,
 	AuthAPI,
 	BookClubAPI,
+	CollectionAPI,
 	EmailerAPI,
 	EpubAPI,
 	FilesystemAPI,
@@ -13,9 +14,11 @@ import {
 	LogAPI,
 	MediaAPI,
 	MetadataAPI,
+	PeopleAPI,
 	SeriesAPI,
 	ServerAPI,
 	SmartListAPI,
+	StoryArcAPI,
 	TagAPI,
 	UploadAPI,
 	UserAPI,
@@ -161,6 +164,13 @@ export class Api {
 		return new BookClubAPI(this)
 	}
 
+	/**
+	 * Get an instance for the CollectionAPI
+	 */
+	get collection(): CollectionAPI {
+		return new CollectionAPI(this)
+	}
+
 	/**
 	 * Get an instance for the EmailerAPI
 	 */
@@ -217,6 +227,13 @@ export class Api {
 		return new MetadataAPI(this)
 	}
 
+	/**
+	 * Get an instance for the PeopleAPI
+	 */
+	get people(): PeopleAPI {
+		return new PeopleAPI(this)
+	}
+
 	/**
 	 * Get an instance for the SeriesAPI
 	 */
@@ -238,6 +255,13 @@ export class Api {
 		return new SmartListAPI(this)
 	}
 
+	/**
+	 * Get an instance for the StoryArcAPI
+	 */
+	get storyArc(): StoryArcAPI {
+		return new StoryArcAPI(this)
+	}
+
 	/**
 	 * Get an instance for the TagAPI
 	 */
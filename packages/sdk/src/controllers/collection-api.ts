@@ -0,0 +1,96 @@
+import { APIBase } from '../base'
+import {
+	Collection,
+	CollectionItem,
+	CreateOrUpdateCollection,
+	Pageable,
+	PaginationQuery,
+} from '../types'
+import { ClassQueryKeys } from './types'
+import { createRouteURLHandler } from './utils'
+
+/**
+ * The root route for the collection API
+ */
+const COLLECTION_ROUTE = '/collections'
+/**
+ * A helper function to format the URL for collection API routes with optional query parameters
+ */
+const collectionURL = createRouteURLHandler(COLLECTION_ROUTE)
+
+/**
+ * The collection API controller, used for interacting with the collection endpoints of the
+ * Stump API
+ */
+export class CollectionAPI extends APIBase {
+	/**
+	 * Fetch all collections visible to the user
+	 */
+	async get(params?: PaginationQuery): Promise<Pageable<Collection[]>> {
+		const { data: collections } = await this.axios.get<Pageable<Collection[]>>(
+			collectionURL('', params),
+		)
+		return collections
+	}
+
+	/**
+	 * Fetch a collection by ID
+	 */
+	async getByID(id: string): Promise<Collection> {
+		const { data: collection } = await this.axios.get<Collection>(collectionURL(id))
+		return collection
+	}
+
+	/**
+	 * Fetch the items of a collection which are visible to the user, in order
+	 */
+	async getItems(id: string): Promise<CollectionItem[]> {
+		const { data: items } = await this.axios.get<CollectionItem[]>(collectionURL(`${id}/items`))
+		return items
+	}
+
+	/**
+	 * Create a collection, optionally with its items in order
+	 */
+	async create(payload: CreateOrUpdateCollection): Promise<Collection> {
+		const { data: collection } = await this.axios.post<Collection>(collectionURL(''), payload)
+		return collection
+	}
+
+	/**
+	 * Update a collection. If items are provided, they replace the items of the collection
+	 */
+	async update(id: string, payload: CreateOrUpdateCollection): Promise<Collection> {
+		const { data: collection } = await this.axios.put<Collection>(collectionURL(id), payload)
+		return collection
+	}
+
+	/**
+	 * Delete a collection
+	 */
+	async delete(id: string): Promise<Collection> {
+		const { data: collection } = await this.axios.delete<Collection>(collectionURL(id))
+		return collection
+	}
+
+	/**
+	 * Fetch the URL for the thumbnail of a collection
+	 */
+	thumbnailURL(id: string): string {
+		return this.withServiceURL(collectionURL(`${id}/thumbnail`))
+	}
+
+	/**
+	 * The query keys for the collection API, used for caching
+	 */
+	get keys(): ClassQueryKeys<InstanceType<typeof CollectionAPI>> {
+		return {
+			create: 'collection.create',
+			delete: 'collection.delete',
+			get: 'collection.get',
+			getByID: 'collection.getByID',
+			getItems: 'collection.getItems',
+			update: 'collection.update',
+		}
+	}
+}
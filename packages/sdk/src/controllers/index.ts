@@ -1,6 +1,7 @@
 export * from './api-key-api'
 export * from './auth-api'
 export * from './bookclub-api'
+export * from './collection-api'
 export * from './emailer-api'
 export * from './epub-api'
 export * from './filesystem-api'
@@ -9,9 +10,11 @@ export * from './library-api'
 export * from './log-api'
 export * from './media-api'
 export * from './metadata-api'
+export * from './people-api'
 export * from './series-api'
 export * from './server-api'
 export * from './smartlist-api'
+export * from './story-arc-api'
 export * from './tag-api'
 export * from './upload-api'
 export * from './user-api'
@@ -1,5 +1,11 @@
 import { APIBase } from '../base'
-import { JobSchedulerConfig, Pageable, PersistedJob, UpdateSchedulerConfig } from '../types'
+import {
+	CreateOrUpdateJobSchedule,
+	JobSchedule,
+	Pageable,
+	PersistedJob,
+	QueuedJob,
+} from '../types'
 import { ClassQueryKeys, PagedQueryParams } from './types'
 import { createRouteURLHandler } from './utils'
 
@@ -24,6 +30,14 @@ export class JobAPI extends APIBase {
 		return jobs
 	}
 
+	/**
+	 * Fetch the jobs which are waiting to be started, in the order they will be started
+	 */
+	async getQueue(): Promise<QueuedJob[]> {
+		const { data: queue } = await this.axios.get<QueuedJob[]>(jobURL('queue'))
+		return queue
+	}
+
 	/**
 	 * Fetch a job by its ID
 	 */
@@ -54,22 +68,45 @@ export class JobAPI extends APIBase {
 	}
 
 	/**
-	 * Get the config for the job scheduler
+	 * Fetch all job schedules
+	 */
+	async getSchedules(): Promise<JobSchedule[]> {
+		const { data: schedules } = await this.axios.get<JobSchedule[]>(jobURL('schedules'))
+		return schedules
+	}
+
+	/**
+	 * Fetch a job schedule by its ID
 	 */
-	async getSchedulerConfig(): Promise<JobSchedulerConfig> {
-		const { data: config } = await this.axios.get<JobSchedulerConfig>(jobURL('scheduler-config'))
-		return config
+	async getScheduleByID(id: string): Promise<JobSchedule> {
+		const { data: schedule } = await this.axios.get<JobSchedule>(jobURL(`schedules/${id}`))
+		return schedule
 	}
 
 	/**
-	 * Update the config for the job scheduler
+	 * Create a new job schedule
 	 */
-	async updateSchedulerConfig(config: UpdateSchedulerConfig): Promise<JobSchedulerConfig> {
-		const { data: updatedConfig } = await this.axios.put<JobSchedulerConfig>(
-			jobURL('scheduler-config'),
-			config,
+	async createSchedule(payload: CreateOrUpdateJobSchedule): Promise<JobSchedule> {
+		const { data: schedule } = await this.axios.post<JobSchedule>(jobURL('schedules'), payload)
+		return schedule
+	}
+
+	/**
+	 * Update an existing job schedule
+	 */
+	async updateSchedule(id: string, payload: CreateOrUpdateJobSchedule): Promise<JobSchedule> {
+		const { data: schedule } = await this.axios.put<JobSchedule>(
+			jobURL(`schedules/${id}`),
+			payload,
 		)
-		return updatedConfig
+		return schedule
+	}
+
+	/**
+	 * Delete a job schedule by its ID
+	 */
+	async deleteSchedule(id: string): Promise<void> {
+		await this.axios.delete(jobURL(`schedules/${id}`))
 	}
 
 	/**
@@ -78,12 +115,16 @@ export class JobAPI extends APIBase {
 	get keys(): ClassQueryKeys<InstanceType<typeof JobAPI>> {
 		return {
 			cancel: 'job.cancel',
+			createSchedule: 'job.createSchedule',
 			delete: 'job.delete',
 			deleteAll: 'job.deleteAll',
+			deleteSchedule: 'job.deleteSchedule',
 			get: 'job.get',
 			getByID: 'job.getByID',
-			getSchedulerConfig: 'job.getSchedulerConfig',
-			updateSchedulerConfig: 'job.updateSchedulerConfig',
+			getQueue: 'job.getQueue',
+			getScheduleByID: 'job.getScheduleByID',
+			getSchedules: 'job.getSchedules',
+			updateSchedule: 'job.updateSchedule',
 		}
 	}
 }
@@ -2,14 +2,21 @@ import { APIBase } from '../base'
 import {
 	CleanLibraryResponse,
 	CreateLibrary,
+	FilenamePatternPreview,
 	GenerateLibraryThumbnails,
+	IsbnEnrichmentRequest,
+	IsbnEnrichmentResponse,
 	Library,
 	LibraryFilter,
 	LibraryStats,
 	LibraryStatsParams,
+	MetadataImportResponse,
+	MetadataTransferFormat,
 	Pageable,
 	PaginationQuery,
 	PatchLibraryThumbnail,
+	PreviewFilenamePatterns,
+	ScanDryRunResponse,
 	UpdateLibrary,
 	UpdateLibraryExcludedUsers,
 	User,
@@ -176,6 +183,63 @@ export class LibraryAPI extends APIBase {
 		await this.api.axios.post(libraryURL(`/${id}/scan`), {})
 	}
 
+	/**
+	 * Initiate a dry-run scan of a library, which reports what a scan would change without
+	 * writing anything. The report is available in the output of the returned job once it completes
+	 */
+	async scanDryRun(id: string): Promise<ScanDryRunResponse> {
+		const { data } = await this.api.axios.post<ScanDryRunResponse>(
+			libraryURL(`/${id}/scan/dry-run`),
+			{},
+		)
+		return data
+	}
+
+	/**
+	 * Fill the metadata of the EPUB and PDF books in a library from Google Books, using the ISBN
+	 * of each book. A summary is available in the output of the returned job once it completes
+	 */
+	async enrichISBN(id: string, params?: IsbnEnrichmentRequest): Promise<IsbnEnrichmentResponse> {
+		const { data } = await this.api.axios.post<IsbnEnrichmentResponse>(
+			libraryURL(`/${id}/metadata/isbn-enrichment`),
+			params ?? {},
+		)
+		return data
+	}
+
+	/**
+	 * Export the metadata of every series and book in a library as a CSV or JSON file, which can
+	 * be edited and imported back using `importMetadata`
+	 */
+	async exportMetadata(id: string, format: MetadataTransferFormat = 'csv'): Promise<Blob> {
+		const { data } = await this.api.axios.get<Blob>(
+			libraryURL(`/${id}/metadata/export`, { format }),
+			{ responseType: 'blob' },
+		)
+		return data
+	}
+
+	/**
+	 * Import a metadata file, in the format of the export, into a library. The changes (or, for a
+	 * dry run, the changes which would be made) are available in the output of the returned job
+	 * once it completes
+	 */
+	async importMetadata(id: string, file: File, dryRun = false): Promise<MetadataImportResponse> {
+		const formData = new FormData()
+		formData.append('file', file)
+		formData.append('dry_run', String(dryRun))
+		const { data } = await this.api.axios.post<MetadataImportResponse>(
+			libraryURL(`/${id}/metadata/import`),
+			formData,
+			{
+				headers: {
+					'Content-Type': 'multipart/form-data',
+				},
+			},
+		)
+		return data
+	}
+
 	/**
 	 * Remove all missing series and media from a library
 	 */
@@ -207,6 +271,19 @@ export class LibraryAPI extends APIBase {
 		await this.api.axios.post(libraryURL(`/${id}/analyze`))
 	}
 
+	/**
+	 * Preview what each of the given filename patterns would extract from a set of sample paths
+	 */
+	async previewFilenamePatterns(
+		payload: PreviewFilenamePatterns,
+	): Promise<FilenamePatternPreview[]> {
+		const { data } = await this.api.axios.post<FilenamePatternPreview[]>(
+			libraryURL('/filename-patterns/preview'),
+			payload,
+		)
+		return data
+	}
+
 	/**
 	 * The query keys for the library API, used for query caching on a client (e.g. react-query)
 	 */
@@ -217,13 +294,18 @@ export class LibraryAPI extends APIBase {
 			create: 'library.create',
 			delete: 'library.delete',
 			deleteThumbnails: 'library.deleteThumbnails',
+			enrichISBN: 'library.enrichISBN',
 			excludedUsers: 'library.excludedUsers',
+			exportMetadata: 'library.exportMetadata',
 			generateThumbnails: 'library.generateThumbnails',
 			get: 'library.get',
 			getByID: 'library.getByID',
 			getLastVisited: 'library.getLastVisited',
 			getStats: 'library.getStats',
+			importMetadata: 'library.importMetadata',
+			previewFilenamePatterns: 'library.previewFilenamePatterns',
 			scan: 'library.scan',
+			scanDryRun: 'library.scanDryRun',
 			update: 'library.update',
 			updateExcludedUsers: 'library.updateExcludedUsers',
 			updateThumbnail: 'library.updateThumbnail',
@@ -1,12 +1,21 @@
 import { APIBase } from '../base'
 import {
+	ApplyMetadataCandidate,
 	Media,
 	MediaFilter,
 	MediaMetadata,
+	MediaMetadataConflict,
+	MetadataCandidate,
+	MetadataLocks,
 	Pageable,
+	PaginationQuery,
 	PatchMediaThumbnail,
 	ProgressUpdateReturn,
 	PutMediaCompletionStatus,
+	ResolveMetadataConflict,
+	SimilarMediaCluster,
+	SimilarMediaQuery,
+	StoryArcMember,
 } from '../types'
 import { ClassQueryKeys, CursorQueryParams, FullQueryParams } from './types'
 import { createRouteURLHandler } from './utils'
@@ -169,6 +178,106 @@ export class MediaAPI extends APIBase {
 		return updatedMeta
 	}
 
+	/**
+	 * Replace the locked fields of the metadata of a media entity. Locked fields are kept as is
+	 * when the media is rescanned or identified
+	 *
+	 * @param id The ID of the media entity
+	 * @param payload The fields to lock, where an empty list unlocks every field
+	 */
+	async updateMetaLocks(id: string, payload: MetadataLocks): Promise<MediaMetadata> {
+		const { data: meta } = await this.axios.put<MediaMetadata>(
+			mediaURL(`${id}/metadata/locks`),
+			payload,
+		)
+		return meta
+	}
+
+	/**
+	 * Fetch the story arcs a media entity is part of, with its position in each
+	 *
+	 * @param id The ID of the media entity
+	 */
+	async getStoryArcs(id: string): Promise<StoryArcMember[]> {
+		const { data: storyArcs } = await this.axios.get<StoryArcMember[]>(
+			mediaURL(`${id}/story-arcs`),
+		)
+		return storyArcs
+	}
+
+	/**
+	 * Fetch the metadata conflicts of a media entity which are pending review
+	 *
+	 * @param id The ID of the media entity
+	 */
+	async getMetaConflicts(id: string): Promise<MediaMetadataConflict[]> {
+		const { data: conflicts } = await this.axios.get<MediaMetadataConflict[]>(
+			mediaURL(`${id}/metadata/conflicts`),
+		)
+		return conflicts
+	}
+
+	/**
+	 * Resolve a metadata conflict of a media entity, either accepting or rejecting the
+	 * value which was read from the file
+	 *
+	 * @param id The ID of the media entity
+	 * @param conflictID The ID of the conflict to resolve
+	 * @param payload The resolution for the conflict
+	 */
+	async resolveMetaConflict(
+		id: string,
+		conflictID: string,
+		payload: ResolveMetadataConflict,
+	): Promise<MediaMetadata | null> {
+		const { data: meta } = await this.axios.put<MediaMetadata | null>(
+			mediaURL(`${id}/metadata/conflicts/${conflictID}`),
+			payload,
+		)
+		return meta
+	}
+
+	/**
+	 * Search the metadata providers of the media's library for books which could be the
+	 * media, ranked by how closely they match
+	 *
+	 * @param id The ID of the media to identify
+	 */
+	async identify(id: string): Promise<MetadataCandidate[]> {
+		const { data: candidates } = await this.axios.post<MetadataCandidate[]>(
+			mediaURL(`${id}/metadata/identify`),
+		)
+		return candidates
+	}
+
+	/**
+	 * Apply a candidate returned by `identify` to a media entity
+	 *
+	 * @param id The ID of the media to apply the candidate to
+	 * @param payload The candidate to apply, and whether to use its cover as the thumbnail
+	 */
+	async applyCandidate(id: string, payload: ApplyMetadataCandidate): Promise<MediaMetadata> {
+		const { data: meta } = await this.axios.post<MediaMetadata>(
+			mediaURL(`${id}/metadata/identify/apply`),
+			payload,
+		)
+		return meta
+	}
+
+	/**
+	 * Fetch clusters of media which are likely duplicates, based on their perceptual hashes
+	 *
+	 * @param params The minimum similarity, optional library to search within and pagination
+	 */
+	async similar(
+		params?: PaginationQuery & SimilarMediaQuery,
+	): Promise<Pageable<SimilarMediaCluster[]>> {
+		const { data: clusters } = await this.axios.get<Pageable<SimilarMediaCluster[]>>(
+			mediaURL('duplicates/similar', params),
+		)
+		return clusters
+	}
+
 	/**
 	 * The keys for the media API, used for query caching on a client (e.g. react-query)
 	 */
@@ -188,6 +297,13 @@ export class MediaAPI extends APIBase {
 			uploadThumbnail: 'media.uploadThumbnail',
 			getMeta: 'media.getMeta',
 			updateMeta: 'media.updateMeta',
+			updateMetaLocks: 'media.updateMetaLocks',
+			getMetaConflicts: 'media.getMetaConflicts',
+			resolveMetaConflict: 'media.resolveMetaConflict',
+			identify: 'media.identify',
+			applyCandidate: 'media.applyCandidate',
+			similar: 'media.similar',
+			getStoryArcs: 'media.getStoryArcs',
 		}
 	}
 }
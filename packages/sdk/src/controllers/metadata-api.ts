@@ -1,5 +1,11 @@
 import { APIBase } from '../base'
-import { MediaMetadataFilter, MediaMetadataOverview } from '../types'
+import {
+	MediaMetadataFilter,
+	MediaMetadataOverview,
+	MetadataProviderConfig,
+	MetadataProviderConfigInput,
+	MetadataProviderKind,
+} from '../types'
 import { ClassQueryKeys } from './types'
 import { createRouteURLHandler } from './utils'
 
@@ -11,6 +17,10 @@ const METADATA_ROUTE = '/metadata'
  * A helper function to format the URL for media-specific metadata API routes with optional query parameters
  */
 const mediaMetadataURL = createRouteURLHandler(`${METADATA_ROUTE}/media`)
+/**
+ * A helper function to format the URL for metadata provider API routes
+ */
+const providersURL = createRouteURLHandler(`${METADATA_ROUTE}/providers`)
 
 /**
  * The metadata API controller, used for interacting with the metadata endpoints of the Stump API
@@ -106,6 +116,36 @@ export class MetadataAPI extends APIBase {
 		return teams
 	}
 
+	/**
+	 * Get the configuration of every metadata provider
+	 */
+	async providers(): Promise<MetadataProviderConfig[]> {
+		const { data: providers } = await this.axios.get<MetadataProviderConfig[]>(providersURL(''))
+		return providers
+	}
+
+	/**
+	 * Get the configuration of a metadata provider
+	 */
+	async providerByID(provider: MetadataProviderKind): Promise<MetadataProviderConfig> {
+		const { data: config } = await this.axios.get<MetadataProviderConfig>(providersURL(provider))
+		return config
+	}
+
+	/**
+	 * Update the configuration of a metadata provider, e.g. to set its API key
+	 */
+	async updateProvider(
+		provider: MetadataProviderKind,
+		input: MetadataProviderConfigInput,
+	): Promise<MetadataProviderConfig> {
+		const { data: config } = await this.axios.put<MetadataProviderConfig>(
+			providersURL(provider),
+			input,
+		)
+		return config
+	}
+
 	/**
 	 * The keys for the queries available on the metadata API
 	 */
@@ -119,8 +159,11 @@ export class MetadataAPI extends APIBase {
 			letterers: 'metadata.letterers',
 			overview: 'metadata.overview',
 			pencillers: 'metadata.pencillers',
+			providerByID: 'metadata.providerByID',
+			providers: 'metadata.providers',
 			publishers: 'metadata.publishers',
 			teams: 'metadata.teams',
+			updateProvider: 'metadata.updateProvider',
 			writers: 'metadata.writers',
 		}
 	}
@@ -0,0 +1,94 @@
+import { APIBase } from '../base'
+import {
+	Media,
+	MergePeople,
+	Pageable,
+	PaginationQuery,
+	PeopleQuery,
+	Person,
+	PersonMediaQuery,
+	SyncPeopleCreditsResponse,
+} from '../types'
+import { ClassQueryKeys, PagedQueryParams } from './types'
+import { createRouteURLHandler } from './utils'
+
+/**
+ * The root route for the people API
+ */
+const PEOPLE_ROUTE = '/people'
+/**
+ * A helper function to format the URL for people API routes with optional query parameters
+ */
+const peopleURL = createRouteURLHandler(PEOPLE_ROUTE)
+
+/**
+ * The people API controller, used for interacting with the people (creators) endpoints of the
+ * Stump API
+ */
+export class PeopleAPI extends APIBase {
+	/**
+	 * Fetch all people credited on at least one book the user can access
+	 */
+	async get(params?: PaginationQuery & PeopleQuery): Promise<Pageable<Person[]>> {
+		const { data: people } = await this.axios.get<Pageable<Person[]>>(peopleURL('', params))
+		return people
+	}
+
+	/**
+	 * Fetch a person by ID, with their aliases and the number of books they are credited on
+	 * in each role
+	 */
+	async getByID(id: string): Promise<Person> {
+		const { data: person } = await this.axios.get<Person>(peopleURL(id))
+		return person
+	}
+
+	/**
+	 * Fetch the books a person is credited on, optionally in a specific role
+	 */
+	async getMedia(
+		id: string,
+		{ page, page_size, params }: PagedQueryParams & { params?: PersonMediaQuery },
+	): Promise<Pageable<Media[]>> {
+		const { data: media } = await this.axios.get<Pageable<Media[]>>(
+			peopleURL(`${id}/media`, {
+				...params,
+				page,
+				page_size,
+			}),
+		)
+		return media
+	}
+
+	/**
+	 * Merge duplicate people into the person with the given ID, keeping their names as aliases
+	 */
+	async merge(id: string, payload: MergePeople): Promise<Person> {
+		const { data: person } = await this.axios.post<Person>(peopleURL(`${id}/merge`), payload)
+		return person
+	}
+
+	/**
+	 * Queue a job which rebuilds the credits of every book from its stored metadata
+	 */
+	async syncCredits(): Promise<SyncPeopleCreditsResponse> {
+		const { data } = await this.axios.post<SyncPeopleCreditsResponse>(
+			peopleURL('credits/sync'),
+			{},
+		)
+		return data
+	}
+
+	/**
+	 * The query keys for the people API, used for caching
+	 */
+	get keys(): ClassQueryKeys<InstanceType<typeof PeopleAPI>> {
+		return {
+			get: 'people.get',
+			getByID: 'people.getByID',
+			getMedia: 'people.getMedia',
+			merge: 'people.merge',
+			syncCredits: 'people.syncCredits',
+		}
+	}
+}
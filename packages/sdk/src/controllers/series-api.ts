@@ -1,5 +1,17 @@
 import { APIBase } from '../base'
-import { Media, Pageable, PatchSeriesThumbnail, Series, SeriesFilter } from '../types'
+import {
+	ComicVineMatchRequest,
+	ComicVineMatchResponse,
+	ConfirmComicVineMatch,
+	Media,
+	MetadataLocks,
+	Pageable,
+	PatchSeriesThumbnail,
+	ScanDryRunResponse,
+	Series,
+	SeriesFilter,
+	SeriesMetadata,
+} from '../types'
 import { MediaAPI } from './media-api'
 import { ClassQueryKeys, CursorQueryParams, FullQueryParams, PagedQueryParams } from './types'
 import { createRouteURLHandler } from './utils'
@@ -134,20 +146,95 @@ export class SeriesAPI extends APIBase {
 		await this.axios.post(seriesURL(`${id}/analyze`))
 	}
 
+	/**
+	 * Initiate a scan of a series
+	 */
+	async scan(id: string): Promise<void> {
+		await this.axios.post(seriesURL(`${id}/scan`))
+	}
+
+	/**
+	 * Initiate a dry-run scan of a series, which reports what a scan would change without
+	 * writing anything. The report is available in the output of the returned job once it completes
+	 */
+	async scanDryRun(id: string): Promise<ScanDryRunResponse> {
+		const { data } = await this.axios.post<ScanDryRunResponse>(
+			seriesURL(`${id}/scan/dry-run`),
+			{},
+		)
+		return data
+	}
+
+	/**
+	 * Match a series to a ComicVine volume. When the series does not know its ComicVine ID yet,
+	 * the candidate volumes are available in the output of the returned job once it completes
+	 */
+	async matchComicVine(
+		id: string,
+		params?: ComicVineMatchRequest,
+	): Promise<ComicVineMatchResponse> {
+		const { data } = await this.axios.post<ComicVineMatchResponse>(
+			seriesURL(`${id}/metadata/comicvine/match`),
+			params ?? {},
+		)
+		return data
+	}
+
+	/**
+	 * Confirm the ComicVine volume of a series and fill its metadata from it
+	 */
+	async confirmComicVine(
+		id: string,
+		payload: ConfirmComicVineMatch,
+	): Promise<ComicVineMatchResponse> {
+		const { data } = await this.axios.post<ComicVineMatchResponse>(
+			seriesURL(`${id}/metadata/comicvine/confirm`),
+			payload,
+		)
+		return data
+	}
+
+	/**
+	 * Update the metadata of a series. Every field which is changed is locked, so that it isn't
+	 * overwritten by a ComicVine match
+	 */
+	async updateMeta(id: string, payload: SeriesMetadata): Promise<SeriesMetadata> {
+		const { data } = await this.axios.put<SeriesMetadata>(seriesURL(`${id}/metadata`), payload)
+		return data
+	}
+
+	/**
+	 * Replace the locked fields of the metadata of a series, where an empty list unlocks every
+	 * field
+	 */
+	async updateMetaLocks(id: string, payload: MetadataLocks): Promise<SeriesMetadata> {
+		const { data } = await this.axios.put<SeriesMetadata>(
+			seriesURL(`${id}/metadata/locks`),
+			payload,
+		)
+		return data
+	}
+
 	/**
 	 * The keys for the series API
 	 */
 	get keys(): ClassQueryKeys<InstanceType<typeof SeriesAPI>> {
 		return {
 			analyze: 'series.analyze',
+			confirmComicVine: 'series.confirmComicVine',
 			get: 'series.get',
 			getByID: 'series.getByID',
 			getCursor: 'series.getCursor',
 			getSeriesMedia: 'series.getSeriesMedia',
+			matchComicVine: 'series.matchComicVine',
 			nextBook: 'series.nextBook',
 			nextBooks: 'series.nextBooks',
 			patchThumbnail: 'series.patchThumbnail',
 			recentlyAdded: 'series.recentlyAdded',
+			scan: 'series.scan',
+			scanDryRun: 'series.scanDryRun',
+			updateMeta: 'series.updateMeta',
+			updateMetaLocks: 'series.updateMetaLocks',
 			uploadThumbnail: 'series.uploadThumbnail',
 		}
 	}
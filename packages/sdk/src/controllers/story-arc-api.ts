@@ -0,0 +1,75 @@
+import { APIBase } from '../base'
+import { Media, Pageable, PaginationQuery, StoryArc, StoryArcMember } from '../types'
+import { ClassQueryKeys, PagedQueryParams } from './types'
+import { createRouteURLHandler } from './utils'
+
+/**
+ * The root route for the story arc API
+ */
+const STORY_ARC_ROUTE = '/story-arcs'
+/**
+ * A helper function to format the URL for story arc API routes with optional query parameters
+ */
+const storyArcURL = createRouteURLHandler(STORY_ARC_ROUTE)
+
+/**
+ * The story arc API controller, used for interacting with the story arc endpoints of the Stump API
+ */
+export class StoryArcAPI extends APIBase {
+	/**
+	 * Fetch all story arcs which have at least one book the user can access
+	 */
+	async get(params?: PaginationQuery): Promise<Pageable<StoryArc[]>> {
+		const { data: storyArcs } = await this.axios.get<Pageable<StoryArc[]>>(
+			storyArcURL('', params),
+		)
+		return storyArcs
+	}
+
+	/**
+	 * Fetch a story arc by ID
+	 */
+	async getByID(id: string): Promise<StoryArc> {
+		const { data: storyArc } = await this.axios.get<StoryArc>(storyArcURL(id))
+		return storyArc
+	}
+
+	/**
+	 * Fetch the books of a story arc in reading order, each with its position in the arc
+	 */
+	async getMedia(
+		id: string,
+		{ page, page_size, ...params }: PagedQueryParams,
+	): Promise<Pageable<StoryArcMember[]>> {
+		const { data: members } = await this.axios.get<Pageable<StoryArcMember[]>>(
+			storyArcURL(`${id}/media`, {
+				...params,
+				page,
+				page_size,
+			}),
+		)
+		return members
+	}
+
+	/**
+	 * Fetch the next book in the story arc, according to the user's progress
+	 */
+	async nextBook(id: string): Promise<Media | undefined> {
+		const { data: upNextBook } = await this.axios.get<Media | undefined>(
+			storyArcURL(`${id}/media/next`),
+		)
+		return upNextBook
+	}
+
+	/**
+	 * The query keys for the story arc API, used for caching
+	 */
+	get keys(): ClassQueryKeys<InstanceType<typeof StoryArcAPI>> {
+		return {
+			get: 'storyArc.get',
+			getByID: 'storyArc.getByID',
+			getMedia: 'storyArc.getMedia',
+			nextBook: 'storyArc.nextBook',
+		}
+	}
+}
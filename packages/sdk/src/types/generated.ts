@@ -7,7 +7,7 @@ export type PaginationQuery = { zero_based?: boolean | null; page?: number | nul
 /**
  * An event that is emitted by the core and consumed by a client
  */
-export type CoreEvent = ({ __typename: "JobStarted" } & string) | ({ __typename: "JobUpdate" } & JobUpdate) | { __typename: "JobOutput"; id: string; output: CoreJobOutput } | ({ __typename: "DiscoveredMissingLibrary" } & string) | { __typename: "CreatedMedia"; id: string; series_id: string } | { __typename: "CreatedManySeries"; count: number; library_id: string } | { __typename: "CreatedOrUpdatedManyMedia"; count: number; series_id: string }
+export type CoreEvent = ({ __typename: "JobStarted" } & string) | ({ __typename: "JobUpdate" } & JobUpdate) | { __typename: "JobOutput"; id: string; output: CoreJobOutput } | { __typename: "JobQueueUpdate"; queue: QueuedJob[] } | { __typename: "JobFailed"; id: string; message: string } | ({ __typename: "DiscoveredMissingLibrary" } & string) | { __typename: "CreatedMedia"; id: string; series_id: string } | { __typename: "CreatedManySeries"; count: number; library_id: string } | { __typename: "CreatedOrUpdatedManyMedia"; count: number; series_id: string }
 
 export type EntityVisibility = "PUBLIC" | "SHARED" | "PRIVATE"
 
@@ -26,9 +26,9 @@ export type LogMetadata = { path: string; size: number; modified: string }
 
 export type LogLevel = "ERROR" | "WARN" | "INFO" | "DEBUG"
 
-export type PersistedJob = { id: string; name: string; description: string | null; status: JobStatus; output_data: CoreJobOutput | null; ms_elapsed: number; created_at: string; completed_at: string | null; logs?: Log[] | null }
+export type PersistedJob = { id: string; name: string; description: string | null; status: JobStatus; output_data: CoreJobOutput | null; ms_elapsed: number; attempts: number; next_retry_at: string | null; parent_id: string | null; created_at: string; completed_at: string | null; logs?: Log[] | null; children?: PersistedJob[] | null }
 
-export type CoreJobOutput = LibraryScanOutput | SeriesScanOutput | ThumbnailGenerationOutput
+export type CoreJobOutput = LibraryScanOutput | SeriesScanOutput | ThumbnailGenerationOutput | ScanDryRunOutput | ComicVineMatchOutput | IsbnEnrichmentOutput | MetadataImportOutput
 
 /**
  * An update event that is emitted by a job
@@ -47,7 +47,20 @@ export type JobProgress = { status?: JobStatus | null; message?: string | null;
  */
 export type LibraryScanOutput = { total_files: number; total_directories: number; ignored_files: number; skipped_files: number; ignored_directories: number; created_media: number; updated_media: number; created_series: number; updated_series: number }
 
-export type SeriesScanOutput = { total_files: number; ignored_files: number; skipped_files: number; created_media: number; updated_media: number }
+/**
+ * The output of a series scan, which is reported to clients once the scan completes
+ */
+export type SeriesScanOutput = { total_files: number; ignored_files: number; skipped_files: number; created_media: number; updated_media: number; missing_media: number; restored_media: number }
+
+/**
+ * The changes a scan would make to a single series
+ */
+export type SeriesDryRunReport = { path: string; is_missing: boolean; media_to_create: string[]; media_to_update: string[]; media_to_mark_missing: string[]; media_to_restore: string[]; ignored_files: string[]; skipped_files: number }
+
+/**
+ * The report produced by a dry-run scan, describing the changes a real scan would make
+ */
+export type ScanDryRunOutput = { library_is_missing: boolean; series_to_create: string[]; series_to_mark_missing: string[]; series_to_restore: string[]; ignored_directories: string[]; series: SeriesDryRunReport[] }
 
 export type ThumbnailGenerationJobVariant = ({ type: "SingleLibrary" } & string) | ({ type: "SingleSeries" } & string) | ({ type: "MediaGroup" } & string[])
 
@@ -55,6 +68,46 @@ export type ThumbnailGenerationJobParams = { variant: ThumbnailGenerationJobVari
 
 export type ThumbnailGenerationOutput = { visited_files: number; skipped_files: number; generated_thumbnails: number; removed_thumbnails: number }
 
+/**
+ * A ComicVine volume which could be the series being matched
+ */
+export type ComicVineCandidate = { volume_id: number; name: string; start_year: number | null; publisher: string | null; issue_count: number | null; site_detail_url: string | null; score: number }
+
+/**
+ * The output of a ComicVine match, which is reported to clients once the match completes
+ */
+export type ComicVineMatchOutput = { candidates: ComicVineCandidate[]; matched_volume_id: number | null; matched_media: number; unmatched_media: number }
+
+/**
+ * The output of an ISBN enrichment, which is reported to clients once it completes
+ */
+export type IsbnEnrichmentOutput = { enriched_media: number; not_found_media: number; unidentified_media: number }
+
+/**
+ * The kind of entity a [MetadataRecord] describes
+ */
+export type MetadataRecordKind = "MEDIA" | "SERIES"
+
+/**
+ * A metadata field which an import would change
+ */
+export type MetadataFieldChange = { field: string; current_value: any; imported_value: any }
+
+/**
+ * The changes a single record of an import makes to the metadata of a media or series
+ */
+export type MetadataImportChange = { record: number; kind: MetadataRecordKind; id: string; path: string | null; fields: MetadataFieldChange[] }
+
+/**
+ * The output of a metadata import, which is reported to clients once it completes
+ */
+export type MetadataImportOutput = { updated_media: number; updated_series: number; unchanged_records: number; failed_records: number; changes: MetadataImportChange[] }
+
+/**
+ * The file formats which library metadata can be exported to and imported from
+ */
+export type MetadataTransferFormat = "csv" | "json"
+
 export type User = { id: string; username: string; is_server_owner: boolean; avatar_url: string | null; created_at: string; last_login: string | null; is_locked: boolean; permissions: UserPermission[]; max_sessions_allowed?: number | null; login_sessions_count?: number | null; user_preferences?: UserPreferences | null; login_activity?: LoginActivity[] | null; age_restriction?: AgeRestriction | null; active_reading_sessions?: ActiveReadingSession[] | null; finished_reading_sessions?: FinishedReadingSession[] | null }
 
 /**
@@ -140,26 +193,84 @@ export type ReadingImageScaleFit = "height" | "width" | "none"
 
 export type FileStatus = "UNKNOWN" | "READY" | "UNSUPPORTED" | "ERROR" | "MISSING"
 
-export type Library = { id: string; name: string; description: string | null; emoji: string | null; path: string; status: string; updated_at: string; series: Series[] | null; tags: Tag[] | null; config: LibraryConfig }
+export type Library = { id: string; name: string; description: string | null; emoji: string | null; path: string; additional_roots: string[] | null; status: string; updated_at: string; series: Series[] | null; tags: Tag[] | null; config: LibraryConfig }
 
 export type LibraryPattern = "SERIES_BASED" | "COLLECTION_BASED"
 
+export type LibraryWatchMode = "DISABLED" | "NATIVE" | "POLLING"
+
 export type LibraryScanMode = "DEFAULT" | "NONE"
 
 export type IgnoreRules = string[]
 
-export type LibraryConfig = { id?: string | null; convert_rar_to_zip: boolean; hard_delete_conversions: boolean; generate_file_hashes: boolean; generate_koreader_hashes: boolean; process_metadata: boolean; library_pattern: LibraryPattern; thumbnail_config: ImageProcessorOptions | null; default_reading_dir?: ReadingDirection; default_reading_mode?: ReadingMode; default_reading_image_scale_fit?: ReadingImageScaleFit; ignore_rules?: IgnoreRules; library_id?: string | null }
+export type FilenamePatternKind = "TEMPLATE" | "REGEX"
+
+/**
+ * A pattern used to infer metadata from the name of a file (without its extension) or
+ * directory, for when the metadata is otherwise missing
+ */
+export type FilenamePattern = { kind: FilenamePatternKind; pattern: string }
+
+/**
+ * An ordered list of [FilenamePattern]s configured for a library. The first pattern which
+ * matches a name is used.
+ */
+export type FilenamePatterns = FilenamePattern[]
+
+/**
+ * The metadata providers used to identify the books of a library, in priority order.
+ * Candidates from earlier providers are preferred when they score equally well.
+ */
+export type MetadataProviderPriority = MetadataProviderKind[]
+
+/**
+ * The metadata which was extracted from a name using a [FilenamePattern]
+ */
+export type FilenameMetadata = { series: string | null; title: string | null; volume: number | null; number: number | null; year: number | null }
+
+export type LibraryConfig = { id?: string | null; convert_rar_to_zip: boolean; hard_delete_conversions: boolean; generate_file_hashes: boolean; generate_koreader_hashes: boolean; process_metadata: boolean; library_pattern: LibraryPattern; thumbnail_config: ImageProcessorOptions | null; default_reading_dir?: ReadingDirection; default_reading_mode?: ReadingMode; default_reading_image_scale_fit?: ReadingImageScaleFit; ignore_rules?: IgnoreRules; watch_mode?: LibraryWatchMode; merge_strategy?: MergeStrategy; filename_patterns?: FilenamePatterns; metadata_providers?: MetadataProviderPriority; library_id?: string | null }
 
 export type LibraryStats = { series_count: number; book_count: number; total_bytes: number; completed_books: number; in_progress_books: number }
 
-export type SeriesMetadata = { _type: string; title: string | null; summary: string | null; publisher: string | null; imprint: string | null; comicid: number | null; volume: number | null; booktype: string | null; age_rating: number | null; status: string | null }
+export type SeriesMetadata = { _type: string; title: string | null; summary: string | null; publisher: string | null; imprint: string | null; comicid: number | null; volume: number | null; booktype: string | null; age_rating: number | null; status: string | null; locked_fields: string[] }
 
 export type Series = { id: string; name: string; path: string; description: string | null; status: FileStatus; updated_at: string; created_at: string; library_id: string; library: Library | null; media: Media[] | null; metadata: SeriesMetadata | null; media_count?: number | null; unread_media_count?: number | null; tags?: Tag[] | null }
 
 /**
  * Struct representing the metadata for a processed file.
  */
-export type MediaMetadata = { title?: string | null; series?: string | null; number?: number | null; volume?: number | null; summary?: string | null; notes?: string | null; age_rating?: number | null; genre?: string[] | null; year?: number | null; month?: number | null; day?: number | null; writers?: string[] | null; pencillers?: string[] | null; inkers?: string[] | null; colorists?: string[] | null; letterers?: string[] | null; cover_artists?: string[] | null; editors?: string[] | null; publisher?: string | null; links?: string[] | null; characters?: string[] | null; teams?: string[] | null; page_count?: number | null }
+export type MediaMetadata = { title?: string | null; series?: string | null; number?: number | null; volume?: number | null; summary?: string | null; notes?: string | null; age_rating?: number | null; genre?: string[] | null; year?: number | null; month?: number | null; day?: number | null; writers?: string[] | null; pencillers?: string[] | null; inkers?: string[] | null; colorists?: string[] | null; letterers?: string[] | null; cover_artists?: string[] | null; editors?: string[] | null; publisher?: string | null; isbn?: string | null; links?: string[] | null; characters?: string[] | null; teams?: string[] | null; page_count?: number | null; locked_fields?: string[] }
+
+/**
+ * The strategy used to reconcile metadata read from a file with the metadata already stored
+ * for it, e.g. when a book is rebuilt during a scan
+ */
+export type MergeStrategy = "REPLACE" | "MERGE" | "CONFLICT"
+
+/**
+ * A recorded difference between the stored metadata of a media and the metadata which was
+ * read from its file, pending review. These are only created for libraries which use the
+ * `CONFLICT` merge strategy.
+ */
+export type MediaMetadataConflict = { id: string; field: string; current_value: any | null; incoming_value: any | null; media_id: string; created_at: string }
+
+/**
+ * An external service which metadata can be fetched from
+ */
+export type MetadataProviderKind = "COMICVINE" | "GOOGLE_BOOKS" | "OPEN_LIBRARY" | "SIDECAR"
+
+/**
+ * The stored configuration of a metadata provider. The API key is encrypted at rest and
+ * is never sent back to clients, only whether one has been set
+ */
+export type MetadataProviderConfig = { provider: MetadataProviderKind; enabled: boolean; has_api_key: boolean }
+
+export type MetadataProviderConfigInput = { enabled: boolean | null; api_key: string | null }
+
+/**
+ * A book which a provider suggested for a media, along with how closely it matches
+ */
+export type MetadataCandidate = { provider: MetadataProviderKind; id: string; score: number; metadata: MediaMetadata; cover_url: string | null }
 
 export type Media = { id: string; name: string; size: number; extension: string; pages: number; updated_at: string; created_at: string; modified_at: string | null; hash: string | null; koreader_hash: string | null; path: string; status: FileStatus; series_id: string; metadata: MediaMetadata | null; series?: Series | null; active_reading_session?: ActiveReadingSession | null; finished_reading_sessions: FinishedReadingSession[] | null; current_page?: number | null; current_epubcfi?: string | null; is_completed?: boolean | null; tags?: Tag[] | null; bookmarks?: Bookmark[] | null }
 
@@ -171,6 +282,55 @@ export type Bookmark = { id: string; preview_content: string | null; epubcfi: st
 
 export type MediaAnnotation = { id: string; highlighted_text: string | null; page: number | null; page_coordinates_x: number | null; page_coordinates_y: number | null; epubcfi: string | null; notes: string | null; media_id: string; media?: Media | null }
 
+/**
+ * A story arc, e.g. "Civil War", which groups books across any number of series and
+ * libraries
+ */
+export type StoryArc = { id: string; name: string }
+
+/**
+ * The membership of a book in a story arc
+ */
+export type StoryArcMember = { position: number | null; story_arc_id: string; media_id: string; media?: Media | null; story_arc?: StoryArc | null }
+
+/**
+ * The role a person had in the creation of a book
+ */
+export type CreatorRole = "WRITER" | "PENCILLER" | "INKER" | "COLORIST" | "LETTERER" | "COVER_ARTIST" | "EDITOR"
+
+/**
+ * A person credited on one or more books, e.g. a writer or colorist. People are
+ * deduplicated by their normalized name, so "Jack Kirby" and "jack  kirby" are the same
+ * person
+ */
+export type Person = { id: string; name: string; aliases?: string[] | null; credit_counts?: PersonRoleCount[] | null }
+
+export type PersonRoleCount = { role: CreatorRole; count: number }
+
+/**
+ * The credit of a person on a book, in a specific role
+ */
+export type MediaCredit = { role: CreatorRole; person_id: string; media_id: string; person?: Person | null; media?: Media | null }
+
+/**
+ * A manually ordered collection of series and individual books, which may come from any
+ * number of libraries
+ */
+export type Collection = { id: string; name: string; description: string | null; updated_at: string; items?: CollectionItem[] | null }
+
+/**
+ * An item in a collection, which is either an entire series or a single book. Exactly one
+ * of `series_id` and `media_id` is set
+ */
+export type CollectionItem = { id: string; position: number; collection_id: string; series_id: string | null; media_id: string | null; series?: Series | null; media?: Media | null }
+
+/**
+ * A reference to a series or book to add to a collection
+ */
+export type CollectionItemInput = { series_id: string } | { media_id: string }
+
+export type CreateOrUpdateCollection = { name: string; description?: string | null; items?: CollectionItemInput[] | null }
+
 export type ActiveReadingSession = { id: string; page: number | null; epubcfi: string | null; percentage_completed: number | null; started_at: string; media_id: string; media: Media | null; user_id: string; user: User | null }
 
 export type FinishedReadingSession = { id: string; started_at: string; completed_at: string; media_id: string; media: Media | null; user_id: string; user: User | null }
@@ -227,7 +387,7 @@ export type SmartList = { id: string; name: string; description: string | null;
 
 export type SmartFilter<T> = { groups: FilterGroup<T>[] }
 
-export type MediaSmartFilter = { name: Filter<string> } | { size: Filter<number> } | { extension: Filter<string> } | { created_at: Filter<string> } | { updated_at: Filter<string> } | { status: Filter<string> } | { path: Filter<string> } | { pages: Filter<number> } | { metadata: MediaMetadataSmartFilter } | { series: SeriesSmartFilter }
+export type MediaSmartFilter = { name: Filter<string> } | { size: Filter<number> } | { extension: Filter<string> } | { created_at: Filter<string> } | { updated_at: Filter<string> } | { status: Filter<string> } | { path: Filter<string> } | { pages: Filter<number> } | { metadata: MediaMetadataSmartFilter } | { series: SeriesSmartFilter } | { story_arcs: StoryArcMemberSmartFilter }
 
 export type MediaMetadataSmartFilter = { publisher: Filter<string> } | { genre: Filter<string> } | { characters: Filter<string> } | { colorists: Filter<string> } | { writers: Filter<string> } | { pencillers: Filter<string> } | { letterers: Filter<string> } | { inkers: Filter<string> } | { editors: Filter<string> } | { age_rating: Filter<number> } | { year: Filter<number> } | { month: Filter<number> } | { day: Filter<number> }
 
@@ -237,6 +397,10 @@ export type SeriesSmartFilter = { name: Filter<string> } | { path: Filter<string
 
 export type LibrarySmartFilter = { name: Filter<string> } | { path: Filter<string> }
 
+export type StoryArcSmartFilter = { name: Filter<string> }
+
+export type StoryArcMemberSmartFilter = { story_arc: StoryArcSmartFilter }
+
 export type SmartListView = ({ book_columns: ReactTableColumnSort[]; group_columns: ReactTableColumnSort[]; book_sorting: ReactTableGlobalSort[] | null; group_sorting: ReactTableGlobalSort[] | null; enable_multi_sort?: boolean | null; search?: string | null }) & { name: string; list_id: string }
 
 export type BookClub = { id: string; name: string; description: string | null; emoji: string | null; is_private: boolean; created_at: string; member_role_spec: BookClubMemberRoleSpec; members?: BookClubMember[] | null; schedule?: BookClubSchedule | null }
@@ -273,9 +437,22 @@ export type UpdateEpubProgress = { epubcfi: string; percentage: number; is_compl
 
 export type EpubContent = { label: string; content: string; children: EpubContent[]; play_order: number }
 
-export type JobStatus = "RUNNING" | "PAUSED" | "COMPLETED" | "CANCELLED" | "FAILED" | "QUEUED"
+export type JobStatus = "RUNNING" | "PAUSED" | "COMPLETED" | "CANCELLED" | "FAILED" | "DEAD_LETTER" | "QUEUED"
+
+export type ScheduledJobKind = "SCAN" | "THUMBNAIL_GENERATION" | "MEDIA_ANALYSIS" | "DATABASE_MAINTENANCE" | "SESSION_CLEANUP"
 
-export type JobSchedulerConfig = { id: string; interval_secs: number; excluded_libraries: Library[] }
+export type JobSchedule = { id: string; name: string; cron_expression: string; job_kind: ScheduledJobKind; enabled: boolean; target_libraries: Library[]; last_run_at: string | null; next_run_at: string | null; created_at: string; updated_at: string }
+
+/**
+ * The priority of a job, which determines the order in which queued jobs are started. A job
+ * is started before any queued jobs with a lower priority, regardless of when it was queued.
+ */
+export type JobPriority = "BACKGROUND" | "INTERACTIVE"
+
+/**
+ * A job which is waiting in the queue for a worker to become available
+ */
+export type QueuedJob = { id: string; name: string; description: string | null; priority: JobPriority; position: number }
 
 export type ReadingListItem = { display_order: number; media_id: string; reading_list_id: string; media: Media | null }
 
@@ -432,9 +609,9 @@ export type UserQueryRelation = { include_read_progresses?: boolean | null; incl
 
 export type SeriesQueryRelation = { load_media?: boolean | null; load_library?: boolean | null; count_media?: boolean | null }
 
-export type CreateLibrary = { name: string; path: string; description?: string | null; tags?: string[] | null; scan_mode?: LibraryScanMode | null; config?: LibraryConfig | null }
+export type CreateLibrary = { name: string; path: string; description?: string | null; tags?: string[] | null; scan_mode?: LibraryScanMode | null; config?: LibraryConfig | null; additional_roots?: string[] }
 
-export type UpdateLibrary = { name: string; path: string; description?: string | null; emoji?: string | null; tags?: string[] | null; config: LibraryConfig; scan_mode?: LibraryScanMode | null }
+export type UpdateLibrary = { name: string; path: string; description?: string | null; emoji?: string | null; tags?: string[] | null; config: LibraryConfig; scan_mode?: LibraryScanMode | null; additional_roots?: string[] | null }
 
 export type UpdateLibraryExcludedUsers = { user_ids: string[] }
 
@@ -442,6 +619,20 @@ export type CleanLibraryResponse = { deleted_media_count: number; deleted_series
 
 export type GenerateLibraryThumbnails = { image_options?: ImageProcessorOptions | null; force_regenerate?: boolean }
 
+export type PreviewFilenamePatterns = { patterns: FilenamePattern[]; paths: string[] }
+
+export type FilenamePatternResult = { pattern: FilenamePattern; metadata?: FilenameMetadata | null; error?: string | null }
+
+export type FilenamePatternPreview = { path: string; name: string; results: FilenamePatternResult[]; matched: FilenameMetadata | null }
+
+export type ScanDryRunResponse = { job_id: string }
+
+export type IsbnEnrichmentRequest = { merge_strategy: MergeStrategy | null }
+
+export type IsbnEnrichmentResponse = { job_id: string }
+
+export type MetadataImportResponse = { job_id: string }
+
 export type LibraryStatsParams = { all_users?: boolean }
 
 /**
@@ -454,15 +645,50 @@ export type PutMediaCompletionStatus = { is_complete: boolean; page?: number | n
  */
 export type MediaIsComplete = { is_completed: boolean; last_completed_at: string | null }
 
+/**
+ * The possible resolutions for a metadata conflict
+ */
+export type MetadataConflictResolution = "ACCEPT" | "REJECT"
+
+export type ResolveMetadataConflict = { resolution: MetadataConflictResolution }
+
+export type ApplyMetadataCandidate = { provider: MetadataProviderKind; id: string; merge_strategy?: MergeStrategy | null; use_cover?: boolean | null }
+
+/**
+ * The fields of a metadata record which are locked, i.e. which are kept as is when the
+ * metadata is refreshed from a file or fetched from a metadata provider
+ */
+export type MetadataLocks = { locked_fields: string[] }
+
+export type SimilarMediaQuery = { threshold?: number | null; library_id?: string | null }
+
+export type SimilarMedia = { media: Media; similarity: number }
+
+export type SimilarMediaCluster = { media: SimilarMedia[] }
+
 export type MediaMetadataOverview = { genres: string[]; writers: string[]; pencillers: string[]; inkers: string[]; colorists: string[]; letterers: string[]; editors: string[]; publishers: string[]; characters: string[]; teams: string[] }
 
+export type ComicVineMatchRequest = { merge_strategy: MergeStrategy | null }
+
+export type ConfirmComicVineMatch = { volume_id: number; merge_strategy: MergeStrategy | null }
+
+export type ComicVineMatchResponse = { job_id: string }
+
 export type CreateOrUpdateBookmark = { epubcfi: string; preview_content: string | null }
 
 export type DeleteBookmark = { epubcfi: string }
 
 export type SeriesIsComplete = { is_complete: boolean; completed_at: string | null }
 
-export type UpdateSchedulerConfig = { interval_secs: number | null; excluded_library_ids: string[] | null }
+export type CreateOrUpdateJobSchedule = { name: string; cron_expression: string; job_kind: ScheduledJobKind; enabled?: boolean; target_library_ids?: string[] }
+
+export type PeopleQuery = { search?: string | null; role?: CreatorRole | null }
+
+export type PersonMediaQuery = { role?: CreatorRole | null }
+
+export type MergePeople = { person_ids: string[] }
+
+export type SyncPeopleCreditsResponse = { job_id: string }
 
 export type GetBookClubsParams = { all?: boolean }
 
@@ -546,7 +772,7 @@ export type UploadConfig = { enabled: boolean; max_file_upload_size: number }
  * }
  * ```
  */
-export type StumpConfig = { profile: string; port: number; verbosity: number; pretty_logs: boolean; db_path: string | null; client_dir: string; custom_templates_dir: string | null; config_dir: string; allowed_origins: string[]; pdfium_path: string | null; enable_swagger: boolean; enable_koreader_sync: boolean; password_hash_cost: number; session_ttl: number; access_token_ttl: number; expired_session_cleanup_interval: number; max_scanner_concurrency: number; max_thumbnail_concurrency: number; max_image_upload_size: number; enable_upload: boolean; max_file_upload_size: number }
+export type StumpConfig = { profile: string; port: number; verbosity: number; pretty_logs: boolean; db_path: string | null; client_dir: string; custom_templates_dir: string | null; config_dir: string; allowed_origins: string[]; pdfium_path: string | null; enable_swagger: boolean; enable_koreader_sync: boolean; password_hash_cost: number; session_ttl: number; access_token_ttl: number; expired_session_cleanup_interval: number; max_scanner_concurrency: number; max_thumbnail_concurrency: number; max_concurrent_jobs: number; max_image_upload_size: number; enable_upload: boolean; max_file_upload_size: number; perceptual_hash_pages: number; s3_endpoint: string | null; s3_region: string; s3_access_key_id: string | null; s3_secret_access_key: string | null }
 
 // DESKTOP TYPE GENERATION
 